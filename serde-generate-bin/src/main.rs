@@ -8,55 +8,104 @@
 //! cargo run -- --help
 //! '''
 
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
 use serde_generate::{
-    cpp, csharp, dart, golang, java, ocaml, python3, rust, solidity, swift, typescript,
-    CodeGeneratorConfig, Encoding, SourceInstaller,
+    analyzer, cpp, csharp, dart, diff, golang, java, ocaml, openapi, php, python3, rust, solidity,
+    swift, typescript,
+    validation::{self, Language},
+    zig, BaseClasses, CodeGeneratorConfig, Encoding, FormatterHook, NonAsciiNames, SourceInstaller,
+};
+use serde_reflection::{
+    ContainerFormat, Format, FormattedValue, Registry, TracerConfig, Value, WellKnownType,
+    WellKnownTypes,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::Duration,
 };
-use serde_reflection::Registry;
-use std::path::PathBuf;
 use structopt::{clap::arg_enum, StructOpt};
 
 arg_enum! {
-#[derive(Debug, StructOpt)]
-enum Language {
-    Python3,
-    Cpp,
-    Solidity,
-    Rust,
-    Java,
-    Go,
-    Dart,
-    TypeScript,
-    CSharp,
-    Swift,
-    OCaml,
-}
-}
-
-arg_enum! {
-#[derive(Debug, StructOpt, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, StructOpt, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Runtime {
     Serde,
     Bincode,
     Bcs,
+    Postcard,
+    Msgpack,
+}
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt, Clone, Copy)]
+enum InputFormat {
+    Yaml,
+    Json,
 }
 }
 
-#[derive(Debug, StructOpt)]
+#[derive(Debug, Clone, StructOpt)]
 #[structopt(
     name = "Serde code generator",
     about = "Generate code for Serde containers"
 )]
 struct Options {
-    /// Path to the YAML-encoded Serde formats.
+    #[structopt(subcommand)]
+    command: Option<Command>,
+
+    /// Paths to the YAML- or JSON-encoded Serde formats. Several files are merged into a
+    /// single registry: containers defined in more than one file must have the same format.
     #[structopt(parse(from_os_str))]
-    input: Option<PathBuf>,
+    inputs: Vec<PathBuf>,
 
-    /// Language for code generation.
-    #[structopt(long, possible_values = &Language::variants(), case_insensitive = true, default_value = "Python3")]
-    language: Language,
+    /// Encoding of the input file and of the registry written with `--emit-registry`
+    /// (by default, JSON for `.json` files and YAML otherwise).
+    #[structopt(long, alias = "registry-format", possible_values = &InputFormat::variants(), case_insensitive = true)]
+    input_format: Option<InputFormat>,
 
-    /// Directory where to write generated modules (otherwise print code on stdout).
+    /// Write the (merged) registry loaded from the input files to the given path.
+    #[structopt(long, parse(from_os_str))]
+    emit_registry: Option<PathBuf>,
+
+    /// Reject input files with tuple arrays larger than this size (by default, 65536).
+    #[structopt(long)]
+    max_tuple_array_size: Option<usize>,
+
+    /// Reject input files whose formats are nested more deeply than this (by default, 64).
+    #[structopt(long)]
+    max_depth: Option<usize>,
+
+    /// Only generate code for the given containers (separated by commas) and the containers
+    /// they reference.
+    #[structopt(long, use_delimiter = true, number_of_values = 1)]
+    include_containers: Vec<String>,
+
+    /// Do not generate code for the given containers (separated by commas). Fails if another
+    /// generated container references them.
+    #[structopt(long, use_delimiter = true, number_of_values = 1)]
+    exclude_containers: Vec<String>,
+
+    /// Path to a YAML- or JSON-encoded file with additional code generation options, such as
+    /// comments, custom code, and external definitions. Command-line flags take precedence.
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Languages for code generation, possibly repeated or separated by commas (`all` selects
+    /// every available language, see `serdegen languages`). With several languages, the code of
+    /// each language is written in a subdirectory of `target_source_dir` named after the
+    /// language, e.g. `python3`.
+    #[structopt(
+        long = "language",
+        use_delimiter = true,
+        number_of_values = 1,
+        default_value = "Python3"
+    )]
+    languages: Vec<String>,
+
+    /// Directory where to write generated modules. Without this option, or with `-`, code is
+    /// printed on stdout and no runtime is installed (Java, Dart, C#, and PHP are not supported).
     #[structopt(long)]
     target_source_dir: Option<PathBuf>,
 
@@ -65,6 +114,21 @@ struct Options {
     #[structopt(long, possible_values = &Runtime::variants(), case_insensitive = true)]
     with_runtimes: Vec<Runtime>,
 
+    /// Only generate type definitions, without serialization methods or helpers. This cannot be
+    /// combined with `--with-runtimes`.
+    #[structopt(long)]
+    types_only: bool,
+
+    /// Only generate deserialization methods, e.g. for clients that only decode data. This is
+    /// supported by the languages marked as one-way in `serdegen languages`.
+    #[structopt(long)]
+    deserialize_only: bool,
+
+    /// Only generate serialization methods. This is supported by the languages marked as
+    /// one-way in `serdegen languages`.
+    #[structopt(long)]
+    serialize_only: bool,
+
     /// Module name for the Serde formats installed in the `target_source_dir`.
     /// Rust crates may contain a version number separated with a colon, e.g. "test:1.2.0".
     /// (By default, the installer will use version "0.1.0".)
@@ -79,9 +143,408 @@ struct Options {
     /// if the target language and the generator code support them.
     #[structopt(long)]
     use_c_style_enums: bool,
+
+    /// Accept floating-point formats when generating code for the postcard runtime.
+    #[structopt(long)]
+    postcard_floats: bool,
+
+    /// Accept floating-point formats when generating code for the BCS runtime, which does not
+    /// support them by default.
+    #[structopt(long)]
+    allow_nonstandard_floats: bool,
+
+    /// Replace the characters of names that are not ASCII letters, digits, or underscores with
+    /// their code points, e.g. `Café` becomes `CafU00E9`. Renamed identifiers are listed on
+    /// stderr.
+    #[structopt(long)]
+    transliterate_names: bool,
+
+    /// Delete previously generated files that are no longer produced (Dart and Swift).
+    /// Files without the header of generated files are never deleted.
+    #[structopt(long)]
+    clean: bool,
+
+    /// Add `json` tags with the original field names to generated structs (Go).
+    #[structopt(long)]
+    json_tags: bool,
+
+    /// Enable nullable reference types and represent optional references as `T?` (C#).
+    #[structopt(long)]
+    nullable_annotations: bool,
+
+    /// Generate conversion functions to and from Yojson, following the JSON conventions of
+    /// serde (OCaml).
+    #[structopt(long)]
+    yojson: bool,
+
+    /// Generate a `Visitor<R>` interface and `accept` methods for enums (Java).
+    #[structopt(long)]
+    enum_visitors: bool,
+
+    /// Represent containers recognized as standard types by native types, e.g. `Duration` by
+    /// `java.time.Duration` (Java) or `datetime.timedelta` (Python).
+    #[structopt(long)]
+    recognize_standard_types: bool,
+
+    /// Version of the OpenAPI specification targeted by the schemas, either 3.0 or 3.1
+    /// (OpenAPI). Default is 3.1.
+    #[structopt(long)]
+    openapi_version: Option<openapi::OpenApiVersion>,
+
+    /// Describe bytes as base64 strings instead of arrays of integers (OpenAPI).
+    #[structopt(long)]
+    openapi_base64_bytes: bool,
+
+    /// Write schemas in JSON instead of YAML (OpenAPI).
+    #[structopt(long)]
+    openapi_json: bool,
+
+    /// Write a type stub `__init__.pyi` next to the generated module and a `py.typed` marker in
+    /// each installed package (Python).
+    #[structopt(long)]
+    type_stubs: bool,
+
+    /// Install each module as a package with one module per container, re-exported by its
+    /// `__init__.py` file (Python), or with one submodule per container, re-exported by its
+    /// `lib.rs` or `mod.rs` file (Rust).
+    #[structopt(long)]
+    split_modules: bool,
+
+    /// Write a package manifest next to the installed modules: a `pyproject.toml` file (Python),
+    /// a `build.zig` file (Zig), a `composer.json` file (PHP), or a `CMakeLists.txt` file (C++).
+    #[structopt(long)]
+    package_manifest: bool,
+
+    /// Version of the package written with `--package-manifest` (Python).
+    /// (By default, the installer will use version "0.1.0".)
+    #[structopt(long)]
+    package_version: Option<String>,
+
+    /// Mark generated enums as `#[non_exhaustive]` (Rust).
+    #[structopt(long)]
+    non_exhaustive_enums: bool,
+
+    /// Install each module as a standalone crate (`crate`, the default), as a single file to
+    /// `include!` (`single-file=<path>`), or as a module directory of an existing crate
+    /// (`module-dir=<path>`). Paths are relative to the `target_source_dir` (Rust).
+    #[structopt(long)]
+    rust_output_mode: Option<rust::OutputMode>,
+
+    /// Write a `fuzz/` directory for `cargo fuzz` next to the installed crate, with one fuzz
+    /// target per given container (separated by commas), decoding and encoding values with the
+    /// runtimes of `--with-runtimes` (Rust).
+    #[structopt(long, use_delimiter = true, number_of_values = 1)]
+    rust_fuzz_targets: Vec<String>,
+
+    /// Format the files written in the `target_source_dir` with rustfmt, prettier, gofmt, or black,
+    /// if installed.
+    #[structopt(long)]
+    format: bool,
+    /// Directory where to record the inputs of each generation in the `target_source_dir`.
+    /// Languages whose registry, options, and config did not change since the last successful
+    /// generation, and whose generated files still exist, are skipped.
+    #[structopt(long, parse(from_os_str))]
+    cache_dir: Option<PathBuf>,
+
+    /// After the initial generation, keep watching the input files and the config file, and
+    /// regenerate code whenever they change. Generation errors are reported without exiting.
+    #[structopt(long)]
+    watch: bool,
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt, Clone, Copy, PartialEq, Eq)]
+enum Deny {
+    Breaking,
+}
+}
+
+#[derive(Debug, Clone, StructOpt)]
+enum Command {
+    /// Report the formats of the registry that cannot be generated in the given languages,
+    /// without generating any code.
+    Check(CheckOptions),
+    /// Compare two versions of a registry. Exit with status 0 if they are identical, 2 if
+    /// the new version only adds containers or enum variants, and 3 for breaking changes.
+    Diff(DiffOptions),
+    /// Write a JSON manifest with the encodings of a canonical value of each container, in
+    /// hexadecimal, to test third-party decoders. Canonical values follow the default rules of
+    /// the tracer: zero, `false`, or `'A'` for primitive types, empty strings, sequences, and
+    /// maps, `None` for options, and the first variant of each enum that is not recursive.
+    Vectors(VectorsOptions),
+    /// Print the languages of this build and the options that their code generators support.
+    Languages,
+}
+
+arg_enum! {
+#[derive(Debug, StructOpt, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum VectorEncoding {
+    Bcs,
+    Bincode,
+}
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct VectorsOptions {
+    /// Paths to the YAML- or JSON-encoded Serde formats.
+    #[structopt(parse(from_os_str), required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Encoding of the input file (by default, JSON for `.json` files and YAML otherwise).
+    #[structopt(long, possible_values = &InputFormat::variants(), case_insensitive = true)]
+    input_format: Option<InputFormat>,
+
+    /// Encodings of the test vectors.
+    #[structopt(long = "encoding", possible_values = &VectorEncoding::variants(), case_insensitive = true, required = true)]
+    encodings: Vec<VectorEncoding>,
+
+    /// Path of the manifest (otherwise print it on stdout).
+    #[structopt(long, parse(from_os_str))]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct DiffOptions {
+    /// Path to the previous version of the registry.
+    #[structopt(parse(from_os_str))]
+    old: PathBuf,
+
+    /// Path to the new version of the registry.
+    #[structopt(parse(from_os_str))]
+    new: PathBuf,
+
+    /// Encoding of the input files (by default, JSON for `.json` files and YAML otherwise).
+    #[structopt(long, possible_values = &InputFormat::variants(), case_insensitive = true)]
+    input_format: Option<InputFormat>,
+
+    /// Format of the report.
+    #[structopt(long, possible_values = &ReportFormat::variants(), case_insensitive = true, default_value = "Text")]
+    format: ReportFormat,
+
+    /// Only fail on breaking changes: compatible additions exit with status 0.
+    #[structopt(long, possible_values = &Deny::variants(), case_insensitive = true)]
+    deny: Option<Deny>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct CheckOptions {
+    /// Paths to the YAML- or JSON-encoded Serde formats.
+    #[structopt(parse(from_os_str), required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Encoding of the input file (by default, JSON for `.json` files and YAML otherwise).
+    #[structopt(long, possible_values = &InputFormat::variants(), case_insensitive = true)]
+    input_format: Option<InputFormat>,
+
+    /// Languages to check (by default, all of them).
+    #[structopt(long = "language")]
+    languages: Vec<Language>,
+
+    /// Runtimes to check the registry against.
+    #[structopt(long, possible_values = &Runtime::variants(), case_insensitive = true)]
+    with_runtimes: Vec<Runtime>,
+
+    /// Check the translation of c-style enums into their equivalent in the target language.
+    #[structopt(long)]
+    use_c_style_enums: bool,
+
+    /// Accept floating-point formats when checking against the postcard runtime.
+    #[structopt(long)]
+    postcard_floats: bool,
+
+    /// Accept floating-point formats when checking against the BCS runtime.
+    #[structopt(long)]
+    allow_nonstandard_floats: bool,
+
+    /// Check the registry with transliterated names, and list the renamed identifiers.
+    #[structopt(long)]
+    transliterate_names: bool,
+}
+
+/// Code generation options read from the file given with `--config`. Qualified names are
+/// written as dotted paths starting with the module name, e.g. `my_module.MyStruct.my_field`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    module_name: Option<String>,
+    serde_package_name: Option<String>,
+    serialization: Option<bool>,
+    deserialization: Option<bool>,
+    use_c_style_enums: bool,
+    postcard_floats: bool,
+    allow_nonstandard_floats: bool,
+    transliterate_names: bool,
+    size_estimators: bool,
+    bcs_length_prefix: bool,
+    cpp_variant_enums: bool,
+    cpp_comparisons: Option<bool>,
+    cpp_zero_copy: bool,
+    runtime_schema: bool,
+    defensive_copies: bool,
+    clean: bool,
+    json_tags: bool,
+    nullable_annotations: bool,
+    yojson: bool,
+    enum_visitors: bool,
+    recognize_standard_types: bool,
+    /// Either `3.0` or `3.1`.
+    openapi_version: Option<String>,
+    openapi_base64_bytes: bool,
+    openapi_json: bool,
+    type_stubs: bool,
+    split_modules: bool,
+    package_manifest: bool,
+    package_version: Option<String>,
+    non_exhaustive_enums: bool,
+    /// Either `crate`, `single-file=<path>`, or `module-dir=<path>`.
+    rust_output_mode: Option<String>,
+    rust_fuzz_targets: Vec<String>,
+    format: bool,
+    /// Type names provided by external modules, indexed by module.
+    external_definitions: BTreeMap<String, Vec<String>>,
+    /// Doc comments, indexed by qualified name.
+    comments: BTreeMap<String, String>,
+    /// Custom code, indexed by qualified name. Values of the form `@file:path` are read from
+    /// the given path, relative to the config file.
+    custom_code: BTreeMap<String, String>,
+    /// Base classes of the generated Python classes, indexed by qualified name.
+    base_classes: BTreeMap<String, String>,
 }
 
-fn get_codegen_config<'a, I>(name: String, runtimes: I, c_style_enums: bool) -> CodeGeneratorConfig
+impl ConfigFile {
+    /// Fill in the options that were not set on the command line.
+    fn update_options(&mut self, options: &mut Options) {
+        if options.module_name.is_none() {
+            options.module_name = self.module_name.take();
+        }
+        if options.serde_package_name.is_none() {
+            options.serde_package_name = self.serde_package_name.take();
+        }
+        options.use_c_style_enums |= self.use_c_style_enums;
+        options.postcard_floats |= self.postcard_floats;
+        options.allow_nonstandard_floats |= self.allow_nonstandard_floats;
+        options.transliterate_names |= self.transliterate_names;
+        options.clean |= self.clean;
+        options.json_tags |= self.json_tags;
+        options.nullable_annotations |= self.nullable_annotations;
+        options.yojson |= self.yojson;
+        options.enum_visitors |= self.enum_visitors;
+        options.recognize_standard_types |= self.recognize_standard_types;
+        if options.openapi_version.is_none() {
+            options.openapi_version = self.openapi_version.as_ref().map(|version| {
+                version
+                    .parse()
+                    .expect("checked when reading the config file")
+            });
+        }
+        options.openapi_base64_bytes |= self.openapi_base64_bytes;
+        options.openapi_json |= self.openapi_json;
+        options.type_stubs |= self.type_stubs;
+        options.split_modules |= self.split_modules;
+        options.package_manifest |= self.package_manifest;
+        if options.package_version.is_none() {
+            options.package_version = self.package_version.take();
+        }
+        options.non_exhaustive_enums |= self.non_exhaustive_enums;
+        if options.rust_output_mode.is_none() {
+            options.rust_output_mode = self
+                .rust_output_mode
+                .as_ref()
+                .map(|mode| mode.parse().expect("checked when reading the config file"));
+        }
+        if options.rust_fuzz_targets.is_empty() {
+            options.rust_fuzz_targets = std::mem::take(&mut self.rust_fuzz_targets);
+        }
+        options.format |= self.format;
+    }
+
+    /// Base classes of the generated Python classes, indexed by qualified name.
+    fn base_classes(&self) -> BaseClasses {
+        self.base_classes
+            .iter()
+            .map(|(path, class)| (path.split('.').map(String::from).collect(), class.clone()))
+            .collect()
+    }
+
+    /// Add the options that have no command-line equivalent.
+    fn update_config(&self, config: CodeGeneratorConfig) -> CodeGeneratorConfig {
+        let qualified_name = |path: &String| path.split('.').map(String::from).collect();
+        let config = config
+            .with_external_definitions(self.external_definitions.clone())
+            .with_comments(
+                self.comments
+                    .iter()
+                    .map(|(path, comment)| (qualified_name(path), comment.clone()))
+                    .collect(),
+            )
+            .with_custom_code(
+                self.custom_code
+                    .iter()
+                    .map(|(path, code)| (qualified_name(path), code.clone()))
+                    .collect(),
+            )
+            .with_size_estimators(self.size_estimators)
+            .with_bcs_length_prefix(self.bcs_length_prefix)
+            .with_runtime_schema(self.runtime_schema);
+        let config = match self.serialization {
+            Some(serialization) => config.with_serialization(serialization),
+            None => config,
+        };
+        match self.deserialization {
+            Some(deserialization) => config.with_deserialization(deserialization),
+            None => config,
+        }
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+    let mut config: ConfigFile = match get_registry_format(path, None) {
+        InputFormat::Yaml => serde_yaml::from_str(&content)
+            .map_err(|error| format!("Failed to parse {}: {}", path.display(), error))?,
+        InputFormat::Json => serde_json::from_str(&content)
+            .map_err(|error| format!("Failed to parse {}: {}", path.display(), error))?,
+    };
+    if let Some(version) = &config.openapi_version {
+        version
+            .parse::<openapi::OpenApiVersion>()
+            .map_err(|error| format!("Failed to parse {}: {}", path.display(), error))?;
+    }
+    if let Some(mode) = &config.rust_output_mode {
+        mode.parse::<rust::OutputMode>()
+            .map_err(|error| format!("Failed to parse {}: {}", path.display(), error))?;
+    }
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for code in config.custom_code.values_mut() {
+        if let Some(file) = code.strip_prefix("@file:") {
+            let file = base_dir.join(file);
+            *code = std::fs::read_to_string(&file)
+                .map_err(|error| format!("Failed to read {}: {}", file.display(), error))?
+                .trim_end()
+                .to_string();
+        }
+    }
+    Ok(config)
+}
+
+fn get_codegen_config<'a, I>(
+    name: String,
+    runtimes: I,
+    c_style_enums: bool,
+    postcard_floats: bool,
+    allow_nonstandard_floats: bool,
+    transliterate_names: bool,
+    format: bool,
+) -> CodeGeneratorConfig
 where
     I: IntoIterator<Item = &'a Runtime>,
 {
@@ -94,112 +557,789 @@ where
             Runtime::Bcs => {
                 encodings.push(Encoding::Bcs);
             }
+            Runtime::Postcard => {
+                encodings.push(Encoding::Postcard);
+            }
+            Runtime::Msgpack => {
+                encodings.push(Encoding::MessagePack);
+            }
             Runtime::Serde => (),
         }
     }
-    CodeGeneratorConfig::new(name)
+    let config = CodeGeneratorConfig::new(name)
         .with_encodings(encodings)
         .with_c_style_enums(c_style_enums)
+        .with_postcard_floats(postcard_floats)
+        .with_allow_nonstandard_floats(allow_nonstandard_floats);
+    let config = if transliterate_names {
+        config.with_non_ascii_names(NonAsciiNames::Transliterate)
+    } else {
+        config
+    };
+    if format {
+        config.with_formatter(FormatterHook::standard())
+    } else {
+        config
+    }
+}
+
+/// Parse the names of languages given with `--language`, expanding `all`.
+fn get_languages(names: &[String]) -> Result<Vec<Language>, Box<dyn std::error::Error>> {
+    let mut languages = Vec::new();
+    for name in names {
+        let selected = if name.eq_ignore_ascii_case("all") {
+            Language::available()
+        } else {
+            vec![name
+                .parse::<Language>()
+                .map_err(|error| format!("{}, or all", error))?]
+        };
+        for language in selected {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+    Ok(languages)
+}
+
+/// Use the given format, or deduce it from the extension of the path.
+fn get_registry_format(path: &Path, format: Option<InputFormat>) -> InputFormat {
+    format.unwrap_or_else(
+        || match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => InputFormat::Json,
+            _ => InputFormat::Yaml,
+        },
+    )
+}
+
+/// Limits of the registries read from the input files.
+fn get_registry_limits(options: &Options) -> validation::RegistryLimits {
+    let mut limits = validation::RegistryLimits::new();
+    if let Some(size) = options.max_tuple_array_size {
+        limits = limits.with_max_tuple_array_size(size);
+    }
+    if let Some(depth) = options.max_depth {
+        limits = limits.with_max_depth(depth);
+    }
+    limits
+}
+
+fn read_registry(
+    input: &Path,
+    format: Option<InputFormat>,
+    limits: &validation::RegistryLimits,
+) -> Result<Registry, Box<dyn std::error::Error>> {
+    let format = get_registry_format(input, format);
+    let content = std::fs::read_to_string(input)
+        .map_err(|error| format!("Failed to read {}: {}", input.display(), error))?;
+    let registry = match format {
+        InputFormat::Yaml => serde_yaml::from_str::<Registry>(&content)
+            .map_err(|error| format!("Failed to parse {}: {}", input.display(), error))?,
+        InputFormat::Json => serde_json::from_str::<Registry>(&content)
+            .map_err(|error| format!("Failed to parse {}: {}", input.display(), error))?,
+    };
+    validation::validate_registry_limits(&registry, limits)
+        .map_err(|error| format!("Invalid registry in {}: {}", input.display(), error))?;
+    Ok(registry)
+}
+
+fn write_registry(
+    output: &Path,
+    format: Option<InputFormat>,
+    registry: &Registry,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match get_registry_format(output, format) {
+        InputFormat::Yaml => serde_yaml::to_string(registry)?,
+        InputFormat::Json => serde_json::to_string_pretty(registry)? + "\n",
+    };
+    std::fs::write(output, content)
+        .map_err(|error| format!("Failed to write {}: {}", output.display(), error))?;
+    Ok(())
+}
+
+/// Merge the registries of several input files. Inputs are sorted first so that the result
+/// (including errors) does not depend on the order of the arguments.
+fn read_registries(
+    inputs: &[PathBuf],
+    format: Option<InputFormat>,
+    limits: &validation::RegistryLimits,
+) -> Result<Registry, Box<dyn std::error::Error>> {
+    let mut inputs = inputs.iter().collect::<Vec<_>>();
+    inputs.sort();
+    let mut merged = BTreeMap::<String, (ContainerFormat, &Path)>::new();
+    for input in inputs {
+        for (name, format) in read_registry(input, format, limits)? {
+            match merged.get(&name) {
+                None => {
+                    merged.insert(name, (format, input));
+                }
+                Some((previous, previous_input)) => {
+                    if *previous != format {
+                        return Err(format!(
+                            "Container {} is defined differently in {} and {}",
+                            name,
+                            previous_input.display(),
+                            input.display()
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+    }
+    Ok(merged
+        .into_iter()
+        .map(|(name, (format, _))| (name, format))
+        .collect())
 }
 
 fn main() {
-    let options = Options::from_args();
-    let serde_package_name_opt = options.serde_package_name.clone();
-    let named_registry_opt = match &options.input {
-        None => None,
-        Some(input) => {
-            let name = options.module_name.clone().unwrap_or_else(|| {
-                input
-                    .file_stem()
-                    .expect("failed to deduce module name from input path")
-                    .to_string_lossy()
-                    .into_owned()
-            });
-            let content = std::fs::read_to_string(input).expect("input file must be readable");
-            let registry = serde_yaml::from_str::<Registry>(content.as_str()).unwrap();
-            Some((registry, name))
+    match run(Options::from_args()) {
+        Ok(0) => (),
+        Ok(status) => std::process::exit(status),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
         }
+    }
+}
+
+/// Report the unsupported parts of the registry for each language. Fails if any language
+/// has issues.
+fn check(options: CheckOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = read_registries(
+        &options.inputs,
+        options.input_format,
+        &validation::RegistryLimits::default(),
+    )?;
+    let runtimes: std::collections::BTreeSet<_> = options.with_runtimes.iter().collect();
+    let config = get_codegen_config(
+        "check".to_string(),
+        runtimes,
+        options.use_c_style_enums,
+        options.postcard_floats,
+        options.allow_nonstandard_floats,
+        options.transliterate_names,
+        false,
+    );
+    for (path, name) in validation::renamed_identifiers(&config, &registry) {
+        println!("Renamed `{}` to `{}`", path, name);
+    }
+    let languages = if options.languages.is_empty() {
+        Language::available()
+    } else {
+        options.languages
     };
-    let runtimes: std::collections::BTreeSet<_> = options.with_runtimes.into_iter().collect();
+    let mut unsupported = Vec::new();
+    for language in languages {
+        let issues: Result<_, serde_generate::ConfigError> = match language {
+            Language::Python3 => {
+                python3::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::Rust => Ok(rust::CodeGenerator::new(&config).validate(&registry)),
+            Language::Cpp => cpp::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+            Language::Solidity => {
+                solidity::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::Java => java::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+            Language::Go => golang::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+            Language::Dart => dart::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+            Language::TypeScript => {
+                typescript::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::CSharp => {
+                csharp::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::Swift => {
+                swift::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::OCaml => {
+                ocaml::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::Zig => zig::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+            Language::OpenApi => {
+                openapi::CodeGenerator::try_new(&config).map(|g| g.validate(&registry))
+            }
+            Language::Php => php::CodeGenerator::try_new(&config).map(|g| g.validate(&registry)),
+        };
+        match issues {
+            Ok(issues) if issues.is_empty() => println!("{}: OK", language.name()),
+            Ok(issues) => {
+                println!("{}: {} issue(s)", language.name(), issues.len());
+                for issue in issues {
+                    println!("  {}", issue);
+                }
+                unsupported.push(language.name().to_string());
+            }
+            Err(error) => {
+                println!("{}: {}", language.name(), error);
+                unsupported.push(language.name().to_string());
+            }
+        }
+    }
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Unsupported registry for {}", unsupported.join(", ")).into())
+    }
+}
+
+/// Print the capabilities of each available language as a table.
+fn print_languages() {
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    println!(
+        "{:<12} {:<24} {:<14} {:<9} {:<12} {:<12} one-way",
+        "language", "encodings", "c-style enums", "manifest", "single file", "split files"
+    );
+    for language in Language::available() {
+        let capabilities = language
+            .capabilities()
+            .expect("available languages have capabilities");
+        let encodings = capabilities
+            .encodings
+            .iter()
+            .map(|encoding| encoding.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{:<12} {:<24} {:<14} {:<9} {:<12} {:<12} {}",
+            language.name(),
+            encodings,
+            yes_no(capabilities.c_style_enums),
+            yes_no(capabilities.package_manifest),
+            yes_no(capabilities.single_file),
+            yes_no(capabilities.split_files),
+            yes_no(capabilities.one_way_serialization)
+        );
+    }
+}
+
+/// Print the changes between two registries and return the exit status.
+fn diff(options: DiffOptions) -> Result<i32, Box<dyn std::error::Error>> {
+    let old = read_registry(
+        &options.old,
+        options.input_format,
+        &validation::RegistryLimits::default(),
+    )?;
+    let new = read_registry(
+        &options.new,
+        options.input_format,
+        &validation::RegistryLimits::default(),
+    )?;
+    let changes = diff::diff_registries(&old, &new);
+    let breaking = changes.iter().filter(|change| change.is_breaking()).count();
+    match options.format {
+        ReportFormat::Text => {
+            for change in &changes {
+                let severity = if change.is_breaking() {
+                    "breaking"
+                } else {
+                    "compatible"
+                };
+                println!("{:<10}  {}", severity, change);
+            }
+            if changes.is_empty() {
+                println!("No changes");
+            } else {
+                println!(
+                    "{} breaking change(s), {} compatible change(s)",
+                    breaking,
+                    changes.len() - breaking
+                );
+            }
+        }
+        ReportFormat::Json => {
+            let mut entries = Vec::new();
+            for change in &changes {
+                let mut entry = serde_json::to_value(change)?;
+                entry["breaking"] = change.is_breaking().into();
+                entries.push(entry);
+            }
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+    }
+    let status = if breaking > 0 {
+        3
+    } else if changes.is_empty() || options.deny == Some(Deny::Breaking) {
+        0
+    } else {
+        2
+    };
+    Ok(status)
+}
+
+/// Code generation config for the given command-line options.
+fn get_options_config(
+    options: &Options,
+    config_file: &ConfigFile,
+    name: &str,
+    runtimes: &std::collections::BTreeSet<&Runtime>,
+    format: bool,
+) -> CodeGeneratorConfig {
+    let config = get_codegen_config(
+        name.to_string(),
+        runtimes.iter().copied(),
+        options.use_c_style_enums,
+        options.postcard_floats,
+        options.allow_nonstandard_floats,
+        options.transliterate_names,
+        format,
+    );
+    let config = config_file.update_config(config);
+    if options.types_only {
+        config.with_serialization(false).with_deserialization(false)
+    } else if options.deserialize_only {
+        config.with_serialization(false).with_deserialization(true)
+    } else if options.serialize_only {
+        config.with_serialization(true).with_deserialization(false)
+    } else {
+        config
+    }
+}
 
-    match options.target_source_dir {
+/// Generate code for one language, printing it on stdout or installing it in `target_dir`.
+fn generate(
+    options: &Options,
+    config_file: &ConfigFile,
+    language: Language,
+    named_registry_opt: Option<&(Registry, String)>,
+    runtimes: &std::collections::BTreeSet<&Runtime>,
+    target_dir: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let serde_package_name_opt = options.serde_package_name.clone();
+    let well_known_types = match named_registry_opt {
+        Some((registry, _)) if options.recognize_standard_types => {
+            WellKnownType::recognize_all(registry)
+        }
+        _ => WellKnownTypes::new(),
+    };
+    let openapi_version = options
+        .openapi_version
+        .unwrap_or(openapi::OpenApiVersion::V3_1);
+    let openapi_bytes = if options.openapi_base64_bytes {
+        openapi::OpenApiBytes::Base64
+    } else {
+        openapi::OpenApiBytes::IntegerArray
+    };
+    match target_dir {
         None => {
             if let Some((registry, name)) = named_registry_opt {
-                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums);
+                let config = get_options_config(options, config_file, name, runtimes, false);
 
+                if !language
+                    .capabilities()
+                    .map_or(false, |capabilities| capabilities.single_file)
+                {
+                    return Err(format!(
+                        "Code generation in {} writes several files and cannot print code on stdout: use `--target-source-dir <DIR>`",
+                        language.name()
+                    )
+                    .into());
+                }
                 let stdout = std::io::stdout();
                 let mut out = stdout.lock();
-                match options.language {
-                    Language::Python3 => python3::CodeGenerator::new(&config)
+                match language {
+                    Language::Python3 => python3::CodeGenerator::try_new(&config)?
                         .with_serde_package_name(serde_package_name_opt)
-                        .output(&mut out, &registry)
-                        .unwrap(),
+                        .with_well_known_types(well_known_types)
+                        .with_base_class(config_file.base_classes())
+                        .output(&mut out, registry)?,
                     Language::Rust => rust::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::Cpp => cpp::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::Solidity => solidity::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::Go => golang::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::Java => {
-                        panic!("Code generation in Java requires `--target-source-dir`")
+                        .with_non_exhaustive_enums(options.non_exhaustive_enums)
+                        .output(&mut out, registry)?,
+                    Language::Cpp => cpp::CodeGenerator::try_new(&config)?
+                        .with_variant_enums(config_file.cpp_variant_enums)
+                        .with_comparisons(config_file.cpp_comparisons.unwrap_or(true))
+                        .with_zero_copy(config_file.cpp_zero_copy)
+                        .output(&mut out, registry)?,
+                    Language::Solidity => {
+                        solidity::CodeGenerator::try_new(&config)?.output(&mut out, registry)?
                     }
-                    Language::Dart => {
-                        panic!("Code generation in Dart requires `--target-source-dir`")
+                    Language::Go => golang::CodeGenerator::try_new(&config)?
+                        .with_json_tags(options.json_tags)
+                        .output(&mut out, registry)?,
+                    Language::Java | Language::Dart | Language::CSharp | Language::Php => {
+                        unreachable!("{} only writes several files", language.name())
+                    }
+                    Language::TypeScript => {
+                        typescript::CodeGenerator::try_new(&config)?.output(&mut out, registry)?
+                    }
+                    Language::Swift => {
+                        swift::CodeGenerator::try_new(&config)?.output(&mut out, registry)?
                     }
-                    Language::TypeScript => typescript::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::CSharp => {
-                        panic!("Code generation in C# requires `--target-source-dir`")
+                    Language::OCaml => ocaml::CodeGenerator::try_new(&config)?
+                        .with_yojson(options.yojson)
+                        .output(&mut out, registry)?,
+                    Language::Zig => {
+                        zig::CodeGenerator::try_new(&config)?.output(&mut out, registry)?
                     }
-                    Language::Swift => swift::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
-                    Language::OCaml => ocaml::CodeGenerator::new(&config)
-                        .output(&mut out, &registry)
-                        .unwrap(),
+                    Language::OpenApi => openapi::CodeGenerator::try_new(&config)?
+                        .with_json(options.openapi_json)
+                        .with_openapi_version(openapi_version)
+                        .with_openapi_bytes(openapi_bytes)
+                        .output(&mut out, registry)?,
                 }
             }
         }
 
         Some(install_dir) => {
             let installer: Box<dyn SourceInstaller<Error = Box<dyn std::error::Error>>> =
-                match options.language {
+                match language {
                     Language::Python3 => {
-                        Box::new(python3::Installer::new(install_dir, serde_package_name_opt))
+                        let mut installer =
+                            python3::Installer::new(install_dir, serde_package_name_opt)
+                                .with_type_stubs(options.type_stubs)
+                                .with_split_modules(options.split_modules)
+                                .with_manifest(options.package_manifest)
+                                .with_well_known_types(well_known_types)
+                                .with_base_class(config_file.base_classes());
+                        if let Some(version) = &options.package_version {
+                            installer = installer.with_package_version(version.clone());
+                        }
+                        Box::new(installer)
                     }
-                    Language::Rust => Box::new(rust::Installer::new(install_dir)),
-                    Language::Cpp => Box::new(cpp::Installer::new(install_dir)),
+                    Language::Rust => Box::new(
+                        rust::Installer::new(install_dir)
+                            .with_non_exhaustive_enums(options.non_exhaustive_enums)
+                            .with_output_mode(options.rust_output_mode.clone().unwrap_or_default())
+                            .with_split_modules(options.split_modules)
+                            .with_fuzz_targets(options.rust_fuzz_targets.clone()),
+                    ),
+                    Language::Cpp => Box::new(
+                        cpp::Installer::new(install_dir)
+                            .with_manifest(options.package_manifest)
+                            .with_variant_enums(config_file.cpp_variant_enums)
+                            .with_comparisons(config_file.cpp_comparisons.unwrap_or(true))
+                            .with_zero_copy(config_file.cpp_zero_copy),
+                    ),
                     Language::Solidity => Box::new(solidity::Installer::new(install_dir)),
-                    Language::Java => Box::new(java::Installer::new(install_dir)),
-                    Language::Go => {
-                        Box::new(golang::Installer::new(install_dir, serde_package_name_opt))
+                    Language::Java => Box::new(
+                        java::Installer::new(install_dir)
+                            .with_enum_visitors(options.enum_visitors)
+                            .with_well_known_types(well_known_types)
+                            .with_defensive_copies(config_file.defensive_copies),
+                    ),
+                    Language::Go => Box::new(
+                        golang::Installer::new(install_dir, serde_package_name_opt)
+                            .with_json_tags(options.json_tags),
+                    ),
+                    Language::Dart => {
+                        Box::new(dart::Installer::new(install_dir).with_clean(options.clean))
                     }
-                    Language::Dart => Box::new(dart::Installer::new(install_dir)),
                     Language::TypeScript => Box::new(typescript::Installer::new(install_dir)),
-                    Language::CSharp => Box::new(csharp::Installer::new(install_dir)),
-                    Language::Swift => Box::new(swift::Installer::new(install_dir)),
-                    Language::OCaml => Box::new(ocaml::Installer::new(install_dir)),
+                    Language::CSharp => Box::new(
+                        csharp::Installer::new(install_dir)
+                            .with_nullable_annotations(options.nullable_annotations)
+                            .with_defensive_copies(config_file.defensive_copies),
+                    ),
+                    Language::Swift => {
+                        Box::new(swift::Installer::new(install_dir).with_clean(options.clean))
+                    }
+                    Language::OCaml => {
+                        Box::new(ocaml::Installer::new(install_dir).with_yojson(options.yojson))
+                    }
+                    Language::Zig => Box::new(
+                        zig::Installer::new(install_dir).with_manifest(options.package_manifest),
+                    ),
+                    Language::Php => Box::new(
+                        php::Installer::new(install_dir).with_manifest(options.package_manifest),
+                    ),
+                    Language::OpenApi => Box::new(
+                        openapi::Installer::new(install_dir)
+                            .with_json(options.openapi_json)
+                            .with_openapi_version(openapi_version)
+                            .with_openapi_bytes(openapi_bytes),
+                    ),
                 };
 
             if let Some((registry, name)) = named_registry_opt {
-                let config = get_codegen_config(name, &runtimes, options.use_c_style_enums);
-                installer.install_module(&config, &registry).unwrap();
+                let config =
+                    get_options_config(options, config_file, name, runtimes, options.format);
+                installer.install_module(&config, registry)?;
             }
 
             for runtime in runtimes {
                 match runtime {
-                    Runtime::Serde => installer.install_serde_runtime().unwrap(),
-                    Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
-                    Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
+                    Runtime::Serde => installer.install_serde_runtime()?,
+                    Runtime::Bincode => installer.install_bincode_runtime()?,
+                    Runtime::Bcs => installer.install_bcs_runtime()?,
+                    Runtime::Postcard => installer.install_postcard_runtime()?,
+                    Runtime::Msgpack => installer.install_msgpack_runtime()?,
                 }
             }
         }
     }
+    Ok(())
+}
+
+/// Test vectors of a registry, as written by the `vectors` command.
+#[derive(Serialize)]
+struct VectorsManifest {
+    /// Hexadecimal `serde_reflection::schema_hash` of the registry.
+    registry_hash: String,
+    /// Hexadecimal encodings, indexed by container and encoding names.
+    vectors: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Write the encodings of a canonical value of each container.
+fn vectors(options: VectorsOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = read_registries(
+        &options.inputs,
+        options.input_format,
+        &validation::RegistryLimits::default(),
+    )?;
+    let config = TracerConfig::default();
+    let mut vectors = BTreeMap::new();
+    for name in registry.keys() {
+        let format = Format::TypeName(name.clone());
+        let value = Value::sample(&config, &registry, &format)?;
+        let value = FormattedValue::new(&registry, &format, &value);
+        let mut encodings = BTreeMap::new();
+        for encoding in &options.encodings {
+            let bytes = match encoding {
+                VectorEncoding::Bcs => bcs::to_bytes(&value)?,
+                VectorEncoding::Bincode => bincode::serialize(&value)?,
+            };
+            encodings.insert(encoding.to_string().to_lowercase(), hex(&bytes));
+        }
+        vectors.insert(name.clone(), encodings);
+    }
+    let manifest = VectorsManifest {
+        registry_hash: hex(&serde_reflection::schema_hash(&registry)),
+        vectors,
+    };
+    let content = serde_json::to_string_pretty(&manifest)? + "\n";
+    match &options.out {
+        Some(path) => std::fs::write(path, content)?,
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// Lowercase hexadecimal representation of `bytes`.
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+fn run(mut options: Options) -> Result<i32, Box<dyn std::error::Error>> {
+    match options.command.take() {
+        Some(Command::Check(options)) => return check(options).map(|()| 0),
+        Some(Command::Diff(options)) => return diff(options),
+        Some(Command::Vectors(options)) => return vectors(options).map(|()| 0),
+        Some(Command::Languages) => {
+            print_languages();
+            return Ok(0);
+        }
+        None => (),
+    }
+    if options.watch {
+        return watch(options);
+    }
+    generate_all(options)
+}
+
+/// Generate code for all the requested languages.
+fn generate_all(mut options: Options) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut config_file = match &options.config {
+        Some(path) => read_config_file(path)?,
+        None => ConfigFile::default(),
+    };
+    config_file.update_options(&mut options);
+    if options.target_source_dir.as_deref() == Some(Path::new("-")) {
+        options.target_source_dir = None;
+    }
+    if options.types_only && !options.with_runtimes.is_empty() {
+        return Err("`--types-only` cannot be combined with `--with-runtimes`".into());
+    }
+    let exclusive_flags = [
+        ("--types-only", options.types_only),
+        ("--deserialize-only", options.deserialize_only),
+        ("--serialize-only", options.serialize_only),
+    ]
+    .iter()
+    .filter(|(_, is_set)| *is_set)
+    .map(|(flag, _)| *flag)
+    .collect::<Vec<_>>();
+    if exclusive_flags.len() > 1 {
+        return Err(format!(
+            "`{}` cannot be combined with `{}`",
+            exclusive_flags[0], exclusive_flags[1]
+        )
+        .into());
+    }
+    if options.cache_dir.is_some() && options.target_source_dir.is_none() {
+        return Err("`--cache-dir` requires `--target-source-dir`".into());
+    }
+    let named_registry_opt = match options.inputs.as_slice() {
+        [] => None,
+        inputs => {
+            let name = match (&options.module_name, inputs) {
+                (Some(name), _) => name.clone(),
+                (None, [input]) => input
+                    .file_stem()
+                    .expect("failed to deduce module name from input path")
+                    .to_string_lossy()
+                    .into_owned(),
+                (None, _) => {
+                    return Err("`--module-name` is required with several input files".into())
+                }
+            };
+            let registry =
+                read_registries(inputs, options.input_format, &get_registry_limits(&options))?;
+            if let Some(output) = &options.emit_registry {
+                write_registry(output, options.input_format, &registry)?;
+            }
+            let registry =
+                if options.include_containers.is_empty() && options.exclude_containers.is_empty() {
+                    registry
+                } else {
+                    analyzer::filter_registry(
+                        &registry,
+                        &options.include_containers.iter().cloned().collect(),
+                        &options.exclude_containers.iter().cloned().collect(),
+                    )?
+                };
+            Some((registry, name))
+        }
+    };
+    let runtimes: std::collections::BTreeSet<_> = options.with_runtimes.iter().collect();
+    if let Some((registry, name)) = &named_registry_opt {
+        let config = get_options_config(&options, &config_file, name, &runtimes, false);
+        for (path, name) in validation::renamed_identifiers(&config, registry) {
+            eprintln!("Renamed `{}` to `{}`", path, name);
+        }
+    }
+    let languages = get_languages(&options.languages)?;
+    if languages.len() > 1 && options.target_source_dir.is_none() {
+        return Err("`--target-source-dir` is required with several languages".into());
+    }
+
+    // With several languages, report all failures at the end.
+    let mut failures = Vec::new();
+    for language in &languages {
+        let target_dir = options.target_source_dir.as_ref().map(|dir| {
+            if languages.len() > 1 {
+                dir.join(language.name().to_lowercase())
+            } else {
+                dir.clone()
+            }
+        });
+        let result = match (&options.cache_dir, &named_registry_opt, &target_dir) {
+            (Some(cache_dir), Some((registry, name)), Some(dir)) => {
+                let config =
+                    get_options_config(&options, &config_file, name, &runtimes, options.format);
+                // Command-line options and the config file also cover the settings of the
+                // installers and code generators.
+                let description = format!("{:?} {:?}", options, config_file);
+                serde_generate::cached_generate(
+                    cache_dir,
+                    dir,
+                    *language,
+                    &config,
+                    registry,
+                    &description,
+                    || {
+                        generate(
+                            &options,
+                            &config_file,
+                            *language,
+                            named_registry_opt.as_ref(),
+                            &runtimes,
+                            target_dir.clone(),
+                        )
+                    },
+                )
+                .map(|generated| {
+                    if !generated {
+                        eprintln!("{}: generated code is up to date", language.name());
+                    }
+                })
+            }
+            _ => generate(
+                &options,
+                &config_file,
+                *language,
+                named_registry_opt.as_ref(),
+                &runtimes,
+                target_dir,
+            ),
+        };
+        match result {
+            Ok(()) => (),
+            Err(error) if languages.len() == 1 => return Err(error),
+            Err(error) => {
+                eprintln!("{}: {}", language.name(), error);
+                failures.push(language.name().to_string());
+            }
+        }
+    }
+    if !failures.is_empty() {
+        return Err(format!("Code generation failed for {}", failures.join(", ")).into());
+    }
+    Ok(0)
+}
+
+/// Delay during which successive changes are grouped into a single regeneration, e.g. for
+/// editors that save files twice.
+const WATCH_DEBOUNCE_DELAY: Duration = Duration::from_millis(200);
+
+/// Generate code, then regenerate it whenever an input file or the config file changes.
+/// Only fails if the files cannot be watched.
+fn watch(options: Options) -> Result<i32, Box<dyn std::error::Error>> {
+    if options.inputs.is_empty() {
+        return Err("`--watch` requires input files".into());
+    }
+    let mut paths = BTreeSet::new();
+    for path in options.inputs.iter().chain(&options.config) {
+        paths.insert(std::fs::canonicalize(path)?);
+    }
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    // Editors may save files by replacing them, hence watching the parent directories.
+    let directories = paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .collect::<BTreeSet<_>>();
+    for directory in directories {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    }
+
+    report_generation(generate_all(options.clone()));
+    loop {
+        let event = receiver.recv()??;
+        if !is_watched_event(&event, &paths) {
+            continue;
+        }
+        // Drain the events that follow closely.
+        while receiver.recv_timeout(WATCH_DEBOUNCE_DELAY).is_ok() {}
+        report_generation(generate_all(options.clone()));
+    }
+}
+
+fn is_watched_event(event: &notify::Event, paths: &BTreeSet<PathBuf>) -> bool {
+    if event.kind.is_access() {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        // The file itself may have been deleted or renamed.
+        let path = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => std::fs::canonicalize(parent)
+                .map(|parent| parent.join(name))
+                .unwrap_or_else(|_| path.clone()),
+            _ => path.clone(),
+        };
+        paths.contains(&path)
+    })
+}
+
+fn report_generation(result: Result<i32, Box<dyn std::error::Error>>) {
+    match result {
+        Ok(_) => eprintln!("Generation succeeded"),
+        Err(error) => eprintln!("Generation failed: {}", error),
+    }
 }
@@ -1,6 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use serde_reflection::Registry;
 use std::process::Command;
 use tempfile::tempdir;
 
@@ -136,6 +137,7 @@ fn test_that_installed_python_code_parses() {
         .arg(dir.path())
         .arg("--module-name")
         .arg("test_types")
+        .arg("--allow-nonstandard-floats")
         .arg("--with-runtimes")
         .arg("serde")
         .arg("bincode")
@@ -160,6 +162,208 @@ fn test_that_installed_python_code_parses() {
     assert!(status.success());
 }
 
+#[test]
+fn test_that_installed_python_code_parses_from_json() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let json_path = dir.path().join("test.json");
+    std::fs::write(json_path.clone(), serde_json::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--allow-nonstandard-floats")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("bincode")
+        .arg("bcs")
+        .arg("--")
+        .arg(json_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let python_path = format!(
+        "{}:{}",
+        std::env::var("PYTHONPATH").unwrap_or_default(),
+        dir.path().to_string_lossy(),
+    );
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import serde_types; import bincode; import bcs; import test_types")
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_json_and_yaml_inputs_generate_the_same_code() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+    // An explicit input format takes precedence over the file extension.
+    let json_path = dir.path().join("test.txt");
+    std::fs::write(json_path.clone(), serde_json::to_string(&registry).unwrap()).unwrap();
+
+    let generate = |path: &std::path::Path, input_format: &str| {
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("-p")
+            .arg("serde-generate-bin")
+            .arg("--")
+            .arg("--language")
+            .arg("rust")
+            .arg("--module-name")
+            .arg("test_types")
+            .arg("--input-format")
+            .arg(input_format)
+            .arg("--")
+            .arg(path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    assert_eq!(generate(&yaml_path, "yaml"), generate(&json_path, "json"));
+}
+
+fn run_serdegen_emitting_registry(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    registry_format: Option<&str>,
+) -> std::process::Output {
+    let mut command = Command::new("cargo");
+    command
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("rust")
+        .arg("--emit-registry")
+        .arg(output);
+    if let Some(format) = registry_format {
+        command.arg("--registry-format").arg(format);
+    }
+    command.arg("--").arg(input).output().unwrap()
+}
+
+#[test]
+fn test_that_registry_is_emitted_as_json() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, serde_yaml::to_string(&registry).unwrap()).unwrap();
+    let json_path = dir.path().join("test.json");
+
+    let output = run_serdegen_emitting_registry(&yaml_path, &json_path, None);
+    assert!(output.status.success());
+    let content = std::fs::read_to_string(&json_path).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Registry>(&content).unwrap(),
+        registry
+    );
+
+    // Generating code from the emitted registry gives the same result.
+    let yaml_output = run_serdegen_emitting_registry(&json_path, &yaml_path, None);
+    assert!(yaml_output.status.success());
+    assert_eq!(yaml_output.stdout, output.stdout);
+    let content = std::fs::read_to_string(&yaml_path).unwrap();
+    assert_eq!(
+        serde_yaml::from_str::<Registry>(&content).unwrap(),
+        registry
+    );
+}
+
+#[test]
+fn test_that_registry_format_applies_to_inputs_and_emitted_registry() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.registry");
+    std::fs::write(&input_path, serde_json::to_string(&registry).unwrap()).unwrap();
+    let output_path = dir.path().join("output.registry");
+
+    let output = run_serdegen_emitting_registry(&input_path, &output_path, Some("json"));
+    assert!(output.status.success());
+    let content = std::fs::read_to_string(&output_path).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Registry>(&content).unwrap(),
+        registry
+    );
+}
+
+#[test]
+fn test_that_json_parse_errors_are_reported() {
+    let dir = tempdir().unwrap();
+    let json_path = dir.path().join("test.json");
+    std::fs::write(json_path.clone(), "{\n  \"Foo\": {\"STRUCT\": [}\n}").unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg(&json_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Failed to parse {}", json_path.display())));
+    assert!(stderr.contains("line 2 column"));
+}
+
+#[test]
+fn test_that_installed_python_code_with_postcard_parses() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("postcard")
+        .arg("--postcard-floats")
+        .arg("--")
+        .arg(yaml_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let python_path = format!(
+        "{}:{}",
+        std::env::var("PYTHONPATH").unwrap_or_default(),
+        dir.path().to_string_lossy(),
+    );
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import serde_types; import postcard; import test_types; test_types.SerdeData.postcard_deserialize")
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_that_installed_python_code_with_package_parses() {
     let registry = test_utils::get_registry().unwrap();
@@ -180,6 +384,7 @@ fn test_that_installed_python_code_with_package_parses() {
         .arg("test_types")
         .arg("--serde-package-name")
         .arg("my_package")
+        .arg("--allow-nonstandard-floats")
         .arg("--with-runtimes")
         .arg("serde")
         .arg("bincode")
@@ -213,7 +418,7 @@ __all__ = ["bcs", "serde_types", "serde_binary", "bincode", "test_types"]
 }
 
 #[test]
-fn test_that_installed_rust_code_compiles() {
+fn test_that_installed_python_package_with_manifest_installs() {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let yaml_path = dir.path().join("test.yaml");
@@ -225,38 +430,77 @@ fn test_that_installed_rust_code_compiles() {
         .arg("serde-generate-bin")
         .arg("--")
         .arg("--language")
-        .arg("rust")
-        .arg("--module-name")
-        .arg("testing:0.2.0")
+        .arg("python3")
         .arg("--target-source-dir")
-        .arg(dir.path())
+        .arg(dir.path().join("project"))
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--serde-package-name")
+        .arg("my_package")
+        .arg("--package-manifest")
+        .arg("--package-version")
+        .arg("1.2.3")
+        .arg("--allow-nonstandard-floats")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("bincode")
+        .arg("bcs")
+        .arg("--")
         .arg(yaml_path)
         .status()
         .unwrap();
     assert!(status.success());
 
-    // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
-    let target_dir = std::env::current_dir().unwrap().join("../target");
-    let status = Command::new("cargo")
-        .current_dir(dir.path().join("testing"))
-        .arg("build")
-        .arg("--target-dir")
-        .arg(target_dir)
+    let manifest = std::fs::read_to_string(dir.path().join("project/pyproject.toml")).unwrap();
+    assert!(manifest.contains("name = \"my_package\"\nversion = \"1.2.3\"\n"));
+    let init = std::fs::read_to_string(dir.path().join("project/my_package/__init__.py")).unwrap();
+    assert_eq!(
+        init,
+        "__all__ = [\"bcs\", \"bincode\", \"serde_binary\", \"serde_json\", \"serde_types\", \"test_types\"]\n"
+    );
+
+    let python_path = format!(
+        "{}:{}",
+        std::env::var("PYTHONPATH").unwrap_or_default(),
+        dir.path().join("project").to_string_lossy(),
+    );
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("from my_package import bcs, bincode, serde_types, test_types; test_types.SerdeData.bcs_deserialize")
+        .env("PYTHONPATH", python_path)
         .status()
         .unwrap();
     assert!(status.success());
-}
 
-#[test]
-fn create_test_yaml() {
-    let registry = test_utils::get_registry().unwrap();
-    let dir = tempdir().unwrap();
-    let yaml_path = dir.path().join("test.yaml");
-    std::fs::write(yaml_path, serde_yaml::to_string(&registry).unwrap()).unwrap();
+    // Building a source distribution (as `python3 -m build --sdist` would) requires setuptools.
+    let has_setuptools = Command::new("python3")
+        .arg("-c")
+        .arg("import setuptools")
+        .status()
+        .map_or(false, |status| status.success());
+    if !has_setuptools {
+        return;
+    }
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("from setuptools import build_meta; build_meta.build_sdist('../dist')")
+        .current_dir(dir.path().join("project"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = Command::new("tar")
+        .arg("tzf")
+        .arg(dir.path().join("dist/my_package-1.2.3.tar.gz"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let files = String::from_utf8(output.stdout).unwrap();
+    assert!(files.contains("my_package-1.2.3/my_package/test_types/__init__.py"));
+    assert!(files.contains("my_package-1.2.3/my_package/serde_binary/__init__.py"));
 }
 
 #[test]
-fn test_that_installed_cpp_code_compiles() {
+fn test_that_installed_python_code_with_split_modules_runs() {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let yaml_path = dir.path().join("test.yaml");
@@ -268,34 +512,44 @@ fn test_that_installed_cpp_code_compiles() {
         .arg("serde-generate-bin")
         .arg("--")
         .arg("--language")
-        .arg("cpp")
+        .arg("python3")
         .arg("--target-source-dir")
         .arg(dir.path())
-        .arg(yaml_path)
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--split-modules")
+        .arg("--allow-nonstandard-floats")
         .arg("--with-runtimes")
         .arg("serde")
-        .arg("bincode")
         .arg("bcs")
         .arg("--")
+        .arg(yaml_path)
         .status()
         .unwrap();
     assert!(status.success());
 
-    let status = Command::new("clang++")
-        .arg("--std=c++17")
+    let module_dir = dir.path().join("test_types");
+    assert!(module_dir.join("serde_data.py").exists());
+    assert!(module_dir.join("c_style_enum.py").exists());
+    let init = std::fs::read_to_string(module_dir.join("__init__.py")).unwrap();
+    assert!(init.contains("from .serde_data import SerdeData, SerdeData__PrimitiveTypes, "));
+
+    let python_path = format!(
+        "{}:{}",
+        std::env::var("PYTHONPATH").unwrap_or_default(),
+        dir.path().to_string_lossy(),
+    );
+    let status = Command::new("python3")
         .arg("-c")
-        .arg("-o")
-        .arg(dir.path().join("test.o"))
-        .arg("-I")
-        .arg(dir.path())
-        .arg(dir.path().join("test.hpp"))
+        .arg("from test_types import List, List__Empty; v = List__Empty(); assert List.bcs_deserialize(v.bcs_serialize()) == v")
+        .env("PYTHONPATH", python_path)
         .status()
         .unwrap();
     assert!(status.success());
 }
 
 #[test]
-fn test_that_installed_java_code_compiles() {
+fn test_that_installed_rust_code_compiles() {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let yaml_path = dir.path().join("test.yaml");
@@ -307,48 +561,30 @@ fn test_that_installed_java_code_compiles() {
         .arg("serde-generate-bin")
         .arg("--")
         .arg("--language")
-        .arg("java")
+        .arg("rust")
+        .arg("--module-name")
+        .arg("testing:0.2.0")
         .arg("--target-source-dir")
         .arg(dir.path())
-        .arg("--module-name")
-        .arg("test.types")
-        .arg("--with-runtimes")
-        .arg("serde")
-        .arg("--")
         .arg(yaml_path)
         .status()
         .unwrap();
     assert!(status.success());
 
-    let paths = std::fs::read_dir(dir.path().join("com/novi/serde"))
-        .unwrap()
-        .map(|e| e.unwrap().path());
-    let status = Command::new("javac")
-        .arg("-cp")
-        .arg(dir.path())
-        .arg("-d")
-        .arg(dir.path())
-        .args(paths)
-        .status()
-        .unwrap();
-    assert!(status.success());
-
-    let paths = std::fs::read_dir(dir.path().join("test/types"))
-        .unwrap()
-        .map(|e| e.unwrap().path());
-    let status = Command::new("javac")
-        .arg("-cp")
-        .arg(dir.path())
-        .arg("-d")
-        .arg(dir.path())
-        .args(paths)
+    // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
+    let target_dir = std::env::current_dir().unwrap().join("../target");
+    let status = Command::new("cargo")
+        .current_dir(dir.path().join("testing"))
+        .arg("build")
+        .arg("--target-dir")
+        .arg(target_dir)
         .status()
         .unwrap();
     assert!(status.success());
 }
 
 #[test]
-fn test_that_installed_ocaml_code_compiles() {
+fn test_that_installed_rust_fuzz_targets_compile() {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let yaml_path = dir.path().join("test.yaml");
@@ -360,22 +596,1308 @@ fn test_that_installed_ocaml_code_compiles() {
         .arg("serde-generate-bin")
         .arg("--")
         .arg("--language")
-        .arg("ocaml")
+        .arg("rust")
+        .arg("--module-name")
+        .arg("testing")
+        .arg("--with-runtimes")
+        .arg("bcs")
+        .arg("bincode")
+        .arg("--allow-nonstandard-floats")
+        .arg("--rust-fuzz-targets")
+        .arg("SerdeData,List")
         .arg("--target-source-dir")
         .arg(dir.path())
-        .arg("--with-runtimes")
-        .arg("serde")
-        .arg("--")
         .arg(yaml_path)
         .status()
         .unwrap();
     assert!(status.success());
 
-    let status = Command::new("dune")
-        .arg("build")
-        .arg("--root")
-        .arg(dir.path())
+    let fuzz_dir = dir.path().join("testing/fuzz");
+    let manifest = std::fs::read_to_string(fuzz_dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("testing = { path = \"..\" }"));
+    assert!(manifest.contains("name = \"serde_data\"\npath = \"fuzz_targets/serde_data.rs\""));
+    assert!(manifest.contains("name = \"list\"\npath = \"fuzz_targets/list.rs\""));
+    let source = std::fs::read_to_string(fuzz_dir.join("fuzz_targets/serde_data.rs")).unwrap();
+    assert!(source.contains("if let Ok(value) = bcs::from_bytes::<SerdeData>(data) {"));
+    assert!(source.contains("if let Ok(value) = bincode::deserialize::<SerdeData>(data) {"));
+
+    // Check the fuzz targets without linking libFuzzer, which requires a nightly toolchain.
+    let target_dir = std::env::current_dir().unwrap().join("../target");
+    let status = Command::new("cargo")
+        .current_dir(&fuzz_dir)
+        .arg("check")
+        .arg("--no-default-features")
+        .arg("--target-dir")
+        .arg(target_dir)
         .status()
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_that_rust_output_modes_skip_the_crate_manifest() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    for (mode, split_modules) in [
+        ("single-file=src/generated.rs", false),
+        ("module-dir=src/generated", true),
+    ] {
+        let mut command = Command::new("cargo");
+        command
+            .arg("run")
+            .arg("-p")
+            .arg("serde-generate-bin")
+            .arg("--")
+            .arg("--language")
+            .arg("rust")
+            .arg("--module-name")
+            .arg("testing")
+            .arg("--rust-output-mode")
+            .arg(mode)
+            .arg("--target-source-dir")
+            .arg(dir.path());
+        if split_modules {
+            command.arg("--split-modules");
+        }
+        let status = command.arg(&yaml_path).status().unwrap();
+        assert!(status.success());
+    }
+
+    assert!(!dir.path().join("testing").exists());
+    let source = std::fs::read_to_string(dir.path().join("src/generated.rs")).unwrap();
+    assert!(source.contains("pub enum SerdeData {"));
+    assert!(!source.contains("#!["));
+    let module = std::fs::read_to_string(dir.path().join("src/generated/mod.rs")).unwrap();
+    assert!(module.contains("mod serde_data;"));
+    let source = std::fs::read_to_string(dir.path().join("src/generated/serde_data.rs")).unwrap();
+    assert!(source.contains("use super::*;"));
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("rust")
+        .arg("--rust-output-mode")
+        .arg("elsewhere")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported output mode elsewhere"));
+}
+
+#[test]
+fn create_test_yaml() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path, serde_yaml::to_string(&registry).unwrap()).unwrap();
+}
+
+#[test]
+fn test_that_installed_cpp_code_compiles() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("cpp")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg(yaml_path)
+        .arg("--allow-nonstandard-floats")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("bincode")
+        .arg("bcs")
+        .arg("--")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-c")
+        .arg("-o")
+        .arg(dir.path().join("test.o"))
+        .arg("-I")
+        .arg(dir.path())
+        .arg(dir.path().join("test.hpp"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_installed_java_code_compiles() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("java")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("test.types")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("--")
+        .arg(yaml_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let paths = std::fs::read_dir(dir.path().join("com/novi/serde"))
+        .unwrap()
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let paths = std::fs::read_dir(dir.path().join("test/types"))
+        .unwrap()
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_installed_ocaml_code_compiles() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("ocaml")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("--")
+        .arg(yaml_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("dune")
+        .arg("build")
+        .arg("--root")
+        .arg(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_unsupported_config_is_reported_without_panicking() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), "{}").unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("go")
+        .arg("--use-c-style-enums")
+        .arg("--")
+        .arg(yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: Go does not support generating c-style enums"));
+    assert!(!stderr.contains("panicked"));
+}
+
+fn run_serdegen_on_inputs(inputs: &[&std::path::Path]) -> std::process::Output {
+    Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("rust")
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--")
+        .args(inputs)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_that_multiple_inputs_are_merged() {
+    let dir = tempdir().unwrap();
+    let core_path = dir.path().join("core.yaml");
+    std::fs::write(&core_path, "Id:\n  NEWTYPESTRUCT: U64\n").unwrap();
+    let api_path = dir.path().join("api.yaml");
+    std::fs::write(
+        &api_path,
+        "Id:\n  NEWTYPESTRUCT: U64\nRequest:\n  STRUCT:\n    - id:\n        TYPENAME: Id\n",
+    )
+    .unwrap();
+
+    let output = run_serdegen_on_inputs(&[&core_path, &api_path]);
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+    assert!(code.contains("pub struct Id(pub u64);"));
+    assert!(code.contains("pub id: Id,"));
+
+    let output = run_serdegen_on_inputs(&[&api_path, &core_path]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), code);
+}
+
+#[test]
+fn test_that_conflicting_inputs_are_rejected() {
+    let dir = tempdir().unwrap();
+    let core_path = dir.path().join("core.yaml");
+    std::fs::write(&core_path, "Id:\n  NEWTYPESTRUCT: U64\n").unwrap();
+    let admin_path = dir.path().join("admin.yaml");
+    std::fs::write(&admin_path, "Id:\n  NEWTYPESTRUCT: STR\n").unwrap();
+
+    let expected = format!(
+        "Container Id is defined differently in {} and {}",
+        admin_path.display(),
+        core_path.display()
+    );
+    for inputs in [[&core_path, &admin_path], [&admin_path, &core_path]] {
+        let output = run_serdegen_on_inputs(&[inputs[0], inputs[1]]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(&expected), "{}", stderr);
+    }
+}
+
+fn run_serdegen_check(input: &std::path::Path, languages: &[&str]) -> std::process::Output {
+    let mut command = Command::new("cargo");
+    command
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("check");
+    for language in languages {
+        command.arg("--language").arg(language);
+    }
+    command.arg("--").arg(input).output().unwrap()
+}
+
+#[test]
+fn test_that_check_reports_unsupported_formats() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        "Point:\n  STRUCT:\n    - x: F64\n    - y: U64\n",
+    )
+    .unwrap();
+
+    let output = run_serdegen_check(&yaml_path, &["solidity"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "Solidity: 1 issue(s)\n  Point.x: floating point is not supported in solidity\n"
+    ));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: Unsupported registry for Solidity"));
+    assert!(!stderr.contains("panicked"));
+
+    let output = run_serdegen_check(&yaml_path, &["python3", "rust"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Python3: OK\nRust: OK\n"
+    );
+}
+
+#[test]
+fn test_that_check_covers_all_languages_by_default() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: F64\n").unwrap();
+
+    let output = run_serdegen_check(&yaml_path, &[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Python3: OK\n"));
+    assert!(stdout.contains("TypeScript: OK\n"));
+    assert!(stdout.contains("Solidity: 1 issue(s)\n"));
+}
+
+#[test]
+fn test_that_languages_are_listed_with_their_capabilities() {
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("languages")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    assert!(lines.next().unwrap().starts_with("language "));
+    let python = lines.find(|line| line.starts_with("Python3 ")).unwrap();
+    assert!(python.contains(" bincode, bcs, postcard, msgpack "));
+    let java = stdout
+        .lines()
+        .find(|line| line.starts_with("Java "))
+        .unwrap();
+    assert!(java.ends_with(" no           yes          yes"));
+    let php = stdout
+        .lines()
+        .find(|line| line.starts_with("Php "))
+        .unwrap();
+    assert!(php.ends_with(" yes          no"));
+}
+
+#[test]
+fn test_that_unknown_languages_are_rejected() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U8\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("Cobol")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown language Cobol"));
+}
+
+#[test]
+fn test_that_check_rejects_floats_with_bcs_unless_allowed() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: F64\n").unwrap();
+
+    let check = |options: &[&str]| {
+        Command::new("cargo")
+            .arg("run")
+            .arg("-p")
+            .arg("serde-generate-bin")
+            .arg("--")
+            .arg("check")
+            .arg("--language")
+            .arg("python3")
+            .args(options)
+            .arg("--with-runtimes")
+            .arg("serde")
+            .arg("bcs")
+            .arg("--")
+            .arg(&yaml_path)
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&[]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Python3: 1 issue(s)\n  Point.x: floating-point numbers are not supported by the BCS encoding\n"
+    );
+
+    let output = check(&["--allow-nonstandard-floats"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Python3: OK\n");
+}
+
+#[test]
+fn test_that_check_lists_transliterated_names() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Café:\n  STRUCT:\n    - 名前: STR\n").unwrap();
+
+    let check = |options: &[&str]| {
+        Command::new("cargo")
+            .arg("run")
+            .arg("-p")
+            .arg("serde-generate-bin")
+            .arg("--")
+            .arg("check")
+            .arg("--language")
+            .arg("go")
+            .args(options)
+            .arg("--")
+            .arg(&yaml_path)
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&[]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Go: 2 issue(s)\n  type names: `Café` contains characters that are not allowed in identifiers\n  fields of Café: `名前` contains characters that are not allowed in identifiers\n"
+    );
+
+    let output = check(&["--transliterate-names"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Renamed `Café` to `CafU00E9`\nRenamed `Café.名前` to `U540DU524D`\nGo: OK\n"
+    );
+}
+
+fn run_serdegen_diff(old: &str, new: &str, options: &[&str]) -> std::process::Output {
+    let dir = tempdir().unwrap();
+    let old_path = dir.path().join("old.yaml");
+    std::fs::write(&old_path, old).unwrap();
+    let new_path = dir.path().join("new.yaml");
+    std::fs::write(&new_path, new).unwrap();
+    Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("diff")
+        .args(options)
+        .arg("--")
+        .arg(old_path)
+        .arg(new_path)
+        .output()
+        .unwrap()
+}
+
+const SHAPES: &str = r#"
+Point:
+  STRUCT:
+    - x: U32
+    - y: U32
+Shape:
+  ENUM:
+    0:
+      Circle:
+        NEWTYPE: U32
+    1:
+      Square:
+        NEWTYPE: U32
+"#;
+
+#[test]
+fn test_that_diff_reports_field_type_changes() {
+    let new = SHAPES.replace("- y: U32", "- y: U64");
+    let output = run_serdegen_diff(SHAPES, &new, &["--deny", "breaking"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "breaking    Point.y: format changed from U32 to U64\n\
+         1 breaking change(s), 0 compatible change(s)\n"
+    );
+}
+
+#[test]
+fn test_that_diff_reports_variant_index_reuse() {
+    let new = SHAPES.replace("Square:", "Triangle:");
+    let output = run_serdegen_diff(SHAPES, &new, &["--format", "json"]);
+    assert_eq!(output.status.code(), Some(3));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!([
+            {
+                "path": "Shape::Square",
+                "kind": "removed_variant",
+                "index": 1,
+                "breaking": true,
+            },
+            {
+                "path": "Shape::Triangle",
+                "kind": "reused_variant_index",
+                "index": 1,
+                "old_name": "Square",
+                "breaking": true,
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_that_diff_accepts_additive_changes() {
+    let new = format!(
+        "{}    2:\n      Triangle:\n        NEWTYPE: U32\nName:\n  NEWTYPESTRUCT: STR\n",
+        SHAPES
+    );
+    let output = run_serdegen_diff(SHAPES, &new, &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "compatible  Name: container added\n\
+         compatible  Shape::Triangle: variant added at index 2\n\
+         0 breaking change(s), 2 compatible change(s)\n"
+    );
+
+    let output = run_serdegen_diff(SHAPES, &new, &["--deny", "breaking"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_serdegen_diff(SHAPES, SHAPES, &[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "No changes\n");
+}
+
+#[test]
+fn test_that_config_file_adds_base_classes() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U32\n").unwrap();
+    let config_path = dir.path().join("codegen.yaml");
+    std::fs::write(
+        &config_path,
+        "module_name: test_types\nbase_classes:\n  test_types: my_package.mixins.Message\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("python3")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\nimport my_package.mixins\n"));
+    assert!(stdout.contains("\nclass Point(my_package.mixins.Message):\n"));
+}
+
+#[test]
+fn test_that_config_file_adds_comments_and_custom_code() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        "Point:\n  STRUCT:\n    - x: U32\n    - y: U32\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("point.py"),
+        "def total(self) -> int:\n    return self.x + self.y\n",
+    )
+    .unwrap();
+    let config_path = dir.path().join("codegen.yaml");
+    std::fs::write(
+        &config_path,
+        r#"
+module_name: ignored
+comments:
+  test_types.Point: A point from the config file.
+  test_types.Point.x: The abscissa.
+custom_code:
+  test_types.Point: "@file:point.py"
+"#,
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--config")
+        .arg(&config_path)
+        // Command-line flags take precedence over the config file.
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        r#"class Point:
+    """A point from the config file.
+    """
+"#
+    ));
+    assert!(stdout.contains(
+        r#"    x: st.uint32
+    """The abscissa.
+    """
+"#
+    ));
+    assert!(stdout.contains(
+        r#"
+    def total(self) -> int:
+        return self.x + self.y
+"#
+    ));
+}
+
+#[test]
+fn test_that_config_file_rejects_unknown_options() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U32\n").unwrap();
+    let config_path = dir.path().join("codegen.json");
+    std::fs::write(&config_path, r#"{"comment": {}}"#).unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Failed to parse"));
+    assert!(stderr.contains("unknown field `comment`"));
+}
+
+#[test]
+fn test_that_several_languages_are_generated_in_one_run() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let status = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--language")
+        .arg("java")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--with-runtimes")
+        .arg("serde")
+        .arg("bincode")
+        .arg("--")
+        .arg(yaml_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Each language is generated in its own subdirectory.
+    let python_dir = dir.path().join("python3");
+    let python_path = format!(
+        "{}:{}",
+        std::env::var("PYTHONPATH").unwrap_or_default(),
+        python_dir.to_string_lossy(),
+    );
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import serde_types; import bincode; import test_types")
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let java_dir = dir.path().join("java");
+    let mut paths = Vec::new();
+    for package in ["com/novi/serde", "com/novi/bincode", "test_types"] {
+        paths.extend(
+            std::fs::read_dir(java_dir.join(package))
+                .unwrap()
+                .map(|e| e.unwrap().path()),
+        );
+    }
+    let status = Command::new("javac")
+        .arg("-cp")
+        .arg(&java_dir)
+        .arg("-d")
+        .arg(&java_dir)
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_failing_languages_are_reported_together() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: F64\n").unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("solidity,python3")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg("--module-name")
+        .arg("test_types")
+        .arg("--")
+        .arg(yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Solidity: "));
+    assert!(stderr.contains("Error: Code generation failed for Solidity\n"));
+    // Python code was still generated after Solidity failed.
+    assert!(dir.path().join("python3/test_types/__init__.py").exists());
+}
+
+#[test]
+fn test_that_types_only_skips_serialization_code() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--types-only")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let code = String::from_utf8_lossy(&output.stdout);
+    assert!(code.contains("class SerdeData:"));
+    assert!(!code.contains("import bcs"));
+    assert!(!code.contains("import bincode"));
+    assert!(!code.contains("_serialize"));
+    assert!(!code.contains("_deserialize"));
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg("--types-only")
+        .arg("--with-runtimes")
+        .arg("bcs")
+        .arg("--target-source-dir")
+        .arg(dir.path())
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("`--types-only` cannot be combined with `--with-runtimes`"));
+}
+
+#[test]
+fn test_that_deserialize_only_skips_serialization_methods() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U32\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg(&yaml_path)
+        .arg("--language")
+        .arg("python3")
+        .arg("--deserialize-only")
+        .arg("--with-runtimes")
+        .arg("bcs")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let code = String::from_utf8_lossy(&output.stdout);
+    assert!(code.contains("def bcs_deserialize(input: bytes)"));
+    assert!(!code.contains("def bcs_serialize"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("go")
+        .arg("--deserialize-only")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(
+        "Go does not support generating serialization and deserialization methods separately"
+    ));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("python3")
+        .arg("--types-only")
+        .arg("--serialize-only")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("`--types-only` cannot be combined with `--serialize-only`"));
+}
+
+#[test]
+fn test_that_language_flag_does_not_consume_inputs() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U32\n").unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("python3")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("class Point:"));
+}
+
+#[test]
+fn test_that_containers_are_filtered() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(yaml_path.clone(), serde_yaml::to_string(&registry).unwrap()).unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("typescript")
+        .arg("--exclude-containers")
+        .arg("List,SerdeData,Tree")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let code = String::from_utf8_lossy(&output.stdout);
+    assert!(code.contains("export class Struct {"));
+    assert!(!code.contains("export class SerdeData {"));
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("-p")
+        .arg("serde-generate-bin")
+        .arg("--")
+        .arg("--language")
+        .arg("typescript")
+        .arg("--include-containers")
+        .arg("SerdeData")
+        .arg("--exclude-containers")
+        .arg("Struct")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error: Container SerdeData references the excluded container Struct"));
+}
+
+#[test]
+fn test_that_watch_mode_regenerates_code() {
+    use std::io::BufRead;
+
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("test.yaml");
+    std::fs::write(&input_path, "---\nFoo:\n  STRUCT:\n    - a: U64\n").unwrap();
+    let target_dir = dir.path().join("out");
+    let output_path = target_dir.join("test/__init__.py");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--watch")
+        .arg("--language")
+        .arg("python3")
+        .arg("--target-source-dir")
+        .arg(&target_dir)
+        .arg(&input_path)
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stderr).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    // Editors may trigger several regenerations for a single save.
+    let wait_for = |prefix: &str| loop {
+        let line = receiver
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .unwrap();
+        if line.starts_with(prefix) {
+            break;
+        }
+    };
+
+    wait_for("Generation succeeded");
+    assert!(std::fs::read_to_string(&output_path)
+        .unwrap()
+        .contains("class Foo:"));
+
+    // Generation errors do not stop the watch.
+    std::fs::write(&input_path, "---\nFoo: [").unwrap();
+    wait_for("Generation failed: ");
+
+    std::fs::write(&input_path, "---\nBar:\n  STRUCT:\n    - b: STR\n").unwrap();
+    wait_for("Generation succeeded");
+    let code = std::fs::read_to_string(&output_path).unwrap();
+    assert!(code.contains("class Bar:"));
+    assert!(!code.contains("class Foo:"));
+
+    assert!(child.try_wait().unwrap().is_none());
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_that_cached_generation_skips_unchanged_inputs() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("test.yaml");
+    std::fs::write(&input_path, "---\nFoo:\n  STRUCT:\n    - a: U64\n").unwrap();
+    let target_dir = dir.path().join("out");
+    let output_path = target_dir.join("test/__init__.py");
+    let run = |flags: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+            .arg("--language")
+            .arg("python3")
+            .arg("--target-source-dir")
+            .arg(&target_dir)
+            .arg("--cache-dir")
+            .arg(dir.path().join("cache"))
+            .args(flags)
+            .arg(&input_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let modified = std::fs::metadata(&output_path).unwrap().modified().unwrap();
+        let skipped = String::from_utf8_lossy(&output.stderr).contains("up to date");
+        (modified, skipped)
+    };
+
+    let (modified, skipped) = run(&[]);
+    assert!(!skipped);
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(run(&[]), (modified, true));
+
+    // Changing the registry or an option invalidates the cache.
+    std::fs::write(&input_path, "---\nBar:\n  STRUCT:\n    - b: STR\n").unwrap();
+    let (modified2, skipped) = run(&[]);
+    assert!(!skipped);
+    assert!(modified2 > modified);
+    assert!(std::fs::read_to_string(&output_path)
+        .unwrap()
+        .contains("class Bar:"));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let (modified3, skipped) = run(&["--types-only"]);
+    assert!(!skipped);
+    assert!(modified3 > modified2);
+    assert_eq!(run(&["--types-only"]), (modified3, true));
+
+    // So does changing the content of the config file.
+    let config_path = dir.path().join("config.yaml");
+    let config_flags = ["--config", config_path.to_str().unwrap()];
+    std::fs::write(&config_path, "comments:\n  test.Bar: Some comments.\n").unwrap();
+    assert!(!run(&config_flags).1);
+    assert!(run(&config_flags).1);
+    std::fs::write(&config_path, "comments:\n  test.Bar: Other comments.\n").unwrap();
+    assert!(!run(&config_flags).1);
+}
+
+#[test]
+fn test_that_code_is_printed_on_stdout() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(&yaml_path, "Point:\n  STRUCT:\n    - x: U32\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .current_dir(dir.path())
+        .arg("--language")
+        .arg("solidity")
+        .arg("--target-source-dir")
+        .arg("-")
+        .arg("--with-runtimes")
+        .arg("bcs")
+        .arg("--")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("contract test {"));
+    assert!(!dir.path().join("-").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("java")
+        .arg("--target-source-dir")
+        .arg("-")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Code generation in Java writes several files and cannot print code on stdout"));
+}
+
+#[test]
+fn test_that_standard_types_are_recognized_on_demand() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        "Duration:\n  STRUCT:\n    - secs: U64\n    - nanos: U32\nTimer:\n  STRUCT:\n    - delay:\n        TYPENAME: Duration\n",
+    )
+    .unwrap();
+
+    let generate = |recognize_standard_types: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_serdegen"));
+        command
+            .arg("--language")
+            .arg("python3")
+            .arg("--module-name")
+            .arg("test");
+        if recognize_standard_types {
+            command.arg("--recognize-standard-types");
+        }
+        let output = command.arg(&yaml_path).output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let code = generate(false);
+    assert!(code.contains("class Duration:"));
+    assert!(code.contains("delay: \"Duration\""));
+
+    let code = generate(true);
+    assert!(!code.contains("class Duration:"));
+    assert!(code.contains("delay: datetime.timedelta"));
+}
+
+#[test]
+fn test_that_vectors_are_emitted_for_each_container() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        r#"---
+Point:
+  STRUCT:
+    - x: U32
+    - visible: BOOL
+Choice:
+  ENUM:
+    0:
+      Again:
+        NEWTYPE:
+          TYPENAME: Choice
+    1:
+      Label:
+        NEWTYPE: STR
+"#,
+    )
+    .unwrap();
+    let out_path = dir.path().join("vectors.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("vectors")
+        .arg("--encoding")
+        .arg("bcs")
+        .arg("--encoding")
+        .arg("bincode")
+        .arg("--out")
+        .arg(&out_path)
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+    let registry: Registry =
+        serde_yaml::from_str(&std::fs::read_to_string(&yaml_path).unwrap()).unwrap();
+    let hash = serde_reflection::schema_hash(&registry);
+    let hash = hash
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    assert_eq!(manifest["registry_hash"].as_str().unwrap(), hash);
+    // 0u32, then `false`.
+    assert_eq!(manifest["vectors"]["Point"]["bcs"], "0000000000");
+    // The recursive variant 0 is skipped: variant index 1, then an empty string.
+    assert_eq!(manifest["vectors"]["Choice"]["bcs"], "0100");
+    assert_eq!(
+        manifest["vectors"]["Choice"]["bincode"],
+        "010000000000000000000000"
+    );
+}
+
+#[test]
+fn test_that_hostile_registries_are_rejected() {
+    let dir = tempdir().unwrap();
+    let target_dir = dir.path().join("out");
+    let deep_option = format!(
+        "Deep:\n  NEWTYPESTRUCT: {}U8{}\n",
+        "{OPTION: ".repeat(10_000),
+        "}".repeat(10_000)
+    );
+    let inputs = [
+        (
+            "Foo:\n  NEWTYPESTRUCT:\n    TUPLEARRAY:\n      CONTENT: U8\n      SIZE: 4294967296\n",
+            "Foo: tuple array of size 4294967296 exceeds the maximum size 65536",
+        ),
+        (
+            "\"../evil\": UNITSTRUCT\n",
+            "Container `../evil` cannot be written to the file `../evil`",
+        ),
+        (deep_option.as_str(), "recursion limit exceeded"),
+    ];
+    for (yaml, message) in inputs {
+        let yaml_path = dir.path().join("test.yaml");
+        std::fs::write(&yaml_path, yaml).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+            .arg("--language")
+            .arg("java")
+            .arg("--target-source-dir")
+            .arg(&target_dir)
+            .arg("--")
+            .arg(&yaml_path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "{}", stderr);
+        assert!(!target_dir.exists());
+    }
+
+    // Limits are configurable.
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        "Foo:\n  NEWTYPESTRUCT:\n    OPTION:\n      OPTION: U8\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--max-depth")
+        .arg("2")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Foo: formats are nested more than 2 levels deep"));
+}
+
+#[test]
+fn test_that_openapi_schemas_follow_the_options() {
+    let dir = tempdir().unwrap();
+    let yaml_path = dir.path().join("test.yaml");
+    std::fs::write(
+        &yaml_path,
+        "Point:\n  STRUCT:\n    - x:\n        OPTION: U32\n    - data: BYTES\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("openapi")
+        .arg("--openapi-version")
+        .arg("3.0")
+        .arg("--openapi-base64-bytes")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("components:\n  schemas:\n    Point:\n"));
+    assert!(stdout.contains("nullable: true"));
+    assert!(stdout.contains("format: \"byte\""));
+
+    let config_path = dir.path().join("config.yaml");
+    std::fs::write(
+        &config_path,
+        "openapi_version: \"3.1\"\nopenapi_json: true\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("openapi")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--target-source-dir")
+        .arg(dir.path().join("out"))
+        .arg("--module-name")
+        .arg("points")
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let document: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("out/points.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        document["components"]["schemas"]["Point"]["properties"]["x"]["type"],
+        serde_json::json!(["integer", "null"])
+    );
+
+    std::fs::write(&config_path, "openapi_version: \"2.0\"\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_serdegen"))
+        .arg("--language")
+        .arg("openapi")
+        .arg("--config")
+        .arg(&config_path)
+        .arg(&yaml_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported OpenAPI version 2.0"));
+}
@@ -39,6 +39,8 @@ class BcsDeserializer : public BinaryDeserializer<BcsDeserializer> {
   public:
     BcsDeserializer(std::vector<uint8_t> bytes)
         : Parent(std::move(bytes), BCS_MAX_CONTAINER_DEPTH) {}
+    BcsDeserializer(bytes_view input)
+        : Parent(input, BCS_MAX_CONTAINER_DEPTH) {}
 
     size_t deserialize_len();
     uint32_t deserialize_variant_index();
@@ -128,10 +130,10 @@ inline uint32_t BcsDeserializer::deserialize_variant_index() {
 
 inline void BcsDeserializer::check_that_key_slices_are_increasing(
     std::tuple<size_t, size_t> key1, std::tuple<size_t, size_t> key2) {
-    if (!std::lexicographical_compare(bytes_.cbegin() + std::get<0>(key1),
-                                      bytes_.cbegin() + std::get<1>(key1),
-                                      bytes_.cbegin() + std::get<0>(key2),
-                                      bytes_.cbegin() + std::get<1>(key2))) {
+    if (!std::lexicographical_compare(input_.begin() + std::get<0>(key1),
+                                      input_.begin() + std::get<1>(key1),
+                                      input_.begin() + std::get<0>(key2),
+                                      input_.begin() + std::get<1>(key2))) {
         throw serde::deserialization_error(
             "Error while decoding map: keys are not serialized in the "
             "expected order");
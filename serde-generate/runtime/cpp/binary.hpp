@@ -21,7 +21,7 @@ class BinarySerializer {
     BinarySerializer(size_t max_container_depth)
         : container_depth_budget_(max_container_depth) {}
 
-    void serialize_str(const std::string &value);
+    void serialize_str(std::string_view value);
 
     void serialize_bool(bool value);
     void serialize_unit();
@@ -53,17 +53,34 @@ template <class D>
 class BinaryDeserializer {
     size_t pos_;
     size_t container_depth_budget_;
+    bool borrowed_;
 
   protected:
     std::vector<uint8_t> bytes_;
+    // The input: either `bytes_` or a buffer borrowed from the caller.
+    bytes_view input_;
     uint8_t read_byte();
+    bytes_view read_bytes(size_t len);
 
   public:
     BinaryDeserializer(std::vector<uint8_t> bytes, size_t max_container_depth)
         : pos_(0), container_depth_budget_(max_container_depth),
-          bytes_(std::move(bytes)) {}
+          borrowed_(false), bytes_(std::move(bytes)), input_(bytes_) {}
+
+    // Read the buffer of the caller without copying it. The buffer must outlive
+    // the deserializer and the views returned by `deserialize_str_view` and
+    // `deserialize_bytes_view`.
+    BinaryDeserializer(bytes_view input, size_t max_container_depth)
+        : pos_(0), container_depth_budget_(max_container_depth),
+          borrowed_(true), input_(input) {}
+
+    // Moving `bytes_` keeps its buffer, hence `input_` remains valid.
+    BinaryDeserializer(const BinaryDeserializer &) = delete;
+    BinaryDeserializer(BinaryDeserializer &&) = default;
 
     std::string deserialize_str();
+    std::string_view deserialize_str_view();
+    bytes_view deserialize_bytes_view();
 
     bool deserialize_bool();
     std::monostate deserialize_unit();
@@ -91,7 +108,7 @@ class BinaryDeserializer {
 };
 
 template <class S>
-void BinarySerializer<S>::serialize_str(const std::string &value) {
+void BinarySerializer<S>::serialize_str(std::string_view value) {
     static_cast<S *>(this)->serialize_len(value.size());
     for (auto c : value) {
         bytes_.push_back(c);
@@ -209,13 +226,23 @@ void BinarySerializer<S>::decrease_container_depth() {
 
 template <class D>
 uint8_t BinaryDeserializer<D>::read_byte() {
-    if (pos_ >= bytes_.size()) {
+    if (pos_ >= input_.size()) {
+        throw serde::deserialization_error("Input is not large enough");
+    }
+    return input_[pos_++];
+}
+
+template <class D>
+bytes_view BinaryDeserializer<D>::read_bytes(size_t len) {
+    if (len > input_.size() - pos_) {
         throw serde::deserialization_error("Input is not large enough");
     }
-    return bytes_.at(pos_++);
+    bytes_view result(input_.data() + pos_, len);
+    pos_ += len;
+    return result;
 }
 
-inline bool is_valid_utf8(const std::string &input) {
+inline bool is_valid_utf8(std::string_view input) {
     uint8_t trailing_digits = 0;
     for (uint8_t byte : input) {
         if (trailing_digits == 0) {
@@ -259,6 +286,29 @@ std::string BinaryDeserializer<D>::deserialize_str() {
     return result;
 }
 
+template <class D>
+std::string_view BinaryDeserializer<D>::deserialize_str_view() {
+    auto bytes = deserialize_bytes_view();
+    std::string_view result(reinterpret_cast<const char *>(bytes.data()),
+                            bytes.size());
+    if (!is_valid_utf8(result)) {
+        throw serde::deserialization_error("Invalid UTF8 string: " +
+                                           std::string(result));
+    }
+    return result;
+}
+
+template <class D>
+bytes_view BinaryDeserializer<D>::deserialize_bytes_view() {
+    // Views into `bytes_` would not outlive the deserializer.
+    if (!borrowed_) {
+        throw serde::deserialization_error(
+            "Views require a deserializer borrowing its input");
+    }
+    auto len = static_cast<D *>(this)->deserialize_len();
+    return read_bytes(len);
+}
+
 template <class D>
 std::monostate BinaryDeserializer<D>::deserialize_unit() {
     return {};
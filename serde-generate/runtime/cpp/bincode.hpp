@@ -34,6 +34,7 @@ class BincodeDeserializer : public BinaryDeserializer<BincodeDeserializer> {
   public:
     BincodeDeserializer(std::vector<uint8_t> bytes)
         : Parent(std::move(bytes), SIZE_MAX) {}
+    BincodeDeserializer(bytes_view input) : Parent(input, SIZE_MAX) {}
 
     float deserialize_f32();
     double deserialize_f64();
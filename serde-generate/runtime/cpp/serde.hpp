@@ -3,6 +3,7 @@
 
 #pragma once
 
+#include <algorithm>
 #include <array>
 #include <cstdint>
 #include <functional>
@@ -11,6 +12,7 @@
 #include <optional>
 #include <stdexcept>
 #include <string>
+#include <string_view>
 #include <tuple>
 #include <type_traits>
 #include <variant>
@@ -108,6 +110,47 @@ bool operator==(const value_ptr<T> &lhs, const value_ptr<T> &rhs) {
     return *lhs == *rhs;
 }
 
+// A read-only view of a sequence of bytes, similar to `std::span<const uint8_t>`
+// in C++20. The bytes must outlive the view.
+class bytes_view {
+  public:
+    constexpr bytes_view() noexcept : data_(nullptr), size_(0) {}
+
+    constexpr bytes_view(const uint8_t *data, std::size_t size) noexcept
+        : data_(data), size_(size) {}
+
+    bytes_view(const std::vector<uint8_t> &bytes) noexcept
+        : data_(bytes.data()), size_(bytes.size()) {}
+
+    const uint8_t *data() const noexcept { return data_; }
+
+    std::size_t size() const noexcept { return size_; }
+
+    bool empty() const noexcept { return size_ == 0; }
+
+    const uint8_t *begin() const noexcept { return data_; }
+
+    const uint8_t *end() const noexcept { return data_ + size_; }
+
+    const uint8_t &operator[](std::size_t index) const { return data_[index]; }
+
+    friend bool operator==(const bytes_view &, const bytes_view &);
+    friend bool operator<(const bytes_view &, const bytes_view &);
+
+  private:
+    const uint8_t *data_;
+    std::size_t size_;
+};
+
+inline bool operator==(const bytes_view &lhs, const bytes_view &rhs) {
+    return std::equal(lhs.begin(), lhs.end(), rhs.begin(), rhs.end());
+}
+
+inline bool operator<(const bytes_view &lhs, const bytes_view &rhs) {
+    return std::lexicographical_compare(lhs.begin(), lhs.end(), rhs.begin(),
+                                        rhs.end());
+}
+
 // Trait to enable serialization of values of type T.
 // This is similar to the `serde::Serialize` trait in Rust.
 template <typename T>
@@ -135,6 +178,28 @@ struct Serializable<std::string> {
     }
 };
 
+// string view
+template <>
+struct Serializable<std::string_view> {
+    template <typename Serializer>
+    static void serialize(const std::string_view &value,
+                          Serializer &serializer) {
+        serializer.serialize_str(value);
+    }
+};
+
+// bytes view (same encoding as `std::vector<uint8_t>`)
+template <>
+struct Serializable<bytes_view> {
+    template <typename Serializer>
+    static void serialize(const bytes_view &value, Serializer &serializer) {
+        serializer.serialize_len(value.size());
+        for (uint8_t byte : value) {
+            serializer.serialize_u8(byte);
+        }
+    }
+};
+
 // unit
 template <>
 struct Serializable<std::monostate> {
@@ -387,6 +452,24 @@ struct Deserializable<std::string> {
     }
 };
 
+// string view (only for deserializers borrowing their input)
+template <>
+struct Deserializable<std::string_view> {
+    template <typename Deserializer>
+    static std::string_view deserialize(Deserializer &deserializer) {
+        return deserializer.deserialize_str_view();
+    }
+};
+
+// bytes view (only for deserializers borrowing their input)
+template <>
+struct Deserializable<bytes_view> {
+    template <typename Deserializer>
+    static bytes_view deserialize(Deserializer &deserializer) {
+        return deserializer.deserialize_bytes_view();
+    }
+};
+
 // unit
 template <>
 struct Deserializable<std::monostate> {
@@ -643,11 +726,143 @@ struct Deserializable<std::variant<Types...>> {
 
         // Read the variant index and execute the corresponding case.
         auto index = deserializer.deserialize_variant_index();
-        if (index > cases.size()) {
+        if (index >= cases.size()) {
             throw deserialization_error("Unknown variant index for enum");
         }
         return cases.at(index)(deserializer);
     }
 };
 
+// --- Hashing ---
+
+// Mix the hash `value` into `seed` (same mixing function as `boost::hash_combine`).
+inline void hash_combine(std::size_t &seed, std::size_t value) {
+    seed ^= value + 0x9e3779b9 + (seed << 6) + (seed >> 2);
+}
+
+// Trait to compute hashes of values of type T, including the types that lack a
+// specialization of `std::hash`. By default, this uses `std::hash<T>`.
+template <typename T>
+struct Hashable {
+    static std::size_t hash(const T &value) { return std::hash<T>{}(value); }
+};
+
+// u128
+template <>
+struct Hashable<uint128_t> {
+    static std::size_t hash(const uint128_t &value) {
+        std::size_t seed = std::hash<uint64_t>{}(value.high);
+        hash_combine(seed, std::hash<uint64_t>{}(value.low));
+        return seed;
+    }
+};
+
+// i128
+template <>
+struct Hashable<int128_t> {
+    static std::size_t hash(const int128_t &value) {
+        std::size_t seed = std::hash<int64_t>{}(value.high);
+        hash_combine(seed, std::hash<uint64_t>{}(value.low));
+        return seed;
+    }
+};
+
+// bytes view (same hash as `std::vector<uint8_t>`)
+template <>
+struct Hashable<bytes_view> {
+    static std::size_t hash(const bytes_view &value) {
+        std::size_t seed = value.size();
+        for (uint8_t byte : value) {
+            hash_combine(seed, std::hash<uint8_t>{}(byte));
+        }
+        return seed;
+    }
+};
+
+// Value pointers (non-nullable)
+template <typename T>
+struct Hashable<value_ptr<T>> {
+    static std::size_t hash(const value_ptr<T> &value) {
+        return Hashable<T>::hash(*value);
+    }
+};
+
+// Options
+template <typename T>
+struct Hashable<std::optional<T>> {
+    static std::size_t hash(const std::optional<T> &option) {
+        std::size_t seed = option.has_value();
+        if (option.has_value()) {
+            hash_combine(seed, Hashable<T>::hash(option.value()));
+        }
+        return seed;
+    }
+};
+
+// Vectors (sequences)
+template <typename T, typename Allocator>
+struct Hashable<std::vector<T, Allocator>> {
+    static std::size_t hash(const std::vector<T, Allocator> &value) {
+        std::size_t seed = value.size();
+        for (const T &item : value) {
+            hash_combine(seed, Hashable<T>::hash(item));
+        }
+        return seed;
+    }
+};
+
+// Fixed-size arrays
+template <typename T, std::size_t N>
+struct Hashable<std::array<T, N>> {
+    static std::size_t hash(const std::array<T, N> &value) {
+        std::size_t seed = 0;
+        for (const T &item : value) {
+            hash_combine(seed, Hashable<T>::hash(item));
+        }
+        return seed;
+    }
+};
+
+// Maps
+template <typename K, typename V, typename Allocator>
+struct Hashable<std::map<K, V, Allocator>> {
+    static std::size_t hash(const std::map<K, V, Allocator> &value) {
+        std::size_t seed = value.size();
+        for (const auto &item : value) {
+            hash_combine(seed, Hashable<K>::hash(item.first));
+            hash_combine(seed, Hashable<V>::hash(item.second));
+        }
+        return seed;
+    }
+};
+
+// Tuples
+template <class... Types>
+struct Hashable<std::tuple<Types...>> {
+    static std::size_t hash(const std::tuple<Types...> &value) {
+        std::size_t seed = 0;
+        std::apply(
+            [&seed](Types const &... args) {
+                (hash_combine(seed, Hashable<Types>::hash(args)), ...);
+            },
+            value);
+        return seed;
+    }
+};
+
+// Enums
+template <class... Types>
+struct Hashable<std::variant<Types...>> {
+    static std::size_t hash(const std::variant<Types...> &value) {
+        std::size_t seed = value.index();
+        std::visit(
+            [&seed](const auto &arg) {
+                using T = typename std::decay<decltype(arg)>::type;
+                hash_combine(seed, Hashable<T>::hash(arg));
+            },
+            value);
+        return seed;
+    }
+};
+
 } // end of namespace serde
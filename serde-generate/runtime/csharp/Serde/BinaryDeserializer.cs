@@ -34,9 +34,9 @@ namespace Serde
 
         public char deserialize_char() => throw new DeserializationException("Not implemented: char deserialization");
 
-        public float deserialize_f32() => reader.ReadSingle();
+        public float deserialize_f32() => BitConverter.ToSingle(BitConverter.GetBytes(reader.ReadInt32()), 0);
 
-        public double deserialize_f64() => reader.ReadDouble();
+        public double deserialize_f64() => BitConverter.Int64BitsToDouble(reader.ReadInt64());
 
         public void increase_container_depth()
         {
@@ -56,9 +56,10 @@ namespace Serde
 
         public void serialize_char(char value) => throw new SerializationException("Not implemented: char serialization");
 
-        public void serialize_f32(float value) => output.Write(value);
+        // Floating-point values are written as their raw bits so that NaN payloads are preserved.
+        public void serialize_f32(float value) => output.Write(BitConverter.ToInt32(BitConverter.GetBytes(value), 0));
 
-        public void serialize_f64(double value) => output.Write(value);
+        public void serialize_f64(double value) => output.Write(BitConverter.DoubleToInt64Bits(value));
 
         public byte[] get_bytes() => buffer.ToArray();
 
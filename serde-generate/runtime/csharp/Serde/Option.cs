@@ -9,18 +9,15 @@ namespace Serde
     public readonly struct Option<T> : IEquatable<Option<T>> where T : IEquatable<T>
     {
         public static Option<T> None => default;
-        public static Option<T> Some(T value)
-        {
-            if (value == null) throw new ArgumentNullException(nameof(value));
-            return new Option<T>(value);
-        }
+        // `null` is a valid value when `T` is an optional reference type, as in `Option<string?>`.
+        public static Option<T> Some(T value) => new Option<T>(value);
 
         readonly bool isSome;
         readonly T value;
 
         Option(T val)
         {
-            isSome = val != null;
+            isSome = true;
             value = val;
         }
 
@@ -36,7 +33,7 @@ namespace Serde
         {
             if (isSome != other.isSome) return false;
             if (!isSome) return true;
-            return value.Equals(other.value);
+            return EqualityComparer<T>.Default.Equals(value, other.value);
         }
 
         public override int GetHashCode()
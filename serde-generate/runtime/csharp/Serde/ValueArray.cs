@@ -33,6 +33,8 @@ namespace Serde
 
         public ReadOnlySpan<T> AsReadOnlySpan() => array;
 
+        public ReadOnlyMemory<T> AsReadOnlyMemory() => array;
+
         public override bool Equals(object obj) => obj is ValueArray<T> bytes && Equals(bytes);
 
         public bool Equals(ValueArray<T> other) {
@@ -40,7 +42,7 @@ namespace Serde
             if (ReferenceEquals(this, other)) return true;
             if (Count != other.Count) return false;
             for (int i = 0; i < Count; i++)
-                if (!array[i].Equals(other[i])) return false;
+                if (!EqualityComparer<T>.Default.Equals(array[i], other[i])) return false;
             return true;
         }
 
@@ -59,7 +61,7 @@ namespace Serde
                 if (hashCode.HasValue) return hashCode.Value;
                 int code = 1849862467;
                 foreach (T elem in array)
-                    code = code * 31 + elem.GetHashCode();
+                    code = code * 31 + EqualityComparer<T>.Default.GetHashCode(elem);
                 hashCode = code;
                 return code;
             }
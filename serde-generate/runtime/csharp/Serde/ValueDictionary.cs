@@ -47,7 +47,7 @@ namespace Serde
             foreach (var key in Keys)
             {
                 if (!other.ContainsKey(key)) return false;
-                if (!dict[key].Equals(other[key])) return false;
+                if (!EqualityComparer<V>.Default.Equals(dict[key], other[key])) return false;
             }
             return true;
         }
@@ -66,7 +66,7 @@ namespace Serde
                 foreach (var pair in dict)
                 {
                     code = code * 31 + pair.Key.GetHashCode();
-                    code = code * 31 + pair.Value.GetHashCode();
+                    code = code * 31 + EqualityComparer<V>.Default.GetHashCode(pair.Value);
                 }
                 hashCode = code;
                 return code;
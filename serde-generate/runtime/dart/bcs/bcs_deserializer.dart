@@ -22,7 +22,7 @@ class BcsDeserializer extends BinaryDeserializer {
       final x = super.deserializeUint8();
       final digit = (x & 0x7F);
       value = value | (digit << shift);
-      if (value > maxInt) {
+      if (value >= maxInt) {
         throw Exception('Overflow while parsing uleb128-encoded uint32 value');
       }
       if (digit == x) {
@@ -23,5 +23,5 @@ class Bytes {
   }
 
   @override
-  int get hashCode => content.hashCode;
+  int get hashCode => listHashCode(content);
 }
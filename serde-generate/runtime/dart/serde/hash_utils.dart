@@ -20,7 +20,7 @@ bool mapEquals<T, U>(Map<T, U>? a, Map<T, U>? b) {
   if (b == null || a.length != b.length) return false;
   if (identical(a, b)) return true;
   for (final T key in a.keys) {
-    if (!a.containsKey(key) || !_elementEquals(a[key], b[key])) {
+    if (!b.containsKey(key) || !_elementEquals(a[key], b[key])) {
       return false;
     }
   }
@@ -35,3 +35,23 @@ bool _elementEquals<T>(T? a, T? b) {
   }
   return a == b;
 }
+
+int listHashCode<T>(List<T>? a) {
+  if (a == null) return null.hashCode;
+  return Object.hashAll(a.map(_elementHashCode));
+}
+
+int mapHashCode<T, U>(Map<T, U>? a) {
+  if (a == null) return null.hashCode;
+  return Object.hashAllUnordered(a.entries.map(
+      (entry) => Object.hash(_elementHashCode(entry.key), _elementHashCode(entry.value))));
+}
+
+int _elementHashCode<T>(T? a) {
+  if (a is List) {
+    return listHashCode(a);
+  } else if (a is Map) {
+    return mapHashCode(a);
+  }
+  return a.hashCode;
+}
@@ -1,6 +1,8 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+import 'dart:typed_data';
+
 import 'package:test/test.dart';
 
 import '../serde/serde.dart';
@@ -28,4 +30,17 @@ void main() {
     expect(Int128.parse('170141183460469231731687303715884105727').toString(),
         '170141183460469231731687303715884105727');
   });
+
+  test('Deep equality and hashing', () {
+    expect(listEquals([1, 2], [1, 2]), isTrue);
+    expect(listHashCode([1, 2]), listHashCode([1, 2]));
+    expect(listEquals([1, 2], [2, 1]), isFalse);
+    expect(mapEquals({1: [2]}, {1: [2]}), isTrue);
+    expect(mapHashCode({1: [2], 3: [4]}), mapHashCode({3: [4], 1: [2]}));
+    expect(mapEquals({1: 2}, {3: 2}), isFalse);
+    expect(Bytes(Uint8List.fromList([1, 2])),
+        Bytes(Uint8List.fromList([1, 2])));
+    expect(Bytes(Uint8List.fromList([1, 2])).hashCode,
+        Bytes(Uint8List.fromList([1, 2])).hashCode);
+  });
 }
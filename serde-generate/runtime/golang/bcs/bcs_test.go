@@ -667,6 +667,13 @@ func TestSerializeDeserializeLenLimit(t *testing.T) {
 		assert.Equal(t, "overflow while parsing uleb128-encoded uint32 value", err.Error())
 	})
 
+	t.Run("overlong uleb128-encoded uint32", func(t *testing.T) {
+		d := bcs.NewDeserializer([]byte{0x81, 0x80, 0x00})
+		_, err := d.DeserializeLen()
+		require.Error(t, err)
+		assert.Equal(t, "invalid uleb128 number (unexpected zero digit)", err.Error())
+	})
+
 	t.Run("deserialize error: EOF", func(t *testing.T) {
 		d := bcs.NewDeserializer([]byte{})
 		_, err := d.DeserializeLen()
@@ -0,0 +1,25 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.bcs;
+
+import com.novi.serde.BinarySizeEstimator;
+
+public class BcsSizeEstimator extends BinarySizeEstimator {
+    private static int u32_as_uleb128_size(int value) {
+        int size = 1;
+        while ((value >>> 7) != 0) {
+            size += 1;
+            value = value >>> 7;
+        }
+        return size;
+    }
+
+    public int len_size(long value) {
+        return u32_as_uleb128_size((int) value);
+    }
+
+    public int variant_index_size(int value) {
+        return u32_as_uleb128_size(value);
+    }
+}
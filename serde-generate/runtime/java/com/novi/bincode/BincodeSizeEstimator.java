@@ -0,0 +1,16 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.bincode;
+
+import com.novi.serde.BinarySizeEstimator;
+
+public class BincodeSizeEstimator extends BinarySizeEstimator {
+    public int len_size(long value) {
+        return 8;
+    }
+
+    public int variant_index_size(int value) {
+        return 4;
+    }
+}
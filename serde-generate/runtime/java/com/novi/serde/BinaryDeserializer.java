@@ -169,20 +169,13 @@ public abstract class BinaryDeserializer implements Deserializer {
         }
     }
 
+    // Floating-point values are read from their raw bits so that NaN payloads are preserved.
     protected float getFloat()  throws DeserializationError {
-        try {
-            return input.getFloat();
-        } catch (java.nio.BufferUnderflowException e) {
-            throw new DeserializationError(INPUT_NOT_LARGE_ENOUGH);
-        }
+        return Float.intBitsToFloat(getInt());
     }
 
     protected double getDouble()  throws DeserializationError {
-        try {
-            return input.getDouble();
-        } catch (java.nio.BufferUnderflowException e) {
-            throw new DeserializationError(INPUT_NOT_LARGE_ENOUGH);
-        }
+        return Double.longBitsToDouble(getLong());
     }
 
     protected void read(byte[] content)  throws DeserializationError {
@@ -0,0 +1,36 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+public abstract class BinarySizeEstimator implements SizeEstimator {
+    public int str_size(String value) {
+        int size = 0;
+        for (int i = 0; i < value.length(); i++) {
+            char c = value.charAt(i);
+            if (c < 0x80) {
+                size += 1;
+            } else if (c < 0x800) {
+                size += 2;
+            } else if (Character.isHighSurrogate(c) && i + 1 < value.length() && Character.isLowSurrogate(value.charAt(i + 1))) {
+                size += 4;
+                i++;
+            } else if (Character.isSurrogate(c)) {
+                // Unpaired surrogates are encoded as '?'.
+                size += 1;
+            } else {
+                size += 3;
+            }
+        }
+        return len_size(size) + size;
+    }
+
+    public int bytes_size(Bytes value) {
+        int size = value.content().length;
+        return len_size(size) + size;
+    }
+
+    public int char_size(Character value) {
+        return str_size(value.toString()) - len_size(1);
+    }
+}
@@ -39,6 +39,11 @@ public final class Bytes {
         return this.content.clone();
     }
 
+    /// Read-only view of the content, without copying.
+    public java.nio.ByteBuffer asReadOnlyBuffer() {
+        return java.nio.ByteBuffer.wrap(this.content).asReadOnlyBuffer();
+    }
+
     public boolean equals(Object obj) {
         if (this == obj) return true;
         if (obj == null) return false;
@@ -0,0 +1,20 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+package com.novi.serde;
+
+/**
+ * Computes the number of bytes used by a serializer for the values whose encoding
+ * depends on the format (e.g. lengths and variant indices).
+ */
+public interface SizeEstimator {
+    int str_size(String value);
+
+    int bytes_size(Bytes value);
+
+    int char_size(Character value);
+
+    int len_size(long value);
+
+    int variant_index_size(int value);
+}
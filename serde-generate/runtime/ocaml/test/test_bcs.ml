@@ -67,6 +67,9 @@ let test_length_de_3f01 () = (check int) "same int" 0x3f01 (De.length @@ mk @@ v
 let test_length_de_4000 () = (check int) "same int" 0x4000 (De.length @@ mk @@ vec [|0x80; 0x80; 0x01|])
 let test_length_de_8001 () = (check int) "same int" 0x8001 (De.length @@ mk @@ vec [|0x81; 0x80; 0x02|])
 let test_length_de_fail () = check_fail (fun () -> De.length @@ mk @@ vec [|0xff; 0xff; 0xff; 0xff; 0x08|])
+let test_length_de_fail_overlong () = check_fail (fun () -> De.length @@ mk @@ vec [|0x81; 0x00|])
+let test_length_de_fail_overlong_zero () = check_fail (fun () -> De.length @@ mk @@ vec [|0x80; 0x80; 0x00|])
+let test_length_de_fail_too_many_bytes () = check_fail (fun () -> De.length @@ mk @@ vec [|0x80; 0x80; 0x80; 0x80; 0x80; 0x01|])
 
 let test_bytes_ser_empty () = (check bytes) "same bytes" (vec [|0|]) (Ser.bytes Bytes.empty).r
 let test_bytes_ser () = (check bytes) "same bytes" (vec [|2; 0; 0|]) (Ser.bytes (Bytes.make 2 '\000')).r
@@ -208,6 +211,9 @@ let () =
       test_case "deserialize 8001" `Quick test_length_de_8001;
       test_case "deserialize 4000" `Quick test_length_de_4000;
       test_case "deserialize max length + 1" `Quick test_length_de_fail;
+      test_case "deserialize overlong length" `Quick test_length_de_fail_overlong;
+      test_case "deserialize overlong zero length" `Quick test_length_de_fail_overlong_zero;
+      test_case "deserialize length with too many bytes" `Quick test_length_de_fail_too_many_bytes;
     ];
     "bytes", [
       test_case "serialize empty" `Quick test_bytes_ser_empty;
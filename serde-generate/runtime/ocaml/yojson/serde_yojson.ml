@@ -0,0 +1,157 @@
+(* Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0 *)
+
+(* Conversions between OCaml values and JSON following the conventions of serde_json. *)
+
+open Stdint
+
+type t = Yojson.Safe.t
+
+let ( let* ) = Result.bind
+
+let error name (json : t) =
+  Error (Format.sprintf "expected %s, got %s" name (Yojson.Safe.to_string json))
+
+let rec results_of_list f = function
+  | [] -> Ok []
+  | json :: l ->
+    let* x = f json in
+    let* l = results_of_list f l in
+    Ok (x :: l)
+
+let unit_to_yojson () : t = `Null
+let unit_of_yojson : t -> (unit, string) result = function
+  | `Null -> Ok ()
+  | json -> error "null" json
+
+let bool_to_yojson b : t = `Bool b
+let bool_of_yojson : t -> (bool, string) result = function
+  | `Bool b -> Ok b
+  | json -> error "a boolean" json
+
+(* Integers that do not fit in an OCaml integer are written as [`Intlit]. *)
+let integer_to_yojson s : t =
+  match int_of_string_opt s with
+  | Some i -> `Int i
+  | None -> `Intlit s
+
+let integer_of_yojson name of_string to_string (json : t) =
+  let s = match json with
+    | `Int i -> Some (string_of_int i)
+    | `Intlit s -> Some s
+    | _ -> None in
+  match s with
+  | None -> error name json
+  | Some s ->
+    match of_string s with
+    | x when to_string x = s -> Ok x
+    | _ -> error name json
+    | exception _ -> error name json
+
+let int8_to_yojson x = integer_to_yojson (Int8.to_string x)
+let int8_of_yojson = integer_of_yojson "an i8" Int8.of_string Int8.to_string
+let int16_to_yojson x = integer_to_yojson (Int16.to_string x)
+let int16_of_yojson = integer_of_yojson "an i16" Int16.of_string Int16.to_string
+let int32_to_yojson x = integer_to_yojson (Int32.to_string x)
+let int32_of_yojson = integer_of_yojson "an i32" Int32.of_string Int32.to_string
+let int64_to_yojson x = integer_to_yojson (Int64.to_string x)
+let int64_of_yojson = integer_of_yojson "an i64" Int64.of_string Int64.to_string
+let int128_to_yojson x = integer_to_yojson (Int128.to_string x)
+let int128_of_yojson = integer_of_yojson "an i128" Int128.of_string Int128.to_string
+let uint8_to_yojson x = integer_to_yojson (Uint8.to_string x)
+let uint8_of_yojson = integer_of_yojson "a u8" Uint8.of_string Uint8.to_string
+let uint16_to_yojson x = integer_to_yojson (Uint16.to_string x)
+let uint16_of_yojson = integer_of_yojson "a u16" Uint16.of_string Uint16.to_string
+let uint32_to_yojson x = integer_to_yojson (Uint32.to_string x)
+let uint32_of_yojson = integer_of_yojson "a u32" Uint32.of_string Uint32.to_string
+let uint64_to_yojson x = integer_to_yojson (Uint64.to_string x)
+let uint64_of_yojson = integer_of_yojson "a u64" Uint64.of_string Uint64.to_string
+let uint128_to_yojson x = integer_to_yojson (Uint128.to_string x)
+let uint128_of_yojson = integer_of_yojson "a u128" Uint128.of_string Uint128.to_string
+
+let float_to_yojson f : t = `Float f
+let float_of_yojson : t -> (float, string) result = function
+  | `Float f -> Ok f
+  | `Int i -> Ok (float_of_int i)
+  | `Intlit s -> Ok (float_of_string s)
+  | json -> error "a number" json
+
+let char_to_yojson c : t = `String (String.make 1 c)
+let char_of_yojson : t -> (char, string) result = function
+  | `String s when String.length s = 1 -> Ok s.[0]
+  | json -> error "a character" json
+
+let string_to_yojson s : t = `String s
+let string_of_yojson : t -> (string, string) result = function
+  | `String s -> Ok s
+  | json -> error "a string" json
+
+(* Bytes are sequences of integers. *)
+let bytes_to_yojson b : t =
+  `List (List.init (Bytes.length b) (fun i -> `Int (Char.code (Bytes.get b i))))
+let bytes_of_yojson (json : t) =
+  let byte = function
+    | `Int i when i >= 0 && i < 256 -> Ok (Char.chr i)
+    | json -> error "a byte" json in
+  match json with
+  | `List l ->
+    let* l = results_of_list byte l in
+    Ok (Bytes.of_seq (List.to_seq l))
+  | json -> error "bytes" json
+
+let option_to_yojson f = function
+  | None -> `Null
+  | Some x -> f x
+let option_of_yojson f (json : t) =
+  match json with
+  | `Null -> Ok None
+  | json -> Result.map Option.some (f json)
+
+let list_to_yojson f l : t = `List (List.map f l)
+let list_of_yojson f (json : t) =
+  match json with
+  | `List l -> results_of_list f l
+  | json -> error "a sequence" json
+
+let array_to_yojson f a : t = `List (Array.to_list (Array.map f a))
+let array_of_yojson length f (json : t) =
+  match json with
+  | `List l when List.length l = length -> Result.map Array.of_list (results_of_list f l)
+  | json -> error (Format.sprintf "a sequence of length %d" length) json
+
+(* Maps are JSON objects when all keys are strings or integers, and sequences of pairs
+   otherwise. *)
+let map_to_yojson fk fv m : t =
+  let key = function
+    | `String s | `Intlit s -> Some s
+    | `Int i -> Some (string_of_int i)
+    | _ -> None in
+  let entries = List.map (fun (k, v) -> (fk k, fv v)) (Common.Map.bindings m) in
+  if List.for_all (fun (k, _) -> key k <> None) entries then
+    `Assoc (List.map (fun (k, v) -> (Option.get (key k), v)) entries)
+  else
+    `List (List.map (fun (k, v) -> `List [k; v]) entries)
+let map_of_yojson fk fv (json : t) =
+  let key s = match fk (`String s) with
+    | Ok k -> Ok k
+    | Error _ -> fk (integer_to_yojson s) in
+  let pair = function
+    | `List [k; v] -> Ok (fk k, v)
+    | json -> error "a key-value pair" json in
+  let rec add m = function
+    | [] -> Ok m
+    | (k, v) :: l ->
+      let* k = k in
+      let* v = fv v in
+      add (Common.Map.add k v m) l in
+  match json with
+  | `Assoc l -> add Common.Map.empty (List.map (fun (k, v) -> (key k, v)) l)
+  | `List l ->
+    let* l = results_of_list pair l in
+    add Common.Map.empty l
+  | json -> error "a map" json
+
+(* Missing fields are read as [`Null], so that optional fields may be omitted. *)
+let field name f fields =
+  let json = Option.value ~default:`Null (List.assoc_opt name fields) in
+  Result.map_error (fun e -> Format.sprintf "%s: %s" name e) (f json)
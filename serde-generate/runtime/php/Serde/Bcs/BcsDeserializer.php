@@ -0,0 +1,65 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde\Bcs;
+
+use Serde\BinaryDeserializer;
+use Serde\DeserializationError;
+
+final class BcsDeserializer extends BinaryDeserializer
+{
+    public function __construct(string $input)
+    {
+        parent::__construct($input, BcsSerializer::MAX_CONTAINER_DEPTH);
+    }
+
+    private function deserializeUleb128AsU32(): int
+    {
+        $value = 0;
+        for ($shift = 0; $shift < 32; $shift += 7) {
+            $byte = ord($this->read(1));
+            $value |= ($byte & 0x7f) << $shift;
+            if ($byte < 0x80) {
+                if ($shift > 0 && $byte === 0) {
+                    throw new DeserializationError('Invalid uleb128 number (unexpected zero digit)');
+                }
+                if ($value > 0xffffffff) {
+                    throw new DeserializationError('Overflow while parsing uleb128-encoded uint32 value');
+                }
+                return $value;
+            }
+        }
+        throw new DeserializationError('Overflow while parsing uleb128-encoded uint32 value');
+    }
+
+    public function deserializeLen(): int
+    {
+        $value = $this->deserializeUleb128AsU32();
+        if ($value > BcsSerializer::MAX_LENGTH) {
+            throw new DeserializationError('Length exceeds the maximum supported value');
+        }
+        return $value;
+    }
+
+    public function deserializeVariantIndex(): int
+    {
+        return $this->deserializeUleb128AsU32();
+    }
+
+    public function deserializeChar(): string
+    {
+        throw new DeserializationError('BCS does not support the char type');
+    }
+
+    public function checkThatKeySlicesAreIncreasing(array $key1, array $key2): void
+    {
+        $slice1 = substr($this->input, $key1[0], $key1[1] - $key1[0]);
+        $slice2 = substr($this->input, $key2[0], $key2[1] - $key2[0]);
+        if (strcmp($slice1, $slice2) >= 0) {
+            throw new DeserializationError('Error while decoding map: keys are not serialized in the expected order');
+        }
+    }
+}
@@ -0,0 +1,63 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde\Bcs;
+
+use Serde\BinarySerializer;
+use Serde\SerializationError;
+
+final class BcsSerializer extends BinarySerializer
+{
+    public const MAX_LENGTH = (1 << 31) - 1;
+    public const MAX_CONTAINER_DEPTH = 500;
+
+    public function __construct()
+    {
+        parent::__construct(self::MAX_CONTAINER_DEPTH);
+    }
+
+    private function serializeU32AsUleb128(int $value): void
+    {
+        while ($value >= 0x80) {
+            $this->output .= chr(($value & 0x7f) | 0x80);
+            $value >>= 7;
+        }
+        $this->output .= chr($value);
+    }
+
+    public function serializeLen(int $value): void
+    {
+        if ($value < 0 || $value > self::MAX_LENGTH) {
+            throw new SerializationError('Length exceeds the maximum supported value');
+        }
+        $this->serializeU32AsUleb128($value);
+    }
+
+    public function serializeVariantIndex(int $value): void
+    {
+        $this->serializeU32AsUleb128($value);
+    }
+
+    public function serializeChar(string $value): void
+    {
+        throw new SerializationError('BCS does not support the char type');
+    }
+
+    public function sortMapEntries(array $offsets): void
+    {
+        $count = count($offsets);
+        if ($count <= 1) {
+            return;
+        }
+        $slices = [];
+        for ($i = 0; $i < $count; $i++) {
+            $end = $i + 1 < $count ? $offsets[$i + 1] : strlen($this->output);
+            $slices[] = substr($this->output, $offsets[$i], $end - $offsets[$i]);
+        }
+        sort($slices, SORT_STRING);
+        $this->output = substr($this->output, 0, $offsets[0]) . implode('', $slices);
+    }
+}
@@ -0,0 +1,187 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde;
+
+/**
+ * Common logic of the binary deserializers. See `BinarySerializer` for the representation of
+ * integers.
+ */
+abstract class BinaryDeserializer
+{
+    protected string $input;
+    protected int $offset = 0;
+    private ?int $containerDepthBudget;
+
+    public function __construct(string $input, ?int $maxContainerDepth)
+    {
+        $this->input = $input;
+        $this->containerDepthBudget = $maxContainerDepth;
+    }
+
+    abstract public function deserializeLen(): int;
+
+    abstract public function deserializeVariantIndex(): int;
+
+    abstract public function deserializeChar(): string;
+
+    /**
+     * Check that the serialized keys of a map, given as `[start, end]` offsets in the input,
+     * are in the order required by the encoding.
+     *
+     * @param array{int, int} $key1
+     * @param array{int, int} $key2
+     */
+    abstract public function checkThatKeySlicesAreIncreasing(array $key1, array $key2): void;
+
+    protected function read(int $length): string
+    {
+        if ($length > strlen($this->input) - $this->offset) {
+            throw new DeserializationError('Input is too short');
+        }
+        $bytes = substr($this->input, $this->offset, $length);
+        $this->offset += $length;
+        return $bytes;
+    }
+
+    public function deserializeUnit(): Unit
+    {
+        return new Unit();
+    }
+
+    public function deserializeBool(): bool
+    {
+        $byte = ord($this->read(1));
+        if ($byte > 1) {
+            throw new DeserializationError('Invalid boolean value');
+        }
+        return $byte === 1;
+    }
+
+    public function deserializeU8(): int
+    {
+        return ord($this->read(1));
+    }
+
+    public function deserializeU16(): int
+    {
+        return unpack('v', $this->read(2))[1];
+    }
+
+    public function deserializeU32(): int
+    {
+        return unpack('V', $this->read(4))[1];
+    }
+
+    public function deserializeU64(): int|string
+    {
+        $value = unpack('P', $this->read(8))[1];
+        // Values above PHP_INT_MAX wrap around.
+        return $value < 0 ? sprintf('%u', $value) : $value;
+    }
+
+    public function deserializeU128(): string
+    {
+        return self::importBigInteger($this->read(16), false);
+    }
+
+    public function deserializeI8(): int
+    {
+        return unpack('c', $this->read(1))[1];
+    }
+
+    public function deserializeI16(): int
+    {
+        $value = unpack('v', $this->read(2))[1];
+        return $value >= 0x8000 ? $value - 0x10000 : $value;
+    }
+
+    public function deserializeI32(): int
+    {
+        $value = unpack('V', $this->read(4))[1];
+        return $value >= 0x80000000 ? $value - 0x100000000 : $value;
+    }
+
+    public function deserializeI64(): int
+    {
+        return unpack('P', $this->read(8))[1];
+    }
+
+    public function deserializeI128(): string
+    {
+        return self::importBigInteger($this->read(16), true);
+    }
+
+    public function deserializeF32(): float
+    {
+        return unpack('g', $this->read(4))[1];
+    }
+
+    public function deserializeF64(): float
+    {
+        return unpack('e', $this->read(8))[1];
+    }
+
+    public function deserializeStr(): string
+    {
+        $value = $this->read($this->deserializeLen());
+        if (preg_match('//u', $value) !== 1) {
+            throw new DeserializationError('Invalid UTF-8 string');
+        }
+        return $value;
+    }
+
+    public function deserializeBytes(): string
+    {
+        return $this->read($this->deserializeLen());
+    }
+
+    public function deserializeOptionTag(): bool
+    {
+        $byte = ord($this->read(1));
+        if ($byte > 1) {
+            throw new DeserializationError('Invalid option tag');
+        }
+        return $byte === 1;
+    }
+
+    public function getBufferOffset(): int
+    {
+        return $this->offset;
+    }
+
+    public function increaseContainerDepth(): void
+    {
+        if ($this->containerDepthBudget !== null) {
+            if ($this->containerDepthBudget === 0) {
+                throw new DeserializationError('Exceeded maximum container depth');
+            }
+            $this->containerDepthBudget -= 1;
+        }
+    }
+
+    public function decreaseContainerDepth(): void
+    {
+        if ($this->containerDepthBudget !== null) {
+            $this->containerDepthBudget += 1;
+        }
+    }
+
+    /**
+     * Decimal string of the given little-endian two's complement encoding.
+     */
+    private static function importBigInteger(string $bytes, bool $signed): string
+    {
+        if (!extension_loaded('gmp')) {
+            throw new DeserializationError('The GMP extension is required to deserialize large integers');
+        }
+        $number = gmp_import($bytes, 1, GMP_LSW_FIRST | GMP_LITTLE_ENDIAN);
+        if ($signed && ord($bytes[strlen($bytes) - 1]) >= 0x80) {
+            $number = gmp_sub($number, gmp_pow(2, 8 * strlen($bytes)));
+        }
+        return gmp_strval($number);
+    }
+}
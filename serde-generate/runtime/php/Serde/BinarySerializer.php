@@ -0,0 +1,195 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde;
+
+/**
+ * Common logic of the binary serializers.
+ *
+ * Integers are written with `pack`, which requires a 64-bit platform. Values of type u64 are
+ * PHP integers, or decimal strings when they do not fit in a PHP integer. Values of type i128
+ * and u128 are decimal strings, which requires the GMP extension.
+ */
+abstract class BinarySerializer
+{
+    protected string $output = '';
+    private ?int $containerDepthBudget;
+
+    public function __construct(?int $maxContainerDepth)
+    {
+        $this->containerDepthBudget = $maxContainerDepth;
+    }
+
+    abstract public function serializeLen(int $value): void;
+
+    abstract public function serializeVariantIndex(int $value): void;
+
+    abstract public function serializeChar(string $value): void;
+
+    /**
+     * Sort the entries of the map whose serialization starts at the given offsets, if the
+     * encoding requires it.
+     *
+     * @param list<int> $offsets
+     */
+    abstract public function sortMapEntries(array $offsets): void;
+
+    public function serializeUnit(Unit $value): void
+    {
+    }
+
+    public function serializeBool(bool $value): void
+    {
+        $this->output .= $value ? "\x01" : "\x00";
+    }
+
+    public function serializeU8(int $value): void
+    {
+        self::checkRange($value, 0, 0xff);
+        $this->output .= pack('C', $value);
+    }
+
+    public function serializeU16(int $value): void
+    {
+        self::checkRange($value, 0, 0xffff);
+        $this->output .= pack('v', $value);
+    }
+
+    public function serializeU32(int $value): void
+    {
+        self::checkRange($value, 0, 0xffffffff);
+        $this->output .= pack('V', $value);
+    }
+
+    public function serializeU64(int|string $value): void
+    {
+        if (is_int($value)) {
+            self::checkRange($value, 0, PHP_INT_MAX);
+            $this->output .= pack('P', $value);
+        } else {
+            $this->output .= self::exportBigInteger($value, 8, false);
+        }
+    }
+
+    public function serializeU128(string $value): void
+    {
+        $this->output .= self::exportBigInteger($value, 16, false);
+    }
+
+    public function serializeI8(int $value): void
+    {
+        self::checkRange($value, -0x80, 0x7f);
+        $this->output .= pack('c', $value);
+    }
+
+    public function serializeI16(int $value): void
+    {
+        self::checkRange($value, -0x8000, 0x7fff);
+        $this->output .= pack('v', $value & 0xffff);
+    }
+
+    public function serializeI32(int $value): void
+    {
+        self::checkRange($value, -0x80000000, 0x7fffffff);
+        $this->output .= pack('V', $value & 0xffffffff);
+    }
+
+    public function serializeI64(int $value): void
+    {
+        $this->output .= pack('P', $value);
+    }
+
+    public function serializeI128(string $value): void
+    {
+        $this->output .= self::exportBigInteger($value, 16, true);
+    }
+
+    public function serializeF32(float $value): void
+    {
+        $this->output .= pack('g', $value);
+    }
+
+    public function serializeF64(float $value): void
+    {
+        $this->output .= pack('e', $value);
+    }
+
+    public function serializeStr(string $value): void
+    {
+        $this->serializeLen(strlen($value));
+        $this->output .= $value;
+    }
+
+    public function serializeBytes(string $value): void
+    {
+        $this->serializeLen(strlen($value));
+        $this->output .= $value;
+    }
+
+    public function serializeOptionTag(bool $value): void
+    {
+        $this->output .= $value ? "\x01" : "\x00";
+    }
+
+    public function getBufferOffset(): int
+    {
+        return strlen($this->output);
+    }
+
+    public function getBytes(): string
+    {
+        return $this->output;
+    }
+
+    public function increaseContainerDepth(): void
+    {
+        if ($this->containerDepthBudget !== null) {
+            if ($this->containerDepthBudget === 0) {
+                throw new SerializationError('Exceeded maximum container depth');
+            }
+            $this->containerDepthBudget -= 1;
+        }
+    }
+
+    public function decreaseContainerDepth(): void
+    {
+        if ($this->containerDepthBudget !== null) {
+            $this->containerDepthBudget += 1;
+        }
+    }
+
+    private static function checkRange(int $value, int $min, int $max): void
+    {
+        if ($value < $min || $value > $max) {
+            throw new SerializationError("Integer $value is out of range");
+        }
+    }
+
+    /**
+     * Little-endian two's complement encoding of the given decimal string on `$size` bytes.
+     */
+    private static function exportBigInteger(string $value, int $size, bool $signed): string
+    {
+        if (!extension_loaded('gmp')) {
+            throw new SerializationError('The GMP extension is required to serialize large integers');
+        }
+        if (preg_match('/^-?[0-9]+$/', $value) !== 1) {
+            throw new SerializationError("Invalid integer: $value");
+        }
+        $number = gmp_init($value, 10);
+        $bound = gmp_pow(2, 8 * $size);
+        $min = $signed ? gmp_neg(gmp_div_q($bound, 2)) : gmp_init(0);
+        $max = gmp_sub($signed ? gmp_div_q($bound, 2) : $bound, 1);
+        if (gmp_cmp($number, $min) < 0 || gmp_cmp($number, $max) > 0) {
+            throw new SerializationError("Integer $value is out of range");
+        }
+        if (gmp_sign($number) < 0) {
+            $number = gmp_add($number, $bound);
+        }
+        $bytes = gmp_export($number, 1, GMP_LSW_FIRST | GMP_LITTLE_ENDIAN);
+        return str_pad($bytes, $size, "\x00");
+    }
+}
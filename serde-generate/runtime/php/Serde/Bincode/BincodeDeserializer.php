@@ -0,0 +1,56 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde\Bincode;
+
+use Serde\BinaryDeserializer;
+use Serde\DeserializationError;
+
+final class BincodeDeserializer extends BinaryDeserializer
+{
+    public function __construct(string $input)
+    {
+        parent::__construct($input, null);
+    }
+
+    public function deserializeLen(): int
+    {
+        $value = unpack('P', $this->read(8))[1];
+        if ($value < 0 || $value > BincodeSerializer::MAX_LENGTH) {
+            throw new DeserializationError('Length exceeds the maximum supported value');
+        }
+        return $value;
+    }
+
+    public function deserializeVariantIndex(): int
+    {
+        return unpack('V', $this->read(4))[1];
+    }
+
+    public function deserializeChar(): string
+    {
+        if ($this->offset >= strlen($this->input)) {
+            throw new DeserializationError('Input is too short');
+        }
+        $byte = ord($this->input[$this->offset]);
+        $length = match (true) {
+            $byte < 0x80 => 1,
+            $byte >= 0xc0 && $byte < 0xe0 => 2,
+            $byte >= 0xe0 && $byte < 0xf0 => 3,
+            $byte >= 0xf0 && $byte < 0xf8 => 4,
+            default => throw new DeserializationError('Invalid UTF-8 character'),
+        };
+        $value = $this->read($length);
+        if (preg_match('/^.$/us', $value) !== 1) {
+            throw new DeserializationError('Invalid UTF-8 character');
+        }
+        return $value;
+    }
+
+    public function checkThatKeySlicesAreIncreasing(array $key1, array $key2): void
+    {
+    }
+}
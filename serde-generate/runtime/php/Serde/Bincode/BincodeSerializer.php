@@ -0,0 +1,48 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde\Bincode;
+
+use Serde\BinarySerializer;
+use Serde\SerializationError;
+
+/**
+ * Bincode, with the default configuration of the Rust crate (version 1).
+ */
+final class BincodeSerializer extends BinarySerializer
+{
+    public const MAX_LENGTH = (1 << 31) - 1;
+
+    public function __construct()
+    {
+        parent::__construct(null);
+    }
+
+    public function serializeLen(int $value): void
+    {
+        if ($value < 0 || $value > self::MAX_LENGTH) {
+            throw new SerializationError('Length exceeds the maximum supported value');
+        }
+        $this->output .= pack('P', $value);
+    }
+
+    public function serializeVariantIndex(int $value): void
+    {
+        $this->output .= pack('V', $value);
+    }
+
+    public function serializeChar(string $value): void
+    {
+        if (preg_match('/^.$/us', $value) !== 1) {
+            throw new SerializationError('Expected a single UTF-8 character');
+        }
+        $this->output .= $value;
+    }
+
+    public function sortMapEntries(array $offsets): void
+    {
+    }
+}
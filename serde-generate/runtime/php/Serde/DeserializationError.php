@@ -0,0 +1,11 @@
+<?php
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+declare(strict_types=1);
+
+namespace Serde;
+
+class DeserializationError extends \Exception
+{
+}
@@ -3,7 +3,6 @@
 
 import dataclasses
 import collections
-import io
 import typing
 from copy import copy
 from typing import get_type_hints
@@ -19,15 +18,14 @@ MAX_CONTAINER_DEPTH = 500
 class BcsSerializer(sb.BinarySerializer):
     def __init__(self):
         super().__init__(
-            output=io.BytesIO(), container_depth_budget=MAX_CONTAINER_DEPTH
+            output=bytearray(), container_depth_budget=MAX_CONTAINER_DEPTH
         )
 
     def serialize_u32_as_uleb128(self, value: int):
         while value >= 0x80:
-            b = (value & 0x7F) | 0x80
-            self.output.write(b.to_bytes(1, "little", signed=False))
+            self.output.append((value & 0x7F) | 0x80)
             value >>= 7
-        self.output.write(value.to_bytes(1, "little", signed=False))
+        self.output.append(value)
 
     def serialize_len(self, value: int):
         if value > MAX_LENGTH:
@@ -44,29 +42,23 @@ class BcsSerializer(sb.BinarySerializer):
     def sort_map_entries(self, offsets: typing.List[int]):
         if len(offsets) < 1:
             return
-        buf = self.output.getbuffer()
-        offsets.append(len(buf))
+        offsets.append(len(self.output))
         slices = []
         for i in range(1, len(offsets)):
-            slices.append(bytes(buf[offsets[i - 1] : offsets[i]]))
-        buf.release()
+            slices.append(bytes(self.output[offsets[i - 1] : offsets[i]]))
         slices.sort()
-        self.output.seek(offsets[0])
-        for s in slices:
-            self.output.write(s)
-        assert offsets[-1] == len(self.output.getbuffer())
+        self.output[offsets[0] :] = b"".join(slices)
+        assert offsets[-1] == len(self.output)
 
 
 class BcsDeserializer(sb.BinaryDeserializer):
     def __init__(self, content):
-        super().__init__(
-            input=io.BytesIO(content), container_depth_budget=MAX_CONTAINER_DEPTH
-        )
+        super().__init__(input=content, container_depth_budget=MAX_CONTAINER_DEPTH)
 
     def deserialize_uleb128_as_u32(self) -> int:
         value = 0
         for shift in range(0, 32, 7):
-            byte = int.from_bytes(self.read(1), "little", signed=False)
+            byte = self.read_byte()
             digit = byte & 0x7F
             value |= digit << shift
             if value > MAX_U32:
@@ -96,8 +88,8 @@ class BcsDeserializer(sb.BinaryDeserializer):
     def check_that_key_slices_are_increasing(
         self, slice1: typing.Tuple[int, int], slice2: typing.Tuple[int, int]
     ):
-        key1 = bytes(self.input.getbuffer()[slice1[0] : slice1[1]])
-        key2 = bytes(self.input.getbuffer()[slice2[0] : slice2[1]])
+        key1 = bytes(self.input[slice1[0] : slice1[1]])
+        key2 = bytes(self.input[slice2[0] : slice2[1]])
         if key1 >= key2:
             raise st.DeserializationError(
                 "Serialized keys in a map must be ordered by increasing lexicographic order"
@@ -114,3 +106,7 @@ def deserialize(content: bytes, obj_type) -> typing.Tuple[typing.Any, bytes]:
     deserializer = BcsDeserializer(content)
     value = deserializer.deserialize_any(obj_type)
     return value, deserializer.get_remaining_buffer()
+
+
+def deserialize_stream(reader: typing.BinaryIO, obj_type) -> typing.Iterator[typing.Any]:
+    return sb.deserialize_stream(reader, BcsDeserializer, obj_type)
@@ -6,6 +6,9 @@ import serde_types as st
 import bcs
 import typing
 import sys
+import io
+import serde_binary as sb
+from unittest import mock
 from dataclasses import dataclass
 from collections import OrderedDict
 
@@ -219,6 +222,34 @@ class BcsTestCase(unittest.TestCase):
             (BcsTestCase.Foo(x=2, y=1), b""),
         )
 
+    @dataclass
+    class Foos:
+        value: typing.Sequence["BcsTestCase.Foo"]
+
+    def test_deserialize_stream(self):
+        foos = BcsTestCase.Foos(
+            value=[BcsTestCase.Foo(x=i, y=1000 * i) for i in range(50)]
+        )
+        content = bcs.serialize(foos, BcsTestCase.Foos)
+        # Small chunks split the values.
+        with mock.patch.object(sb, "STREAM_CHUNK_SIZE", 2):
+            stream = bcs.deserialize_stream(io.BytesIO(content), BcsTestCase.Foos)
+            self.assertEqual(list(stream), foos.value)
+            stream = bcs.deserialize_stream(io.BytesIO(b"\x00"), BcsTestCase.Foos)
+            self.assertEqual(list(stream), [])
+            with self.assertRaisesRegex(st.DeserializationError, "Input is too short"):
+                list(
+                    bcs.deserialize_stream(
+                        io.BytesIO(content[:-1]), BcsTestCase.Foos
+                    )
+                )
+            stream = bcs.deserialize_stream(
+                io.BytesIO(content + b"\x00"), BcsTestCase.Foos
+            )
+            self.assertEqual(next(stream), foos.value[0])
+            with self.assertRaisesRegex(st.DeserializationError, "not read"):
+                list(stream)
+
     class Bar:
         VARIANTS = []  # type: typing.Sequence[typing.Type['Bar']]
 
@@ -3,7 +3,6 @@
 
 import dataclasses
 import collections
-import io
 import struct
 import typing
 from copy import copy
@@ -15,24 +14,30 @@ import serde_binary as sb
 # Maximum length in practice for sequences (e.g. in Java).
 MAX_LENGTH = (1 << 31) - 1
 
+_F64 = struct.Struct("<d")
+_U32 = struct.Struct("<I")
+_U64 = struct.Struct("<Q")
+
 
 class BincodeSerializer(sb.BinarySerializer):
     def __init__(self):
-        super().__init__(output=io.BytesIO(), container_depth_budget=None)
+        super().__init__(output=bytearray(), container_depth_budget=None)
 
+    # Single-precision values are handled through their bit patterns: converting them to Python
+    # floats (i.e. doubles) would quiet signaling NaNs.
     def serialize_f32(self, value: st.float32):
-        self.output.write(struct.pack("<f", value))
+        self.output += _U32.pack(int(st.float32(value).view(st.uint32)))
 
     def serialize_f64(self, value: st.float64):
-        self.output.write(struct.pack("<d", value))
+        self.output += _F64.pack(value)
 
     def serialize_len(self, value: int):
         if value > MAX_LENGTH:
             raise st.SerializationError("Length exceeds the maximum supported value.")
-        self.output.write(int(value).to_bytes(8, "little", signed=False))
+        self.output += _U64.pack(value)
 
     def serialize_variant_index(self, value: int):
-        self.output.write(int(value).to_bytes(4, "little", signed=False))
+        self.output += _U32.pack(value)
 
     def sort_map_entries(self, offsets: typing.List[int]):
         pass
@@ -40,24 +45,23 @@ class BincodeSerializer(sb.BinarySerializer):
 
 class BincodeDeserializer(sb.BinaryDeserializer):
     def __init__(self, content):
-        super().__init__(input=io.BytesIO(content), container_depth_budget=None)
+        super().__init__(input=content, container_depth_budget=None)
 
     def deserialize_f32(self) -> st.float32:
-        (value,) = struct.unpack("<f", self.read(4))
-        return st.float32(value)
+        (bits,) = self.unpack(_U32)
+        return st.uint32(bits).view(st.float32)
 
     def deserialize_f64(self) -> st.float64:
-        (value,) = struct.unpack("<d", self.read(8))
-        return st.float64(value)
+        return st.float64(self.unpack(_F64)[0])
 
     def deserialize_len(self) -> int:
-        value = int.from_bytes(self.read(8), byteorder="little", signed=False)
+        (value,) = self.unpack(_U64)
         if value > MAX_LENGTH:
             raise st.DeserializationError("Length exceeds the maximum supported value.")
         return value
 
     def deserialize_variant_index(self) -> int:
-        return int.from_bytes(self.read(4), byteorder="little", signed=False)
+        return self.unpack(_U32)[0]
 
     def check_that_key_slices_are_increasing(
         self, slice1: typing.Tuple[int, int], slice2: typing.Tuple[int, int]
@@ -75,3 +79,7 @@ def deserialize(content: bytes, obj_type) -> typing.Tuple[typing.Any, bytes]:
     deserializer = BincodeDeserializer(content)
     value = deserializer.deserialize_any(obj_type)
     return value, deserializer.get_remaining_buffer()
+
+
+def deserialize_stream(reader: typing.BinaryIO, obj_type) -> typing.Iterator[typing.Any]:
+    return sb.deserialize_stream(reader, BincodeDeserializer, obj_type)
@@ -3,6 +3,7 @@
 
 from collections import OrderedDict
 from dataclasses import dataclass
+import struct
 import unittest
 import serde_types as st
 import bincode
@@ -101,6 +102,28 @@ class BincodeTestCase(unittest.TestCase):
         self.assertEqual(reminder, b"")
         self.assertAlmostEqual(value, 0.000000000003)
 
+    def test_bincode_float_bit_patterns(self):
+        # NaN (with and without payload), -0.0, infinities, and a signaling NaN.
+        for bits in [0x7FC00000, 0xFFC00001, 0x80000000, 0x7F800000, 0xFF800000, 0x7FA00000]:
+            content = struct.pack("<I", bits)
+            value, reminder = bincode.deserialize(content, st.float32)
+            self.assertEqual(reminder, b"")
+            self.assertEqual(int(value.view(st.uint32)), bits)
+            self.assertEqual(bincode.serialize(value, st.float32), content)
+        for bits in [
+            0x7FF8000000000000,
+            0xFFF8000000000001,
+            0x8000000000000000,
+            0x7FF0000000000000,
+            0xFFF0000000000000,
+            0x7FF4000000000000,
+        ]:
+            content = struct.pack("<Q", bits)
+            value, reminder = bincode.deserialize(content, st.float64)
+            self.assertEqual(reminder, b"")
+            self.assertEqual(struct.unpack("<Q", struct.pack("<d", value))[0], bits)
+            self.assertEqual(bincode.serialize(value, st.float64), content)
+
     def test_serialize_bytes(self):
         self.assertEqual(bincode.serialize(b"", bytes), b"\x00" * 8)
         self.assertEqual(
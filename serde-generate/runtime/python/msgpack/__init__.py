@@ -0,0 +1,631 @@
+# Copyright (c) Zefchain Labs, Inc.
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+"""
+Module implementing the MessagePack encoding, with the same layout as the Rust crate `rmp-serde`
+when structs are encoded as maps (`rmp_serde::to_vec_named`):
+
+* Integers and lengths use the smallest MessagePack format that holds their value. Integers
+  wider than 64 bits are not supported.
+* Structs are maps from field names to values, in declaration order. Newtype structs are
+  encoded as their content, tuple structs as arrays, and unit structs as empty arrays.
+* Enums are externally tagged: the name of the variant for variants without fields,
+  `{name: content}` otherwise.
+* Options are `nil` or their content, and the unit value is `nil`.
+
+The names of fields and variants are read from the `SCHEMA` attribute of the generated classes.
+"""
+
+import collections
+import datetime
+import functools
+import struct
+import typing
+
+import serde_types as st
+import serde_binary as sb
+
+_INTEGER_RANGES = {
+    st.uint8: (0, 1 << 8),
+    st.uint16: (0, 1 << 16),
+    st.uint32: (0, 1 << 32),
+    st.uint64: (0, 1 << 64),
+    st.int8: (-(1 << 7), 1 << 7),
+    st.int16: (-(1 << 15), 1 << 15),
+    st.int32: (-(1 << 31), 1 << 31),
+    st.int64: (-(1 << 63), 1 << 63),
+}
+
+# Formats of the integers following the markers of the MessagePack integer families.
+_INTEGER_MARKERS = {
+    0xCC: struct.Struct(">B"),
+    0xCD: struct.Struct(">H"),
+    0xCE: struct.Struct(">I"),
+    0xCF: struct.Struct(">Q"),
+    0xD0: struct.Struct(">b"),
+    0xD1: struct.Struct(">h"),
+    0xD2: struct.Struct(">i"),
+    0xD3: struct.Struct(">q"),
+}
+
+_U8 = struct.Struct(">B")
+_U16 = struct.Struct(">H")
+_U32 = struct.Struct(">I")
+_F32 = struct.Struct(">f")
+_F64 = struct.Struct(">d")
+
+_NIL = 0xC0
+_FALSE = 0xC2
+_TRUE = 0xC3
+
+
+@functools.lru_cache(maxsize=None)
+def _variants(enum_type) -> typing.Dict[typing.Any, typing.Tuple[int, str, typing.Any]]:
+    """Index, name, and schema of the variants of an enum, indexed by both index and name."""
+    variants = {}
+    for index, name, schema in enum_type.SCHEMA["variants"]:
+        variants[index] = (index, name, schema)
+        variants[name] = (index, name, schema)
+    return variants
+
+
+def _enum_class(variant_type):
+    """Class holding the schema of the enum of a variant class: the base class of the variants,
+    or the variant class itself for Pydantic tagged unions."""
+    if sb._is_union_variant(variant_type):
+        return variant_type
+    for base in variant_type.__mro__[1:]:
+        if sb._is_enum(base):
+            return base
+    raise st.SerializationError("Unexpected type", variant_type)
+
+
+def _struct_fields(obj_type, schema) -> typing.List[typing.Tuple[str, str, typing.Any]]:
+    """Serialized name, Python name, and type of the fields of a struct or a struct variant."""
+    fields = sb._field_types(obj_type)
+    if fields is None or len(fields) != len(schema["fields"]):
+        raise st.SerializationError("Unexpected type", obj_type)
+    return [
+        (name, field, field_type)
+        for (name, _), (field, field_type) in zip(schema["fields"], fields)
+    ]
+
+
+class MsgPackSerializer(sb.BinarySerializer):
+    def __init__(self):
+        super().__init__(output=bytearray(), container_depth_budget=None)
+
+    def serialize_nil(self):
+        self.output.append(_NIL)
+
+    def serialize_int(self, value: int, obj_type):
+        low, high = _INTEGER_RANGES[obj_type]
+        if not low <= value < high:
+            raise st.SerializationError("Integer out of range:", value, obj_type)
+        if 0 <= value < 0x80 or -0x20 <= value < 0:
+            self.output += struct.pack(">b" if value < 0 else ">B", value)
+        elif value < 0:
+            for marker in (0xD0, 0xD1, 0xD2, 0xD3):
+                fmt = _INTEGER_MARKERS[marker]
+                if value >= -(1 << (8 * fmt.size - 1)):
+                    break
+            self.output.append(marker)
+            self.output += fmt.pack(value)
+        else:
+            for marker in (0xCC, 0xCD, 0xCE, 0xCF):
+                fmt = _INTEGER_MARKERS[marker]
+                if value < 1 << (8 * fmt.size):
+                    break
+            self.output.append(marker)
+            self.output += fmt.pack(value)
+
+    def serialize_header(self, length: int, fix_marker: int, markers: typing.Tuple[int, int]):
+        """Write the marker and the length of an array or a map."""
+        if length < 16:
+            self.output.append(fix_marker | length)
+        elif length < 1 << 16:
+            self.output.append(markers[0])
+            self.output += _U16.pack(length)
+        elif length < 1 << 32:
+            self.output.append(markers[1])
+            self.output += _U32.pack(length)
+        else:
+            raise st.SerializationError("Length exceeds the maximum supported value.")
+
+    def serialize_len(self, value: int):
+        self.serialize_header(value, 0x90, (0xDC, 0xDD))
+
+    def serialize_map_len(self, value: int):
+        self.serialize_header(value, 0x80, (0xDE, 0xDF))
+
+    def serialize_bool(self, value: bool):
+        self.output.append(_TRUE if value else _FALSE)
+
+    def serialize_u8(self, value: st.uint8):
+        self.serialize_int(int(value), st.uint8)
+
+    def serialize_u16(self, value: st.uint16):
+        self.serialize_int(int(value), st.uint16)
+
+    def serialize_u32(self, value: st.uint32):
+        self.serialize_int(int(value), st.uint32)
+
+    def serialize_u64(self, value: st.uint64):
+        self.serialize_int(int(value), st.uint64)
+
+    def serialize_u128(self, value: st.uint128):
+        raise st.SerializationError("Integers wider than 64 bits are not supported")
+
+    def serialize_i8(self, value: st.int8):
+        self.serialize_int(int(value), st.int8)
+
+    def serialize_i16(self, value: st.int16):
+        self.serialize_int(int(value), st.int16)
+
+    def serialize_i32(self, value: st.int32):
+        self.serialize_int(int(value), st.int32)
+
+    def serialize_i64(self, value: st.int64):
+        self.serialize_int(int(value), st.int64)
+
+    def serialize_i128(self, value: st.int128):
+        raise st.SerializationError("Integers wider than 64 bits are not supported")
+
+    def serialize_f32(self, value: st.float32):
+        self.output.append(0xCA)
+        self.output += _F32.pack(value)
+
+    def serialize_f64(self, value: st.float64):
+        self.output.append(0xCB)
+        self.output += _F64.pack(value)
+
+    def serialize_unit(self, value: st.unit):
+        self.serialize_nil()
+
+    def serialize_str(self, value: str):
+        content = value.encode()
+        length = len(content)
+        if length < 32:
+            self.output.append(0xA0 | length)
+        elif length < 1 << 8:
+            self.output.append(0xD9)
+            self.output += _U8.pack(length)
+        elif length < 1 << 16:
+            self.output.append(0xDA)
+            self.output += _U16.pack(length)
+        elif length < 1 << 32:
+            self.output.append(0xDB)
+            self.output += _U32.pack(length)
+        else:
+            raise st.SerializationError("Length exceeds the maximum supported value.")
+        self.output += content
+
+    def serialize_char(self, value: st.char):
+        self.serialize_str(str(value))
+
+    def serialize_bytes(self, value: bytes):
+        length = len(value)
+        if length < 1 << 8:
+            self.output.append(0xC4)
+            self.output += _U8.pack(length)
+        elif length < 1 << 16:
+            self.output.append(0xC5)
+            self.output += _U16.pack(length)
+        elif length < 1 << 32:
+            self.output.append(0xC6)
+            self.output += _U32.pack(length)
+        else:
+            raise st.SerializationError("Length exceeds the maximum supported value.")
+        self.output += value
+
+    def serialize_duration(self, value: datetime.timedelta):
+        # Same layout as Rust's `struct Duration { secs: u64, nanos: u32 }`.
+        if value < datetime.timedelta(0):
+            raise st.SerializationError("Negative durations are not supported:", value)
+        self.serialize_map_len(2)
+        self.serialize_str("secs")
+        self.serialize_u64(st.uint64(value.days * 86400 + value.seconds))
+        self.serialize_str("nanos")
+        self.serialize_u32(st.uint32(value.microseconds * 1000))
+
+    # noqa: C901
+    def serialize_any(self, obj: typing.Any, obj_type):
+        obj_type = sb._strip_annotations(obj_type)
+        if obj_type in self.primitive_type_serializer:
+            self.primitive_type_serializer[obj_type](obj)
+
+        elif sb._generic_origin(obj_type) is not None:  # Generic type
+            origin = sb._generic_origin(obj_type)
+            types = getattr(obj_type, "__args__")
+
+            if origin == collections.abc.Sequence:  # Sequence
+                self.serialize_len(len(obj))
+                for item in obj:
+                    self.serialize_any(item, types[0])
+
+            elif origin == tuple:  # Tuple
+                if len(types) == 1 and types[0] == ():
+                    types = ()
+                if len(obj) != len(types):
+                    raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+                self.serialize_len(len(types))
+                for item, item_type in zip(obj, types):
+                    self.serialize_any(item, item_type)
+
+            elif origin == typing.Union:
+                if types[-1] == type(None):  # Option
+                    # Options of unions are flattened, e.g. `Optional[Union[A, B]]`.
+                    content_type = (
+                        types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                    )
+                    if obj is None:
+                        self.serialize_nil()
+                    else:
+                        self.serialize_any(obj, content_type)
+                else:  # Union of enum variants
+                    if obj.__class__ not in types:
+                        raise st.SerializationError(
+                            "Wrong Value for the type", obj, obj_type
+                        )
+                    self.serialize_any(obj, obj.__class__)
+
+            elif origin == dict:  # Map, in the order of the dict
+                self.serialize_map_len(len(obj))
+                for key, value in obj.items():
+                    self.serialize_any(key, types[0])
+                    self.serialize_any(value, types[1])
+
+            else:
+                raise st.SerializationError("Unexpected type", obj_type)
+
+        elif sb._is_int_enum(obj_type):  # Enum without data
+            if not isinstance(obj, obj_type):
+                raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+            self.serialize_str(_variants(obj_type)[int(obj)][1])
+
+        elif sb._is_enum(obj_type):  # Enum
+            if not hasattr(obj, "INDEX"):
+                raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+            self.serialize_variant(obj, obj_type.VARIANTS[obj.__class__.INDEX])
+
+        elif sb._is_union_variant(obj_type):  # Variant of a tagged union
+            self.serialize_variant(obj, obj_type)
+
+        else:  # Struct
+            if not isinstance(obj, obj_type) or not hasattr(obj_type, "SCHEMA"):
+                raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+            self.serialize_content(obj, obj_type, obj_type.SCHEMA)
+
+    def serialize_variant(self, obj: typing.Any, variant_type):
+        if not isinstance(obj, variant_type):
+            raise st.SerializationError("Wrong Value for the type", obj, variant_type)
+        _, name, schema = _variants(_enum_class(variant_type))[variant_type.INDEX]
+        if schema["kind"] == "unit":
+            self.serialize_str(name)
+        else:
+            self.serialize_map_len(1)
+            self.serialize_str(name)
+            self.serialize_content(obj, variant_type, schema)
+
+    def serialize_content(self, obj: typing.Any, obj_type, schema):
+        """Serialize the fields of a struct or a variant, given the schema of its container."""
+        kind = schema["kind"]
+        if kind in ("unit_struct", "unit"):
+            self.serialize_len(0)
+        elif kind in ("newtype_struct", "newtype", "tuple_struct", "tuple"):
+            # Tuple structs hold a Python tuple, which is encoded as an array.
+            ((field, field_type),) = sb._field_types(obj_type)
+            self.serialize_any(getattr(obj, field), field_type)
+        else:
+            fields = _struct_fields(obj_type, schema)
+            self.serialize_map_len(len(fields))
+            for name, field, field_type in fields:
+                self.serialize_str(name)
+                self.serialize_any(getattr(obj, field), field_type)
+
+
+class MsgPackDeserializer(sb.BinaryDeserializer):
+    def __init__(self, content):
+        super().__init__(input=content, container_depth_budget=None)
+
+    def peek_byte(self) -> int:
+        if self.offset >= len(self.input):
+            raise sb._InputTooShort("Input is too short")
+        return self.input[self.offset]
+
+    def deserialize_header(
+        self, fix_marker: int, markers: typing.Tuple[int, int], what: str
+    ) -> int:
+        """Read the length of an array or a map."""
+        marker = self.read_byte()
+        if marker & 0xF0 == fix_marker:
+            return marker & 0x0F
+        if marker == markers[0]:
+            return self.unpack(_U16)[0]
+        if marker == markers[1]:
+            return self.unpack(_U32)[0]
+        raise st.DeserializationError("Expected %s, found marker" % what, marker)
+
+    def deserialize_len(self) -> int:
+        return self.deserialize_header(0x90, (0xDC, 0xDD), "an array")
+
+    def deserialize_map_len(self) -> int:
+        return self.deserialize_header(0x80, (0xDE, 0xDF), "a map")
+
+    def deserialize_int(self, obj_type) -> int:
+        marker = self.read_byte()
+        if marker < 0x80:
+            value = marker
+        elif marker >= 0xE0:
+            value = marker - 0x100
+        elif marker in _INTEGER_MARKERS:
+            (value,) = self.unpack(_INTEGER_MARKERS[marker])
+        else:
+            raise st.DeserializationError("Expected an integer, found marker", marker)
+        low, high = _INTEGER_RANGES[obj_type]
+        if not low <= value < high:
+            raise st.DeserializationError("Integer out of range:", value, obj_type)
+        return value
+
+    def deserialize_bool(self) -> bool:
+        marker = self.read_byte()
+        if marker == _FALSE:
+            return False
+        elif marker == _TRUE:
+            return True
+        else:
+            raise st.DeserializationError("Unexpected boolean value:", marker)
+
+    def deserialize_u8(self) -> st.uint8:
+        return st.uint8(self.deserialize_int(st.uint8))
+
+    def deserialize_u16(self) -> st.uint16:
+        return st.uint16(self.deserialize_int(st.uint16))
+
+    def deserialize_u32(self) -> st.uint32:
+        return st.uint32(self.deserialize_int(st.uint32))
+
+    def deserialize_u64(self) -> st.uint64:
+        return st.uint64(self.deserialize_int(st.uint64))
+
+    def deserialize_u128(self) -> st.uint128:
+        raise st.DeserializationError("Integers wider than 64 bits are not supported")
+
+    def deserialize_i8(self) -> st.int8:
+        return st.int8(self.deserialize_int(st.int8))
+
+    def deserialize_i16(self) -> st.int16:
+        return st.int16(self.deserialize_int(st.int16))
+
+    def deserialize_i32(self) -> st.int32:
+        return st.int32(self.deserialize_int(st.int32))
+
+    def deserialize_i64(self) -> st.int64:
+        return st.int64(self.deserialize_int(st.int64))
+
+    def deserialize_i128(self) -> st.int128:
+        raise st.DeserializationError("Integers wider than 64 bits are not supported")
+
+    def deserialize_float(self) -> float:
+        marker = self.read_byte()
+        if marker == 0xCA:
+            return self.unpack(_F32)[0]
+        if marker == 0xCB:
+            return self.unpack(_F64)[0]
+        raise st.DeserializationError("Expected a float, found marker", marker)
+
+    def deserialize_f32(self) -> st.float32:
+        return st.float32(self.deserialize_float())
+
+    def deserialize_f64(self) -> st.float64:
+        return st.float64(self.deserialize_float())
+
+    def deserialize_unit(self) -> st.unit:
+        marker = self.read_byte()
+        if marker != _NIL:
+            raise st.DeserializationError("Expected nil, found marker", marker)
+
+    def deserialize_str(self) -> str:
+        marker = self.read_byte()
+        if marker & 0xE0 == 0xA0:
+            length = marker & 0x1F
+        elif marker == 0xD9:
+            (length,) = self.unpack(_U8)
+        elif marker == 0xDA:
+            (length,) = self.unpack(_U16)
+        elif marker == 0xDB:
+            (length,) = self.unpack(_U32)
+        else:
+            raise st.DeserializationError("Expected a string, found marker", marker)
+        content = self.read(length)
+        try:
+            return content.decode()
+        except UnicodeDecodeError:
+            raise st.DeserializationError("Invalid unicode string:", content)
+
+    def deserialize_char(self) -> st.char:
+        value = self.deserialize_str()
+        if len(value) != 1:
+            raise st.DeserializationError("Invalid char value:", value)
+        return st.char(value)
+
+    def deserialize_bytes(self) -> bytes:
+        marker = self.read_byte()
+        if marker == 0xC4:
+            (length,) = self.unpack(_U8)
+        elif marker == 0xC5:
+            (length,) = self.unpack(_U16)
+        elif marker == 0xC6:
+            (length,) = self.unpack(_U32)
+        else:
+            raise st.DeserializationError("Expected bytes, found marker", marker)
+        return self.read(length)
+
+    def deserialize_duration(self) -> datetime.timedelta:
+        values = {}
+        for _ in range(self.deserialize_map_len()):
+            name = self.deserialize_str()
+            if name == "secs" and name not in values:
+                values[name] = int(self.deserialize_u64())
+            elif name == "nanos" and name not in values:
+                values[name] = int(self.deserialize_u32())
+            else:
+                raise st.DeserializationError("Unexpected field of duration:", name)
+        if len(values) != 2:
+            raise st.DeserializationError("Missing fields of duration")
+        secs, nanos = values["secs"], values["nanos"]
+        # `timedelta` only has microsecond precision.
+        if nanos >= 1_000_000_000 or nanos % 1000 != 0:
+            raise st.DeserializationError("Unsupported duration:", secs, nanos)
+        try:
+            return datetime.timedelta(seconds=secs, microseconds=nanos // 1000)
+        except OverflowError:
+            raise st.DeserializationError("Duration out of range:", secs, nanos)
+
+    # noqa
+    def deserialize_any(self, obj_type) -> typing.Any:
+        obj_type = sb._strip_annotations(obj_type)
+        if obj_type in self.primitive_type_deserializer:
+            return self.primitive_type_deserializer[obj_type]()
+
+        elif sb._generic_origin(obj_type) is not None:  # Generic type
+            origin = sb._generic_origin(obj_type)
+            types = getattr(obj_type, "__args__")
+
+            if origin == collections.abc.Sequence:  # Sequence
+                length = self.deserialize_len()
+                return [self.deserialize_any(types[0]) for _ in range(length)]
+
+            elif origin == tuple:  # Tuple
+                if len(types) == 1 and types[0] == ():
+                    types = ()
+                length = self.deserialize_len()
+                if length != len(types):
+                    raise st.DeserializationError("Unexpected length of tuple:", length)
+                return tuple(self.deserialize_any(item_type) for item_type in types)
+
+            elif origin == typing.Union:
+                if types[-1] == type(None):  # Option
+                    # Options of unions are flattened, e.g. `Optional[Union[A, B]]`.
+                    content_type = (
+                        types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                    )
+                    if self.peek_byte() == _NIL:
+                        self.offset += 1
+                        return None
+                    return self.deserialize_any(content_type)
+                else:  # Union of enum variants
+                    name, has_content = self.deserialize_variant_name()
+                    for variant_type in types:
+                        _, variant_name, schema = _variants(variant_type)[
+                            variant_type.INDEX
+                        ]
+                        if variant_name == name:
+                            return self.deserialize_variant(
+                                variant_type, schema, has_content
+                            )
+                    raise st.DeserializationError("Unexpected variant name", name)
+
+            elif origin == dict:  # Map
+                length = self.deserialize_map_len()
+                result = dict()
+                for _ in range(length):
+                    key = self.deserialize_any(types[0])
+                    result[key] = self.deserialize_any(types[1])
+                return result
+
+            else:
+                raise st.DeserializationError("Unexpected type", obj_type)
+
+        elif sb._is_int_enum(obj_type):  # Enum without data
+            name = self.deserialize_str()
+            if name not in _variants(obj_type):
+                raise st.DeserializationError("Unexpected variant name", name)
+            return obj_type(_variants(obj_type)[name][0])
+
+        elif sb._is_enum(obj_type) or sb._is_union_variant(obj_type):  # Enum
+            name, has_content = self.deserialize_variant_name()
+            variants = _variants(obj_type)
+            if name not in variants:
+                raise st.DeserializationError("Unexpected variant name", name)
+            index, _, schema = variants[name]
+            if sb._is_enum(obj_type):
+                variant_type = obj_type.VARIANTS[index]
+            elif index == obj_type.INDEX:
+                variant_type = obj_type
+            else:
+                raise st.DeserializationError("Unexpected variant name", name)
+            return self.deserialize_variant(variant_type, schema, has_content)
+
+        else:  # Struct
+            if not hasattr(obj_type, "SCHEMA"):
+                raise st.DeserializationError("Unexpected type", obj_type)
+            return self.deserialize_content(obj_type, obj_type.SCHEMA)
+
+    def deserialize_variant_name(self) -> typing.Tuple[str, bool]:
+        """Read the name of a variant, and whether the content of the variant follows."""
+        marker = self.peek_byte()
+        if marker & 0xF0 == 0x80 or marker in (0xDE, 0xDF):
+            length = self.deserialize_map_len()
+            if length != 1:
+                raise st.DeserializationError("Unexpected length of variant:", length)
+            return self.deserialize_str(), True
+        return self.deserialize_str(), False
+
+    def deserialize_variant(self, variant_type, schema, has_content: bool) -> typing.Any:
+        if schema["kind"] == "unit":
+            if has_content:
+                self.deserialize_unit()
+            return variant_type()
+        if not has_content:
+            raise st.DeserializationError("Missing content of variant", variant_type)
+        return self.deserialize_content(variant_type, schema)
+
+    def deserialize_content(self, obj_type, schema) -> typing.Any:
+        """Deserialize the fields of a struct or a variant, given the schema of its container."""
+        kind = schema["kind"]
+        if kind in ("unit_struct", "unit"):
+            marker = self.read_byte()
+            if marker not in (_NIL, 0x90):
+                raise st.DeserializationError("Expected an empty array, found marker", marker)
+            return obj_type()
+        if kind in ("newtype_struct", "newtype", "tuple_struct", "tuple"):
+            ((field, field_type),) = sb._field_types(obj_type)
+            return obj_type(**{field: self.deserialize_any(field_type)})
+        fields = _struct_fields(obj_type, schema)
+        values = {}
+        marker = self.peek_byte()
+        if marker & 0xF0 == 0x90 or marker in (0xDC, 0xDD):
+            # Structs encoded as arrays (`rmp_serde::to_vec`) are accepted as well.
+            length = self.deserialize_len()
+            if length != len(fields):
+                raise st.DeserializationError("Unexpected number of fields:", length)
+            for _, field, field_type in fields:
+                values[field] = self.deserialize_any(field_type)
+            return obj_type(**values)
+        by_name = {name: (field, field_type) for name, field, field_type in fields}
+        for _ in range(self.deserialize_map_len()):
+            name = self.deserialize_str()
+            if name not in by_name or by_name[name][0] in values:
+                raise st.DeserializationError("Unexpected field name:", name)
+            field, field_type = by_name[name]
+            values[field] = self.deserialize_any(field_type)
+        if len(values) != len(fields):
+            raise st.DeserializationError("Missing fields of", obj_type)
+        return obj_type(**values)
+
+
+def serialize(obj: typing.Any, obj_type) -> bytes:
+    serializer = MsgPackSerializer()
+    serializer.serialize_any(obj, obj_type)
+    return serializer.get_buffer()
+
+
+def deserialize(content: bytes, obj_type) -> typing.Tuple[typing.Any, bytes]:
+    deserializer = MsgPackDeserializer(content)
+    value = deserializer.deserialize_any(obj_type)
+    return value, deserializer.get_remaining_buffer()
+
+
+def deserialize_stream(reader: typing.BinaryIO, obj_type) -> typing.Iterator[typing.Any]:
+    return sb.deserialize_stream(reader, MsgPackDeserializer, obj_type)
@@ -0,0 +1,143 @@
+# Copyright (c) Zefchain Labs, Inc.
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+import dataclasses
+import unittest
+import serde_types as st
+import msgpack
+import typing
+
+
+@dataclasses.dataclass
+class Point:
+    SCHEMA = {
+        "kind": "struct",
+        "fields": [["x", {"kind": "u32"}], ["class", {"kind": "i8"}]],
+    }  # type: typing.Dict[str, typing.Any]
+    x: st.uint32
+    class_: st.int8
+
+
+@dataclasses.dataclass
+class Wrapper:
+    SCHEMA = {
+        "kind": "newtype_struct",
+        "format": {"kind": "seq", "format": {"kind": "u8"}},
+    }  # type: typing.Dict[str, typing.Any]
+    value: typing.Sequence[st.uint8]
+
+
+class MsgPackTestCase(unittest.TestCase):
+    def test_msgpack_bool(self):
+        self.assertEqual(msgpack.serialize(False, bool), b"\xc2")
+        self.assertEqual(msgpack.serialize(True, bool), b"\xc3")
+        self.assertEqual(msgpack.deserialize(b"\xc3", bool), (True, b""))
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\x01", bool)
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"", bool)
+
+    def test_msgpack_unsigned_integers(self):
+        self.assertEqual(msgpack.serialize(0x7F, st.uint64), b"\x7f")
+        self.assertEqual(msgpack.serialize(0x80, st.uint8), b"\xcc\x80")
+        self.assertEqual(msgpack.serialize(0x100, st.uint16), b"\xcd\x01\x00")
+        self.assertEqual(msgpack.serialize(0x10000, st.uint32), b"\xce\x00\x01\x00\x00")
+        self.assertEqual(
+            msgpack.serialize((1 << 64) - 1, st.uint64), b"\xcf" + b"\xff" * 8
+        )
+        # Any integer family is accepted if the value is in range.
+        self.assertEqual(msgpack.deserialize(b"\xcd\x00\x01", st.uint8), (1, b""))
+        self.assertEqual(msgpack.deserialize(b"\xd0\x05", st.uint32), (5, b""))
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\xcd\x01\x00", st.uint8)
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\xff", st.uint16)
+        with self.assertRaises(st.SerializationError):
+            msgpack.serialize(0x100, st.uint8)
+
+    def test_msgpack_signed_integers(self):
+        self.assertEqual(msgpack.serialize(5, st.int16), b"\x05")
+        self.assertEqual(msgpack.serialize(-1, st.int8), b"\xff")
+        self.assertEqual(msgpack.serialize(-32, st.int64), b"\xe0")
+        self.assertEqual(msgpack.serialize(-33, st.int64), b"\xd0\xdf")
+        self.assertEqual(msgpack.serialize(-129, st.int16), b"\xd1\xff\x7f")
+        self.assertEqual(msgpack.serialize(200, st.int16), b"\xcc\xc8")
+        self.assertEqual(
+            msgpack.serialize(-(1 << 63), st.int64), b"\xd3\x80" + b"\x00" * 7
+        )
+        self.assertEqual(msgpack.deserialize(b"\xe0", st.int64), (-32, b""))
+        self.assertEqual(msgpack.deserialize(b"\xd1\xff\x7f", st.int16), (-129, b""))
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\xcc\xc8", st.int8)
+
+    def test_msgpack_wide_integers(self):
+        with self.assertRaises(st.SerializationError):
+            msgpack.serialize(st.uint128(1), st.uint128)
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\x01", st.int128)
+
+    def test_msgpack_floats(self):
+        self.assertEqual(msgpack.serialize(1.5, st.float32), b"\xca\x3f\xc0\x00\x00")
+        self.assertEqual(msgpack.serialize(1.5, st.float64), b"\xcb\x3f\xf8" + b"\x00" * 6)
+        self.assertEqual(msgpack.deserialize(b"\xca\x3f\xc0\x00\x00", st.float64), (1.5, b""))
+
+    def test_msgpack_str_and_bytes(self):
+        self.assertEqual(msgpack.serialize("abc", str), b"\xa3abc")
+        self.assertEqual(msgpack.serialize("a" * 32, str), b"\xd9\x20" + b"a" * 32)
+        self.assertEqual(msgpack.serialize("a" * 256, str), b"\xda\x01\x00" + b"a" * 256)
+        self.assertEqual(msgpack.serialize(b"ab", bytes), b"\xc4\x02ab")
+        self.assertEqual(msgpack.serialize(b"a" * 256, bytes), b"\xc5\x01\x00" + b"a" * 256)
+        self.assertEqual(msgpack.deserialize(b"\xa3abc", str), ("abc", b""))
+        self.assertEqual(msgpack.deserialize(b"\xc4\x02ab", bytes), (b"ab", b""))
+        self.assertEqual(msgpack.serialize(st.char("x"), st.char), b"\xa1x")
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\xa2xy", st.char)
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\xa3ab", str)
+
+    def test_msgpack_containers(self):
+        self.assertEqual(msgpack.serialize(None, st.unit), b"\xc0")
+        self.assertEqual(
+            msgpack.serialize(None, typing.Optional[st.uint8]), b"\xc0"
+        )
+        self.assertEqual(msgpack.serialize(3, typing.Optional[st.uint8]), b"\x03")
+        self.assertEqual(
+            msgpack.deserialize(b"\xc0", typing.Optional[st.uint8]), (None, b"")
+        )
+        self.assertEqual(
+            msgpack.serialize([1] * 16, typing.Sequence[st.uint8]),
+            b"\xdc\x00\x10" + b"\x01" * 16,
+        )
+        self.assertEqual(
+            msgpack.serialize((1, "a"), typing.Tuple[st.uint8, str]), b"\x92\x01\xa1a"
+        )
+        # Maps keep the order of the dict.
+        self.assertEqual(
+            msgpack.serialize({2: 0, 1: 1}, typing.Dict[st.uint8, st.uint8]),
+            b"\x82\x02\x00\x01\x01",
+        )
+        self.assertEqual(
+            msgpack.deserialize(b"\x82\x02\x00\x01\x01", typing.Dict[st.uint8, st.uint8]),
+            ({2: 0, 1: 1}, b""),
+        )
+
+    def test_msgpack_structs(self):
+        value = Point(x=st.uint32(1), class_=st.int8(-1))
+        encoded = b"\x82\xa1x\x01\xa5class\xff"
+        self.assertEqual(msgpack.serialize(value, Point), encoded)
+        self.assertEqual(msgpack.deserialize(encoded, Point), (value, b""))
+        # Fields may come in any order, or as an array.
+        self.assertEqual(
+            msgpack.deserialize(b"\x82\xa5class\xff\xa1x\x01", Point), (value, b"")
+        )
+        self.assertEqual(msgpack.deserialize(b"\x92\x01\xff", Point), (value, b""))
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\x81\xa1x\x01", Point)
+        with self.assertRaises(st.DeserializationError):
+            msgpack.deserialize(b"\x82\xa1x\x01\xa1x\x01", Point)
+
+        # Newtype structs are encoded as their content.
+        self.assertEqual(msgpack.serialize(Wrapper([1, 2]), Wrapper), b"\x92\x01\x02")
+        self.assertEqual(
+            msgpack.deserialize(b"\x92\x01\x02", Wrapper), (Wrapper([1, 2]), b"")
+        )
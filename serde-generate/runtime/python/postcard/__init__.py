@@ -0,0 +1,178 @@
+# Copyright (c) Zefchain Labs, Inc.
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+import dataclasses
+import collections
+import struct
+import typing
+from copy import copy
+from typing import get_type_hints
+
+import serde_types as st
+import serde_binary as sb
+
+# Maximum length in practice for sequences (e.g. in Java).
+MAX_LENGTH = (1 << 31) - 1
+MAX_U32 = (1 << 32) - 1
+
+_F32 = struct.Struct("<f")
+_F64 = struct.Struct("<d")
+
+
+class PostcardSerializer(sb.BinarySerializer):
+    def __init__(self):
+        super().__init__(output=bytearray(), container_depth_budget=None)
+
+    def serialize_varint(self, value: int):
+        while value >= 0x80:
+            self.output.append((value & 0x7F) | 0x80)
+            value >>= 7
+        self.output.append(value)
+
+    def serialize_zigzag(self, value: int, bits: int):
+        self.serialize_varint(((value << 1) ^ (value >> (bits - 1))) & ((1 << bits) - 1))
+
+    def serialize_u16(self, value: st.uint16):
+        self.serialize_varint(int(value))
+
+    def serialize_u32(self, value: st.uint32):
+        self.serialize_varint(int(value))
+
+    def serialize_u64(self, value: st.uint64):
+        self.serialize_varint(int(value))
+
+    def serialize_u128(self, value: st.uint128):
+        self.serialize_varint(int(value))
+
+    def serialize_i16(self, value: st.int16):
+        self.serialize_zigzag(int(value), 16)
+
+    def serialize_i32(self, value: st.int32):
+        self.serialize_zigzag(int(value), 32)
+
+    def serialize_i64(self, value: st.int64):
+        self.serialize_zigzag(int(value), 64)
+
+    def serialize_i128(self, value: st.int128):
+        self.serialize_zigzag(int(value), 128)
+
+    def serialize_f32(self, value: st.float32):
+        self.output += _F32.pack(value)
+
+    def serialize_f64(self, value: st.float64):
+        self.output += _F64.pack(value)
+
+    def serialize_char(self, value: st.char):
+        self.serialize_str(str(value))
+
+    def serialize_len(self, value: int):
+        if value > MAX_LENGTH:
+            raise st.SerializationError("Length exceeds the maximum supported value.")
+        self.serialize_varint(value)
+
+    def serialize_variant_index(self, value: int):
+        if value > MAX_U32:
+            raise st.SerializationError(
+                "Variant index exceeds the maximum supported value."
+            )
+        self.serialize_varint(value)
+
+    def sort_map_entries(self, offsets: typing.List[int]):
+        pass
+
+
+class PostcardDeserializer(sb.BinaryDeserializer):
+    def __init__(self, content):
+        super().__init__(input=content, container_depth_budget=None)
+
+    def deserialize_varint(self, bits: int) -> int:
+        # Same rules as the Rust implementation: non-minimal encodings are accepted but the
+        # last possible byte must not overflow the integer type.
+        max_bytes = (bits + 6) // 7
+        max_of_last_byte = (1 << (bits % 7)) - 1
+        value = 0
+        for i in range(max_bytes):
+            byte = self.read_byte()
+            value |= (byte & 0x7F) << (7 * i)
+            if byte & 0x80 == 0:
+                if i == max_bytes - 1 and byte > max_of_last_byte:
+                    break
+                return value
+        raise st.DeserializationError(
+            "Overflow while parsing varint-encoded integer value"
+        )
+
+    def deserialize_zigzag(self, bits: int) -> int:
+        value = self.deserialize_varint(bits)
+        return (value >> 1) ^ -(value & 1)
+
+    def deserialize_u16(self) -> st.uint16:
+        return st.uint16(self.deserialize_varint(16))
+
+    def deserialize_u32(self) -> st.uint32:
+        return st.uint32(self.deserialize_varint(32))
+
+    def deserialize_u64(self) -> st.uint64:
+        return st.uint64(self.deserialize_varint(64))
+
+    def deserialize_u128(self) -> st.uint128:
+        return st.uint128(self.deserialize_varint(128))
+
+    def deserialize_i16(self) -> st.int16:
+        return st.int16(self.deserialize_zigzag(16))
+
+    def deserialize_i32(self) -> st.int32:
+        return st.int32(self.deserialize_zigzag(32))
+
+    def deserialize_i64(self) -> st.int64:
+        return st.int64(self.deserialize_zigzag(64))
+
+    def deserialize_i128(self) -> st.int128:
+        return st.int128(self.deserialize_zigzag(128))
+
+    def deserialize_f32(self) -> st.float32:
+        return st.float32(self.unpack(_F32)[0])
+
+    def deserialize_f64(self) -> st.float64:
+        return st.float64(self.unpack(_F64)[0])
+
+    def deserialize_char(self) -> st.char:
+        length = self.deserialize_varint(64)
+        if length > 4:
+            raise st.DeserializationError("Invalid length for a char value")
+        content = self.read(length)
+        try:
+            # Like the Rust implementation, only the first character is retained.
+            return st.char(content.decode()[0])
+        except (UnicodeDecodeError, IndexError):
+            raise st.DeserializationError("Invalid char value:", content)
+
+    def deserialize_len(self) -> int:
+        value = self.deserialize_varint(64)
+        if value > MAX_LENGTH:
+            raise st.DeserializationError("Length exceeds the maximum supported value.")
+        return value
+
+    def deserialize_variant_index(self) -> int:
+        return self.deserialize_varint(32)
+
+    def check_that_key_slices_are_increasing(
+        self, slice1: typing.Tuple[int, int], slice2: typing.Tuple[int, int]
+    ):
+        pass
+
+
+def serialize(obj: typing.Any, obj_type) -> bytes:
+    serializer = PostcardSerializer()
+    serializer.serialize_any(obj, obj_type)
+    return serializer.get_buffer()
+
+
+def deserialize(content: bytes, obj_type) -> typing.Tuple[typing.Any, bytes]:
+    deserializer = PostcardDeserializer(content)
+    value = deserializer.deserialize_any(obj_type)
+    return value, deserializer.get_remaining_buffer()
+
+
+def deserialize_stream(reader: typing.BinaryIO, obj_type) -> typing.Iterator[typing.Any]:
+    return sb.deserialize_stream(reader, PostcardDeserializer, obj_type)
@@ -0,0 +1,162 @@
+# Copyright (c) Zefchain Labs, Inc.
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+from collections import OrderedDict
+import unittest
+import serde_types as st
+import postcard
+import typing
+
+
+class PostcardTestCase(unittest.TestCase):
+    def test_postcard_bool(self):
+        self.assertEqual(postcard.serialize(False, bool), b"\x00")
+        self.assertEqual(postcard.serialize(True, bool), b"\x01")
+        self.assertEqual(postcard.deserialize(b"\x00", bool), (False, b""))
+        self.assertEqual(postcard.deserialize(b"\x01", bool), (True, b""))
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x02", bool)
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"", bool)
+
+    def test_postcard_u8(self):
+        self.assertEqual(postcard.serialize(0x1, st.uint8), b"\x01")
+        self.assertEqual(postcard.serialize(0xFF, st.uint8), b"\xff")
+        self.assertEqual(postcard.deserialize(b"\xff", st.uint8), (255, b""))
+
+    def test_postcard_u16(self):
+        self.assertEqual(postcard.serialize(0x7F, st.uint16), b"\x7f")
+        self.assertEqual(postcard.serialize(0x80, st.uint16), b"\x80\x01")
+        self.assertEqual(postcard.serialize(0xFFFF, st.uint16), b"\xff\xff\x03")
+        self.assertEqual(postcard.deserialize(b"\xff\xff\x03", st.uint16), (65535, b""))
+        # Non-minimal encodings are accepted.
+        self.assertEqual(postcard.deserialize(b"\x81\x00", st.uint16), (1, b""))
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\xff\xff\x04", st.uint16)
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x80\x80\x80\x00", st.uint16)
+
+    def test_postcard_u32(self):
+        self.assertEqual(postcard.serialize(300, st.uint32), b"\xac\x02")
+        self.assertEqual(
+            postcard.serialize((1 << 32) - 1, st.uint32), b"\xff\xff\xff\xff\x0f"
+        )
+        self.assertEqual(
+            postcard.deserialize(b"\xff\xff\xff\xff\x0f", st.uint32),
+            (4294967295, b""),
+        )
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\xff\xff\xff\xff\x10", st.uint32)
+
+    def test_postcard_u64(self):
+        self.assertEqual(
+            postcard.serialize((1 << 64) - 1, st.uint64), b"\xff" * 9 + b"\x01"
+        )
+        self.assertEqual(
+            postcard.deserialize(b"\xff" * 9 + b"\x01", st.uint64),
+            ((1 << 64) - 1, b""),
+        )
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\xff" * 9 + b"\x02", st.uint64)
+
+    def test_postcard_u128(self):
+        self.assertEqual(
+            postcard.serialize(st.uint128((1 << 128) - 1), st.uint128),
+            b"\xff" * 18 + b"\x03",
+        )
+        self.assertEqual(
+            postcard.deserialize(b"\xff" * 18 + b"\x03", st.uint128),
+            (st.uint128((1 << 128) - 1), b""),
+        )
+
+    def test_postcard_i8(self):
+        self.assertEqual(postcard.serialize(-2, st.int8), b"\xfe")
+        self.assertEqual(postcard.deserialize(b"\xff", st.int8), (-1, b""))
+
+    def test_postcard_signed_zigzag(self):
+        self.assertEqual(postcard.serialize(0, st.int16), b"\x00")
+        self.assertEqual(postcard.serialize(-1, st.int16), b"\x01")
+        self.assertEqual(postcard.serialize(1, st.int32), b"\x02")
+        self.assertEqual(postcard.serialize(-64, st.int64), b"\x7f")
+        self.assertEqual(postcard.serialize(64, st.int64), b"\x80\x01")
+        self.assertEqual(postcard.serialize(-32768, st.int16), b"\xff\xff\x03")
+        self.assertEqual(postcard.deserialize(b"\xff\xff\x03", st.int16), (-32768, b""))
+        self.assertEqual(postcard.deserialize(b"\xfe\xff\x03", st.int16), (32767, b""))
+        self.assertEqual(
+            postcard.serialize(st.int128(-(1 << 127)), st.int128),
+            b"\xff" * 18 + b"\x03",
+        )
+        self.assertEqual(
+            postcard.deserialize(b"\x03", st.int128), (st.int128(-2), b"")
+        )
+
+    def test_postcard_f32(self):
+        self.assertEqual(postcard.serialize(0.3, st.float32), b"\x9a\x99\x99\x3e")
+        value, reminder = postcard.deserialize(b"\x9a\x99\x99\x3e", st.float32)
+        self.assertEqual(reminder, b"")
+        self.assertAlmostEqual(value, 0.3)
+
+    def test_postcard_f64(self):
+        self.assertEqual(
+            postcard.serialize(0.000000000003, st.float64),
+            b"\x1a\xdf\xc4\x41\x66\x63\x8a\x3d",
+        )
+
+    def test_postcard_char(self):
+        self.assertEqual(postcard.serialize(st.char("Δ"), st.char), b"\x02\xce\x94")
+        self.assertEqual(
+            postcard.deserialize(b"\x02\xce\x94", st.char), (st.char("Δ"), b"")
+        )
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x00", st.char)
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x05abcde", st.char)
+
+    def test_serialize_bytes(self):
+        self.assertEqual(postcard.serialize(b"", bytes), b"\x00")
+        self.assertEqual(postcard.serialize(b"\x00\x00", bytes), b"\x02\x00\x00")
+        self.assertEqual(
+            postcard.serialize(b"\x00" * 128, bytes), b"\x80\x01" + b"\x00" * 128
+        )
+        self.assertEqual(postcard.deserialize(b"\x00", bytes), (b"", b""))
+
+    def test_serialize_tuple(self):
+        T = typing.Tuple[st.uint8, st.uint16]
+        self.assertEqual(postcard.serialize((0, 1), T), b"\x00\x01")
+        self.assertEqual(postcard.deserialize(b"\x02\x80\x01", T), ((2, 128), b""))
+
+    def test_serialize_option(self):
+        T = typing.Optional[st.uint16]
+        self.assertEqual(postcard.serialize(None, T), b"\x00")
+        self.assertEqual(postcard.serialize(6, T), b"\x01\x06")
+        self.assertEqual(postcard.deserialize(b"\x00", T), (None, b""))
+        self.assertEqual(postcard.deserialize(b"\x01\x02", T), (2, b""))
+        with self.assertRaisesRegex(st.DeserializationError, "Wrong tag.*"):
+            postcard.deserialize(b"\x02\x06", T)
+
+    def test_serialize_sequence(self):
+        Seq = typing.Sequence[st.uint16]
+        self.assertEqual(postcard.serialize([], Seq), b"\x00")
+        self.assertEqual(postcard.serialize([0, 1], Seq), b"\x02\x00\x01")
+        self.assertEqual(
+            postcard.serialize([256] * 256, Seq), b"\x80\x02" + b"\x80\x02" * 256
+        )
+        self.assertEqual(postcard.deserialize(b"\x01\x03", Seq), ([3], b""))
+
+    def test_serialize_str(self):
+        self.assertEqual(postcard.serialize("ABCΔ", str), b"\x05ABC\xce\x94")
+        self.assertEqual(
+            postcard.deserialize(b"\x05ABC\xce\x94A", str), ("ABCΔ", b"A")
+        )
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x03AB", str)
+        with self.assertRaises(st.DeserializationError):
+            postcard.deserialize(b"\x03\x80ab", str)
+
+    def test_serialize_map(self):
+        Map = typing.Dict[st.uint16, st.uint8]
+        m = OrderedDict([(256, 3), (1, 5)])
+        e = postcard.serialize(m, Map)
+        self.assertEqual(e, b"\x02\x80\x02\x03\x01\x05")
+        self.assertEqual((m, b""), postcard.deserialize(e, Map))
+        self.assertEqual((m, b""), postcard.deserialize(b"\x02\x01\x05\x80\x02\x03", Map))
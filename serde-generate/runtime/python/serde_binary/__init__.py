@@ -9,12 +9,149 @@ Note: This internal module is currently only meant to share code between the BCS
 
 import dataclasses
 import collections
-import io
+import datetime
+import enum
+import functools
+import struct
+import types
 import typing
 from typing import get_type_hints
 
 import serde_types as st
 
+_U16 = struct.Struct("<H")
+_U32 = struct.Struct("<I")
+_U64 = struct.Struct("<Q")
+_I8 = struct.Struct("<b")
+_I16 = struct.Struct("<h")
+_I32 = struct.Struct("<i")
+_I64 = struct.Struct("<q")
+_U128 = struct.Struct("<QQ")
+_I128 = struct.Struct("<Qq")
+
+# Integer types with a fixed-width encoding, unless the methods named after the suffix are
+# redefined by a subclass. Sequences and tuples of such integers are encoded in one step.
+_FIXED_WIDTH_INTEGERS = {
+    st.uint8: ("B", "u8"),
+    st.uint16: ("H", "u16"),
+    st.uint32: ("I", "u32"),
+    st.uint64: ("Q", "u64"),
+    st.int8: ("b", "i8"),
+    st.int16: ("h", "i16"),
+    st.int32: ("i", "i32"),
+    st.int64: ("q", "i64"),
+}
+
+# Number of bytes read at once by `deserialize_stream`.
+STREAM_CHUNK_SIZE = 1 << 16
+
+
+class _InputTooShort(st.DeserializationError):
+    """Raised when the input ends in the middle of a value, so that streams may read more
+    input and try again."""
+
+
+def _is_enum(obj_type) -> bool:
+    """Whether `obj_type` is the base class of the variants of an enum."""
+    return hasattr(obj_type, "VARIANTS") and not hasattr(obj_type, "INDEX")
+
+
+def _is_int_enum(obj_type) -> bool:
+    """Whether `obj_type` is an enum without data, generated as an `enum.IntEnum` whose
+    values are the variant indices."""
+    return isinstance(obj_type, type) and issubclass(obj_type, enum.IntEnum)
+
+
+def _is_union_variant(obj_type) -> bool:
+    """Whether `obj_type` is a variant of an enum represented as a union of variant classes
+    (Pydantic tagged unions). The variant index is then encoded by the variant class."""
+    return hasattr(obj_type, "INDEX") and not hasattr(obj_type, "VARIANTS")
+
+
+def _field_names(obj_type) -> typing.Optional[typing.List[str]]:
+    """Names of the encoded fields of a struct or variant class, given as a dataclass or a
+    Pydantic model, or None for other types. The `Literal` tags of Pydantic models are not
+    encoded."""
+    if dataclasses.is_dataclass(obj_type):
+        return [field.name for field in dataclasses.fields(obj_type)]
+    model_fields = getattr(obj_type, "model_fields", None)
+    if not isinstance(model_fields, dict) or _is_enum(obj_type):
+        return None
+    return [
+        name
+        for name, field in model_fields.items()
+        if typing.get_origin(field.annotation) is not typing.Literal
+    ]
+
+
+@functools.lru_cache(maxsize=None)
+def _field_types(
+    obj_type,
+) -> typing.Optional[typing.Tuple[typing.Tuple[str, typing.Any], ...]]:
+    """Names and types of the encoded fields of a struct or variant class (see
+    `_field_names`), computed once per class."""
+    fields = _field_names(obj_type)
+    if fields is None:
+        return None
+    hints = get_type_hints(obj_type)
+    return tuple((field, hints[field]) for field in fields)
+
+
+@functools.lru_cache(maxsize=None)
+def _chain_field(obj_type) -> typing.Optional[str]:
+    """Name of the last field of a struct class if it optionally holds another value of the
+    same class, as in linked lists. Chains of such values are (de)serialized with loops rather
+    than recursive calls, which would exceed the recursion limit of Python on long chains."""
+    fields = _field_types(obj_type)
+    if not fields or hasattr(obj_type, "INDEX"):
+        return None
+    field, field_type = fields[-1]
+    field_type = _strip_annotations(field_type)
+    if _generic_origin(field_type) == typing.Union and getattr(
+        field_type, "__args__"
+    ) == (obj_type, type(None)):
+        return field
+    return None
+
+
+def _fixed_width_formats(obj, base_class, prefix: str) -> typing.Dict[typing.Any, str]:
+    """Formats of the integer types that `obj` encodes with the methods of `base_class`."""
+    formats = {}
+    for obj_type, (fmt, suffix) in _FIXED_WIDTH_INTEGERS.items():
+        name = prefix + suffix
+        if getattr(type(obj), name) is getattr(base_class, name):
+            formats[obj_type] = fmt
+    return formats
+
+
+def _same_fixed_width_format(formats, types) -> typing.Optional[str]:
+    """Format of the items of a tuple type, if they all have the same fixed-width format."""
+    if not types:
+        return None
+    fmt = formats.get(types[0])
+    if fmt is None or any(item_type is not types[0] for item_type in types):
+        return None
+    return fmt
+
+
+def _strip_annotations(obj_type):
+    """Remove the metadata of `typing.Annotated` types, e.g. Pydantic tagged unions."""
+    if hasattr(obj_type, "__metadata__"):
+        return obj_type.__origin__
+    return obj_type
+
+
+def _generic_origin(obj_type):
+    """Origin of a generic type, or None for other types. The builtin spellings `list[T]` and
+    `X | None` of Python 3.10 have the same origins as `typing.Sequence[T]` and
+    `typing.Optional[X]`."""
+    if isinstance(obj_type, getattr(types, "UnionType", ())):
+        return typing.Union
+    origin = getattr(obj_type, "__origin__", None)
+    if origin is list:
+        return collections.abc.Sequence
+    return origin
+
 
 @dataclasses.dataclass
 class BinarySerializer:
@@ -24,11 +161,15 @@ class BinarySerializer:
     index, and how they sort map entries (or not).
     """
 
-    output: io.BytesIO
+    output: bytearray
     container_depth_budget: typing.Optional[int]
     primitive_type_serializer: typing.Mapping = dataclasses.field(init=False)
+    fixed_width_formats: typing.Mapping = dataclasses.field(init=False)
 
     def __post_init__(self):
+        self.fixed_width_formats = _fixed_width_formats(
+            self, BinarySerializer, "serialize_"
+        )
         self.primitive_type_serializer = {
             bool: self.serialize_bool,
             st.uint8: self.serialize_u8,
@@ -47,11 +188,12 @@ class BinarySerializer:
             st.char: self.serialize_char,
             str: self.serialize_str,
             bytes: self.serialize_bytes,
+            datetime.timedelta: self.serialize_duration,
         }
 
     def serialize_bytes(self, value: bytes):
         self.serialize_len(len(value))
-        self.output.write(value)
+        self.output += value
 
     def serialize_str(self, value: str):
         self.serialize_bytes(value.encode())
@@ -59,38 +201,47 @@ class BinarySerializer:
     def serialize_unit(self, value: st.unit):
         pass
 
+    def serialize_duration(self, value: datetime.timedelta):
+        # Same layout as Rust's `struct Duration { secs: u64, nanos: u32 }`.
+        if value < datetime.timedelta(0):
+            raise st.SerializationError("Negative durations are not supported:", value)
+        self.increase_container_depth()
+        self.serialize_u64(st.uint64(value.days * 86400 + value.seconds))
+        self.serialize_u32(st.uint32(value.microseconds * 1000))
+        self.decrease_container_depth()
+
     def serialize_bool(self, value: bool):
-        self.output.write(int(value).to_bytes(1, "little", signed=False))
+        self.output.append(int(value))
 
     def serialize_u8(self, value: st.uint8):
-        self.output.write(int(value).to_bytes(1, "little", signed=False))
+        self.output.append(int(value))
 
     def serialize_u16(self, value: st.uint16):
-        self.output.write(int(value).to_bytes(2, "little", signed=False))
+        self.output += _U16.pack(value)
 
     def serialize_u32(self, value: st.uint32):
-        self.output.write(int(value).to_bytes(4, "little", signed=False))
+        self.output += _U32.pack(value)
 
     def serialize_u64(self, value: st.uint64):
-        self.output.write(int(value).to_bytes(8, "little", signed=False))
+        self.output += _U64.pack(value)
 
     def serialize_u128(self, value: st.uint128):
-        self.output.write(int(value).to_bytes(16, "little", signed=False))
+        self.output += int(value).to_bytes(16, "little", signed=False)
 
     def serialize_i8(self, value: st.uint8):
-        self.output.write(int(value).to_bytes(1, "little", signed=True))
+        self.output += _I8.pack(value)
 
     def serialize_i16(self, value: st.uint16):
-        self.output.write(int(value).to_bytes(2, "little", signed=True))
+        self.output += _I16.pack(value)
 
     def serialize_i32(self, value: st.uint32):
-        self.output.write(int(value).to_bytes(4, "little", signed=True))
+        self.output += _I32.pack(value)
 
     def serialize_i64(self, value: st.uint64):
-        self.output.write(int(value).to_bytes(8, "little", signed=True))
+        self.output += _I64.pack(value)
 
     def serialize_i128(self, value: st.uint128):
-        self.output.write(int(value).to_bytes(16, "little", signed=True))
+        self.output += int(value).to_bytes(16, "little", signed=True)
 
     def serialize_f32(self, value: st.float32):
         raise NotImplementedError
@@ -102,10 +253,10 @@ class BinarySerializer:
         raise NotImplementedError
 
     def get_buffer_offset(self) -> int:
-        return len(self.output.getbuffer())
+        return len(self.output)
 
     def get_buffer(self) -> bytes:
-        return self.output.getvalue()
+        return bytes(self.output)
 
     def increase_container_depth(self):
         if self.container_depth_budget is not None:
@@ -128,33 +279,53 @@ class BinarySerializer:
 
     # noqa: C901
     def serialize_any(self, obj: typing.Any, obj_type):
+        obj_type = _strip_annotations(obj_type)
         if obj_type in self.primitive_type_serializer:
             self.primitive_type_serializer[obj_type](obj)
 
-        elif hasattr(obj_type, "__origin__"):  # Generic type
+        elif _generic_origin(obj_type) is not None:  # Generic type
+            origin = _generic_origin(obj_type)
             types = getattr(obj_type, "__args__")
 
-            if getattr(obj_type, "__origin__") == collections.abc.Sequence:  # Sequence
+            if origin == collections.abc.Sequence:  # Sequence
                 assert len(types) == 1
                 item_type = types[0]
                 self.serialize_len(len(obj))
-                for item in obj:
-                    self.serialize_any(item, item_type)
+                fmt = self.fixed_width_formats.get(item_type)
+                if fmt is not None:
+                    self.output += struct.pack("<%d%s" % (len(obj), fmt), *obj)
+                else:
+                    for item in obj:
+                        self.serialize_any(item, item_type)
 
-            elif getattr(obj_type, "__origin__") == tuple:  # Tuple
+            elif origin == tuple:  # Tuple
                 if len(types) != 1 or types[0] != ():
-                    for i in range(len(obj)):
-                        self.serialize_any(obj[i], types[i])
-
-            elif getattr(obj_type, "__origin__") == typing.Union:  # Option
-                assert len(types) == 2 and types[1] == type(None)
-                if obj is None:
-                    self.output.write(b"\x00")
-                else:
-                    self.output.write(b"\x01")
-                    self.serialize_any(obj, types[0])
+                    fmt = _same_fixed_width_format(self.fixed_width_formats, types)
+                    if fmt is not None and len(obj) == len(types):
+                        self.output += struct.pack("<%d%s" % (len(obj), fmt), *obj)
+                    else:
+                        for i in range(len(obj)):
+                            self.serialize_any(obj[i], types[i])
+
+            elif origin == typing.Union:
+                if types[-1] == type(None):  # Option
+                    # Options of unions are flattened, e.g. `Optional[Union[A, B]]`.
+                    content_type = (
+                        types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                    )
+                    if obj is None:
+                        self.output.append(0)
+                    else:
+                        self.output.append(1)
+                        self.serialize_any(obj, content_type)
+                else:  # Union of enum variants
+                    if obj.__class__ not in types:
+                        raise st.SerializationError(
+                            "Wrong Value for the type", obj, obj_type
+                        )
+                    self.serialize_any(obj, obj.__class__)
 
-            elif getattr(obj_type, "__origin__") == dict:  # Map
+            elif origin == dict:  # Map
                 assert len(types) == 2
                 self.serialize_len(len(obj))
                 offsets = []
@@ -168,9 +339,18 @@ class BinarySerializer:
                 raise st.SerializationError("Unexpected type", obj_type)
 
         else:
-            if not dataclasses.is_dataclass(obj_type):  # Enum
-                if not hasattr(obj_type, "VARIANTS"):
-                    raise st.SerializationError("Unexpected type", obj_type)
+            if _is_int_enum(obj_type):  # Enum without data
+                if not isinstance(obj, obj_type):
+                    raise st.SerializationError(
+                        "Wrong Value for the type", obj, obj_type
+                    )
+                self.serialize_variant_index(int(obj))
+                # Same depth as the (empty) content of a variant.
+                self.increase_container_depth()
+                self.decrease_container_depth()
+                return
+
+            if _is_enum(obj_type):  # Enum
                 if not hasattr(obj, "INDEX"):
                     raise st.SerializationError(
                         "Wrong Value for the type", obj, obj_type
@@ -178,21 +358,45 @@ class BinarySerializer:
                 self.serialize_variant_index(obj.__class__.INDEX)
                 # Proceed to variant
                 obj_type = obj_type.VARIANTS[obj.__class__.INDEX]
-                if not dataclasses.is_dataclass(obj_type):
-                    raise st.SerializationError("Unexpected type", obj_type)
+            elif _is_union_variant(obj_type):
+                self.serialize_variant_index(obj_type.INDEX)
+
+            fields = _field_types(obj_type)
+            if fields is None:
+                raise st.SerializationError("Unexpected type", obj_type)
 
             # pyre-ignore
             if not isinstance(obj, obj_type):
                 raise st.SerializationError("Wrong Value for the type", obj, obj_type)
 
+            chain_field = _chain_field(obj_type)
+            if chain_field is not None:
+                self.serialize_chain(obj, obj_type, fields, chain_field)
+                return
+
             # Content of struct or variant
-            fields = dataclasses.fields(obj_type)
-            types = get_type_hints(obj_type)
             self.increase_container_depth()
-            for field in fields:
-                field_value = obj.__dict__[field.name]
-                field_type = types[field.name]
-                self.serialize_any(field_value, field_type)
+            for field, field_type in fields:
+                self.serialize_any(getattr(obj, field), field_type)
+            self.decrease_container_depth()
+
+    def serialize_chain(self, obj: typing.Any, obj_type, fields, chain_field: str):
+        """Serialize a chain of structs (see `_chain_field`) without recursion. Each struct
+        counts as a container, as if the chain was serialized recursively."""
+        depth = 0
+        while True:
+            self.increase_container_depth()
+            depth += 1
+            for field, field_type in fields[:-1]:
+                self.serialize_any(getattr(obj, field), field_type)
+            obj = getattr(obj, chain_field)
+            if obj is None:
+                self.output.append(0)
+                break
+            if not isinstance(obj, obj_type):
+                raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+            self.output.append(1)
+        for _ in range(depth):
             self.decrease_container_depth()
 
 
@@ -204,11 +408,17 @@ class BinaryDeserializer:
     index, and how they verify the ordering of keys in map entries (or not).
     """
 
-    input: io.BytesIO
+    input: memoryview
     container_depth_budget: typing.Optional[int]
+    offset: int = dataclasses.field(default=0, init=False)
     primitive_type_deserializer: typing.Mapping = dataclasses.field(init=False)
+    fixed_width_formats: typing.Mapping = dataclasses.field(init=False)
 
     def __post_init__(self):
+        self.input = memoryview(self.input).cast("B")
+        self.fixed_width_formats = _fixed_width_formats(
+            self, BinaryDeserializer, "deserialize_"
+        )
         self.primitive_type_deserializer = {
             bool: self.deserialize_bool,
             st.uint8: self.deserialize_u8,
@@ -227,14 +437,37 @@ class BinaryDeserializer:
             st.char: self.deserialize_char,
             str: self.deserialize_str,
             bytes: self.deserialize_bytes,
+            datetime.timedelta: self.deserialize_duration,
         }
 
     def read(self, length: int) -> bytes:
-        value = self.input.read(length)
-        if value is None or len(value) < length:
-            raise st.DeserializationError("Input is too short")
+        end = self.offset + length
+        if end > len(self.input):
+            raise _InputTooShort("Input is too short")
+        value = bytes(self.input[self.offset : end])
+        self.offset = end
         return value
 
+    def read_byte(self) -> int:
+        offset = self.offset
+        if offset >= len(self.input):
+            raise _InputTooShort("Input is too short")
+        self.offset = offset + 1
+        return self.input[offset]
+
+    def unpack(self, fmt: struct.Struct) -> tuple:
+        """Read the values of a fixed-width format."""
+        offset = self.offset
+        end = offset + fmt.size
+        if end > len(self.input):
+            raise _InputTooShort("Input is too short")
+        self.offset = end
+        return fmt.unpack_from(self.input, offset)
+
+    def unpack_array(self, fmt: str, length: int) -> tuple:
+        """Read `length` consecutive values of the given fixed-width format."""
+        return self.unpack(struct.Struct("<%d%s" % (length, fmt)))
+
     def deserialize_bytes(self) -> bytes:
         length = self.deserialize_len()
         return self.read(length)
@@ -249,8 +482,21 @@ class BinaryDeserializer:
     def deserialize_unit(self) -> st.unit:
         pass
 
+    def deserialize_duration(self) -> datetime.timedelta:
+        self.increase_container_depth()
+        secs = int(self.deserialize_u64())
+        nanos = int(self.deserialize_u32())
+        self.decrease_container_depth()
+        # `timedelta` only has microsecond precision.
+        if nanos >= 1_000_000_000 or nanos % 1000 != 0:
+            raise st.DeserializationError("Unsupported duration:", secs, nanos)
+        try:
+            return datetime.timedelta(seconds=secs, microseconds=nanos // 1000)
+        except OverflowError:
+            raise st.DeserializationError("Duration out of range:", secs, nanos)
+
     def deserialize_bool(self) -> bool:
-        b = int.from_bytes(self.read(1), byteorder="little", signed=False)
+        b = self.read_byte()
         if b == 0:
             return False
         elif b == 1:
@@ -259,36 +505,36 @@ class BinaryDeserializer:
             raise st.DeserializationError("Unexpected boolean value:", b)
 
     def deserialize_u8(self) -> st.uint8:
-        return st.uint8(int.from_bytes(self.read(1), byteorder="little", signed=False))
+        return st.uint8(self.read_byte())
 
     def deserialize_u16(self) -> st.uint16:
-        return st.uint16(int.from_bytes(self.read(2), byteorder="little", signed=False))
+        return st.uint16(self.unpack(_U16)[0])
 
     def deserialize_u32(self) -> st.uint32:
-        return st.uint32(int.from_bytes(self.read(4), byteorder="little", signed=False))
+        return st.uint32(self.unpack(_U32)[0])
 
     def deserialize_u64(self) -> st.uint64:
-        return st.uint64(int.from_bytes(self.read(8), byteorder="little", signed=False))
+        return st.uint64(self.unpack(_U64)[0])
 
     def deserialize_u128(self) -> st.uint128:
-        return st.uint128(
-            int.from_bytes(self.read(16), byteorder="little", signed=False)
-        )
+        low, high = self.unpack(_U128)
+        return st.uint128(high << 64 | low)
 
     def deserialize_i8(self) -> st.int8:
-        return st.int8(int.from_bytes(self.read(1), byteorder="little", signed=True))
+        return st.int8(self.unpack(_I8)[0])
 
     def deserialize_i16(self) -> st.int16:
-        return st.int16(int.from_bytes(self.read(2), byteorder="little", signed=True))
+        return st.int16(self.unpack(_I16)[0])
 
     def deserialize_i32(self) -> st.int32:
-        return st.int32(int.from_bytes(self.read(4), byteorder="little", signed=True))
+        return st.int32(self.unpack(_I32)[0])
 
     def deserialize_i64(self) -> st.int64:
-        return st.int64(int.from_bytes(self.read(8), byteorder="little", signed=True))
+        return st.int64(self.unpack(_I64)[0])
 
     def deserialize_i128(self) -> st.int128:
-        return st.int128(int.from_bytes(self.read(16), byteorder="little", signed=True))
+        low, high = self.unpack(_I128)
+        return st.int128(high << 64 | low)
 
     def deserialize_f32(self) -> st.float32:
         raise NotImplementedError
@@ -300,12 +546,10 @@ class BinaryDeserializer:
         raise NotImplementedError
 
     def get_buffer_offset(self) -> int:
-        return self.input.tell()
+        return self.offset
 
     def get_remaining_buffer(self) -> bytes:
-        buf = self.input.getbuffer()
-        offset = self.get_buffer_offset()
-        return bytes(buf[offset:])
+        return bytes(self.input[self.offset :])
 
     def increase_container_depth(self):
         if self.container_depth_budget is not None:
@@ -330,15 +574,20 @@ class BinaryDeserializer:
 
     # noqa
     def deserialize_any(self, obj_type) -> typing.Any:
+        obj_type = _strip_annotations(obj_type)
         if obj_type in self.primitive_type_deserializer:
             return self.primitive_type_deserializer[obj_type]()
 
-        elif hasattr(obj_type, "__origin__"):  # Generic type
+        elif _generic_origin(obj_type) is not None:  # Generic type
+            origin = _generic_origin(obj_type)
             types = getattr(obj_type, "__args__")
-            if getattr(obj_type, "__origin__") == collections.abc.Sequence:  # Sequence
+            if origin == collections.abc.Sequence:  # Sequence
                 assert len(types) == 1
                 item_type = types[0]
                 length = self.deserialize_len()
+                fmt = self.fixed_width_formats.get(item_type)
+                if fmt is not None:
+                    return list(map(item_type, self.unpack_array(fmt, length)))
                 result = []
                 for i in range(0, length):
                     item = self.deserialize_any(item_type)
@@ -346,26 +595,41 @@ class BinaryDeserializer:
 
                 return result
 
-            elif getattr(obj_type, "__origin__") == tuple:  # Tuple
+            elif origin == tuple:  # Tuple
                 result = []
                 if len(types) == 1 and types[0] == ():
                     return tuple()
+                fmt = _same_fixed_width_format(self.fixed_width_formats, types)
+                if fmt is not None:
+                    return tuple(map(types[0], self.unpack_array(fmt, len(types))))
                 for i in range(len(types)):
                     item = self.deserialize_any(types[i])
                     result.append(item)
                 return tuple(result)
 
-            elif getattr(obj_type, "__origin__") == typing.Union:  # Option
-                assert len(types) == 2 and types[1] == type(None)
-                tag = int.from_bytes(self.read(1), byteorder="little", signed=False)
-                if tag == 0:
-                    return None
-                elif tag == 1:
-                    return self.deserialize_any(types[0])
-                else:
-                    raise st.DeserializationError("Wrong tag for Option value")
+            elif origin == typing.Union:
+                if types[-1] == type(None):  # Option
+                    # Options of unions are flattened, e.g. `Optional[Union[A, B]]`.
+                    content_type = (
+                        types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                    )
+                    tag = self.read_byte()
+                    if tag == 0:
+                        return None
+                    elif tag == 1:
+                        return self.deserialize_any(content_type)
+                    else:
+                        raise st.DeserializationError("Wrong tag for Option value")
+                else:  # Union of enum variants
+                    variant_index = self.deserialize_variant_index()
+                    for variant_type in types:
+                        if variant_type.INDEX == variant_index:
+                            return self.deserialize_struct(variant_type)
+                    raise st.DeserializationError(
+                        "Unexpected variant index", variant_index
+                    )
 
-            elif getattr(obj_type, "__origin__") == dict:  # Map
+            elif origin == dict:  # Map
                 assert len(types) == 2
                 length = self.deserialize_len()
                 result = dict()
@@ -391,21 +655,21 @@ class BinaryDeserializer:
                 raise st.DeserializationError("Unexpected type", obj_type)
 
         else:
-            # handle structs
-            if dataclasses.is_dataclass(obj_type):
-                values = []
-                fields = dataclasses.fields(obj_type)
-                typing_hints = get_type_hints(obj_type)
+            # handle enums without data
+            if _is_int_enum(obj_type):
+                variant_index = self.deserialize_variant_index()
+                try:
+                    value = obj_type(variant_index)
+                except ValueError:
+                    raise st.DeserializationError(
+                        "Unexpected variant index", variant_index
+                    )
                 self.increase_container_depth()
-                for field in fields:
-                    field_type = typing_hints[field.name]
-                    field_value = self.deserialize_any(field_type)
-                    values.append(field_value)
                 self.decrease_container_depth()
-                return obj_type(*values)
+                return value
 
             # handle variant
-            elif hasattr(obj_type, "VARIANTS"):
+            elif _is_enum(obj_type):
                 variant_index = self.deserialize_variant_index()
                 if variant_index not in range(len(obj_type.VARIANTS)):
                     raise st.DeserializationError(
@@ -414,5 +678,94 @@ class BinaryDeserializer:
                 new_type = obj_type.VARIANTS[variant_index]
                 return self.deserialize_any(new_type)
 
-            else:
-                raise st.DeserializationError("Unexpected type", obj_type)
+            # handle variant of a union
+            elif _is_union_variant(obj_type):
+                variant_index = self.deserialize_variant_index()
+                if variant_index != obj_type.INDEX:
+                    raise st.DeserializationError(
+                        "Unexpected variant index", variant_index
+                    )
+
+            # handle structs
+            return self.deserialize_struct(obj_type)
+
+    def deserialize_struct(self, obj_type) -> typing.Any:
+        fields = _field_types(obj_type)
+        if fields is None:
+            raise st.DeserializationError("Unexpected type", obj_type)
+        chain_field = _chain_field(obj_type)
+        if chain_field is not None:
+            return self.deserialize_chain(obj_type, fields, chain_field)
+        values = {}
+        self.increase_container_depth()
+        for field, field_type in fields:
+            values[field] = self.deserialize_any(field_type)
+        self.decrease_container_depth()
+        return obj_type(**values)
+
+    def deserialize_chain(self, obj_type, fields, chain_field: str) -> typing.Any:
+        """Deserialize a chain of structs (see `_chain_field`) without recursion. Each struct
+        counts as a container, as if the chain was deserialized recursively."""
+        nodes = []
+        while True:
+            self.increase_container_depth()
+            values = {}
+            for field, field_type in fields[:-1]:
+                values[field] = self.deserialize_any(field_type)
+            nodes.append(values)
+            tag = self.read_byte()
+            if tag == 0:
+                break
+            if tag != 1:
+                raise st.DeserializationError("Wrong tag for Option value")
+        for _ in nodes:
+            self.decrease_container_depth()
+        value = None
+        for values in reversed(nodes):
+            values[chain_field] = value
+            value = obj_type(**values)
+        return value
+
+
+def deserialize_stream(
+    reader: typing.BinaryIO, new_deserializer, obj_type
+) -> typing.Iterator[typing.Any]:
+    """Deserialize the sequence held by the newtype struct `obj_type` from the file-like object
+    `reader`, yielding the items one at a time. Only the unread input and the current item are
+    kept in memory. Once all the items were read, the input must be exhausted.
+
+    `new_deserializer` creates a deserializer for the given bytes."""
+    fields = _field_types(obj_type)
+    if fields is None or len(fields) != 1:
+        raise st.DeserializationError("Unexpected type", obj_type)
+    item_type = getattr(_strip_annotations(fields[0][1]), "__args__")[0]
+    input = b""
+    at_end = False
+    length = None
+    count = 0
+    while True:
+        deserializer = new_deserializer(input)
+        # The items are nested in `obj_type`.
+        deserializer.increase_container_depth()
+        # Offset of the first value that was not entirely read.
+        start = 0
+        try:
+            if length is None:
+                length = deserializer.deserialize_len()
+                start = deserializer.get_buffer_offset()
+            while count < length:
+                item = deserializer.deserialize_any(item_type)
+                start = deserializer.get_buffer_offset()
+                count += 1
+                yield item
+            if start < len(input) or reader.read(1):
+                raise st.DeserializationError("Some input bytes were not read")
+            return
+        except _InputTooShort:
+            if at_end:
+                raise
+        # Values larger than a chunk are read again after each chunk, so read more input
+        # every time.
+        chunk = reader.read(max(STREAM_CHUNK_SIZE, len(input)))
+        at_end = not chunk
+        input = input[start:] + chunk
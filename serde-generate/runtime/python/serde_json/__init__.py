@@ -0,0 +1,269 @@
+# Copyright (c) Zefchain Labs, Inc.
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+"""
+Module converting generated classes to and from JSON, e.g. for logging and debugging.
+
+* Structs and the content of variants are objects whose keys are the Python field names.
+* Enums are externally tagged: `"Variant"` for variants without fields, `{"Variant": {..}}` otherwise.
+* Bytes are base64 strings. Integers of all sizes are JSON integers. Tuples are lists.
+* Maps are objects if keys are strings, lists of `[key, value]` pairs otherwise.
+"""
+
+import base64
+import collections
+import datetime
+import json
+import typing
+from typing import get_type_hints
+
+import serde_types as st
+import serde_binary as sb
+
+_INTEGER_RANGES = {
+    st.uint8: (0, 1 << 8),
+    st.uint16: (0, 1 << 16),
+    st.uint32: (0, 1 << 32),
+    st.uint64: (0, 1 << 64),
+    st.uint128: (0, 1 << 128),
+    st.int8: (-(1 << 7), 1 << 7),
+    st.int16: (-(1 << 15), 1 << 15),
+    st.int32: (-(1 << 31), 1 << 31),
+    st.int64: (-(1 << 63), 1 << 63),
+    st.int128: (-(1 << 127), 1 << 127),
+}
+
+
+def serialize(obj: typing.Any, obj_type) -> str:
+    return json.dumps(to_value(obj, obj_type))
+
+
+def deserialize(content: str, obj_type) -> typing.Any:
+    try:
+        value = json.loads(content)
+    except ValueError as e:
+        raise st.DeserializationError("Invalid JSON:", e)
+    return from_value(value, obj_type)
+
+
+def _variant_name(variant_type, enum_type) -> str:
+    """Name of a variant, given its class and either the base class of the enum or None for
+    Pydantic tagged unions, whose variants hold their name in a `kind` field."""
+    if enum_type is None:
+        return variant_type.model_fields["kind"].default
+    return variant_type.__name__[len(enum_type.__name__) + 2 :]
+
+
+# noqa: C901
+def to_value(obj: typing.Any, obj_type) -> typing.Any:
+    """Convert `obj` of type `obj_type` into a JSON value made of dicts, lists, strings,
+    numbers, booleans, and None."""
+    obj_type = sb._strip_annotations(obj_type)
+    if obj_type == st.unit:
+        return None
+    if obj_type in _INTEGER_RANGES:
+        return int(obj)
+    if obj_type in (bool, str):
+        return obj
+    if obj_type in (st.float32, st.float64):
+        return float(obj)
+    if obj_type == st.char:
+        return str(obj)
+    if obj_type == bytes:
+        return base64.b64encode(obj).decode()
+    if obj_type == datetime.timedelta:
+        return {
+            "secs": obj.days * 86400 + obj.seconds,
+            "nanos": obj.microseconds * 1000,
+        }
+
+    origin = sb._generic_origin(obj_type)
+    if origin is not None:  # Generic type
+        types = getattr(obj_type, "__args__")
+
+        if origin == collections.abc.Sequence:
+            return [to_value(item, types[0]) for item in obj]
+
+        if origin == tuple:
+            if len(types) == 1 and types[0] == ():
+                return []
+            return [to_value(obj[i], types[i]) for i in range(len(obj))]
+
+        if origin == typing.Union:
+            if types[-1] == type(None):  # Option
+                if obj is None:
+                    return None
+                content_type = types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                return to_value(obj, content_type)
+            if obj.__class__ not in types:  # Union of enum variants
+                raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+            return _variant_to_value(obj, obj.__class__, None)
+
+        if origin == dict:
+            if types[0] == str:
+                return {key: to_value(value, types[1]) for key, value in obj.items()}
+            return [
+                [to_value(key, types[0]), to_value(value, types[1])]
+                for key, value in obj.items()
+            ]
+
+        raise st.SerializationError("Unexpected type", obj_type)
+
+    if sb._is_int_enum(obj_type):
+        if not isinstance(obj, obj_type):
+            raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+        return obj.name
+    if sb._is_enum(obj_type):
+        if not isinstance(obj, obj_type) or not hasattr(obj, "INDEX"):
+            raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+        return _variant_to_value(obj, obj.__class__, obj_type)
+    if sb._is_union_variant(obj_type):
+        return _variant_to_value(obj, obj_type, None)
+    return _struct_to_value(obj, obj_type)
+
+
+def _variant_to_value(obj: typing.Any, variant_type, enum_type) -> typing.Any:
+    name = _variant_name(variant_type, enum_type)
+    content = _struct_to_value(obj, variant_type)
+    if not content:
+        return name
+    return {name: content}
+
+
+def _struct_to_value(obj: typing.Any, obj_type) -> typing.Dict[str, typing.Any]:
+    fields = sb._field_names(obj_type)
+    if fields is None:
+        raise st.SerializationError("Unexpected type", obj_type)
+    # pyre-ignore
+    if not isinstance(obj, obj_type):
+        raise st.SerializationError("Wrong Value for the type", obj, obj_type)
+    types = get_type_hints(obj_type)
+    return {field: to_value(getattr(obj, field), types[field]) for field in fields}
+
+
+def _expect(value: typing.Any, expected_type, obj_type) -> typing.Any:
+    if not isinstance(value, expected_type) or (
+        isinstance(value, bool) and expected_type != bool
+    ):
+        raise st.DeserializationError("Unexpected JSON value for the type", value, obj_type)
+    return value
+
+
+# noqa: C901
+def from_value(value: typing.Any, obj_type) -> typing.Any:
+    """Convert a JSON value into an object of type `obj_type`, as done by `deserialize`."""
+    obj_type = sb._strip_annotations(obj_type)
+    if obj_type == st.unit:
+        if value is not None:
+            raise st.DeserializationError("Unexpected JSON value for the type", value, obj_type)
+        return None
+    if obj_type in _INTEGER_RANGES:
+        low, high = _INTEGER_RANGES[obj_type]
+        if not low <= _expect(value, int, obj_type) < high:
+            raise st.DeserializationError("Integer out of range:", value, obj_type)
+        return obj_type(value)
+    if obj_type in (bool, str):
+        return _expect(value, obj_type, obj_type)
+    if obj_type in (st.float32, st.float64):
+        return obj_type(_expect(value, (int, float), obj_type))
+    if obj_type == st.char:
+        try:
+            return st.char(_expect(value, str, obj_type))
+        except ValueError as e:
+            raise st.DeserializationError(str(e))
+    if obj_type == bytes:
+        try:
+            return base64.b64decode(_expect(value, str, obj_type), validate=True)
+        except ValueError:
+            raise st.DeserializationError("Invalid base64 string:", value)
+    if obj_type == datetime.timedelta:
+        content = _expect(value, dict, obj_type)
+        secs = from_value(content.get("secs"), st.uint64)
+        nanos = from_value(content.get("nanos"), st.uint32)
+        if nanos >= 1_000_000_000 or nanos % 1000 != 0:
+            raise st.DeserializationError("Unsupported duration:", secs, nanos)
+        return datetime.timedelta(seconds=int(secs), microseconds=int(nanos) // 1000)
+
+    origin = sb._generic_origin(obj_type)
+    if origin is not None:  # Generic type
+        types = getattr(obj_type, "__args__")
+
+        if origin == collections.abc.Sequence:
+            return [from_value(item, types[0]) for item in _expect(value, list, obj_type)]
+
+        if origin == tuple:
+            items = _expect(value, list, obj_type)
+            if len(types) == 1 and types[0] == ():
+                types = ()
+            if len(items) != len(types):
+                raise st.DeserializationError("Wrong number of items for the type", value, obj_type)
+            return tuple(from_value(item, types[i]) for i, item in enumerate(items))
+
+        if origin == typing.Union:
+            if types[-1] == type(None):  # Option
+                if value is None:
+                    return None
+                content_type = types[0] if len(types) == 2 else typing.Union[types[:-1]]
+                return from_value(value, content_type)
+            # Union of enum variants
+            variants = {_variant_name(variant, None): variant for variant in types}
+            return _variant_from_value(value, variants, obj_type)
+
+        if origin == dict:
+            if types[0] == str:
+                entries = _expect(value, dict, obj_type).items()
+            else:
+                entries = []
+                for entry in _expect(value, list, obj_type):
+                    if not isinstance(entry, list) or len(entry) != 2:
+                        raise st.DeserializationError("Expected a pair in map:", entry)
+                    entries.append(entry)
+            return {
+                from_value(key, types[0]): from_value(item, types[1])
+                for key, item in entries
+            }
+
+        raise st.DeserializationError("Unexpected type", obj_type)
+
+    if sb._is_int_enum(obj_type):
+        name = _expect(value, str, obj_type)
+        if name not in obj_type.__members__:
+            raise st.DeserializationError("Unexpected variant name", name, obj_type)
+        return obj_type[name]
+    if sb._is_enum(obj_type):
+        variants = {
+            _variant_name(variant, obj_type): variant for variant in obj_type.VARIANTS
+        }
+        return _variant_from_value(value, variants, obj_type)
+    if sb._is_union_variant(obj_type):
+        variants = {_variant_name(obj_type, None): obj_type}
+        return _variant_from_value(value, variants, obj_type)
+    return _struct_from_value(_expect(value, dict, obj_type), obj_type)
+
+
+def _variant_from_value(value: typing.Any, variants, obj_type) -> typing.Any:
+    if isinstance(value, str):
+        name, content = value, {}
+    elif isinstance(value, dict) and len(value) == 1:
+        ((name, content),) = value.items()
+    else:
+        raise st.DeserializationError("Unexpected JSON value for the type", value, obj_type)
+    if name not in variants:
+        raise st.DeserializationError("Unexpected variant name", name, obj_type)
+    return _struct_from_value(_expect(content, dict, obj_type), variants[name])
+
+
+def _struct_from_value(content: typing.Dict[str, typing.Any], obj_type) -> typing.Any:
+    fields = sb._field_names(obj_type)
+    if fields is None:
+        raise st.DeserializationError("Unexpected type", obj_type)
+    unknown = set(content) - set(fields)
+    if unknown:
+        raise st.DeserializationError("Unexpected fields for the type", sorted(unknown), obj_type)
+    types = get_type_hints(obj_type)
+    values = {}
+    for field in fields:
+        if field not in content:
+            raise st.DeserializationError("Missing field for the type", field, obj_type)
+        values[field] = from_value(content[field], types[field])
+    return obj_type(**values)
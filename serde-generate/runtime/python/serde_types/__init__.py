@@ -59,6 +59,9 @@ class char:
 
 unit = typing.Type[None]
 
+# Result of the `match` methods of the generated enums.
+MatchResult = typing.TypeVar("MatchResult")
+
 bool = bool
 int8 = np.int8
 int16 = np.int16
@@ -72,3 +75,24 @@ uint64 = np.uint64
 
 float32 = np.float32
 float64 = np.float64
+
+
+def walk_schema(
+    schema: typing.Mapping[str, typing.Any],
+    visitor: typing.Callable[[typing.Mapping[str, typing.Any], typing.List[str]], None],
+    path: typing.Optional[typing.List[str]] = None,
+) -> None:
+    """Call `visitor` on each node of a `SCHEMA` attribute of a generated class in depth-first
+    order, together with its path: field or variant names, positions in tuples, and the keys
+    `format`, `key`, or `value`. References to other containers are not followed."""
+    path = path or []
+    visitor(schema, path)
+    for key in ("format", "key", "value"):
+        if key in schema:
+            walk_schema(schema[key], visitor, path + [key])
+    for index, child in enumerate(schema.get("formats", [])):
+        walk_schema(child, visitor, path + [str(index)])
+    for name, child in schema.get("fields", []):
+        walk_schema(child, visitor, path + [name])
+    for _index, name, child in schema.get("variants", []):
+        walk_schema(child, visitor, path + [name])
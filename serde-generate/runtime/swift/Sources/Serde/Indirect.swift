@@ -14,3 +14,5 @@ public enum Indirect<T>: Hashable where T: Hashable {
         set { self = .wrapped(newValue) }
     }
 }
+
+extension Indirect: Sendable where T: Sendable {}
@@ -2,7 +2,7 @@
 
 import Foundation
 
-public struct Int128: Hashable {
+public struct Int128: Hashable, Sendable {
     public var high: Int64
     public var low: UInt64
 
@@ -13,3 +13,5 @@ public struct Tuple2<T0: Hashable, T1: Hashable>: Hashable {
         self.field1 = field1
     }
 }
+
+extension Tuple2: Sendable where T0: Sendable, T1: Sendable {}
@@ -15,3 +15,5 @@ public struct Tuple3<T0: Hashable, T1: Hashable, T2: Hashable>: Hashable {
         self.field2 = field2
     }
 }
+
+extension Tuple3: Sendable where T0: Sendable, T1: Sendable, T2: Sendable {}
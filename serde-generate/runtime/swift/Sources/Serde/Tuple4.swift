@@ -17,3 +17,5 @@ public struct Tuple4<T0: Hashable, T1: Hashable, T2: Hashable, T3: Hashable>: Ha
         self.field3 = field3
     }
 }
+
+extension Tuple4: Sendable where T0: Sendable, T1: Sendable, T2: Sendable, T3: Sendable {}
@@ -19,3 +19,5 @@ public struct Tuple5<T0: Hashable, T1: Hashable, T2: Hashable, T3: Hashable, T4:
         self.field4 = field4
     }
 }
+
+extension Tuple5: Sendable where T0: Sendable, T1: Sendable, T2: Sendable, T3: Sendable, T4: Sendable {}
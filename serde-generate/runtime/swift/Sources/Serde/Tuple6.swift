@@ -21,3 +21,5 @@ public struct Tuple6<T0: Hashable, T1: Hashable, T2: Hashable, T3: Hashable, T4:
         self.field5 = field5
     }
 }
+
+extension Tuple6: Sendable where T0: Sendable, T1: Sendable, T2: Sendable, T3: Sendable, T4: Sendable, T5: Sendable {}
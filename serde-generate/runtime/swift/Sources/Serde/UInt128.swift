@@ -2,7 +2,7 @@
 
 import Foundation
 
-public struct UInt128: Hashable {
+public struct UInt128: Hashable, Sendable {
     public var high: UInt64
     public var low: UInt64
 
@@ -4,4 +4,4 @@ import Foundation
 
 // Defining Unit as () creates all sorts of warnings in the generated code.
 
-public struct Unit: Hashable {}
+public struct Unit: Hashable, Sendable {}
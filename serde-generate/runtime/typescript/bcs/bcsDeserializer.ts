@@ -7,14 +7,16 @@ import { BinaryDeserializer } from "../serde/binaryDeserializer.ts";
 
 export class BcsDeserializer extends BinaryDeserializer {
   private static readonly MAX_UINT_32 = 2 ** 32 - 1;
+  private static readonly MAX_LENGTH = 2 ** 31 - 1;
 
   public deserializeUleb128AsU32(): number {
     let value = 0;
     for (let shift = 0; shift < 32; shift += 7) {
       const x = this.deserializeU8();
       const digit = x & 0x7f;
-      value = value | (digit << shift);
-      if (value < 0 || value > BcsDeserializer.MAX_UINT_32) {
+      // Avoid bitwise operators here: they would overflow into negative 32-bit integers.
+      value += digit * 2 ** shift;
+      if (value > BcsDeserializer.MAX_UINT_32) {
         throw new Error("Overflow while parsing uleb128-encoded uint32 value");
       }
       if (digit == x) {
@@ -28,7 +30,11 @@ export class BcsDeserializer extends BinaryDeserializer {
   }
 
   deserializeLen(): number {
-    return this.deserializeUleb128AsU32();
+    const value = this.deserializeUleb128AsU32();
+    if (value > BcsDeserializer.MAX_LENGTH) {
+      throw new Error("Length is too large");
+    }
+    return value;
   }
 
   public deserializeVariantIndex(): number {
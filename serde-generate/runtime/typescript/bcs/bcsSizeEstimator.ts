@@ -0,0 +1,25 @@
+/**
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+import { BinarySizeEstimator } from "../serde/sizeEstimator.ts";
+
+export class BcsSizeEstimator extends BinarySizeEstimator {
+  public u32AsUleb128Size(value: number): number {
+    let size = 1;
+    while (value >>> 7 != 0) {
+      size += 1;
+      value = value >>> 7;
+    }
+    return size;
+  }
+
+  lenSize(value: number): number {
+    return this.u32AsUleb128Size(value);
+  }
+
+  public variantIndexSize(value: number): number {
+    return this.u32AsUleb128Size(value);
+  }
+}
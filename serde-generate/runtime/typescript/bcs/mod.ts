@@ -5,3 +5,4 @@
 
 export * from "./bcsSerializer.ts";
 export * from "./bcsDeserializer.ts";
+export * from "./bcsSizeEstimator.ts";
@@ -0,0 +1,16 @@
+/**
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+import { BinarySizeEstimator } from "../serde/sizeEstimator.ts";
+
+export class BincodeSizeEstimator extends BinarySizeEstimator {
+  lenSize(_value: number): number {
+    return 8;
+  }
+
+  public variantIndexSize(_value: number): number {
+    return 4;
+  }
+}
@@ -5,3 +5,4 @@
 
 export { BincodeSerializer } from "./bincodeSerializer.ts";
 export { BincodeDeserializer } from "./bincodeDeserializer.ts";
+export { BincodeSizeEstimator } from "./bincodeSizeEstimator.ts";
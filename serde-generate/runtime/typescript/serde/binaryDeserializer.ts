@@ -106,7 +106,7 @@ export abstract class BinaryDeserializer implements Deserializer {
   }
 
   public deserializeI64(): bigint {
-    const low = this.deserializeI32();
+    const low = this.deserializeU32();
     const high = this.deserializeI32();
 
     // combine the two 32-bit values and return (little endian)
@@ -115,7 +115,7 @@ export abstract class BinaryDeserializer implements Deserializer {
   }
 
   public deserializeI128(): bigint {
-    const low = this.deserializeI64();
+    const low = this.deserializeU64();
     const high = this.deserializeI64();
 
     // combine the two 64-bit values and return (little endian)
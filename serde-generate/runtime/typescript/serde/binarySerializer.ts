@@ -87,7 +87,7 @@ export abstract class BinarySerializer implements Serializer {
     this.serializeWithFunction(DataView.prototype.setUint32, 4, value);
   }
 
-  public serializeU64(value: BigInt | number): void {
+  public serializeU64(value: bigint | number): void {
     const low = BigInt(value.toString()) & BinarySerializer.BIG_32Fs;
     const high = BigInt(value.toString()) >> BinarySerializer.BIG_32;
 
@@ -96,7 +96,7 @@ export abstract class BinarySerializer implements Serializer {
     this.serializeU32(Number(high));
   }
 
-  public serializeU128(value: BigInt | number): void {
+  public serializeU128(value: bigint | number): void {
     const low = BigInt(value.toString()) & BinarySerializer.BIG_64Fs;
     const high = BigInt(value.toString()) >> BinarySerializer.BIG_64;
 
@@ -160,6 +160,8 @@ export abstract class BinarySerializer implements Serializer {
     throw new Error("Method serializeChar not implemented.");
   }
 
+  // `number` values cannot hold the payload of a single-precision NaN: signaling NaNs may be
+  // written as quiet ones.
   public serializeF32(value: number): void {
     const bytes = 4;
     this.ensureBufferWillHandleSize(bytes);
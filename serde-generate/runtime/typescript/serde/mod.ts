@@ -8,3 +8,6 @@ export * from "./serializer.ts";
 export * from "./deserializer.ts";
 export * from "./binarySerializer.ts";
 export * from "./binaryDeserializer.ts";
+export * from "./valueMap.ts";
+export * from "./sizeEstimator.ts";
+export * from "./schema.ts";
@@ -0,0 +1,49 @@
+/**
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+/**
+ * Description of a container, a variant, or a format, attached to generated classes as
+ * `SCHEMA` (see `CodeGeneratorConfig::with_runtime_schema` in serde-generate).
+ * Other containers are referenced by name with `{kind: "ref", name: "MyType"}`.
+ */
+export interface Schema {
+  kind: string;
+  name?: string;
+  format?: Schema;
+  formats?: Schema[];
+  key?: Schema;
+  value?: Schema;
+  size?: number;
+  fields?: [string, Schema][];
+  variants?: [number, string, Schema][];
+}
+
+/**
+ * Call `visitor` on each node of `schema` in depth-first order, together with its path:
+ * field or variant names, positions in tuples, and the keys `format`, `key`, or `value`.
+ * References to other containers are not followed.
+ */
+export function walkSchema(
+  schema: Schema,
+  visitor: (schema: Schema, path: string[]) => void,
+  path: string[] = [],
+): void {
+  visitor(schema, path);
+  for (const key of ["format", "key", "value"] as const) {
+    const child = schema[key];
+    if (child !== undefined) {
+      walkSchema(child, visitor, [...path, key]);
+    }
+  }
+  schema.formats?.forEach((child, index) =>
+    walkSchema(child, visitor, [...path, index.toString()])
+  );
+  schema.fields?.forEach(([name, child]) =>
+    walkSchema(child, visitor, [...path, name])
+  );
+  schema.variants?.forEach(([_index, name, child]) =>
+    walkSchema(child, visitor, [...path, name])
+  );
+}
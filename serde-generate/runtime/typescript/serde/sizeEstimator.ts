@@ -0,0 +1,57 @@
+/**
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+/**
+ * Computes the number of bytes used by a serializer for the values whose encoding
+ * depends on the format (e.g. lengths and variant indices).
+ */
+export interface SizeEstimator {
+  strSize(value: string): number;
+
+  bytesSize(value: Uint8Array): number;
+
+  charSize(value: string): number;
+
+  lenSize(value: number): number;
+
+  variantIndexSize(value: number): number;
+}
+
+export abstract class BinarySizeEstimator implements SizeEstimator {
+  abstract lenSize(value: number): number;
+
+  abstract variantIndexSize(value: number): number;
+
+  // Size of the UTF-8 encoding used by `TextEncoder`.
+  public static utf8Size(value: string): number {
+    let size = 0;
+    for (const c of value) {
+      const codePoint = c.codePointAt(0)!;
+      if (codePoint < 0x80) {
+        size += 1;
+      } else if (codePoint < 0x800) {
+        size += 2;
+      } else if (codePoint < 0x10000) {
+        size += 3;
+      } else {
+        size += 4;
+      }
+    }
+    return size;
+  }
+
+  public strSize(value: string): number {
+    const size = BinarySizeEstimator.utf8Size(value);
+    return this.lenSize(size) + size;
+  }
+
+  public bytesSize(value: Uint8Array): number {
+    return this.lenSize(value.length) + value.length;
+  }
+
+  public charSize(value: string): number {
+    return BinarySizeEstimator.utf8Size(value);
+  }
+}
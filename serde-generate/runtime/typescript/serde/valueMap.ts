@@ -0,0 +1,91 @@
+/**
+ * Copyright (c) Zefchain Labs, Inc.
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ */
+
+/**
+ * Compute a string that identifies a deserialized value structurally.
+ * Class instances (structs and enum variants) include their class name so that
+ * distinct variants with the same fields are not confused.
+ */
+export function canonicalKey(value: unknown): string {
+  if (value === null || value === undefined) {
+    return "null";
+  }
+  switch (typeof value) {
+    case "bigint":
+      return value.toString() + "n";
+    case "number":
+    case "boolean":
+      return String(value);
+    case "string":
+      return JSON.stringify(value);
+  }
+  if (value instanceof Uint8Array) {
+    return "b[" + Array.from(value).join(",") + "]";
+  }
+  if (Array.isArray(value)) {
+    return "[" + value.map(canonicalKey).join(",") + "]";
+  }
+  if (value instanceof Map) {
+    const entries = Array.from(value.entries())
+      .map(([k, v]) => canonicalKey(k) + ":" + canonicalKey(v))
+      .sort();
+    return "m{" + entries.join(",") + "}";
+  }
+  const obj = value as Record<string, unknown>;
+  const fields = Object.keys(obj)
+    .map((k) => JSON.stringify(k) + ":" + canonicalKey(obj[k]));
+  return (value as object).constructor.name + "{" + fields.join(",") + "}";
+}
+
+/**
+ * A `Map` whose keys are compared structurally rather than by reference.
+ * Used for maps keyed by containers, tuples, arrays or bytes.
+ */
+export class ValueMap<K, V> extends Map<K, V> {
+  private readonly canonicalKeys: Map<string, K> = new Map();
+
+  constructor(entries?: Iterable<readonly [K, V]>) {
+    super();
+    if (entries) {
+      for (const [key, value] of entries) {
+        this.set(key, value);
+      }
+    }
+  }
+
+  get(key: K): V | undefined {
+    const stored = this.canonicalKeys.get(canonicalKey(key));
+    return stored === undefined ? undefined : super.get(stored);
+  }
+
+  has(key: K): boolean {
+    return this.canonicalKeys.has(canonicalKey(key));
+  }
+
+  set(key: K, value: V): this {
+    const ckey = canonicalKey(key);
+    const stored = this.canonicalKeys.get(ckey);
+    if (stored === undefined) {
+      this.canonicalKeys.set(ckey, key);
+      return super.set(key, value);
+    }
+    return super.set(stored, value);
+  }
+
+  delete(key: K): boolean {
+    const ckey = canonicalKey(key);
+    const stored = this.canonicalKeys.get(ckey);
+    if (stored === undefined) {
+      return false;
+    }
+    this.canonicalKeys.delete(ckey);
+    return super.delete(stored);
+  }
+
+  clear(): void {
+    this.canonicalKeys.clear();
+    super.clear();
+  }
+}
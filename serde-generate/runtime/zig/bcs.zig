@@ -0,0 +1,21 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Binary Canonical Serialization (BCS).
+
+const serde = @import("serde.zig");
+
+/// Maximum length supported for BCS sequences and maps.
+pub const max_length: usize = (1 << 31) - 1;
+pub const max_container_depth: usize = 500;
+
+const encoding = serde.Encoding{
+    .uleb128 = true,
+    .max_length = max_length,
+    .max_container_depth = max_container_depth,
+    .canonical_maps = true,
+    .chars = false,
+};
+
+pub const Serializer = serde.Serializer(encoding);
+pub const Deserializer = serde.Deserializer(encoding);
@@ -0,0 +1,20 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Bincode, with the default configuration of the Rust crate (version 1).
+
+const serde = @import("serde.zig");
+
+/// Maximum length supported for bincode sequences and maps.
+pub const max_length: usize = (1 << 31) - 1;
+
+const encoding = serde.Encoding{
+    .uleb128 = false,
+    .max_length = max_length,
+    .max_container_depth = null,
+    .canonical_maps = false,
+    .chars = true,
+};
+
+pub const Serializer = serde.Serializer(encoding);
+pub const Deserializer = serde.Deserializer(encoding);
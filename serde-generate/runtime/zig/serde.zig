@@ -0,0 +1,424 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Runtime of the Zig code generated by serde-generate: binary serializers and deserializers
+//! over slices, parameterized by the details of each encoding (see `bcs.zig` and
+//! `bincode.zig`).
+//!
+//! Deserialized values own their slices and pointers, which are allocated with the allocator
+//! given to the deserializer. Memory is not released when deserialization fails midway, so an
+//! arena allocator is recommended.
+
+const std = @import("std");
+
+pub const Error = error{
+    OutOfMemory,
+    EndOfInput,
+    RemainingInput,
+    InvalidBool,
+    InvalidUtf8,
+    InvalidChar,
+    InvalidUleb128,
+    LengthTooLarge,
+    TooManyNestedContainers,
+    UnknownVariant,
+    UnorderedMapKeys,
+    Unsupported,
+};
+
+/// Entry of a map. Maps are represented as slices of entries.
+pub fn Entry(comptime K: type, comptime V: type) type {
+    return struct {
+        key: K,
+        value: V,
+    };
+}
+
+/// Range of serialized bytes.
+pub const Slice = struct {
+    start: usize,
+    end: usize,
+};
+
+/// Details of a binary encoding.
+pub const Encoding = struct {
+    /// Whether lengths and variant indices are encoded in ULEB128 rather than as u64 and u32.
+    uleb128: bool,
+    /// Maximum length of sequences, maps, strings, and byte arrays.
+    max_length: usize,
+    /// Maximum number of nested containers, if any.
+    max_container_depth: ?usize,
+    /// Whether map entries are sorted by serialized keys, which deserializers check.
+    canonical_maps: bool,
+    /// Whether chars are supported (as UTF-8).
+    chars: bool,
+};
+
+pub fn Serializer(comptime encoding: Encoding) type {
+    return struct {
+        const Self = @This();
+
+        allocator: std.mem.Allocator,
+        output: std.ArrayListUnmanaged(u8) = .{},
+        container_depth_budget: ?usize = encoding.max_container_depth,
+
+        pub fn init(allocator: std.mem.Allocator) Self {
+            return .{ .allocator = allocator };
+        }
+
+        pub fn deinit(self: *Self) void {
+            self.output.deinit(self.allocator);
+        }
+
+        /// Return the serialized bytes, which are owned by the caller.
+        pub fn toOwnedSlice(self: *Self) Error![]u8 {
+            return self.output.toOwnedSlice(self.allocator);
+        }
+
+        fn write(self: *Self, bytes: []const u8) Error!void {
+            try self.output.appendSlice(self.allocator, bytes);
+        }
+
+        fn writeInt(self: *Self, comptime T: type, value: T) Error!void {
+            var bytes: [@sizeOf(T)]u8 = undefined;
+            std.mem.writeInt(T, &bytes, value, .little);
+            try self.write(&bytes);
+        }
+
+        fn writeUleb128(self: *Self, value: u32) Error!void {
+            var rest = value;
+            while (rest >= 0x80) : (rest >>= 7) {
+                try self.output.append(self.allocator, @as(u8, @truncate(rest)) | 0x80);
+            }
+            try self.output.append(self.allocator, @intCast(rest));
+        }
+
+        pub fn serializeUnit(self: *Self, value: void) Error!void {
+            _ = self;
+            _ = value;
+        }
+
+        pub fn serializeBool(self: *Self, value: bool) Error!void {
+            try self.output.append(self.allocator, @intFromBool(value));
+        }
+
+        pub fn serializeU8(self: *Self, value: u8) Error!void {
+            try self.output.append(self.allocator, value);
+        }
+
+        pub fn serializeU16(self: *Self, value: u16) Error!void {
+            try self.writeInt(u16, value);
+        }
+
+        pub fn serializeU32(self: *Self, value: u32) Error!void {
+            try self.writeInt(u32, value);
+        }
+
+        pub fn serializeU64(self: *Self, value: u64) Error!void {
+            try self.writeInt(u64, value);
+        }
+
+        pub fn serializeU128(self: *Self, value: u128) Error!void {
+            try self.writeInt(u128, value);
+        }
+
+        pub fn serializeI8(self: *Self, value: i8) Error!void {
+            try self.writeInt(i8, value);
+        }
+
+        pub fn serializeI16(self: *Self, value: i16) Error!void {
+            try self.writeInt(i16, value);
+        }
+
+        pub fn serializeI32(self: *Self, value: i32) Error!void {
+            try self.writeInt(i32, value);
+        }
+
+        pub fn serializeI64(self: *Self, value: i64) Error!void {
+            try self.writeInt(i64, value);
+        }
+
+        pub fn serializeI128(self: *Self, value: i128) Error!void {
+            try self.writeInt(i128, value);
+        }
+
+        pub fn serializeF32(self: *Self, value: f32) Error!void {
+            try self.writeInt(u32, @bitCast(value));
+        }
+
+        pub fn serializeF64(self: *Self, value: f64) Error!void {
+            try self.writeInt(u64, @bitCast(value));
+        }
+
+        pub fn serializeChar(self: *Self, value: u21) Error!void {
+            if (!encoding.chars) return error.Unsupported;
+            var bytes: [4]u8 = undefined;
+            const len = std.unicode.utf8Encode(value, &bytes) catch return error.InvalidChar;
+            try self.write(bytes[0..len]);
+        }
+
+        pub fn serializeStr(self: *Self, value: []const u8) Error!void {
+            try self.serializeLen(value.len);
+            try self.write(value);
+        }
+
+        pub fn serializeBytes(self: *Self, value: []const u8) Error!void {
+            try self.serializeLen(value.len);
+            try self.write(value);
+        }
+
+        pub fn serializeLen(self: *Self, value: usize) Error!void {
+            if (value > encoding.max_length) return error.LengthTooLarge;
+            if (encoding.uleb128) {
+                try self.writeUleb128(@intCast(value));
+            } else {
+                try self.writeInt(u64, value);
+            }
+        }
+
+        pub fn serializeVariantIndex(self: *Self, value: u32) Error!void {
+            if (encoding.uleb128) {
+                try self.writeUleb128(value);
+            } else {
+                try self.writeInt(u32, value);
+            }
+        }
+
+        pub fn serializeOptionTag(self: *Self, value: bool) Error!void {
+            try self.serializeBool(value);
+        }
+
+        pub fn getBufferOffset(self: *const Self) usize {
+            return self.output.items.len;
+        }
+
+        /// Sort the serialized entries of a map, which start at the given offsets and end
+        /// at the current offset, if the encoding requires it.
+        pub fn sortMapEntries(self: *Self, offsets: []const usize) Error!void {
+            if (!encoding.canonical_maps or offsets.len <= 1) return;
+            const content = self.output.items[offsets[0]..];
+            const slices = try self.allocator.alloc([]const u8, offsets.len);
+            defer self.allocator.free(slices);
+            for (offsets, 0..) |start, i| {
+                const end = if (i + 1 < offsets.len) offsets[i + 1] else self.output.items.len;
+                slices[i] = self.output.items[start..end];
+            }
+            std.mem.sort([]const u8, slices, {}, lessThan);
+            const sorted = try self.allocator.alloc(u8, content.len);
+            defer self.allocator.free(sorted);
+            var position: usize = 0;
+            for (slices) |slice| {
+                @memcpy(sorted[position..][0..slice.len], slice);
+                position += slice.len;
+            }
+            @memcpy(content, sorted);
+        }
+
+        pub fn increaseContainerDepth(self: *Self) Error!void {
+            if (self.container_depth_budget) |budget| {
+                if (budget == 0) return error.TooManyNestedContainers;
+                self.container_depth_budget = budget - 1;
+            }
+        }
+
+        pub fn decreaseContainerDepth(self: *Self) void {
+            if (self.container_depth_budget) |budget| {
+                self.container_depth_budget = budget + 1;
+            }
+        }
+    };
+}
+
+pub fn Deserializer(comptime encoding: Encoding) type {
+    return struct {
+        const Self = @This();
+
+        allocator: std.mem.Allocator,
+        input: []const u8,
+        offset: usize = 0,
+        container_depth_budget: ?usize = encoding.max_container_depth,
+
+        pub fn init(allocator: std.mem.Allocator, input: []const u8) Self {
+            return .{ .allocator = allocator, .input = input };
+        }
+
+        fn read(self: *Self, len: usize) Error![]const u8 {
+            if (len > self.input.len - self.offset) return error.EndOfInput;
+            const bytes = self.input[self.offset .. self.offset + len];
+            self.offset += len;
+            return bytes;
+        }
+
+        fn readInt(self: *Self, comptime T: type) Error!T {
+            const bytes = try self.read(@sizeOf(T));
+            return std.mem.readInt(T, bytes[0..@sizeOf(T)], .little);
+        }
+
+        fn readUleb128(self: *Self) Error!u32 {
+            var value: u64 = 0;
+            var shift: u6 = 0;
+            while (shift < 32) : (shift += 7) {
+                const byte = try self.deserializeU8();
+                const digit = byte & 0x7F;
+                value |= @as(u64, digit) << shift;
+                if (value > std.math.maxInt(u32)) return error.InvalidUleb128;
+                if (digit == byte) {
+                    // Reject non-canonical encodings.
+                    if (shift > 0 and digit == 0) return error.InvalidUleb128;
+                    return @intCast(value);
+                }
+            }
+            return error.InvalidUleb128;
+        }
+
+        pub fn deserializeUnit(self: *Self) Error!void {
+            _ = self;
+        }
+
+        pub fn deserializeBool(self: *Self) Error!bool {
+            return switch (try self.deserializeU8()) {
+                0 => false,
+                1 => true,
+                else => error.InvalidBool,
+            };
+        }
+
+        pub fn deserializeU8(self: *Self) Error!u8 {
+            return (try self.read(1))[0];
+        }
+
+        pub fn deserializeU16(self: *Self) Error!u16 {
+            return self.readInt(u16);
+        }
+
+        pub fn deserializeU32(self: *Self) Error!u32 {
+            return self.readInt(u32);
+        }
+
+        pub fn deserializeU64(self: *Self) Error!u64 {
+            return self.readInt(u64);
+        }
+
+        pub fn deserializeU128(self: *Self) Error!u128 {
+            return self.readInt(u128);
+        }
+
+        pub fn deserializeI8(self: *Self) Error!i8 {
+            return self.readInt(i8);
+        }
+
+        pub fn deserializeI16(self: *Self) Error!i16 {
+            return self.readInt(i16);
+        }
+
+        pub fn deserializeI32(self: *Self) Error!i32 {
+            return self.readInt(i32);
+        }
+
+        pub fn deserializeI64(self: *Self) Error!i64 {
+            return self.readInt(i64);
+        }
+
+        pub fn deserializeI128(self: *Self) Error!i128 {
+            return self.readInt(i128);
+        }
+
+        pub fn deserializeF32(self: *Self) Error!f32 {
+            return @bitCast(try self.readInt(u32));
+        }
+
+        pub fn deserializeF64(self: *Self) Error!f64 {
+            return @bitCast(try self.readInt(u64));
+        }
+
+        pub fn deserializeChar(self: *Self) Error!u21 {
+            if (!encoding.chars) return error.Unsupported;
+            if (self.offset >= self.input.len) return error.EndOfInput;
+            const len = std.unicode.utf8ByteSequenceLength(self.input[self.offset]) catch
+                return error.InvalidChar;
+            const bytes = try self.read(len);
+            return std.unicode.utf8Decode(bytes) catch error.InvalidChar;
+        }
+
+        pub fn deserializeStr(self: *Self) Error![]const u8 {
+            const len = try self.deserializeLen();
+            const bytes = try self.read(len);
+            if (!std.unicode.utf8ValidateSlice(bytes)) return error.InvalidUtf8;
+            return self.allocator.dupe(u8, bytes);
+        }
+
+        pub fn deserializeBytes(self: *Self) Error![]const u8 {
+            const len = try self.deserializeLen();
+            return self.allocator.dupe(u8, try self.read(len));
+        }
+
+        pub fn deserializeLen(self: *Self) Error!usize {
+            const value: u64 = if (encoding.uleb128)
+                try self.readUleb128()
+            else
+                try self.readInt(u64);
+            if (value > encoding.max_length) return error.LengthTooLarge;
+            return @intCast(value);
+        }
+
+        pub fn deserializeVariantIndex(self: *Self) Error!u32 {
+            if (encoding.uleb128) {
+                return self.readUleb128();
+            } else {
+                return self.readInt(u32);
+            }
+        }
+
+        pub fn deserializeOptionTag(self: *Self) Error!bool {
+            return self.deserializeBool();
+        }
+
+        pub fn getBufferOffset(self: *const Self) usize {
+            return self.offset;
+        }
+
+        /// Check that the serialized keys of two consecutive map entries are increasing, if
+        /// the encoding requires it.
+        pub fn checkThatKeySlicesAreIncreasing(self: *const Self, key1: Slice, key2: Slice) Error!void {
+            if (!encoding.canonical_maps) return;
+            const bytes1 = self.input[key1.start..key1.end];
+            const bytes2 = self.input[key2.start..key2.end];
+            if (!lessThan({}, bytes1, bytes2)) return error.UnorderedMapKeys;
+        }
+
+        /// Allocate a slice for the content of a sequence or a map.
+        pub fn alloc(self: *Self, comptime T: type, len: usize) Error![]T {
+            return self.allocator.alloc(T, len);
+        }
+
+        /// Allocate a copy of `value`, e.g. for a recursive container.
+        pub fn create(self: *Self, comptime T: type, value: T) Error!*const T {
+            const pointer = try self.allocator.create(T);
+            pointer.* = value;
+            return pointer;
+        }
+
+        /// Fail unless the whole input was read.
+        pub fn finish(self: *const Self) Error!void {
+            if (self.offset < self.input.len) return error.RemainingInput;
+        }
+
+        pub fn increaseContainerDepth(self: *Self) Error!void {
+            if (self.container_depth_budget) |budget| {
+                if (budget == 0) return error.TooManyNestedContainers;
+                self.container_depth_budget = budget - 1;
+            }
+        }
+
+        pub fn decreaseContainerDepth(self: *Self) void {
+            if (self.container_depth_budget) |budget| {
+                self.container_depth_budget = budget + 1;
+            }
+        }
+    };
+}
+
+fn lessThan(context: void, bytes1: []const u8, bytes2: []const u8) bool {
+    _ = context;
+    return std.mem.order(u8, bytes1, bytes2) == .lt;
+}
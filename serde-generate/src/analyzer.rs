@@ -1,7 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Registry, Result};
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, Result};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 /// Compute dependencies while ignoring external names.
@@ -42,6 +42,182 @@ pub fn get_dependency_map_with_external_dependencies<'a>(
     Ok(children)
 }
 
+/// An error while restricting a registry with `filter_registry`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterError {
+    /// A container named in a filter is not defined in the registry.
+    UnknownContainer(String),
+    /// A container that is kept references an excluded container.
+    ExcludedDependency {
+        container: String,
+        dependency: String,
+    },
+    /// The registry contains formats that are not fully known.
+    UnknownFormat(serde_reflection::Error),
+}
+
+impl std::fmt::Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterError::UnknownContainer(name) => {
+                write!(f, "Container {} is not defined in the registry", name)
+            }
+            FilterError::ExcludedDependency {
+                container,
+                dependency,
+            } => write!(
+                f,
+                "Container {} references the excluded container {}",
+                container, dependency
+            ),
+            FilterError::UnknownFormat(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Restrict a registry to the containers in `include` (all of them if `include` is empty) minus
+/// the containers in `exclude`, together with the containers that they reference.
+/// * Fails if a kept container references an excluded container, since the generated code
+///   would not compile.
+/// * Names of containers that are not in the registry are rejected.
+pub fn filter_registry(
+    registry: &Registry,
+    include: &BTreeSet<String>,
+    exclude: &BTreeSet<String>,
+) -> std::result::Result<Registry, FilterError> {
+    if let Some(name) = include
+        .iter()
+        .chain(exclude)
+        .find(|name| !registry.contains_key(*name))
+    {
+        return Err(FilterError::UnknownContainer(name.clone()));
+    }
+    let dependencies = get_dependency_map(registry).map_err(FilterError::UnknownFormat)?;
+    let mut queue: Vec<&str> = if include.is_empty() {
+        registry.keys().map(String::as_str).collect()
+    } else {
+        include.iter().map(String::as_str).collect()
+    };
+    queue.retain(|name| !exclude.contains(*name));
+    let mut kept = BTreeSet::new();
+    while let Some(name) = queue.pop() {
+        if !kept.insert(name) {
+            continue;
+        }
+        for dependency in &dependencies[name] {
+            if exclude.contains(*dependency) {
+                return Err(FilterError::ExcludedDependency {
+                    container: name.to_string(),
+                    dependency: dependency.to_string(),
+                });
+            }
+            // Names outside the registry are provided externally.
+            if registry.contains_key(*dependency) {
+                queue.push(dependency);
+            }
+        }
+    }
+    Ok(registry
+        .iter()
+        .filter(|(name, _)| kept.contains(name.as_str()))
+        .map(|(name, format)| (name.clone(), format.clone()))
+        .collect())
+}
+
+/// Size in bytes of the BCS encoding of the values of `format`, if all these values have the
+/// same size, e.g. 12 for `(u32, u64)`. Strings, sequences, maps, options, enums, and recursive
+/// containers have no fixed size, nor do formats that BCS does not support, such as `char`.
+pub fn bcs_fixed_size(registry: &Registry, format: &Format) -> Option<usize> {
+    format_fixed_size(registry, format, &mut BTreeSet::new())
+}
+
+/// BCS offsets in bytes of the fields of the struct `type_name`, e.g. to generate zero-copy
+/// accessors for memory-mapped values. Returns `None` unless `type_name` is a struct with named
+/// fields which all have a fixed size (see `bcs_fixed_size`).
+pub fn field_offsets(registry: &Registry, type_name: &str) -> Option<Vec<(String, usize)>> {
+    let (name, format) = registry.get_key_value(type_name)?;
+    let fields = struct_fields(format)?;
+    let mut visiting = BTreeSet::new();
+    visiting.insert(name.as_str());
+    let mut offset = 0usize;
+    fields
+        .iter()
+        .map(|field| {
+            let field_offset = offset;
+            let size = format_fixed_size(registry, &field.value, &mut visiting)?;
+            offset = offset.checked_add(size)?;
+            Some((field.name.clone(), field_offset))
+        })
+        .collect()
+}
+
+fn struct_fields(format: &ContainerFormat) -> Option<&[Named<Format>]> {
+    match format {
+        ContainerFormat::Struct(fields) => Some(fields),
+        _ => None,
+    }
+}
+
+/// Sum of the fixed sizes of `formats`, if all of them have one.
+fn formats_fixed_size<'a>(
+    registry: &'a Registry,
+    formats: impl IntoIterator<Item = &'a Format>,
+    visiting: &mut BTreeSet<&'a str>,
+) -> Option<usize> {
+    formats.into_iter().try_fold(0usize, |total, format| {
+        total.checked_add(format_fixed_size(registry, format, visiting)?)
+    })
+}
+
+/// `visiting` holds the containers being measured, whose occurrence means a recursive type.
+fn format_fixed_size<'a>(
+    registry: &'a Registry,
+    format: &'a Format,
+    visiting: &mut BTreeSet<&'a str>,
+) -> Option<usize> {
+    use Format::*;
+    match format {
+        Unit => Some(0),
+        Bool | I8 | U8 => Some(1),
+        I16 | U16 => Some(2),
+        I32 | U32 | F32 => Some(4),
+        I64 | U64 | F64 => Some(8),
+        I128 | U128 => Some(16),
+        Tuple(formats) => formats_fixed_size(registry, formats, visiting),
+        TupleArray { content, size } => {
+            format_fixed_size(registry, content, visiting)?.checked_mul(*size)
+        }
+        TypeName(name) => {
+            let (name, format) = registry.get_key_value(name)?;
+            if !visiting.insert(name.as_str()) {
+                return None;
+            }
+            let size = container_fixed_size(registry, format, visiting);
+            visiting.remove(name.as_str());
+            size
+        }
+        Variable(_) | Char | Str | Bytes | Option(_) | Seq(_) | Map { .. } => None,
+    }
+}
+
+fn container_fixed_size<'a>(
+    registry: &'a Registry,
+    format: &'a ContainerFormat,
+    visiting: &mut BTreeSet<&'a str>,
+) -> Option<usize> {
+    match format {
+        ContainerFormat::UnitStruct => Some(0),
+        ContainerFormat::NewTypeStruct(format) => format_fixed_size(registry, format, visiting),
+        ContainerFormat::TupleStruct(formats) => formats_fixed_size(registry, formats, visiting),
+        ContainerFormat::Struct(fields) => {
+            formats_fixed_size(registry, fields.iter().map(|field| &field.value), visiting)
+        }
+        ContainerFormat::Enum(_) => None,
+    }
+}
+
 /// Classic topological sorting algorithm except that it doesn't abort in case of cycles.
 pub fn best_effort_topological_sort<T>(children: &BTreeMap<T, BTreeSet<T>>) -> Vec<T>
 where
@@ -0,0 +1,153 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{validation::Language, CodeGeneratorConfig};
+use serde_reflection::Registry;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Call `generate` unless the last successful call recorded in `cache_dir` for the same
+/// language, module, and target directory used the same registry, config, and version of
+/// this crate, and the files it wrote in `target_dir` still exist.
+/// * `options` describes the settings of `generate` that are not part of `config`, such as
+///   installer options.
+/// * The key of each generation and the files written in `target_dir` are recorded in a stamp
+///   file of `cache_dir` after `generate` succeeds. Deleting the stamp file forces the next
+///   generation.
+/// * Returns whether `generate` was called.
+pub fn cached_generate<E, F>(
+    cache_dir: &Path,
+    target_dir: &Path,
+    language: Language,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+    options: &str,
+    generate: F,
+) -> Result<bool, E>
+where
+    F: FnOnce() -> Result<(), E>,
+    E: From<std::io::Error>,
+{
+    let key = generation_key(language, config, registry, options);
+    if let Ok(target_dir) = std::fs::canonicalize(target_dir) {
+        let stamp_path = stamp_path(cache_dir, &target_dir, language, config);
+        if is_up_to_date(&stamp_path, &key, &target_dir) {
+            return Ok(false);
+        }
+    }
+    // Leave some slack for file systems that round modification times.
+    let start = SystemTime::now() - Duration::from_secs(2);
+    generate()?;
+    let target_dir = std::fs::canonicalize(target_dir)?;
+    let mut stamp = key;
+    for path in modified_files(&target_dir, start)? {
+        let path = path.strip_prefix(&target_dir).unwrap_or(&path);
+        stamp.push('\n');
+        stamp.push_str(&path.to_string_lossy());
+    }
+    std::fs::create_dir_all(cache_dir)?;
+    std::fs::write(stamp_path(cache_dir, &target_dir, language, config), stamp)?;
+    Ok(true)
+}
+
+/// Hash of everything that determines the generated code, in hexadecimal.
+fn generation_key(
+    language: Language,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+    options: &str,
+) -> String {
+    let mut hasher = Fnv128::new();
+    let inputs = (
+        env!("CARGO_PKG_VERSION"),
+        language,
+        options,
+        config,
+        registry,
+    );
+    bincode::serialize_into(&mut hasher, &inputs).expect("hashing does not fail");
+    format!("{:032x}", hasher.0)
+}
+
+/// Whether the stamp file holds the given key, and the files listed after it still exist.
+fn is_up_to_date(stamp_path: &Path, key: &str, target_dir: &Path) -> bool {
+    let stamp = match std::fs::read_to_string(stamp_path) {
+        Ok(stamp) => stamp,
+        Err(_) => return false,
+    };
+    let mut lines = stamp.lines();
+    lines.next() == Some(key) && lines.all(|path| target_dir.join(path).is_file())
+}
+
+/// Files of `dir` (recursively) modified since `start`.
+fn modified_files(dir: &Path, start: SystemTime) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            files.extend(modified_files(&entry.path(), start)?);
+        } else if metadata.modified()? >= start {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn stamp_path(
+    cache_dir: &Path,
+    target_dir: &Path,
+    language: Language,
+    config: &CodeGeneratorConfig,
+) -> PathBuf {
+    let module_name = config
+        .module_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    let mut hasher = Fnv128::new();
+    bincode::serialize_into(&mut hasher, &target_dir.to_string_lossy())
+        .expect("hashing does not fail");
+    cache_dir.join(
+        format!(
+            "{}.{:?}.{:016x}.stamp",
+            module_name, language, hasher.0 as u64
+        )
+        .to_lowercase(),
+    )
+}
+
+/// 128-bit FNV-1a hash, which is stable across platforms and Rust versions.
+struct Fnv128(u128);
+
+impl Fnv128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl std::io::Write for Fnv128 {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        for byte in bytes {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
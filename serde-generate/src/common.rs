@@ -1,7 +1,9 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde_reflection::Format;
+use crate::{validation::Language, CodeGeneratorConfig, KeywordEscaping, NonAsciiNames};
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use std::borrow::Cow;
 
 pub(crate) fn mangle_type(format: &Format) -> String {
     use Format::*;
@@ -57,3 +59,534 @@ pub(crate) fn lowercase_first_letter(s: &str) -> String {
         Some(f) => f.to_lowercase().collect::<String>() + c.as_str(),
     }
 }
+
+/// Text in the first line of the generated source files that installers may overwrite or delete.
+pub(crate) const GENERATED_FILE_MARKER: &str = "@generated by serde-generate";
+
+/// Whether the file at `path` starts with the marker of generated files.
+pub(crate) fn is_generated_file(path: &std::path::Path) -> std::io::Result<bool> {
+    use std::io::BufRead;
+    let file = std::fs::File::open(path)?;
+    let mut first_line = String::new();
+    // Binary content is not ours either.
+    if std::io::BufReader::new(file)
+        .read_line(&mut first_line)
+        .is_err()
+    {
+        return Ok(false);
+    }
+    Ok(first_line.contains(GENERATED_FILE_MARKER))
+}
+
+/// Create a generated file, or truncate it if it already exists. Existing files without the
+/// marker of generated files are never overwritten.
+pub(crate) fn create_generated_file(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    if path.exists() && !is_generated_file(path)? {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "Refusing to overwrite {} because it was not generated by serde-generate",
+                path.display()
+            ),
+        ));
+    }
+    std::fs::File::create(path)
+}
+
+/// Delete the files of `dir` that carry the marker of generated files but are not in `current`.
+/// Sub-directories and other files are left untouched.
+pub(crate) fn remove_stale_generated_files(
+    dir: &std::path::Path,
+    current: &std::collections::BTreeSet<std::path::PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && !current.contains(&path) && is_generated_file(&path)? {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reserved keywords of the given language.
+pub(crate) fn keywords(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Cpp => CPP_KEYWORDS,
+        Language::CSharp => CSHARP_KEYWORDS,
+        Language::Dart => DART_KEYWORDS,
+        Language::Go => GO_KEYWORDS,
+        Language::Java => JAVA_KEYWORDS,
+        Language::OCaml => OCAML_KEYWORDS,
+        // Names are only used as strings.
+        Language::OpenApi => &[],
+        Language::Python3 => PYTHON_KEYWORDS,
+        Language::Rust => RUST_KEYWORDS,
+        Language::Php => PHP_KEYWORDS,
+        Language::Solidity => SOLIDITY_KEYWORDS,
+        Language::Swift => SWIFT_KEYWORDS,
+        Language::TypeScript => TYPESCRIPT_KEYWORDS,
+        Language::Zig => ZIG_KEYWORDS,
+    }
+}
+
+/// Whether `identifier` is reserved in the given language. Besides keywords, this includes the
+/// special `__dunder__` names of Python, which would clash with the methods and attributes of
+/// dataclasses such as `__init__` or `__hash__`.
+pub(crate) fn is_keyword(language: Language, identifier: &str) -> bool {
+    keywords(language).contains(&identifier)
+        || (language == Language::Python3
+            && identifier.len() > 4
+            && identifier.starts_with("__")
+            && identifier.ends_with("__"))
+}
+
+/// Rename `identifier` as configured by `CodeGeneratorConfig::with_keyword_escaping` if it is
+/// reserved in the given language (see `is_keyword`). With `KeywordEscaping::Error`, the
+/// identifier is returned unchanged since the registry was rejected during validation.
+pub(crate) fn escape_keyword(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    identifier: &str,
+) -> String {
+    if !is_keyword(language, identifier) {
+        return identifier.to_string();
+    }
+    match config.keyword_escaping(language) {
+        KeywordEscaping::Suffix(suffix) => format!("{}{}", identifier, suffix),
+        KeywordEscaping::Prefix(prefix) => format!("{}{}", prefix, identifier),
+        KeywordEscaping::Error | KeywordEscaping::PassThrough => identifier.to_string(),
+    }
+}
+
+/// Whether identifiers of the given language may contain Unicode letters and digits. Go is
+/// excluded because exported identifiers must start with an uppercase letter, and OpenAPI
+/// because names of component schemas must be ASCII.
+pub(crate) fn allows_unicode_identifiers(language: Language) -> bool {
+    !matches!(
+        language,
+        Language::Dart
+            | Language::Go
+            | Language::OCaml
+            | Language::OpenApi
+            | Language::Php
+            | Language::Solidity
+            | Language::Zig
+    )
+}
+
+/// Whether `name` only contains characters allowed by `config.non_ascii_names` in the
+/// identifiers of the given language.
+pub(crate) fn has_valid_characters(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    name: &str,
+) -> bool {
+    let allows_unicode = config.non_ascii_names == NonAsciiNames::PassThrough
+        && allows_unicode_identifiers(language);
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || (allows_unicode && c.is_alphanumeric()))
+}
+
+/// Replace the characters of `name` other than ASCII letters, digits, and underscores, as
+/// described in `NonAsciiNames::Transliterate`.
+pub(crate) fn transliterate(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            result.push(c);
+        } else {
+            result.push_str(&format!("U{:04X}", c as u32));
+        }
+    }
+    result
+}
+
+/// Registry to generate code for: the given one, or a copy with transliterated names if so
+/// configured.
+pub(crate) fn apply_non_ascii_names<'a>(
+    config: &CodeGeneratorConfig,
+    registry: &'a Registry,
+) -> Cow<'a, Registry> {
+    if config.non_ascii_names != NonAsciiNames::Transliterate {
+        return Cow::Borrowed(registry);
+    }
+    let registry = registry
+        .iter()
+        .map(|(name, format)| {
+            let mut format = format.clone();
+            transliterate_container(&mut format);
+            format
+                .visit_mut(&mut |format| {
+                    if let Format::TypeName(name) = format {
+                        *name = transliterate(name);
+                    }
+                    Ok(())
+                })
+                .expect("formats should not contain variables");
+            (transliterate(name), format)
+        })
+        .collect();
+    Cow::Owned(registry)
+}
+
+fn transliterate_container(format: &mut ContainerFormat) {
+    match format {
+        ContainerFormat::Struct(fields) => transliterate_fields(fields),
+        ContainerFormat::Enum(variants) => {
+            for variant in variants.values_mut() {
+                variant.name = transliterate(&variant.name);
+                if let VariantFormat::Struct(fields) = &mut variant.value {
+                    transliterate_fields(fields);
+                }
+            }
+        }
+        ContainerFormat::UnitStruct
+        | ContainerFormat::NewTypeStruct(_)
+        | ContainerFormat::TupleStruct(_) => (),
+    }
+}
+
+fn transliterate_fields(fields: &mut [Named<Format>]) {
+    for field in fields {
+        field.name = transliterate(&field.name);
+    }
+}
+
+#[rustfmt::skip]
+const CPP_KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break",
+    "case", "catch", "char", "char16_t", "char32_t", "class", "compl", "const", "constexpr",
+    "const_cast", "continue", "decltype", "default", "delete", "do", "double", "dynamic_cast",
+    "else", "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if",
+    "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq", "nullptr",
+    "operator", "or", "or_eq", "private", "protected", "public", "register", "reinterpret_cast",
+    "return", "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct",
+    "switch", "template", "this", "thread_local", "throw", "true", "try", "typedef", "typeid",
+    "typename", "union", "unsigned", "using", "virtual", "void", "volatile", "wchar_t", "while",
+    "xor", "xor_eq",
+];
+
+#[rustfmt::skip]
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked", "class",
+    "const", "continue", "decimal", "default", "delegate", "do", "double", "else", "enum", "event",
+    "explicit", "extern", "false", "finally", "fixed", "float", "for", "foreach", "goto", "if",
+    "implicit", "in", "int", "interface", "internal", "is", "lock", "long", "namespace", "new",
+    "null", "object", "operator", "out", "override", "params", "private", "protected", "public",
+    "readonly", "ref", "return", "sbyte", "sealed", "short", "sizeof", "stackalloc", "static",
+    "string", "struct", "switch", "this", "throw", "true", "try", "typeof", "uint", "ulong",
+    "unchecked", "unsafe", "ushort", "using", "virtual", "void", "volatile", "while",
+];
+
+const DART_KEYWORDS: &[&str] = &[
+    "assert", "break", "case", "catch", "class", "const", "continue", "default", "do", "else",
+    "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new", "null",
+    "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void", "while",
+    "with",
+];
+
+#[rustfmt::skip]
+const GO_KEYWORDS: &[&str] = &[
+    "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for",
+    "func", "go", "goto", "if", "import", "interface", "map", "package", "range", "return",
+    "select", "struct", "switch", "type", "var",
+];
+
+#[rustfmt::skip]
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+    "continue", "default", "do", "double", "else", "enum", "extends", "false", "final", "finally",
+    "float", "for", "goto", "if", "implements", "import", "instanceof", "int", "interface", "long",
+    "native", "new", "null", "package", "private", "protected", "public", "return", "short",
+    "static", "strictfp", "super", "switch", "synchronized", "this", "throw", "throws", "transient",
+    "true", "try", "void", "volatile", "while",
+];
+
+#[rustfmt::skip]
+const OCAML_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "asr", "begin", "class", "constraint", "do", "done", "downto", "else",
+    "end", "exception", "external", "false", "for", "fun", "function", "functor", "if", "in",
+    "include", "inherit", "initializer", "land", "lazy", "let", "lor", "lsl", "lsr", "lxor",
+    "match", "method", "mod", "module", "mutable", "new", "nonrec", "object", "of", "open", "or",
+    "private", "rec", "sig", "struct", "then", "to", "true", "try", "type", "val", "virtual",
+    "when", "while", "with", "bool", "string", "bytes", "char", "unit", "option", "float", "list",
+    "int32", "int64",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[rustfmt::skip]
+const SOLIDITY_KEYWORDS: &[&str] = &[
+    "abstract", "after", "alias", "anonymous", "as", "assembly", "break", "catch", "constant",
+    "continue", "constructor", "contract", "delete", "do", "else", "emit", "enum", "error",
+    "event", "external", "fallback", "for", "function", "if", "immutable", "import", "indexed",
+    "interface", "internal", "is", "library", "mapping", "memory", "modifier", "new", "override",
+    "payable", "pragma", "private", "public", "pure", "receive", "return", "returns", "revert",
+    "storage", "struct", "throw", "try", "type", "unchecked", "using", "virtual", "view", "while",
+    "addmod", "blockhash", "ecrecover", "keccak256", "mulmod", "sha256", "ripemd160", "block",
+    "msg", "tx", "balance", "transfer", "send", "call", "delegatecall", "staticcall", "this",
+    "super", "gwei", "finney", "szabo", "ether", "seconds", "minutes", "hours", "days", "weeks",
+    "years", "wei", "hex", "address", "bool", "bytes", "string", "int", "int8", "int16", "int32",
+    "int64", "int128", "int256", "uint", "uint8", "uint16", "uint32", "uint64", "uint128",
+    "uint256", "bytes1", "bytes2", "bytes3", "bytes4", "bytes5", "bytes6", "bytes7", "bytes8",
+    "bytes9", "bytes10", "bytes11", "bytes12", "bytes13", "bytes14", "bytes15", "bytes16",
+    "bytes17", "bytes18", "bytes19", "bytes20", "bytes21", "bytes22", "bytes23", "bytes24",
+    "bytes25", "bytes26", "bytes27", "bytes28", "bytes29", "bytes30", "bytes31", "bytes32",
+];
+
+#[rustfmt::skip]
+const SWIFT_KEYWORDS: &[&str] = &[
+    "Any", "Self", "as", "associatedtype", "break", "case", "catch", "class", "continue", "default",
+    "defer", "deinit", "do", "else", "enum", "extension", "fallthrough", "false", "fileprivate",
+    "for", "func", "guard", "if", "import", "in", "init", "inout", "internal", "is", "let", "nil",
+    "open", "operator", "private", "protocol", "public", "repeat", "rethrows", "return", "self",
+    "static", "struct", "subscript", "super", "switch", "throw", "throws", "true", "try",
+    "typealias", "var", "where", "while",
+];
+
+#[rustfmt::skip]
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+    "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements",
+    "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private",
+    "protected", "public", "return", "static", "super", "switch", "this", "throw", "true", "try",
+    "typeof", "var", "void", "while", "with", "yield",
+];
+
+// Also includes primitive types, which cannot be shadowed, the imports of the generated code,
+// and the names of its methods.
+#[rustfmt::skip]
+const ZIG_KEYWORDS: &[&str] = &[
+    "addrspace", "align", "allowzero", "and", "anyframe", "anytype", "asm", "async", "await",
+    "break", "callconv", "catch", "comptime", "const", "continue", "defer", "else", "enum",
+    "errdefer", "error", "export", "extern", "fn", "for", "if", "inline", "linksection",
+    "noalias", "noinline", "nosuspend", "opaque", "or", "orelse", "packed", "pub", "resume",
+    "return", "struct", "suspend", "switch", "test", "threadlocal", "try", "union", "unreachable",
+    "usingnamespace", "var", "volatile", "while", "anyerror", "anyopaque", "bool", "f16", "f32",
+    "f64", "f80", "f128", "false", "i8", "i16", "i32", "i64", "i128", "isize", "noreturn", "null",
+    "true", "type", "u8", "u16", "u21", "u32", "u64", "u128", "undefined", "usize", "void", "std",
+    "serde", "bcs", "bincode", "serialize", "deserialize", "bcsSerialize", "bcsDeserialize",
+    "bincodeSerialize", "bincodeDeserialize",
+];
+
+// https://www.php.net/manual/en/reserved.php
+// PHP keywords are case-insensitive: type names are compared in lowercase. Also includes the
+// reserved class names, `this`, and the class holding the serialization helpers.
+#[rustfmt::skip]
+const PHP_KEYWORDS: &[&str] = &[
+    "__halt_compiler", "abstract", "and", "array", "as", "break", "callable", "case", "catch",
+    "class", "clone", "const", "continue", "declare", "default", "do", "echo", "else", "elseif",
+    "empty", "enddeclare", "endfor", "endforeach", "endif", "endswitch", "endwhile", "enum",
+    "eval", "exit", "extends", "final", "finally", "fn", "for", "foreach", "function", "global",
+    "goto", "if", "implements", "include", "include_once", "instanceof", "insteadof",
+    "interface", "isset", "list", "match", "namespace", "new", "or", "print", "private",
+    "protected", "public", "readonly", "require", "require_once", "return", "static", "switch",
+    "throw", "trait", "try", "unset", "use", "var", "while", "xor", "yield", "bool", "false",
+    "float", "int", "iterable", "mixed", "never", "null", "object", "parent", "self", "string",
+    "true", "void", "this", "traithelpers",
+];
+
+/// Names of the fields of the tuple variant `enum_name::variant_name`, if configured with
+/// `CodeGeneratorConfig::with_tuple_field_names`.
+pub(crate) fn tuple_field_names<'a>(
+    config: &'a CodeGeneratorConfig,
+    enum_name: &str,
+    variant_name: &str,
+) -> Option<&'a [String]> {
+    config
+        .tuple_field_names
+        .get(&(enum_name.to_string(), variant_name.to_string()))
+        .map(Vec::as_slice)
+}
+
+/// Whether a field may be omitted by the compact encoding when it holds its default value
+/// (see `CodeGeneratorConfig::with_elision_containers`).
+pub(crate) fn is_elidable(format: &Format) -> bool {
+    use Format::*;
+    matches!(
+        format,
+        Bool | I8
+            | I16
+            | I32
+            | I64
+            | I128
+            | U8
+            | U16
+            | U32
+            | U64
+            | U128
+            | Str
+            | Bytes
+            | Option(_)
+            | Seq(_)
+            | Map { .. }
+    )
+}
+
+/// Bit of the presence bitmap used by each field in the compact encoding, if any.
+pub(crate) fn presence_bits(fields: &[Named<Format>]) -> Vec<Option<usize>> {
+    let mut next_bit = 0;
+    fields
+        .iter()
+        .map(|field| {
+            if is_elidable(&field.value) {
+                next_bit += 1;
+                Some(next_bit - 1)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Number of bytes of the presence bitmap of the compact encoding.
+pub(crate) fn presence_bitmap_size(fields: &[Named<Format>]) -> usize {
+    let count = fields.iter().filter(|f| is_elidable(&f.value)).count();
+    (count + 7) / 8
+}
+
+/// Whether the last field of the struct `name` optionally holds another `name`, as in linked
+/// lists. Chains of such values are (de)serialized with loops rather than recursive calls,
+/// which would exhaust the stack of long chains.
+pub(crate) fn is_self_recursive_chain(name: &str, fields: &[Named<Format>]) -> bool {
+    match fields.last().map(|field| &field.value) {
+        Some(Format::Option(format)) => matches!(format.as_ref(), Format::TypeName(n) if n == name),
+        _ => false,
+    }
+}
+
+/// Describe a container as a literal that is valid in both Python and TypeScript (see
+/// `CodeGeneratorConfig::with_runtime_schema`).
+pub(crate) fn quote_container_schema(format: &ContainerFormat) -> String {
+    use ContainerFormat::*;
+    match format {
+        UnitStruct => quote_schema("unit_struct", &[]),
+        NewTypeStruct(format) => {
+            quote_schema("newtype_struct", &[("format", quote_format_schema(format))])
+        }
+        TupleStruct(formats) => quote_schema(
+            "tuple_struct",
+            &[("formats", quote_formats_schema(formats))],
+        ),
+        Struct(fields) => quote_schema("struct", &[("fields", quote_fields_schema(fields))]),
+        Enum(variants) => {
+            let variants = variants
+                .iter()
+                .map(|(index, variant)| {
+                    format!(
+                        "[{}, \"{}\", {}]",
+                        index,
+                        variant.name,
+                        quote_variant_schema(&variant.value)
+                    )
+                })
+                .collect::<Vec<_>>();
+            quote_schema(
+                "enum",
+                &[("variants", format!("[{}]", variants.join(", ")))],
+            )
+        }
+    }
+}
+
+fn quote_variant_schema(format: &VariantFormat) -> String {
+    use VariantFormat::*;
+    match format {
+        Unit => quote_schema("unit", &[]),
+        NewType(format) => quote_schema("newtype", &[("format", quote_format_schema(format))]),
+        Tuple(formats) => quote_schema("tuple", &[("formats", quote_formats_schema(formats))]),
+        Struct(fields) => quote_schema("struct", &[("fields", quote_fields_schema(fields))]),
+        Variable(_) => panic!("unexpected value"),
+    }
+}
+
+fn quote_format_schema(format: &Format) -> String {
+    use Format::*;
+    let kind = match format {
+        Variable(_) => panic!("unexpected value"),
+        TypeName(name) => return quote_schema("ref", &[("name", format!("\"{}\"", name))]),
+        Option(format) => {
+            return quote_schema("option", &[("format", quote_format_schema(format))])
+        }
+        Seq(format) => return quote_schema("seq", &[("format", quote_format_schema(format))]),
+        Map { key, value } => {
+            return quote_schema(
+                "map",
+                &[
+                    ("key", quote_format_schema(key)),
+                    ("value", quote_format_schema(value)),
+                ],
+            )
+        }
+        Tuple(formats) => {
+            return quote_schema("tuple", &[("formats", quote_formats_schema(formats))])
+        }
+        TupleArray { content, size } => {
+            return quote_schema(
+                "array",
+                &[
+                    ("format", quote_format_schema(content)),
+                    ("size", size.to_string()),
+                ],
+            )
+        }
+        Unit => "unit",
+        Bool => "bool",
+        I8 => "i8",
+        I16 => "i16",
+        I32 => "i32",
+        I64 => "i64",
+        I128 => "i128",
+        U8 => "u8",
+        U16 => "u16",
+        U32 => "u32",
+        U64 => "u64",
+        U128 => "u128",
+        F32 => "f32",
+        F64 => "f64",
+        Char => "char",
+        Str => "str",
+        Bytes => "bytes",
+    };
+    quote_schema(kind, &[])
+}
+
+fn quote_formats_schema(formats: &[Format]) -> String {
+    let formats = formats.iter().map(quote_format_schema).collect::<Vec<_>>();
+    format!("[{}]", formats.join(", "))
+}
+
+fn quote_fields_schema(fields: &[Named<Format>]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "[\"{}\", {}]",
+                field.name,
+                quote_format_schema(&field.value)
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", fields.join(", "))
+}
+
+fn quote_schema(kind: &str, entries: &[(&str, String)]) -> String {
+    let mut result = format!("{{\"kind\": \"{}\"", kind);
+    for (key, value) in entries {
+        result += &format!(", \"{}\": {}", key, value);
+    }
+    result + "}"
+}
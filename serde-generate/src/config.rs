@@ -1,26 +1,93 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::collections::{BTreeMap, BTreeSet};
+use crate::{indent::IndentConfig, validation::Language};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    process::Command,
+    sync::Arc,
+};
 
 /// Code generation options meant to be supported by all languages.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct CodeGeneratorConfig {
     pub(crate) module_name: String,
     pub(crate) serialization: bool,
+    pub(crate) deserialization: Option<bool>,
     pub(crate) encodings: BTreeSet<Encoding>,
     pub(crate) external_definitions: ExternalDefinitions,
     pub(crate) comments: DocComments,
     pub(crate) custom_code: CustomCode,
     pub(crate) c_style_enums: bool,
+    pub(crate) postcard_floats: bool,
+    pub(crate) allow_nonstandard_floats: bool,
+    pub(crate) keyword_escaping: Option<KeywordEscaping>,
+    pub(crate) non_ascii_names: NonAsciiNames,
+    pub(crate) formatter: Option<FormatterHook>,
+    pub(crate) size_estimators: bool,
+    pub(crate) bcs_length_prefix: bool,
+    pub(crate) runtime_schema: bool,
+    pub(crate) elision_containers: BTreeSet<String>,
+    pub(crate) tuple_field_names: BTreeMap<(String, String), Vec<String>>,
+    pub(crate) map_types: BTreeMap<Language, String>,
+    pub(crate) indent: Option<IndentConfig>,
 }
 
-#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize)]
 pub enum Encoding {
     Bincode,
     Bcs,
+    Postcard,
+    /// MessagePack, with the same layout as the `rmp-serde` crate when structs are encoded
+    /// as maps (`rmp_serde::to_vec_named`). Integers wider than 64 bits are not supported.
+    MessagePack,
 }
 
+/// How to rename identifiers that are reserved keywords in the target language.
+/// Only identifiers are affected: serialized data stays the same.
+/// Field names are escaped in every language, variant names when they are not compiled into
+/// classes (Dart and Python C-style enums, PHP enums without data, Rust, Solidity, Swift, Zig),
+/// and type names only in OCaml and PHP. Other keywords are reported as errors by the code
+/// generators, unless they are passed through.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum KeywordEscaping {
+    /// Append the given string, e.g. `type_`.
+    Suffix(String),
+    /// Prepend the given string, e.g. `r#type` in Rust or `@class` in C#.
+    Prefix(String),
+    /// Reject registries that use keywords as identifiers.
+    Error,
+    /// Keep keywords unchanged, even if the generated code does not compile.
+    PassThrough,
+}
+
+/// How to deal with names of the registry that are not made of ASCII letters, digits, and
+/// underscores, e.g. `Café` or names containing `-` after a serde rename.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum NonAsciiNames {
+    /// Keep names made of Unicode letters, digits, and underscores in languages whose
+    /// identifiers allow them (C++, C#, Java, Python, Rust, Swift, and TypeScript). Other
+    /// names are rejected.
+    PassThrough,
+    /// Replace each character other than ASCII letters, digits, and underscores with `U`
+    /// followed by its code point in (at least 4) uppercase hexadecimal digits, e.g. `Café`
+    /// becomes `CafU00E9`. Names are replaced before applying the naming conventions of the
+    /// target language.
+    Transliterate,
+    /// Reject names that are not made of ASCII letters, digits, and underscores.
+    Error,
+}
+
+/// A function applied by `SourceInstaller::install_module` to each generated source file, e.g.
+/// to run a code formatter. The hook receives the target language so that the same config may
+/// be used for several languages.
+#[derive(Clone)]
+pub struct FormatterHook(Arc<FormatterFn>);
+
+type FormatterFn = dyn Fn(Language, &Path) -> std::io::Result<()> + Send + Sync;
+
 /// Track types definitions provided by external modules.
 pub type ExternalDefinitions =
     std::collections::BTreeMap</* module */ String, /* type names */ Vec<String>>;
@@ -29,6 +96,11 @@ pub type ExternalDefinitions =
 pub type DocComments =
     std::collections::BTreeMap</* qualified name */ Vec<String>, /* comment */ String>;
 
+/// Track base classes to be added to particular definitions (see
+/// `python3::CodeGenerator::with_base_class`).
+pub type BaseClasses =
+    std::collections::BTreeMap</* qualified name */ Vec<String>, /* class */ String>;
+
 /// Track custom code to be added to particular definitions (use with care!).
 pub type CustomCode = std::collections::BTreeMap<
     /* qualified name */ Vec<String>,
@@ -36,6 +108,8 @@ pub type CustomCode = std::collections::BTreeMap<
 >;
 
 /// How to copy generated source code and available runtimes for a given language.
+///
+/// The provided methods are available when `Self::Error` can be built from a `ConfigError`.
 pub trait SourceInstaller {
     type Error;
 
@@ -54,6 +128,51 @@ pub trait SourceInstaller {
 
     /// Install the Libra Canonical Serialization (BCS) runtime.
     fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error>;
+
+    /// Install the postcard runtime. By default, the encoding is reported as unsupported.
+    fn install_postcard_runtime(&self) -> std::result::Result<(), Self::Error>
+    where
+        Self::Error: From<crate::ConfigError>,
+    {
+        Err(crate::ConfigError::Runtime {
+            encoding: Encoding::Postcard,
+        }
+        .into())
+    }
+
+    /// Install the MessagePack runtime. By default, the encoding is reported as unsupported.
+    fn install_msgpack_runtime(&self) -> std::result::Result<(), Self::Error>
+    where
+        Self::Error: From<crate::ConfigError>,
+    {
+        Err(crate::ConfigError::Runtime {
+            encoding: Encoding::MessagePack,
+        }
+        .into())
+    }
+
+    /// Create the module for the registry, then install the serde runtime and the runtimes of
+    /// the encodings selected in the config (see `CodeGeneratorConfig::with_encodings`).
+    fn install_everything(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &serde_reflection::Registry,
+    ) -> std::result::Result<(), Self::Error>
+    where
+        Self::Error: From<crate::ConfigError>,
+    {
+        self.install_module(config, registry)?;
+        self.install_serde_runtime()?;
+        for encoding in &config.encodings {
+            match encoding {
+                Encoding::Bincode => self.install_bincode_runtime()?,
+                Encoding::Bcs => self.install_bcs_runtime()?,
+                Encoding::Postcard => self.install_postcard_runtime()?,
+                Encoding::MessagePack => self.install_msgpack_runtime()?,
+            }
+        }
+        Ok(())
+    }
 }
 
 impl CodeGeneratorConfig {
@@ -62,11 +181,24 @@ impl CodeGeneratorConfig {
         Self {
             module_name,
             serialization: true,
+            deserialization: None,
             encodings: BTreeSet::new(),
             external_definitions: BTreeMap::new(),
             comments: BTreeMap::new(),
             custom_code: BTreeMap::new(),
             c_style_enums: false,
+            postcard_floats: false,
+            allow_nonstandard_floats: false,
+            keyword_escaping: None,
+            non_ascii_names: NonAsciiNames::PassThrough,
+            formatter: None,
+            size_estimators: false,
+            bcs_length_prefix: false,
+            runtime_schema: false,
+            elision_containers: BTreeSet::new(),
+            tuple_field_names: BTreeMap::new(),
+            map_types: BTreeMap::new(),
+            indent: None,
         }
     }
 
@@ -74,12 +206,21 @@ impl CodeGeneratorConfig {
         &self.module_name
     }
 
-    /// Whether to include serialization methods.
+    /// Whether to include serialization methods. Unless `with_deserialization` is used, this
+    /// also decides whether to include deserialization methods.
     pub fn with_serialization(mut self, serialization: bool) -> Self {
         self.serialization = serialization;
         self
     }
 
+    /// Whether to include deserialization methods, independently of serialization methods,
+    /// e.g. `with_serialization(false).with_deserialization(true)` for clients that only decode
+    /// data. Only supported by some languages (see `Capabilities::one_way_serialization`).
+    pub fn with_deserialization(mut self, deserialization: bool) -> Self {
+        self.deserialization = Some(deserialization);
+        self
+    }
+
     /// Whether to include specialized methods for specific encodings.
     pub fn with_encodings<I>(mut self, encodings: I) -> Self
     where
@@ -117,6 +258,259 @@ impl CodeGeneratorConfig {
         self.c_style_enums = c_style_enums;
         self
     }
+
+    /// Whether to accept `f32` and `f64` formats when generating code for the postcard
+    /// encoding. Disabled by default so that code meant for embedded targets stays float-free.
+    pub fn with_postcard_floats(mut self, postcard_floats: bool) -> Self {
+        self.postcard_floats = postcard_floats;
+        self
+    }
+
+    /// Whether to accept `f32` and `f64` formats when generating code for the BCS encoding.
+    /// BCS does not define floating-point numbers, hence such formats are rejected by default.
+    /// Enable this only with a (forked) BCS runtime that serializes floats.
+    pub fn with_allow_nonstandard_floats(mut self, allow_nonstandard_floats: bool) -> Self {
+        self.allow_nonstandard_floats = allow_nonstandard_floats;
+        self
+    }
+
+    /// How to escape identifiers that are reserved keywords. Default: `Suffix("_")` in Java,
+    /// OCaml, PHP, Python, Solidity, TypeScript, and Zig, and `Error` in the other languages.
+    pub fn with_keyword_escaping(mut self, keyword_escaping: KeywordEscaping) -> Self {
+        self.keyword_escaping = Some(keyword_escaping);
+        self
+    }
+
+    /// How to deal with names that are not ASCII identifiers. Default:
+    /// `NonAsciiNames::PassThrough`.
+    ///
+    /// Transliterated names replace the original ones everywhere in the generated code,
+    /// including JSON field names and runtime schemas, and in the other settings of the config
+    /// (e.g. `with_comments`), which must use the new names. See
+    /// `validation::renamed_identifiers` for the list of renamed containers, fields, and variants.
+    pub fn with_non_ascii_names(mut self, non_ascii_names: NonAsciiNames) -> Self {
+        self.non_ascii_names = non_ascii_names;
+        self
+    }
+
+    /// Indentation of the generated code, instead of the usual style of each language, e.g.
+    /// 4 spaces in Python and Rust, 2 spaces in TypeScript, or tabs in Go. Lines that are
+    /// aligned rather than indented keep their extra spaces. YAML documents (OpenAPI) cannot be
+    /// indented with tabs.
+    pub fn with_indent(mut self, indent: IndentConfig) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Function to call on each source file written by `SourceInstaller::install_module`.
+    pub fn with_formatter(mut self, formatter: FormatterHook) -> Self {
+        self.formatter = Some(formatter);
+        self
+    }
+
+    /// Whether to include methods computing the serialized size of values without
+    /// serializing them (Java and TypeScript only). Requires serialization methods.
+    pub fn with_size_estimators(mut self, size_estimators: bool) -> Self {
+        self.size_estimators = size_estimators;
+        self
+    }
+
+    /// Whether the BCS serialization methods of the generated types frame their output with a
+    /// 4-byte big-endian length prefix, which the deserialization methods then expect and check
+    /// (Java and Python only). Default: false.
+    pub fn with_bcs_length_prefix(mut self, bcs_length_prefix: bool) -> Self {
+        self.bcs_length_prefix = bcs_length_prefix;
+        self
+    }
+
+    /// Whether to attach a description of its format to each generated container, as a
+    /// class attribute `SCHEMA` (Python and TypeScript only). Schemas are nested literals
+    /// mirroring `ContainerFormat`:
+    /// * containers: `{"kind": "unit_struct"}`, `{"kind": "newtype_struct", "format": F}`,
+    ///   `{"kind": "tuple_struct", "formats": [F, ..]}`, `{"kind": "struct", "fields": [["name", F], ..]}`,
+    ///   or `{"kind": "enum", "variants": [[index, "Name", V], ..]}`;
+    /// * variants: `{"kind": "unit"}`, `{"kind": "newtype", "format": F}`,
+    ///   `{"kind": "tuple", "formats": [F, ..]}`, or `{"kind": "struct", "fields": [["name", F], ..]}`;
+    /// * formats: `{"kind": "ref", "name": "MyType"}` for other containers (including recursive
+    ///   ones), `{"kind": "option", "format": F}`, `{"kind": "seq", "format": F}`,
+    ///   `{"kind": "map", "key": F, "value": F}`, `{"kind": "tuple", "formats": [F, ..]}`,
+    ///   `{"kind": "array", "format": F, "size": N}`, or `{"kind": K}` for primitive types, where `K`
+    ///   is one of `unit`, `bool`, `i8`, `i16`, `i32`, `i64`, `i128`, `u8`, `u16`, `u32`, `u64`,
+    ///   `u128`, `f32`, `f64`, `char`, `str`, and `bytes`.
+    ///
+    /// The runtimes provide a helper `walk_schema` (Python) or `walkSchema` (TypeScript) to
+    /// visit the nodes of a schema.
+    ///
+    /// Python classes always have a schema with the MessagePack encoding, whose runtime reads
+    /// the names of fields and variants from it.
+    pub fn with_runtime_schema(mut self, runtime_schema: bool) -> Self {
+        self.runtime_schema = runtime_schema;
+        self
+    }
+
+    /// Structs that also get a compact encoding (Rust, Python, and TypeScript only). The
+    /// compact encoding is a presence bitmap followed by the BCS encoding of the fields that
+    /// are not at their default value. Fields with a default value are the ones of type
+    /// `bool`, integers, strings, bytes, options, sequences, and maps; the `i`-th of them is
+    /// present if bit `i % 8` of byte `i / 8` of the bitmap is set. Other fields are always
+    /// encoded.
+    ///
+    /// This encoding deliberately differs from the standard ones: it is only available through
+    /// the methods `compact_serialize` (Rust, Python) or `compactSerialize` (TypeScript), and
+    /// `compact_deserialize` (Python) or `compactDeserialize` (TypeScript).
+    pub fn with_elision_containers(mut self, elision_containers: BTreeSet<String>) -> Self {
+        self.elision_containers = elision_containers;
+        self
+    }
+
+    /// Names of the fields of tuple variants, indexed by enum and variant names (Swift, Java,
+    /// and TypeScript only). Instead of `x0`, `x1`, .. (Swift) or `field0`, `field1`, ..
+    /// (Java, TypeScript), the values of a named tuple variant are labeled after the given
+    /// names, like the fields of a struct variant. Serialized data stays the same.
+    ///
+    /// Code generation fails if the number of names does not match the number of fields.
+    pub fn with_tuple_field_names(
+        mut self,
+        tuple_field_names: BTreeMap<(String, String), Vec<String>>,
+    ) -> Self {
+        self.tuple_field_names = tuple_field_names;
+        self
+    }
+
+    /// Paths of the types used for maps, indexed by language (Rust only), instead of
+    /// `std::collections::BTreeMap`. For instance, `indexmap::IndexMap` preserves the order of
+    /// entries when a value is deserialized then serialized again.
+    ///
+    /// The type must support the derive macros of the generated code, and the corresponding
+    /// dependency is not added to the `Cargo.toml` file written by the installer.
+    pub fn with_map_types(mut self, map_types: BTreeMap<Language, String>) -> Self {
+        self.map_types = map_types;
+        self
+    }
+
+    /// How to escape keywords in the given language (see `with_keyword_escaping`).
+    pub(crate) fn keyword_escaping(&self, language: Language) -> KeywordEscaping {
+        match &self.keyword_escaping {
+            Some(keyword_escaping) => keyword_escaping.clone(),
+            None => match language {
+                Language::Java
+                | Language::OCaml
+                | Language::Php
+                | Language::Python3
+                | Language::Solidity
+                | Language::TypeScript
+                | Language::Zig => KeywordEscaping::Suffix("_".to_string()),
+                _ => KeywordEscaping::Error,
+            },
+        }
+    }
+
+    /// Whether to include deserialization methods (see `with_deserialization`).
+    pub(crate) fn has_deserialization(&self) -> bool {
+        self.deserialization.unwrap_or(self.serialization)
+    }
+
+    /// Whether to include serialization or deserialization methods, hence their helpers.
+    pub(crate) fn has_serde_methods(&self) -> bool {
+        self.serialization || self.has_deserialization()
+    }
+
+    pub(crate) fn format_source(&self, language: Language, path: &Path) -> std::io::Result<()> {
+        match &self.formatter {
+            Some(formatter) => formatter.format(language, path),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FormatterHook {
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(Language, &Path) -> std::io::Result<()> + Send + Sync + 'static,
+    {
+        Self(Arc::new(hook))
+    }
+
+    /// Run `program` with the given arguments followed by the path of each source file in
+    /// `language`. Files are left untouched, with a warning, if `program` is not installed.
+    pub fn command(language: Language, program: &str, args: &[&str]) -> Self {
+        let program = program.to_string();
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        Self::new(move |target, path| {
+            if target != language {
+                return Ok(());
+            }
+            match Command::new(&program).args(&args).arg(path).status() {
+                Ok(status) if status.success() => Ok(()),
+                Ok(status) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("{} failed on {} ({})", program, path.display(), status),
+                )),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!(
+                        "Warning: {} was not found, not formatting {}",
+                        program,
+                        path.display()
+                    );
+                    Ok(())
+                }
+                Err(error) => Err(error),
+            }
+        })
+    }
+
+    /// Format Rust sources with `rustfmt`.
+    pub fn rustfmt() -> Self {
+        Self::command(Language::Rust, "rustfmt", &["--edition", "2018"])
+    }
+
+    /// Format TypeScript sources with `prettier`.
+    pub fn prettier() -> Self {
+        Self::command(Language::TypeScript, "prettier", &["--write"])
+    }
+
+    /// Format Go sources with `gofmt`.
+    pub fn gofmt() -> Self {
+        Self::command(Language::Go, "gofmt", &["-w"])
+    }
+
+    /// Format Python sources with `black`.
+    pub fn black() -> Self {
+        Self::command(Language::Python3, "black", &["--quiet"])
+    }
+
+    /// Combination of `rustfmt`, `prettier`, `gofmt`, and `black`.
+    pub fn standard() -> Self {
+        let hooks = [
+            Self::rustfmt(),
+            Self::prettier(),
+            Self::gofmt(),
+            Self::black(),
+        ];
+        Self::new(move |language, path| {
+            for hook in &hooks {
+                hook.format(language, path)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub(crate) fn format(&self, language: Language, path: &Path) -> std::io::Result<()> {
+        (self.0)(language, path)
+    }
+}
+
+impl Serialize for FormatterHook {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Hooks are only described by their presence.
+        serializer.serialize_unit_struct("FormatterHook")
+    }
+}
+
+impl std::fmt::Debug for FormatterHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FormatterHook")
+    }
 }
 
 impl Encoding {
@@ -124,6 +518,8 @@ impl Encoding {
         match self {
             Encoding::Bincode => "bincode",
             Encoding::Bcs => "bcs",
+            Encoding::Postcard => "postcard",
+            Encoding::MessagePack => "msgpack",
         }
     }
 }
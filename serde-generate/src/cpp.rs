@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    analyzer,
+    analyzer, common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
@@ -14,6 +15,22 @@ use std::{
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: true,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
+/// Doc comment of the deserialization functions generated with
+/// `CodeGenerator::with_zero_copy`.
+const ZERO_COPY_DESERIALIZE_DOC: &str =
+    "/// Deserialize without copying strings and bytes: the result holds views into\n/// `input`, which must outlive it.\n";
+
 /// Main configuration object for code-generation in C++.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -21,6 +38,12 @@ pub struct CodeGenerator<'a> {
     /// Mapping from external type names to suitably qualified names (e.g. "MyClass" -> "name::MyClass").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether enums are generated as aliases of `std::variant`.
+    variant_enums: bool,
+    /// Whether containers come with `operator==` and a specialization of `std::hash`.
+    comparisons: bool,
+    /// Whether strings and bytes are generated as views into the input.
+    zero_copy: bool,
 }
 
 /// Shared state for the code generation of a C++ source file.
@@ -35,14 +58,21 @@ struct CppEmitter<'a, T> {
     known_sizes: HashSet<&'a str>,
     /// Current namespace (e.g. vec!["name", "MyClass"])
     current_namespace: Vec<String>,
+    /// Qualified names and fields of the structs that need a specialization of `std::hash`.
+    hashed_structs: Vec<(String, Vec<String>)>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a C++ code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("C++ does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a C++ code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Cpp, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -50,17 +80,66 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}::{}", namespace, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             external_qualified_names,
+            variant_enums: false,
+            comparisons: true,
+            zero_copy: false,
+        })
+    }
+
+    /// Whether enums are generated as aliases of `std::variant`, e.g.
+    /// `using Shape = std::variant<Shape_Circle, Shape_Rect>;`, instead of structs holding such a
+    /// variant in their field `value`. Alternatives follow the order of the variant indices, and
+    /// serialization functions are free functions such as `Shape_bcsSerialize` and
+    /// `Shape_bcsDeserialize`. Custom code attached to such enums is written after the alias.
+    /// Default: false.
+    pub fn with_variant_enums(mut self, variant_enums: bool) -> Self {
+        self.variant_enums = variant_enums;
+        self
+    }
+
+    /// Whether containers come with a memberwise `operator==` and a specialization of
+    /// `std::hash`, so that values can be compared and used as keys of `std::unordered_map`.
+    /// Fields are hashed with the trait `serde::Hashable` of the runtime, which also covers
+    /// vectors, options, maps, and tuples. Default: true.
+    pub fn with_comparisons(mut self, comparisons: bool) -> Self {
+        self.comparisons = comparisons;
+        self
+    }
+
+    /// Whether strings and bytes are generated as the views `std::string_view` and
+    /// `serde::bytes_view`. Deserialization functions then take a `serde::bytes_view` of the
+    /// input and return values pointing into it instead of copying strings and bytes, so the
+    /// caller must keep the input alive as long as the values. Default: false.
+    pub fn with_zero_copy(mut self, zero_copy: bool) -> Self {
+        self.zero_copy = zero_copy;
+        self
+    }
+
+    fn naming_rules(&self) -> validation::NamingRules<'a> {
+        let rules = validation::NamingRules::new(self.config, Language::Cpp);
+        if self.variant_enums {
+            rules.with_cpp_variant_classes()
+        } else {
+            rules
         }
     }
 
+    /// Report the parts of the registry that cannot be generated in C++, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support_with(&self.naming_rules(), registry)
+    }
+
     pub fn output(
         &self,
         out: &mut dyn Write,
         registry: &Registry,
     ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let registry = validation::check_registry_with(&self.naming_rules(), registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -68,11 +147,13 @@ impl<'a> CodeGenerator<'a> {
             .map(String::from)
             .collect();
         let mut emitter = CppEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             known_names: HashSet::new(),
             known_sizes: HashSet::new(),
             current_namespace,
+            hashed_structs: Vec::new(),
         };
 
         emitter.output_preamble()?;
@@ -84,7 +165,10 @@ impl<'a> CodeGenerator<'a> {
         for name in entries {
             for dependency in &dependencies[name] {
                 if !emitter.known_names.contains(dependency) {
-                    emitter.output_container_forward_definition(dependency)?;
+                    emitter.output_container_forward_definition(
+                        dependency,
+                        registry.get(*dependency),
+                    )?;
                     emitter.known_names.insert(*dependency);
                 }
             }
@@ -99,6 +183,9 @@ impl<'a> CodeGenerator<'a> {
         for (name, format) in registry {
             emitter.output_container_traits(name, format)?;
         }
+        if self.comparisons {
+            emitter.output_hash_specializations()?;
+        }
         Ok(())
     }
 }
@@ -174,6 +261,10 @@ where
         Ok(())
     }
 
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::Cpp, name)
+    }
+
     /// Compute a fully qualified reference to the container type `name`.
     fn quote_qualified_name(&self, name: &str) -> String {
         self.generator
@@ -211,7 +302,9 @@ where
             F32 => "float".into(),
             F64 => "double".into(),
             Char => "char32_t".into(),
+            Str if self.generator.zero_copy => "std::string_view".into(),
             Str => "std::string".into(),
+            Bytes if self.generator.zero_copy => "serde::bytes_view".into(),
             Bytes => "std::vector<uint8_t>".into(),
 
             Option(format) => format!(
@@ -246,14 +339,16 @@ where
             .join(", ")
     }
 
+    /// Write a struct named `class_name`, whose comments and custom code are found under `name`.
     fn output_struct_or_variant_container(
         &mut self,
         name: &str,
+        class_name: &str,
         fields: &[Named<Format>],
     ) -> Result<()> {
         writeln!(self.out)?;
         self.output_comment(name)?;
-        writeln!(self.out, "struct {} {{", name)?;
+        writeln!(self.out, "struct {} {{", class_name)?;
         self.enter_class(name);
         for field in fields {
             self.output_comment(&field.name)?;
@@ -261,19 +356,24 @@ where
                 self.out,
                 "{} {};",
                 self.quote_type(&field.value, true),
-                field.name
+                self.quote_field(&field.name)
             )?;
         }
         if !fields.is_empty() {
             writeln!(self.out)?;
         }
-        self.output_class_method_declarations(name)?;
+        self.output_class_method_declarations(class_name)?;
         self.output_custom_code()?;
         self.leave_class();
         writeln!(self.out, "}};")
     }
 
-    fn output_variant(&mut self, name: &str, variant: &VariantFormat) -> Result<()> {
+    fn output_variant(
+        &mut self,
+        name: &str,
+        class_name: &str,
+        variant: &VariantFormat,
+    ) -> Result<()> {
         use VariantFormat::*;
         let fields = match variant {
             Unit => Vec::new(),
@@ -288,13 +388,77 @@ where
             Struct(fields) => fields.clone(),
             Variable(_) => panic!("incorrect value"),
         };
-        self.output_struct_or_variant_container(name, &fields)
+        self.output_struct_or_variant_container(name, class_name, &fields)
     }
 
-    fn output_container_forward_definition(&mut self, name: &str) -> Result<()> {
+    fn output_container_forward_definition(
+        &mut self,
+        name: &str,
+        format: Option<&ContainerFormat>,
+    ) -> Result<()> {
+        if let Some(variants) = format.and_then(|format| self.variant_enum(format)) {
+            for variant in variants.values() {
+                writeln!(self.out, "\nstruct {}_{};", name, variant.name)?;
+            }
+            return self.output_variant_alias(name, variants);
+        }
         writeln!(self.out, "\nstruct {};", name)
     }
 
+    /// The variants of an enum generated as an alias of `std::variant` (see
+    /// `CodeGenerator::with_variant_enums`), if so configured.
+    fn variant_enum<'b>(
+        &self,
+        format: &'b ContainerFormat,
+    ) -> Option<&'b BTreeMap<u32, Named<VariantFormat>>> {
+        match format {
+            ContainerFormat::Enum(variants) if self.generator.variant_enums => Some(variants),
+            _ => None,
+        }
+    }
+
+    fn output_variant_alias(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "using {} = std::variant<{}>;",
+            name,
+            variants
+                .values()
+                .map(|variant| format!("{}_{}", name, variant.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// Write the classes of the variants (named after the enum and the variant) followed by the
+    /// alias `std::variant` of the enum, unless it was declared already.
+    fn output_variant_enum_container(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        self.current_namespace.push(name.to_string());
+        for (expected_index, (index, variant)) in variants.iter().enumerate() {
+            assert_eq!(*index, expected_index as u32);
+            let class_name = format!("{}_{}", name, variant.name);
+            self.output_variant(&variant.name, &class_name, &variant.value)?;
+        }
+        self.current_namespace.pop();
+        if !self.known_names.contains(name) {
+            self.output_variant_alias(name, variants)?;
+        }
+        self.current_namespace.push(name.to_string());
+        self.output_custom_code()?;
+        self.current_namespace.pop();
+        Ok(())
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
@@ -306,7 +470,7 @@ where
         self.enter_class(name);
         for (expected_index, (index, variant)) in variants.iter().enumerate() {
             assert_eq!(*index, expected_index as u32);
-            self.output_variant(&variant.name, &variant.value)?;
+            self.output_variant(&variant.name, &variant.name, &variant.value)?;
         }
         writeln!(
             self.out,
@@ -325,11 +489,13 @@ where
     }
 
     fn output_class_method_declarations(&mut self, name: &str) -> Result<()> {
-        writeln!(
-            self.out,
-            "friend bool operator==(const {}&, const {}&);",
-            name, name
-        )?;
+        if self.generator.comparisons {
+            writeln!(
+                self.out,
+                "friend bool operator==(const {}&, const {}&);",
+                name, name
+            )?;
+        }
         if self.generator.config.serialization {
             for encoding in &self.generator.config.encodings {
                 writeln!(
@@ -337,17 +503,37 @@ where
                     "std::vector<uint8_t> {}Serialize() const;",
                     encoding.name()
                 )?;
-                writeln!(
-                    self.out,
-                    "static {} {}Deserialize(std::vector<uint8_t>);",
-                    name,
-                    encoding.name()
-                )?;
+                if self.generator.zero_copy {
+                    write!(self.out, "{}", ZERO_COPY_DESERIALIZE_DOC)?;
+                    writeln!(
+                        self.out,
+                        "static {0} {1}Deserialize(serde::bytes_view input);\n\
+                         static {0} {1}Deserialize(std::vector<uint8_t> &&) = delete;",
+                        name,
+                        encoding.name()
+                    )?;
+                } else {
+                    writeln!(
+                        self.out,
+                        "static {} {}Deserialize(std::vector<uint8_t>);",
+                        name,
+                        encoding.name()
+                    )?;
+                }
             }
         }
         Ok(())
     }
 
+    /// The type of the input of deserialization functions.
+    fn deserialization_input_type(&self) -> &'static str {
+        if self.generator.zero_copy {
+            "serde::bytes_view"
+        } else {
+            "std::vector<uint8_t>"
+        }
+    }
+
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
@@ -361,12 +547,16 @@ where
                 value: Format::Tuple(formats.clone()),
             }],
             Struct(fields) => fields.clone(),
+            Enum(variants) if self.generator.variant_enums => {
+                self.output_variant_enum_container(name, variants)?;
+                return Ok(());
+            }
             Enum(variants) => {
                 self.output_enum_container(name, variants)?;
                 return Ok(());
             }
         };
-        self.output_struct_or_variant_container(name, &fields)
+        self.output_struct_or_variant_container(name, name, &fields)
     }
 
     fn output_struct_equality_test(&mut self, name: &str, fields: &[&str]) -> Result<()> {
@@ -416,7 +606,7 @@ inline std::vector<uint8_t> {}::{}Serialize() const {{
         writeln!(
             self.out,
             r#"
-inline {} {}::{}Deserialize(std::vector<uint8_t> input) {{
+inline {} {}::{}Deserialize({} input) {{
     auto deserializer = serde::{}Deserializer(input);
     auto value = serde::Deserializable<{}>::deserialize(deserializer);
     if (deserializer.get_buffer_offset() < input.size()) {{
@@ -427,6 +617,7 @@ inline {} {}::{}Deserialize(std::vector<uint8_t> input) {{
             name,
             name,
             encoding.name(),
+            self.deserialization_input_type(),
             encoding.name().to_camel_case(),
             name,
         )
@@ -505,7 +696,9 @@ template <typename Deserializer>
         is_container: bool,
     ) -> Result<()> {
         self.output_open_namespace()?;
-        self.output_struct_equality_test(name, fields)?;
+        if self.generator.comparisons {
+            self.output_struct_equality_test(name, fields)?;
+        }
         if self.generator.config.serialization {
             for encoding in &self.generator.config.encodings {
                 self.output_struct_serialize_for_encoding(name, *encoding)?;
@@ -514,6 +707,10 @@ template <typename Deserializer>
         }
         self.output_close_namespace()?;
         let namespaced_name = self.quote_qualified_name(name);
+        self.hashed_structs.push((
+            namespaced_name.clone(),
+            fields.iter().map(|field| field.to_string()).collect(),
+        ));
         if self.generator.config.serialization {
             self.output_struct_serializable(&namespaced_name, fields, is_container)?;
             self.output_struct_deserializable(&namespaced_name, fields, is_container)?;
@@ -521,15 +718,168 @@ template <typename Deserializer>
         Ok(())
     }
 
-    fn get_variant_fields(format: &VariantFormat) -> Vec<&str> {
+    /// Write the serialization of an enum generated as an alias of `std::variant`: the index of
+    /// the alternative is the index of the variant.
+    fn output_variant_enum_traits(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        if !self.generator.config.serialization {
+            return Ok(());
+        }
+        let namespaced_name = self.quote_qualified_name(name);
+        writeln!(
+            self.out,
+            r#"
+template <>
+template <typename Serializer>
+void serde::Serializable<{0}>::serialize(const {0} &obj, Serializer &serializer) {{
+    serializer.increase_container_depth();
+    serializer.serialize_variant_index(obj.index());
+    switch (obj.index()) {{"#,
+            namespaced_name,
+        )?;
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                r#"    case {0}:
+        serde::Serializable<{1}_{2}>::serialize(std::get<{0}>(obj), serializer);
+        break;"#,
+                index, namespaced_name, variant.name,
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"    }}
+    serializer.decrease_container_depth();
+}}
+
+template <>
+template <typename Deserializer>
+{0} serde::Deserializable<{0}>::deserialize(Deserializer &deserializer) {{
+    deserializer.increase_container_depth();
+    {0} obj;
+    switch (deserializer.deserialize_variant_index()) {{"#,
+            namespaced_name,
+        )?;
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                r#"    case {0}:
+        obj.emplace<{0}>(serde::Deserializable<{1}_{2}>::deserialize(deserializer));
+        break;"#,
+                index, namespaced_name, variant.name,
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"    default:
+        throw serde::deserialization_error("Unknown variant index for {}");
+    }}
+    deserializer.decrease_container_depth();
+    return obj;
+}}"#,
+            name,
+        )?;
+
+        // Functions are defined after the specializations that they use.
+        self.output_open_namespace()?;
+        let zero_copy_doc = if self.generator.zero_copy {
+            ZERO_COPY_DESERIALIZE_DOC
+        } else {
+            ""
+        };
+        for encoding in &self.generator.config.encodings {
+            writeln!(
+                self.out,
+                r#"
+inline std::vector<uint8_t> {0}_{1}Serialize(const {0} &value) {{
+    auto serializer = serde::{2}Serializer();
+    serde::Serializable<{0}>::serialize(value, serializer);
+    return std::move(serializer).bytes();
+}}
+
+{3}inline {0} {0}_{1}Deserialize({4} input) {{
+    auto deserializer = serde::{2}Deserializer(input);
+    auto value = serde::Deserializable<{0}>::deserialize(deserializer);
+    if (deserializer.get_buffer_offset() < input.size()) {{
+        throw serde::deserialization_error("Some input bytes were not read");
+    }}
+    return value;
+}}"#,
+                name,
+                encoding.name(),
+                encoding.name().to_camel_case(),
+                zero_copy_doc,
+                self.deserialization_input_type(),
+            )?;
+            if self.generator.zero_copy {
+                writeln!(
+                    self.out,
+                    "\n{0} {0}_{1}Deserialize(std::vector<uint8_t> &&) = delete;",
+                    name,
+                    encoding.name(),
+                )?;
+            }
+        }
+        self.output_close_namespace()
+    }
+
+    /// Write the specializations of `std::hash` for all the structs seen so far. All classes are
+    /// declared before the definitions of their call operators, so that recursive types can
+    /// hash each other.
+    fn output_hash_specializations(&mut self) -> Result<()> {
+        for (name, _) in &self.hashed_structs {
+            writeln!(
+                self.out,
+                r#"
+template <>
+struct std::hash<{0}> {{
+    std::size_t operator()(const {0} &) const;
+}};"#,
+                name,
+            )?;
+        }
+        for (name, fields) in &self.hashed_structs {
+            if fields.is_empty() {
+                writeln!(
+                    self.out,
+                    "\ninline std::size_t std::hash<{0}>::operator()(const {0} &) const {{ return 0; }}",
+                    name,
+                )?;
+                continue;
+            }
+            writeln!(
+                self.out,
+                "\ninline std::size_t std::hash<{0}>::operator()(const {0} &obj) const {{",
+                name,
+            )?;
+            self.out.indent();
+            writeln!(self.out, "std::size_t seed = 0;")?;
+            for field in fields {
+                writeln!(
+                    self.out,
+                    "serde::hash_combine(seed, serde::Hashable<decltype(obj.{0})>::hash(obj.{0}));",
+                    field,
+                )?;
+            }
+            writeln!(self.out, "return seed;")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+        }
+        Ok(())
+    }
+
+    fn get_variant_fields(&self, format: &VariantFormat) -> Vec<String> {
         use VariantFormat::*;
         match format {
             Unit => Vec::new(),
-            NewType(_format) => vec!["value"],
-            Tuple(_formats) => vec!["value"],
+            NewType(_format) => vec!["value".to_string()],
+            Tuple(_formats) => vec!["value".to_string()],
             Struct(fields) => fields
                 .iter()
-                .map(|field| field.name.as_str())
+                .map(|field| self.quote_field(&field.name))
                 .collect::<Vec<_>>(),
             Variable(_) => panic!("incorrect value"),
         }
@@ -541,20 +891,35 @@ template <typename Deserializer>
             UnitStruct => self.output_struct_traits(name, &[], true),
             NewTypeStruct(_format) => self.output_struct_traits(name, &["value"], true),
             TupleStruct(_formats) => self.output_struct_traits(name, &["value"], true),
-            Struct(fields) => self.output_struct_traits(
-                name,
-                &fields
+            Struct(fields) => {
+                let fields = fields
                     .iter()
-                    .map(|field| field.name.as_str())
-                    .collect::<Vec<_>>(),
-                true,
-            ),
+                    .map(|field| self.quote_field(&field.name))
+                    .collect::<Vec<_>>();
+                self.output_struct_traits(
+                    name,
+                    &fields.iter().map(String::as_str).collect::<Vec<_>>(),
+                    true,
+                )
+            }
+            Enum(variants) if self.generator.variant_enums => {
+                for variant in variants.values() {
+                    let fields = self.get_variant_fields(&variant.value);
+                    self.output_struct_traits(
+                        &format!("{}_{}", name, variant.name),
+                        &fields.iter().map(String::as_str).collect::<Vec<_>>(),
+                        false,
+                    )?;
+                }
+                self.output_variant_enum_traits(name, variants)
+            }
             Enum(variants) => {
                 self.output_struct_traits(name, &["value"], true)?;
                 for variant in variants.values() {
+                    let fields = self.get_variant_fields(&variant.value);
                     self.output_struct_traits(
                         &format!("{}::{}", name, variant.name),
-                        &Self::get_variant_fields(&variant.value),
+                        &fields.iter().map(String::as_str).collect::<Vec<_>>(),
                         false,
                     )?;
                 }
@@ -567,17 +932,112 @@ template <typename Deserializer>
 /// Installer for generated source files in C++.
 pub struct Installer {
     install_dir: PathBuf,
+    manifest: bool,
+    cmake_target: Option<String>,
+    cmake_namespace: Option<String>,
+    variant_enums: bool,
+    comparisons: bool,
+    zero_copy: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            manifest: false,
+            cmake_target: None,
+            cmake_namespace: None,
+            variant_enums: false,
+            comparisons: true,
+            zero_copy: false,
+        }
+    }
+
+    /// Whether to write a `CMakeLists.txt` file defining an interface library for the generated
+    /// header and the runtimes installed next to it, with install rules. Default: false.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Name of the CMake library target. Default: the module name, with `::` replaced by `_`.
+    pub fn with_cmake_target(mut self, target: String) -> Self {
+        self.cmake_target = Some(target);
+        self
+    }
+
+    /// Namespace of the exported CMake target, e.g. `my_project` for `my_project::my_target`.
+    /// Default: the name of the target.
+    pub fn with_cmake_namespace(mut self, namespace: String) -> Self {
+        self.cmake_namespace = Some(namespace);
+        self
+    }
+
+    /// Whether enums are generated as aliases of `std::variant` (see
+    /// `CodeGenerator::with_variant_enums`).
+    pub fn with_variant_enums(mut self, variant_enums: bool) -> Self {
+        self.variant_enums = variant_enums;
+        self
+    }
+
+    /// Whether containers come with comparisons and hashing (see
+    /// `CodeGenerator::with_comparisons`).
+    pub fn with_comparisons(mut self, comparisons: bool) -> Self {
+        self.comparisons = comparisons;
+        self
+    }
+
+    /// Whether strings and bytes are generated as views (see `CodeGenerator::with_zero_copy`).
+    pub fn with_zero_copy(mut self, zero_copy: bool) -> Self {
+        self.zero_copy = zero_copy;
+        self
+    }
+
+    fn write_cmake_lists(&self, module_name: &str) -> Result<()> {
+        let target = self
+            .cmake_target
+            .clone()
+            .unwrap_or_else(|| module_name.replace("::", "_"));
+        let namespace = self.cmake_namespace.as_ref().unwrap_or(&target);
+        std::fs::create_dir_all(&self.install_dir)?;
+        let mut file = std::fs::File::create(self.install_dir.join("CMakeLists.txt"))?;
+        // Runtimes are installed in the same directory as the module, possibly later on.
+        write!(
+            file,
+            r#"cmake_minimum_required(VERSION 3.14)
+project({0} LANGUAGES CXX)
+
+include(GNUInstallDirs)
+
+add_library({0} INTERFACE)
+add_library({1}::{0} ALIAS {0})
+target_compile_features({0} INTERFACE cxx_std_17)
+target_include_directories({0} INTERFACE
+    $<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}>
+    $<INSTALL_INTERFACE:${{CMAKE_INSTALL_INCLUDEDIR}}/{0}>
+)
+
+install(TARGETS {0} EXPORT {0}Targets)
+install(DIRECTORY ${{CMAKE_CURRENT_SOURCE_DIR}}/
+    DESTINATION ${{CMAKE_INSTALL_INCLUDEDIR}}/{0}
+    FILES_MATCHING PATTERN "*.hpp"
+)
+install(EXPORT {0}Targets
+    NAMESPACE {1}::
+    DESTINATION ${{CMAKE_INSTALL_LIBDIR}}/cmake/{0}
+)
+"#,
+            target, namespace
+        )
+    }
+
+    fn header_path(&self, name: &str) -> PathBuf {
+        self.install_dir.join(name.to_string() + ".hpp")
     }
 
     fn create_header_file(&self, name: &str) -> Result<std::fs::File> {
-        let dir_path = &self.install_dir;
-        std::fs::create_dir_all(dir_path)?;
-        std::fs::File::create(dir_path.join(name.to_string() + ".hpp"))
+        std::fs::create_dir_all(&self.install_dir)?;
+        std::fs::File::create(self.header_path(name))
     }
 }
 
@@ -590,8 +1050,16 @@ impl crate::SourceInstaller for Installer {
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
         let mut file = self.create_header_file(&config.module_name)?;
-        let generator = CodeGenerator::new(config);
-        generator.output(&mut file, registry)
+        let generator = CodeGenerator::try_new(config)?
+            .with_variant_enums(self.variant_enums)
+            .with_comparisons(self.comparisons)
+            .with_zero_copy(self.zero_copy);
+        generator.output(&mut file, registry)?;
+        config.format_source(Language::Cpp, &self.header_path(&config.module_name))?;
+        if self.manifest {
+            self.write_cmake_lists(&config.module_name)?;
+        }
+        Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
@@ -4,18 +4,30 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use include_dir::include_dir as include_directory;
 use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Write as _,
     io::{Result, Write},
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: true,
+    package_manifest: false,
+    single_file: false,
+    split_files: true,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in C#.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -23,6 +35,10 @@ pub struct CodeGenerator<'a> {
     /// Mapping from external type names to fully-qualified class names (e.g. "MyClass" -> "MyNamespace.MyClass").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether to enable nullable reference types and represent optional references as `T?`.
+    nullable_annotations: bool,
+    /// Whether constructors copy their fields of type `Bytes`.
+    defensive_copies: bool,
 }
 
 /// Shared state for the code generation of a C# source file.
@@ -45,7 +61,15 @@ struct CSharpEmitter<'a, T> {
 
 impl<'a> CodeGenerator<'a> {
     /// Create a C# code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a C# code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::CSharp, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -53,10 +77,34 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", namespace, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             external_qualified_names,
-        }
+            nullable_annotations: false,
+            defensive_copies: false,
+        })
+    }
+
+    /// Whether to emit `#nullable enable` and represent optional values of reference types
+    /// (strings, classes, arrays and dictionaries) as `T?` instead of `Serde.Option<T>`.
+    /// This requires C# 8. Default: false.
+    pub fn with_nullable_annotations(mut self, nullable_annotations: bool) -> Self {
+        self.nullable_annotations = nullable_annotations;
+        self
+    }
+
+    /// Whether the constructors of the generated classes copy their fields of type `Bytes`, so
+    /// that callers cannot change a value through an array that they still hold. This costs
+    /// one copy per field, including during deserialization. Default: false.
+    pub fn with_defensive_copies(mut self, defensive_copies: bool) -> Self {
+        self.defensive_copies = defensive_copies;
+        self
+    }
+
+    /// Report the parts of the registry that cannot be generated in C#, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::CSharp, registry)
     }
 
     /// Output class definitions for `registry` in separate source files.
@@ -67,6 +115,18 @@ impl<'a> CodeGenerator<'a> {
         install_dir: std::path::PathBuf,
         registry: &Registry,
     ) -> Result<std::path::PathBuf> {
+        let (dir_path, _) = self.output_files(install_dir, registry)?;
+        Ok(dir_path)
+    }
+
+    /// Same as `write_source_files` but also return the paths of the generated files.
+    fn output_files(
+        &self,
+        install_dir: std::path::PathBuf,
+        registry: &Registry,
+    ) -> Result<(PathBuf, BTreeSet<PathBuf>)> {
+        let registry = validation::check_registry(self.config, Language::CSharp, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -93,19 +153,25 @@ impl<'a> CodeGenerator<'a> {
             }
         }
 
+        let mut files = BTreeSet::new();
         for (name, format) in registry {
-            self.write_container_class(
+            files.insert(self.write_container_class(
                 &dir_path,
                 current_namespace.clone(),
                 cstyle_enum_names.clone(),
                 name,
                 format,
-            )?;
+            )?);
         }
         if self.config.serialization {
-            self.write_helper_class(&dir_path, current_namespace, cstyle_enum_names, registry)?;
+            files.insert(self.write_helper_class(
+                &dir_path,
+                current_namespace,
+                cstyle_enum_names,
+                registry,
+            )?);
         }
-        Ok(dir_path)
+        Ok((dir_path, files))
     }
 
     fn write_container_class(
@@ -115,10 +181,12 @@ impl<'a> CodeGenerator<'a> {
         cstyle_enum_names: Vec<String>,
         name: &str,
         format: &ContainerFormat,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join(name.to_string() + ".cs"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join(name.to_string() + ".cs");
+        let mut file = std::fs::File::create(&path)?;
         let mut emitter = CSharpEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
@@ -130,7 +198,7 @@ impl<'a> CodeGenerator<'a> {
         emitter.output_container(name, format)?;
         emitter.output_close_namespace()?;
 
-        Ok(())
+        Ok(path)
     }
 
     fn write_helper_class(
@@ -139,10 +207,12 @@ impl<'a> CodeGenerator<'a> {
         current_namespace: Vec<String>,
         cstyle_enum_names: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("TraitHelpers.cs"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join("TraitHelpers.cs");
+        let mut file = std::fs::File::create(&path)?;
         let mut emitter = CSharpEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
@@ -154,7 +224,7 @@ impl<'a> CodeGenerator<'a> {
         emitter.output_trait_helpers(registry)?;
         emitter.output_close_namespace()?;
 
-        Ok(())
+        Ok(path)
     }
 }
 
@@ -172,6 +242,9 @@ using System.Linq;
 using System.Text;
 using System.Numerics;"
         )?;
+        if self.generator.nullable_annotations {
+            writeln!(self.out, "\n#nullable enable")?;
+        }
         Ok(())
     }
 
@@ -195,6 +268,10 @@ using System.Numerics;"
         Ok(())
     }
 
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::CSharp, name)
+    }
+
     /// Compute a safe reference to the registry type `name` in the given context.
     /// If `name` is not marked as "reserved" (e.g. "Builder"), we compare the global
     /// name `self.external_qualified_names[name]` with the current namespace and try to use the
@@ -251,12 +328,31 @@ using System.Numerics;"
         use Format::*;
         match format {
             TypeName(name) => !self.cstyle_enum_names.contains(name),
-            Str | Seq(_) | Map { .. } | TupleArray { .. } => true,
+            Str | Bytes | Seq(_) | Map { .. } | TupleArray { .. } => true,
             Variable(_) => panic!("unexpected value"),
             _ => false,
         }
     }
 
+    /// Whether optional values of the given format are represented as `T?`.
+    fn is_nullable_option(&self, format: &Format) -> bool {
+        match format {
+            Format::Option(format) => {
+                self.generator.nullable_annotations && self.is_nullable(format)
+            }
+            _ => false,
+        }
+    }
+
+    /// Type of the parameters of the methods `Equals`, as well as the operators `==` and `!=`.
+    fn quote_equatable_type(&self, name: &str) -> String {
+        if self.generator.nullable_annotations {
+            format!("{}?", name)
+        } else {
+            name.to_string()
+        }
+    }
+
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
@@ -279,6 +375,9 @@ using System.Numerics;"
             Str => "string".into(),
             Bytes => "Serde.ValueArray<byte>".into(),
 
+            Option(content) if self.is_nullable_option(format) => {
+                format!("{}?", self.quote_type(content))
+            }
             Option(format) => format!("Serde.Option<{}>", self.quote_type(format)),
             Seq(format) => format!("Serde.ValueArray<{}>", self.quote_type(format)),
             Map { key, value } => format!(
@@ -441,6 +540,21 @@ using System.Numerics;"
         )?;
         self.out.indent();
         match format0 {
+            Option(format) if self.is_nullable_option(format0) => {
+                write!(
+                    self.out,
+                    r#"
+if (value is {{}} val) {{
+    serializer.serialize_option_tag(true);
+    {}
+}} else {{
+    serializer.serialize_option_tag(false);
+}}
+"#,
+                    self.quote_serialize_value("val", format)
+                )?;
+            }
+
             Option(format) => {
                 write!(
                     self.out,
@@ -531,6 +645,21 @@ foreach (var item in value) {{
         )?;
         self.out.indent();
         match format0 {
+            Option(format) if self.is_nullable_option(format0) => {
+                write!(
+                    self.out,
+                    r#"
+bool tag = deserializer.deserialize_option_tag();
+if (!tag) {{
+    return null;
+}} else {{
+    return {};
+}}
+"#,
+                    self.quote_deserialize(format),
+                )?;
+            }
+
             Option(format) => {
                 write!(
                     self.out,
@@ -706,7 +835,7 @@ return new Serde.ValueArray<{0}>(obj);
                 self.out,
                 "public {} {};",
                 self.quote_type(&field.value),
-                field.name
+                self.quote_field(&field.name)
             )?;
         }
         if !fields.is_empty() {
@@ -720,7 +849,7 @@ return new Serde.ValueArray<{0}>(obj);
             name,
             fields
                 .iter()
-                .map(|f| format!("{} _{}", self.quote_type(&f.value), &f.name))
+                .map(|f| format!("{} _{}", self.quote_type(&f.value), f.name))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -733,7 +862,21 @@ return new Serde.ValueArray<{0}>(obj);
                     &field.name
                 )?;
             }
-            writeln!(self.out, "{0} = _{0};", &field.name)?;
+            if self.generator.defensive_copies && field.value == Format::Bytes {
+                writeln!(
+                    self.out,
+                    "{} = new Serde.ValueArray<byte>(_{}.ToArray());",
+                    self.quote_field(&field.name),
+                    field.name
+                )?;
+            } else {
+                writeln!(
+                    self.out,
+                    "{} = _{};",
+                    self.quote_field(&field.name),
+                    field.name
+                )?;
+            }
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
@@ -754,7 +897,7 @@ return new Serde.ValueArray<{0}>(obj);
                 writeln!(
                     self.out,
                     "{}",
-                    self.quote_serialize_value(&field.name, &field.value)
+                    self.quote_serialize_value(&self.quote_field(&field.name), &field.value)
                 )?;
             }
             writeln!(self.out, "serializer.decrease_container_depth();")?;
@@ -807,32 +950,42 @@ return new Serde.ValueArray<{0}>(obj);
             }
         }
         // Equality
+        let object = self.quote_equatable_type("object");
+        let equatable = self.quote_equatable_type(name);
         writeln!(
             self.out,
-            "public override bool Equals(object obj) => obj is {} other && Equals(other);\n",
-            name
+            "public override bool Equals({} obj) => obj is {} other && Equals(other);\n",
+            object, name
         )?;
         writeln!(
             self.out,
             "public static bool operator ==({0} left, {0} right) => Equals(left, right);\n",
-            name
+            equatable
         )?;
         writeln!(
             self.out,
             "public static bool operator !=({0} left, {0} right) => !Equals(left, right);\n",
-            name
+            equatable
         )?;
 
-        writeln!(self.out, "public bool Equals({} other) {{", name)?;
+        writeln!(self.out, "public bool Equals({} other) {{", equatable)?;
         self.out.indent();
         writeln!(self.out, "if (other == null) return false;")?;
         writeln!(self.out, "if (ReferenceEquals(this, other)) return true;")?;
         for field in fields {
-            writeln!(
-                self.out,
-                "if (!{0}.Equals(other.{0})) return false;",
-                &field.name,
-            )?;
+            if self.is_nullable_option(&field.value) {
+                writeln!(
+                    self.out,
+                    "if (!Equals({0}, other.{0})) return false;",
+                    self.quote_field(&field.name),
+                )?;
+            } else {
+                writeln!(
+                    self.out,
+                    "if (!{0}.Equals(other.{0})) return false;",
+                    self.quote_field(&field.name),
+                )?;
+            }
         }
         writeln!(self.out, "return true;")?;
         self.out.unindent();
@@ -845,11 +998,19 @@ return new Serde.ValueArray<{0}>(obj);
         self.out.indent();
         writeln!(self.out, "int value = 7;")?;
         for field in fields {
-            writeln!(
-                self.out,
-                "value = 31 * value + {0}.GetHashCode();",
-                &field.name
-            )?;
+            if self.is_nullable_option(&field.value) {
+                writeln!(
+                    self.out,
+                    "value = 31 * value + ({0}?.GetHashCode() ?? 0);",
+                    self.quote_field(&field.name)
+                )?;
+            } else {
+                writeln!(
+                    self.out,
+                    "value = 31 * value + {0}.GetHashCode();",
+                    self.quote_field(&field.name)
+                )?;
+            }
         }
         writeln!(self.out, "return value;")?;
         self.out.unindent();
@@ -958,11 +1119,16 @@ switch (index) {{"#,
         // Equals
         writeln!(
             self.out,
-            "public override bool Equals(object obj) => obj is {} other && Equals(other);\n",
+            "public override bool Equals({} obj) => obj is {} other && Equals(other);\n",
+            self.quote_equatable_type("object"),
             name
         )?;
 
-        writeln!(self.out, "public bool Equals({} other) {{", name)?;
+        writeln!(
+            self.out,
+            "public bool Equals({} other) {{",
+            self.quote_equatable_type(name)
+        )?;
         self.out.indent();
         writeln!(self.out, "if (other == null) return false;")?;
         writeln!(self.out, "if (ReferenceEquals(this, other)) return true;")?;
@@ -1147,11 +1313,32 @@ public static {0} {1}Deserialize(ArraySegment<byte> input) {{
 /// Installer for generated source files in C#.
 pub struct Installer {
     install_dir: PathBuf,
+    nullable_annotations: bool,
+    defensive_copies: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            nullable_annotations: false,
+            defensive_copies: false,
+        }
+    }
+
+    /// Whether to generate code for nullable reference types (see
+    /// `CodeGenerator::with_nullable_annotations`). The project file of the module then enables
+    /// them with C# 8.
+    pub fn with_nullable_annotations(mut self, nullable_annotations: bool) -> Self {
+        self.nullable_annotations = nullable_annotations;
+        self
+    }
+
+    /// Whether constructors copy their fields of type `Bytes` (see
+    /// `CodeGenerator::with_defensive_copies`).
+    pub fn with_defensive_copies(mut self, defensive_copies: bool) -> Self {
+        self.defensive_copies = defensive_copies;
+        self
     }
 
     fn install_runtime(
@@ -1178,8 +1365,13 @@ impl crate::SourceInstaller for Installer {
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
         let name = config.module_name.clone();
-        let generator = CodeGenerator::new(config);
-        let dir_path = generator.write_source_files(self.install_dir.clone(), registry)?;
+        let generator = CodeGenerator::try_new(config)?
+            .with_nullable_annotations(self.nullable_annotations)
+            .with_defensive_copies(self.defensive_copies);
+        let (dir_path, files) = generator.output_files(self.install_dir.clone(), registry)?;
+        for path in files {
+            config.format_source(Language::CSharp, &path)?;
+        }
 
         let back_path: String = "..\\"
             .to_string()
@@ -1197,6 +1389,11 @@ impl crate::SourceInstaller for Installer {
             )?;
         }
 
+        let language_options = if self.nullable_annotations {
+            "       <LangVersion>8.0</LangVersion>\n       <Nullable>enable</Nullable>\n"
+        } else {
+            "       <LangVersion>7.2</LangVersion>\n"
+        };
         let mut proj = std::fs::File::create(dir_path.join(name + ".csproj"))?;
         write!(
             proj,
@@ -1204,8 +1401,7 @@ impl crate::SourceInstaller for Installer {
 <Project Sdk="Microsoft.NET.Sdk">
     <PropertyGroup>
        <TargetFramework>netstandard2.0</TargetFramework>
-       <LangVersion>7.2</LangVersion>
-    </PropertyGroup>
+{}    </PropertyGroup>
     <ItemGroup>
       <PackageReference Include="System.Memory" Version="4.5.4" />
       <PackageReference Include="System.ValueTuple" Version="4.5.0" />
@@ -1214,7 +1410,7 @@ impl crate::SourceInstaller for Installer {
 {}    </ItemGroup>
 </Project>
 "#,
-            dependencies
+            language_options, dependencies
         )?;
 
         Ok(())
@@ -4,17 +4,29 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::{CamelCase, MixedCase, SnakeCase};
 use include_dir::include_dir as include_directory;
 use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
     path::{Path, PathBuf},
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: true,
+    package_manifest: false,
+    single_file: false,
+    split_files: true,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in Dart.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -35,7 +47,15 @@ struct DartEmitter<'a, T> {
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Dart code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a Dart code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Dart, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -43,11 +63,29 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", namespace, name));
             }
         }
-        Self { config }
+        Ok(Self { config })
+    }
+
+    /// Report the parts of the registry that cannot be generated in Dart, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Dart, registry)
     }
 
     /// Output class definitions for `registry`.
     pub fn output(&self, install_dir: std::path::PathBuf, registry: &Registry) -> Result<()> {
+        self.output_files(install_dir, registry)?;
+        Ok(())
+    }
+
+    /// Same as `output` but return the paths of the generated files.
+    fn output_files(
+        &self,
+        install_dir: std::path::PathBuf,
+        registry: &Registry,
+    ) -> Result<BTreeSet<PathBuf>> {
+        let registry = validation::check_registry(self.config, Language::Dart, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -55,9 +93,10 @@ impl<'a> CodeGenerator<'a> {
             .map(String::from)
             .collect::<Vec<_>>();
 
+        let mut files = BTreeSet::new();
         let mut dir_path = install_dir;
         std::fs::create_dir_all(&dir_path)?;
-        self.write_package(&dir_path)?;
+        files.insert(self.write_package(&dir_path)?);
         dir_path = dir_path.join("lib").join("src");
         for part in &current_namespace {
             dir_path = dir_path.join(part);
@@ -65,25 +104,34 @@ impl<'a> CodeGenerator<'a> {
         std::fs::create_dir_all(&dir_path)?;
 
         for (name, format) in registry {
-            self.write_container_class(
+            files.insert(self.write_container_class(
                 &dir_path,
                 current_namespace.clone(),
                 name,
                 format,
                 registry,
-            )?;
+            )?);
         }
-        self.write_helper_class(&dir_path, current_namespace.clone(), registry)?;
-        self.write_library(&dir_path, current_namespace, registry)?;
-        Ok(())
+        if self.config.serialization {
+            files.insert(self.write_helper_class(
+                &dir_path,
+                current_namespace.clone(),
+                registry,
+            )?);
+        }
+        files.insert(self.write_library(&dir_path, current_namespace, registry)?);
+        Ok(files)
     }
 
-    fn write_package(&self, install_dir: &Path) -> Result<()> {
-        let mut file = std::fs::File::create(install_dir.join("pubspec.yaml"))?;
-        let mut out = IndentedWriter::new(&mut file, IndentConfig::Space(2));
+    fn write_package(&self, install_dir: &Path) -> Result<PathBuf> {
+        let path = install_dir.join("pubspec.yaml");
+        let mut file = common::create_generated_file(&path)?;
+        let mut out = IndentedWriter::new(&mut file, IndentConfig::Space(2))
+            .with_output_indent(self.config.indent);
         writeln!(
             &mut out,
-            r#"name: {}
+            r#"# {}. Do not edit.
+name: {}
 
 environment:
   sdk: '>=3.0.0 <4.0.0'
@@ -92,9 +140,10 @@ dependencies:
   meta: ^1.0.0
   tuple: ^2.0.0
 "#,
+            common::GENERATED_FILE_MARKER,
             self.config.module_name
         )?;
-        Ok(())
+        Ok(path)
     }
 
     fn write_library(
@@ -102,11 +151,12 @@ dependencies:
         install_dir: &Path,
         current_namespace: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file =
-            std::fs::File::create(install_dir.join(self.config.module_name.clone() + ".dart"))?;
+    ) -> Result<PathBuf> {
+        let path = install_dir.join(self.config.module_name.clone() + ".dart");
+        let mut file = common::create_generated_file(&path)?;
         let mut emitter = DartEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(2))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             registry,
@@ -114,21 +164,25 @@ dependencies:
 
         writeln!(
             &mut emitter.out,
-            r#"library {}_types;
+            r#"// {}. Do not edit.
+library {}_types;
 
 import 'dart:typed_data';
 import 'package:meta/meta.dart';
 import 'package:tuple/tuple.dart';
 import '../serde/serde.dart';"#,
+            common::GENERATED_FILE_MARKER,
             self.config.module_name,
         )?;
 
-        for encoding in &self.config.encodings {
-            writeln!(
-                &mut emitter.out,
-                "import '../{0}/{0}.dart';",
-                encoding.name()
-            )?;
+        if self.config.serialization {
+            for encoding in &self.config.encodings {
+                writeln!(
+                    &mut emitter.out,
+                    "import '../{0}/{0}.dart';",
+                    encoding.name()
+                )?;
+            }
         }
 
         if let Some(files) = &self.config.external_definitions.get("import") {
@@ -139,12 +193,14 @@ import '../serde/serde.dart';"#,
 
         writeln!(&mut emitter.out, "\nexport '../serde/serde.dart';")?;
 
-        writeln!(&mut emitter.out, "\npart 'trait_helpers.dart';")?;
+        if self.config.serialization {
+            writeln!(&mut emitter.out, "\npart 'trait_helpers.dart';")?;
+        }
         for name in registry.keys() {
             writeln!(&mut emitter.out, "part '{}.dart';", name.to_snake_case())?;
         }
 
-        Ok(())
+        Ok(path)
     }
 
     fn write_container_class(
@@ -154,18 +210,20 @@ import '../serde/serde.dart';"#,
         name: &str,
         format: &ContainerFormat,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file =
-            std::fs::File::create(dir_path.join(name.to_string().to_snake_case() + ".dart"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join(name.to_string().to_snake_case() + ".dart");
+        let mut file = common::create_generated_file(&path)?;
         let mut emitter = DartEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(2))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             registry,
         };
 
         emitter.output_preamble()?;
-        emitter.output_container(name, format)
+        emitter.output_container(name, format)?;
+        Ok(path)
     }
 
     fn write_helper_class(
@@ -173,17 +231,20 @@ import '../serde/serde.dart';"#,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("trait_helpers.dart"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join("trait_helpers.dart");
+        let mut file = common::create_generated_file(&path)?;
         let mut emitter = DartEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(2)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(2))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             registry,
         };
 
         emitter.output_preamble()?;
-        emitter.output_trait_helpers(registry)
+        emitter.output_trait_helpers(registry)?;
+        Ok(path)
     }
 }
 
@@ -194,7 +255,8 @@ where
     fn output_preamble(&mut self) -> Result<()> {
         writeln!(
             self.out,
-            "part of {}_types;",
+            "// {}. Do not edit.\npart of {}_types;",
+            common::GENERATED_FILE_MARKER,
             self.generator.config.module_name
         )?;
 
@@ -240,7 +302,7 @@ where
         match name {
             "hashCode" => "hashCode_".to_string(),
             "runtimeType" => "runtimeType_".to_string(),
-            name => name.to_string(),
+            name => common::escape_keyword(self.generator.config, Language::Dart, name),
         }
     }
 
@@ -856,11 +918,10 @@ return obj;
             write!(self.out, "\n@override")?;
 
             if field_count == 1 {
-                writeln!(
-                    self.out,
-                    "\nint get hashCode => {}.hashCode;",
-                    fields.first().unwrap().name.to_mixed_case()
-                )?;
+                let field = fields.first().unwrap();
+                let value = self
+                    .quote_field_hash(&self.quote_field(&field.name.to_mixed_case()), &field.value);
+                writeln!(self.out, "\nint get hashCode => {};", value)?;
             } else {
                 let use_hash_all = field_count > 20;
 
@@ -875,11 +936,11 @@ return obj;
                 self.out.indent();
 
                 for field in fields {
-                    writeln!(
-                        self.out,
-                        "{},",
-                        self.quote_field(&field.name.to_mixed_case())
-                    )?;
+                    let value = self.quote_field_hash(
+                        &self.quote_field(&field.name.to_mixed_case()),
+                        &field.value,
+                    );
+                    writeln!(self.out, "{},", value)?;
                 }
 
                 self.out.unindent();
@@ -933,6 +994,21 @@ return obj;
         writeln!(self.out, "}}")
     }
 
+    /// Hash lists and maps by content so that `hashCode` agrees with `operator ==`.
+    fn quote_field_hash(&self, name: &str, format: &Format) -> String {
+        // Like listHashCode and mapHashCode, we discard the enclosing Format::Option.
+        let value = if let Format::Option(value) = format {
+            value
+        } else {
+            format
+        };
+        match value {
+            Format::Seq(_) | Format::TupleArray { .. } => format!("listHashCode({})", name),
+            Format::Map { .. } => format!("mapHashCode({})", name),
+            _ => format!("{}.hashCode", name),
+        }
+    }
+
     fn output_class_serialize_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
         writeln!(
             self.out,
@@ -1104,7 +1180,7 @@ switch (index) {{"#,
                     "case {}: return {}{}.load(deserializer);",
                     index,
                     self.quote_qualified_name(name).to_camel_case(),
-                    self.quote_field(&variant.name),
+                    variant.name,
                 )?;
             }
             writeln!(
@@ -1192,14 +1268,25 @@ switch (index) {{"#,
     }
 }
 
-/// Installer for generated source files in Go.
+/// Installer for generated source files in Dart.
 pub struct Installer {
     install_dir: PathBuf,
+    clean: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            clean: false,
+        }
+    }
+
+    /// Whether `install_module` should delete the generated files of a previous run that are
+    /// no longer produced. Files without the marker of generated files are never deleted.
+    pub fn with_clean(mut self, clean: bool) -> Self {
+        self.clean = clean;
+        self
     }
 
     fn install_runtime(
@@ -1225,18 +1312,35 @@ impl crate::SourceInstaller for Installer {
         config: &CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let generator = CodeGenerator::new(config);
-        generator.output(self.install_dir.clone(), registry)?;
+        let generator = CodeGenerator::try_new(config)?;
+        let mut files = generator.output_files(self.install_dir.clone(), registry)?;
         // write the main module file to export the public api
-        std::fs::write(
-            self.install_dir
-                .join("lib")
-                .join(format!("{}.dart", &config.module_name)),
-            format!(
-                "export 'src/{name}/{name}.dart';",
-                name = &config.module_name
-            ),
+        let path = self
+            .install_dir
+            .join("lib")
+            .join(format!("{}.dart", &config.module_name));
+        let mut file = common::create_generated_file(&path)?;
+        writeln!(
+            file,
+            "// {}. Do not edit.\nexport 'src/{name}/{name}.dart';",
+            common::GENERATED_FILE_MARKER,
+            name = &config.module_name
         )?;
+        files.insert(path);
+        for path in &files {
+            if path.extension() == Some("dart".as_ref()) {
+                config.format_source(Language::Dart, path)?;
+            }
+        }
+        if self.clean {
+            let dirs = files
+                .iter()
+                .filter_map(|path| path.parent())
+                .collect::<BTreeSet<_>>();
+            for dir in dirs {
+                common::remove_stale_generated_files(dir, &files)?;
+            }
+        }
         Ok(())
     }
 
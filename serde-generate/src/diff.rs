@@ -0,0 +1,323 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Serialize;
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::collections::BTreeMap;
+
+/// A difference between two versions of a registry.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// Location of the change (e.g. `MyStruct.field`, `MyStruct.0`, or `MyEnum::Variant.field`).
+    pub path: String,
+    /// What changed.
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// The nature of a `Change`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// A new container was defined.
+    AddedContainer,
+    /// A container was removed.
+    RemovedContainer,
+    /// A container or a variant changed shape, e.g. from a struct to an enum.
+    ChangedKind { old: String, new: String },
+    /// A field was added to a struct or a variant.
+    AddedField { format: Format },
+    /// A field was removed from a struct or a variant.
+    RemovedField,
+    /// The fields of a struct or a variant were reordered.
+    ReorderedFields { old: Vec<String>, new: Vec<String> },
+    /// The format of a field (or of a newtype) changed.
+    ChangedFormat { old: Format, new: Format },
+    /// A variant was added to an enum, using an index that was not used before.
+    AddedVariant { index: u32 },
+    /// A variant was removed from an enum.
+    RemovedVariant { index: u32 },
+    /// A variant of an enum now uses a different index.
+    MovedVariant { old_index: u32, new_index: u32 },
+    /// The index of a variant was previously used by another variant.
+    ReusedVariantIndex { index: u32, old_name: String },
+}
+
+impl Change {
+    /// Whether the change may prevent data serialized with one version of the registry from
+    /// being deserialized with the other one. Only additions of containers and enum variants
+    /// are compatible.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(
+            self.kind,
+            ChangeKind::AddedContainer | ChangeKind::AddedVariant { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ChangeKind::AddedContainer => write!(f, "container added"),
+            ChangeKind::RemovedContainer => write!(f, "container removed"),
+            ChangeKind::ChangedKind { old, new } => write!(f, "changed from {} to {}", old, new),
+            ChangeKind::AddedField { format } => {
+                write!(f, "field added with format {}", format)
+            }
+            ChangeKind::RemovedField => write!(f, "field removed"),
+            ChangeKind::ReorderedFields { old, new } => write!(
+                f,
+                "fields reordered from ({}) to ({})",
+                old.join(", "),
+                new.join(", ")
+            ),
+            ChangeKind::ChangedFormat { old, new } => {
+                write!(f, "format changed from {} to {}", old, new)
+            }
+            ChangeKind::AddedVariant { index } => write!(f, "variant added at index {}", index),
+            ChangeKind::RemovedVariant { index } => {
+                write!(f, "variant removed from index {}", index)
+            }
+            ChangeKind::MovedVariant {
+                old_index,
+                new_index,
+            } => write!(
+                f,
+                "variant moved from index {} to index {}",
+                old_index, new_index
+            ),
+            ChangeKind::ReusedVariantIndex { index, old_name } => write!(
+                f,
+                "index {} was previously used by variant {}",
+                index, old_name
+            ),
+        }
+    }
+}
+
+/// Compare two versions of a registry. Changes are sorted by container name.
+pub fn diff_registries(old: &Registry, new: &Registry) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let names = old
+        .keys()
+        .chain(new.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    for name in names {
+        let change = |kind| Change {
+            path: name.clone(),
+            kind,
+        };
+        match (old.get(name), new.get(name)) {
+            (Some(old), Some(new)) => diff_containers(name, old, new, &mut changes),
+            (Some(_), None) => changes.push(change(ChangeKind::RemovedContainer)),
+            (None, Some(_)) => changes.push(change(ChangeKind::AddedContainer)),
+            (None, None) => unreachable!(),
+        }
+    }
+    changes
+}
+
+/// The fields of a container or a variant, named after their position if needed.
+enum Shape<'a> {
+    Unit,
+    NewType(&'a Format),
+    Tuple(&'a [Format]),
+    Struct(&'a [Named<Format>]),
+    Enum(&'a BTreeMap<u32, Named<VariantFormat>>),
+}
+
+impl Shape<'_> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Shape::Unit => "unit",
+            Shape::NewType(_) => "newtype",
+            Shape::Tuple(_) => "tuple",
+            Shape::Struct(_) => "struct",
+            Shape::Enum(_) => "enum",
+        }
+    }
+}
+
+fn container_shape(format: &ContainerFormat) -> Shape<'_> {
+    match format {
+        ContainerFormat::UnitStruct => Shape::Unit,
+        ContainerFormat::NewTypeStruct(format) => Shape::NewType(format),
+        ContainerFormat::TupleStruct(formats) => Shape::Tuple(formats),
+        ContainerFormat::Struct(fields) => Shape::Struct(fields),
+        ContainerFormat::Enum(variants) => Shape::Enum(variants),
+    }
+}
+
+fn variant_shape(format: &VariantFormat) -> Shape<'_> {
+    match format {
+        VariantFormat::Unit => Shape::Unit,
+        VariantFormat::NewType(format) => Shape::NewType(format),
+        VariantFormat::Tuple(formats) => Shape::Tuple(formats),
+        VariantFormat::Struct(fields) => Shape::Struct(fields),
+        VariantFormat::Variable(_) => panic!("unexpected value"),
+    }
+}
+
+fn diff_containers(
+    path: &str,
+    old: &ContainerFormat,
+    new: &ContainerFormat,
+    changes: &mut Vec<Change>,
+) {
+    diff_shapes(path, container_shape(old), container_shape(new), changes);
+}
+
+fn diff_shapes(path: &str, old: Shape, new: Shape, changes: &mut Vec<Change>) {
+    let change = |kind| Change {
+        path: path.to_string(),
+        kind,
+    };
+    match (old, new) {
+        (Shape::Unit, Shape::Unit) => (),
+        (Shape::NewType(old), Shape::NewType(new)) => diff_formats(path, old, new, changes),
+        (Shape::Tuple(old), Shape::Tuple(new)) => {
+            let name = |index: usize| format!("{}.{}", path, index);
+            for index in 0..std::cmp::max(old.len(), new.len()) {
+                match (old.get(index), new.get(index)) {
+                    (Some(old), Some(new)) => diff_formats(&name(index), old, new, changes),
+                    (Some(_), None) => changes.push(Change {
+                        path: name(index),
+                        kind: ChangeKind::RemovedField,
+                    }),
+                    (None, Some(format)) => changes.push(Change {
+                        path: name(index),
+                        kind: ChangeKind::AddedField {
+                            format: format.clone(),
+                        },
+                    }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (Shape::Struct(old), Shape::Struct(new)) => diff_fields(path, old, new, changes),
+        (Shape::Enum(old), Shape::Enum(new)) => diff_variants(path, old, new, changes),
+        (old, new) => changes.push(change(ChangeKind::ChangedKind {
+            old: old.kind().to_string(),
+            new: new.kind().to_string(),
+        })),
+    }
+}
+
+fn diff_formats(path: &str, old: &Format, new: &Format, changes: &mut Vec<Change>) {
+    if old != new {
+        changes.push(Change {
+            path: path.to_string(),
+            kind: ChangeKind::ChangedFormat {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        });
+    }
+}
+
+fn diff_fields(
+    path: &str,
+    old: &[Named<Format>],
+    new: &[Named<Format>],
+    changes: &mut Vec<Change>,
+) {
+    let find = |fields: &'_ [Named<Format>], name: &str| {
+        fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value.clone())
+    };
+    let field_path = |name: &str| format!("{}.{}", path, name);
+    // Relative order of the fields present in both versions.
+    let old_common = old
+        .iter()
+        .filter(|field| find(new, &field.name).is_some())
+        .map(|field| field.name.clone())
+        .collect::<Vec<_>>();
+    let new_common = new
+        .iter()
+        .filter(|field| find(old, &field.name).is_some())
+        .map(|field| field.name.clone())
+        .collect::<Vec<_>>();
+    if old_common != new_common {
+        changes.push(Change {
+            path: path.to_string(),
+            kind: ChangeKind::ReorderedFields {
+                old: old_common,
+                new: new_common,
+            },
+        });
+    }
+    for field in old {
+        match find(new, &field.name) {
+            Some(format) => diff_formats(&field_path(&field.name), &field.value, &format, changes),
+            None => changes.push(Change {
+                path: field_path(&field.name),
+                kind: ChangeKind::RemovedField,
+            }),
+        }
+    }
+    for field in new {
+        if find(old, &field.name).is_none() {
+            changes.push(Change {
+                path: field_path(&field.name),
+                kind: ChangeKind::AddedField {
+                    format: field.value.clone(),
+                },
+            });
+        }
+    }
+}
+
+fn diff_variants(
+    path: &str,
+    old: &BTreeMap<u32, Named<VariantFormat>>,
+    new: &BTreeMap<u32, Named<VariantFormat>>,
+    changes: &mut Vec<Change>,
+) {
+    let find = |variants: &BTreeMap<u32, Named<VariantFormat>>, name: &str| {
+        variants
+            .iter()
+            .find(|(_, variant)| variant.name == name)
+            .map(|(index, _)| *index)
+    };
+    let variant_path = |name: &str| format!("{}::{}", path, name);
+    for (index, variant) in old {
+        match find(new, &variant.name) {
+            Some(new_index) if new_index == *index => diff_shapes(
+                &variant_path(&variant.name),
+                variant_shape(&variant.value),
+                variant_shape(&new[index].value),
+                changes,
+            ),
+            Some(new_index) => changes.push(Change {
+                path: variant_path(&variant.name),
+                kind: ChangeKind::MovedVariant {
+                    old_index: *index,
+                    new_index,
+                },
+            }),
+            None => changes.push(Change {
+                path: variant_path(&variant.name),
+                kind: ChangeKind::RemovedVariant { index: *index },
+            }),
+        }
+    }
+    for (index, variant) in new {
+        if find(old, &variant.name).is_some() {
+            continue;
+        }
+        let kind = match old.get(index) {
+            Some(old_variant) => ChangeKind::ReusedVariantIndex {
+                index: *index,
+                old_name: old_variant.name.clone(),
+            },
+            None => ChangeKind::AddedVariant { index: *index },
+        };
+        changes.push(Change {
+            path: variant_path(&variant.name),
+            kind,
+        });
+    }
+}
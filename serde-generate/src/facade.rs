@@ -0,0 +1,91 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{validation::Language, CodeGeneratorConfig, ConfigError};
+use serde_reflection::{FrozenRegistry, Registry};
+use std::{borrow::Cow, io::Write, sync::Arc};
+
+/// Registries accepted by `generate`.
+pub trait AsFrozenRegistry {
+    /// The frozen registry, computed with the default limits of `FrozenRegistry` unless `self`
+    /// is frozen already.
+    fn as_frozen(&self) -> serde_reflection::Result<Cow<'_, FrozenRegistry>>;
+}
+
+impl AsFrozenRegistry for FrozenRegistry {
+    fn as_frozen(&self) -> serde_reflection::Result<Cow<'_, FrozenRegistry>> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl AsFrozenRegistry for Arc<FrozenRegistry> {
+    fn as_frozen(&self) -> serde_reflection::Result<Cow<'_, FrozenRegistry>> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl AsFrozenRegistry for Registry {
+    fn as_frozen(&self) -> serde_reflection::Result<Cow<'_, FrozenRegistry>> {
+        FrozenRegistry::new(self, FrozenRegistry::DEFAULT_MAX_DEPTH).map(Cow::Owned)
+    }
+}
+
+/// Write the definitions of the containers of `registry` in `language` as a single source
+/// file, with the default options of the code generator.
+///
+/// A frozen registry may be shared by several threads, each calling `generate` for a different
+/// language; a plain `Registry` is frozen (and thus validated) first. Languages whose code
+/// generator writes several files, or is disabled by the features of this crate, are rejected.
+pub fn generate<R>(
+    language: Language,
+    config: &CodeGeneratorConfig,
+    registry: &R,
+    out: &mut dyn Write,
+) -> std::result::Result<(), Box<dyn std::error::Error>>
+where
+    R: AsFrozenRegistry + ?Sized,
+{
+    match language.capabilities() {
+        None => return Err(ConfigError::Unavailable { language }.into()),
+        Some(capabilities) if !capabilities.single_file => {
+            return Err(ConfigError::SingleFile { language }.into())
+        }
+        Some(_) => (),
+    }
+    // Each call works on its own copy of the formats, since `Registry` cannot be shared
+    // between threads.
+    let registry = registry.as_frozen()?.to_registry();
+    let registry = &registry;
+    match language {
+        #[cfg(feature = "cpp")]
+        Language::Cpp => crate::cpp::CodeGenerator::try_new(config)?.output(out, registry)?,
+        #[cfg(feature = "golang")]
+        Language::Go => crate::golang::CodeGenerator::try_new(config)?.output(out, registry)?,
+        #[cfg(feature = "ocaml")]
+        Language::OCaml => crate::ocaml::CodeGenerator::try_new(config)?.output(out, registry)?,
+        #[cfg(feature = "openapi")]
+        Language::OpenApi => {
+            crate::openapi::CodeGenerator::try_new(config)?.output(out, registry)?
+        }
+        #[cfg(feature = "python3")]
+        Language::Python3 => {
+            crate::python3::CodeGenerator::try_new(config)?.output(out, registry)?
+        }
+        #[cfg(feature = "rust")]
+        Language::Rust => crate::rust::CodeGenerator::new(config).output(out, registry)?,
+        #[cfg(feature = "solidity")]
+        Language::Solidity => {
+            crate::solidity::CodeGenerator::try_new(config)?.output(out, registry)?
+        }
+        #[cfg(feature = "swift")]
+        Language::Swift => crate::swift::CodeGenerator::try_new(config)?.output(out, registry)?,
+        #[cfg(feature = "typescript")]
+        Language::TypeScript => {
+            crate::typescript::CodeGenerator::try_new(config)?.output(out, registry)?
+        }
+        #[cfg(feature = "zig")]
+        Language::Zig => crate::zig::CodeGenerator::try_new(config)?.output(out, registry)?,
+        _ => unreachable!("{} was checked to write a single file", language),
+    }
+    Ok(())
+}
@@ -4,7 +4,8 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
@@ -14,6 +15,17 @@ use std::{
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in Go.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -24,6 +36,8 @@ pub struct CodeGenerator<'a> {
     /// Mapping from external type names to fully-qualified class names (e.g. "MyClass" -> "com.my_org.my_package.MyClass").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether to add `json` tags with the original field names to struct fields.
+    json_tags: bool,
 }
 
 /// Shared state for the code generation of a Go source file.
@@ -38,10 +52,15 @@ struct GoEmitter<'a, T> {
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Go code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Go does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a Go code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Go, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             let package_name = {
@@ -57,13 +76,20 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", package_name, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             serde_module_path:
                 "github.com/novifinancial/serde-reflection/serde-generate/runtime/golang"
                     .to_string(),
             external_qualified_names,
-        }
+            json_tags: false,
+        })
+    }
+
+    /// Report the parts of the registry that cannot be generated in Go, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Go, registry)
     }
 
     /// Whether the package providing Serde definitions is located within a different module.
@@ -72,8 +98,17 @@ impl<'a> CodeGenerator<'a> {
         self
     }
 
+    /// Whether to add `json:"<name>"` tags to the fields of structs, so that `encoding/json`
+    /// uses the field names of the registry. Default: false.
+    pub fn with_json_tags(mut self, json_tags: bool) -> Self {
+        self.json_tags = json_tags;
+        self
+    }
+
     /// Output class definitions for `registry`.
     pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = validation::check_registry(self.config, Language::Go, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -83,7 +118,7 @@ impl<'a> CodeGenerator<'a> {
 
         let mut emitter = GoEmitter {
             // `go fmt` indents using tabs so let's do the same.
-            out: IndentedWriter::new(out, IndentConfig::Tab),
+            out: IndentedWriter::new(out, IndentConfig::Tab).with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
         };
@@ -592,7 +627,11 @@ return obj, nil
                 .collect(),
             Variable(_) => panic!("incorrect value"),
         };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+        let json_names = match variant {
+            Struct(fields) => Some(fields.as_slice()),
+            _ => None,
+        };
+        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields, json_names)
     }
 
     fn output_struct_or_variant_container(
@@ -601,6 +640,7 @@ return obj, nil
         variant_index: Option<u32>,
         name: &str,
         fields: &[Named<Format>],
+        json_names: Option<&[Named<Format>]>,
     ) -> Result<()> {
         let full_name = match variant_base {
             None => name.to_string(),
@@ -611,9 +651,15 @@ return obj, nil
         self.output_comment(name)?;
         writeln!(self.out, "type {} struct {{", full_name)?;
         self.enter_class(name);
-        for field in fields {
+        for (index, field) in fields.iter().enumerate() {
             self.output_comment(&field.name)?;
-            writeln!(self.out, "{} {}", field.name, self.quote_type(&field.value))?;
+            write!(self.out, "{} {}", field.name, self.quote_type(&field.value))?;
+            match json_names {
+                Some(names) if self.generator.json_tags => {
+                    writeln!(self.out, " `json:\"{}\"`", names[index].name)?
+                }
+                _ => writeln!(self.out)?,
+            }
         }
         self.leave_class();
         writeln!(self.out, "}}")?;
@@ -957,7 +1003,11 @@ switch index {{"#,
                 return Ok(());
             }
         };
-        self.output_struct_or_variant_container(None, None, name, &fields)
+        let json_names = match format {
+            Struct(fields) => Some(fields.as_slice()),
+            _ => None,
+        };
+        self.output_struct_or_variant_container(None, None, name, &fields, json_names)
     }
 }
 
@@ -965,6 +1015,7 @@ switch index {{"#,
 pub struct Installer {
     install_dir: PathBuf,
     serde_module_path: Option<String>,
+    json_tags: bool,
 }
 
 impl Installer {
@@ -972,9 +1023,16 @@ impl Installer {
         Installer {
             install_dir,
             serde_module_path,
+            json_tags: false,
         }
     }
 
+    /// Whether generated structs should have `json` tags (see `CodeGenerator::with_json_tags`).
+    pub fn with_json_tags(mut self, json_tags: bool) -> Self {
+        self.json_tags = json_tags;
+        self
+    }
+
     fn runtime_installation_message(&self, name: &str) {
         eprintln!(
             "Not installing sources for published package {}{}",
@@ -998,13 +1056,14 @@ impl crate::SourceInstaller for Installer {
         let dir_path = self.install_dir.join(&config.module_name);
         std::fs::create_dir_all(&dir_path)?;
         let source_path = dir_path.join("lib.go");
-        let mut file = std::fs::File::create(source_path)?;
+        let mut file = std::fs::File::create(&source_path)?;
 
-        let mut generator = CodeGenerator::new(config);
+        let mut generator = CodeGenerator::try_new(config)?.with_json_tags(self.json_tags);
         if let Some(path) = &self.serde_module_path {
             generator = generator.with_serde_module_path(path.clone());
         }
         generator.output(&mut file, registry)?;
+        config.format_source(Language::Go, &source_path)?;
         Ok(())
     }
 
@@ -1,9 +1,10 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use serde::Serialize;
 use std::io::{Result, Write};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 pub enum IndentConfig {
     Tab,
     Space(usize),
@@ -14,6 +15,10 @@ pub struct IndentedWriter<T> {
     indentation: Vec<u8>,
     config: IndentConfig,
     at_begining_of_line: bool,
+    /// Indentation style of the output, if it differs from `config` (see `with_output_indent`).
+    output_indent: Option<IndentConfig>,
+    /// Leading whitespace of the current line, not written yet when `output_indent` is set.
+    pending: Vec<u8>,
 }
 
 impl<T> IndentedWriter<T> {
@@ -23,9 +28,20 @@ impl<T> IndentedWriter<T> {
             indentation: Vec::new(),
             config,
             at_begining_of_line: true,
+            output_indent: None,
+            pending: Vec::new(),
         }
     }
 
+    /// Rewrite the leading whitespace of each line, made of indentation units of `config`
+    /// (including the ones written as text), with the units of `output_indent`. Columns that
+    /// do not make a whole unit are kept as spaces, and lines made of whitespace are written
+    /// empty. With `None`, lines are written as is.
+    pub fn with_output_indent(mut self, output_indent: Option<IndentConfig>) -> Self {
+        self.output_indent = output_indent.filter(|indent| *indent != self.config);
+        self
+    }
+
     pub fn indent(&mut self) {
         match self.config {
             IndentConfig::Tab => {
@@ -50,8 +66,72 @@ impl<T> IndentedWriter<T> {
     }
 }
 
+/// Leading whitespace of a line, made of `levels` units of indentation followed by `columns`
+/// spaces.
+fn measure_indentation(whitespace: &[u8], config: IndentConfig) -> (usize, usize) {
+    match config {
+        IndentConfig::Tab => {
+            let tabs = whitespace.iter().filter(|c| **c == b'\t').count();
+            (tabs, whitespace.len() - tabs)
+        }
+        IndentConfig::Space(width) => {
+            let width = width.max(1);
+            let columns: usize = whitespace
+                .iter()
+                .map(|c| if *c == b'\t' { width } else { 1 })
+                .sum();
+            (columns / width, columns % width)
+        }
+    }
+}
+
+impl<T: Write> IndentedWriter<T> {
+    /// Write the line `buf`, which does not contain newlines, when `output_indent` is set.
+    fn write_reindented(&mut self, output_indent: IndentConfig, buf: &[u8]) -> Result<()> {
+        if !self.at_begining_of_line {
+            return self.out.write_all(buf);
+        }
+        let start = buf
+            .iter()
+            .position(|c| *c != b' ' && *c != b'\t')
+            .unwrap_or(buf.len());
+        self.pending.extend_from_slice(&buf[..start]);
+        if start == buf.len() {
+            return Ok(());
+        }
+        let mut whitespace = self.indentation.clone();
+        whitespace.append(&mut self.pending);
+        let (levels, columns) = measure_indentation(&whitespace, self.config);
+        let unit = match output_indent {
+            IndentConfig::Tab => "\t".to_string(),
+            IndentConfig::Space(width) => " ".repeat(width),
+        };
+        write!(self.out, "{}{}", unit.repeat(levels), " ".repeat(columns))?;
+        self.at_begining_of_line = false;
+        self.out.write_all(&buf[start..])
+    }
+}
+
 impl<T: Write> Write for IndentedWriter<T> {
     fn write(&mut self, mut buf: &[u8]) -> Result<usize> {
+        if let Some(output_indent) = self.output_indent {
+            let bytes_written = buf.len();
+            while !buf.is_empty() {
+                let (line, has_newline, rest) = match buf.iter().position(|&b| b == b'\n') {
+                    Some(idx) => (&buf[..idx], true, &buf[idx + 1..]),
+                    None => (buf, false, &buf[buf.len()..]),
+                };
+                self.write_reindented(output_indent, line)?;
+                if has_newline {
+                    self.out.write_all(b"\n")?;
+                    self.pending.clear();
+                    self.at_begining_of_line = true;
+                }
+                buf = rest;
+            }
+            return Ok(bytes_written);
+        }
+
         let mut bytes_written = 0;
 
         while !buf.is_empty() {
@@ -124,4 +204,40 @@ foo
 
         Ok(())
     }
+
+    #[test]
+    fn output_indent() -> Result<()> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        let mut out = IndentedWriter::new(&mut buffer, IndentConfig::Space(4))
+            .with_output_indent(Some(IndentConfig::Tab));
+
+        writeln!(out, "def foo():")?;
+        out.indent();
+        write!(out, "  ")?;
+        writeln!(out, "  if x:\n        return 1\n   \n      y")?;
+        writeln!(out, "z")?;
+        out.unindent();
+        writeln!(out, "foo")?;
+
+        let expect: &[u8] = b"\
+def foo():
+\t\tif x:
+\t\t\treturn 1
+
+\t\t  y
+\tz
+foo
+";
+        assert_eq!(buffer, expect);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut out = IndentedWriter::new(&mut buffer, IndentConfig::Tab)
+            .with_output_indent(Some(IndentConfig::Space(2)));
+        out.indent();
+        writeln!(out, "a\n\tb\n\t c")?;
+        assert_eq!(buffer, b"  a\n    b\n     c\n");
+
+        Ok(())
+    }
 }
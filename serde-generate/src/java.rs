@@ -4,17 +4,32 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use include_dir::include_dir as include_directory;
-use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use serde_reflection::{
+    ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat, WellKnownType,
+    WellKnownTypes,
+};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: false,
+    split_files: true,
+    one_way_serialization: true,
+    bcs_length_prefix: true,
+};
+
 /// Main configuration object for code-generation in Java.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -22,6 +37,12 @@ pub struct CodeGenerator<'a> {
     /// Mapping from external type names to fully-qualified class names (e.g. "MyClass" -> "com.my_org.my_package.MyClass").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Whether to generate a `Visitor<R>` interface and `accept` methods for enums.
+    enum_visitors: bool,
+    /// Registry types to be mapped to a standard Java class.
+    well_known_types: WellKnownTypes,
+    /// Whether constructors copy their fields of type `Bytes`.
+    defensive_copies: bool,
 }
 
 /// Shared state for the code generation of a Java source file.
@@ -37,14 +58,21 @@ struct JavaEmitter<'a, T> {
     /// * We count multiplicities to allow inplace backtracking.
     /// * Names in the registry are assumed to never clash.
     current_reserved_names: HashMap<String, usize>,
+    /// Registry types mapped to a standard Java class (e.g. "Duration").
+    well_known_types: &'a WellKnownTypes,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Java code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Java does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a Java code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Java, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -52,10 +80,43 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", namespace, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             external_qualified_names,
-        }
+            enum_visitors: false,
+            well_known_types: WellKnownTypes::new(),
+            defensive_copies: false,
+        })
+    }
+
+    /// Whether to generate, for each enum, a nested interface `Visitor<R>` with one method
+    /// `visit<Variant>` per variant, together with a method `<R> R accept(Visitor<R> visitor)`
+    /// dispatching on the variant. Default: false.
+    pub fn with_enum_visitors(mut self, enum_visitors: bool) -> Self {
+        self.enum_visitors = enum_visitors;
+        self
+    }
+
+    /// Containers recognized as standard types (see `Tracer::well_known_types`), to be
+    /// represented by a standard Java class instead of a generated one, e.g.
+    /// `java.time.Duration`. The serialized data is unchanged. Default: none.
+    pub fn with_well_known_types(mut self, well_known_types: WellKnownTypes) -> Self {
+        self.well_known_types = well_known_types;
+        self
+    }
+
+    /// Whether the constructors of the generated classes copy their fields of type `Bytes`, so
+    /// that callers cannot change a value through an array that they still hold. This costs
+    /// one copy per field, including during deserialization. Default: false.
+    pub fn with_defensive_copies(mut self, defensive_copies: bool) -> Self {
+        self.defensive_copies = defensive_copies;
+        self
+    }
+
+    /// Report the parts of the registry that cannot be generated in Java, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Java, registry)
     }
 
     /// Output class definitions for ` registry` in separate source files.
@@ -66,6 +127,18 @@ impl<'a> CodeGenerator<'a> {
         install_dir: std::path::PathBuf,
         registry: &Registry,
     ) -> Result<()> {
+        self.output_files(install_dir, registry)?;
+        Ok(())
+    }
+
+    /// Same as `write_source_files` but return the paths of the generated files.
+    fn output_files(
+        &self,
+        install_dir: std::path::PathBuf,
+        registry: &Registry,
+    ) -> Result<BTreeSet<PathBuf>> {
+        let registry = validation::check_registry(self.config, Language::Java, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -79,50 +152,81 @@ impl<'a> CodeGenerator<'a> {
         }
         std::fs::create_dir_all(&dir_path)?;
 
+        let well_known_types: WellKnownTypes = self
+            .well_known_types
+            .iter()
+            .filter(|(name, _)| registry.contains_key(*name))
+            .map(|(name, well_known)| (name.clone(), *well_known))
+            .collect();
+        let mut files = BTreeSet::new();
         for (name, format) in registry {
-            self.write_container_class(&dir_path, current_namespace.clone(), name, format)?;
+            if well_known_types.contains_key(name) {
+                continue;
+            }
+            files.insert(self.write_container_class(
+                &dir_path,
+                current_namespace.clone(),
+                &well_known_types,
+                name,
+                format,
+            )?);
         }
-        if self.config.serialization {
-            self.write_helper_class(&dir_path, current_namespace, registry)?;
+        if self.config.has_serde_methods() {
+            files.insert(self.write_helper_class(
+                &dir_path,
+                current_namespace,
+                &well_known_types,
+                registry,
+            )?);
         }
-        Ok(())
+        Ok(files)
     }
 
     fn write_container_class(
         &self,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
+        well_known_types: &WellKnownTypes,
         name: &str,
         format: &ContainerFormat,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join(name.to_string() + ".java"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join(name.to_string() + ".java");
+        let mut file = std::fs::File::create(&path)?;
         let mut emitter = JavaEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
+            well_known_types,
         };
 
         emitter.output_preamble()?;
-        emitter.output_container(name, format)
+        emitter.output_container(name, format)?;
+        Ok(path)
     }
 
     fn write_helper_class(
         &self,
         dir_path: &std::path::Path,
         current_namespace: Vec<String>,
+        well_known_types: &WellKnownTypes,
         registry: &Registry,
-    ) -> Result<()> {
-        let mut file = std::fs::File::create(dir_path.join("TraitHelpers.java"))?;
+    ) -> Result<PathBuf> {
+        let path = dir_path.join("TraitHelpers.java");
+        let mut file = std::fs::File::create(&path)?;
         let mut emitter = JavaEmitter {
-            out: IndentedWriter::new(&mut file, IndentConfig::Space(4)),
+            out: IndentedWriter::new(&mut file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
             current_reserved_names: HashMap::new(),
+            well_known_types,
         };
 
         emitter.output_preamble()?;
-        emitter.output_trait_helpers(registry)
+        emitter.output_trait_helpers(registry)?;
+        Ok(path)
     }
 }
 
@@ -135,6 +239,10 @@ where
         Ok(())
     }
 
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::Java, name)
+    }
+
     /// Compute a safe reference to the registry type `name` in the given context.
     /// If `name` is not marked as "reserved" (e.g. "Builder"), we compare the global
     /// name `self.qualified_names[name]` with the current namespace and try to use the
@@ -190,7 +298,10 @@ where
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(x) => self.quote_qualified_name(x),
+            TypeName(x) => match self.well_known_types.get(x) {
+                Some(WellKnownType::Duration) => "java.time.Duration".into(),
+                None => self.quote_qualified_name(x),
+            },
             Unit => "com.novi.serde.Unit".into(),
             Bool => "Boolean".into(),
             I8 => "Byte".into(),
@@ -277,14 +388,56 @@ where
         writeln!(self.out, "final class TraitHelpers {{")?;
         let reserved_names = &[];
         self.enter_class("TraitHelpers", reserved_names);
+        for (name, well_known) in self.well_known_types {
+            self.output_well_known_helpers(name, *well_known)?;
+        }
         for (mangled_name, subtype) in &subtypes {
-            self.output_serialization_helper(mangled_name, subtype)?;
-            self.output_deserialization_helper(mangled_name, subtype)?;
+            if self.generator.config.serialization {
+                self.output_serialization_helper(mangled_name, subtype)?;
+            }
+            if self.generator.config.has_deserialization() {
+                self.output_deserialization_helper(mangled_name, subtype)?;
+            }
+            if self.generator.config.serialization && self.generator.config.size_estimators {
+                self.output_size_estimation_helper(mangled_name, subtype)?;
+            }
         }
         self.leave_class(reserved_names);
         writeln!(self.out, "}}\n")
     }
 
+    fn output_well_known_helpers(&mut self, name: &str, well_known: WellKnownType) -> Result<()> {
+        match well_known {
+            WellKnownType::Duration => write!(
+                self.out,
+                r#"static void serialize_{0}(java.time.Duration value, com.novi.serde.Serializer serializer) throws com.novi.serde.SerializationError {{
+    if (value.isNegative()) {{
+        throw new com.novi.serde.SerializationError("Negative durations are not supported");
+    }}
+    serializer.increase_container_depth();
+    serializer.serialize_u64(value.getSeconds());
+    serializer.serialize_u32(value.getNano());
+    serializer.decrease_container_depth();
+}}
+
+static java.time.Duration deserialize_{0}(com.novi.serde.Deserializer deserializer) throws com.novi.serde.DeserializationError {{
+    deserializer.increase_container_depth();
+    long seconds = deserializer.deserialize_u64();
+    int nanos = deserializer.deserialize_u32();
+    deserializer.decrease_container_depth();
+    if (seconds < 0 || Integer.toUnsignedLong(nanos) >= 1000000000L) {{
+        throw new com.novi.serde.DeserializationError("Duration out of range");
+    }}
+    return java.time.Duration.ofSeconds(seconds, nanos);
+}}
+
+"#,
+                name
+            )?,
+        }
+        Ok(())
+    }
+
     fn needs_helper(format: &Format) -> bool {
         use Format::*;
         matches!(
@@ -296,7 +449,9 @@ where
     fn quote_serialize_value(&self, value: &str, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(_) => format!("{}.serialize(serializer);", value),
+            TypeName(name) if !self.well_known_types.contains_key(name) => {
+                format!("{}.serialize(serializer);", value)
+            }
             Unit => format!("serializer.serialize_unit({});", value),
             Bool => format!("serializer.serialize_bool({});", value),
             I8 => format!("serializer.serialize_i8({});", value),
@@ -323,10 +478,35 @@ where
         }
     }
 
+    fn quote_size_value(&self, value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(name) => match self.well_known_types.get(name) {
+                Some(WellKnownType::Duration) => "12".to_string(),
+                None => format!("{}.estimatedSize(estimator)", value),
+            },
+            Unit => "0".to_string(),
+            Bool | I8 | U8 => "1".to_string(),
+            I16 | U16 => "2".to_string(),
+            I32 | U32 | F32 => "4".to_string(),
+            I64 | U64 | F64 => "8".to_string(),
+            I128 | U128 => "16".to_string(),
+            Char => format!("estimator.char_size({})", value),
+            Str => format!("estimator.str_size({})", value),
+            Bytes => format!("estimator.bytes_size({})", value),
+            _ => format!(
+                "{}.estimated_size_{}({}, estimator)",
+                self.quote_qualified_name("TraitHelpers"),
+                common::mangle_type(format),
+                value
+            ),
+        }
+    }
+
     fn quote_deserialize(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(name) => format!(
+            TypeName(name) if !self.well_known_types.contains_key(name) => format!(
                 "{}.deserialize(deserializer)",
                 self.quote_qualified_name(name)
             ),
@@ -447,6 +627,99 @@ for ({1} item : value) {{
         writeln!(self.out, "}}\n")
     }
 
+    fn output_size_estimation_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "static int estimated_size_{}({} value, com.novi.serde.SizeEstimator estimator) {{",
+            name,
+            self.quote_type(format0)
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (value.isPresent()) {{
+    return 1 + {};
+}} else {{
+    return 1;
+}}
+"#,
+                    self.quote_size_value("value.get()", format)
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+int size = estimator.len_size(value.size());
+for ({} item : value) {{
+    size += {};
+}}
+return size;
+"#,
+                    self.quote_type(format),
+                    self.quote_size_value("item", format)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+int size = estimator.len_size(value.size());
+for (java.util.Map.Entry<{}, {}> entry : value.entrySet()) {{
+    size += {};
+    size += {};
+}}
+return size;
+"#,
+                    self.quote_type(key),
+                    self.quote_type(value),
+                    self.quote_size_value("entry.getKey()", key),
+                    self.quote_size_value("entry.getValue()", value)
+                )?;
+            }
+
+            Tuple(formats) => {
+                writeln!(self.out)?;
+                writeln!(self.out, "int size = 0;")?;
+                for (index, format) in formats.iter().enumerate() {
+                    let expr = format!("value.field{}", index);
+                    writeln!(
+                        self.out,
+                        "size += {};",
+                        self.quote_size_value(&expr, format)
+                    )?;
+                }
+                writeln!(self.out, "return size;")?;
+            }
+
+            TupleArray { content, .. } => {
+                write!(
+                    self.out,
+                    r#"
+int size = 0;
+for ({} item : value) {{
+    size += {};
+}}
+return size;
+"#,
+                    self.quote_type(content),
+                    self.quote_size_value("item", content),
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
     fn output_deserialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
         use Format::*;
 
@@ -572,14 +845,20 @@ return obj;
                 name: "value".to_string(),
                 value: format.as_ref().clone(),
             }],
-            Tuple(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect(),
+            Tuple(formats) => {
+                let names = common::tuple_field_names(self.generator.config, base, name);
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Named {
+                        name: match names {
+                            Some(names) => names[i].clone(),
+                            None => format!("field{}", i),
+                        },
+                        value: f.clone(),
+                    })
+                    .collect()
+            }
             Struct(fields) => fields.clone(),
             Variable(_) => panic!("incorrect value"),
         };
@@ -626,7 +905,7 @@ return obj;
                 self.out,
                 "public final {} {};",
                 self.quote_type(&field.value),
-                field.name
+                self.quote_field(&field.name)
             )?;
         }
         if !fields.is_empty() {
@@ -639,7 +918,11 @@ return obj;
             name,
             fields
                 .iter()
-                .map(|f| format!("{} {}", self.quote_type(&f.value), &f.name))
+                .map(|f| format!(
+                    "{} {}",
+                    self.quote_type(&f.value),
+                    self.quote_field(&f.name)
+                ))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -648,11 +931,19 @@ return obj;
             writeln!(
                 self.out,
                 "java.util.Objects.requireNonNull({0}, \"{0} must not be null\");",
-                &field.name
+                self.quote_field(&field.name)
             )?;
         }
         for field in fields {
-            writeln!(self.out, "this.{} = {};", &field.name, &field.name)?;
+            if self.generator.defensive_copies && field.value == Format::Bytes {
+                writeln!(
+                    self.out,
+                    "this.{0} = new com.novi.serde.Bytes({0}.content());",
+                    self.quote_field(&field.name)
+                )?;
+            } else {
+                writeln!(self.out, "this.{0} = {0};", self.quote_field(&field.name))?;
+            }
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
@@ -671,7 +962,7 @@ return obj;
                 writeln!(
                     self.out,
                     "{}",
-                    self.quote_serialize_value(&field.name, &field.value)
+                    self.quote_serialize_value(&self.quote_field(&field.name), &field.value)
                 )?;
             }
             writeln!(self.out, "serializer.decrease_container_depth();")?;
@@ -684,8 +975,44 @@ return obj;
                 }
             }
         }
+        // Size estimation
+        if self.generator.config.serialization && self.generator.config.size_estimators {
+            writeln!(
+                self.out,
+                "\npublic int estimatedSize(com.novi.serde.SizeEstimator estimator) {{",
+            )?;
+            self.out.indent();
+            writeln!(self.out, "int size = 0;")?;
+            if let Some(index) = variant_index {
+                writeln!(self.out, "size += estimator.variant_index_size({});", index)?;
+            }
+            for field in fields {
+                writeln!(
+                    self.out,
+                    "size += {};",
+                    self.quote_size_value(&self.quote_field(&field.name), &field.value)
+                )?;
+            }
+            writeln!(self.out, "return size;")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            if variant_index.is_none() {
+                for encoding in &self.generator.config.encodings {
+                    self.output_class_estimated_size_for_encoding(*encoding)?;
+                }
+            }
+        }
+        // Visitor dispatch
+        if let (Some(base), true) = (variant_base, self.generator.enum_visitors) {
+            writeln!(
+                self.out,
+                "\npublic <R> R accept({0}.Visitor<R> visitor) {{\n    return visitor.visit{1}(this);\n}}",
+                base, name,
+            )?;
+        }
         // Deserialize (struct) or Load (variant)
-        if self.generator.config.serialization {
+        if self.generator.config.has_deserialization() {
             if variant_index.is_none() {
                 writeln!(
                     self.out,
@@ -706,7 +1033,7 @@ return obj;
                 writeln!(
                     self.out,
                     "builder.{} = {};",
-                    field.name,
+                    self.quote_field(&field.name),
                     self.quote_deserialize(&field.value)
                 )?;
             }
@@ -737,7 +1064,7 @@ if (getClass() != obj.getClass()) return false;
             writeln!(
                 self.out,
                 "if (!java.util.Objects.equals(this.{0}, other.{0})) {{ return false; }}",
-                &field.name,
+                self.quote_field(&field.name),
             )?;
         }
         writeln!(self.out, "return true;")?;
@@ -746,12 +1073,17 @@ if (getClass() != obj.getClass()) return false;
         // Hashing
         writeln!(self.out, "\npublic int hashCode() {{")?;
         self.out.indent();
-        writeln!(self.out, "int value = 7;",)?;
+        // Variants of the same enum start from different seeds to spread hash values.
+        writeln!(
+            self.out,
+            "int value = {};",
+            7 + u64::from(variant_index.unwrap_or(0))
+        )?;
         for field in fields {
             writeln!(
                 self.out,
                 "value = 31 * value + (this.{0} != null ? this.{0}.hashCode() : 0);",
-                &field.name
+                self.quote_field(&field.name)
             )?;
         }
         writeln!(self.out, "return value;")?;
@@ -782,7 +1114,7 @@ if (getClass() != obj.getClass()) return false;
                 self.out,
                 "public {} {};",
                 self.quote_type(&field.value),
-                field.name
+                self.quote_field(&field.name)
             )?;
         }
         if !fields.is_empty() {
@@ -798,7 +1130,7 @@ if (getClass() != obj.getClass()) return false;
             name,
             fields
                 .iter()
-                .map(|f| format!("\n        {}", f.name))
+                .map(|f| format!("\n        {}", self.quote_field(&f.name)))
                 .collect::<Vec<_>>()
                 .join(",")
         )?;
@@ -817,16 +1149,24 @@ if (getClass() != obj.getClass()) return false;
         writeln!(self.out)?;
         self.output_comment(name)?;
         writeln!(self.out, "public abstract class {} {{", name)?;
-        let reserved_names = variants
+        let mut reserved_names = variants
             .values()
             .map(|v| v.name.as_str())
             .collect::<Vec<_>>();
+        if self.generator.enum_visitors {
+            reserved_names.push("Visitor");
+        }
         self.enter_class(name, &reserved_names);
+        if self.generator.enum_visitors {
+            self.output_enum_visitor(variants)?;
+        }
         if self.generator.config.serialization {
             writeln!(
                 self.out,
                 "\nabstract public void serialize(com.novi.serde.Serializer serializer) throws com.novi.serde.SerializationError;"
             )?;
+        }
+        if self.generator.config.has_deserialization() {
             write!(
                 self.out,
                 "\npublic static {} deserialize(com.novi.serde.Deserializer deserializer) throws com.novi.serde.DeserializationError {{",
@@ -856,29 +1196,89 @@ switch (index) {{"#,
             writeln!(self.out, "}}")?;
             self.out.unindent();
             writeln!(self.out, "}}")?;
-
-            for encoding in &self.generator.config.encodings {
+        }
+        for encoding in &self.generator.config.encodings {
+            if self.generator.config.serialization {
                 self.output_class_serialize_for_encoding(*encoding)?;
+            }
+            if self.generator.config.has_deserialization() {
                 self.output_class_deserialize_for_encoding(name, *encoding)?;
             }
         }
+        if self.generator.config.serialization && self.generator.config.size_estimators {
+            writeln!(
+                self.out,
+                "\nabstract public int estimatedSize(com.novi.serde.SizeEstimator estimator);"
+            )?;
+            for encoding in &self.generator.config.encodings {
+                self.output_class_estimated_size_for_encoding(*encoding)?;
+            }
+        }
 
         self.output_variants(name, variants)?;
         self.leave_class(&reserved_names);
         writeln!(self.out, "}}\n")
     }
 
+    fn output_enum_visitor(
+        &mut self,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out, "\npublic interface Visitor<R> {{")?;
+        self.out.indent();
+        for variant in variants.values() {
+            writeln!(self.out, "R visit{0}({0} value);", variant.name)?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        writeln!(
+            self.out,
+            "\nabstract public <R> R accept(Visitor<R> visitor);"
+        )
+    }
+
     fn output_class_serialize_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        let output = if self.has_length_prefix(encoding) {
+            "byte[] content = serializer.get_bytes();
+    return java.nio.ByteBuffer.allocate(4 + content.length).putInt(content.length).put(content).array();"
+        } else {
+            "return serializer.get_bytes();"
+        };
         writeln!(
             self.out,
             r#"
 public byte[] {0}Serialize() throws com.novi.serde.SerializationError {{
     com.novi.serde.Serializer serializer = new com.novi.{0}.{1}Serializer();
     serialize(serializer);
-    return serializer.get_bytes();
+    {2}
+}}"#,
+            encoding.name(),
+            encoding.name().to_camel_case(),
+            output
+        )
+    }
+
+    /// Whether top-level messages of the encoding are framed with a 4-byte big-endian length
+    /// (see `CodeGeneratorConfig::with_bcs_length_prefix`).
+    fn has_length_prefix(&self, encoding: Encoding) -> bool {
+        encoding == Encoding::Bcs && self.generator.config.bcs_length_prefix
+    }
+
+    fn output_class_estimated_size_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        let prefix = if self.has_length_prefix(encoding) {
+            " + 4"
+        } else {
+            ""
+        };
+        writeln!(
+            self.out,
+            r#"
+public int estimated{1}Size() {{
+    return estimatedSize(new com.novi.{0}.{1}SizeEstimator()){2};
 }}"#,
             encoding.name(),
-            encoding.name().to_camel_case()
+            encoding.name().to_camel_case(),
+            prefix
         )
     }
 
@@ -887,23 +1287,41 @@ public byte[] {0}Serialize() throws com.novi.serde.SerializationError {{
         name: &str,
         encoding: Encoding,
     ) -> Result<()> {
+        // The length prefix is checked before the content is deserialized.
+        let (prefix_check, content) = if self.has_length_prefix(encoding) {
+            (
+                r#"
+    if (input.length < 4) {
+         throw new com.novi.serde.DeserializationError("Input is too short for a length prefix");
+    }
+    if (java.nio.ByteBuffer.wrap(input, 0, 4).getInt() != input.length - 4) {
+         throw new com.novi.serde.DeserializationError("Length prefix does not match the message length");
+    }
+    byte[] content = java.util.Arrays.copyOfRange(input, 4, input.length);"#,
+                "content",
+            )
+        } else {
+            ("", "input")
+        };
         writeln!(
             self.out,
             r#"
 public static {0} {1}Deserialize(byte[] input) throws com.novi.serde.DeserializationError {{
     if (input == null) {{
          throw new com.novi.serde.DeserializationError("Cannot deserialize null array");
-    }}
-    com.novi.serde.Deserializer deserializer = new com.novi.{1}.{2}Deserializer(input);
+    }}{3}
+    com.novi.serde.Deserializer deserializer = new com.novi.{1}.{2}Deserializer({4});
     {0} value = deserialize(deserializer);
-    if (deserializer.get_buffer_offset() < input.length) {{
+    if (deserializer.get_buffer_offset() < {4}.length) {{
          throw new com.novi.serde.DeserializationError("Some input bytes were not read");
     }}
     return value;
 }}"#,
             name,
             encoding.name(),
-            encoding.name().to_camel_case()
+            encoding.name().to_camel_case(),
+            prefix_check,
+            content
         )
     }
 
@@ -936,11 +1354,39 @@ public static {0} {1}Deserialize(byte[] input) throws com.novi.serde.Deserializa
 /// Installer for generated source files in Java.
 pub struct Installer {
     install_dir: PathBuf,
+    enum_visitors: bool,
+    well_known_types: WellKnownTypes,
+    defensive_copies: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            enum_visitors: false,
+            well_known_types: WellKnownTypes::new(),
+            defensive_copies: false,
+        }
+    }
+
+    /// Whether generated enums should have visitors (see `CodeGenerator::with_enum_visitors`).
+    pub fn with_enum_visitors(mut self, enum_visitors: bool) -> Self {
+        self.enum_visitors = enum_visitors;
+        self
+    }
+
+    /// Containers to be represented by standard Java classes (see
+    /// `CodeGenerator::with_well_known_types`).
+    pub fn with_well_known_types(mut self, well_known_types: WellKnownTypes) -> Self {
+        self.well_known_types = well_known_types;
+        self
+    }
+
+    /// Whether constructors copy their fields of type `Bytes` (see
+    /// `CodeGenerator::with_defensive_copies`).
+    pub fn with_defensive_copies(mut self, defensive_copies: bool) -> Self {
+        self.defensive_copies = defensive_copies;
+        self
     }
 
     fn install_runtime(
@@ -966,8 +1412,13 @@ impl crate::SourceInstaller for Installer {
         config: &CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let generator = CodeGenerator::new(config);
-        generator.write_source_files(self.install_dir.clone(), registry)?;
+        let generator = CodeGenerator::try_new(config)?
+            .with_enum_visitors(self.enum_visitors)
+            .with_well_known_types(self.well_known_types.clone())
+            .with_defensive_copies(self.defensive_copies);
+        for path in generator.output_files(self.install_dir.clone(), registry)? {
+            config.format_source(Language::Java, &path)?;
+        }
         Ok(())
     }
 
@@ -25,6 +25,14 @@
 //!
 //! * TypeScript 4 (packaged and tested with Deno) [(follow-up issue)](https://github.com/zefchain/serde-reflection/issues/58)
 //! * Solidity (tested with Revm) [(initial PR with discussion)](https://github.com/zefchain/serde-reflection/pull/61)
+//! * Zig >= 0.13 (BCS and Bincode only)
+//! * PHP >= 8.1 on 64-bit platforms (BCS and Bincode only)
+//!
+//! Besides programming languages, the module `openapi` (feature `openapi`) writes an
+//! [OpenAPI](https://spec.openapis.org/oas/latest.html) fragment with one schema per container
+//! under `components.schemas`, following the JSON conventions of Serde as implemented by
+//! `serde_json`. See `openapi::CodeGenerator::with_openapi_version` and
+//! `openapi::CodeGenerator::with_openapi_bytes`.
 //!
 //! ## Supported Encodings
 //!
@@ -37,6 +45,35 @@
 //! * [BCS](https://github.com/diem/bcs) (short for Binary Canonical Serialization, the main format used
 //!   in the [Diem blockchain](https://github.com/diem/diem)).
 //!
+//! A runtime for [postcard](https://docs.rs/postcard/1/postcard/) is also provided in Python.
+//!
+//! Python also provides a runtime for [MessagePack](https://msgpack.org/) (`Encoding::MessagePack`),
+//! compatible with the `rmp-serde` crate when structs are encoded as maps (`rmp_serde::to_vec_named`).
+//! Integers wider than 64 bits are rejected with this encoding.
+//!
+//! Floating-point formats (`F32` and `F64`) are supported by Bincode. Since BCS does not specify a
+//! canonical encoding for them, they are rejected with BCS unless
+//! `CodeGeneratorConfig::with_allow_nonstandard_floats` is set. Likewise, they are rejected with
+//! postcard unless `CodeGeneratorConfig::with_postcard_floats` is set.
+//!
+//! With Bincode, runtimes preserve the bit patterns of floating-point values, including negative
+//! zeros, infinities, and NaN payloads, so that re-serializing a value produces the same bytes.
+//! The only exception is TypeScript, where `F32` values are doubles and signaling NaNs may become
+//! quiet. Note that the generated equality methods follow the language (e.g. NaN is not equal to
+//! itself in Python).
+//!
+//! Structs listed with `CodeGeneratorConfig::with_elision_containers` also get a compact encoding
+//! in Rust (encoding only), Python, and TypeScript: a presence bitmap followed by the BCS encoding of
+//! the fields that are not at their default value, such as zero, empty strings, or `None`. This
+//! deliberately non-standard encoding is only used by the methods `compact_serialize` and
+//! `compact_deserialize` (`compactSerialize` and `compactDeserialize` in TypeScript). It requires the
+//! BCS runtime in Python and TypeScript, and the `bcs` crate in Rust.
+//!
+//! Names of containers, fields, and variants must be valid identifiers in the target language.
+//! Names with Unicode letters (e.g. after `#[serde(rename = "Café")]`) are kept in languages that
+//! allow them and rejected otherwise. With `CodeGeneratorConfig::with_non_ascii_names`, other
+//! characters may instead be replaced with their code points, e.g. `CafU00E9`.
+//!
 //! ## Quick Start with Python and Bincode
 //!
 //! In the following example, we transfer a `Test` value from Rust to Python using [`bincode`](https://docs.rs/bincode/1.3.1/bincode/).
@@ -51,7 +88,7 @@
 //!     b: (u32, u32),
 //! }
 //!
-//! # fn main() -> Result<(), std::io::Error> {
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! // Obtain the Serde format of `Test`. (In practice, formats are more likely read from a file.)
 //! let mut tracer = Tracer::new(TracerConfig::default());
 //! tracer.trace_simple_type::<Test>().unwrap();
@@ -61,7 +98,7 @@
 //! let mut source = Vec::new();
 //! let config = serde_generate::CodeGeneratorConfig::new("testing".to_string())
 //!     .with_encodings(vec![serde_generate::Encoding::Bincode]);
-//! let generator = serde_generate::python3::CodeGenerator::new(&config);
+//! let generator = serde_generate::python3::CodeGenerator::try_new(&config)?;
 //! generator.output(&mut source, &registry)?;
 //!
 //! assert!(
@@ -103,10 +140,22 @@
 //! saved on disk.
 //!
 //! The tool `serdegen` assumes that a Rust value of type `serde_reflection::Registry` has
-//! been serialized into a YAML file. The recommended way to generate such a value is to
+//! been serialized into a YAML file, or into a JSON file if the file name ends with `.json`
+//! (see also the option `--input-format`). The recommended way to generate such a value is to
 //! use the library `serde-reflection` to introspect Rust definitions (see also the
 //! example above).
 //!
+//! Several input files may be passed to `serdegen`. They are merged into a single registry,
+//! provided that containers defined in more than one file have the same format. With
+//! `--emit-registry <path>`, the resulting registry is also written back to disk, in JSON or YAML
+//! depending on the extension of the path, or on the option `--registry-format` (an alias of
+//! `--input-format`).
+//!
+//! Input files are validated before generating code: tuple arrays larger than 65536 elements,
+//! formats nested more than 64 levels deep (see `--max-tuple-array-size` and `--max-depth`), and
+//! container names that cannot be used as file names in languages writing one file per container
+//! are rejected (see also `validation::validate_registry_limits`).
+//!
 //! For a quick test, one may create a test file like this:
 //! ```bash
 //! cat >test.yaml <<EOF
@@ -127,19 +176,105 @@
 //! cargo run -p serde-generate-bin -- --language python3 test.yaml > test.py
 //! ```
 //!
+//! Passing `--target-source-dir -` also prints code on stdout. Runtimes are not installed in this
+//! mode, and languages whose code spans several files (Java, Dart, C#) are rejected.
+//!
 //! To create a python module `test` and install the bincode runtime in a directory `$DEST`, you may run:
 //! ```bash
 //! cargo run -p serde-generate-bin -- --language python3 --with-runtimes serde bincode --module-name test --target-source-dir "$DEST" test.yaml
 //! ```
 //!
+//! Several languages may be generated at once by repeating `--language` or by passing a
+//! comma-separated list (`all` selects every language). Each language is then installed in a
+//! subdirectory of the target directory named after the language, e.g. `$DEST/python3` and
+//! `$DEST/java`. Failures are reported together after all languages have been processed:
+//! ```bash
+//! cargo run -p serde-generate-bin -- --language python3,java --with-runtimes serde bincode --module-name test --target-source-dir "$DEST" test.yaml
+//! ```
+//!
+//! To generate type definitions only, without serialization methods or helper classes, pass
+//! `--types-only`. No runtime is needed in this case, hence `--with-runtimes` is rejected:
+//! ```bash
+//! cargo run -p serde-generate-bin -- --language python3 --types-only test.yaml > test.py
+//! ```
+//!
+//! Containers may be selected with `--include-containers` and `--exclude-containers` (separated by
+//! commas). Included containers come with the containers they reference, and generation fails if a
+//! generated container references an excluded one (see also `analyzer::filter_registry`):
+//! ```bash
+//! cargo run -p serde-generate-bin -- --language typescript --exclude-containers Internal,Debug test.yaml > test.ts
+//! ```
+//!
+//! Dart and Swift files written in the target directory start with a `@generated by serde-generate` header.
+//! Files without this header, such as user-written extensions, are never overwritten. With `--clean`, generated
+//! files that are no longer produced by the current run are deleted.
+//!
+//! To find out whether a registry can be generated in some languages without generating any code,
+//! use the `check` subcommand. It reports the unsupported formats of each language (all languages
+//! by default) and fails if any is found:
+//! ```bash
+//! cargo run -p serde-generate-bin -- check --language solidity --language typescript test.yaml
+//! ```
+//!
+//! The `diff` subcommand compares two versions of a registry (see also the module `diff`). It lists
+//! the changes by path and exits with status 0 if the registries are identical, 2 if the new version
+//! only adds containers or enum variants, and 3 in case of breaking changes. With `--deny breaking`,
+//! additions exit with status 0, which is convenient for CI checks:
+//! ```bash
+//! cargo run -p serde-generate-bin -- diff --deny breaking old.yaml new.yaml
+//! ```
+//!
+//! The `vectors` subcommand writes a JSON manifest of test vectors for third-party decoders: the
+//! encoding of a canonical value of each container, in hexadecimal, together with a hash of the
+//! registry. Canonical values follow the default rules of the tracer (see `Value::sample` in
+//! `serde-reflection`), e.g. zero for integers, empty sequences, and the first non-recursive variant
+//! of each enum:
+//! ```bash
+//! cargo run -p serde-generate-bin -- vectors --encoding bcs --out vectors.json test.yaml
+//! ```
+//!
+//! Options without a command-line flag, such as comments, custom code, and external definitions,
+//! may be given in a YAML or JSON file with `--config <path>`. Qualified names are written as dotted
+//! paths starting with the module name. Custom code of the form `@file:<path>` is read from a path
+//! relative to the config file. Command-line flags take precedence over the config file:
+//! ```yaml
+//! module_name: test
+//! comments:
+//!   test.Foo: Some comments.
+//! custom_code:
+//!   test.Foo: "@file:foo.py"
+//! external_definitions:
+//!   other_module: [Bar]
+//! ```
+//!
+//! With `--cache-dir <dir>`, each generation in the target directory is recorded in a stamp file
+//! of `<dir>`, and languages whose registry, options, and config did not change since the last
+//! successful generation, and whose generated files still exist, are skipped without writing
+//! any file (see also `cached_generate`).
+//!
+//! With `--watch`, `serdegen` keeps running after the initial generation and regenerates code
+//! whenever an input file or the config file changes. Each run prints a line on stderr telling
+//! whether generation succeeded; errors do not stop the watch.
+//!
+//! With `--format`, generated sources are formatted with `rustfmt`, `prettier`, `gofmt`, or `black`
+//! when the corresponding tool is installed (see also `CodeGeneratorConfig::with_formatter`).
+//!
 //! See the help message of the tool with `--help` for more options.
 //!
 //! Note: Outside of this repository, you may install the tool with `cargo install serde-generate-bin` then use `$HOME/.cargo/bin/serdegen`.
 
 /// Dependency analysis and topological sort for Serde formats.
 pub mod analyzer;
+/// Skipping code generation when its inputs did not change.
+pub mod cache;
+/// Comparison of registries to detect breaking changes.
+pub mod diff;
+/// Language-independent entry point of the code generators.
+pub mod facade;
 /// Utility function to generate indented text
 pub mod indent;
+/// Detection of identifier collisions and unsupported formats in generated code.
+pub mod validation;
 
 /// Support for code-generation in C++
 #[cfg(feature = "cpp")]
@@ -159,6 +294,12 @@ pub mod java;
 /// Support for code-generation in OCaml
 #[cfg(feature = "ocaml")]
 pub mod ocaml;
+/// Support for the generation of OpenAPI component schemas
+#[cfg(feature = "openapi")]
+pub mod openapi;
+/// Support for code-generation in PHP
+#[cfg(feature = "php")]
+pub mod php;
 /// Support for code-generation in Python 3
 #[cfg(feature = "python3")]
 pub mod python3;
@@ -174,10 +315,112 @@ pub mod swift;
 /// Support for code-generation in TypeScript/JavaScript
 #[cfg(feature = "typescript")]
 pub mod typescript;
+/// Support for code-generation in Zig
+#[cfg(feature = "zig")]
+pub mod zig;
 
 /// Common logic for codegen.
 mod common;
 /// Common configuration objects and traits used in public APIs.
 mod config;
 
+pub use cache::cached_generate;
 pub use config::*;
+pub use facade::generate;
+
+/// A configuration that a code generator does not support.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The language has no equivalent of C-style enums (see `CodeGeneratorConfig::with_c_style_enums`).
+    CStyleEnums { language: validation::Language },
+    /// The code generator of the language cannot generate serialization methods without
+    /// deserialization methods, or conversely (see `CodeGeneratorConfig::with_deserialization`).
+    OneWaySerialization { language: validation::Language },
+    /// The code generator of the language cannot frame BCS messages with a length prefix (see
+    /// `CodeGeneratorConfig::with_bcs_length_prefix`).
+    BcsLengthPrefix { language: validation::Language },
+    /// The code generator of the language does not support the encoding.
+    Encoding {
+        language: validation::Language,
+        encoding: Encoding,
+    },
+    /// The feature requires a more recent version of the language than the configured one
+    /// (see `python3::CodeGenerator::with_python_version`).
+    LanguageVersion {
+        language: validation::Language,
+        feature: &'static str,
+        version: (u32, u32),
+    },
+    /// Two options of the config cannot be used together.
+    Conflict {
+        language: validation::Language,
+        options: (&'static str, &'static str),
+    },
+    /// The code generator of the language writes several files (see `facade::generate`).
+    SingleFile { language: validation::Language },
+    /// The code generator of the language is disabled by the features of this crate.
+    Unavailable { language: validation::Language },
+    /// The installer does not provide a runtime for the encoding (see
+    /// `SourceInstaller::install_everything`).
+    Runtime { encoding: Encoding },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::CStyleEnums { language } => {
+                write!(f, "{} does not support generating c-style enums", language)
+            }
+            ConfigError::OneWaySerialization { language } => write!(
+                f,
+                "{} does not support generating serialization and deserialization methods separately",
+                language
+            ),
+            ConfigError::BcsLengthPrefix { language } => write!(
+                f,
+                "{} does not support length-prefixed BCS messages yet",
+                language
+            ),
+            ConfigError::Encoding { language, encoding } => write!(
+                f,
+                "{} does not support the {} encoding yet",
+                language,
+                encoding.name()
+            ),
+            ConfigError::LanguageVersion {
+                language,
+                feature,
+                version: (major, minor),
+            } => write!(
+                f,
+                "{} requires {} version {}.{} or later",
+                feature, language, major, minor
+            ),
+            ConfigError::Conflict {
+                language,
+                options: (first, second),
+            } => write!(
+                f,
+                "{} cannot be combined with {} in {}",
+                first, second, language
+            ),
+            ConfigError::SingleFile { language } => write!(
+                f,
+                "{} writes several files and cannot output a single source file",
+                language
+            ),
+            ConfigError::Unavailable { language } => write!(
+                f,
+                "{} is disabled by the features of serde-generate",
+                language
+            ),
+            ConfigError::Runtime { encoding } => write!(
+                f,
+                "The installer does not provide a runtime for the {} encoding",
+                encoding.name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -2,14 +2,14 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    common::uppercase_first_letter,
+    common::{self, uppercase_first_letter},
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use heck::SnakeCase;
 use include_dir::include_dir as include_directory;
-use phf::phf_set;
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
     collections::BTreeMap,
@@ -17,9 +17,21 @@ use std::{
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 pub struct CodeGenerator<'a> {
     config: &'a CodeGeneratorConfig,
     libraries: Vec<String>,
+    yojson: bool,
 }
 
 struct OCamlEmitter<'a, T> {
@@ -29,21 +41,44 @@ struct OCamlEmitter<'a, T> {
 }
 
 impl<'a> CodeGenerator<'a> {
+    /// Create an OCaml code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("OCaml does not support generating c-style enums");
-        }
-        Self {
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create an OCaml code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::OCaml, config)?;
+        Ok(Self {
             config,
             libraries: config
                 .external_definitions
                 .keys()
                 .map(|k| k.to_string())
                 .collect::<Vec<_>>(),
-        }
+            yojson: false,
+        })
+    }
+
+    /// Whether to generate functions `<type>_to_yojson` and `<type>_of_yojson` converting
+    /// values to and from JSON, with the same conventions as serde_json. The generated code
+    /// requires the libraries `yojson` and `serde_yojson`. Default: false.
+    pub fn with_yojson(mut self, yojson: bool) -> Self {
+        self.yojson = yojson;
+        self
+    }
+
+    /// Report the parts of the registry that cannot be generated in OCaml, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::OCaml, registry)
     }
 
     pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = validation::check_registry(self.config, Language::OCaml, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -51,7 +86,8 @@ impl<'a> CodeGenerator<'a> {
             .map(String::from)
             .collect();
         let mut emitter = OCamlEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(2)),
+            out: IndentedWriter::new(out, IndentConfig::Space(2))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
         };
@@ -62,6 +98,9 @@ impl<'a> CodeGenerator<'a> {
             let last = i == n - 1;
             emitter.output_container(name, format, first, last)?;
         }
+        if self.yojson && !registry.is_empty() {
+            emitter.output_yojson_converters(registry)?;
+        }
         for (name, _) in registry.iter() {
             emitter.output_custom_code(name)?;
         }
@@ -69,27 +108,6 @@ impl<'a> CodeGenerator<'a> {
     }
 }
 
-static KEYWORDS: phf::Set<&str> = phf_set! {
-    "and", "as", "assert", "asr",
-    "begin", "class", "constraint",
-    "do", "done", "downto", "else",
-    "end", "exception", "external",
-    "false", "for", "fun", "function",
-    "functor", "if", "in", "include",
-    "inherit", "initializer", "land",
-    "lazy", "let", "lor", "lsl",
-    "lsr", "lxor", "match", "method",
-    "mod", "module", "mutable", "new",
-    "nonrec", "object", "of", "open",
-    "or", "private", "rec", "sig",
-    "struct", "then", "to", "true",
-    "try", "type", "val", "virtual",
-    "when", "while", "with", "bool",
-    "string", "bytes", "char", "unit",
-    "option", "float", "list",
-    "int32", "int64"
-};
-
 impl<'a, T> OCamlEmitter<'a, T>
 where
     T: Write,
@@ -126,12 +144,7 @@ where
     }
 
     fn safe_snake_case(&self, s: &str) -> String {
-        let s = s.to_snake_case();
-        if KEYWORDS.contains(&*s) {
-            s + "_"
-        } else {
-            s
-        }
+        common::escape_keyword(self.generator.config, Language::OCaml, &s.to_snake_case())
     }
 
     fn output_format(&mut self, format: &Format, is_struct: bool) -> Result<()> {
@@ -343,15 +356,361 @@ where
         }
         Ok(())
     }
+
+    fn yojson_primitive(format: &Format) -> Option<&'static str> {
+        use Format::*;
+        Some(match format {
+            Unit => "unit",
+            Bool => "bool",
+            I8 => "int8",
+            I16 => "int16",
+            I32 => "int32",
+            I64 => "int64",
+            I128 => "int128",
+            U8 => "uint8",
+            U16 => "uint16",
+            U32 => "uint32",
+            U64 => "uint64",
+            U128 => "uint128",
+            F32 | F64 => "float",
+            Char => "char",
+            Str => "string",
+            Bytes => "bytes",
+            _ => return None,
+        })
+    }
+
+    /// OCaml function converting values of the given format to JSON.
+    fn quote_to_yojson(&self, format: &Format) -> String {
+        use Format::*;
+        if let Some(name) = Self::yojson_primitive(format) {
+            return format!("Serde_yojson.{}_to_yojson", name);
+        }
+        match format {
+            TypeName(s) => format!("{}_to_yojson", self.safe_snake_case(s)),
+            Option(f) => format!(
+                "(Serde_yojson.option_to_yojson {})",
+                self.quote_to_yojson(f)
+            ),
+            Seq(f) => format!("(Serde_yojson.list_to_yojson {})", self.quote_to_yojson(f)),
+            Map { key, value } => format!(
+                "(Serde_yojson.map_to_yojson {} {})",
+                self.quote_to_yojson(key),
+                self.quote_to_yojson(value)
+            ),
+            TupleArray { content, size: _ } => format!(
+                "(Serde_yojson.array_to_yojson {})",
+                self.quote_to_yojson(content)
+            ),
+            Tuple(formats) => {
+                let vars = (0..formats.len())
+                    .map(|i| format!("x{}", i))
+                    .collect::<Vec<_>>();
+                let values = formats
+                    .iter()
+                    .zip(&vars)
+                    .map(|(f, x)| format!("{} {}", self.quote_to_yojson(f), x))
+                    .collect::<Vec<_>>();
+                format!(
+                    "(fun ({}) -> `List [{}])",
+                    vars.join(", "),
+                    values.join("; ")
+                )
+            }
+            _ => panic!("incorrect value"),
+        }
+    }
+
+    /// OCaml function converting JSON to values of the given format.
+    fn quote_of_yojson(&self, format: &Format) -> String {
+        use Format::*;
+        if let Some(name) = Self::yojson_primitive(format) {
+            return format!("Serde_yojson.{}_of_yojson", name);
+        }
+        match format {
+            TypeName(s) => format!("{}_of_yojson", self.safe_snake_case(s)),
+            Option(f) => format!(
+                "(Serde_yojson.option_of_yojson {})",
+                self.quote_of_yojson(f)
+            ),
+            Seq(f) => format!("(Serde_yojson.list_of_yojson {})", self.quote_of_yojson(f)),
+            Map { key, value } => format!(
+                "(Serde_yojson.map_of_yojson {} {})",
+                self.quote_of_yojson(key),
+                self.quote_of_yojson(value)
+            ),
+            TupleArray { content, size } => format!(
+                "(Serde_yojson.array_of_yojson {} {})",
+                size,
+                self.quote_of_yojson(content)
+            ),
+            Tuple(formats) => {
+                let vars = (0..formats.len())
+                    .map(|i| format!("x{}", i))
+                    .collect::<Vec<_>>();
+                let bindings = formats
+                    .iter()
+                    .zip(&vars)
+                    .map(|(f, x)| format!("let* {0} = {1} {0} in ", x, self.quote_of_yojson(f)))
+                    .collect::<Vec<_>>();
+                format!(
+                    "(function `List [{}] -> {}Ok ({}) | json -> Serde_yojson.error \"a tuple\" json)",
+                    vars.join("; "),
+                    bindings.join(""),
+                    vars.join(", ")
+                )
+            }
+            _ => panic!("incorrect value"),
+        }
+    }
+
+    /// Write the JSON fields of the record `value`, one per line.
+    fn output_yojson_fields_to(&mut self, value: &str, fields: &[Named<Format>]) -> Result<()> {
+        self.out.indent();
+        for field in fields {
+            writeln!(
+                self.out,
+                "(\"{}\", {} {}.{});",
+                field.name,
+                self.quote_to_yojson(&field.value),
+                value,
+                self.safe_snake_case(&field.name)
+            )?;
+        }
+        self.out.unindent();
+        Ok(())
+    }
+
+    /// Read the fields of a record from the JSON fields `l`, and return the record expression.
+    fn output_yojson_fields_of(&mut self, fields: &[Named<Format>]) -> Result<String> {
+        let mut values = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            writeln!(
+                self.out,
+                "let* x{} = Serde_yojson.field \"{}\" {} l in",
+                i,
+                field.name,
+                self.quote_of_yojson(&field.value)
+            )?;
+            values.push(format!("{} = x{}", self.safe_snake_case(&field.name), i));
+        }
+        Ok(format!("{{ {} }}", values.join("; ")))
+    }
+
+    fn output_yojson_variants_to(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        use VariantFormat::*;
+        writeln!(self.out, "match x with")?;
+        for variant in variants.values() {
+            let constructor = format!("{}_{}", name, variant.name);
+            match &variant.value {
+                Variable(_) => panic!("incorrect value"),
+                Unit => writeln!(
+                    self.out,
+                    "| {} -> `String \"{}\"",
+                    constructor, variant.name
+                )?,
+                NewType(format) => writeln!(
+                    self.out,
+                    "| {} x -> `Assoc [(\"{}\", {} x)]",
+                    constructor,
+                    variant.name,
+                    self.quote_to_yojson(format)
+                )?,
+                Tuple(formats) if formats.is_empty() => writeln!(
+                    self.out,
+                    "| {} -> `Assoc [(\"{}\", `List [])]",
+                    constructor, variant.name
+                )?,
+                Tuple(formats) => writeln!(
+                    self.out,
+                    "| {} x -> `Assoc [(\"{}\", {} x)]",
+                    constructor,
+                    variant.name,
+                    self.quote_to_yojson(&Format::Tuple(formats.clone()))
+                )?,
+                Struct(fields) if fields.is_empty() => writeln!(
+                    self.out,
+                    "| {} -> `Assoc [(\"{}\", `Assoc [])]",
+                    constructor, variant.name
+                )?,
+                Struct(fields) => {
+                    writeln!(
+                        self.out,
+                        "| {} r -> `Assoc [(\"{}\", `Assoc [",
+                        constructor, variant.name
+                    )?;
+                    self.output_yojson_fields_to("r", fields)?;
+                    writeln!(self.out, "])]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn output_yojson_variants_of(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        use VariantFormat::*;
+        writeln!(self.out, "match json with")?;
+        for variant in variants.values() {
+            let constructor = format!("{}_{}", name, variant.name);
+            match &variant.value {
+                Variable(_) => panic!("incorrect value"),
+                Unit => writeln!(
+                    self.out,
+                    "| `String \"{}\" -> Ok {}",
+                    variant.name, constructor
+                )?,
+                Tuple(formats) if formats.is_empty() => writeln!(
+                    self.out,
+                    "| `Assoc [(\"{}\", `List [])] -> Ok {}",
+                    variant.name, constructor
+                )?,
+                Struct(fields) if fields.is_empty() => writeln!(
+                    self.out,
+                    "| `Assoc [(\"{}\", `Assoc [])] -> Ok {}",
+                    variant.name, constructor
+                )?,
+                NewType(_) | Tuple(_) => {
+                    let converter = match &variant.value {
+                        NewType(format) => self.quote_of_yojson(format),
+                        Tuple(formats) => self.quote_of_yojson(&Format::Tuple(formats.clone())),
+                        _ => unreachable!(),
+                    };
+                    writeln!(self.out, "| `Assoc [(\"{}\", json)] ->", variant.name)?;
+                    self.out.indent();
+                    writeln!(self.out, "let* x = {} json in", converter)?;
+                    writeln!(self.out, "Ok ({} x)", constructor)?;
+                    self.out.unindent();
+                }
+                Struct(fields) => {
+                    writeln!(self.out, "| `Assoc [(\"{}\", `Assoc l)] ->", variant.name)?;
+                    self.out.indent();
+                    let record = self.output_yojson_fields_of(fields)?;
+                    writeln!(self.out, "Ok ({} {})", constructor, record)?;
+                    self.out.unindent();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the functions `<type>_to_yojson` and `<type>_of_yojson` of a container, as part
+    /// of a recursive definition started by `keyword`.
+    fn output_yojson_container(
+        &mut self,
+        keyword: &str,
+        name: &str,
+        format: &ContainerFormat,
+    ) -> Result<()> {
+        use ContainerFormat::*;
+        let type_name = self.safe_snake_case(name);
+
+        writeln!(
+            self.out,
+            "\n{} {}_to_yojson (x : {}) : Yojson.Safe.t =",
+            keyword, type_name, type_name
+        )?;
+        self.out.indent();
+        match format {
+            UnitStruct => writeln!(self.out, "Serde_yojson.unit_to_yojson x")?,
+            // Cyclic newtypes are wrapped in a constructor but, as other newtypes, they are
+            // not tagged in JSON.
+            NewTypeStruct(format) if Self::is_cyclic(name, format.as_ref()) => writeln!(
+                self.out,
+                "match x with {}_ x -> {} x",
+                name.to_camel_case(),
+                self.quote_to_yojson(format)
+            )?,
+            NewTypeStruct(format) => writeln!(self.out, "{} x", self.quote_to_yojson(format))?,
+            TupleStruct(formats) => writeln!(
+                self.out,
+                "{} x",
+                self.quote_to_yojson(&Format::Tuple(formats.clone()))
+            )?,
+            Struct(fields) => {
+                writeln!(self.out, "`Assoc [")?;
+                self.output_yojson_fields_to("x", fields)?;
+                writeln!(self.out, "]")?;
+            }
+            Enum(variants) => self.output_yojson_variants_to(&name.to_camel_case(), variants)?,
+        }
+        self.out.unindent();
+
+        writeln!(
+            self.out,
+            "\nand {0}_of_yojson (json : Yojson.Safe.t) : ({0}, string) result =",
+            type_name
+        )?;
+        self.out.indent();
+        match format {
+            UnitStruct => writeln!(self.out, "Serde_yojson.unit_of_yojson json")?,
+            NewTypeStruct(format) if Self::is_cyclic(name, format.as_ref()) => {
+                writeln!(
+                    self.out,
+                    "let* x = {} json in",
+                    self.quote_of_yojson(format)
+                )?;
+                writeln!(self.out, "Ok ({}_ x)", name.to_camel_case())?;
+            }
+            NewTypeStruct(format) => writeln!(self.out, "{} json", self.quote_of_yojson(format))?,
+            TupleStruct(formats) => writeln!(
+                self.out,
+                "{} json",
+                self.quote_of_yojson(&Format::Tuple(formats.clone()))
+            )?,
+            Struct(fields) => {
+                writeln!(self.out, "match json with")?;
+                writeln!(self.out, "| `Assoc l ->")?;
+                self.out.indent();
+                let record = self.output_yojson_fields_of(fields)?;
+                writeln!(self.out, "Ok ({} : {})", record, type_name)?;
+                self.out.unindent();
+                writeln!(self.out, "| json -> Serde_yojson.error \"{}\" json", name)?;
+            }
+            Enum(variants) => {
+                self.output_yojson_variants_of(&name.to_camel_case(), variants)?;
+                writeln!(self.out, "| json -> Serde_yojson.error \"{}\" json", name)?;
+            }
+        }
+        self.out.unindent();
+        Ok(())
+    }
+
+    fn output_yojson_converters(&mut self, registry: &Registry) -> Result<()> {
+        writeln!(self.out, "\nlet ( let* ) = Result.bind")?;
+        for (i, (name, format)) in registry.iter().enumerate() {
+            let keyword = if i == 0 { "let rec" } else { "and" };
+            self.output_yojson_container(keyword, name, format)?;
+        }
+        Ok(())
+    }
 }
 
 pub struct Installer {
     install_dir: PathBuf,
+    yojson: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            yojson: false,
+        }
+    }
+
+    /// Whether to generate conversions to and from JSON (see `CodeGenerator::with_yojson`).
+    /// The runtime library `serde_yojson` is then installed with the other runtimes.
+    pub fn with_yojson(mut self, yojson: bool) -> Self {
+        self.yojson = yojson;
+        self
     }
 
     fn install_runtime(
@@ -367,6 +726,13 @@ impl Installer {
         }
         Ok(())
     }
+
+    fn install_yojson_runtime(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.yojson {
+            self.install_runtime(include_directory!("runtime/ocaml/yojson"), "yojson")?;
+        }
+        Ok(())
+    }
 }
 
 impl crate::SourceInstaller for Installer {
@@ -385,24 +751,34 @@ impl crate::SourceInstaller for Installer {
         let dune_source_path = dir_path.join("dune");
         let mut dune_file = std::fs::File::create(dune_source_path)?;
         let name = config.module_name.to_snake_case();
-        let mut runtime_str = "";
+        let mut libraries = Vec::new();
         if config.encodings.len() == 1 {
             for enc in config.encodings.iter() {
                 match enc {
-                    Encoding::Bcs => runtime_str = "\n(libraries bcs_runtime)",
-                    Encoding::Bincode => runtime_str = "\n(libraries bincode_runtime)",
+                    Encoding::Bcs => libraries.push("bcs_runtime"),
+                    Encoding::Bincode => libraries.push("bincode_runtime"),
+                    Encoding::Postcard | Encoding::MessagePack => (),
                 }
             }
         }
+        if self.yojson {
+            libraries.extend(["yojson", "serde_yojson"]);
+        }
+        let runtime_str = if libraries.is_empty() {
+            String::new()
+        } else {
+            format!("\n(libraries {})", libraries.join(" "))
+        };
         writeln!(
             dune_file,
             "(env (_ (flags (:standard -w -30-42 -warn-error -a))))\n\n(library\n (name {0})\n (modules {0})\n (preprocess (pps ppx)){1})",
             name, runtime_str
         )?;
         let source_path = dir_path.join(format!("{}.ml", name));
-        let mut file = std::fs::File::create(source_path)?;
-        let generator = CodeGenerator::new(config);
+        let mut file = std::fs::File::create(&source_path)?;
+        let generator = CodeGenerator::try_new(config)?.with_yojson(self.yojson);
         generator.output(&mut file, registry)?;
+        config.format_source(Language::OCaml, &source_path)?;
         Ok(())
     }
 
@@ -410,7 +786,8 @@ impl crate::SourceInstaller for Installer {
         self.install_runtime(include_directory!("runtime/ocaml/common"), "common")?;
         self.install_runtime(include_directory!("runtime/ocaml/virtual"), "virtual")?;
         self.install_runtime(include_directory!("runtime/ocaml/ppx"), "ppx")?;
-        self.install_runtime(include_directory!("runtime/ocaml/serde"), "serde")
+        self.install_runtime(include_directory!("runtime/ocaml/serde"), "serde")?;
+        self.install_yojson_runtime()
     }
 
     fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
@@ -418,7 +795,8 @@ impl crate::SourceInstaller for Installer {
         self.install_runtime(include_directory!("runtime/ocaml/virtual"), "virtual")?;
         self.install_runtime(include_directory!("runtime/ocaml/ppx"), "ppx")?;
         self.install_runtime(include_directory!("runtime/ocaml/serde"), "serde")?;
-        self.install_runtime(include_directory!("runtime/ocaml/bincode"), "bincode")
+        self.install_runtime(include_directory!("runtime/ocaml/bincode"), "bincode")?;
+        self.install_yojson_runtime()
     }
 
     fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
@@ -426,6 +804,7 @@ impl crate::SourceInstaller for Installer {
         self.install_runtime(include_directory!("runtime/ocaml/virtual"), "virtual")?;
         self.install_runtime(include_directory!("runtime/ocaml/ppx"), "ppx")?;
         self.install_runtime(include_directory!("runtime/ocaml/serde"), "serde")?;
-        self.install_runtime(include_directory!("runtime/ocaml/bcs"), "bcs")
+        self.install_runtime(include_directory!("runtime/ocaml/bcs"), "bcs")?;
+        self.install_yojson_runtime()
     }
 }
@@ -0,0 +1,684 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    indent::IndentConfig,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError,
+};
+use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
+use std::{
+    collections::HashMap,
+    io::{Result, Write},
+    path::PathBuf,
+};
+
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    // Schemas describe values, not (de)serialization methods.
+    one_way_serialization: true,
+    bcs_length_prefix: false,
+};
+
+/// Main configuration object for the generation of OpenAPI component schemas.
+///
+/// Schemas follow the JSON conventions of Serde, as implemented by `serde_json`:
+/// * structs are objects whose fields are all required, newtype structs are their content,
+///   tuples and tuple structs are arrays of fixed length, and unit structs are `null`;
+/// * enums are externally tagged: unit variants are strings holding the name of the variant,
+///   other variants are objects with a single property named after the variant;
+/// * options are nullable, bytes are arrays of integers or base64 strings (see
+///   `with_openapi_bytes`), and maps are objects.
+///
+/// The nullable syntax depends on `with_openapi_version`.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// Mapping from external type names to the modules defining them (e.g. "MyType" -> "other"),
+    /// whose schemas are referenced in the document of the module, e.g. `other.yaml`.
+    /// Derived from `config.external_definitions`.
+    external_modules: HashMap<String, String>,
+    /// Whether to write JSON instead of YAML.
+    json: bool,
+    /// Version of the OpenAPI specification targeted by the schemas.
+    openapi_version: OpenApiVersion,
+    /// How the schemas describe bytes.
+    openapi_bytes: OpenApiBytes,
+}
+
+/// Version of the OpenAPI specification targeted by generated schemas, which decides how
+/// nullable values are described (see `CodeGenerator::with_openapi_version`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenApiVersion {
+    /// OpenAPI 3.0: nullable schemas are marked `nullable: true`.
+    V3_0,
+    /// OpenAPI 3.1: nullable schemas accept the JSON Schema type `null`.
+    V3_1,
+}
+
+/// How OpenAPI schemas describe bytes. Serde writes bytes as arrays of integers in JSON,
+/// unless their type is serialized as a string, e.g. with a base64 helper (see
+/// `CodeGenerator::with_openapi_bytes`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenApiBytes {
+    /// Arrays of integers between 0 and 255.
+    IntegerArray,
+    /// Strings holding the standard base64 encoding of the bytes.
+    Base64,
+}
+
+impl std::str::FromStr for OpenApiVersion {
+    type Err = String;
+
+    /// Parse a version of the form `3.0` or `3.1`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "3.0" => Ok(OpenApiVersion::V3_0),
+            "3.1" => Ok(OpenApiVersion::V3_1),
+            _ => Err(format!(
+                "Unsupported OpenAPI version {} (expected 3.0 or 3.1)",
+                s
+            )),
+        }
+    }
+}
+
+/// A node of the generated document.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create an OpenAPI generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::OpenApi, config)?;
+        let mut external_modules = HashMap::new();
+        for (namespace, names) in &config.external_definitions {
+            for name in names {
+                external_modules.insert(name.to_string(), namespace.to_string());
+            }
+        }
+        Ok(Self {
+            config,
+            external_modules,
+            json: false,
+            openapi_version: OpenApiVersion::V3_1,
+            openapi_bytes: OpenApiBytes::IntegerArray,
+        })
+    }
+
+    /// Whether to write the document in JSON instead of YAML. External definitions are
+    /// then referenced in `.json` documents.
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Version of the OpenAPI specification targeted by generated schemas. Default is
+    /// `OpenApiVersion::V3_1`.
+    pub fn with_openapi_version(mut self, openapi_version: OpenApiVersion) -> Self {
+        self.openapi_version = openapi_version;
+        self
+    }
+
+    /// How generated schemas describe bytes. Default is `OpenApiBytes::IntegerArray`, as
+    /// written by `serde_json`.
+    pub fn with_openapi_bytes(mut self, openapi_bytes: OpenApiBytes) -> Self {
+        self.openapi_bytes = openapi_bytes;
+        self
+    }
+
+    fn extension(&self) -> &'static str {
+        if self.json {
+            "json"
+        } else {
+            "yaml"
+        }
+    }
+
+    /// Report the parts of the registry that cannot be described in OpenAPI, without
+    /// generating any schema.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::OpenApi, registry)
+    }
+
+    /// Output a document fragment with one schema per container of `registry` under
+    /// `components.schemas`.
+    pub fn output(
+        &self,
+        out: &mut dyn Write,
+        registry: &Registry,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let registry = validation::check_registry(self.config, Language::OpenApi, registry)?;
+        let schemas = registry
+            .iter()
+            .map(|(name, format)| (name.clone(), self.container_schema(name, format)))
+            .collect();
+        let document = object(vec![(
+            "components",
+            object(vec![("schemas", Node::Object(schemas))]),
+        )]);
+        let mut text = String::new();
+        match (
+            self.json,
+            self.config.indent.unwrap_or(IndentConfig::Space(2)),
+        ) {
+            (true, IndentConfig::Tab) => write_json(&mut text, &document, "", "\t"),
+            (true, IndentConfig::Space(width)) => {
+                write_json(&mut text, &document, "", &" ".repeat(width))
+            }
+            (false, IndentConfig::Tab) => {
+                return Err("YAML documents cannot be indented with tabs".into())
+            }
+            (false, IndentConfig::Space(width)) => {
+                write_yaml(&mut text, &document, 0, width.max(1))
+            }
+        }
+        writeln!(out, "{}", text.trim_end())?;
+        Ok(())
+    }
+
+    fn comment(&self, path: &[&str]) -> Option<&String> {
+        let mut qualified_name = vec![self.config.module_name.clone()];
+        qualified_name.extend(path.iter().map(|name| name.to_string()));
+        self.config.comments.get(&qualified_name)
+    }
+
+    fn container_schema(&self, name: &str, format: &ContainerFormat) -> Node {
+        use ContainerFormat::*;
+        let schema = match format {
+            UnitStruct => self.null_schema(),
+            NewTypeStruct(format) => self.format_schema(format),
+            TupleStruct(formats) => self.tuple_schema(formats),
+            Struct(fields) => self.struct_schema(&[name], fields),
+            Enum(variants) => {
+                let variants = variants
+                    .values()
+                    .map(|variant| self.variant_schema(name, variant))
+                    .collect();
+                object(vec![("oneOf", Node::Array(variants))])
+            }
+        };
+        self.describe(schema, self.comment(&[name]))
+    }
+
+    fn variant_schema(&self, name: &str, variant: &Named<VariantFormat>) -> Node {
+        use VariantFormat::*;
+        let content = match &variant.value {
+            Unit => {
+                let schema = object(vec![
+                    ("type", string("string")),
+                    ("enum", Node::Array(vec![string(&variant.name)])),
+                ]);
+                return self.describe(schema, self.comment(&[name, &variant.name]));
+            }
+            NewType(format) => self.format_schema(format),
+            Tuple(formats) => self.tuple_schema(formats),
+            Struct(fields) => self.struct_schema(&[name, &variant.name], fields),
+            Variable(_) => panic!("variable is not supported in OpenAPI"),
+        };
+        let schema = object(vec![
+            ("type", string("object")),
+            (
+                "properties",
+                Node::Object(vec![(variant.name.clone(), content)]),
+            ),
+            ("required", Node::Array(vec![string(&variant.name)])),
+            ("additionalProperties", Node::Bool(false)),
+        ]);
+        self.describe(schema, self.comment(&[name, &variant.name]))
+    }
+
+    fn struct_schema(&self, path: &[&str], fields: &[Named<Format>]) -> Node {
+        let mut entries = vec![("type", string("object"))];
+        if !fields.is_empty() {
+            let properties = fields
+                .iter()
+                .map(|field| {
+                    let mut path = path.to_vec();
+                    path.push(&field.name);
+                    let schema = self.format_schema(&field.value);
+                    (
+                        field.name.clone(),
+                        self.describe(schema, self.comment(&path)),
+                    )
+                })
+                .collect();
+            let required = fields.iter().map(|field| string(&field.name)).collect();
+            entries.push(("properties", Node::Object(properties)));
+            entries.push(("required", Node::Array(required)));
+        }
+        object(entries)
+    }
+
+    fn format_schema(&self, format: &Format) -> Node {
+        use Format::*;
+        match format {
+            TypeName(name) => {
+                let document = match self.external_modules.get(name) {
+                    Some(module) => format!("{}.{}", module, self.extension()),
+                    None => String::new(),
+                };
+                object(vec![(
+                    "$ref",
+                    string(&format!("{}#/components/schemas/{}", document, name)),
+                )])
+            }
+            Unit => self.null_schema(),
+            Bool => object(vec![("type", string("boolean"))]),
+            I8 => integer_schema(None, Some(i8::MIN as i128), Some(i8::MAX as i128)),
+            I16 => integer_schema(None, Some(i16::MIN as i128), Some(i16::MAX as i128)),
+            I32 => integer_schema(Some("int32"), None, None),
+            I64 => integer_schema(Some("int64"), None, None),
+            I128 => integer_schema(None, None, None),
+            U8 => integer_schema(None, Some(0), Some(u8::MAX as i128)),
+            U16 => integer_schema(None, Some(0), Some(u16::MAX as i128)),
+            U32 => integer_schema(Some("int64"), Some(0), Some(u32::MAX as i128)),
+            U64 => integer_schema(None, Some(0), Some(u64::MAX as i128)),
+            U128 => integer_schema(None, Some(0), None),
+            F32 => object(vec![
+                ("type", string("number")),
+                ("format", string("float")),
+            ]),
+            F64 => object(vec![
+                ("type", string("number")),
+                ("format", string("double")),
+            ]),
+            Char => object(vec![
+                ("type", string("string")),
+                ("minLength", number(1)),
+                ("maxLength", number(1)),
+            ]),
+            Str => object(vec![("type", string("string"))]),
+            Bytes => match (self.openapi_bytes, self.openapi_version) {
+                (OpenApiBytes::IntegerArray, _) => object(vec![
+                    ("type", string("array")),
+                    ("items", self.format_schema(&U8)),
+                ]),
+                (OpenApiBytes::Base64, OpenApiVersion::V3_0) => {
+                    object(vec![("type", string("string")), ("format", string("byte"))])
+                }
+                (OpenApiBytes::Base64, OpenApiVersion::V3_1) => object(vec![
+                    ("type", string("string")),
+                    ("contentEncoding", string("base64")),
+                ]),
+            },
+            Option(format) => self.nullable(self.format_schema(format)),
+            Seq(format) => object(vec![
+                ("type", string("array")),
+                ("items", self.format_schema(format)),
+            ]),
+            // Keys are written as strings by `serde_json`.
+            Map { value, .. } => object(vec![
+                ("type", string("object")),
+                ("additionalProperties", self.format_schema(value)),
+            ]),
+            Tuple(formats) => self.tuple_schema(formats),
+            TupleArray { content, size } => object(vec![
+                ("type", string("array")),
+                ("items", self.format_schema(content)),
+                ("minItems", number(*size)),
+                ("maxItems", number(*size)),
+            ]),
+            Variable(_) => panic!("variable is not supported in OpenAPI"),
+        }
+    }
+
+    /// Arrays of fixed length. OpenAPI 3.0 cannot constrain each position, so items must
+    /// match one of the formats.
+    fn tuple_schema(&self, formats: &[Format]) -> Node {
+        let mut schemas = Vec::new();
+        for format in formats {
+            schemas.push(self.format_schema(format));
+        }
+        let mut entries = vec![("type", string("array"))];
+        match self.openapi_version {
+            OpenApiVersion::V3_0 => {
+                let mut choices = Vec::new();
+                for schema in schemas {
+                    if !choices.contains(&schema) {
+                        choices.push(schema);
+                    }
+                }
+                let items = match choices.len() {
+                    0 => Node::Object(Vec::new()),
+                    1 => choices.pop().unwrap(),
+                    _ => object(vec![("oneOf", Node::Array(choices))]),
+                };
+                entries.push(("items", items));
+            }
+            OpenApiVersion::V3_1 => {
+                if !schemas.is_empty() {
+                    entries.push(("prefixItems", Node::Array(schemas)));
+                }
+                entries.push(("items", Node::Bool(false)));
+            }
+        }
+        entries.push(("minItems", number(formats.len())));
+        entries.push(("maxItems", number(formats.len())));
+        object(entries)
+    }
+
+    /// Schema of the unit value `()`, written as `null` by `serde_json`.
+    fn null_schema(&self) -> Node {
+        match self.openapi_version {
+            // OpenAPI 3.0 has no `null` type.
+            OpenApiVersion::V3_0 => object(vec![
+                ("type", string("object")),
+                ("nullable", Node::Bool(true)),
+                ("enum", Node::Array(vec![Node::Null])),
+            ]),
+            OpenApiVersion::V3_1 => object(vec![("type", string("null"))]),
+        }
+    }
+
+    fn nullable(&self, schema: Node) -> Node {
+        let mut entries = match schema {
+            Node::Object(entries) => entries,
+            _ => unreachable!("schemas are objects"),
+        };
+        let is_reference = entries.iter().any(|(key, _)| key == "$ref");
+        match self.openapi_version {
+            // Siblings of `$ref` are ignored in OpenAPI 3.0.
+            OpenApiVersion::V3_0 if is_reference => object(vec![
+                ("allOf", Node::Array(vec![Node::Object(entries)])),
+                ("nullable", Node::Bool(true)),
+            ]),
+            OpenApiVersion::V3_0 => {
+                entries.retain(|(key, _)| key != "nullable");
+                entries.push(("nullable".to_string(), Node::Bool(true)));
+                Node::Object(entries)
+            }
+            OpenApiVersion::V3_1 => {
+                let position = entries.iter().position(|(key, _)| key == "type");
+                match position.map(|position| &mut entries[position].1) {
+                    Some(Node::String(kind)) if kind == "null" => (),
+                    Some(kind @ Node::String(_)) if !is_reference => {
+                        *kind = Node::Array(vec![kind.clone(), string("null")]);
+                    }
+                    _ => {
+                        return object(vec![(
+                            "anyOf",
+                            Node::Array(vec![Node::Object(entries), self.null_schema()]),
+                        )]);
+                    }
+                }
+                Node::Object(entries)
+            }
+        }
+    }
+
+    fn describe(&self, schema: Node, description: Option<&String>) -> Node {
+        let description = match description {
+            Some(description) => description.trim(),
+            None => return schema,
+        };
+        let mut entries = match schema {
+            Node::Object(entries) => entries,
+            _ => unreachable!("schemas are objects"),
+        };
+        let is_reference = entries.iter().any(|(key, _)| key == "$ref");
+        if is_reference && self.openapi_version == OpenApiVersion::V3_0 {
+            entries = vec![(
+                "allOf".to_string(),
+                Node::Array(vec![Node::Object(entries)]),
+            )];
+        }
+        entries.push(("description".to_string(), string(description)));
+        Node::Object(entries)
+    }
+}
+
+fn object(entries: Vec<(&str, Node)>) -> Node {
+    Node::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn string(value: &str) -> Node {
+    Node::String(value.to_string())
+}
+
+fn number<T: std::fmt::Display>(value: T) -> Node {
+    Node::Number(value.to_string())
+}
+
+fn integer_schema(format: Option<&str>, minimum: Option<i128>, maximum: Option<i128>) -> Node {
+    let mut entries = vec![("type", string("integer"))];
+    if let Some(format) = format {
+        entries.push(("format", string(format)));
+    }
+    if let Some(minimum) = minimum {
+        entries.push(("minimum", number(minimum)));
+    }
+    if let Some(maximum) = maximum {
+        entries.push(("maximum", number(maximum)));
+    }
+    object(entries)
+}
+
+/// Quote a string as in JSON, which is also a valid double-quoted scalar in YAML.
+fn quote(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn write_scalar(out: &mut String, node: &Node) {
+    match node {
+        Node::Null => out.push_str("null"),
+        Node::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Node::Number(value) => out.push_str(value),
+        Node::String(value) => out.push_str(&quote(value)),
+        Node::Array(items) if items.is_empty() => out.push_str("[]"),
+        Node::Object(entries) if entries.is_empty() => out.push_str("{}"),
+        _ => unreachable!("not a scalar"),
+    }
+}
+
+fn is_scalar(node: &Node) -> bool {
+    match node {
+        Node::Array(items) => items.is_empty(),
+        Node::Object(entries) => entries.is_empty(),
+        _ => true,
+    }
+}
+
+fn write_json(out: &mut String, node: &Node, padding: &str, unit: &str) {
+    let inner_padding = format!("{}{}", padding, unit);
+    match node {
+        Node::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                out.push_str(&inner_padding);
+                write_json(out, item, &inner_padding, unit);
+                out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(padding);
+            out.push(']');
+        }
+        Node::Object(entries) if !entries.is_empty() => {
+            out.push_str("{\n");
+            for (index, (key, value)) in entries.iter().enumerate() {
+                out.push_str(&inner_padding);
+                out.push_str(&quote(key));
+                out.push_str(": ");
+                write_json(out, value, &inner_padding, unit);
+                out.push_str(if index + 1 < entries.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            out.push_str(padding);
+            out.push('}');
+        }
+        node => write_scalar(out, node),
+    }
+}
+
+/// Whether a key can be written without quotes in YAML: `$ref` or identifiers that YAML 1.1
+/// does not read as booleans or `null`, e.g. `y` or `no`.
+fn is_plain_key(key: &str) -> bool {
+    if key == "$ref" {
+        return true;
+    }
+    let is_identifier = key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let is_reserved = matches!(
+        key.to_ascii_lowercase().as_str(),
+        "y" | "n" | "yes" | "no" | "on" | "off" | "true" | "false" | "null"
+    );
+    is_identifier && !is_reserved
+}
+
+/// Write `node`, a non-empty array or object, as YAML lines indented by `indentation`. Values
+/// of objects are indented by `width` more spaces.
+fn write_yaml(out: &mut String, node: &Node, indentation: usize, width: usize) {
+    let padding = " ".repeat(indentation);
+    match node {
+        Node::Array(items) => {
+            for item in items {
+                if is_scalar(item) {
+                    out.push_str(&padding);
+                    out.push_str("- ");
+                    write_scalar(out, item);
+                    out.push('\n');
+                } else {
+                    // Write the item at the next indentation, then move its first line after the dash.
+                    let mut text = String::new();
+                    write_yaml(&mut text, item, indentation + 2, width);
+                    out.push_str(&padding);
+                    out.push_str("- ");
+                    out.push_str(&text[indentation + 2..]);
+                }
+            }
+        }
+        Node::Object(entries) => {
+            for (key, value) in entries {
+                out.push_str(&padding);
+                if is_plain_key(key) {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&quote(key));
+                }
+                out.push(':');
+                if is_scalar(value) {
+                    out.push(' ');
+                    write_scalar(out, value);
+                    out.push('\n');
+                } else {
+                    out.push('\n');
+                    write_yaml(out, value, indentation + width, width);
+                }
+            }
+        }
+        node => write_scalar(out, node),
+    }
+}
+
+/// Installer for generated OpenAPI documents.
+pub struct Installer {
+    install_dir: PathBuf,
+    json: bool,
+    openapi_version: OpenApiVersion,
+    openapi_bytes: OpenApiBytes,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            json: false,
+            openapi_version: OpenApiVersion::V3_1,
+            openapi_bytes: OpenApiBytes::IntegerArray,
+        }
+    }
+
+    /// See `CodeGenerator::with_json`.
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// See `CodeGenerator::with_openapi_version`.
+    pub fn with_openapi_version(mut self, openapi_version: OpenApiVersion) -> Self {
+        self.openapi_version = openapi_version;
+        self
+    }
+
+    /// See `CodeGenerator::with_openapi_bytes`.
+    pub fn with_openapi_bytes(mut self, openapi_bytes: OpenApiBytes) -> Self {
+        self.openapi_bytes = openapi_bytes;
+        self
+    }
+
+    fn document_path(&self, name: &str) -> PathBuf {
+        let extension = if self.json { "json" } else { "yaml" };
+        self.install_dir.join(format!("{}.{}", name, extension))
+    }
+
+    fn create_document_file(&self, name: &str) -> Result<std::fs::File> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        std::fs::File::create(self.document_path(name))
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = Box<dyn std::error::Error>;
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_document_file(&config.module_name)?;
+        let generator = CodeGenerator::try_new(config)?
+            .with_json(self.json)
+            .with_openapi_version(self.openapi_version)
+            .with_openapi_bytes(self.openapi_bytes);
+        generator.output(&mut file, registry)?;
+        config.format_source(Language::OpenApi, &self.document_path(&config.module_name))?;
+        Ok(())
+    }
+
+    /// Schemas do not need a runtime.
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Err("OpenAPI does not support the bincode encoding".into())
+    }
+
+    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Err("OpenAPI does not support the bcs encoding".into())
+    }
+}
@@ -0,0 +1,1000 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    common,
+    indent::{IndentConfig, IndentedWriter},
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding, KeywordEscaping,
+};
+use include_dir::include_dir as include_directory;
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::{Result, Write},
+    path::{Path, PathBuf},
+};
+
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: true,
+    package_manifest: true,
+    single_file: false,
+    split_files: true,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
+/// Main configuration object for code-generation in PHP.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// Mapping from external type names to fully-qualified class names (e.g. "MyClass" -> "\my_org\my_package\MyClass").
+    /// Derived from `config.external_definitions`.
+    external_qualified_names: HashMap<String, String>,
+}
+
+/// Shared state for the code generation of a PHP source file.
+struct PhpEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+    /// Current namespace (e.g. vec!["my_org", "my_package", "MyClass"])
+    current_namespace: Vec<String>,
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create a PHP code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Php, config)?;
+        let mut external_qualified_names = HashMap::new();
+        for (namespace, names) in &config.external_definitions {
+            for name in names {
+                external_qualified_names.insert(
+                    name.to_string(),
+                    format!("\\{}\\{}", quote_namespace(namespace), name),
+                );
+            }
+        }
+        Ok(Self {
+            config,
+            external_qualified_names,
+        })
+    }
+
+    /// Report the parts of the registry that cannot be generated in PHP, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Php, registry)
+    }
+
+    /// Output class definitions for `registry` in separate source files, following PSR-4:
+    /// the namespace is the module name with dots replaced by backslashes, and source files
+    /// are created in the corresponding subdirectory of `install_dir`.
+    pub fn write_source_files(&self, install_dir: PathBuf, registry: &Registry) -> Result<()> {
+        self.output_files(install_dir, registry)?;
+        Ok(())
+    }
+
+    /// Same as `write_source_files` but return the paths of the generated files.
+    fn output_files(&self, install_dir: PathBuf, registry: &Registry) -> Result<BTreeSet<PathBuf>> {
+        let registry = validation::check_registry(self.config, Language::Php, registry)?;
+        let registry: &Registry = &registry;
+        let current_namespace = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let mut dir_path = install_dir;
+        for part in &current_namespace {
+            dir_path = dir_path.join(part);
+        }
+        std::fs::create_dir_all(&dir_path)?;
+
+        let mut files = BTreeSet::new();
+        for (name, format) in registry {
+            self.write_container_classes(&dir_path, &current_namespace, name, format, &mut files)?;
+        }
+        if self.config.serialization {
+            let path = dir_path.join("TraitHelpers.php");
+            let mut emitter = self.create_emitter(&path, current_namespace)?;
+            emitter.output_preamble()?;
+            emitter.output_trait_helpers(registry)?;
+            files.insert(path);
+        }
+        Ok(files)
+    }
+
+    fn create_emitter(
+        &self,
+        path: &Path,
+        current_namespace: Vec<String>,
+    ) -> Result<PhpEmitter<'_, std::fs::File>> {
+        let file = std::fs::File::create(path)?;
+        Ok(PhpEmitter {
+            out: IndentedWriter::new(file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
+            generator: self,
+            current_namespace,
+        })
+    }
+
+    fn write_container_classes(
+        &self,
+        dir_path: &Path,
+        current_namespace: &[String],
+        name: &str,
+        format: &ContainerFormat,
+        files: &mut BTreeSet<PathBuf>,
+    ) -> Result<()> {
+        use ContainerFormat::*;
+        let class_name = quote_class_name(self.config, name);
+        let fields = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![Named {
+                name: "value".to_string(),
+                value: format.as_ref().clone(),
+            }],
+            TupleStruct(formats) => formats
+                .iter()
+                .enumerate()
+                .map(|(i, f)| Named {
+                    name: format!("field{}", i),
+                    value: f.clone(),
+                })
+                .collect(),
+            Struct(fields) => fields.clone(),
+            Enum(variants) => {
+                let path = dir_path.join(format!("{}.php", class_name));
+                let mut emitter = self.create_emitter(&path, current_namespace.to_vec())?;
+                emitter.output_preamble()?;
+                if is_unit_only_enum(variants) {
+                    emitter.output_native_enum(name, variants)?;
+                    files.insert(path);
+                    return Ok(());
+                }
+                emitter.output_enum_base_class(name, variants)?;
+                files.insert(path);
+                for (index, variant) in variants {
+                    let class_name = variant_class_name(name, &variant.name);
+                    let path = dir_path.join(format!("{}.php", class_name));
+                    let mut namespace = current_namespace.to_vec();
+                    namespace.push(name.to_string());
+                    let mut emitter = self.create_emitter(&path, namespace)?;
+                    emitter.output_preamble()?;
+                    emitter.output_variant_class(name, *index, &variant.name, &variant.value)?;
+                    files.insert(path);
+                }
+                return Ok(());
+            }
+        };
+        let path = dir_path.join(format!("{}.php", class_name));
+        let mut emitter = self.create_emitter(&path, current_namespace.to_vec())?;
+        emitter.output_preamble()?;
+        emitter.output_struct_class(name, &fields)?;
+        files.insert(path);
+        Ok(())
+    }
+}
+
+/// PHP namespace corresponding to a (dot-separated) module name.
+fn quote_namespace(module_name: &str) -> String {
+    module_name.replace('.', "\\")
+}
+
+/// Name of the class generated for the registry type `name`. Class names are
+/// case-insensitive, therefore keywords are matched in lowercase.
+fn quote_class_name(config: &CodeGeneratorConfig, name: &str) -> String {
+    if !common::keywords(Language::Php).contains(&name.to_lowercase().as_str()) {
+        return name.to_string();
+    }
+    match config.keyword_escaping(Language::Php) {
+        KeywordEscaping::Suffix(suffix) => format!("{}{}", name, suffix),
+        KeywordEscaping::Prefix(prefix) => format!("{}{}", prefix, name),
+        KeywordEscaping::Error | KeywordEscaping::PassThrough => name.to_string(),
+    }
+}
+
+/// Name of the class generated for the variant `name` of the enum `base`.
+fn variant_class_name(base: &str, name: &str) -> String {
+    format!("{}__{}", base, name)
+}
+
+/// Enums without data are compiled into PHP enums.
+fn is_unit_only_enum(variants: &BTreeMap<u32, Named<VariantFormat>>) -> bool {
+    variants
+        .values()
+        .all(|variant| variant.value == VariantFormat::Unit)
+}
+
+impl<'a, T> PhpEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_preamble(&mut self) -> Result<()> {
+        writeln!(
+            self.out,
+            "<?php\n\ndeclare(strict_types=1);\n\nnamespace {};",
+            quote_namespace(&self.generator.config.module_name)
+        )?;
+        Ok(())
+    }
+
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::Php, name)
+    }
+
+    /// Compute a reference to the registry type `name`. Generated classes share the
+    /// namespace of the module.
+    fn quote_qualified_name(&self, name: &str) -> String {
+        self.generator
+            .external_qualified_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| quote_class_name(self.generator.config, name))
+    }
+
+    fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comments.get(&path) {
+            let text = textwrap::indent(doc, " * ").replace("\n\n", "\n *\n");
+            writeln!(self.out, "/**\n{} */", text)?;
+        }
+        Ok(())
+    }
+
+    /// Output the custom code of the current container, at the end of its body.
+    fn output_custom_code(&mut self) -> std::io::Result<()> {
+        if let Some(code) = self
+            .generator
+            .config
+            .custom_code
+            .get(&self.current_namespace)
+        {
+            writeln!(self.out, "\n{}", code)?;
+        }
+        Ok(())
+    }
+
+    fn enter_class(&mut self, name: &str) {
+        self.out.indent();
+        self.current_namespace.push(name.to_string());
+    }
+
+    fn leave_class(&mut self) {
+        self.out.unindent();
+        self.current_namespace.pop();
+    }
+
+    /// Type declaration of a value. Integers that may not fit in a PHP integer are decimal
+    /// strings. Sequences and tuples are lists, and maps are lists of `[key, value]` pairs.
+    fn quote_type(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) => self.quote_qualified_name(x),
+            Unit => "\\Serde\\Unit".into(),
+            Bool => "bool".into(),
+            I8 | I16 | I32 | I64 | U8 | U16 | U32 => "int".into(),
+            U64 => "int|string".into(),
+            I128 | U128 => "string".into(),
+            F32 | F64 => "float".into(),
+            Char | Str | Bytes => "string".into(),
+            // Nested options cannot be told apart from `null`.
+            Option(format) => match format.as_ref() {
+                Option(_) => "mixed".into(),
+                format => {
+                    let content = self.quote_type(format);
+                    if content.contains('|') {
+                        format!("{}|null", content)
+                    } else {
+                        format!("?{}", content)
+                    }
+                }
+            },
+            Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. } => "array".into(),
+            Variable(_) => panic!("unexpected value"),
+        }
+    }
+
+    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
+        let mut subtypes = BTreeMap::new();
+        for format in registry.values() {
+            format
+                .visit(&mut |f| {
+                    if Self::needs_helper(f) {
+                        subtypes.insert(common::mangle_type(f), f.clone());
+                    }
+                    Ok(())
+                })
+                .unwrap();
+        }
+        writeln!(self.out, "\nfinal class TraitHelpers\n{{")?;
+        self.enter_class("TraitHelpers");
+        let mut first = true;
+        for (mangled_name, subtype) in &subtypes {
+            if !first {
+                writeln!(self.out)?;
+            }
+            first = false;
+            self.output_serialization_helper(mangled_name, subtype)?;
+            writeln!(self.out)?;
+            self.output_deserialization_helper(mangled_name, subtype)?;
+        }
+        self.leave_class();
+        writeln!(self.out, "}}")
+    }
+
+    fn needs_helper(format: &Format) -> bool {
+        use Format::*;
+        matches!(
+            format,
+            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+        )
+    }
+
+    fn quote_serialize_value(&self, value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(_) => format!("{}->serialize($serializer);", value),
+            Unit => format!("$serializer->serializeUnit({});", value),
+            Bool => format!("$serializer->serializeBool({});", value),
+            I8 => format!("$serializer->serializeI8({});", value),
+            I16 => format!("$serializer->serializeI16({});", value),
+            I32 => format!("$serializer->serializeI32({});", value),
+            I64 => format!("$serializer->serializeI64({});", value),
+            I128 => format!("$serializer->serializeI128({});", value),
+            U8 => format!("$serializer->serializeU8({});", value),
+            U16 => format!("$serializer->serializeU16({});", value),
+            U32 => format!("$serializer->serializeU32({});", value),
+            U64 => format!("$serializer->serializeU64({});", value),
+            U128 => format!("$serializer->serializeU128({});", value),
+            F32 => format!("$serializer->serializeF32({});", value),
+            F64 => format!("$serializer->serializeF64({});", value),
+            Char => format!("$serializer->serializeChar({});", value),
+            Str => format!("$serializer->serializeStr({});", value),
+            Bytes => format!("$serializer->serializeBytes({});", value),
+            _ => format!(
+                "TraitHelpers::serialize_{}({}, $serializer);",
+                common::mangle_type(format),
+                value
+            ),
+        }
+    }
+
+    fn quote_deserialize(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(name) => format!(
+                "{}::deserialize($deserializer)",
+                self.quote_qualified_name(name)
+            ),
+            Unit => "$deserializer->deserializeUnit()".to_string(),
+            Bool => "$deserializer->deserializeBool()".to_string(),
+            I8 => "$deserializer->deserializeI8()".to_string(),
+            I16 => "$deserializer->deserializeI16()".to_string(),
+            I32 => "$deserializer->deserializeI32()".to_string(),
+            I64 => "$deserializer->deserializeI64()".to_string(),
+            I128 => "$deserializer->deserializeI128()".to_string(),
+            U8 => "$deserializer->deserializeU8()".to_string(),
+            U16 => "$deserializer->deserializeU16()".to_string(),
+            U32 => "$deserializer->deserializeU32()".to_string(),
+            U64 => "$deserializer->deserializeU64()".to_string(),
+            U128 => "$deserializer->deserializeU128()".to_string(),
+            F32 => "$deserializer->deserializeF32()".to_string(),
+            F64 => "$deserializer->deserializeF64()".to_string(),
+            Char => "$deserializer->deserializeChar()".to_string(),
+            Str => "$deserializer->deserializeStr()".to_string(),
+            Bytes => "$deserializer->deserializeBytes()".to_string(),
+            _ => format!(
+                "TraitHelpers::deserialize_{}($deserializer)",
+                common::mangle_type(format),
+            ),
+        }
+    }
+
+    fn output_serialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "public static function serialize_{}({} $value, \\Serde\\BinarySerializer $serializer): void\n{{",
+            name,
+            self.quote_type(format0)
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if ($value === null) {{
+    $serializer->serializeOptionTag(false);
+}} else {{
+    $serializer->serializeOptionTag(true);
+    {}
+}}
+"#,
+                    self.quote_serialize_value("$value", format)
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+$serializer->serializeLen(count($value));
+foreach ($value as $item) {{
+    {}
+}}
+"#,
+                    self.quote_serialize_value("$item", format)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+$serializer->serializeLen(count($value));
+$offsets = [];
+foreach ($value as [$key, $item]) {{
+    $offsets[] = $serializer->getBufferOffset();
+    {}
+    {}
+}}
+$serializer->sortMapEntries($offsets);
+"#,
+                    self.quote_serialize_value("$key", key),
+                    self.quote_serialize_value("$item", value)
+                )?;
+            }
+
+            Tuple(formats) => {
+                writeln!(self.out)?;
+                for (index, format) in formats.iter().enumerate() {
+                    let expr = format!("$value[{}]", index);
+                    writeln!(self.out, "{}", self.quote_serialize_value(&expr, format))?;
+                }
+            }
+
+            TupleArray { content, size } => {
+                write!(
+                    self.out,
+                    r#"
+if (count($value) !== {0}) {{
+    throw new \Serde\SerializationError('Invalid length for fixed-size array: ' . count($value) . ' instead of {0}');
+}}
+foreach ($value as $item) {{
+    {1}
+}}
+"#,
+                    size,
+                    self.quote_serialize_value("$item", content),
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_deserialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "public static function deserialize_{}(\\Serde\\BinaryDeserializer $deserializer): {}\n{{",
+            name,
+            self.quote_type(format0),
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (!$deserializer->deserializeOptionTag()) {{
+    return null;
+}}
+return {};
+"#,
+                    self.quote_deserialize(format),
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+$length = $deserializer->deserializeLen();
+$obj = [];
+for ($i = 0; $i < $length; $i++) {{
+    $obj[] = {};
+}}
+return $obj;
+"#,
+                    self.quote_deserialize(format)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+$length = $deserializer->deserializeLen();
+$obj = [];
+$previousKeySlice = null;
+for ($i = 0; $i < $length; $i++) {{
+    $keyStart = $deserializer->getBufferOffset();
+    $key = {};
+    $keySlice = [$keyStart, $deserializer->getBufferOffset()];
+    if ($previousKeySlice !== null) {{
+        $deserializer->checkThatKeySlicesAreIncreasing($previousKeySlice, $keySlice);
+    }}
+    $previousKeySlice = $keySlice;
+    $obj[] = [$key, {}];
+}}
+return $obj;
+"#,
+                    self.quote_deserialize(key),
+                    self.quote_deserialize(value),
+                )?;
+            }
+
+            Tuple(formats) => {
+                write!(
+                    self.out,
+                    r#"
+return [{}
+];
+"#,
+                    formats
+                        .iter()
+                        .map(|f| format!("\n    {}", self.quote_deserialize(f)))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+            }
+
+            TupleArray { content, size } => {
+                write!(
+                    self.out,
+                    r#"
+$obj = [];
+for ($i = 0; $i < {}; $i++) {{
+    $obj[] = {};
+}}
+return $obj;
+"#,
+                    size,
+                    self.quote_deserialize(content)
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    /// Output a constructor with promoted properties.
+    fn output_constructor(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out, "public function __construct(")?;
+        self.out.indent();
+        for field in fields {
+            self.output_comment(&field.name)?;
+            writeln!(
+                self.out,
+                "public {} ${},",
+                self.quote_type(&field.value),
+                self.quote_field(&field.name)
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, ") {{\n}}")
+    }
+
+    /// Expression constructing the current class from the deserialized fields.
+    fn quote_new_from_deserializer(&self, fields: &[Named<Format>]) -> String {
+        if fields.is_empty() {
+            return "new self()".to_string();
+        }
+        format!(
+            "new self(\n{}\n)",
+            fields
+                .iter()
+                .map(|f| format!("    {},", self.quote_deserialize(&f.value)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+
+    fn output_serialize_fields(&mut self, fields: &[Named<Format>]) -> Result<()> {
+        for field in fields {
+            let value = format!("$this->{}", self.quote_field(&field.name));
+            writeln!(
+                self.out,
+                "{}",
+                self.quote_serialize_value(&value, &field.value)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_struct_class(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "final class {}\n{{",
+            quote_class_name(self.generator.config, name)
+        )?;
+        self.enter_class(name);
+        self.output_constructor(fields)?;
+        if self.generator.config.serialization {
+            if !fields.is_empty() {
+                writeln!(self.out)?;
+            }
+            writeln!(
+                self.out,
+                "public function serialize(\\Serde\\BinarySerializer $serializer): void\n{{"
+            )?;
+            self.out.indent();
+            writeln!(self.out, "$serializer->increaseContainerDepth();")?;
+            self.output_serialize_fields(fields)?;
+            writeln!(self.out, "$serializer->decreaseContainerDepth();")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            writeln!(
+                self.out,
+                "\npublic static function deserialize(\\Serde\\BinaryDeserializer $deserializer): self\n{{"
+            )?;
+            self.out.indent();
+            writeln!(self.out, "$deserializer->increaseContainerDepth();")?;
+            writeln!(
+                self.out,
+                "$obj = {};",
+                self.quote_new_from_deserializer(fields)
+            )?;
+            writeln!(self.out, "$deserializer->decreaseContainerDepth();")?;
+            writeln!(self.out, "return $obj;")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            self.output_encoding_methods()?;
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}}")
+    }
+
+    /// Output a native enum for an enum without data.
+    fn output_native_enum(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "enum {}: int\n{{",
+            quote_class_name(self.generator.config, name)
+        )?;
+        self.enter_class(name);
+        for (index, variant) in variants {
+            self.output_comment(&variant.name)?;
+            writeln!(
+                self.out,
+                "case {} = {};",
+                self.quote_field(&variant.name),
+                index
+            )?;
+        }
+        if self.generator.config.serialization {
+            if !variants.is_empty() {
+                writeln!(self.out)?;
+            }
+            write!(
+                self.out,
+                r#"public function serialize(\Serde\BinarySerializer $serializer): void
+{{
+    $serializer->increaseContainerDepth();
+    $serializer->serializeVariantIndex($this->value);
+    $serializer->decreaseContainerDepth();
+}}
+
+public static function deserialize(\Serde\BinaryDeserializer $deserializer): self
+{{
+    $deserializer->increaseContainerDepth();
+    $index = $deserializer->deserializeVariantIndex();
+    $obj = self::tryFrom($index) ?? throw new \Serde\DeserializationError('Unknown variant index for {}: ' . $index);
+    $deserializer->decreaseContainerDepth();
+    return $obj;
+}}
+"#,
+                name
+            )?;
+            self.output_encoding_methods()?;
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}}")
+    }
+
+    /// Output the abstract base class of an enum. Variants are final subclasses.
+    fn output_enum_base_class(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "abstract class {}\n{{",
+            quote_class_name(self.generator.config, name)
+        )?;
+        self.enter_class(name);
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                "abstract public function serialize(\\Serde\\BinarySerializer $serializer): void;"
+            )?;
+            writeln!(
+                self.out,
+                "\npublic static function deserialize(\\Serde\\BinaryDeserializer $deserializer): self\n{{"
+            )?;
+            self.out.indent();
+            writeln!(self.out, "$deserializer->increaseContainerDepth();")?;
+            writeln!(
+                self.out,
+                "$index = $deserializer->deserializeVariantIndex();"
+            )?;
+            writeln!(self.out, "$obj = match ($index) {{")?;
+            self.out.indent();
+            for (index, variant) in variants {
+                writeln!(
+                    self.out,
+                    "{} => {}::load($deserializer),",
+                    index,
+                    variant_class_name(name, &variant.name)
+                )?;
+            }
+            writeln!(
+                self.out,
+                "default => throw new \\Serde\\DeserializationError('Unknown variant index for {}: ' . $index),",
+                name
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}};")?;
+            writeln!(self.out, "$deserializer->decreaseContainerDepth();")?;
+            writeln!(self.out, "return $obj;")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            self.output_encoding_methods()?;
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_variant_class(
+        &mut self,
+        base: &str,
+        index: u32,
+        name: &str,
+        variant: &VariantFormat,
+    ) -> Result<()> {
+        use VariantFormat::*;
+        let fields = match variant {
+            Unit => Vec::new(),
+            NewType(format) => vec![Named {
+                name: "value".to_string(),
+                value: format.as_ref().clone(),
+            }],
+            Tuple(formats) => {
+                let names = common::tuple_field_names(self.generator.config, base, name);
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Named {
+                        name: match names {
+                            Some(names) => names[i].clone(),
+                            None => format!("field{}", i),
+                        },
+                        value: f.clone(),
+                    })
+                    .collect()
+            }
+            Struct(fields) => fields.clone(),
+            Variable(_) => panic!("incorrect value"),
+        };
+
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(
+            self.out,
+            "final class {} extends {}\n{{",
+            variant_class_name(base, name),
+            quote_class_name(self.generator.config, base)
+        )?;
+        self.enter_class(name);
+        self.output_constructor(&fields)?;
+        if self.generator.config.serialization {
+            if !fields.is_empty() {
+                writeln!(self.out)?;
+            }
+            writeln!(
+                self.out,
+                "public function serialize(\\Serde\\BinarySerializer $serializer): void\n{{"
+            )?;
+            self.out.indent();
+            writeln!(self.out, "$serializer->increaseContainerDepth();")?;
+            writeln!(self.out, "$serializer->serializeVariantIndex({});", index)?;
+            self.output_serialize_fields(&fields)?;
+            writeln!(self.out, "$serializer->decreaseContainerDepth();")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            writeln!(
+                self.out,
+                "\npublic static function load(\\Serde\\BinaryDeserializer $deserializer): self\n{{"
+            )?;
+            self.out.indent();
+            writeln!(
+                self.out,
+                "return {};",
+                self.quote_new_from_deserializer(&fields)
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_encoding_methods(&mut self) -> Result<()> {
+        for encoding in &self.generator.config.encodings {
+            let class_prefix = match encoding {
+                Encoding::Bcs => "Bcs\\Bcs",
+                Encoding::Bincode => "Bincode\\Bincode",
+                Encoding::Postcard | Encoding::MessagePack => panic!("unsupported encoding"),
+            };
+            write!(
+                self.out,
+                r#"
+public function {0}Serialize(): string
+{{
+    $serializer = new \Serde\{1}Serializer();
+    $this->serialize($serializer);
+    return $serializer->getBytes();
+}}
+
+public static function {0}Deserialize(string $input): self
+{{
+    $deserializer = new \Serde\{1}Deserializer($input);
+    $value = self::deserialize($deserializer);
+    if ($deserializer->getBufferOffset() < strlen($input)) {{
+        throw new \Serde\DeserializationError('Some input bytes were not read');
+    }}
+    return $value;
+}}
+"#,
+                encoding.name(),
+                class_prefix,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Installer for generated source files in PHP.
+pub struct Installer {
+    install_dir: PathBuf,
+    manifest: bool,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            manifest: false,
+        }
+    }
+
+    /// Whether to write a `composer.json` file declaring the autoloading of the generated
+    /// module and of the runtimes. Default: false.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    fn install_runtime(
+        &self,
+        source_dir: include_dir::Dir,
+        path: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let dir_path = self.install_dir.join(path);
+        std::fs::create_dir_all(&dir_path)?;
+        for entry in source_dir.files() {
+            let mut file = std::fs::File::create(dir_path.join(entry.path()))?;
+            file.write_all(entry.contents())?;
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self, config: &CodeGeneratorConfig) -> Result<()> {
+        let mut file = std::fs::File::create(self.install_dir.join("composer.json"))?;
+        write!(
+            file,
+            r#"{{
+    "name": "serde-generate/{0}",
+    "type": "library",
+    "require": {{
+        "php": ">=8.1"
+    }},
+    "suggest": {{
+        "ext-gmp": "Required for 128-bit integers and for 64-bit unsigned integers above PHP_INT_MAX"
+    }},
+    "autoload": {{
+        "psr-4": {{
+            "{1}\\": "{2}/",
+            "Serde\\": "Serde/"
+        }}
+    }}
+}}
+"#,
+            config.module_name.to_lowercase().replace('.', "-"),
+            quote_namespace(&config.module_name).replace('\\', "\\\\"),
+            config.module_name.replace('.', "/"),
+        )
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = Box<dyn std::error::Error>;
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        let generator = CodeGenerator::try_new(config)?;
+        for path in generator.output_files(self.install_dir.clone(), registry)? {
+            config.format_source(Language::Php, &path)?;
+        }
+        if self.manifest {
+            self.write_manifest(config)?;
+        }
+        Ok(())
+    }
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        self.install_runtime(include_directory!("runtime/php/Serde"), "Serde")
+    }
+
+    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
+        self.install_runtime(
+            include_directory!("runtime/php/Serde/Bincode"),
+            "Serde/Bincode",
+        )
+    }
+
+    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
+        self.install_runtime(include_directory!("runtime/php/Serde/Bcs"), "Serde/Bcs")
+    }
+}
@@ -2,17 +2,56 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    analyzer, common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    BaseClasses, CodeGeneratorConfig, ConfigError, Encoding,
+};
+use heck::SnakeCase;
+use serde_reflection::{
+    ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat, WellKnownType,
+    WellKnownTypes,
 };
-use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
-    collections::{BTreeMap, HashMap},
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+};
+
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[
+        Encoding::Bincode,
+        Encoding::Bcs,
+        Encoding::Postcard,
+        Encoding::MessagePack,
+    ],
+    c_style_enums: true,
+    package_manifest: true,
+    single_file: true,
+    split_files: true,
+    one_way_serialization: true,
+    bcs_length_prefix: true,
 };
 
 /// Main configuration object for code-generation in Python.
+///
+/// The variants of an enum `Choice` are classes `Choice__A`, `Choice__B`, .. that declare
+/// `__match_args__`, so that values can be matched positionally with Python 3.10 or later.
+/// Newtype variants match their `value`, tuple variants their items (also available as
+/// properties `field0`, `field1`, ..), and struct variants their fields in declaration order:
+/// ```python
+/// match Choice.bcs_deserialize(data):
+///     case Choice__A():
+///         ...
+///     case Choice__B(value):
+///         ...
+///     case Choice__C(first, second):
+///         ...
+///     case Choice__D(x=7):
+///         ...
+/// ```
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
     config: &'a CodeGeneratorConfig,
@@ -22,6 +61,48 @@ pub struct CodeGenerator<'a> {
     /// Assumes suitable imports (e.g. "from my_package import my_module").
     /// Derived from `config.external_definitions`.
     external_qualified_names: HashMap<String, String>,
+    /// Registry types to be mapped to a standard Python class.
+    well_known_types: WellKnownTypes,
+    /// Oldest version of Python that the generated code must support.
+    python_version: (u32, u32),
+    /// Whether dataclasses use `__slots__` to store their fields.
+    dataclass_slots: bool,
+    /// How dataclasses hash fields holding lists or dicts.
+    unhashable_fields: UnhashableFields,
+    /// Python classes generated for containers.
+    python_models: PythonModels,
+    /// Whether to include methods `to_json` and `from_json`.
+    json_methods: bool,
+    /// Base classes of the generated classes, indexed by qualified name.
+    base_classes: BaseClasses,
+}
+
+/// How generated Python dataclasses deal with fields holding lists or dicts, which cannot be
+/// hashed in Python. Only the Python representation of values is affected (see
+/// `CodeGenerator::with_unhashable_fields`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnhashableFields {
+    /// Hashing a value with such fields raises a `TypeError`.
+    Error,
+    /// Such fields are ignored when hashing values.
+    Exclude,
+    /// Sequences are stored as tuples, so that values without dicts are hashable.
+    Tuple,
+}
+
+/// Python classes generated for the containers of a registry. Field types and encodings are
+/// the same in all cases. Numeric fields keep the numpy types of `serde_types`, so Pydantic
+/// models check them as instances rather than coercing plain JSON numbers (see
+/// `CodeGenerator::with_python_models`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PythonModels {
+    /// Frozen dataclasses. The variants of an enum are subclasses of the enum class.
+    Dataclasses,
+    /// Frozen Pydantic v2 models, with the same subclasses for variants as dataclasses.
+    Pydantic,
+    /// Frozen Pydantic v2 models. An enum is the discriminated union of its variants, tagged
+    /// by a `kind` field holding the name of the variant, which suits the validation of JSON.
+    PydanticTaggedUnions,
 }
 
 /// Shared state for the code generation of a Python source file.
@@ -32,14 +113,26 @@ struct PythonEmitter<'a, T> {
     generator: &'a CodeGenerator<'a>,
     /// Current namespace (e.g. vec!["my_package", "my_module", "MyClass"])
     current_namespace: Vec<String>,
+    /// Registry types mapped to a standard Python class (e.g. "Duration").
+    well_known_types: WellKnownTypes,
+    /// Containers whose values are hashable in Python (i.e. contain no list or dict, or no dict
+    /// if sequences are stored as tuples).
+    hashable_types: BTreeSet<String>,
+    /// Whether some enums are generated as `enum.IntEnum` classes.
+    has_c_style_enums: bool,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Python code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Python 3 does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a Python code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Python3, config)?;
         let mut external_qualified_names = HashMap::new();
         for (module_path, names) in &config.external_definitions {
             let module = {
@@ -54,11 +147,24 @@ impl<'a> CodeGenerator<'a> {
                 external_qualified_names.insert(name.to_string(), format!("{}.{}", module, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             serde_package_name: None,
             external_qualified_names,
-        }
+            well_known_types: WellKnownTypes::new(),
+            python_version: (3, 7),
+            dataclass_slots: false,
+            unhashable_fields: UnhashableFields::Error,
+            python_models: PythonModels::Dataclasses,
+            json_methods: false,
+            base_classes: BaseClasses::new(),
+        })
+    }
+
+    /// Report the parts of the registry that cannot be generated in Python, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support_with(&self.naming_rules(), registry)
     }
 
     /// Whether the module providing Serde definitions is located within a package.
@@ -67,24 +173,252 @@ impl<'a> CodeGenerator<'a> {
         self
     }
 
+    /// Containers recognized as standard types (see `Tracer::well_known_types`), to be
+    /// represented by a standard Python class instead of a generated one, e.g.
+    /// `datetime.timedelta`. The serialized data is unchanged. Default: none.
+    pub fn with_well_known_types(mut self, well_known_types: WellKnownTypes) -> Self {
+        self.well_known_types = well_known_types;
+        self
+    }
+
+    /// Oldest version of Python that the generated code must support. Default: 3.7. From 3.8,
+    /// the classes of enum variants are marked `@typing.final`. From 3.10, annotations are
+    /// written with builtin generics and unions, e.g. `list[int]` and `X | None`, after
+    /// `from __future__ import annotations`.
+    pub fn with_python_version(mut self, major: u32, minor: u32) -> Self {
+        self.python_version = (major, minor);
+        self
+    }
+
+    /// Whether generated dataclasses use `__slots__` to store their fields, which saves memory.
+    /// Requires Python 3.10 (see `with_python_version`). Default: false.
+    pub fn with_dataclass_slots(mut self, dataclass_slots: bool) -> Self {
+        self.dataclass_slots = dataclass_slots;
+        self
+    }
+
+    /// How generated dataclasses hash fields holding lists or dicts. Default:
+    /// `UnhashableFields::Error`.
+    pub fn with_unhashable_fields(mut self, unhashable_fields: UnhashableFields) -> Self {
+        self.unhashable_fields = unhashable_fields;
+        self
+    }
+
+    /// Python classes generated for containers. Default: `PythonModels::Dataclasses`. Pydantic
+    /// models require Python 3.9 (see `with_python_version`) and cannot be combined with
+    /// `with_dataclass_slots` or `with_unhashable_fields`.
+    pub fn with_python_models(mut self, python_models: PythonModels) -> Self {
+        self.python_models = python_models;
+        self
+    }
+
+    /// Whether to include methods `to_json` and `from_json` converting values to and from a
+    /// JSON view, e.g. for logging. Requires serialization methods. Enums are externally
+    /// tagged, bytes are base64 strings, and maps with non-string keys are lists of pairs. Keys
+    /// of objects are the Python field names, so this is not meant to match the JSON encoding
+    /// of Serde. Default: false.
+    pub fn with_json_methods(mut self, json_methods: bool) -> Self {
+        self.json_methods = json_methods;
+        self
+    }
+
+    /// Classes that the generated dataclasses and variant classes derive from, after their
+    /// usual base, e.g. mixins providing logging helpers. Keys are qualified names of modules,
+    /// containers, or variants: the entry of the longest prefix of the name of a class applies.
+    /// Values are qualified names of classes, e.g. `my_package.mixins.Message`, whose module is
+    /// imported by the generated code. Base classes must not define fields or constructors.
+    /// C-style enums are not affected. Default: none.
+    pub fn with_base_class(mut self, base_classes: BaseClasses) -> Self {
+        self.base_classes = base_classes;
+        self
+    }
+
+    fn naming_rules(&self) -> validation::NamingRules<'a> {
+        let rules = validation::NamingRules::new(self.config, Language::Python3);
+        if self.python_models == PythonModels::PydanticTaggedUnions {
+            rules.with_python_variant_tags()
+        } else {
+            rules
+        }
+    }
+
+    /// Check that the options of the generator can be used together.
+    fn check_options(&self) -> std::result::Result<(), ConfigError> {
+        if self.dataclass_slots && self.python_version < (3, 10) {
+            return Err(ConfigError::LanguageVersion {
+                language: Language::Python3,
+                feature: "Generating dataclasses with slots",
+                version: (3, 10),
+            });
+        }
+        if self.python_models != PythonModels::Dataclasses {
+            if self.dataclass_slots {
+                return Err(ConfigError::Conflict {
+                    language: Language::Python3,
+                    options: ("Pydantic models", "dataclass slots"),
+                });
+            }
+            if self.unhashable_fields != UnhashableFields::Error {
+                return Err(ConfigError::Conflict {
+                    language: Language::Python3,
+                    options: ("Pydantic models", "unhashable fields"),
+                });
+            }
+            if self.python_version < (3, 9) {
+                return Err(ConfigError::LanguageVersion {
+                    language: Language::Python3,
+                    feature: "Generating Pydantic models",
+                    version: (3, 9),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the options of the generator, then the registry. Return the registry to generate
+    /// code for, with transliterated names if so configured.
+    fn check_registry<'b>(&self, registry: &'b Registry) -> Result<Cow<'b, Registry>> {
+        self.check_options()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+        validation::check_registry_with(&self.naming_rules(), registry)
+    }
+
     /// Write container definitions in Python.
     pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = self.check_registry(registry)?;
+        let registry: &Registry = &registry;
+        let mut emitter = self.emitter(out, registry);
+        emitter.output_preamble()?;
+        for (name, format) in registry {
+            if emitter.well_known_types.contains_key(name) {
+                continue;
+            }
+            emitter.output_container(name, format)?;
+        }
+        Ok(())
+    }
+
+    /// Write a type stub (`.pyi` file) for the definitions written by `output`. The stub
+    /// declares the fields and the serialization methods of each container, without their
+    /// implementation. Custom code is not included.
+    pub fn output_stub(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = self.check_registry(registry)?;
+        let registry: &Registry = &registry;
+        let mut emitter = self.emitter(out, registry);
+        emitter.output_stub_preamble()?;
+        for (name, format) in registry {
+            if emitter.well_known_types.contains_key(name) {
+                continue;
+            }
+            emitter.output_stub_container(name, format)?;
+        }
+        Ok(())
+    }
+
+    /// Write container definitions in Python as a package in the directory `module_dir`, with
+    /// one module per container (e.g. `serde_data.py` for `SerdeData`) and an `__init__.py` file
+    /// re-exporting all of them, so that `from <module_name> import Foo` keeps working.
+    pub fn write_source_files(&self, module_dir: PathBuf, registry: &Registry) -> Result<()> {
+        self.output_files(&module_dir, registry)?;
+        Ok(())
+    }
+
+    /// Same as `write_source_files` but return the paths of the generated files.
+    fn output_files(&self, module_dir: &Path, registry: &Registry) -> Result<Vec<PathBuf>> {
+        let registry = self.check_registry(registry)?;
+        let registry: &Registry = &registry;
+        let module_names = get_module_names(registry, &self.well_known_types)?;
+        let external = self
+            .external_qualified_names
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let dependencies =
+            analyzer::get_dependency_map_with_external_dependencies(registry, &external)
+                .expect("formats should not contain variables");
+        let reachable_types = get_reachable_types(&dependencies);
+
+        std::fs::create_dir_all(module_dir)?;
+        let mut paths = Vec::new();
+        for (name, module) in &module_names {
+            let path = module_dir.join(format!("{}.py", module));
+            let file = std::fs::File::create(&path)?;
+            let mut emitter = self.emitter(file, registry);
+            emitter.output_preamble()?;
+            // Containers that depend back on this one are imported at the end of the module,
+            // once the classes of this module are defined. This way, circular imports succeed
+            // and `typing.get_type_hints` can resolve the annotations of all the classes.
+            let (cyclic, acyclic): (Vec<_>, Vec<_>) = dependencies[name.as_str()]
+                .iter()
+                .filter(|dependency| {
+                    **dependency != name && module_names.contains_key(**dependency)
+                })
+                .partition(|dependency| reachable_types[**dependency].contains(name.as_str()));
+            emitter.output_container_imports(&acyclic, &module_names)?;
+            emitter.output_container(name, &registry[name])?;
+            if !cyclic.is_empty() {
+                writeln!(
+                    emitter.out,
+                    "# Mutually recursive containers, imported once the classes above are defined."
+                )?;
+                emitter.output_container_imports(&cyclic, &module_names)?;
+            }
+            paths.push(path);
+        }
+
+        // Variant classes are re-exported as well.
+        let mut exported_names = Vec::new();
+        let path = module_dir.join("__init__.py");
+        let mut file = std::fs::File::create(&path)?;
+        writeln!(file, "# pyre-strict")?;
+        for (name, module) in &module_names {
+            let mut names = vec![name.clone()];
+            if let ContainerFormat::Enum(variants) = &registry[name] {
+                if !self.config.c_style_enums || !is_c_style_enum(variants) {
+                    names.extend(
+                        variants
+                            .values()
+                            .map(|variant| format!("{}__{}", name, variant.name)),
+                    );
+                }
+            }
+            writeln!(file, "from .{} import {}", module, names.join(", "))?;
+            exported_names.extend(names.into_iter().map(|name| format!("\"{}\"", name)));
+        }
+        writeln!(file, "\n__all__ = [{}]", exported_names.join(", "))?;
+        paths.push(path);
+        Ok(paths)
+    }
+
+    fn emitter<T: Write>(&'a self, out: T, registry: &Registry) -> PythonEmitter<'a, T> {
         let current_namespace = self
             .config
             .module_name
             .split('.')
             .map(String::from)
             .collect();
-        let mut emitter = PythonEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+        let well_known_types = self
+            .well_known_types
+            .iter()
+            .filter(|(name, _)| registry.contains_key(*name))
+            .map(|(name, well_known)| (name.clone(), *well_known))
+            .collect();
+        let hashable_types =
+            get_hashable_types(registry, self.unhashable_fields == UnhashableFields::Tuple);
+        let has_c_style_enums = self.config.c_style_enums
+            && registry.values().any(|format| match format {
+                ContainerFormat::Enum(variants) => is_c_style_enum(variants),
+                _ => false,
+            });
+        PythonEmitter {
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
-        };
-        emitter.output_preamble()?;
-        for (name, format) in registry {
-            emitter.output_container(name, format)?;
+            well_known_types,
+            hashable_types,
+            has_c_style_enums,
         }
-        Ok(())
     }
 }
 
@@ -102,28 +436,102 @@ where
         }
     }
 
-    fn output_preamble(&mut self) -> Result<()> {
-        let from_serde_package = match &self.generator.serde_package_name {
+    fn quote_from_serde_package(&self) -> String {
+        match &self.generator.serde_package_name {
             None => "".to_string(),
             Some(name) => format!("from {} ", name),
-        };
+        }
+    }
+
+    fn output_preamble(&mut self) -> Result<()> {
+        let from_serde_package = self.quote_from_serde_package();
+        writeln!(self.out, "# pyre-strict")?;
+        if self.has_builtin_generics() {
+            writeln!(self.out, "from __future__ import annotations")?;
+        }
+        writeln!(self.out, "{}", self.quote_models_import())?;
+        if self.generator.unhashable_fields == UnhashableFields::Exclude {
+            writeln!(self.out, "import dataclasses")?;
+        }
+        if !self.well_known_types.is_empty() {
+            writeln!(self.out, "import datetime")?;
+        }
+        if self.has_c_style_enums {
+            writeln!(self.out, "import enum")?;
+        }
         writeln!(
             self.out,
-            r#"# pyre-strict
-from dataclasses import dataclass
-import typing
+            r#"import typing
 {}import serde_types as st"#,
             from_serde_package,
         )?;
-        for encoding in &self.generator.config.encodings {
-            writeln!(self.out, "{}import {}", from_serde_package, encoding.name())?;
+        if self.generator.config.has_serde_methods() {
+            for encoding in &self.generator.config.encodings {
+                writeln!(self.out, "{}import {}", from_serde_package, encoding.name())?;
+            }
+            if self.generator.json_methods {
+                writeln!(self.out, "{}import serde_json", from_serde_package)?;
+            }
+            if !self.generator.config.elision_containers.is_empty()
+                && !self.generator.config.encodings.contains(&Encoding::Bcs)
+            {
+                writeln!(self.out, "{}import bcs", from_serde_package)?;
+            }
         }
         for module in self.generator.config.external_definitions.keys() {
             writeln!(self.out, "{}\n", self.quote_import(module))?;
         }
+        for module in self.base_class_modules() {
+            writeln!(self.out, "import {}", module)?;
+        }
+        Ok(())
+    }
+
+    /// Import the given containers from the modules of the same package (see
+    /// `CodeGenerator::write_source_files`).
+    fn output_container_imports(
+        &mut self,
+        names: &[&&str],
+        module_names: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        for name in names {
+            writeln!(self.out, "from .{} import {}", module_names[**name], name)?;
+        }
         Ok(())
     }
 
+    fn quote_models_import(&self) -> &'static str {
+        match self.generator.python_models {
+            PythonModels::Dataclasses => "from dataclasses import dataclass",
+            PythonModels::Pydantic | PythonModels::PydanticTaggedUnions => "import pydantic",
+        }
+    }
+
+    /// Whether annotations use the syntax of Python 3.10, e.g. `list[int]` and `X | None`
+    /// (see `CodeGenerator::with_python_version`). Annotations are then left unevaluated
+    /// by `from __future__ import annotations`, so that registry types need no quotes.
+    fn has_builtin_generics(&self) -> bool {
+        self.generator.python_version >= (3, 10)
+    }
+
+    fn is_pydantic(&self) -> bool {
+        self.generator.python_models != PythonModels::Dataclasses
+    }
+
+    /// Whether the enum is generated as an `enum.IntEnum` (see
+    /// `CodeGeneratorConfig::with_c_style_enums`).
+    fn is_c_style(&self, variants: &BTreeMap<u32, Named<VariantFormat>>) -> bool {
+        self.generator.config.c_style_enums && is_c_style_enum(variants)
+    }
+
+    /// Whether the enum is the union of its variant classes rather than their base class
+    /// (see `PythonModels::PydanticTaggedUnions`). Empty unions do not exist in Python.
+    fn is_tagged_union(&self, variants: &BTreeMap<u32, Named<VariantFormat>>) -> bool {
+        self.generator.python_models == PythonModels::PydanticTaggedUnions
+            && !variants.is_empty()
+            && !self.is_c_style(variants)
+    }
+
     /// Compute a reference to the registry type `name`.
     /// Use a qualified name in case of external definitions.
     fn quote_qualified_name(&self, name: &str) -> String {
@@ -132,15 +540,24 @@ import typing
             .get(name)
             .cloned()
             .unwrap_or_else(|| {
-                // Need quotes because of circular dependencies.
-                format!("\"{}\"", name)
+                if self.has_builtin_generics() {
+                    name.to_string()
+                } else {
+                    // Need quotes because of circular dependencies.
+                    format!("\"{}\"", name)
+                }
             })
     }
 
     fn quote_type(&self, format: &Format) -> String {
         use Format::*;
         match format {
-            TypeName(x) => self.quote_qualified_name(x),
+            TypeName(x) => match self.well_known_types.get(x) {
+                Some(WellKnownType::Duration) => "datetime.timedelta".into(),
+                None => self.quote_qualified_name(x),
+            },
+            // Pydantic cannot validate `None` as a `typing.Type[None]`.
+            Unit if self.is_pydantic() => "pydantic.SkipValidation[st.unit]".into(),
             Unit => "st.unit".into(),
             Bool => "bool".into(),
             I8 => "st.int8".into(),
@@ -159,22 +576,34 @@ import typing
             Str => "str".into(),
             Bytes => "bytes".into(),
 
+            Option(format) if self.has_builtin_generics() => {
+                format!("{} | None", self.quote_type(format))
+            }
             Option(format) => format!("typing.Optional[{}]", self.quote_type(format)),
+            Seq(format) if self.has_builtin_generics() => {
+                format!("list[{}]", self.quote_type(format))
+            }
             Seq(format) => format!("typing.Sequence[{}]", self.quote_type(format)),
             Map { key, value } => format!(
-                "typing.Dict[{}, {}]",
+                "{}[{}, {}]",
+                self.quote_generic("Dict"),
                 self.quote_type(key),
                 self.quote_type(value)
             ),
             Tuple(formats) => {
                 if formats.is_empty() {
-                    "typing.Tuple[()]".into()
+                    format!("{}[()]", self.quote_generic("Tuple"))
                 } else {
-                    format!("typing.Tuple[{}]", self.quote_types(formats))
+                    format!(
+                        "{}[{}]",
+                        self.quote_generic("Tuple"),
+                        self.quote_types(formats)
+                    )
                 }
             }
             TupleArray { content, size } => format!(
-                "typing.Tuple[{}]",
+                "{}[{}]",
+                self.quote_generic("Tuple"),
                 self.quote_types(&vec![content.as_ref().clone(); *size])
             ), // Sadly, there are no fixed-size arrays in python.
 
@@ -182,6 +611,184 @@ import typing
         }
     }
 
+    /// Generic type of the `typing` module, e.g. `Dict`, or the corresponding builtin type.
+    fn quote_generic(&self, name: &str) -> String {
+        if self.has_builtin_generics() {
+            name.to_lowercase()
+        } else {
+            format!("typing.{}", name)
+        }
+    }
+
+    /// Frozen dataclasses are hashable. Containers without lists or dicts are also ordered.
+    fn quote_dataclass_decorator(&self, name: &str) -> String {
+        let mut options = vec!["frozen=True"];
+        if self.hashable_types.contains(name) {
+            options.push("order=True");
+        }
+        if self.generator.dataclass_slots {
+            options.push("slots=True");
+        }
+        format!("@dataclass({})", options.join(", "))
+    }
+
+    /// Header of the class of a struct or enum variant. `container` is the name of the struct or
+    /// enum, `variant` the name of the variant, if any, and `base` the enum class that a variant
+    /// derives from, if any.
+    fn quote_class_header(
+        &self,
+        name: &str,
+        container: &str,
+        variant: Option<&str>,
+        base: Option<&str>,
+    ) -> String {
+        let mut bases = Vec::new();
+        bases.extend(base);
+        if bases.is_empty() && self.is_pydantic() {
+            bases.push("pydantic.BaseModel");
+        }
+        bases.extend(self.get_base_class(container, variant));
+        let class = if bases.is_empty() {
+            format!("class {}:", name)
+        } else {
+            format!("class {}({}):", name, bases.join(", "))
+        };
+        if self.is_pydantic() {
+            class
+        } else {
+            format!("{}\n{}", self.quote_dataclass_decorator(container), class)
+        }
+    }
+
+    /// The class configured with `CodeGenerator::with_base_class` for the given struct
+    /// or enum variant.
+    fn get_base_class(&self, container: &str, variant: Option<&str>) -> Option<&'a str> {
+        let mut path = self
+            .generator
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let module_len = path.len();
+        path.push(container.to_string());
+        path.extend(variant.map(String::from));
+        while path.len() >= module_len {
+            if let Some(class) = self.generator.base_classes.get(&path) {
+                return Some(class);
+            }
+            path.pop();
+        }
+        None
+    }
+
+    /// Modules of the classes configured with `CodeGenerator::with_base_class`.
+    fn base_class_modules(&self) -> BTreeSet<&'a str> {
+        self.generator
+            .base_classes
+            .values()
+            .filter_map(|class| class.rsplit_once('.').map(|(module, _)| module))
+            .collect()
+    }
+
+    /// Configuration of Pydantic models that do not derive from an enum class. Values of the
+    /// types of `serde_types` are only checked to be instances.
+    fn output_model_config(&mut self) -> Result<()> {
+        if self.is_pydantic() {
+            writeln!(
+                self.out,
+                "model_config = pydantic.ConfigDict(frozen=True, arbitrary_types_allowed=True)"
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Pydantic requires class attributes to be annotated as such.
+    /// Whether classes hold their schema: the MessagePack runtime reads the names of fields and
+    /// variants from it.
+    fn has_runtime_schema(&self) -> bool {
+        let config = &self.generator.config;
+        config.runtime_schema || config.encodings.contains(&Encoding::MessagePack)
+    }
+
+    fn quote_class_attribute(&self, name: &str, type_: &str, value: &str) -> String {
+        if self.is_pydantic() {
+            format!("{}: typing.ClassVar[{}] = {}", name, type_, value)
+        } else {
+            format!("{} = {}  # type: {}", name, value, type_)
+        }
+    }
+
+    fn is_hashable(&self, format: &Format) -> bool {
+        let mut is_hashable = true;
+        format
+            .visit(&mut |format| {
+                match format {
+                    Format::Seq(_) => {
+                        is_hashable &= self.generator.unhashable_fields == UnhashableFields::Tuple
+                    }
+                    Format::Map { .. } => is_hashable = false,
+                    Format::TypeName(name) => is_hashable &= self.hashable_types.contains(name),
+                    _ => (),
+                }
+                Ok(())
+            })
+            .expect("formats should not contain variables");
+        is_hashable
+    }
+
+    /// Expression converting the sequences of a value into tuples, if it contains any (see
+    /// `UnhashableFields::Tuple`). Sequences nested in maps or other containers are left as is.
+    fn quote_tuple_conversion(value: &str, format: &Format, depth: usize) -> Option<String> {
+        use Format::*;
+        match format {
+            Seq(format) => {
+                let item = format!("x{}", depth);
+                Some(
+                    match Self::quote_tuple_conversion(&item, format, depth + 1) {
+                        None => format!("tuple({})", value),
+                        Some(conversion) => {
+                            format!("tuple({} for {} in {})", conversion, item, value)
+                        }
+                    },
+                )
+            }
+            Option(format) => Self::quote_tuple_conversion(value, format, depth)
+                .map(|conversion| format!("None if {} is None else {}", value, conversion)),
+            Tuple(formats) => Self::quote_tuple_items_conversion(value, formats, depth),
+            TupleArray { content, size } => Self::quote_tuple_items_conversion(
+                value,
+                &vec![content.as_ref().clone(); *size],
+                depth,
+            ),
+            _ => None,
+        }
+    }
+
+    fn quote_tuple_items_conversion(
+        value: &str,
+        formats: &[Format],
+        depth: usize,
+    ) -> Option<String> {
+        let conversions = formats
+            .iter()
+            .enumerate()
+            .map(|(i, format)| {
+                let item = format!("{}[{}]", value, i);
+                Self::quote_tuple_conversion(&item, format, depth)
+            })
+            .collect::<Vec<_>>();
+        if conversions.iter().all(Option::is_none) {
+            return None;
+        }
+        let items = conversions
+            .into_iter()
+            .enumerate()
+            .map(|(i, conversion)| conversion.unwrap_or_else(|| format!("{}[{}]", value, i)))
+            .collect::<Vec<_>>();
+        Some(format!("({},)", items.join(", ")))
+    }
+
     fn quote_types(&self, formats: &[Format]) -> String {
         formats
             .iter()
@@ -199,6 +806,18 @@ import typing
         Ok(())
     }
 
+    fn output_schema(&mut self, format: &ContainerFormat) -> std::io::Result<()> {
+        if self.has_runtime_schema() {
+            let schema = self.quote_class_attribute(
+                "SCHEMA",
+                &format!("{}[str, typing.Any]", self.quote_generic("Dict")),
+                &common::quote_container_schema(format),
+            );
+            writeln!(self.out, "{}", schema)?;
+        }
+        Ok(())
+    }
+
     fn output_custom_code(&mut self) -> std::io::Result<bool> {
         match self
             .generator
@@ -219,23 +838,56 @@ import typing
             writeln!(self.out, "pass")?;
             return Ok(());
         }
+        let mut conversions = Vec::new();
         for field in fields {
+            let name =
+                common::escape_keyword(self.generator.config, Language::Python3, &field.name);
+            let default = match self.generator.unhashable_fields {
+                UnhashableFields::Exclude if !self.is_hashable(&field.value) => {
+                    " = dataclasses.field(hash=False)"
+                }
+                _ => "",
+            };
             writeln!(
                 self.out,
-                "{}: {}",
-                field.name,
-                self.quote_type(&field.value)
+                "{}: {}{}",
+                name,
+                self.quote_type(&field.value),
+                default
             )?;
+            // Attribute docstrings follow the field.
+            self.output_comment(&field.name)?;
+            if self.generator.unhashable_fields == UnhashableFields::Tuple {
+                let value = format!("self.{}", name);
+                if let Some(conversion) = Self::quote_tuple_conversion(&value, &field.value, 0) {
+                    conversions.push((name, conversion));
+                }
+            }
+        }
+        if !conversions.is_empty() {
+            writeln!(self.out, "\ndef __post_init__(self) -> None:")?;
+            self.out.indent();
+            for (name, conversion) in conversions {
+                writeln!(
+                    self.out,
+                    "object.__setattr__(self, \"{}\", {})",
+                    name, conversion
+                )?;
+            }
+            self.out.unindent();
         }
         Ok(())
     }
 
+    /// Write the class of a variant of the enum `base`. In a tagged union, the class also holds
+    /// the schema, methods, and custom code of the enum, whose format is `enum_format`.
     fn output_variant(
         &mut self,
         base: &str,
         name: &str,
         index: u32,
         variant: &VariantFormat,
+        enum_format: &ContainerFormat,
     ) -> Result<()> {
         use VariantFormat::*;
         let fields = match variant {
@@ -252,58 +904,148 @@ import typing
             Variable(_) => panic!("incorrect value"),
         };
 
+        let is_tagged = self.generator.python_models == PythonModels::PydanticTaggedUnions;
         // Regarding comments, we pretend the namespace is `[module, base, name]`.
+        let class_name = format!("{}__{}", base, name);
+        let parent = if is_tagged { None } else { Some(base) };
+        // `typing.final` requires Python 3.8.
+        let decorator = if self.generator.python_version >= (3, 8) {
+            "@typing.final\n"
+        } else {
+            ""
+        };
         writeln!(
             self.out,
-            "\n@dataclass(frozen=True)\nclass {0}__{1}({0}):",
-            base, name
+            "\n{}{}",
+            decorator,
+            self.quote_class_header(&class_name, base, Some(name), parent)
         )?;
         self.out.indent();
         self.output_comment(name)?;
-        if self.generator.config.serialization {
-            writeln!(self.out, "INDEX = {}  # type: int", index)?;
+        if is_tagged {
+            self.output_model_config()?;
+            self.output_schema(enum_format)?;
+        }
+        if self.generator.config.has_serde_methods() {
+            let index = self.quote_class_attribute("INDEX", "int", &index.to_string());
+            writeln!(self.out, "{}", index)?;
         }
+        if is_tagged {
+            writeln!(self.out, "kind: typing.Literal[\"{0}\"] = \"{0}\"", name)?;
+        }
+        self.output_match_args(variant)?;
         self.current_namespace.push(name.to_string());
         self.output_fields(&fields)?;
+        self.current_namespace.pop();
+        if let Tuple(formats) = variant {
+            self.output_tuple_properties(formats)?;
+        }
+        if is_tagged {
+            if self.generator.config.has_serde_methods() {
+                self.output_serde_methods(base)?;
+            }
+            self.output_custom_code()?;
+        }
+        self.current_namespace.push(name.to_string());
         self.output_custom_code()?;
         self.current_namespace.pop();
         self.out.unindent();
         writeln!(self.out)
     }
 
+    /// Write the attributes matched by positional patterns on a variant class.
+    fn output_match_args(&mut self, variant: &VariantFormat) -> Result<()> {
+        use VariantFormat::*;
+        let names = match variant {
+            Unit => Vec::new(),
+            NewType(_) => vec!["value".to_string()],
+            Tuple(formats) => (0..formats.len()).map(|i| format!("field{}", i)).collect(),
+            Struct(fields) => fields
+                .iter()
+                .map(|field| {
+                    common::escape_keyword(self.generator.config, Language::Python3, &field.name)
+                })
+                .collect(),
+            Variable(_) => panic!("incorrect value"),
+        };
+        let value = match names.len() {
+            0 => "()".to_string(),
+            1 => format!("(\"{}\",)", names[0]),
+            _ => format!(
+                "({})",
+                names
+                    .iter()
+                    .map(|name| format!("\"{}\"", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let type_ = format!("{}[str, ...]", self.quote_generic("Tuple"));
+        let attribute = self.quote_class_attribute("__match_args__", &type_, &value);
+        writeln!(self.out, "{}", attribute)
+    }
+
+    /// Write the properties `field0`, `field1`, .. giving access to the items of a tuple
+    /// variant, whose value is stored as a tuple.
+    fn output_tuple_properties(&mut self, formats: &[Format]) -> Result<()> {
+        for (i, format) in formats.iter().enumerate() {
+            writeln!(
+                self.out,
+                "\n@property\ndef field{0}(self) -> {1}:\n    return self.value[{0}]",
+                i,
+                self.quote_type(format)
+            )?;
+        }
+        Ok(())
+    }
+
     fn output_enum_container(
         &mut self,
         name: &str,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
-        writeln!(self.out, "\nclass {}:", name)?;
+        let format = ContainerFormat::Enum(variants.clone());
+        if self.is_c_style(variants) {
+            return self.output_c_style_enum(name, variants, &format);
+        }
+        if self.is_tagged_union(variants) {
+            return self.output_tagged_union(name, variants, &format);
+        }
+        if self.is_pydantic() {
+            writeln!(self.out, "\nclass {}(pydantic.BaseModel):", name)?;
+        } else {
+            writeln!(self.out, "\nclass {}:", name)?;
+        }
         self.out.indent();
         self.output_comment(name)?;
+        self.output_model_config()?;
+        self.output_schema(&format)?;
         self.current_namespace.push(name.to_string());
-        if self.generator.config.serialization {
-            writeln!(
-                self.out,
-                "VARIANTS = []  # type: typing.Sequence[typing.Type[{}]]",
-                name
-            )?;
-            for encoding in &self.generator.config.encodings {
-                self.output_serialize_method_for_encoding(name, *encoding)?;
-                self.output_deserialize_method_for_encoding(name, *encoding)?;
-            }
+        if self.generator.config.has_serde_methods() {
+            // Class annotations are evaluated by Pydantic, hence the quotes.
+            let variants_type = if self.is_pydantic() {
+                format!("typing.Sequence[typing.Type[\"{}\"]]", name)
+            } else {
+                format!("typing.Sequence[typing.Type[{}]]", name)
+            };
+            let attribute = self.quote_class_attribute("VARIANTS", &variants_type, "[]");
+            writeln!(self.out, "{}", attribute)?;
+            self.output_serde_methods(name)?;
         }
+        let has_match_method = self.output_match_method(name, variants)?;
         let wrote_custom_code = self.output_custom_code()?;
-        if !self.generator.config.serialization && !wrote_custom_code {
+        if !self.generator.config.has_serde_methods() && !has_match_method && !wrote_custom_code {
             writeln!(self.out, "pass")?;
         }
         writeln!(self.out)?;
         self.out.unindent();
 
         for (index, variant) in variants {
-            self.output_variant(name, &variant.name, *index, &variant.value)?;
+            self.output_variant(name, &variant.name, *index, &variant.value, &format)?;
         }
         self.current_namespace.pop();
 
-        if self.generator.config.serialization {
+        if self.generator.config.has_serde_methods() {
             writeln!(
                 self.out,
                 "{}.VARIANTS = [\n{}]\n",
@@ -318,40 +1060,385 @@ import typing
         Ok(())
     }
 
-    fn output_serialize_method_for_encoding(
-        &mut self,
+    /// The parameters of the `match` method of an enum, named after the variants, with the
+    /// types of the variant classes. There is no method for enums without variants, or if a
+    /// field of a variant would hide the method.
+    fn get_match_parameters(
+        &self,
         name: &str,
-        encoding: Encoding,
-    ) -> Result<()> {
-        writeln!(
-            self.out,
-            r#"
-def {0}_serialize(self) -> bytes:
-    return {0}.serialize(self, {1})"#,
-            encoding.name(),
-            name
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Option<Vec<(String, String)>> {
+        let hides_method = variants.values().any(|variant| match &variant.value {
+            VariantFormat::Struct(fields) => fields.iter().any(|field| field.name == "match"),
+            _ => false,
+        });
+        if variants.is_empty() || hides_method {
+            return None;
+        }
+        let parameters = variants
+            .values()
+            .map(|variant| {
+                (
+                    common::escape_keyword(self.generator.config, Language::Python3, &variant.name),
+                    self.quote_qualified_name(&format!("{}__{}", name, variant.name)),
+                )
+            })
+            .collect();
+        Some(parameters)
+    }
+
+    fn quote_match_signature(parameters: &[(String, String)]) -> String {
+        format!(
+            "def match(\n    self,\n    *,\n{}) -> st.MatchResult:",
+            parameters
+                .iter()
+                .map(|(parameter, class)| format!(
+                    "    {}: typing.Callable[[{}], st.MatchResult],\n",
+                    parameter, class
+                ))
+                .collect::<Vec<_>>()
+                .join("")
         )
     }
 
-    fn output_deserialize_method_for_encoding(
+    /// Write a method calling the function named after the variant of a value. Since every
+    /// variant must be handled, type checkers detect missing cases.
+    fn output_match_method(
         &mut self,
         name: &str,
-        encoding: Encoding,
-    ) -> Result<()> {
-        writeln!(
-            self.out,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<bool> {
+        let parameters = match self.get_match_parameters(name, variants) {
+            Some(parameters) => parameters,
+            None => return Ok(false),
+        };
+        writeln!(self.out, "\n{}", Self::quote_match_signature(&parameters))?;
+        self.out.indent();
+        for (variant, (parameter, _)) in variants.values().zip(&parameters) {
+            writeln!(
+                self.out,
+                "if isinstance(self, {}__{}):\n    return {}(self)",
+                name, variant.name, parameter
+            )?;
+        }
+        writeln!(self.out, "raise TypeError(\"Unexpected variant\", self)")?;
+        self.out.unindent();
+        Ok(true)
+    }
+
+    /// Write an enum whose variants have no data as an `enum.IntEnum` whose values are the
+    /// variant indices.
+    fn output_c_style_enum(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+        format: &ContainerFormat,
+    ) -> Result<()> {
+        writeln!(self.out, "\nclass {}(enum.IntEnum):", name)?;
+        self.out.indent();
+        self.output_comment(name)?;
+        self.current_namespace.push(name.to_string());
+        for (index, variant) in variants {
+            writeln!(
+                self.out,
+                "{} = {}",
+                common::escape_keyword(self.generator.config, Language::Python3, &variant.name),
+                index
+            )?;
+            self.output_comment(&variant.name)?;
+        }
+        if self.generator.config.has_serde_methods() {
+            self.output_serde_methods(name)?;
+        } else if variants.is_empty() {
+            writeln!(self.out, "pass")?;
+        }
+        self.output_custom_code()?;
+        self.current_namespace.pop();
+        self.out.unindent();
+        writeln!(self.out)?;
+        // Other class attributes would be enum members.
+        if self.has_runtime_schema() {
+            writeln!(
+                self.out,
+                "{}.SCHEMA = {}\n",
+                name,
+                common::quote_container_schema(format)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the classes of the variants, followed by their union, discriminated by the `kind`
+    /// field of each variant.
+    fn output_tagged_union(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+        format: &ContainerFormat,
+    ) -> Result<()> {
+        self.current_namespace.push(name.to_string());
+        for (index, variant) in variants {
+            self.output_variant(name, &variant.name, *index, &variant.value, format)?;
+        }
+        self.current_namespace.pop();
+        writeln!(self.out, "{}", Self::quote_tagged_union(name, variants))?;
+        // Attribute docstrings follow the definition.
+        self.output_comment(name)?;
+        writeln!(self.out)
+    }
+
+    fn quote_tagged_union(name: &str, variants: &BTreeMap<u32, Named<VariantFormat>>) -> String {
+        format!(
+            "{} = typing.Annotated[typing.Union[{}], pydantic.Field(discriminator=\"kind\")]",
+            name,
+            variants
+                .values()
+                .map(|variant| format!("{}__{}", name, variant.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn output_serialize_method_for_encoding(
+        &mut self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<()> {
+        let output = if self.has_length_prefix(encoding) {
+            format!(
+                r#"content = {0}.serialize(self, {1})
+    if len(content) > 0xFFFFFFFF:
+        raise st.SerializationError("Message is too long for a length prefix")
+    return len(content).to_bytes(4, "big") + content"#,
+                encoding.name(),
+                name
+            )
+        } else {
+            format!("return {0}.serialize(self, {1})", encoding.name(), name)
+        };
+        writeln!(
+            self.out,
+            r#"
+def {0}_serialize(self) -> bytes:
+    {1}"#,
+            encoding.name(),
+            output
+        )
+    }
+
+    /// Whether top-level messages of the encoding are framed with a 4-byte big-endian length
+    /// (see `CodeGeneratorConfig::with_bcs_length_prefix`).
+    fn has_length_prefix(&self, encoding: Encoding) -> bool {
+        encoding == Encoding::Bcs && self.generator.config.bcs_length_prefix
+    }
+
+    fn output_deserialize_method_for_encoding(
+        &mut self,
+        name: &str,
+        encoding: Encoding,
+    ) -> Result<()> {
+        // The length prefix is checked before the content is deserialized.
+        let (prefix_check, content) = if self.has_length_prefix(encoding) {
+            (
+                r#"
+    if len(input) < 4:
+        raise st.DeserializationError("Input is too short for a length prefix")
+    if int.from_bytes(input[:4], "big") != len(input) - 4:
+        raise st.DeserializationError("Length prefix does not match the message length")"#,
+                "input[4:]",
+            )
+        } else {
+            ("", "input")
+        };
+        writeln!(
+            self.out,
             r#"
 @staticmethod
-def {0}_deserialize(input: bytes) -> '{1}':
-    v, buffer = {0}.deserialize(input, {1})
+def {0}_deserialize(input: bytes) -> '{1}':{2}
+    v, buffer = {0}.deserialize({3}, {1})
     if buffer:
         raise st.DeserializationError("Some input bytes were not read");
     return v"#,
             encoding.name(),
-            name
+            name,
+            prefix_check,
+            content
         )
     }
 
+    /// Write the serialization and deserialization methods of a container, as configured.
+    fn output_serde_methods(&mut self, name: &str) -> Result<()> {
+        for encoding in &self.generator.config.encodings {
+            if self.generator.config.serialization {
+                self.output_serialize_method_for_encoding(name, *encoding)?;
+            }
+            if self.generator.config.has_deserialization() {
+                self.output_deserialize_method_for_encoding(name, *encoding)?;
+            }
+        }
+        self.output_json_methods(name)
+    }
+
+    /// Write the methods deserializing the items of a newtype of a sequence one at a time (see
+    /// `get_stream_item`).
+    fn output_stream_methods(&mut self, name: &str, item: &Format) -> Result<()> {
+        if !self.generator.config.has_deserialization() {
+            return Ok(());
+        }
+        for encoding in &self.generator.config.encodings {
+            writeln!(
+                self.out,
+                r#"
+@staticmethod
+def {0}_deserialize_stream(reader: typing.BinaryIO) -> typing.Iterator[{2}]:
+    return {0}.deserialize_stream(reader, {1})"#,
+                encoding.name(),
+                name,
+                self.quote_type(item)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_json_methods(&mut self, name: &str) -> Result<()> {
+        if !self.generator.json_methods {
+            return Ok(());
+        }
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                r#"
+def to_json(self) -> str:
+    return serde_json.serialize(self, {0})"#,
+                name
+            )?;
+        }
+        if self.generator.config.has_deserialization() {
+            writeln!(
+                self.out,
+                r#"
+@staticmethod
+def from_json(input: str) -> '{0}':
+    return serde_json.deserialize(input, {0})"#,
+                name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the methods of the compact encoding (see
+    /// `CodeGeneratorConfig::with_elision_containers`).
+    fn output_compact_methods(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let size = common::presence_bitmap_size(fields);
+        let bits = common::presence_bits(fields);
+        let identifiers = fields
+            .iter()
+            .map(|field| {
+                common::escape_keyword(self.generator.config, Language::Python3, &field.name)
+            })
+            .collect::<Vec<_>>();
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                r#"
+def compact_serialize(self) -> bytes:
+    types = typing.get_type_hints({})
+    bitmap = bytearray({})
+    serializer = bcs.BcsSerializer()"#,
+                name, size
+            )?;
+            self.out.indent();
+            for ((field, bit), identifier) in fields.iter().zip(&bits).zip(&identifiers) {
+                let value = format!("self.{}", identifier);
+                let encode = format!(
+                    "serializer.serialize_any({}, types[\"{}\"])",
+                    value, identifier
+                );
+                match bit {
+                    None => writeln!(self.out, "{}", encode)?,
+                    Some(bit) => writeln!(
+                        self.out,
+                        "if {}:\n    bitmap[{}] |= 1 << {}\n    {}",
+                        Self::quote_is_present(&value, &field.value),
+                        bit / 8,
+                        bit % 8,
+                        encode
+                    )?,
+                }
+            }
+            writeln!(self.out, "return bytes(bitmap) + serializer.get_buffer()")?;
+            self.out.unindent();
+        }
+        if self.generator.config.has_deserialization() {
+            writeln!(
+                self.out,
+                r#"
+@staticmethod
+def compact_deserialize(input: bytes) -> '{0}':
+    types = typing.get_type_hints({0})
+    if len(input) < {1}:
+        raise st.DeserializationError("Missing presence bitmap")
+    deserializer = bcs.BcsDeserializer(input[{1}:])"#,
+                name, size
+            )?;
+            self.out.indent();
+            for ((field, bit), identifier) in fields.iter().zip(&bits).zip(&identifiers) {
+                let decode = format!("deserializer.deserialize_any(types[\"{}\"])", identifier);
+                match bit {
+                    None => writeln!(self.out, "{} = {}", identifier, decode)?,
+                    Some(bit) => writeln!(
+                        self.out,
+                        "{} = {} if input[{}] & (1 << {}) else {}",
+                        identifier,
+                        decode,
+                        bit / 8,
+                        bit % 8,
+                        self.quote_default_value(&field.value)
+                    )?,
+                }
+            }
+            writeln!(
+                self.out,
+                r#"if deserializer.get_remaining_buffer():
+    raise st.DeserializationError("Some input bytes were not read")
+return {}({})"#,
+                name,
+                identifiers
+                    .iter()
+                    .map(|identifier| format!("{0}={0}", identifier))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+            self.out.unindent();
+        }
+        Ok(())
+    }
+
+    /// Condition under which an elidable field is not at its default value.
+    fn quote_is_present(value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Bool => value.to_string(),
+            Str | Bytes | Seq(_) | Map { .. } => format!("len({}) != 0", value),
+            Option(_) => format!("{} is not None", value),
+            _ => format!("int({}) != 0", value),
+        }
+    }
+
+    /// Default value of an elidable field.
+    fn quote_default_value(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Bool => "False".into(),
+            Str => "\"\"".into(),
+            Bytes => "b\"\"".into(),
+            Seq(_) => "[]".into(),
+            Map { .. } => "{}".into(),
+            Option(_) => "None".into(),
+            _ => format!("{}(0)", self.quote_type(format)),
+        }
+    }
+
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         use ContainerFormat::*;
         let fields = match format {
@@ -372,26 +1459,388 @@ def {0}_deserialize(input: bytes) -> '{1}':
             }
         };
         // Struct case.
-        writeln!(self.out, "\n@dataclass(frozen=True)\nclass {}:", name)?;
+        writeln!(
+            self.out,
+            "\n{}",
+            self.quote_class_header(name, name, None, None)
+        )?;
         self.out.indent();
         self.output_comment(name)?;
+        self.output_model_config()?;
+        self.output_schema(format)?;
         self.current_namespace.push(name.to_string());
         self.output_fields(&fields)?;
-        for encoding in &self.generator.config.encodings {
-            self.output_serialize_method_for_encoding(name, *encoding)?;
-            self.output_deserialize_method_for_encoding(name, *encoding)?;
+        if self.generator.config.has_serde_methods() {
+            self.output_serde_methods(name)?;
+            if let Some(item) = get_stream_item(format) {
+                self.output_stream_methods(name, item)?;
+            }
+            if self.generator.config.elision_containers.contains(name) {
+                self.output_compact_methods(name, &fields)?;
+            }
         }
         self.output_custom_code()?;
         self.current_namespace.pop();
         self.out.unindent();
         writeln!(self.out)
     }
+
+    fn output_stub_preamble(&mut self) -> Result<()> {
+        writeln!(self.out, "{}", self.quote_models_import())?;
+        if !self.well_known_types.is_empty() {
+            writeln!(self.out, "import datetime")?;
+        }
+        if self.has_c_style_enums {
+            writeln!(self.out, "import enum")?;
+        }
+        writeln!(
+            self.out,
+            "import typing\n{}import serde_types as st",
+            self.quote_from_serde_package()
+        )?;
+        for module in self.generator.config.external_definitions.keys() {
+            writeln!(self.out, "{}", self.quote_import(module))?;
+        }
+        for module in self.base_class_modules() {
+            writeln!(self.out, "import {}", module)?;
+        }
+        Ok(())
+    }
+
+    /// Write the class attributes, fields, and methods of a container or variant, or `...` if
+    /// there are none.
+    fn output_stub_body(
+        &mut self,
+        attributes: &[String],
+        fields: &[Named<Format>],
+        methods: &[String],
+    ) -> Result<()> {
+        if attributes.is_empty() && fields.is_empty() && methods.is_empty() {
+            return writeln!(self.out, "...");
+        }
+        for attribute in attributes {
+            writeln!(self.out, "{}", attribute)?;
+        }
+        for field in fields {
+            writeln!(
+                self.out,
+                "{}: {}",
+                common::escape_keyword(self.generator.config, Language::Python3, &field.name),
+                self.quote_type(&field.value)
+            )?;
+        }
+        for method in methods {
+            writeln!(self.out, "{}", method)?;
+        }
+        Ok(())
+    }
+
+    fn quote_stub_schema(&self) -> Option<String> {
+        if self.has_runtime_schema() {
+            Some(format!(
+                "SCHEMA: typing.ClassVar[{}[str, typing.Any]]",
+                self.quote_generic("Dict")
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn get_stub_methods(&self, name: &str) -> Vec<String> {
+        let config = self.generator.config;
+        if !config.has_serde_methods() {
+            return Vec::new();
+        }
+        let mut prefixes = config
+            .encodings
+            .iter()
+            .map(|encoding| encoding.name())
+            .collect::<Vec<_>>();
+        if config.elision_containers.contains(name) {
+            prefixes.push("compact");
+        }
+        let mut methods = Vec::new();
+        for prefix in prefixes {
+            if config.serialization {
+                methods.push(format!("def {}_serialize(self) -> bytes: ...", prefix));
+            }
+            if config.has_deserialization() {
+                methods.push(format!(
+                    "@staticmethod\ndef {}_deserialize(input: bytes) -> \"{}\": ...",
+                    prefix, name
+                ));
+            }
+        }
+        if self.generator.json_methods {
+            if config.serialization {
+                methods.push("def to_json(self) -> str: ...".to_string());
+            }
+            if config.has_deserialization() {
+                methods.push(format!(
+                    "@staticmethod\ndef from_json(input: str) -> \"{}\": ...",
+                    name
+                ));
+            }
+        }
+        methods
+    }
+
+    fn output_stub_enum_container(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        if self.is_c_style(variants) {
+            writeln!(self.out, "\nclass {}(enum.IntEnum):", name)?;
+            self.out.indent();
+            for (index, variant) in variants {
+                writeln!(
+                    self.out,
+                    "{} = {}",
+                    common::escape_keyword(self.generator.config, Language::Python3, &variant.name),
+                    index
+                )?;
+            }
+            let attributes = self.quote_stub_schema().into_iter().collect::<Vec<_>>();
+            let methods = self.get_stub_methods(name);
+            if variants.is_empty() || !attributes.is_empty() || !methods.is_empty() {
+                self.output_stub_body(&attributes, &[], &methods)?;
+            }
+            self.out.unindent();
+            return Ok(());
+        }
+        let is_tagged = self.is_tagged_union(variants);
+        if !is_tagged {
+            if self.is_pydantic() {
+                writeln!(self.out, "\nclass {}(pydantic.BaseModel):", name)?;
+            } else {
+                writeln!(self.out, "\nclass {}:", name)?;
+            }
+            self.out.indent();
+            let mut attributes = Vec::new();
+            attributes.extend(self.quote_stub_schema());
+            if self.generator.config.has_serde_methods() {
+                attributes.push(format!(
+                    "VARIANTS: typing.ClassVar[typing.Sequence[typing.Type[\"{}\"]]]",
+                    name
+                ));
+            }
+            let mut methods = self.get_stub_methods(name);
+            if let Some(parameters) = self.get_match_parameters(name, variants) {
+                methods.push(format!("{} ...", Self::quote_match_signature(&parameters)));
+            }
+            self.output_stub_body(&attributes, &[], &methods)?;
+            self.out.unindent();
+        }
+
+        for variant in variants.values() {
+            use VariantFormat::*;
+            let fields = match &variant.value {
+                Unit => Vec::new(),
+                NewType(format) => vec![Named {
+                    name: "value".to_string(),
+                    value: format.as_ref().clone(),
+                }],
+                Tuple(formats) => vec![Named {
+                    name: "value".to_string(),
+                    value: Format::Tuple(formats.clone()),
+                }],
+                Struct(fields) => fields.clone(),
+                Variable(_) => panic!("incorrect value"),
+            };
+            let class_name = format!("{}__{}", name, variant.name);
+            let parent = if is_tagged { None } else { Some(name) };
+            writeln!(
+                self.out,
+                "\n@typing.final\n{}",
+                self.quote_class_header(&class_name, name, Some(&variant.name), parent)
+            )?;
+            self.out.indent();
+            let mut attributes = Vec::new();
+            let mut methods = Vec::new();
+            if is_tagged {
+                attributes.extend(self.quote_stub_schema());
+                methods = self.get_stub_methods(name);
+            }
+            if self.generator.config.has_serde_methods() {
+                attributes.push("INDEX: typing.ClassVar[int]".to_string());
+            }
+            if is_tagged {
+                attributes.push(format!("kind: typing.Literal[\"{}\"]", variant.name));
+            }
+            self.output_stub_body(&attributes, &fields, &methods)?;
+            self.out.unindent();
+        }
+        if is_tagged {
+            writeln!(self.out, "\n{}", Self::quote_tagged_union(name, variants))?;
+        }
+        Ok(())
+    }
+
+    fn output_stub_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let fields = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![Named {
+                name: "value".to_string(),
+                value: format.as_ref().clone(),
+            }],
+            TupleStruct(formats) => vec![Named {
+                name: "value".to_string(),
+                value: Format::Tuple(formats.clone()),
+            }],
+            Struct(fields) => fields.clone(),
+            Enum(variants) => return self.output_stub_enum_container(name, variants),
+        };
+        writeln!(
+            self.out,
+            "\n{}",
+            self.quote_class_header(name, name, None, None)
+        )?;
+        self.out.indent();
+        let attributes = self.quote_stub_schema().into_iter().collect::<Vec<_>>();
+        let mut methods = self.get_stub_methods(name);
+        if let Some(item) = get_stream_item(format) {
+            if self.generator.config.has_deserialization() {
+                methods.extend(self.generator.config.encodings.iter().map(|encoding| {
+                    format!(
+                        "@staticmethod\ndef {}_deserialize_stream(reader: typing.BinaryIO) -> typing.Iterator[{}]: ...",
+                        encoding.name(),
+                        self.quote_type(item)
+                    )
+                }));
+            }
+        }
+        self.output_stub_body(&attributes, &fields, &methods)?;
+        self.out.unindent();
+        Ok(())
+    }
+}
+
+/// The items of a container that is a newtype of a sequence, e.g. `struct Records(Vec<Record>)`.
+/// Such containers may be deserialized one item at a time from a file-like object.
+fn get_stream_item(format: &ContainerFormat) -> Option<&Format> {
+    match format {
+        ContainerFormat::NewTypeStruct(format) => match format.as_ref() {
+            Format::Seq(item) => Some(item),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the variants of an enum have no data, so that a C-style enum may be generated.
+fn is_c_style_enum(variants: &BTreeMap<u32, Named<VariantFormat>>) -> bool {
+    variants
+        .values()
+        .all(|variant| variant.value == VariantFormat::Unit)
+}
+
+/// Compute the containers whose Python values are hashable, that is, containers that do not
+/// (transitively) contain maps, or sequences unless they are stored as tuples. External
+/// definitions are assumed not hashable.
+fn get_hashable_types(registry: &Registry, sequences_are_hashable: bool) -> BTreeSet<String> {
+    let mut hashable_types = registry.keys().cloned().collect::<BTreeSet<_>>();
+    loop {
+        let unhashable_types = registry
+            .iter()
+            .filter(|(name, format)| {
+                if !hashable_types.contains(*name) {
+                    return false;
+                }
+                let mut is_hashable = true;
+                format
+                    .visit(&mut |format| {
+                        match format {
+                            Format::Seq(_) => is_hashable &= sequences_are_hashable,
+                            Format::Map { .. } => is_hashable = false,
+                            Format::TypeName(name) if !hashable_types.contains(name) => {
+                                is_hashable = false
+                            }
+                            _ => (),
+                        }
+                        Ok(())
+                    })
+                    .expect("formats should not contain variables");
+                !is_hashable
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        if unhashable_types.is_empty() {
+            return hashable_types;
+        }
+        for name in unhashable_types {
+            hashable_types.remove(&name);
+        }
+    }
+}
+
+/// Names of the modules of the containers written by `CodeGenerator::write_source_files`:
+/// container names in snake case, followed by an underscore if they are keywords. Well-known
+/// types are not generated.
+fn get_module_names(
+    registry: &Registry,
+    well_known_types: &WellKnownTypes,
+) -> Result<BTreeMap<String, String>> {
+    let mut module_names = BTreeMap::new();
+    let mut containers = HashMap::new();
+    for name in registry.keys() {
+        if well_known_types.contains_key(name) {
+            continue;
+        }
+        let mut module = name.to_snake_case();
+        if common::is_keyword(Language::Python3, &module) {
+            module.push('_');
+        }
+        if let Some(other) = containers.insert(module.clone(), name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Containers {} and {} would both be written in the module {}",
+                    other, name, module
+                ),
+            ));
+        }
+        module_names.insert(name.clone(), module);
+    }
+    Ok(module_names)
+}
+
+/// Compute the containers that each container references, directly or transitively.
+fn get_reachable_types<'a>(
+    dependencies: &BTreeMap<&'a str, BTreeSet<&'a str>>,
+) -> BTreeMap<&'a str, BTreeSet<&'a str>> {
+    let mut reachable_types = BTreeMap::new();
+    for name in dependencies.keys() {
+        let mut reachable = BTreeSet::new();
+        let mut queue = dependencies[name].iter().copied().collect::<Vec<_>>();
+        while let Some(dependency) = queue.pop() {
+            if reachable.insert(dependency) {
+                if let Some(children) = dependencies.get(dependency) {
+                    queue.extend(children.iter().copied());
+                }
+            }
+        }
+        reachable_types.insert(*name, reachable);
+    }
+    reachable_types
 }
 
 /// Installer for generated source files in Python.
 pub struct Installer {
     install_dir: PathBuf,
     serde_package_name: Option<String>,
+    type_stubs: bool,
+    split_modules: bool,
+    manifest: bool,
+    package_version: String,
+    well_known_types: WellKnownTypes,
+    python_version: (u32, u32),
+    dataclass_slots: bool,
+    unhashable_fields: UnhashableFields,
+    python_models: PythonModels,
+    json_methods: bool,
+    base_classes: BaseClasses,
 }
 
 impl Installer {
@@ -399,13 +1848,192 @@ impl Installer {
         Installer {
             install_dir,
             serde_package_name,
+            type_stubs: false,
+            split_modules: false,
+            manifest: false,
+            package_version: "0.1.0".to_string(),
+            well_known_types: WellKnownTypes::new(),
+            python_version: (3, 7),
+            dataclass_slots: false,
+            unhashable_fields: UnhashableFields::Error,
+            python_models: PythonModels::Dataclasses,
+            json_methods: false,
+            base_classes: BaseClasses::new(),
         }
     }
 
+    /// Whether to write a `pyproject.toml` file so that the installed modules can be built and
+    /// installed with `pip install <install_dir>`. When a Serde package name is given, the
+    /// installed modules become sub-packages of this package, created in `install_dir`, and the
+    /// `__init__.py` file of the package lists them in `__all__`. Default: false.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Version of the Python project written with `with_manifest`. Default: "0.1.0".
+    pub fn with_package_version(mut self, package_version: String) -> Self {
+        self.package_version = package_version;
+        self
+    }
+
+    /// Directory where to install the Python modules.
+    fn package_dir(&self) -> PathBuf {
+        match &self.serde_package_name {
+            Some(name) if self.manifest => self.install_dir.join(name),
+            _ => self.install_dir.clone(),
+        }
+    }
+
+    /// Whether to write a type stub `__init__.pyi` next to each generated module (see
+    /// `CodeGenerator::output_stub`), and a `py.typed` marker in each installed package so
+    /// that type checkers use the annotations (PEP 561).
+    pub fn with_type_stubs(mut self, type_stubs: bool) -> Self {
+        self.type_stubs = type_stubs;
+        self
+    }
+
+    /// Whether to install each generated module as a package with one module per container
+    /// (see `CodeGenerator::write_source_files`). Not compatible with type stubs. Default: false.
+    pub fn with_split_modules(mut self, split_modules: bool) -> Self {
+        self.split_modules = split_modules;
+        self
+    }
+
+    /// Containers to be represented by standard Python classes (see
+    /// `CodeGenerator::with_well_known_types`).
+    pub fn with_well_known_types(mut self, well_known_types: WellKnownTypes) -> Self {
+        self.well_known_types = well_known_types;
+        self
+    }
+
+    /// Oldest version of Python that the generated code must support (see
+    /// `CodeGenerator::with_python_version`). Also written in the manifest, if any.
+    pub fn with_python_version(mut self, major: u32, minor: u32) -> Self {
+        self.python_version = (major, minor);
+        self
+    }
+
+    /// Whether generated dataclasses use `__slots__` (see
+    /// `CodeGenerator::with_dataclass_slots`).
+    pub fn with_dataclass_slots(mut self, dataclass_slots: bool) -> Self {
+        self.dataclass_slots = dataclass_slots;
+        self
+    }
+
+    /// How generated dataclasses hash fields holding lists or dicts (see
+    /// `CodeGenerator::with_unhashable_fields`).
+    pub fn with_unhashable_fields(mut self, unhashable_fields: UnhashableFields) -> Self {
+        self.unhashable_fields = unhashable_fields;
+        self
+    }
+
+    /// Python classes generated for containers (see `CodeGenerator::with_python_models`).
+    pub fn with_python_models(mut self, python_models: PythonModels) -> Self {
+        self.python_models = python_models;
+        self
+    }
+
+    /// Whether to include JSON methods (see `CodeGenerator::with_json_methods`).
+    pub fn with_json_methods(mut self, json_methods: bool) -> Self {
+        self.json_methods = json_methods;
+        self
+    }
+
+    /// Base classes of the generated classes (see `CodeGenerator::with_base_class`).
+    pub fn with_base_class(mut self, base_classes: BaseClasses) -> Self {
+        self.base_classes = base_classes;
+        self
+    }
+
+    fn generator<'a>(
+        &self,
+        config: &'a CodeGeneratorConfig,
+    ) -> std::result::Result<CodeGenerator<'a>, ConfigError> {
+        Ok(CodeGenerator::try_new(config)?
+            .with_serde_package_name(self.serde_package_name.clone())
+            .with_well_known_types(self.well_known_types.clone())
+            .with_python_version(self.python_version.0, self.python_version.1)
+            .with_dataclass_slots(self.dataclass_slots)
+            .with_unhashable_fields(self.unhashable_fields)
+            .with_python_models(self.python_models)
+            .with_json_methods(self.json_methods)
+            .with_base_class(self.base_classes.clone()))
+    }
+
+    fn module_init_path(&self, name: &str) -> PathBuf {
+        self.package_dir().join(name).join("__init__.py")
+    }
+
     fn create_module_init_file(&self, name: &str) -> Result<std::fs::File> {
-        let dir_path = self.install_dir.join(name);
-        std::fs::create_dir_all(&dir_path)?;
-        std::fs::File::create(dir_path.join("__init__.py"))
+        std::fs::create_dir_all(self.package_dir().join(name))?;
+        if self.type_stubs {
+            std::fs::File::create(self.package_dir().join(name).join("py.typed"))?;
+        }
+        let file = std::fs::File::create(self.module_init_path(name))?;
+        if self.manifest && self.serde_package_name.is_some() {
+            self.write_package_init_file()?;
+        }
+        Ok(file)
+    }
+
+    /// Write the `__init__.py` file of the Serde package, listing the modules installed so far.
+    fn write_package_init_file(&self) -> Result<()> {
+        let package_dir = self.package_dir();
+        let mut modules = Vec::new();
+        for entry in std::fs::read_dir(&package_dir)? {
+            let path = entry?.path();
+            if path.join("__init__.py").exists() {
+                if let Some(name) = path.file_name() {
+                    modules.push(format!("{:?}", name.to_string_lossy()));
+                }
+            }
+        }
+        modules.sort();
+        let mut file = std::fs::File::create(package_dir.join("__init__.py"))?;
+        writeln!(file, "__all__ = [{}]", modules.join(", "))?;
+        if self.type_stubs {
+            std::fs::File::create(package_dir.join("py.typed"))?;
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self, config: &crate::CodeGeneratorConfig) -> Result<()> {
+        let name = self
+            .serde_package_name
+            .as_ref()
+            .unwrap_or(&config.module_name);
+        let mut file = std::fs::File::create(self.install_dir.join("pyproject.toml"))?;
+        write!(
+            file,
+            r#"[build-system]
+requires = ["setuptools>=61"]
+build-backend = "setuptools.build_meta"
+
+[project]
+name = "{}"
+version = "{}"
+requires-python = ">={}.{}"
+dependencies = ["numpy>=1.20.1"]
+
+[tool.setuptools.packages.find]
+where = ["."]
+"#,
+            name, self.package_version, self.python_version.0, self.python_version.1,
+        )?;
+        if self.serde_package_name.is_some() {
+            writeln!(file, "include = [\"{0}\", \"{0}.*\"]", name)?;
+        }
+        if self.type_stubs {
+            write!(
+                file,
+                r#"
+[tool.setuptools.package-data]
+"*" = ["py.typed", "*.pyi"]
+"#
+            )?;
+        }
+        Ok(())
     }
 
     fn fix_serde_package(&self, content: &str) -> String {
@@ -432,10 +2060,45 @@ impl crate::SourceInstaller for Installer {
         config: &crate::CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
+        if self.split_modules {
+            if self.type_stubs {
+                return Err(ConfigError::Conflict {
+                    language: Language::Python3,
+                    options: ("split modules", "type stubs"),
+                }
+                .into());
+            }
+            let generator = self.generator(config)?;
+            let module_dir = self.package_dir().join(&config.module_name);
+            for path in generator.output_files(&module_dir, registry)? {
+                config.format_source(Language::Python3, &path)?;
+            }
+            if self.manifest {
+                if self.serde_package_name.is_some() {
+                    self.write_package_init_file()?;
+                }
+                self.write_manifest(config)?;
+            }
+            return Ok(());
+        }
         let mut file = self.create_module_init_file(&config.module_name)?;
-        let generator =
-            CodeGenerator::new(config).with_serde_package_name(self.serde_package_name.clone());
+        let generator = self.generator(config)?;
         generator.output(&mut file, registry)?;
+        config.format_source(
+            Language::Python3,
+            &self.module_init_path(&config.module_name),
+        )?;
+        if self.type_stubs {
+            let stub_path = self
+                .module_init_path(&config.module_name)
+                .with_extension("pyi");
+            let mut file = std::fs::File::create(&stub_path)?;
+            generator.output_stub(&mut file, registry)?;
+            config.format_source(Language::Python3, &stub_path)?;
+        }
+        if self.manifest {
+            self.write_manifest(config)?;
+        }
         Ok(())
     }
 
@@ -452,6 +2115,12 @@ impl crate::SourceInstaller for Installer {
             "{}",
             self.fix_serde_package(include_str!("../runtime/python/serde_binary/__init__.py"))
         )?;
+        let mut file = self.create_module_init_file("serde_json")?;
+        write!(
+            file,
+            "{}",
+            self.fix_serde_package(include_str!("../runtime/python/serde_json/__init__.py"))
+        )?;
         Ok(())
     }
 
@@ -474,4 +2143,24 @@ impl crate::SourceInstaller for Installer {
         )?;
         Ok(())
     }
+
+    fn install_postcard_runtime(&self) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_module_init_file("postcard")?;
+        write!(
+            file,
+            "{}",
+            self.fix_serde_package(include_str!("../runtime/python/postcard/__init__.py"))
+        )?;
+        Ok(())
+    }
+
+    fn install_msgpack_runtime(&self) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_module_init_file("msgpack")?;
+        write!(
+            file,
+            "{}",
+            self.fix_serde_package(include_str!("../runtime/python/msgpack/__init__.py"))
+        )?;
+        Ok(())
+    }
 }
@@ -2,16 +2,29 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    analyzer,
+    analyzer, common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
+use heck::SnakeCase;
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashSet},
     io::{Result, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+};
+
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs, Encoding::Postcard],
+    c_style_enums: true,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: true,
+    bcs_length_prefix: false,
 };
 
 /// Main configuration object for code-generation in Rust.
@@ -24,6 +37,14 @@ pub struct CodeGenerator<'a> {
     custom_derive_block: Option<String>,
     /// Whether definitions and fields should be marked as `pub`.
     track_visibility: bool,
+    /// Whether enums should be marked as `#[non_exhaustive]`.
+    non_exhaustive_enums: bool,
+    /// Whether structs should be marked as `#[non_exhaustive]`.
+    non_exhaustive_structs: bool,
+    /// Whether the output starts with the inner attribute `#![allow(unused_imports)]`.
+    inner_attributes: bool,
+    /// Whether to export encoding and decoding functions to JavaScript with `wasm-bindgen`.
+    wasm_exports: bool,
 }
 
 /// Shared state for the code generation of a Rust source file.
@@ -49,9 +70,19 @@ impl<'a> CodeGenerator<'a> {
                 .collect(),
             custom_derive_block: None,
             track_visibility: true,
+            non_exhaustive_enums: false,
+            non_exhaustive_structs: false,
+            inner_attributes: true,
+            wasm_exports: false,
         }
     }
 
+    /// Report the parts of the registry that cannot be generated in Rust, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Rust, registry)
+    }
+
     /// Which derive macros should be added (independently from serialization).
     pub fn with_derive_macros(mut self, derive_macros: Vec<String>) -> Self {
         self.derive_macros = derive_macros;
@@ -71,12 +102,65 @@ impl<'a> CodeGenerator<'a> {
         self
     }
 
+    /// Whether enums should be marked as `#[non_exhaustive]`, so that matching on them
+    /// requires a wildcard arm outside of the generated crate.
+    pub fn with_non_exhaustive_enums(mut self, non_exhaustive_enums: bool) -> Self {
+        self.non_exhaustive_enums = non_exhaustive_enums;
+        self
+    }
+
+    /// Whether structs should be marked as `#[non_exhaustive]`, so that they cannot be
+    /// constructed outside of the generated crate.
+    pub fn with_non_exhaustive_structs(mut self, non_exhaustive_structs: bool) -> Self {
+        self.non_exhaustive_structs = non_exhaustive_structs;
+        self
+    }
+
+    /// Whether the output may start with inner attributes such as `#![allow(unused_imports)]`.
+    /// Otherwise, the attribute is repeated on each `use` declaration, so that the output can be
+    /// `include!`d by an existing crate. Default: true.
+    pub fn with_inner_attributes(mut self, inner_attributes: bool) -> Self {
+        self.inner_attributes = inner_attributes;
+        self
+    }
+
+    /// Whether to export the functions `__serde_encode_<Type>` and `__serde_decode_<Type>` to
+    /// JavaScript with `wasm-bindgen`, for the TypeScript definitions generated with
+    /// `typescript::CodeGenerator::with_wasm_bridge`. Values are converted with
+    /// `serde-wasm-bindgen` and encoded with the single encoding of the config, hence the
+    /// generated crate must depend on `wasm-bindgen`, `serde-wasm-bindgen`, and the crate of
+    /// the encoding. Requires both serialization and deserialization. Default: false.
+    pub fn with_wasm_exports(mut self, wasm_exports: bool) -> Self {
+        self.wasm_exports = wasm_exports;
+        self
+    }
+
+    /// The encoding of the functions exported with `with_wasm_exports`.
+    fn wasm_encoding(&self) -> std::result::Result<Encoding, Box<dyn std::error::Error>> {
+        if !self.config.serialization || !self.config.has_deserialization() {
+            return Err(
+                "WebAssembly exports require both serialization and deserialization".into(),
+            );
+        }
+        match self.config.encodings.iter().collect::<Vec<_>>().as_slice() {
+            [encoding] if CAPABILITIES.encodings.contains(encoding) => Ok(**encoding),
+            _ => Err("WebAssembly exports require exactly one supported encoding".into()),
+        }
+    }
+
     /// Write container definitions in Rust.
     pub fn output(
         &self,
         out: &mut dyn Write,
         registry: &Registry,
     ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let registry = validation::check_registry(self.config, Language::Rust, registry)?;
+        let registry: &Registry = &registry;
+        let wasm_encoding = if self.wasm_exports {
+            Some(self.wasm_encoding()?)
+        } else {
+            None
+        };
         let external_names = self
             .config
             .external_definitions
@@ -100,7 +184,8 @@ impl<'a> CodeGenerator<'a> {
             .map(String::from)
             .collect();
         let mut emitter = RustEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             known_sizes: Cow::Owned(known_sizes),
             current_namespace,
@@ -112,9 +197,106 @@ impl<'a> CodeGenerator<'a> {
             emitter.output_container(name, format)?;
             emitter.known_sizes.to_mut().insert(name);
         }
+        if let Some(encoding) = wasm_encoding {
+            for name in registry.keys() {
+                emitter.output_wasm_exports(name, encoding)?;
+            }
+        }
         Ok(())
     }
 
+    /// Write container definitions in Rust as a module directory `module_dir` of an existing
+    /// crate, with one submodule per container (e.g. `serde_data.rs` for `SerdeData`) and a
+    /// `mod.rs` file re-exporting all of them. Definitions should be visible (see
+    /// `with_track_visibility`) to be re-exported.
+    pub fn write_source_files(
+        &self,
+        module_dir: PathBuf,
+        registry: &Registry,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.output_files(&module_dir, "mod.rs", registry)?;
+        Ok(())
+    }
+
+    /// Same as `write_source_files` but with a custom name for the root file of the module, and
+    /// return the paths of the generated files.
+    fn output_files(
+        &self,
+        module_dir: &Path,
+        root_name: &str,
+        registry: &Registry,
+    ) -> std::result::Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let registry = validation::check_registry(self.config, Language::Rust, registry)?;
+        let registry: &Registry = &registry;
+        let wasm_encoding = if self.wasm_exports {
+            Some(self.wasm_encoding()?)
+        } else {
+            None
+        };
+        let module_names = get_module_names(registry)?;
+        let external_names = self
+            .config
+            .external_definitions
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        let dependencies =
+            analyzer::get_dependency_map_with_external_dependencies(registry, &external_names)?;
+        let entries = analyzer::best_effort_topological_sort(&dependencies);
+        let mut known_sizes = external_names
+            .iter()
+            .map(<String as std::ops::Deref>::deref)
+            .collect::<HashSet<_>>();
+        let current_namespace = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        std::fs::create_dir_all(module_dir)?;
+        let mut paths = Vec::new();
+        for name in entries {
+            let path = module_dir.join(format!("{}.rs", module_names[name]));
+            let file = std::fs::File::create(&path)?;
+            let mut emitter = RustEmitter {
+                out: IndentedWriter::new(file, IndentConfig::Space(4))
+                    .with_output_indent(self.config.indent),
+                generator: self,
+                known_sizes: Cow::Borrowed(&known_sizes),
+                current_namespace: current_namespace.clone(),
+            };
+            // Imports and sibling containers are found in the parent module.
+            writeln!(emitter.out, "#![allow(unused_imports)]")?;
+            writeln!(emitter.out, "use super::*;")?;
+            emitter.output_container(name, &registry[name])?;
+            known_sizes.insert(name);
+            paths.push(path);
+        }
+
+        let path = module_dir.join(root_name);
+        let file = std::fs::File::create(&path)?;
+        let mut emitter = RustEmitter {
+            out: IndentedWriter::new(file, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
+            generator: self,
+            known_sizes: Cow::Borrowed(&known_sizes),
+            current_namespace,
+        };
+        emitter.output_preamble()?;
+        for module in module_names.values() {
+            writeln!(emitter.out, "mod {0};\npub use {0}::*;", module)?;
+        }
+        if let Some(encoding) = wasm_encoding {
+            for name in registry.keys() {
+                emitter.output_wasm_exports(name, encoding)?;
+            }
+        }
+        paths.push(path);
+        Ok(paths)
+    }
+
     /// For each container, generate a Rust definition.
     pub fn quote_container_definitions(
         &self,
@@ -136,7 +318,8 @@ impl<'a> CodeGenerator<'a> {
             let mut content = Vec::new();
             {
                 let mut emitter = RustEmitter {
-                    out: IndentedWriter::new(&mut content, IndentConfig::Space(4)),
+                    out: IndentedWriter::new(&mut content, IndentConfig::Space(4))
+                        .with_output_indent(self.config.indent),
                     generator: self,
                     known_sizes: Cow::Borrowed(&known_sizes),
                     current_namespace: current_namespace.clone(),
@@ -186,35 +369,92 @@ where
             .flatten()
             .cloned()
             .collect::<HashSet<_>>();
-        writeln!(self.out, "#![allow(unused_imports)]")?;
+        // Without inner attributes, each `use` declaration is annotated instead.
+        let use_prefix = if self.generator.inner_attributes {
+            writeln!(self.out, "#![allow(unused_imports)]")?;
+            ""
+        } else {
+            "#[allow(unused_imports)]\n"
+        };
         if !external_names.contains("Map") {
-            writeln!(self.out, "use std::collections::BTreeMap as Map;")?;
+            let map_type = self
+                .generator
+                .config
+                .map_types
+                .get(&Language::Rust)
+                .map_or("std::collections::BTreeMap", String::as_str);
+            writeln!(self.out, "{}use {} as Map;", use_prefix, map_type)?;
         }
-        if self.generator.config.serialization {
-            writeln!(self.out, "use serde::{{Serialize, Deserialize}};")?;
+        match (
+            self.generator.config.serialization,
+            self.generator.config.has_deserialization(),
+        ) {
+            (true, true) => writeln!(
+                self.out,
+                "{}use serde::{{Serialize, Deserialize}};",
+                use_prefix
+            )?,
+            (true, false) => writeln!(self.out, "{}use serde::Serialize;", use_prefix)?,
+            (false, true) => writeln!(self.out, "{}use serde::Deserialize;", use_prefix)?,
+            (false, false) => (),
         }
-        if self.generator.config.serialization && !external_names.contains("Bytes") {
-            writeln!(self.out, "use serde_bytes::ByteBuf as Bytes;")?;
+        if self.generator.config.has_serde_methods() && !external_names.contains("Bytes") {
+            writeln!(self.out, "{}use serde_bytes::ByteBuf as Bytes;", use_prefix)?;
         }
         for (module, definitions) in &self.generator.config.external_definitions {
             // Skip the empty module name.
             if !module.is_empty() {
                 writeln!(
                     self.out,
-                    "use {}::{{{}}};",
+                    "{}use {}::{{{}}};",
+                    use_prefix,
                     module,
                     definitions.to_vec().join(", "),
                 )?;
             }
         }
         writeln!(self.out)?;
-        if !self.generator.config.serialization && !external_names.contains("Bytes") {
+        if !self.generator.config.has_serde_methods() && !external_names.contains("Bytes") {
             // If we are not going to use Serde derive macros, use plain vectors.
             writeln!(self.out, "type Bytes = Vec<u8>;\n")?;
         }
         Ok(())
     }
 
+    /// Write the functions exported to JavaScript for the container `name` (see
+    /// `CodeGenerator::with_wasm_exports`).
+    fn output_wasm_exports(&mut self, name: &str, encoding: Encoding) -> Result<()> {
+        let (encode, decode) = match encoding {
+            Encoding::Bcs => ("bcs::to_bytes(&value)", "bcs::from_bytes(input)"),
+            Encoding::Bincode => ("bincode::serialize(&value)", "bincode::deserialize(input)"),
+            Encoding::Postcard => (
+                "postcard::to_allocvec(&value)",
+                "postcard::from_bytes(input)",
+            ),
+            Encoding::MessagePack => unreachable!("checked by `wasm_encoding`"),
+        };
+        writeln!(
+            self.out,
+            r#"
+#[wasm_bindgen::prelude::wasm_bindgen]
+#[allow(non_snake_case)]
+pub fn __serde_encode_{0}(value: wasm_bindgen::JsValue) -> Result<Vec<u8>, wasm_bindgen::JsError> {{
+    let value: {0} = serde_wasm_bindgen::from_value(value)?;
+    Ok({1}?)
+}}
+
+#[wasm_bindgen::prelude::wasm_bindgen]
+#[allow(non_snake_case)]
+pub fn __serde_decode_{0}(input: &[u8]) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsError> {{
+    let value: {0} = {2}?;
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    Ok(value.serialize(&serializer)?)
+}}"#,
+            name, encode, decode
+        )
+    }
+
     fn quote_type(format: &Format, known_sizes: Option<&HashSet<&str>>) -> String {
         use Format::*;
         match format {
@@ -277,36 +517,48 @@ where
         };
         for field in fields {
             self.output_comment(&field.name)?;
+            let identifier = self.output_identifier(&field.name)?;
             writeln!(
                 self.out,
                 "{}{}: {},",
                 prefix,
-                field.name,
+                identifier,
                 Self::quote_type(&field.value, Some(&self.known_sizes)),
             )?;
         }
         Ok(())
     }
 
+    /// Escape a field or variant name that is a reserved keyword. The serialized name is kept
+    /// with a `serde(rename)` attribute.
+    fn output_identifier(&mut self, name: &str) -> Result<String> {
+        let identifier = common::escape_keyword(self.generator.config, Language::Rust, name);
+        if identifier != name && self.generator.config.has_serde_methods() {
+            writeln!(self.out, "#[serde(rename = \"{}\")]", name)?;
+        }
+        Ok(identifier)
+    }
+
     fn output_variant(&mut self, base: &str, name: &str, variant: &VariantFormat) -> Result<()> {
         self.output_comment(name)?;
+        let identifier = self.output_identifier(name)?;
         use VariantFormat::*;
         match variant {
-            Unit => writeln!(self.out, "{},", name),
+            Unit => writeln!(self.out, "{},", identifier),
             NewType(format) => writeln!(
                 self.out,
                 "{}({}),",
-                name,
+                identifier,
                 Self::quote_type(format, Some(&self.known_sizes))
             ),
             Tuple(formats) => writeln!(
                 self.out,
                 "{}({}),",
-                name,
+                identifier,
                 Self::quote_types(formats, Some(&self.known_sizes))
             ),
             Struct(fields) => {
-                writeln!(self.out, "{} {{", name)?;
+                writeln!(self.out, "{} {{", identifier)?;
                 self.current_namespace.push(name.to_string());
                 self.out.indent();
                 self.output_fields(&[base, name], fields)?;
@@ -330,11 +582,68 @@ where
         Ok(())
     }
 
+    /// Write the encoder of the compact encoding (see
+    /// `CodeGeneratorConfig::with_elision_containers`).
+    fn output_compact_serialize(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(self.out, "impl {} {{", name)?;
+        self.out.indent();
+        writeln!(
+            self.out,
+            r#"/// Serialize into a presence bitmap followed by the BCS encoding of the fields
+/// without a default value.
+pub fn compact_serialize(&self) -> Result<Vec<u8>, bcs::Error> {{"#
+        )?;
+        self.out.indent();
+        writeln!(
+            self.out,
+            "let mut bitmap = vec![0u8; {}];\nlet mut fields = Vec::new();",
+            common::presence_bitmap_size(fields)
+        )?;
+        for (field, bit) in fields.iter().zip(common::presence_bits(fields)) {
+            let value = format!(
+                "self.{}",
+                common::escape_keyword(self.generator.config, Language::Rust, &field.name)
+            );
+            let encode = format!("fields.extend(bcs::to_bytes(&{})?);", value);
+            match bit {
+                None => writeln!(self.out, "{}", encode)?,
+                Some(bit) => {
+                    writeln!(
+                        self.out,
+                        "if {} {{\n    bitmap[{}] |= 1 << {};\n    {}\n}}",
+                        Self::quote_is_present(&value, &field.value),
+                        bit / 8,
+                        bit % 8,
+                        encode
+                    )?;
+                }
+            }
+        }
+        writeln!(self.out, "bitmap.extend(fields);\nOk(bitmap)")?;
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    /// Condition under which an elidable field is not at its default value.
+    fn quote_is_present(value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Bool => value.to_string(),
+            Str | Bytes | Seq(_) | Map { .. } => format!("!{}.is_empty()", value),
+            Option(_) => format!("{}.is_some()", value),
+            _ => format!("{} != 0", value),
+        }
+    }
+
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
         self.output_comment(name)?;
         let mut derive_macros = self.generator.derive_macros.clone();
         if self.generator.config.serialization {
             derive_macros.push("Serialize".to_string());
+        }
+        if self.generator.config.has_deserialization() {
             derive_macros.push("Deserialize".to_string());
         }
         let mut prefix = String::new();
@@ -345,6 +654,13 @@ where
             prefix.push_str(text);
             prefix.push('\n');
         }
+        let non_exhaustive = match format {
+            ContainerFormat::Enum(_) => self.generator.non_exhaustive_enums,
+            _ => self.generator.non_exhaustive_structs,
+        };
+        if non_exhaustive {
+            prefix.push_str("#[non_exhaustive]\n");
+        }
         if self.generator.track_visibility {
             prefix.push_str("pub ");
         }
@@ -379,6 +695,11 @@ where
                 self.out.unindent();
                 self.current_namespace.pop();
                 writeln!(self.out, "}}\n")?;
+                if self.generator.config.serialization
+                    && self.generator.config.elision_containers.contains(name)
+                {
+                    self.output_compact_serialize(name, fields)?;
+                }
             }
             Enum(variants) => {
                 writeln!(self.out, "{}enum {} {{", prefix, name)?;
@@ -394,14 +715,275 @@ where
     }
 }
 
+/// Choose the name of the submodule of each container, e.g. `serde_data` for `SerdeData`.
+fn get_module_names(registry: &Registry) -> Result<BTreeMap<String, String>> {
+    let mut module_names = BTreeMap::new();
+    let mut containers = BTreeMap::new();
+    for name in registry.keys() {
+        let mut module = name.to_snake_case();
+        if common::is_keyword(Language::Rust, &module) {
+            module.push('_');
+        }
+        if let Some(other) = containers.insert(module.clone(), name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Containers {} and {} would both be written in the module {}",
+                    other, name, module
+                ),
+            ));
+        }
+        module_names.insert(name.clone(), module);
+    }
+    Ok(module_names)
+}
+
+/// Where the Rust installer writes the generated definitions. Relative paths are resolved
+/// against the installation directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    /// A standalone crate `<module_name>/` with a `Cargo.toml` file and a `src/lib.rs` file.
+    Crate,
+    /// A single source file, meant to be `include!`d by an existing crate. Inner attributes are
+    /// omitted (see `CodeGenerator::with_inner_attributes`).
+    SingleFile(PathBuf),
+    /// A module directory of an existing crate, declared with `mod <name>;`: a `mod.rs` file,
+    /// plus one submodule per container with `Installer::with_split_modules`.
+    ModuleDir(PathBuf),
+}
+
+impl Default for OutputMode {
+    fn default() -> Self {
+        OutputMode::Crate
+    }
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = String;
+
+    /// Parse `crate`, `single-file=<path>`, or `module-dir=<path>`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            None if s == "crate" => Ok(OutputMode::Crate),
+            Some(("single-file", path)) if !path.is_empty() => {
+                Ok(OutputMode::SingleFile(PathBuf::from(path)))
+            }
+            Some(("module-dir", path)) if !path.is_empty() => {
+                Ok(OutputMode::ModuleDir(PathBuf::from(path)))
+            }
+            _ => Err(format!(
+                "Unsupported output mode {} (expected crate, single-file=<path>, or module-dir=<path>)",
+                s
+            )),
+        }
+    }
+}
+
 /// Installer for generated source files in Rust.
 pub struct Installer {
     install_dir: PathBuf,
+    non_exhaustive_enums: bool,
+    non_exhaustive_structs: bool,
+    output_mode: OutputMode,
+    split_modules: bool,
+    fuzz_targets: Vec<String>,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            non_exhaustive_enums: false,
+            non_exhaustive_structs: false,
+            output_mode: OutputMode::Crate,
+            split_modules: false,
+            fuzz_targets: Vec::new(),
+        }
+    }
+
+    /// Whether enums should be marked as `#[non_exhaustive]`.
+    pub fn with_non_exhaustive_enums(mut self, non_exhaustive_enums: bool) -> Self {
+        self.non_exhaustive_enums = non_exhaustive_enums;
+        self
+    }
+
+    /// Whether structs should be marked as `#[non_exhaustive]`.
+    pub fn with_non_exhaustive_structs(mut self, non_exhaustive_structs: bool) -> Self {
+        self.non_exhaustive_structs = non_exhaustive_structs;
+        self
+    }
+
+    /// Whether to install each module as a standalone crate (the default), a single source
+    /// file, or a module directory of an existing crate.
+    pub fn with_output_mode(mut self, output_mode: OutputMode) -> Self {
+        self.output_mode = output_mode;
+        self
+    }
+
+    /// Whether to write one submodule per container (see `CodeGenerator::write_source_files`).
+    /// Not compatible with `OutputMode::SingleFile`. Default: false.
+    pub fn with_split_modules(mut self, split_modules: bool) -> Self {
+        self.split_modules = split_modules;
+        self
+    }
+
+    /// Containers for which to write a `fuzz/` directory in the layout of `cargo fuzz`, next to
+    /// the crate of the module. Each fuzz target decodes its input as a value of the container
+    /// with each encoding of the config and checks that the value is encoded again into the same
+    /// bytes. Only compatible with `OutputMode::Crate`. Default: none.
+    ///
+    /// The fuzz crate links libFuzzer through its default feature `libfuzzer`, hence
+    /// `cargo check --no-default-features` also works on a stable toolchain.
+    pub fn with_fuzz_targets(mut self, fuzz_targets: Vec<String>) -> Self {
+        self.fuzz_targets = fuzz_targets;
+        self
+    }
+
+    /// Write the source files of a module in `dir_path`, starting with `root_name`.
+    fn write_module(
+        &self,
+        generator: &CodeGenerator,
+        config: &CodeGeneratorConfig,
+        dir_path: &Path,
+        root_name: &str,
+        registry: &Registry,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.split_modules {
+            for path in generator.output_files(dir_path, root_name, registry)? {
+                config.format_source(Language::Rust, &path)?;
+            }
+            return Ok(());
+        }
+        std::fs::create_dir_all(dir_path)?;
+        let source_path = dir_path.join(root_name);
+        let mut source = std::fs::File::create(&source_path)?;
+        generator.output(&mut source, registry)?;
+        config.format_source(Language::Rust, &source_path)?;
+        Ok(())
+    }
+
+    /// Write the fuzz crate of `self.fuzz_targets` in `dir_path`, for the crate `name`.
+    fn write_fuzz_crate(
+        &self,
+        config: &CodeGeneratorConfig,
+        dir_path: &Path,
+        name: &str,
+        registry: &Registry,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if !config.serialization || !config.has_deserialization() {
+            return Err("Fuzz targets require both serialization and deserialization".into());
+        }
+        let encodings = config
+            .encodings
+            .iter()
+            .filter(|encoding| CAPABILITIES.encodings.contains(encoding))
+            .collect::<Vec<_>>();
+        if encodings.is_empty() {
+            return Err("Fuzz targets require at least one supported encoding".into());
+        }
+        for target in &self.fuzz_targets {
+            if !registry.contains_key(target) {
+                return Err(format!("Unknown container {} for fuzz targets", target).into());
+            }
+        }
+        let crate_name = name.replace('-', "_");
+        std::fs::create_dir_all(dir_path.join("fuzz_targets"))?;
+        let mut cargo = std::fs::File::create(dir_path.join("Cargo.toml"))?;
+        write!(
+            cargo,
+            r#"[package]
+name = "{0}-fuzz"
+version = "0.0.0"
+publish = false
+edition = "2018"
+
+[package.metadata]
+cargo-fuzz = true
+
+[features]
+default = ["libfuzzer"]
+libfuzzer = ["libfuzzer-sys/link_libfuzzer"]
+
+[dependencies]
+libfuzzer-sys = {{ version = "0.4", default-features = false }}
+{0} = {{ path = ".." }}
+"#,
+            name,
+        )?;
+        for encoding in &encodings {
+            match encoding {
+                Encoding::Bcs => writeln!(cargo, "bcs = \"0.1.1\"")?,
+                Encoding::Bincode => writeln!(cargo, "bincode = \"1.3\"")?,
+                Encoding::Postcard => writeln!(
+                    cargo,
+                    "postcard = {{ version = \"1.0\", features = [\"alloc\"] }}"
+                )?,
+                Encoding::MessagePack => unreachable!("filtered by `CAPABILITIES`"),
+            }
+        }
+        writeln!(cargo, "\n[workspace]\nmembers = [\".\"]")?;
+
+        for target in &self.fuzz_targets {
+            let target_name = target.to_snake_case();
+            write!(
+                cargo,
+                r#"
+[[bin]]
+name = "{0}"
+path = "fuzz_targets/{0}.rs"
+test = false
+doc = false
+bench = false
+"#,
+                target_name,
+            )?;
+            let source_path = dir_path.join(format!("fuzz_targets/{}.rs", target_name));
+            let mut source = std::fs::File::create(&source_path)?;
+            writeln!(
+                source,
+                r#"#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use {}::{};
+
+fuzz_target!(|data: &[u8]| {{"#,
+                crate_name, target,
+            )?;
+            for encoding in &encodings {
+                let (encode, decode) = match encoding {
+                    Encoding::Bcs => ("bcs::to_bytes", "bcs::from_bytes"),
+                    Encoding::Bincode => ("bincode::serialize", "bincode::deserialize"),
+                    Encoding::Postcard => ("postcard::to_allocvec", "postcard::from_bytes"),
+                    Encoding::MessagePack => unreachable!("filtered by `CAPABILITIES`"),
+                };
+                writeln!(
+                    source,
+                    "    if let Ok(value) = {}::<{}>(data) {{",
+                    decode, target
+                )?;
+                if **encoding == Encoding::Bcs {
+                    // BCS is canonical: only the encoding of a value is accepted.
+                    writeln!(
+                        source,
+                        "        assert_eq!({}(&value).unwrap(), data);",
+                        encode
+                    )?;
+                } else {
+                    // Other encodings may accept trailing bytes.
+                    writeln!(
+                        source,
+                        r#"        let bytes = {2}(&value).unwrap();
+        let value = {1}::<{0}>(&bytes).unwrap();
+        assert_eq!({2}(&value).unwrap(), bytes);"#,
+                        target, decode, encode,
+                    )?;
+                }
+                writeln!(source, "    }}")?;
+            }
+            writeln!(source, "}});")?;
+            config.format_source(Language::Rust, &source_path)?;
+        }
+        Ok(())
     }
 
     fn runtime_installation_message(name: &str) {
@@ -417,7 +999,44 @@ impl crate::SourceInstaller for Installer {
         config: &CodeGeneratorConfig,
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
-        let generator = CodeGenerator::new(config);
+        let generator = CodeGenerator::new(config)
+            .with_non_exhaustive_enums(self.non_exhaustive_enums)
+            .with_non_exhaustive_structs(self.non_exhaustive_structs);
+        match &self.output_mode {
+            OutputMode::Crate => (),
+            OutputMode::SingleFile(_) | OutputMode::ModuleDir(_)
+                if !self.fuzz_targets.is_empty() =>
+            {
+                return Err(ConfigError::Conflict {
+                    language: Language::Rust,
+                    options: ("fuzz targets", "single-file or module-dir output"),
+                }
+                .into());
+            }
+            OutputMode::SingleFile(path) => {
+                if self.split_modules {
+                    return Err(ConfigError::Conflict {
+                        language: Language::Rust,
+                        options: ("split modules", "single-file output"),
+                    }
+                    .into());
+                }
+                let source_path = self.install_dir.join(path);
+                if let Some(parent) = source_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let mut source = std::fs::File::create(&source_path)?;
+                generator
+                    .with_inner_attributes(false)
+                    .output(&mut source, registry)?;
+                config.format_source(Language::Rust, &source_path)?;
+                return Ok(());
+            }
+            OutputMode::ModuleDir(path) => {
+                let dir_path = self.install_dir.join(path);
+                return self.write_module(&generator, config, &dir_path, "mod.rs", registry);
+            }
+        }
         let (name, version) = {
             let parts = config.module_name.splitn(2, ':').collect::<Vec<_>>();
             if parts.len() >= 2 {
@@ -442,10 +1061,20 @@ serde_bytes = "0.11"
 "#,
             name, version,
         )?;
-        std::fs::create_dir(dir_path.join("src"))?;
-        let source_path = dir_path.join("src/lib.rs");
-        let mut source = std::fs::File::create(source_path)?;
-        generator.output(&mut source, registry)
+        if config.serialization && !config.elision_containers.is_empty() {
+            writeln!(cargo, "bcs = \"0.1.1\"")?;
+        }
+        self.write_module(
+            &generator,
+            config,
+            &dir_path.join("src"),
+            "lib.rs",
+            registry,
+        )?;
+        if !self.fuzz_targets.is_empty() {
+            self.write_fuzz_crate(config, &dir_path.join("fuzz"), &name, registry)?;
+        }
+        Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
@@ -462,4 +1091,9 @@ serde_bytes = "0.11"
         Self::runtime_installation_message("bcs");
         Ok(())
     }
+
+    fn install_postcard_runtime(&self) -> std::result::Result<(), Self::Error> {
+        Self::runtime_installation_message("postcard");
+        Ok(())
+    }
 }
@@ -2,11 +2,12 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::SnakeCase;
-use phf::phf_set;
 use serde_reflection::{ContainerFormat, Format, Named, Registry, VariantFormat};
 use std::{
     collections::{HashMap, HashSet},
@@ -14,10 +15,23 @@ use std::{
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in solidity
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
     config: &'a CodeGeneratorConfig,
+    /// Whether to report deserialization failures with custom errors instead of `require`.
+    custom_errors: bool,
 }
 
 /// Shared state for the code generation of a solidity source file.
@@ -35,13 +49,38 @@ fn get_data_location(need_memory: bool) -> String {
     }
 }
 
+/// Custom errors used with `CodeGenerator::with_custom_errors`.
+const CUSTOM_ERRORS: &str = r#"
+error IncompleteDeserialization(uint256 expected, uint256 actual);
+error PositionOutOfBound(uint256 position, uint256 length);
+error InvalidVariantIndex(uint256 index);"#;
+
+/// Statement checking that `size` bytes can be read from `input` at position `pos`.
+fn check_position(custom_errors: bool, size: usize) -> String {
+    let last = match size {
+        1 => "pos".to_string(),
+        _ => format!("pos + {}", size - 1),
+    };
+    if custom_errors {
+        format!("if ({last} >= input.length) revert PositionOutOfBound(pos, input.length);")
+    } else {
+        format!("require({last} < input.length, \"Position out of bound\");")
+    }
+}
+
 fn output_generic_bcs_deserialize<T: std::io::Write>(
     out: &mut IndentedWriter<T>,
     key_name: &str,
     code_name: &str,
     need_memory: bool,
+    custom_errors: bool,
 ) -> Result<()> {
     let data_location = get_data_location(need_memory);
+    let check_length = if custom_errors {
+        "if (new_pos != input.length) revert IncompleteDeserialization(input.length, new_pos);"
+    } else {
+        "require(new_pos == input.length, \"incomplete deserialization\");"
+    };
     writeln!(
         out,
         r#"
@@ -49,50 +88,18 @@ function bcs_deserialize_{key_name}(bytes memory input) public pure returns ({co
   uint256 new_pos;
   {code_name}{data_location} value;
   (new_pos, value) = bcs_deserialize_offset_{key_name}(0, input);
-  require(new_pos == input.length, "incomplete deserialization");
+  {check_length}
   return value;
 }}"#
     )?;
     Ok(())
 }
 
-static KEYWORDS: phf::Set<&str> = phf_set! {
-    "abstract", "after", "alias", "anonymous",
-    "as", "assembly", "break", "catch", "constant",
-    "continue", "constructor", "contract", "delete",
-    "do", "else", "emit", "enum", "error", "event",
-    "external", "fallback", "for", "function", "if",
-    "immutable", "import", "indexed", "interface",
-    "internal", "is", "library", "mapping", "memory",
-    "modifier", "new", "override", "payable", "pragma",
-    "private", "public", "pure", "receive", "return",
-    "returns", "revert", "storage", "struct", "throw",
-    "try", "type", "unchecked", "using", "virtual",
-    "view", "while", "addmod", "blockhash", "ecrecover",
-    "keccak256", "mulmod", "sha256", "ripemd160",
-    "block", "msg", "tx", "balance", "transfer", "send",
-    "call", "delegatecall", "staticcall", "this",
-    "super", "gwei", "finney", "szabo", "ether",
-    "seconds", "minutes", "hours", "days", "weeks",
-    "years", "wei", "hex", "address", "bool", "bytes",
-    "string", "int", "int8", "int16", "int32", "int64",
-    "int128", "int256", "uint", "uint8", "uint16",
-    "uint32", "uint64", "uint128", "uint256",
-    "bytes1", "bytes2", "bytes3", "bytes4", "bytes5",
-    "bytes6", "bytes7", "bytes8", "bytes9", "bytes10",
-    "bytes11", "bytes12", "bytes13", "bytes14", "bytes15",
-    "bytes16", "bytes17", "bytes18", "bytes19", "bytes20",
-    "bytes21", "bytes22", "bytes23", "bytes24", "bytes25",
-    "bytes26", "bytes27", "bytes28", "bytes29", "bytes30",
-    "bytes31", "bytes32"
-};
+/// Maximum number of variants in an enum, so that the variant index fits in one byte.
+const MAX_ENUM_VARIANTS: usize = 128;
 
-fn safe_variable(s: &str) -> String {
-    if KEYWORDS.contains(s) {
-        s.to_owned() + "_"
-    } else {
-        s.to_string()
-    }
+fn safe_variable(config: &CodeGeneratorConfig, s: &str) -> String {
+    common::escape_keyword(config, Language::Solidity, s)
 }
 
 #[derive(Clone, Debug)]
@@ -136,7 +143,11 @@ impl Primitive {
         }
     }
 
-    pub fn output<T: std::io::Write>(&self, out: &mut IndentedWriter<T>) -> Result<()> {
+    pub fn output<T: std::io::Write>(
+        &self,
+        out: &mut IndentedWriter<T>,
+        custom_errors: bool,
+    ) -> Result<()> {
         use Primitive::*;
         match self {
             Unit => writeln!(
@@ -311,6 +322,7 @@ function bcs_deserialize_offset_int128(uint256 pos, bytes memory input) internal
                 )?;
             }
             U8 => {
+                let check = check_position(custom_errors, 1);
                 writeln!(
                     out,
                     r#"
@@ -318,13 +330,14 @@ function bcs_serialize_uint8(uint8 input) internal pure returns (bytes memory) {
   return abi.encodePacked(input);
 }}
 function bcs_deserialize_offset_uint8(uint256 pos, bytes memory input) internal pure returns (uint256, uint8) {{
-  require(pos < input.length, "Position out of bound");
+  {check}
   uint8 value = uint8(input[pos]);
   return (pos + 1, value);
 }}"#
                 )?;
             }
             U16 => {
+                let check = check_position(custom_errors, 2);
                 writeln!(
                     out,
                     r#"
@@ -337,7 +350,7 @@ function bcs_serialize_uint16(uint16 input) internal pure returns (bytes memory)
   return result;
 }}
 function bcs_deserialize_offset_uint16(uint256 pos, bytes memory input) internal pure returns (uint256, uint16) {{
-  require(pos + 1 < input.length, "Position out of bound");
+  {check}
   uint16 value = uint8(input[pos+1]);
   value = value << 8;
   value += uint8(input[pos]);
@@ -346,6 +359,7 @@ function bcs_deserialize_offset_uint16(uint256 pos, bytes memory input) internal
                 )?;
             }
             U32 => {
+                let check = check_position(custom_errors, 4);
                 writeln!(
                     out,
                     r#"
@@ -360,7 +374,7 @@ function bcs_serialize_uint32(uint32 input) internal pure returns (bytes memory)
   return result;
 }}
 function bcs_deserialize_offset_uint32(uint256 pos, bytes memory input) internal pure returns (uint256, uint32) {{
-  require(pos + 3 < input.length, "Position out of bound");
+  {check}
   uint32 value = uint8(input[pos + 3]);
   for (uint256 i=0; i<3; i++) {{
     value = value << 8;
@@ -371,6 +385,7 @@ function bcs_deserialize_offset_uint32(uint256 pos, bytes memory input) internal
                 )?;
             }
             U64 => {
+                let check = check_position(custom_errors, 8);
                 writeln!(
                     out,
                     r#"
@@ -385,7 +400,7 @@ function bcs_serialize_uint64(uint64 input) internal pure returns (bytes memory)
   return result;
 }}
 function bcs_deserialize_offset_uint64(uint256 pos, bytes memory input) internal pure returns (uint256, uint64) {{
-  require(pos + 7 < input.length, "Position out of bound");
+  {check}
   uint64 value = uint8(input[pos + 7]);
   for (uint256 i=0; i<7; i++) {{
     value = value << 8;
@@ -396,6 +411,7 @@ function bcs_deserialize_offset_uint64(uint256 pos, bytes memory input) internal
                 )?;
             }
             U128 => {
+                let check = check_position(custom_errors, 16);
                 writeln!(
                     out,
                     r#"
@@ -410,7 +426,7 @@ function bcs_serialize_uint128(uint128 input) internal pure returns (bytes memor
   return result;
 }}
 function bcs_deserialize_offset_uint128(uint256 pos, bytes memory input) internal pure returns (uint256, uint128) {{
-  require(pos + 15 < input.length, "Position out of bound");
+  {check}
   uint128 value = uint8(input[pos + 15]);
   for (uint256 i=0; i<15; i++) {{
     value = value << 8;
@@ -521,7 +537,9 @@ enum SolFormat {
     },
     /// An option encapsulated as a solidity struct.
     Option(Box<SolFormat>),
-    /// A Tuplearray encapsulated as a solidity struct.
+    /// A Tuplearray encapsulated as a solidity struct holding a dynamic array of `size` values.
+    /// Fixed-size `bytesN` types only go up to `bytes32`, so this struct is used for every
+    /// size, including arrays of more than 32 bytes such as `[u8; 64]`.
     TupleArray { format: Box<SolFormat>, size: usize },
     /// A complex enum encapsulated as a solidity struct.
     Enum {
@@ -557,10 +575,12 @@ impl SolFormat {
         &self,
         out: &mut IndentedWriter<T>,
         sol_registry: &SolRegistry,
+        config: &CodeGeneratorConfig,
+        custom_errors: bool,
     ) -> Result<()> {
         use SolFormat::*;
         match self {
-            Primitive(primitive) => primitive.output(out)?,
+            Primitive(primitive) => primitive.output(out, custom_errors)?,
             TypeName(_) => {
                 // by definition for TypeName the code already exists
             }
@@ -597,7 +617,7 @@ function bcs_deserialize_offset_{full_name}(uint256 pos, bytes memory input) int
   return (new_pos, {full_name}(true, value));
 }}"#
                 )?;
-                output_generic_bcs_deserialize(out, &full_name, &full_name, true)?;
+                output_generic_bcs_deserialize(out, &full_name, &full_name, true, custom_errors)?;
             }
             Seq(format) => {
                 let inner_key_name = format.key_name();
@@ -630,7 +650,7 @@ function bcs_deserialize_offset_{key_name}(uint256 pos, bytes memory input) inte
   return (new_pos, result);
 }}"#
                 )?;
-                output_generic_bcs_deserialize(out, &key_name, &code_name, true)?;
+                output_generic_bcs_deserialize(out, &key_name, &code_name, true, custom_errors)?;
             }
             TupleArray { format, size } => {
                 let inner_key_name = format.key_name();
@@ -661,7 +681,13 @@ function bcs_deserialize_offset_{struct_name}(uint256 pos, bytes memory input) i
   return (new_pos, {struct_name}(values));
 }}"#
                 )?;
-                output_generic_bcs_deserialize(out, &struct_name, &struct_name, true)?;
+                output_generic_bcs_deserialize(
+                    out,
+                    &struct_name,
+                    &struct_name,
+                    true,
+                    custom_errors,
+                )?;
             }
             Struct { name, formats } => {
                 writeln!(out, "struct {name} {{")?;
@@ -670,7 +696,7 @@ function bcs_deserialize_offset_{struct_name}(uint256 pos, bytes memory input) i
                         out,
                         "  {} {};",
                         named_format.value.code_name(),
-                        safe_variable(&named_format.name)
+                        safe_variable(config, &named_format.name)
                     )?;
                 }
                 writeln!(out, "}}")?;
@@ -679,11 +705,11 @@ function bcs_deserialize_offset_{struct_name}(uint256 pos, bytes memory input) i
                     out,
                     "  bytes memory result = bcs_serialize_{}(input.{});",
                     &formats[0].value.key_name(),
-                    safe_variable(&formats[0].name)
+                    safe_variable(config, &formats[0].name)
                 )?;
                 for named_format in &formats[1..] {
                     let key_name = named_format.value.key_name();
-                    let safe_name = safe_variable(&named_format.name);
+                    let safe_name = safe_variable(config, &named_format.name);
                     writeln!(out, "  result = abi.encodePacked(result, bcs_serialize_{key_name}(input.{safe_name}));")?;
                 }
                 writeln!(out, "  return result;")?;
@@ -694,7 +720,7 @@ function bcs_deserialize_offset_{struct_name}(uint256 pos, bytes memory input) i
                     let data_location = sol_registry.data_location(&named_format.value);
                     let code_name = named_format.value.code_name();
                     let key_name = named_format.value.key_name();
-                    let safe_name = safe_variable(&named_format.name);
+                    let safe_name = safe_variable(config, &named_format.name);
                     writeln!(out, "  {code_name}{data_location} {safe_name};")?;
                     writeln!(out, "  (new_pos, {safe_name}) = bcs_deserialize_offset_{key_name}(new_pos, input);")?;
                 }
@@ -703,16 +729,24 @@ function bcs_deserialize_offset_{struct_name}(uint256 pos, bytes memory input) i
                     "  return (new_pos, {name}({}));",
                     formats
                         .iter()
-                        .map(|named_format| safe_variable(&named_format.name))
+                        .map(|named_format| safe_variable(config, &named_format.name))
                         .collect::<Vec<_>>()
                         .join(", ")
                 )?;
                 writeln!(out, "}}")?;
-                output_generic_bcs_deserialize(out, name, name, true)?;
+                output_generic_bcs_deserialize(out, name, name, true, custom_errors)?;
             }
             SimpleEnum { name, names } => {
+                let names = names
+                    .iter()
+                    .map(|variant| safe_variable(config, variant))
+                    .collect::<Vec<_>>();
                 let names_join = names.join(", ");
-                let number_names = names.len();
+                let check_choice = if custom_errors {
+                    "revert InvalidVariantIndex(choice);".to_string()
+                } else {
+                    format!("require(choice < {});", names.len())
+                };
                 writeln!(
                     out,
                     r#"
@@ -735,10 +769,10 @@ function bcs_deserialize_offset_{name}(uint256 pos, bytes memory input) internal
                 writeln!(
                     out,
                     r#"
-  require(choice < {number_names});
+  {check_choice}
 }}"#
                 )?;
-                output_generic_bcs_deserialize(out, name, name, false)?;
+                output_generic_bcs_deserialize(out, name, name, false, custom_errors)?;
             }
             Enum { name, formats } => {
                 writeln!(out, "struct {name} {{")?;
@@ -748,7 +782,7 @@ function bcs_deserialize_offset_{name}(uint256 pos, bytes memory input) internal
                     writeln!(out, "  // choice={idx} corresponds to {name}")?;
                     if let Some(format) = &named_format.value {
                         let code_name = format.code_name();
-                        let snake_name = named_format.name.to_snake_case();
+                        let snake_name = safe_variable(config, &named_format.name.to_snake_case());
                         writeln!(out, "  {code_name} {snake_name};")?;
                     }
                 }
@@ -761,7 +795,7 @@ function bcs_deserialize_offset_{name}(uint256 pos, bytes memory input) internal
                 for (idx, named_format) in formats.iter().enumerate() {
                     if let Some(format) = &named_format.value {
                         let key_name = format.key_name();
-                        let snake_name = named_format.name.to_snake_case();
+                        let snake_name = safe_variable(config, &named_format.name.to_snake_case());
                         writeln!(out, "  if (input.choice == {idx}) {{")?;
                         writeln!(out, "    return abi.encodePacked(result, bcs_serialize_{key_name}(input.{snake_name}));")?;
                         writeln!(out, "  }}")?;
@@ -780,7 +814,7 @@ function bcs_deserialize_offset_{name}(uint256 pos, bytes memory input) internal
                 for (idx, named_format) in formats.iter().enumerate() {
                     if let Some(format) = &named_format.value {
                         let data_location = sol_registry.data_location(format);
-                        let snake_name = named_format.name.to_snake_case();
+                        let snake_name = safe_variable(config, &named_format.name.to_snake_case());
                         let code_name = format.code_name();
                         let key_name = format.key_name();
                         writeln!(out, "  {code_name}{data_location} {snake_name};")?;
@@ -796,7 +830,7 @@ function bcs_deserialize_offset_{name}(uint256 pos, bytes memory input) internal
                     entries.join(", ")
                 )?;
                 writeln!(out, "}}")?;
-                output_generic_bcs_deserialize(out, name, name, true)?;
+                output_generic_bcs_deserialize(out, name, name, true, custom_errors)?;
             }
         }
         Ok(())
@@ -870,7 +904,7 @@ impl SolRegistry {
         }
     }
 
-    fn has_circular_dependency(&self) -> bool {
+    fn find_circular_dependency(&self) -> Option<&str> {
         for start_key in self.names.keys() {
             let mut level = HashSet::<String>::new();
             level.insert(start_key.to_string());
@@ -880,7 +914,7 @@ impl SolRegistry {
                 for key in level {
                     for depend in self.names.get(&key).unwrap().get_dependency() {
                         if depend == *start_key {
-                            return true;
+                            return Some(start_key);
                         }
                         if !total_dependency.contains(&depend) {
                             total_dependency.insert(depend.clone());
@@ -894,7 +928,7 @@ impl SolRegistry {
                 level = new_level;
             }
         }
-        false
+        None
     }
 
     fn parse_format(&mut self, format: Format) -> SolFormat {
@@ -1030,7 +1064,6 @@ impl SolRegistry {
                     !map.is_empty(),
                     "The enum should be non-trivial in solidity"
                 );
-                assert!(map.len() < 256, "The enum should have at most 256 entries");
                 let is_trivial = map
                     .iter()
                     .all(|(_, v)| matches!(v.value, VariantFormat::Unit));
@@ -1115,11 +1148,76 @@ impl SolRegistry {
 
 impl<'a> CodeGenerator<'a> {
     /// Create a solidity code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Solidity does not support generating c-style enums");
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a solidity code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Solidity, config)?;
+        Ok(Self {
+            config,
+            custom_errors: false,
+        })
+    }
+
+    /// Report deserialization failures with typed custom errors, e.g.
+    /// `revert IncompleteDeserialization(expected, actual)`, instead of `require` with a
+    /// message (default: false). This requires Solidity 0.8.4 or later.
+    pub fn with_custom_errors(mut self, custom_errors: bool) -> Self {
+        self.custom_errors = custom_errors;
+        self
+    }
+
+    /// Report the parts of the registry that cannot be generated in Solidity, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        let mut issues = validation::validate_support(self.config, Language::Solidity, registry);
+        validation::visit_formats(registry, &mut |path, format| {
+            if matches!(format, Format::F32 | Format::F64) {
+                issues.push(validation::Issue {
+                    path: path.to_string(),
+                    reason: "floating point is not supported in solidity".to_string(),
+                });
+            }
+        });
+        for (key, container_format) in registry {
+            match container_format {
+                ContainerFormat::UnitStruct => issues.push(validation::Issue {
+                    path: key.clone(),
+                    reason: "UnitStruct is not supported in solidity".to_string(),
+                }),
+                ContainerFormat::Enum(variants) if variants.len() > MAX_ENUM_VARIANTS => issues
+                    .push(validation::Issue {
+                        path: key.clone(),
+                        reason: format!(
+                            "{} variants but Solidity supports at most {}",
+                            variants.len(),
+                            MAX_ENUM_VARIANTS
+                        ),
+                    }),
+                _ => (),
+            }
         }
-        Self { config }
+        if issues.is_empty() {
+            // Only parse registries with supported formats.
+            let mut sol_registry = SolRegistry::default();
+            for (key, container_format) in registry {
+                sol_registry.parse_container_format(Named {
+                    name: key.to_string(),
+                    value: container_format.clone(),
+                });
+            }
+            if let Some(key) = sol_registry.find_circular_dependency() {
+                issues.push(validation::Issue {
+                    path: key.to_string(),
+                    reason: "solidity does not allow for circular dependencies".to_string(),
+                });
+            }
+        }
+        issues
     }
 
     pub fn output(
@@ -1127,8 +1225,35 @@ impl<'a> CodeGenerator<'a> {
         out: &mut dyn Write,
         registry: &Registry,
     ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let registry = validation::check_registry(self.config, Language::Solidity, registry)?;
+        let registry: &Registry = &registry;
+        // Variant indices are encoded as a single byte, which matches ULEB128 only below 128.
+        for (key, container_format) in registry {
+            if let ContainerFormat::Enum(variants) = container_format {
+                if variants.len() > MAX_ENUM_VARIANTS {
+                    return Err(format!(
+                        "Enum {} has {} variants but Solidity supports at most {}",
+                        key,
+                        variants.len(),
+                        MAX_ENUM_VARIANTS
+                    )
+                    .into());
+                }
+            }
+        }
+        let issues = self.validate(registry);
+        if !issues.is_empty() {
+            let message = issues
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Err(message.into());
+        }
+
         let mut emitter = SolEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
         };
 
@@ -1143,11 +1268,13 @@ impl<'a> CodeGenerator<'a> {
             };
             sol_registry.parse_container_format(container_format);
         }
-        if sol_registry.has_circular_dependency() {
-            panic!("solidity does not allow for circular dependencies");
-        }
         for sol_format in sol_registry.names.values() {
-            sol_format.output(&mut emitter.out, &sol_registry)?;
+            sol_format.output(
+                &mut emitter.out,
+                &sol_registry,
+                self.config,
+                self.custom_errors,
+            )?;
         }
 
         emitter.output_close_library()?;
@@ -1161,11 +1288,16 @@ where
     T: std::io::Write,
 {
     fn output_preamble(&mut self) -> Result<()> {
+        let (version, errors) = if self.generator.custom_errors {
+            ("0.8.4", CUSTOM_ERRORS)
+        } else {
+            ("0.8.0", "")
+        };
         writeln!(
             self.out,
             r#"
 /// SPDX-License-Identifier: UNLICENSED
-pragma solidity ^0.8.0;
+pragma solidity ^{version};{errors}
 function bcs_serialize_len(uint256 x) pure returns (bytes memory) {{
   uint256 power = 128;
   bytes memory result;
@@ -1231,17 +1363,30 @@ function bcs_deserialize_offset_len(uint256 pos, bytes memory input) pure return
 /// Installer for generated source files in solidity
 pub struct Installer {
     install_dir: PathBuf,
+    custom_errors: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            custom_errors: false,
+        }
+    }
+
+    /// See `CodeGenerator::with_custom_errors`.
+    pub fn with_custom_errors(mut self, custom_errors: bool) -> Self {
+        self.custom_errors = custom_errors;
+        self
+    }
+
+    fn header_path(&self, name: &str) -> PathBuf {
+        self.install_dir.join(name.to_string() + ".sol")
     }
 
     fn create_header_file(&self, name: &str) -> Result<std::fs::File> {
-        let dir_path = &self.install_dir;
-        std::fs::create_dir_all(dir_path)?;
-        std::fs::File::create(dir_path.join(name.to_string() + ".sol"))
+        std::fs::create_dir_all(&self.install_dir)?;
+        std::fs::File::create(self.header_path(name))
     }
 
     fn runtime_installation_message(name: &str) {
@@ -1258,8 +1403,10 @@ impl crate::SourceInstaller for Installer {
         registry: &Registry,
     ) -> std::result::Result<(), Self::Error> {
         let mut file = self.create_header_file(&config.module_name)?;
-        let generator = CodeGenerator::new(config);
-        generator.output(&mut file, registry)
+        let generator = CodeGenerator::try_new(config)?.with_custom_errors(self.custom_errors);
+        generator.output(&mut file, registry)?;
+        config.format_source(Language::Solidity, &self.header_path(&config.module_name))?;
+        Ok(())
     }
 
     fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
@@ -6,17 +6,29 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig, Encoding,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use include_dir::include_dir as include_directory;
 use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{Result, Write},
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in Swift.
 pub struct CodeGenerator<'a> {
     /// Language-independent configuration.
@@ -34,14 +46,21 @@ struct SwiftEmitter<'a, T> {
     generator: &'a CodeGenerator<'a>,
     /// Current namespace (e.g. vec!["Package", "MyClass"])
     current_namespace: Vec<String>,
+    /// Containers that conform to `Sendable`, i.e. that do not depend on external types.
+    sendable_types: BTreeSet<String>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a Swift code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("Swift does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a Swift code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Swift, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             let package_name = {
@@ -57,14 +76,22 @@ impl<'a> CodeGenerator<'a> {
                     .insert(name.to_string(), format!("{}.{}", package_name, name));
             }
         }
-        Self {
+        Ok(Self {
             config,
             external_qualified_names,
-        }
+        })
+    }
+
+    /// Report the parts of the registry that cannot be generated in Swift, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Swift, registry)
     }
 
     /// Output class definitions for `registry`.
     pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = validation::check_registry(self.config, Language::Swift, registry)?;
+        let registry: &Registry = &registry;
         let current_namespace = self
             .config
             .module_name
@@ -73,9 +100,11 @@ impl<'a> CodeGenerator<'a> {
             .collect::<Vec<_>>();
 
         let mut emitter = SwiftEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(4)),
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
             generator: self,
             current_namespace,
+            sendable_types: get_sendable_types(registry, &self.external_qualified_names),
         };
 
         emitter.output_preamble()?;
@@ -98,10 +127,26 @@ where
     T: Write,
 {
     fn output_preamble(&mut self) -> Result<()> {
-        writeln!(self.out, "import Serde\n")?;
+        writeln!(
+            self.out,
+            "// {}. Do not edit.\n\nimport Serde\n",
+            common::GENERATED_FILE_MARKER
+        )?;
         Ok(())
     }
 
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::Swift, name)
+    }
+
+    fn quote_variant(&self, name: &str) -> String {
+        common::escape_keyword(
+            self.generator.config,
+            Language::Swift,
+            &common::lowercase_first_letter(name),
+        )
+    }
+
     /// Compute a reference to the registry type `name`.
     fn quote_qualified_name(&self, name: &str) -> String {
         self.generator
@@ -469,7 +514,7 @@ return obj
     fn output_variant(&mut self, name: &str, variant: &VariantFormat) -> Result<()> {
         use VariantFormat::*;
         self.output_comment(name)?;
-        let name = common::lowercase_first_letter(name);
+        let name = self.quote_variant(name);
         match variant {
             Unit => {
                 writeln!(self.out, "case {}", name)?;
@@ -494,7 +539,13 @@ return obj
                         name,
                         fields
                             .iter()
-                            .map(|f| format!("{}: {}", f.name, self.quote_type(&f.value)))
+                            .map(|f| {
+                                format!(
+                                    "{}: {}",
+                                    self.quote_field(&f.name),
+                                    self.quote_type(&f.value)
+                                )
+                            })
                             .collect::<Vec<_>>()
                             .join(", ")
                     )?;
@@ -505,7 +556,7 @@ return obj
         Ok(())
     }
 
-    fn variant_fields(variant: &VariantFormat) -> Vec<Named<Format>> {
+    fn variant_fields(&self, variant: &VariantFormat) -> Vec<Named<Format>> {
         use VariantFormat::*;
         match variant {
             Unit => Vec::new(),
@@ -522,23 +573,44 @@ return obj
                     value: f,
                 })
                 .collect(),
-            Struct(fields) => fields.clone(),
+            Struct(fields) => fields
+                .iter()
+                .map(|f| Named {
+                    name: self.quote_field(&f.name),
+                    value: f.value.clone(),
+                })
+                .collect(),
             Variable(_) => panic!("incorrect value"),
         }
     }
 
+    /// Protocols of a generated struct or enum. Generated types have value semantics, so they
+    /// are `Sendable` unless they hold external types.
+    fn quote_conformances(&self, name: &str) -> &'static str {
+        if self.sendable_types.contains(name) {
+            "Hashable, Sendable"
+        } else {
+            "Hashable"
+        }
+    }
+
     fn output_struct_container(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
         // Struct
         writeln!(self.out)?;
         self.output_comment(name)?;
-        writeln!(self.out, "public struct {}: Hashable {{", name)?;
+        writeln!(
+            self.out,
+            "public struct {}: {} {{",
+            name,
+            self.quote_conformances(name)
+        )?;
         self.enter_class(name);
         for field in fields {
             self.output_comment(&field.name)?;
             writeln!(
                 self.out,
                 "@Indirect public var {}: {}",
-                field.name,
+                self.quote_field(&field.name),
                 self.quote_type(&field.value)
             )?;
         }
@@ -548,13 +620,17 @@ return obj
             "\npublic init({}) {{",
             fields
                 .iter()
-                .map(|f| format!("{}: {}", &f.name, self.quote_type(&f.value)))
+                .map(|f| format!(
+                    "{}: {}",
+                    self.quote_field(&f.name),
+                    self.quote_type(&f.value)
+                ))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
         self.out.indent();
         for field in fields {
-            writeln!(self.out, "self.{0} = {0}", &field.name)?;
+            writeln!(self.out, "self.{0} = {0}", self.quote_field(&field.name))?;
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
@@ -570,7 +646,10 @@ return obj
                 writeln!(
                     self.out,
                     "{}",
-                    self.quote_serialize_value(&format!("self.{}", &field.name), &field.value)
+                    self.quote_serialize_value(
+                        &format!("self.{}", self.quote_field(&field.name)),
+                        &field.value
+                    )
                 )?;
             }
             writeln!(self.out, "try serializer.decrease_container_depth()")?;
@@ -594,7 +673,7 @@ return obj
                 writeln!(
                     self.out,
                     "let {} = {}",
-                    field.name,
+                    self.quote_field(&field.name),
                     self.quote_deserialize(&field.value)
                 )?;
             }
@@ -605,7 +684,7 @@ return obj
                 name,
                 fields
                     .iter()
-                    .map(|f| format!("{0}: {0}", &f.name))
+                    .map(|f| format!("{0}: {0}", self.quote_field(&f.name)))
                     .collect::<Vec<_>>()
                     .join(", ")
             )?;
@@ -664,9 +743,39 @@ public static func {1}Deserialize(input: [UInt8]) throws -> {0} {{
         name: &str,
         variants: &BTreeMap<u32, Named<VariantFormat>>,
     ) -> Result<()> {
+        // Tuple variants with configured field names are labeled like struct variants.
+        let variants = &variants
+            .iter()
+            .map(|(index, variant)| {
+                let names = common::tuple_field_names(self.generator.config, name, &variant.name);
+                let value = match (&variant.value, names) {
+                    (VariantFormat::Tuple(formats), Some(names)) => VariantFormat::Struct(
+                        names
+                            .iter()
+                            .zip(formats)
+                            .map(|(name, format)| Named {
+                                name: name.clone(),
+                                value: format.clone(),
+                            })
+                            .collect(),
+                    ),
+                    (value, _) => value.clone(),
+                };
+                let variant = Named {
+                    name: variant.name.clone(),
+                    value,
+                };
+                (*index, variant)
+            })
+            .collect::<BTreeMap<_, _>>();
         writeln!(self.out)?;
         self.output_comment(name)?;
-        writeln!(self.out, "indirect public enum {}: Hashable {{", name)?;
+        writeln!(
+            self.out,
+            "indirect public enum {}: {} {{",
+            name,
+            self.quote_conformances(name)
+        )?;
         self.current_namespace.push(name.to_string());
         self.out.indent();
         for variant in variants.values() {
@@ -683,8 +792,8 @@ public static func {1}Deserialize(input: [UInt8]) throws -> {0} {{
             writeln!(self.out, "try serializer.increase_container_depth()")?;
             writeln!(self.out, "switch self {{")?;
             for (index, variant) in variants {
-                let fields = Self::variant_fields(&variant.value);
-                let formatted_variant_name = common::lowercase_first_letter(&variant.name);
+                let fields = self.variant_fields(&variant.value);
+                let formatted_variant_name = self.quote_variant(&variant.name);
                 if fields.is_empty() {
                     writeln!(self.out, "case .{}:", formatted_variant_name)?;
                 } else {
@@ -741,8 +850,8 @@ switch index {{"#,
             for (index, variant) in variants {
                 writeln!(self.out, "case {}:", index)?;
                 self.out.indent();
-                let formatted_variant_name = common::lowercase_first_letter(&variant.name);
-                let fields = Self::variant_fields(&variant.value);
+                let formatted_variant_name = self.quote_variant(&variant.name);
+                let fields = self.variant_fields(&variant.value);
                 if fields.is_empty() {
                     writeln!(self.out, "try deserializer.decrease_container_depth()")?;
                     writeln!(self.out, "return .{}", formatted_variant_name)?;
@@ -831,14 +940,65 @@ switch index {{"#,
     }
 }
 
+/// Containers of the registry that do not depend on external types, whose conformance to
+/// `Sendable` is unknown.
+fn get_sendable_types(
+    registry: &Registry,
+    external_qualified_names: &HashMap<String, String>,
+) -> BTreeSet<String> {
+    let mut sendable_types = registry
+        .keys()
+        .filter(|name| !external_qualified_names.contains_key(*name))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    loop {
+        let other_types = registry
+            .iter()
+            .filter(|(name, format)| {
+                if !sendable_types.contains(*name) {
+                    return false;
+                }
+                let mut is_sendable = true;
+                format
+                    .visit(&mut |format| {
+                        if let Format::TypeName(name) = format {
+                            is_sendable &= sendable_types.contains(name);
+                        }
+                        Ok(())
+                    })
+                    .expect("formats should not contain variables");
+                !is_sendable
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        if other_types.is_empty() {
+            return sendable_types;
+        }
+        for name in other_types {
+            sendable_types.remove(&name);
+        }
+    }
+}
+
 /// Installer for generated source files in Swift.
 pub struct Installer {
     install_dir: PathBuf,
+    clean: bool,
 }
 
 impl Installer {
     pub fn new(install_dir: PathBuf) -> Self {
-        Installer { install_dir }
+        Installer {
+            install_dir,
+            clean: false,
+        }
+    }
+
+    /// Whether `install_module` should delete the generated files of a previous run that are
+    /// no longer produced. Files without the marker of generated files are never deleted.
+    pub fn with_clean(mut self, clean: bool) -> Self {
+        self.clean = clean;
+        self
     }
 
     fn install_runtime(
@@ -867,9 +1027,14 @@ impl crate::SourceInstaller for Installer {
         let dir_path = self.install_dir.join("Sources").join(&config.module_name);
         std::fs::create_dir_all(&dir_path)?;
         let source_path = dir_path.join(format!("{}.swift", config.module_name.to_camel_case()));
-        let mut file = std::fs::File::create(source_path)?;
-        let generator = CodeGenerator::new(config);
-        generator.output(&mut file, registry)?;
+        let generator = CodeGenerator::try_new(config)?;
+        let mut content = Vec::new();
+        generator.output(&mut content, registry)?;
+        common::create_generated_file(&source_path)?.write_all(&content)?;
+        config.format_source(Language::Swift, &source_path)?;
+        if self.clean {
+            common::remove_stale_generated_files(&dir_path, &BTreeSet::from([source_path]))?;
+        }
         Ok(())
     }
 
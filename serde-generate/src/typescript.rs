@@ -4,7 +4,8 @@
 use crate::{
     common,
     indent::{IndentConfig, IndentedWriter},
-    CodeGeneratorConfig,
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
 };
 use heck::CamelCase;
 use include_dir::include_dir as include_directory;
@@ -15,6 +16,17 @@ use std::{
     path::PathBuf,
 };
 
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: false,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: true,
+    bcs_length_prefix: false,
+};
+
 /// Main configuration object for code-generation in TypeScript, powered by
 /// the Deno runtime.
 pub struct CodeGenerator<'a> {
@@ -25,6 +37,43 @@ pub struct CodeGenerator<'a> {
     external_qualified_names: HashMap<String, String>,
     /// vector of namespaces to import
     namespaces_to_import: Vec<String>,
+    /// Module exporting the functions `__serde_encode_<Type>` and `__serde_decode_<Type>`
+    /// (see `with_wasm_bridge`).
+    wasm_bridge: Option<String>,
+}
+
+/// How `serde-wasm-bindgen` represents the content of a container or a variant.
+#[derive(Clone, Copy)]
+enum WasmShape {
+    /// `null` (or no content for a variant).
+    Unit,
+    /// The value of the single field.
+    NewType,
+    /// An array of field values.
+    Tuple,
+    /// An object indexed by field names.
+    Struct,
+}
+
+impl WasmShape {
+    fn of_container(format: &ContainerFormat) -> Self {
+        match format {
+            ContainerFormat::UnitStruct => WasmShape::Unit,
+            ContainerFormat::NewTypeStruct(_) => WasmShape::NewType,
+            ContainerFormat::TupleStruct(_) => WasmShape::Tuple,
+            ContainerFormat::Struct(_) | ContainerFormat::Enum(_) => WasmShape::Struct,
+        }
+    }
+
+    fn of_variant(format: &VariantFormat) -> Self {
+        match format {
+            VariantFormat::Unit => WasmShape::Unit,
+            VariantFormat::NewType(_) => WasmShape::NewType,
+            VariantFormat::Tuple(_) => WasmShape::Tuple,
+            VariantFormat::Struct(_) => WasmShape::Struct,
+            VariantFormat::Variable(_) => panic!("incorrect value"),
+        }
+    }
 }
 
 /// Shared state for the code generation of a TypeScript source file.
@@ -33,14 +82,21 @@ struct TypeScriptEmitter<'a, T> {
     out: IndentedWriter<T>,
     /// Generator.
     generator: &'a CodeGenerator<'a>,
+    /// Current namespace (e.g. vec!["my_module", "MyEnum", "MyVariant"])
+    current_namespace: Vec<String>,
 }
 
 impl<'a> CodeGenerator<'a> {
     /// Create a TypeScript code generator for the given config.
+    #[deprecated(note = "Use `try_new`, which returns an error for unsupported configs")]
     pub fn new(config: &'a CodeGeneratorConfig) -> Self {
-        if config.c_style_enums {
-            panic!("TypeScript does not support generating c-style enums");
-        }
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create a TypeScript code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::TypeScript, config)?;
         let mut external_qualified_names = HashMap::new();
         for (namespace, names) in &config.external_definitions {
             for name in names {
@@ -50,7 +106,7 @@ impl<'a> CodeGenerator<'a> {
                 );
             }
         }
-        Self {
+        Ok(Self {
             config,
             external_qualified_names,
             namespaces_to_import: config
@@ -58,14 +114,43 @@ impl<'a> CodeGenerator<'a> {
                 .keys()
                 .map(|k| k.to_string())
                 .collect::<Vec<_>>(),
-        }
+            wasm_bridge: None,
+        })
+    }
+
+    /// Call into a WebAssembly module for encoding and decoding, e.g. a module built with
+    /// `wasm-pack` from Rust definitions generated with `rust::CodeGenerator::with_wasm_exports`.
+    /// Classes gain the methods `encodeWasm` and `decodeWasm`, which convert values to and from
+    /// the JavaScript representation of `serde-wasm-bindgen` (see `toWasmValue` and
+    /// `fromWasmValue`) and call the functions `__serde_encode_<Type>` and
+    /// `__serde_decode_<Type>` of the module `module_specifier`. The TypeScript implementation
+    /// of the encodings remains available.
+    pub fn with_wasm_bridge(mut self, module_specifier: String) -> Self {
+        self.wasm_bridge = Some(module_specifier);
+        self
+    }
+
+    /// Report the parts of the registry that cannot be generated in TypeScript, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::TypeScript, registry)
     }
 
     /// Output class definitions for `registry` in a single source file.
     pub fn output(&self, out: &mut dyn Write, registry: &Registry) -> Result<()> {
+        let registry = validation::check_registry(self.config, Language::TypeScript, registry)?;
+        let registry: &Registry = &registry;
+        let current_namespace = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect();
         let mut emitter = TypeScriptEmitter {
-            out: IndentedWriter::new(out, IndentConfig::Space(2)),
+            out: IndentedWriter::new(out, IndentConfig::Space(2))
+                .with_output_indent(self.config.indent),
             generator: self,
+            current_namespace,
         };
 
         emitter.output_preamble()?;
@@ -74,7 +159,7 @@ impl<'a> CodeGenerator<'a> {
             emitter.output_container(name, format)?;
         }
 
-        if self.config.serialization {
+        if self.config.has_serde_methods() {
             emitter.output_helpers(registry)?;
         }
 
@@ -92,9 +177,34 @@ where
             r#"
 import {{ Serializer, Deserializer }} from '../serde/mod.ts';
 import {{ BcsSerializer, BcsDeserializer }} from '../bcs/mod.ts';
-import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64, int128, uint8, uint16, uint32, uint64, uint128, float32, float64, char, str, bytes }} from '../serde/mod.ts';
+import {{ Optional, Seq, Tuple, ListTuple, ValueMap, unit, bool, int8, int16, int32, int64, int128, uint8, uint16, uint32, uint64, uint128, float32, float64, char, str, bytes }} from '../serde/mod.ts';
 "#,
         )?;
+        if self.generator.config.runtime_schema {
+            writeln!(self.out, "import {{ Schema }} from '../serde/mod.ts';\n")?;
+        }
+        if self.generator.config.serialization && self.generator.config.size_estimators {
+            writeln!(
+                self.out,
+                "import {{ SizeEstimator }} from '../serde/mod.ts';"
+            )?;
+            for encoding in self.size_estimator_encodings() {
+                writeln!(
+                    self.out,
+                    "import {{ {1}SizeEstimator }} from '../{0}/mod.ts';",
+                    encoding.name(),
+                    encoding.name().to_camel_case()
+                )?;
+            }
+            writeln!(self.out)?;
+        }
+        if let Some(module_specifier) = &self.generator.wasm_bridge {
+            writeln!(
+                self.out,
+                "import * as wasmBridge from '{}';\n",
+                module_specifier
+            )?;
+        }
         for namespace in self.generator.namespaces_to_import.iter() {
             writeln!(
                 self.out,
@@ -107,6 +217,10 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
         Ok(())
     }
 
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::TypeScript, name)
+    }
+
     fn quote_qualified_name(&self, name: &str) -> String {
         self.generator
             .external_qualified_names
@@ -115,11 +229,24 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
             .unwrap_or_else(|| name.to_string())
     }
 
+    fn get_comment(&self, name: &str) -> Option<&String> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        self.generator.config.comments.get(&path)
+    }
+
+    fn quote_comment(doc: &str) -> String {
+        let text = textwrap::indent(doc, " * ").replace("\n\n", "\n *\n");
+        format!("/**\n{} */", text)
+    }
+
     fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
-        let path = vec![name.to_string()];
-        if let Some(doc) = self.generator.config.comments.get(&path) {
-            let text = textwrap::indent(doc, " * ").replace("\n\n", "\n *\n");
-            writeln!(self.out, "/**\n{} */", text)?;
+        // Types and variants may also be documented by their name alone.
+        let doc = self
+            .get_comment(name)
+            .or_else(|| self.generator.config.comments.get(&vec![name.to_string()]));
+        if let Some(doc) = doc {
+            writeln!(self.out, "{}", Self::quote_comment(doc))?;
         }
         Ok(())
     }
@@ -148,9 +275,12 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
 
             Option(format) => format!("Optional<{}>", self.quote_type(format)),
             Seq(format) => format!("Seq<{}>", self.quote_type(format)),
-            Map { key, value } => {
-                format!("Map<{},{}>", self.quote_type(key), self.quote_type(value))
-            }
+            Map { key, value } => format!(
+                "{}<{},{}>",
+                Self::quote_map_class(key),
+                self.quote_type(key),
+                self.quote_type(value)
+            ),
             Tuple(formats) => format!("Tuple<[{}]>", self.quote_types(formats, ", ")),
             TupleArray {
                 content,
@@ -160,6 +290,17 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
         }
     }
 
+    /// Maps keyed by values without a primitive JavaScript representation (e.g. enums or
+    /// tuples) are compared structurally so that lookups work after deserialization.
+    fn quote_map_class(key: &Format) -> &'static str {
+        use Format::*;
+        match key {
+            Unit | Bool | I8 | I16 | I32 | I64 | I128 | U8 | U16 | U32 | U64 | U128 | F32 | F64
+            | Char | Str => "Map",
+            _ => "ValueMap",
+        }
+    }
+
     fn quote_types(&self, formats: &[Format], sep: &str) -> String {
         formats
             .iter()
@@ -184,8 +325,15 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
         writeln!(self.out, "export class Helpers {{")?;
         self.out.indent();
         for (mangled_name, subtype) in &subtypes {
-            self.output_serialization_helper(mangled_name, subtype)?;
-            self.output_deserialization_helper(mangled_name, subtype)?;
+            if self.generator.config.serialization {
+                self.output_serialization_helper(mangled_name, subtype)?;
+            }
+            if self.generator.config.has_deserialization() {
+                self.output_deserialization_helper(mangled_name, subtype)?;
+            }
+            if self.generator.config.serialization && self.generator.config.size_estimators {
+                self.output_size_estimation_helper(mangled_name, subtype)?;
+            }
         }
         self.out.unindent();
         writeln!(self.out, "}}")?;
@@ -232,6 +380,30 @@ import {{ Optional, Seq, Tuple, ListTuple, unit, bool, int8, int16, int32, int64
         }
     }
 
+    fn quote_size_value(&self, value: &str, format: &Format, use_this: bool) -> String {
+        use Format::*;
+        let this_str = if use_this { "this." } else { "" };
+
+        match format {
+            TypeName(_) => format!("{}{}.estimatedSize(estimator)", this_str, value),
+            Unit => "0".to_string(),
+            Bool | I8 | U8 => "1".to_string(),
+            I16 | U16 => "2".to_string(),
+            I32 | U32 | F32 => "4".to_string(),
+            I64 | U64 | F64 => "8".to_string(),
+            I128 | U128 => "16".to_string(),
+            Char => format!("estimator.charSize({}{})", this_str, value),
+            Str => format!("estimator.strSize({}{})", this_str, value),
+            Bytes => format!("estimator.bytesSize({}{})", this_str, value),
+            _ => format!(
+                "Helpers.estimatedSize{}({}{}, estimator)",
+                common::mangle_type(format).to_camel_case(),
+                this_str,
+                value
+            ),
+        }
+    }
+
     fn quote_deserialize(&self, format: &Format) -> String {
         use Format::*;
         match format {
@@ -354,6 +526,118 @@ value.forEach((item) =>{{
         writeln!(self.out, "}}\n")
     }
 
+    fn output_size_estimation_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "static estimatedSize{}(value: {}, estimator: SizeEstimator): number {{",
+            name.to_camel_case(),
+            self.quote_type(format0)
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (value) {{
+    return 1 + {};
+}} else {{
+    return 1;
+}}
+"#,
+                    self.quote_size_value("value", format, false)
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+let size = estimator.lenSize(value.length);
+value.forEach((item: {}) => {{
+    size += {};
+}});
+return size;
+"#,
+                    self.quote_type(format),
+                    self.quote_size_value("item", format, false)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+let size = estimator.lenSize(value.size);
+for (const [k, v] of value.entries()) {{
+  size += {};
+  size += {};
+}}
+return size;
+"#,
+                    self.quote_size_value("k", key, false),
+                    self.quote_size_value("v", value, false)
+                )?;
+            }
+
+            Tuple(formats) => {
+                writeln!(self.out)?;
+                writeln!(self.out, "let size = 0;")?;
+                for (index, format) in formats.iter().enumerate() {
+                    let expr = format!("value[{}]", index);
+                    writeln!(
+                        self.out,
+                        "size += {};",
+                        self.quote_size_value(&expr, format, false)
+                    )?;
+                }
+                writeln!(self.out, "return size;")?;
+            }
+
+            TupleArray { content, .. } => {
+                write!(
+                    self.out,
+                    r#"
+let size = 0;
+value.forEach((item) =>{{
+    size += {};
+}});
+return size;
+"#,
+                    self.quote_size_value("item[0]", content, false)
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
+    }
+
+    /// Encodings with a TypeScript runtime.
+    fn size_estimator_encodings(&self) -> Vec<Encoding> {
+        self.generator
+            .config
+            .encodings
+            .iter()
+            .copied()
+            .filter(|encoding| matches!(encoding, Encoding::Bincode | Encoding::Bcs))
+            .collect()
+    }
+
+    fn output_estimated_size_for_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        writeln!(
+            self.out,
+            r#"public estimated{0}Size(): number {{
+  return this.estimatedSize(new {0}SizeEstimator());
+}}
+"#,
+            encoding.name().to_camel_case()
+        )
+    }
+
     fn output_deserialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
         use Format::*;
 
@@ -401,7 +685,7 @@ return list;
                     self.out,
                     r#"
 const length = deserializer.deserializeLen();
-const obj = new Map<{0}, {1}>();
+const obj = new {4}<{0}, {1}>();
 let previousKeyStart = 0;
 let previousKeyEnd = 0;
 for (let i = 0; i < length; i++) {{
@@ -424,6 +708,7 @@ return obj;
                     self.quote_type(value),
                     self.quote_deserialize(key),
                     self.quote_deserialize(value),
+                    Self::quote_map_class(key),
                 )?;
             }
 
@@ -478,18 +763,31 @@ return list;
                 name: "value".to_string(),
                 value: format.as_ref().clone(),
             }],
-            Tuple(formats) => formats
-                .iter()
-                .enumerate()
-                .map(|(i, f)| Named {
-                    name: format!("field{}", i),
-                    value: f.clone(),
-                })
-                .collect(),
+            Tuple(formats) => {
+                let names = common::tuple_field_names(self.generator.config, base, name);
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Named {
+                        name: match names {
+                            Some(names) => names[i].clone(),
+                            None => format!("field{}", i),
+                        },
+                        value: f.clone(),
+                    })
+                    .collect()
+            }
             Struct(fields) => fields.clone(),
             Variable(_) => panic!("incorrect value"),
         };
-        self.output_struct_or_variant_container(Some(base), Some(index), name, &fields)
+        self.output_struct_or_variant_container(
+            Some(base),
+            Some(index),
+            name,
+            &fields,
+            None,
+            WasmShape::of_variant(variant),
+        )
     }
 
     fn output_variants(
@@ -509,6 +807,8 @@ return list;
         variant_index: Option<u32>,
         name: &str,
         fields: &[Named<Format>],
+        schema: Option<&ContainerFormat>,
+        shape: WasmShape,
     ) -> Result<()> {
         let mut variant_base_name = String::new();
 
@@ -526,19 +826,51 @@ return list;
             self.output_comment(name)?;
             writeln!(self.out, "export class {} {{", name)?;
         }
+        if let Some(format) = schema {
+            self.output_schema(format)?;
+        }
         if !fields.is_empty() {
             writeln!(self.out)?;
         }
         // Constructor.
-        writeln!(
-            self.out,
-            "constructor ({}) {{",
-            fields
-                .iter()
-                .map(|f| { format!("public {}: {}", &f.name, self.quote_type(&f.value)) })
-                .collect::<Vec<_>>()
-                .join(", ")
-        )?;
+        self.current_namespace.push(name.to_string());
+        let field_comments = fields
+            .iter()
+            .map(|f| self.get_comment(&f.name).cloned())
+            .collect::<Vec<_>>();
+        self.current_namespace.pop();
+        let parameters = fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "public {}: {}",
+                    self.quote_field(&f.name),
+                    self.quote_type(&f.value)
+                )
+            })
+            .collect::<Vec<_>>();
+        if field_comments.iter().all(Option::is_none) {
+            writeln!(self.out, "constructor ({}) {{", parameters.join(", "))?;
+        } else {
+            // Document parameter properties with one parameter per line.
+            writeln!(self.out, "constructor (")?;
+            self.out.indent();
+            for (index, (parameter, comment)) in
+                parameters.iter().zip(field_comments.iter()).enumerate()
+            {
+                if let Some(doc) = comment {
+                    writeln!(self.out, "{}", Self::quote_comment(doc))?;
+                }
+                let separator = if index + 1 < parameters.len() {
+                    ","
+                } else {
+                    ""
+                };
+                writeln!(self.out, "{}{}", parameter, separator)?;
+            }
+            self.out.unindent();
+            writeln!(self.out, ") {{")?;
+        }
         if let Some(_base) = variant_base {
             self.out.indent();
             writeln!(self.out, "super();")?;
@@ -555,18 +887,53 @@ return list;
             if let Some(index) = variant_index {
                 writeln!(self.out, "serializer.serializeVariantIndex({});", index)?;
             }
+            if variant_index.is_none() && common::is_self_recursive_chain(name, fields) {
+                self.output_chain_serialization(name, fields)?;
+            } else {
+                for field in fields {
+                    writeln!(
+                        self.out,
+                        "{}",
+                        self.quote_serialize_value(
+                            &self.quote_field(&field.name),
+                            &field.value,
+                            true
+                        )
+                    )?;
+                }
+            }
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
+        // Size estimation
+        if self.generator.config.serialization && self.generator.config.size_estimators {
+            writeln!(
+                self.out,
+                "public estimatedSize(estimator: SizeEstimator): number {{",
+            )?;
+            self.out.indent();
+            writeln!(self.out, "let size = 0;")?;
+            if let Some(index) = variant_index {
+                writeln!(self.out, "size += estimator.variantIndexSize({});", index)?;
+            }
             for field in fields {
                 writeln!(
                     self.out,
-                    "{}",
-                    self.quote_serialize_value(&field.name, &field.value, true)
+                    "size += {};",
+                    self.quote_size_value(&self.quote_field(&field.name), &field.value, true)
                 )?;
             }
+            writeln!(self.out, "return size;")?;
             self.out.unindent();
             writeln!(self.out, "}}\n")?;
+            if variant_index.is_none() {
+                for encoding in self.size_estimator_encodings() {
+                    self.output_estimated_size_for_encoding(encoding)?;
+                }
+            }
         }
         // Deserialize (struct) or Load (variant)
-        if self.generator.config.serialization {
+        if self.generator.config.has_deserialization() {
             if variant_index.is_none() {
                 writeln!(
                     self.out,
@@ -581,29 +948,226 @@ return list;
                 )?;
             }
             self.out.indent();
-            for field in fields {
+            if variant_index.is_none() && common::is_self_recursive_chain(name, fields) {
+                self.output_chain_deserialization(name, fields)?;
+            } else {
+                for field in fields {
+                    writeln!(
+                        self.out,
+                        "const {} = {};",
+                        self.quote_field(&field.name),
+                        self.quote_deserialize(&field.value)
+                    )?;
+                }
                 writeln!(
                     self.out,
-                    "const {} = {};",
-                    field.name,
-                    self.quote_deserialize(&field.value)
+                    r#"return new {0}{1}({2});"#,
+                    variant_base_name,
+                    name,
+                    fields
+                        .iter()
+                        .map(|f| self.quote_field(&f.name))
+                        .collect::<Vec<_>>()
+                        .join(",")
                 )?;
             }
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
+        if self.generator.config.has_serde_methods()
+            && variant_index.is_none()
+            && self.generator.config.elision_containers.contains(name)
+        {
+            self.output_compact_methods(name, fields)?;
+        }
+        if self.generator.wasm_bridge.is_some() {
+            self.output_wasm_value_methods(variant_base, name, fields, shape)?;
+            if variant_base.is_none() {
+                self.output_wasm_bridge_methods(name)?;
+            }
+        }
+        writeln!(self.out, "}}")
+    }
+
+    /// Write the body of `serialize` for a chain of values (see `common::is_self_recursive_chain`):
+    /// the fields of each value, then the option tag announcing the next one.
+    fn output_chain_serialization(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let (last, fields) = fields.split_last().unwrap();
+        writeln!(self.out, "let node: {} = this;\nwhile (true) {{", name)?;
+        self.out.indent();
+        for field in fields {
+            let value = format!("node.{}", self.quote_field(&field.name));
+            writeln!(
+                self.out,
+                "{}",
+                self.quote_serialize_value(&value, &field.value, false)
+            )?;
+        }
+        writeln!(
+            self.out,
+            r#"const next = node.{};
+if (next === null) {{
+  serializer.serializeOptionTag(false);
+  return;
+}}
+serializer.serializeOptionTag(true);
+node = next;"#,
+            self.quote_field(&last.name)
+        )?;
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    /// Write the body of `deserialize` for a chain of values (see
+    /// `common::is_self_recursive_chain`): the values are read in a loop, then linked together.
+    fn output_chain_deserialization(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let (last, fields) = fields.split_last().unwrap();
+        writeln!(self.out, "const nodes: {}[] = [];\ndo {{", name)?;
+        self.out.indent();
+        for field in fields {
+            writeln!(
+                self.out,
+                "const {} = {};",
+                self.quote_field(&field.name),
+                self.quote_deserialize(&field.value)
+            )?;
+        }
+        let mut arguments = fields
+            .iter()
+            .map(|f| self.quote_field(&f.name))
+            .collect::<Vec<_>>();
+        arguments.push("null".to_string());
+        writeln!(
+            self.out,
+            "nodes.push(new {}({}));",
+            name,
+            arguments.join(", ")
+        )?;
+        self.out.unindent();
+        writeln!(
+            self.out,
+            r#"}} while (deserializer.deserializeOptionTag());
+for (let i = 0; i + 1 < nodes.length; i++) {{
+  nodes[i].{} = nodes[i + 1];
+}}
+return nodes[0];"#,
+            self.quote_field(&last.name)
+        )
+    }
+
+    /// Write the methods of the compact encoding (see
+    /// `CodeGeneratorConfig::with_elision_containers`).
+    fn output_compact_methods(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        let size = common::presence_bitmap_size(fields);
+        let bits = common::presence_bits(fields);
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                r#"public compactSerialize(): Uint8Array {{
+  const bitmap = new Uint8Array({});
+  const serializer = new BcsSerializer();"#,
+                size
+            )?;
+            self.out.indent();
+            for (field, bit) in fields.iter().zip(&bits) {
+                let identifier = self.quote_field(&field.name);
+                let encode = self.quote_serialize_value(&identifier, &field.value, true);
+                match bit {
+                    None => writeln!(self.out, "{}", encode)?,
+                    Some(bit) => writeln!(
+                        self.out,
+                        "if ({}) {{\n  bitmap[{}] |= 1 << {};\n  {}\n}}",
+                        Self::quote_is_present(&format!("this.{}", identifier), &field.value),
+                        bit / 8,
+                        bit % 8,
+                        encode
+                    )?,
+                }
+            }
+            writeln!(
+                self.out,
+                r#"const bytes = serializer.getBytes();
+const result = new Uint8Array(bitmap.length + bytes.length);
+result.set(bitmap);
+result.set(bytes, bitmap.length);
+return result;"#
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}}\n")?;
+        }
+        if self.generator.config.has_deserialization() {
+            writeln!(
+                self.out,
+                r#"static compactDeserialize(input: Uint8Array): {0} {{
+  if (input.length < {1}) {{
+    throw new Error("Missing presence bitmap");
+  }}
+  const deserializer = new BcsDeserializer(input.subarray({1}));"#,
+                name, size
+            )?;
+            self.out.indent();
+            for (field, bit) in fields.iter().zip(&bits) {
+                let identifier = self.quote_field(&field.name);
+                let decode = self.quote_deserialize(&field.value);
+                match bit {
+                    None => writeln!(self.out, "const {} = {};", identifier, decode)?,
+                    Some(bit) => writeln!(
+                        self.out,
+                        "const {} = (input[{}] & (1 << {})) !== 0 ? {} : {};",
+                        identifier,
+                        bit / 8,
+                        bit % 8,
+                        decode,
+                        self.quote_default_value(&field.value)
+                    )?,
+                }
+            }
             writeln!(
                 self.out,
-                r#"return new {0}{1}({2});"#,
-                variant_base_name,
+                r#"if (deserializer.getBufferOffset() !== input.length - {}) {{
+  throw new Error("Some input bytes were not read");
+}}
+return new {}({});"#,
+                size,
                 name,
                 fields
                     .iter()
-                    .map(|f| f.name.to_string())
+                    .map(|f| self.quote_field(&f.name))
                     .collect::<Vec<_>>()
-                    .join(",")
+                    .join(", ")
             )?;
             self.out.unindent();
             writeln!(self.out, "}}\n")?;
         }
-        writeln!(self.out, "}}")
+        Ok(())
+    }
+
+    /// Condition under which an elidable field is not at its default value.
+    fn quote_is_present(value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Bool => value.to_string(),
+            Str | Bytes | Seq(_) => format!("{}.length !== 0", value),
+            Map { .. } => format!("{}.size !== 0", value),
+            Option(_) => format!("{} !== null", value),
+            I64 | I128 | U64 | U128 => format!("{} !== BigInt(0)", value),
+            _ => format!("{} !== 0", value),
+        }
+    }
+
+    /// Default value of an elidable field.
+    fn quote_default_value(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            Bool => "false".into(),
+            Str => "\"\"".into(),
+            Bytes => "new Uint8Array()".into(),
+            Seq(_) => "[]".into(),
+            Map { .. } => format!("new {}()", self.quote_type(format)),
+            Option(_) => "null".into(),
+            I64 | I128 | U64 | U128 => "BigInt(0)".into(),
+            _ => "0".into(),
+        }
     }
 
     fn output_enum_container(
@@ -613,11 +1177,23 @@ return list;
     ) -> Result<()> {
         self.output_comment(name)?;
         writeln!(self.out, "export abstract class {} {{", name)?;
+        self.output_schema(&ContainerFormat::Enum(variants.clone()))?;
         if self.generator.config.serialization {
             writeln!(
                 self.out,
                 "abstract serialize(serializer: Serializer): void;\n"
             )?;
+            if self.generator.config.size_estimators {
+                writeln!(
+                    self.out,
+                    "abstract estimatedSize(estimator: SizeEstimator): number;\n"
+                )?;
+                for encoding in self.size_estimator_encodings() {
+                    self.output_estimated_size_for_encoding(encoding)?;
+                }
+            }
+        }
+        if self.generator.config.has_deserialization() {
             write!(
                 self.out,
                 "static deserialize(deserializer: Deserializer): {} {{",
@@ -648,9 +1224,68 @@ switch (index) {{"#,
             self.out.unindent();
             writeln!(self.out, "}}")?;
         }
+        if self.generator.wasm_bridge.is_some() {
+            self.output_wasm_enum_methods(name, variants)?;
+            self.output_wasm_bridge_methods(name)?;
+        }
         writeln!(self.out, "}}\n")?;
+        self.current_namespace.push(name.to_string());
         self.output_variants(name, variants)?;
-        Ok(())
+        self.current_namespace.pop();
+        self.output_variant_guards(name, variants)
+    }
+
+    fn output_variant_guards(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        for variant in variants.values() {
+            writeln!(
+                self.out,
+                "\nexport function is{0}Variant{1}(value: {0}): value is {0}Variant{1} {{",
+                name, variant.name
+            )?;
+            self.out.indent();
+            writeln!(
+                self.out,
+                "return value instanceof {}Variant{};",
+                name, variant.name
+            )?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+        }
+
+        writeln!(
+            self.out,
+            "\nexport function match{0}<R>(value: {0}, handlers: {{",
+            name
+        )?;
+        self.out.indent();
+        for variant in variants.values() {
+            writeln!(
+                self.out,
+                "{1}: (value: {0}Variant{1}) => R,",
+                name, variant.name
+            )?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}): R {{")?;
+        self.out.indent();
+        for variant in variants.values() {
+            writeln!(
+                self.out,
+                "if (value instanceof {0}Variant{1}) return handlers.{1}(value);",
+                name, variant.name
+            )?;
+        }
+        writeln!(
+            self.out,
+            "throw new Error(\"Unknown variant of {}\");",
+            name
+        )?;
+        self.out.unindent();
+        writeln!(self.out, "}}\n")
     }
 
     fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
@@ -675,7 +1310,284 @@ switch (index) {{"#,
                 return Ok(());
             }
         };
-        self.output_struct_or_variant_container(None, None, name, &fields)
+        self.output_struct_or_variant_container(
+            None,
+            None,
+            name,
+            &fields,
+            Some(format),
+            WasmShape::of_container(format),
+        )
+    }
+
+    /// Convert `value` into its JavaScript representation in `serde-wasm-bindgen`, using
+    /// variables suffixed with `depth` in nested functions.
+    fn quote_to_wasm_value(value: &str, format: &Format, depth: usize) -> String {
+        use Format::*;
+        match format {
+            TypeName(_) => format!("{}.toWasmValue()", value),
+            Unit => "null".to_string(),
+            Option(format) => format!(
+                "({0} === null ? undefined : {1})",
+                value,
+                Self::quote_to_wasm_value(value, format, depth)
+            ),
+            Seq(format) => format!(
+                "{0}.map((x{1}: any) => {2})",
+                value,
+                depth,
+                Self::quote_to_wasm_value(&format!("x{}", depth), format, depth + 1)
+            ),
+            Map { key, value: format } => format!(
+                "new Map(Array.from({0}.entries(), ([k{1}, v{1}]: [any, any]) => [{2}, {3}]))",
+                value,
+                depth,
+                Self::quote_to_wasm_value(&format!("k{}", depth), key, depth + 1),
+                Self::quote_to_wasm_value(&format!("v{}", depth), format, depth + 1)
+            ),
+            Tuple(formats) => format!(
+                "[{}]",
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(index, format)| Self::quote_to_wasm_value(
+                        &format!("{}[{}]", value, index),
+                        format,
+                        depth
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TupleArray { content, .. } => format!(
+                "{0}.map((x{1}: any) => {2})",
+                value,
+                depth,
+                Self::quote_to_wasm_value(&format!("x{}[0]", depth), content, depth + 1)
+            ),
+            Variable(_) => panic!("unexpected value"),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Convert `value` from its JavaScript representation in `serde-wasm-bindgen`.
+    fn quote_from_wasm_value(&self, value: &str, format: &Format, depth: usize) -> String {
+        use Format::*;
+        match format {
+            TypeName(name) => format!(
+                "{}.fromWasmValue({})",
+                self.quote_qualified_name(name),
+                value
+            ),
+            Unit => "null".to_string(),
+            Option(format) => format!(
+                "({0} === null || {0} === undefined ? null : {1})",
+                value,
+                self.quote_from_wasm_value(value, format, depth)
+            ),
+            Seq(format) => format!(
+                "({0} as any[]).map((x{1}: any) => {2})",
+                value,
+                depth,
+                self.quote_from_wasm_value(&format!("x{}", depth), format, depth + 1)
+            ),
+            Map { key, value: format } => format!(
+                "new {0}<{1}, {2}>(Array.from(({3} as Map<any, any>).entries(), ([k{4}, v{4}]: [any, any]) => [{5}, {6}] as [{1}, {2}]))",
+                Self::quote_map_class(key),
+                self.quote_type(key),
+                self.quote_type(format),
+                value,
+                depth,
+                self.quote_from_wasm_value(&format!("k{}", depth), key, depth + 1),
+                self.quote_from_wasm_value(&format!("v{}", depth), format, depth + 1)
+            ),
+            Tuple(formats) => format!(
+                "[{}] as {}",
+                formats
+                    .iter()
+                    .enumerate()
+                    .map(|(index, format)| self.quote_from_wasm_value(
+                        &format!("{}[{}]", value, index),
+                        format,
+                        depth
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.quote_type(format)
+            ),
+            TupleArray { content, .. } => format!(
+                "({0} as any[]).map((x{1}: any) => [{2}]) as {3}",
+                value,
+                depth,
+                self.quote_from_wasm_value(&format!("x{}", depth), content, depth + 1),
+                self.quote_type(format)
+            ),
+            Variable(_) => panic!("unexpected value"),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Write `toWasmValue` and `fromWasmValue` (or `loadWasmValue` for the content of a
+    /// variant) for a struct or a variant. Variants are represented as in serde's externally
+    /// tagged enums: the variant name, or an object mapping the variant name to its content.
+    fn output_wasm_value_methods(
+        &mut self,
+        variant_base: Option<&str>,
+        name: &str,
+        fields: &[Named<Format>],
+        shape: WasmShape,
+    ) -> Result<()> {
+        let class_name = match variant_base {
+            Some(base) => format!("{}Variant{}", base, name),
+            None => name.to_string(),
+        };
+        if self.generator.config.serialization {
+            let content = match shape {
+                WasmShape::Unit => "null".to_string(),
+                WasmShape::NewType => Self::quote_to_wasm_value(
+                    &format!("this.{}", self.quote_field(&fields[0].name)),
+                    &fields[0].value,
+                    0,
+                ),
+                WasmShape::Tuple => format!(
+                    "[{}]",
+                    fields
+                        .iter()
+                        .map(|field| Self::quote_to_wasm_value(
+                            &format!("this.{}", self.quote_field(&field.name)),
+                            &field.value,
+                            0
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                WasmShape::Struct => format!(
+                    "{{{}}}",
+                    fields
+                        .iter()
+                        .map(|field| format!(
+                            "{:?}: {}",
+                            field.name,
+                            Self::quote_to_wasm_value(
+                                &format!("this.{}", self.quote_field(&field.name)),
+                                &field.value,
+                                0
+                            )
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            let value = match (variant_base, shape) {
+                (Some(_), WasmShape::Unit) => format!("{:?}", name),
+                (Some(_), _) => format!("{{{:?}: {}}}", name, content),
+                (None, _) => content,
+            };
+            writeln!(
+                self.out,
+                "public toWasmValue(): any {{\n  return {};\n}}\n",
+                value
+            )?;
+        }
+        if self.generator.config.has_deserialization() {
+            let arguments = fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let value = match shape {
+                        WasmShape::Unit => unreachable!("no fields"),
+                        WasmShape::NewType => "value".to_string(),
+                        WasmShape::Tuple => format!("value[{}]", index),
+                        WasmShape::Struct => format!("value[{:?}]", field.name),
+                    };
+                    self.quote_from_wasm_value(&value, &field.value, 0)
+                })
+                .collect::<Vec<_>>();
+            writeln!(
+                self.out,
+                "static {}WasmValue(value: any): {} {{\n  return new {}({});\n}}\n",
+                if variant_base.is_some() {
+                    "load"
+                } else {
+                    "from"
+                },
+                class_name,
+                class_name,
+                arguments.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write `toWasmValue` and `fromWasmValue` for an enum, dispatching on the variant name.
+    fn output_wasm_enum_methods(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        if self.generator.config.serialization {
+            writeln!(self.out, "abstract toWasmValue(): any;\n")?;
+        }
+        if self.generator.config.has_deserialization() {
+            writeln!(
+                self.out,
+                r#"static fromWasmValue(value: any): {0} {{
+  const [name, content] = typeof value === "string" ? [value, null] : Object.entries(value)[0];
+  switch (name) {{"#,
+                name
+            )?;
+            for variant in variants.values() {
+                writeln!(
+                    self.out,
+                    "    case {:?}: return {}Variant{}.loadWasmValue(content);",
+                    variant.name, name, variant.name
+                )?;
+            }
+            writeln!(
+                self.out,
+                r#"    default: throw new Error("Unknown variant name for {}: " + name);
+  }}
+}}
+"#,
+                name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write `encodeWasm` and `decodeWasm`, which call the functions of the WebAssembly module.
+    fn output_wasm_bridge_methods(&mut self, name: &str) -> Result<()> {
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                r#"public encodeWasm(): Uint8Array {{
+  return wasmBridge.__serde_encode_{0}(this.toWasmValue());
+}}
+"#,
+                name
+            )?;
+        }
+        if self.generator.config.has_deserialization() {
+            writeln!(
+                self.out,
+                r#"static decodeWasm(input: Uint8Array): {0} {{
+  return {0}.fromWasmValue(wasmBridge.__serde_decode_{0}(input));
+}}
+"#,
+                name
+            )?;
+        }
+        Ok(())
+    }
+
+    fn output_schema(&mut self, format: &ContainerFormat) -> Result<()> {
+        if self.generator.config.runtime_schema {
+            writeln!(
+                self.out,
+                "static readonly SCHEMA: Schema = {};",
+                common::quote_container_schema(format)
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -715,10 +1627,11 @@ impl crate::SourceInstaller for Installer {
         let dir_path = self.install_dir.join(&config.module_name);
         std::fs::create_dir_all(&dir_path)?;
         let source_path = dir_path.join("mod.ts");
-        let mut file = std::fs::File::create(source_path)?;
+        let mut file = std::fs::File::create(&source_path)?;
 
-        let generator = CodeGenerator::new(config);
+        let generator = CodeGenerator::try_new(config)?;
         generator.output(&mut file, registry)?;
+        config.format_source(Language::TypeScript, &source_path)?;
         Ok(())
     }
 
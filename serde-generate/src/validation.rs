@@ -0,0 +1,1215 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{common, CodeGeneratorConfig, ConfigError, Encoding, KeywordEscaping, NonAsciiNames};
+use heck::{CamelCase, MixedCase, SnakeCase};
+use serde::Serialize;
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Target languages of the code generators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum Language {
+    Cpp,
+    CSharp,
+    Dart,
+    Go,
+    Java,
+    OCaml,
+    OpenApi,
+    Php,
+    Python3,
+    Rust,
+    Solidity,
+    Swift,
+    TypeScript,
+    Zig,
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Language::Cpp => "C++",
+            Language::CSharp => "C#",
+            Language::Dart => "Dart",
+            Language::Go => "Go",
+            Language::Java => "Java",
+            Language::OCaml => "OCaml",
+            Language::OpenApi => "OpenAPI",
+            Language::Php => "PHP",
+            Language::Python3 => "Python 3",
+            Language::Rust => "Rust",
+            Language::Solidity => "Solidity",
+            Language::Swift => "Swift",
+            Language::TypeScript => "TypeScript",
+            Language::Zig => "Zig",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = String;
+
+    /// Parse the identifier of a language (see `Language::name`), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Language::ALL
+            .iter()
+            .copied()
+            .find(|language| language.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown language {} (expected one of {})",
+                    s,
+                    Language::ALL
+                        .iter()
+                        .map(|language| language.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Capabilities of a language whose code generator is compiled, given by the constant
+/// `CAPABILITIES` of its module.
+macro_rules! capabilities {
+    ($feature:literal, $module:ident) => {{
+        #[cfg(feature = $feature)]
+        let capabilities = Some(crate::$module::CAPABILITIES);
+        #[cfg(not(feature = $feature))]
+        let capabilities = None;
+        capabilities
+    }};
+}
+
+impl Language {
+    /// All target languages, including those whose code generator is disabled by the
+    /// features of this crate.
+    pub const ALL: &'static [Language] = &[
+        Language::Cpp,
+        Language::CSharp,
+        Language::Dart,
+        Language::Go,
+        Language::Java,
+        Language::OCaml,
+        Language::OpenApi,
+        Language::Php,
+        Language::Python3,
+        Language::Rust,
+        Language::Solidity,
+        Language::Swift,
+        Language::TypeScript,
+        Language::Zig,
+    ];
+
+    /// Identifier of the language, e.g. `Python3` or `CSharp`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Cpp => "Cpp",
+            Language::CSharp => "CSharp",
+            Language::Dart => "Dart",
+            Language::Go => "Go",
+            Language::Java => "Java",
+            Language::OCaml => "OCaml",
+            Language::OpenApi => "OpenApi",
+            Language::Php => "Php",
+            Language::Python3 => "Python3",
+            Language::Rust => "Rust",
+            Language::Solidity => "Solidity",
+            Language::Swift => "Swift",
+            Language::TypeScript => "TypeScript",
+            Language::Zig => "Zig",
+        }
+    }
+
+    /// Languages whose code generator is enabled by the features of this crate.
+    pub fn available() -> Vec<Language> {
+        Language::ALL
+            .iter()
+            .copied()
+            .filter(|language| language.capabilities().is_some())
+            .collect()
+    }
+
+    /// What the code generator of the language supports, or `None` if it is disabled by the
+    /// features of this crate.
+    pub fn capabilities(self) -> Option<Capabilities> {
+        match self {
+            Language::Cpp => capabilities!("cpp", cpp),
+            Language::CSharp => capabilities!("csharp", csharp),
+            Language::Dart => capabilities!("dart", dart),
+            Language::Go => capabilities!("golang", golang),
+            Language::Java => capabilities!("java", java),
+            Language::OCaml => capabilities!("ocaml", ocaml),
+            Language::OpenApi => capabilities!("openapi", openapi),
+            Language::Php => capabilities!("php", php),
+            Language::Python3 => capabilities!("python3", python3),
+            Language::Rust => capabilities!("rust", rust),
+            Language::Solidity => capabilities!("solidity", solidity),
+            Language::Swift => capabilities!("swift", swift),
+            Language::TypeScript => capabilities!("typescript", typescript),
+            Language::Zig => capabilities!("zig", zig),
+        }
+    }
+}
+
+/// Options supported by the code generator of a language (see `Language::capabilities`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Encodings whose serialization methods and runtimes can be generated.
+    pub encodings: &'static [Encoding],
+    /// Whether enums without variant data can be generated as native enums (see
+    /// `CodeGeneratorConfig::with_c_style_enums`).
+    pub c_style_enums: bool,
+    /// Whether the installer can write a package manifest next to the installed modules.
+    pub package_manifest: bool,
+    /// Whether all definitions can be written in a single source file, e.g. on stdout.
+    pub single_file: bool,
+    /// Whether definitions can be written with one source file per container.
+    pub split_files: bool,
+    /// Whether serialization and deserialization methods can be generated without each
+    /// other (see `CodeGeneratorConfig::with_deserialization`).
+    pub one_way_serialization: bool,
+    /// Whether BCS messages can be framed with a length prefix (see
+    /// `CodeGeneratorConfig::with_bcs_length_prefix`).
+    pub bcs_length_prefix: bool,
+}
+
+impl Capabilities {
+    /// Reject the options of `config` that the code generator does not support.
+    pub(crate) fn check(
+        &self,
+        language: Language,
+        config: &CodeGeneratorConfig,
+    ) -> Result<(), ConfigError> {
+        if config.c_style_enums && !self.c_style_enums {
+            return Err(ConfigError::CStyleEnums { language });
+        }
+        if config.serialization != config.has_deserialization() && !self.one_way_serialization {
+            return Err(ConfigError::OneWaySerialization { language });
+        }
+        if config.bcs_length_prefix && !self.bcs_length_prefix {
+            return Err(ConfigError::BcsLengthPrefix { language });
+        }
+        if let Some(encoding) = config
+            .encodings
+            .iter()
+            .find(|encoding| !self.encodings.contains(encoding))
+        {
+            return Err(ConfigError::Encoding {
+                language,
+                encoding: *encoding,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An identifier of the generated code that would not compile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CollisionError {
+    /// Two distinct names of the registry are mapped to the same identifier.
+    Names {
+        scope: String,
+        first: String,
+        second: String,
+        identifier: String,
+    },
+    /// A name of the registry is mapped to a reserved keyword of the language, or to a special
+    /// `__dunder__` name in Python.
+    Keyword {
+        scope: String,
+        name: String,
+        identifier: String,
+    },
+    /// A name of the registry contains characters that identifiers of the language do not
+    /// allow (see `CodeGeneratorConfig::with_non_ascii_names`).
+    Characters { scope: String, name: String },
+}
+
+impl std::fmt::Display for CollisionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollisionError::Names {
+                scope,
+                first,
+                second,
+                identifier,
+            } => write!(
+                f,
+                "In {}, `{}` and `{}` both map to the identifier `{}`",
+                scope, first, second, identifier
+            ),
+            CollisionError::Keyword {
+                scope,
+                name,
+                identifier,
+            } => write!(
+                f,
+                "In {}, `{}` maps to the reserved keyword `{}`",
+                scope, name, identifier
+            ),
+            CollisionError::Characters { scope, name } => write!(
+                f,
+                "In {}, `{}` contains characters that are not allowed in identifiers",
+                scope, name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CollisionError {}
+
+/// Check that the identifiers generated for the given language are distinct, do not clash
+/// with reserved keywords, and only contain allowed characters (see
+/// `CodeGeneratorConfig::with_non_ascii_names`).
+/// The naming rules mirror the ones applied by the corresponding code generator, including
+/// the escaping of keywords configured by `CodeGeneratorConfig::with_keyword_escaping`.
+pub fn validate_registry_for(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    registry: &Registry,
+) -> std::result::Result<(), Vec<CollisionError>> {
+    validate_names(&NamingRules::new(config, language), registry)
+}
+
+fn validate_names(
+    rules: &NamingRules,
+    registry: &Registry,
+) -> std::result::Result<(), Vec<CollisionError>> {
+    let mut errors = Vec::new();
+
+    let mut types = rules.scope("type names".to_string(), rules.escapes_type_names);
+    let mut files = Scope::new("file names".to_string(), None, false);
+    for (name, format) in registry {
+        let type_name = rules.name("type names", name, &mut errors);
+        types.insert(name, (rules.type_name)(&type_name), &mut errors);
+        if let Some(file_name) = rules.file_name {
+            files.insert(name, file_name(&type_name), &mut errors);
+        }
+        match format {
+            ContainerFormat::Struct(fields) => {
+                rules.check_fields(&format!("fields of {}", name), fields, &mut errors);
+            }
+            ContainerFormat::Enum(variants) => {
+                let mut scope =
+                    rules.scope(format!("variants of {}", name), rules.escapes_variant_names);
+                let is_c_style = rules.has_c_style_enums
+                    && variants
+                        .values()
+                        .all(|variant| matches!(variant.value, VariantFormat::Unit));
+                for variant in variants.values() {
+                    let variant_name = rules.name(&scope.description, &variant.name, &mut errors);
+                    match rules.variant_class {
+                        Some(variant_class) if !is_c_style => {
+                            let qualified_name = format!("{}::{}", name, variant.name);
+                            types.insert(
+                                &qualified_name,
+                                variant_class(&type_name, &variant_name),
+                                &mut errors,
+                            );
+                        }
+                        _ => {
+                            scope.insert(
+                                &variant.name,
+                                (rules.variant_name)(&variant_name),
+                                &mut errors,
+                            );
+                        }
+                    }
+                    if let VariantFormat::Struct(fields) = &variant.value {
+                        rules.check_fields(
+                            &format!("fields of {}::{}", name, variant.name),
+                            fields,
+                            &mut errors,
+                        );
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Same as `validate_support` but report all errors as a single I/O error. Return the
+/// registry to generate code for, with transliterated names if so configured.
+pub(crate) fn check_registry<'a>(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    registry: &'a Registry,
+) -> std::io::Result<Cow<'a, Registry>> {
+    check_registry_with(&NamingRules::new(config, language), registry)
+}
+
+/// Same as `check_registry` with the naming rules of a code generator.
+pub(crate) fn check_registry_with<'a>(
+    rules: &NamingRules,
+    registry: &'a Registry,
+) -> std::io::Result<Cow<'a, Registry>> {
+    let (renamed, errors) = support_errors(rules, registry);
+    if errors.is_empty() {
+        Ok(renamed)
+    } else {
+        let messages = errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            messages.join("\n"),
+        ))
+    }
+}
+
+/// An error found by `support_errors`.
+enum SupportError {
+    Collision(CollisionError),
+    Registry(RegistryError),
+    Issue(Issue),
+}
+
+impl std::fmt::Display for SupportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SupportError::Collision(error) => error.fmt(f),
+            SupportError::Registry(error) => error.fmt(f),
+            SupportError::Issue(issue) => issue.fmt(f),
+        }
+    }
+}
+
+impl From<SupportError> for Issue {
+    fn from(error: SupportError) -> Self {
+        match error {
+            SupportError::Collision(error) => error.into(),
+            SupportError::Registry(error) => error.into(),
+            SupportError::Issue(issue) => issue,
+        }
+    }
+}
+
+/// Run the checks of `validate_support` and return the errors together with the registry
+/// with transliterated names, which the checks after the collision check apply to.
+fn support_errors<'a>(
+    rules: &NamingRules,
+    registry: &'a Registry,
+) -> (Cow<'a, Registry>, Vec<SupportError>) {
+    let (config, language) = (rules.config, rules.language);
+    let mut errors = match validate_names(rules, registry) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(SupportError::Collision).collect(),
+    };
+    let renamed = common::apply_non_ascii_names(config, registry);
+    let registry = renamed.as_ref();
+    let issues = validate_floats(config, registry)
+        .into_iter()
+        .chain(validate_wide_integers(config, registry))
+        .chain(validate_elision_containers(config, language, registry))
+        .chain(validate_tuple_field_names(config, language, registry))
+        .chain(validate_variant_tags(rules, registry));
+    errors.extend(issues.map(SupportError::Issue));
+    errors.extend(
+        validate_file_names(rules, registry)
+            .into_iter()
+            .map(SupportError::Registry),
+    );
+    (renamed, errors)
+}
+
+/// A part of a registry that the code generator of a language cannot handle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// Location in the registry (e.g. `MyStruct.field` or `MyEnum::Variant`).
+    pub path: String,
+    /// Why the code generator cannot handle it.
+    pub reason: String,
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+impl From<CollisionError> for Issue {
+    fn from(error: CollisionError) -> Self {
+        match error {
+            CollisionError::Names {
+                scope,
+                first,
+                second,
+                identifier,
+            } => Issue {
+                path: scope,
+                reason: format!(
+                    "`{}` and `{}` both map to the identifier `{}`",
+                    first, second, identifier
+                ),
+            },
+            CollisionError::Keyword {
+                scope,
+                name,
+                identifier,
+            } => Issue {
+                path: scope,
+                reason: format!("`{}` maps to the reserved keyword `{}`", name, identifier),
+            },
+            CollisionError::Characters { scope, name } => Issue {
+                path: scope,
+                reason: format!(
+                    "`{}` contains characters that are not allowed in identifiers",
+                    name
+                ),
+            },
+        }
+    }
+}
+
+/// Report the parts of the registry that the code generators of all languages reject
+/// with the given config: identifier collisions and, unless allowed, floating-point
+/// numbers with the BCS and postcard encodings, as well as integers wider than 64 bits with
+/// the msgpack encoding.
+/// Code generators add their own checks in their `validate` method.
+pub fn validate_support(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    registry: &Registry,
+) -> Vec<Issue> {
+    validate_support_with(&NamingRules::new(config, language), registry)
+}
+
+/// Same as `validate_support` with the naming rules of a code generator.
+pub(crate) fn validate_support_with(rules: &NamingRules, registry: &Registry) -> Vec<Issue> {
+    let (_, errors) = support_errors(rules, registry);
+    errors.into_iter().map(Issue::from).collect()
+}
+
+/// List the containers, fields, and variants of the registry whose names are changed by
+/// `NonAsciiNames::Transliterate`, if so configured, as pairs of a path in the original
+/// registry (e.g. `MyStruct.my_field` or `MyEnum::Variant`) and the new name.
+pub fn renamed_identifiers(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> Vec<(String, String)> {
+    let mut renames = Vec::new();
+    if config.non_ascii_names != NonAsciiNames::Transliterate {
+        return renames;
+    }
+    let mut rename = |path: String, name: &str| {
+        let identifier = common::transliterate(name);
+        if identifier != name {
+            renames.push((path, identifier));
+        }
+    };
+    for (name, format) in registry {
+        rename(name.clone(), name);
+        match format {
+            ContainerFormat::Struct(fields) => {
+                for field in fields {
+                    rename(format!("{}.{}", name, field.name), &field.name);
+                }
+            }
+            ContainerFormat::Enum(variants) => {
+                for variant in variants.values() {
+                    let path = format!("{}::{}", name, variant.name);
+                    rename(path.clone(), &variant.name);
+                    if let VariantFormat::Struct(fields) = &variant.value {
+                        for field in fields {
+                            rename(format!("{}.{}", path, field.name), &field.name);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    renames
+}
+
+/// Report the floating-point formats of the registry that the configured encodings do not
+/// support: BCS has no floats (unless `CodeGeneratorConfig::with_allow_nonstandard_floats` is
+/// set) and floats are disabled for postcard by default (see
+/// `CodeGeneratorConfig::with_postcard_floats`). Bincode supports floats.
+fn validate_floats(config: &CodeGeneratorConfig, registry: &Registry) -> Vec<Issue> {
+    let mut encodings = Vec::new();
+    if config.encodings.contains(&Encoding::Bcs) && !config.allow_nonstandard_floats {
+        encodings.push("BCS");
+    }
+    if config.encodings.contains(&Encoding::Postcard) && !config.postcard_floats {
+        encodings.push("postcard");
+    }
+    let mut issues = Vec::new();
+    if encodings.is_empty() {
+        return issues;
+    }
+    let reason = format!(
+        "floating-point numbers are not supported by the {} encoding",
+        encodings.join(" and ")
+    );
+    visit_formats(registry, &mut |path, format| {
+        if matches!(format, Format::F32 | Format::F64)
+            && issues.last().map(|issue: &Issue| issue.path.as_str()) != Some(path)
+        {
+            issues.push(Issue {
+                path: path.to_string(),
+                reason: reason.clone(),
+            });
+        }
+    });
+    issues
+}
+
+/// Report the integers wider than 64 bits, which the MessagePack encoding does not support.
+fn validate_wide_integers(config: &CodeGeneratorConfig, registry: &Registry) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if !config.encodings.contains(&Encoding::MessagePack) {
+        return issues;
+    }
+    visit_formats(registry, &mut |path, format| {
+        if matches!(format, Format::I128 | Format::U128)
+            && issues.last().map(|issue: &Issue| issue.path.as_str()) != Some(path)
+        {
+            issues.push(Issue {
+                path: path.to_string(),
+                reason: "integers wider than 64 bits are not supported by the msgpack encoding"
+                    .to_string(),
+            });
+        }
+    });
+    issues
+}
+
+/// Report the containers selected for the compact encoding (see
+/// `CodeGeneratorConfig::with_elision_containers`) that are not structs of the registry.
+/// Other languages ignore the selection. Like BCS, the compact encoding has no floats unless
+/// `CodeGeneratorConfig::with_allow_nonstandard_floats` is set.
+fn validate_elision_containers(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    registry: &Registry,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let supported = matches!(
+        language,
+        Language::Python3 | Language::Rust | Language::TypeScript
+    );
+    if !config.has_serde_methods() || !supported {
+        return issues;
+    }
+    let reason = "floating-point numbers are not supported by the compact encoding";
+    for name in &config.elision_containers {
+        match registry.get(name) {
+            Some(format @ ContainerFormat::Struct(_)) => {
+                if config.allow_nonstandard_floats {
+                    continue;
+                }
+                visit_container_formats(name, format, &mut |path, format| {
+                    if matches!(format, Format::F32 | Format::F64)
+                        && issues.last().map(|issue: &Issue| issue.path.as_str()) != Some(path)
+                    {
+                        issues.push(Issue {
+                            path: path.to_string(),
+                            reason: reason.to_string(),
+                        });
+                    }
+                });
+            }
+            Some(_) => issues.push(Issue {
+                path: name.clone(),
+                reason: "the compact encoding only applies to structs with named fields"
+                    .to_string(),
+            }),
+            None => issues.push(Issue {
+                path: name.clone(),
+                reason: "unknown container selected for the compact encoding".to_string(),
+            }),
+        }
+    }
+    issues
+}
+
+/// Report the names of tuple-variant fields (see `CodeGeneratorConfig::with_tuple_field_names`)
+/// that do not match a tuple variant of the registry, field by field. Other languages ignore
+/// these names.
+fn validate_tuple_field_names(
+    config: &CodeGeneratorConfig,
+    language: Language,
+    registry: &Registry,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if !matches!(
+        language,
+        Language::Java | Language::Swift | Language::TypeScript
+    ) {
+        return issues;
+    }
+    for ((enum_name, variant_name), names) in &config.tuple_field_names {
+        let path = format!("{}::{}", enum_name, variant_name);
+        let variant = match registry.get(enum_name) {
+            Some(ContainerFormat::Enum(variants)) => variants
+                .values()
+                .find(|variant| &variant.name == variant_name),
+            _ => None,
+        };
+        let reason = match variant.map(|variant| &variant.value) {
+            None => "unknown variant selected for tuple field names".to_string(),
+            Some(VariantFormat::Tuple(formats)) if formats.len() != names.len() => format!(
+                "expected {} tuple field names, found {}",
+                formats.len(),
+                names.len()
+            ),
+            Some(VariantFormat::Tuple(_)) => {
+                match names
+                    .iter()
+                    .enumerate()
+                    .find(|(i, name)| names[..*i].contains(name))
+                {
+                    Some((_, name)) => format!("duplicate tuple field name `{}`", name),
+                    None => continue,
+                }
+            }
+            Some(_) => "tuple field names only apply to tuple variants".to_string(),
+        };
+        issues.push(Issue { path, reason });
+    }
+    issues
+}
+
+/// Report the fields of struct variants named like the field holding the name of the variant,
+/// e.g. `kind` in Python tagged unions (see `python3::PythonModels::PydanticTaggedUnions`).
+fn validate_variant_tags(rules: &NamingRules, registry: &Registry) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let tag = match rules.variant_tag {
+        Some(tag) => tag,
+        None => return issues,
+    };
+    for (name, format) in registry {
+        if let ContainerFormat::Enum(variants) = format {
+            for variant in variants.values() {
+                if let VariantFormat::Struct(fields) = &variant.value {
+                    if fields.iter().any(|field| field.name == tag) {
+                        issues.push(Issue {
+                            path: format!("{}::{}.{}", name, variant.name, tag),
+                            reason: format!("`{}` is reserved for the tag of the variant", tag),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// A registry that cannot be processed safely, e.g. because it was crafted to exhaust memory
+/// or to create files outside of the target directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistryError {
+    /// A tuple array is larger than the limit (see `RegistryLimits::with_max_tuple_array_size`).
+    TupleArraySize {
+        path: String,
+        size: usize,
+        max_size: usize,
+    },
+    /// Formats are nested too deeply (see `RegistryLimits::with_max_depth`).
+    Depth { path: String, max_depth: usize },
+    /// The name of a container cannot be used as a file name: it is empty, contains a path
+    /// separator, or is reserved by the file system.
+    FileName { name: String, file_name: String },
+}
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::TupleArraySize {
+                path,
+                size,
+                max_size,
+            } => write!(
+                f,
+                "{}: tuple array of size {} exceeds the maximum size {}",
+                path, size, max_size
+            ),
+            RegistryError::Depth { path, max_depth } => write!(
+                f,
+                "{}: formats are nested more than {} levels deep",
+                path, max_depth
+            ),
+            RegistryError::FileName { name, file_name } => write!(
+                f,
+                "Container `{}` cannot be written to the file `{}`",
+                name, file_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<RegistryError> for Issue {
+    fn from(error: RegistryError) -> Self {
+        match error {
+            RegistryError::TupleArraySize {
+                path,
+                size,
+                max_size,
+            } => Issue {
+                path,
+                reason: format!(
+                    "tuple array of size {} exceeds the maximum size {}",
+                    size, max_size
+                ),
+            },
+            RegistryError::Depth { path, max_depth } => Issue {
+                path,
+                reason: format!("formats are nested more than {} levels deep", max_depth),
+            },
+            RegistryError::FileName { name, file_name } => Issue {
+                path: name,
+                reason: format!("cannot be written to the file `{}`", file_name),
+            },
+        }
+    }
+}
+
+/// Bounds on the registries accepted by `validate_registry_limits`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryLimits {
+    max_tuple_array_size: usize,
+    max_depth: usize,
+}
+
+impl Default for RegistryLimits {
+    fn default() -> Self {
+        Self {
+            max_tuple_array_size: 1 << 16,
+            max_depth: 64,
+        }
+    }
+}
+
+impl RegistryLimits {
+    /// Default limits: tuple arrays of at most 65536 elements and 64 levels of nested formats.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum size of tuple arrays. Code generators may emit one item per element.
+    pub fn with_max_tuple_array_size(mut self, max_tuple_array_size: usize) -> Self {
+        self.max_tuple_array_size = max_tuple_array_size;
+        self
+    }
+
+    /// Maximum number of nested formats in a container, e.g. 3 for `Option<Vec<u8>>`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// Check the registry against the given limits before generating code, typically after
+/// reading it from an untrusted file.
+pub fn validate_registry_limits(
+    registry: &Registry,
+    limits: &RegistryLimits,
+) -> std::result::Result<(), RegistryError> {
+    for (name, format) in registry {
+        validate_container_limits(name, format, limits)?;
+    }
+    Ok(())
+}
+
+fn validate_container_limits(
+    name: &str,
+    format: &ContainerFormat,
+    limits: &RegistryLimits,
+) -> std::result::Result<(), RegistryError> {
+    match format {
+        ContainerFormat::UnitStruct => Ok(()),
+        ContainerFormat::NewTypeStruct(format) => validate_format_limits(name, format, 1, limits),
+        ContainerFormat::TupleStruct(formats) => formats
+            .iter()
+            .try_for_each(|format| validate_format_limits(name, format, 1, limits)),
+        ContainerFormat::Struct(fields) => fields.iter().try_for_each(|field| {
+            validate_format_limits(&format!("{}.{}", name, field.name), &field.value, 1, limits)
+        }),
+        ContainerFormat::Enum(variants) => variants.values().try_for_each(|variant| {
+            let path = format!("{}::{}", name, variant.name);
+            match &variant.value {
+                VariantFormat::NewType(format) => validate_format_limits(&path, format, 1, limits),
+                VariantFormat::Tuple(formats) => formats
+                    .iter()
+                    .try_for_each(|format| validate_format_limits(&path, format, 1, limits)),
+                VariantFormat::Struct(fields) => fields.iter().try_for_each(|field| {
+                    let path = format!("{}.{}", path, field.name);
+                    validate_format_limits(&path, &field.value, 1, limits)
+                }),
+                VariantFormat::Unit | VariantFormat::Variable(_) => Ok(()),
+            }
+        }),
+    }
+}
+
+/// Unlike `Format::visit`, stop before exceeding the maximum depth so that hostile formats
+/// cannot overflow the stack.
+fn validate_format_limits(
+    path: &str,
+    format: &Format,
+    depth: usize,
+    limits: &RegistryLimits,
+) -> std::result::Result<(), RegistryError> {
+    if depth > limits.max_depth {
+        return Err(RegistryError::Depth {
+            path: path.to_string(),
+            max_depth: limits.max_depth,
+        });
+    }
+    let depth = depth + 1;
+    match format {
+        Format::Option(format) | Format::Seq(format) => {
+            validate_format_limits(path, format, depth, limits)
+        }
+        Format::Map { key, value } => {
+            validate_format_limits(path, key, depth, limits)?;
+            validate_format_limits(path, value, depth, limits)
+        }
+        Format::Tuple(formats) => formats
+            .iter()
+            .try_for_each(|format| validate_format_limits(path, format, depth, limits)),
+        Format::TupleArray { content, size } => {
+            if *size > limits.max_tuple_array_size {
+                return Err(RegistryError::TupleArraySize {
+                    path: path.to_string(),
+                    size: *size,
+                    max_size: limits.max_tuple_array_size,
+                });
+            }
+            validate_format_limits(path, content, depth, limits)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// File names that Windows reserves, with or without an extension.
+const RESERVED_FILE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Whether a file may be created under this name (without extension) in the target directory
+/// and nowhere else.
+fn is_safe_file_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':', '\0'])
+        && !RESERVED_FILE_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Report the containers whose source file (for languages that create one file per container)
+/// would not be created in the target directory.
+fn validate_file_names(rules: &NamingRules, registry: &Registry) -> Vec<RegistryError> {
+    let file_name = match rules.file_name {
+        Some(file_name) => file_name,
+        None => return Vec::new(),
+    };
+    registry
+        .keys()
+        .filter_map(|name| {
+            let file_name = file_name(name);
+            if is_safe_file_name(&file_name) {
+                None
+            } else {
+                Some(RegistryError::FileName {
+                    name: name.clone(),
+                    file_name,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Call `f` on every format of the registry (including nested formats) together with the
+/// path of the enclosing container, variant, or field.
+pub(crate) fn visit_formats(registry: &Registry, f: &mut dyn FnMut(&str, &Format)) {
+    for (name, format) in registry {
+        visit_container_formats(name, format, f);
+    }
+}
+
+fn visit_container_formats(name: &str, format: &ContainerFormat, f: &mut dyn FnMut(&str, &Format)) {
+    let mut visit = |path: String, format: &Format| {
+        format
+            .visit(&mut |format| {
+                f(&path, format);
+                Ok(())
+            })
+            .expect("formats should not contain variables");
+    };
+    match format {
+        ContainerFormat::UnitStruct => (),
+        ContainerFormat::NewTypeStruct(format) => visit(name.to_string(), format),
+        ContainerFormat::TupleStruct(formats) => {
+            for (index, format) in formats.iter().enumerate() {
+                visit(format!("{}.{}", name, index), format);
+            }
+        }
+        ContainerFormat::Struct(fields) => {
+            for field in fields {
+                visit(format!("{}.{}", name, field.name), &field.value);
+            }
+        }
+        ContainerFormat::Enum(variants) => {
+            for variant in variants.values() {
+                let path = format!("{}::{}", name, variant.name);
+                match &variant.value {
+                    VariantFormat::NewType(format) => visit(path, format),
+                    VariantFormat::Tuple(formats) => {
+                        for (index, format) in formats.iter().enumerate() {
+                            visit(format!("{}.{}", path, index), format);
+                        }
+                    }
+                    VariantFormat::Struct(fields) => {
+                        for field in fields {
+                            visit(format!("{}.{}", path, field.name), &field.value);
+                        }
+                    }
+                    VariantFormat::Unit | VariantFormat::Variable(_) => (),
+                }
+            }
+        }
+    }
+}
+
+/// Identifiers already used in a given namespace of the generated code.
+struct Scope<'a> {
+    description: String,
+    /// Config and language whose keywords are reserved in this namespace, if any.
+    keywords: Option<(&'a CodeGeneratorConfig, Language)>,
+    /// Whether the code generator escapes keywords in this namespace.
+    escaped: bool,
+    identifiers: BTreeMap<String, String>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(
+        description: String,
+        keywords: Option<(&'a CodeGeneratorConfig, Language)>,
+        escaped: bool,
+    ) -> Self {
+        Self {
+            description,
+            keywords,
+            escaped,
+            identifiers: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, name: &str, identifier: String, errors: &mut Vec<CollisionError>) {
+        let identifier = match self.keywords {
+            Some((config, language)) if common::is_keyword(language, &identifier) => {
+                match config.keyword_escaping(language) {
+                    KeywordEscaping::PassThrough => identifier,
+                    KeywordEscaping::Suffix(_) | KeywordEscaping::Prefix(_) if self.escaped => {
+                        common::escape_keyword(config, language, &identifier)
+                    }
+                    _ => {
+                        errors.push(CollisionError::Keyword {
+                            scope: self.description.clone(),
+                            name: name.to_string(),
+                            identifier: identifier.clone(),
+                        });
+                        identifier
+                    }
+                }
+            }
+            _ => identifier,
+        };
+        match self.identifiers.get(&identifier) {
+            Some(first) => errors.push(CollisionError::Names {
+                scope: self.description.clone(),
+                first: first.clone(),
+                second: name.to_string(),
+                identifier,
+            }),
+            None => {
+                self.identifiers.insert(identifier, name.to_string());
+            }
+        }
+    }
+}
+
+/// How a code generator turns the names of a registry into identifiers.
+pub(crate) struct NamingRules<'a> {
+    config: &'a CodeGeneratorConfig,
+    language: Language,
+    type_name: fn(&str) -> String,
+    /// Source file created for each container, if any.
+    file_name: Option<fn(&str) -> String>,
+    field_name: fn(&str) -> String,
+    variant_name: fn(&str) -> String,
+    /// Variants compiled into classes sharing the namespace of the container types.
+    variant_class: Option<fn(&str, &str) -> String>,
+    /// Whether enums without data are compiled into native enums (using `variant_name`).
+    has_c_style_enums: bool,
+    /// Whether keywords are escaped in type names.
+    escapes_type_names: bool,
+    /// Whether keywords are escaped in variant names (using `variant_name`).
+    /// Field names are always escaped.
+    escapes_variant_names: bool,
+    /// Field added to struct variants to hold the name of the variant, if any.
+    variant_tag: Option<&'static str>,
+}
+
+impl<'a> NamingRules<'a> {
+    pub(crate) fn new(config: &'a CodeGeneratorConfig, language: Language) -> Self {
+        let rules = Self {
+            config,
+            language,
+            type_name: |name| name.to_string(),
+            file_name: None,
+            field_name: |name| name.to_string(),
+            variant_name: |name| name.to_string(),
+            variant_class: None,
+            has_c_style_enums: false,
+            escapes_type_names: false,
+            escapes_variant_names: false,
+            variant_tag: None,
+        };
+        match language {
+            Language::Dart => Self {
+                type_name: |name| match name {
+                    "List" => "List_".to_string(),
+                    "Map" => "Map_".to_string(),
+                    name => name.to_string(),
+                },
+                file_name: Some(|name| name.to_snake_case()),
+                field_name: |name| match name.to_mixed_case().as_str() {
+                    "hashCode" => "hashCode_".to_string(),
+                    "runtimeType" => "runtimeType_".to_string(),
+                    name => name.to_string(),
+                },
+                variant_name: |name| name.to_mixed_case(),
+                variant_class: Some(|base, name| format!("{}{}", base, name)),
+                has_c_style_enums: config.c_style_enums,
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Go => Self {
+                field_name: |name| name.to_camel_case(),
+                variant_class: Some(|base, name| format!("{}__{}", base, name)),
+                ..rules
+            },
+            Language::OCaml => Self {
+                type_name: |name| name.to_snake_case(),
+                field_name: |name| name.to_snake_case(),
+                escapes_type_names: true,
+                // Constructors are prefixed with the type name.
+                escapes_variant_names: true,
+                ..rules
+            },
+            // Class names are case-insensitive.
+            Language::Php => Self {
+                type_name: |name| name.to_lowercase(),
+                file_name: Some(|name| name.to_string()),
+                variant_class: Some(|base, name| format!("{}__{}", base, name).to_lowercase()),
+                has_c_style_enums: true,
+                escapes_type_names: true,
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Python3 => Self {
+                variant_class: Some(|base, name| format!("{}__{}", base, name)),
+                has_c_style_enums: config.c_style_enums,
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Rust => Self {
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Solidity => Self {
+                variant_name: |name| name.to_snake_case(),
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Zig => Self {
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::Swift => Self {
+                variant_name: common::lowercase_first_letter,
+                escapes_variant_names: true,
+                ..rules
+            },
+            Language::TypeScript => Self {
+                variant_class: Some(|base, name| format!("{}Variant{}", base, name)),
+                ..rules
+            },
+            Language::CSharp | Language::Java => Self {
+                file_name: Some(|name| name.to_string()),
+                ..rules
+            },
+            Language::Cpp | Language::OpenApi => rules,
+        }
+    }
+
+    /// Tag struct variants with a field `kind`, as done by the Python code generator with
+    /// `python3::PythonModels::PydanticTaggedUnions`.
+    pub(crate) fn with_python_variant_tags(self) -> Self {
+        Self {
+            variant_tag: Some("kind"),
+            ..self
+        }
+    }
+
+    /// Compile variants into classes `{Enum}_{Variant}`, as done by the C++ code generator
+    /// with `cpp::CodeGenerator::with_variant_enums`.
+    pub(crate) fn with_cpp_variant_classes(self) -> Self {
+        Self {
+            variant_class: Some(|base, name| format!("{}_{}", base, name)),
+            ..self
+        }
+    }
+
+    fn scope(&self, description: String, escaped: bool) -> Scope<'a> {
+        Scope::new(description, Some((self.config, self.language)), escaped)
+    }
+
+    fn check_fields(
+        &self,
+        description: &str,
+        fields: &[Named<Format>],
+        errors: &mut Vec<CollisionError>,
+    ) {
+        let mut scope = self.scope(description.to_string(), true);
+        for field in fields {
+            let field_name = self.name(description, &field.name, errors);
+            scope.insert(&field.name, (self.field_name)(&field_name), errors);
+        }
+    }
+
+    /// Name used in place of `name` before applying the naming conventions of the language:
+    /// either `name` itself, whose characters are checked, or its transliteration.
+    fn name(&self, scope: &str, name: &str, errors: &mut Vec<CollisionError>) -> String {
+        if self.config.non_ascii_names == NonAsciiNames::Transliterate {
+            return common::transliterate(name);
+        }
+        if !common::has_valid_characters(self.config, self.language, name) {
+            errors.push(CollisionError::Characters {
+                scope: scope.to_string(),
+                name: name.to_string(),
+            });
+        }
+        name.to_string()
+    }
+}
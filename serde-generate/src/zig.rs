@@ -0,0 +1,996 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    analyzer, common,
+    indent::{IndentConfig, IndentedWriter},
+    validation::{self, Capabilities, Language},
+    CodeGeneratorConfig, ConfigError, Encoding,
+};
+use serde_reflection::{ContainerFormat, Format, FormatHolder, Named, Registry, VariantFormat};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::{Result, Write},
+    path::PathBuf,
+};
+
+/// Options supported by the code generator (see `Language::capabilities`).
+pub(crate) const CAPABILITIES: Capabilities = Capabilities {
+    encodings: &[Encoding::Bincode, Encoding::Bcs],
+    c_style_enums: false,
+    package_manifest: true,
+    single_file: true,
+    split_files: false,
+    one_way_serialization: false,
+    bcs_length_prefix: false,
+};
+
+/// Main configuration object for code-generation in Zig.
+pub struct CodeGenerator<'a> {
+    /// Language-independent configuration.
+    config: &'a CodeGeneratorConfig,
+    /// Mapping from external type names to qualified names (e.g. "MyType" -> "other.MyType").
+    /// Derived from `config.external_definitions`.
+    external_qualified_names: HashMap<String, String>,
+}
+
+/// Shared state for the code generation of a Zig source file.
+struct ZigEmitter<'a, T> {
+    /// Writer.
+    out: IndentedWriter<T>,
+    /// Generator.
+    generator: &'a CodeGenerator<'a>,
+    /// Current namespace (e.g. vec!["my_module", "MyType"])
+    current_namespace: Vec<String>,
+    /// Containers referenced through pointers, so that recursive definitions have a finite size.
+    boxed_types: HashSet<String>,
+}
+
+impl<'a> CodeGenerator<'a> {
+    /// Create a Zig code generator for the given config, or return an error if the config
+    /// requires features that are not supported.
+    pub fn try_new(config: &'a CodeGeneratorConfig) -> std::result::Result<Self, ConfigError> {
+        CAPABILITIES.check(Language::Zig, config)?;
+        let mut external_qualified_names = HashMap::new();
+        for (namespace, names) in &config.external_definitions {
+            for name in names {
+                external_qualified_names
+                    .insert(name.to_string(), format!("{}.{}", namespace, name));
+            }
+        }
+        Ok(Self {
+            config,
+            external_qualified_names,
+        })
+    }
+
+    /// Report the parts of the registry that cannot be generated in Zig, without
+    /// generating any code.
+    pub fn validate(&self, registry: &Registry) -> Vec<validation::Issue> {
+        validation::validate_support(self.config, Language::Zig, registry)
+    }
+
+    /// Output type definitions for `registry`.
+    pub fn output(
+        &self,
+        out: &mut dyn Write,
+        registry: &Registry,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let registry = validation::check_registry(self.config, Language::Zig, registry)?;
+        let registry: &Registry = &registry;
+        let current_namespace = self
+            .config
+            .module_name
+            .split('.')
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let mut emitter = ZigEmitter {
+            // `zig fmt` indents with 4 spaces.
+            out: IndentedWriter::new(out, IndentConfig::Space(4))
+                .with_output_indent(self.config.indent),
+            generator: self,
+            current_namespace,
+            boxed_types: self.boxed_types(registry)?,
+        };
+
+        emitter.output_preamble()?;
+
+        for (name, format) in registry {
+            emitter.output_container(name, format)?;
+        }
+
+        if self.config.serialization {
+            emitter.output_trait_helpers(registry)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the containers that must be referenced through pointers. Like in C++ and
+    /// Rust, containers are visited in topological order and references that require a known
+    /// size (i.e. that are not within slices) to a container not yet visited are boxed. To
+    /// keep each format mapped to a single Zig type, boxed containers are referenced through
+    /// pointers everywhere.
+    fn boxed_types(
+        &self,
+        registry: &Registry,
+    ) -> std::result::Result<HashSet<String>, serde_reflection::Error> {
+        let external_names = self
+            .external_qualified_names
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let dependencies =
+            analyzer::get_dependency_map_with_external_dependencies(registry, &external_names)?;
+        let mut known_sizes = external_names
+            .iter()
+            .map(String::as_str)
+            .collect::<HashSet<_>>();
+        let mut boxed_types = HashSet::new();
+        for name in analyzer::best_effort_topological_sort(&dependencies) {
+            for format in Self::field_formats(&registry[name]) {
+                Self::visit_unknown_sizes(format, true, &known_sizes, &mut boxed_types);
+            }
+            known_sizes.insert(name);
+        }
+        Ok(boxed_types)
+    }
+
+    /// Formats of the fields of a container, including the fields of its variants.
+    fn field_formats(format: &ContainerFormat) -> Vec<&Format> {
+        use ContainerFormat::*;
+        match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![format.as_ref()],
+            TupleStruct(formats) => formats.iter().collect(),
+            Struct(fields) => fields.iter().map(|field| &field.value).collect(),
+            Enum(variants) => variants
+                .values()
+                .flat_map(|variant| match &variant.value {
+                    VariantFormat::NewType(format) => vec![format.as_ref()],
+                    VariantFormat::Tuple(formats) => formats.iter().collect(),
+                    VariantFormat::Struct(fields) => {
+                        fields.iter().map(|field| &field.value).collect()
+                    }
+                    VariantFormat::Unit | VariantFormat::Variable(_) => Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn visit_unknown_sizes(
+        format: &Format,
+        require_known_size: bool,
+        known_sizes: &HashSet<&str>,
+        boxed_types: &mut HashSet<String>,
+    ) {
+        use Format::*;
+        match format {
+            TypeName(name) => {
+                if require_known_size && !known_sizes.contains(name.as_str()) {
+                    boxed_types.insert(name.clone());
+                }
+            }
+            Option(format) => {
+                Self::visit_unknown_sizes(format, require_known_size, known_sizes, boxed_types)
+            }
+            Seq(format) => Self::visit_unknown_sizes(format, false, known_sizes, boxed_types),
+            Map { key, value } => {
+                Self::visit_unknown_sizes(key, false, known_sizes, boxed_types);
+                Self::visit_unknown_sizes(value, false, known_sizes, boxed_types);
+            }
+            Tuple(formats) => {
+                for format in formats {
+                    Self::visit_unknown_sizes(format, require_known_size, known_sizes, boxed_types);
+                }
+            }
+            TupleArray { content, .. } => {
+                Self::visit_unknown_sizes(content, require_known_size, known_sizes, boxed_types)
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<'a, T> ZigEmitter<'a, T>
+where
+    T: Write,
+{
+    fn output_preamble(&mut self) -> Result<()> {
+        writeln!(self.out, "const std = @import(\"std\");")?;
+        writeln!(self.out, "const serde = @import(\"serde.zig\");")?;
+        if self.generator.config.serialization {
+            for encoding in &self.generator.config.encodings {
+                writeln!(
+                    self.out,
+                    "const {0} = @import(\"{0}.zig\");",
+                    encoding.name()
+                )?;
+            }
+        }
+        for namespace in self.generator.config.external_definitions.keys() {
+            writeln!(self.out, "const {0} = @import(\"{0}.zig\");", namespace)?;
+        }
+        Ok(())
+    }
+
+    /// Compute a reference to the registry type `name`.
+    fn quote_qualified_name(&self, name: &str) -> String {
+        self.generator
+            .external_qualified_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn quote_field(&self, name: &str) -> String {
+        common::escape_keyword(self.generator.config, Language::Zig, name)
+    }
+
+    fn output_comment(&mut self, name: &str) -> std::io::Result<()> {
+        let mut path = self.current_namespace.clone();
+        path.push(name.to_string());
+        if let Some(doc) = self.generator.config.comments.get(&path) {
+            let text = textwrap::indent(doc, "/// ").replace("\n\n", "\n///\n");
+            write!(self.out, "{}", text)?;
+        }
+        Ok(())
+    }
+
+    /// Output the custom code of the current container, inside its body.
+    fn output_custom_code(&mut self) -> std::io::Result<()> {
+        if let Some(code) = self
+            .generator
+            .config
+            .custom_code
+            .get(&self.current_namespace)
+        {
+            write!(self.out, "\n{}", code)?;
+        }
+        Ok(())
+    }
+
+    fn quote_type(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) => {
+                let qname = self.quote_qualified_name(x);
+                if self.boxed_types.contains(x) {
+                    format!("*const {}", qname)
+                } else {
+                    qname
+                }
+            }
+            Unit => "void".into(),
+            Bool => "bool".into(),
+            I8 => "i8".into(),
+            I16 => "i16".into(),
+            I32 => "i32".into(),
+            I64 => "i64".into(),
+            I128 => "i128".into(),
+            U8 => "u8".into(),
+            U16 => "u16".into(),
+            U32 => "u32".into(),
+            U64 => "u64".into(),
+            U128 => "u128".into(),
+            F32 => "f32".into(),
+            F64 => "f64".into(),
+            Char => "u21".into(),
+            Str => "[]const u8".into(),
+            Bytes => "[]const u8".into(),
+
+            Option(format) => format!("?{}", self.quote_type(format)),
+            Seq(format) => format!("[]const {}", self.quote_type(format)),
+            Map { key, value } => format!(
+                "[]const serde.Entry({}, {})",
+                self.quote_type(key),
+                self.quote_type(value)
+            ),
+            Tuple(formats) => self.quote_tuple_type(formats),
+            TupleArray { content, size } => format!("[{}]{}", size, self.quote_type(content)),
+
+            Variable(_) => panic!("unexpected value"),
+        }
+    }
+
+    /// Tuples use `std.meta.Tuple` so that identical tuples have the same type.
+    fn quote_tuple_type(&self, formats: &[Format]) -> String {
+        if formats.is_empty() {
+            return "std.meta.Tuple(&.{})".to_string();
+        }
+        format!(
+            "std.meta.Tuple(&.{{ {} }})",
+            formats
+                .iter()
+                .map(|format| self.quote_type(format))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn enter_class(&mut self, name: &str) {
+        self.out.indent();
+        self.current_namespace.push(name.to_string());
+    }
+
+    fn leave_class(&mut self) {
+        self.out.unindent();
+        self.current_namespace.pop();
+    }
+
+    fn output_trait_helpers(&mut self, registry: &Registry) -> Result<()> {
+        let mut subtypes = BTreeMap::new();
+        for format in registry.values() {
+            format
+                .visit(&mut |f| {
+                    if Self::needs_helper(f) {
+                        subtypes.insert(common::mangle_type(f), f.clone());
+                    }
+                    Ok(())
+                })
+                .unwrap();
+        }
+        for (mangled_name, subtype) in &subtypes {
+            self.output_serialization_helper(mangled_name, subtype)?;
+            self.output_deserialization_helper(mangled_name, subtype)?;
+        }
+        Ok(())
+    }
+
+    fn needs_helper(format: &Format) -> bool {
+        use Format::*;
+        matches!(
+            format,
+            Option(_) | Seq(_) | Map { .. } | Tuple(_) | TupleArray { .. }
+        )
+    }
+
+    fn quote_serialize_value(&self, value: &str, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) if self.boxed_types.contains(x) => {
+                format!("try {}.*.serialize(serializer);", value)
+            }
+            TypeName(_) => format!("try {}.serialize(serializer);", value),
+            Unit => format!("try serializer.serializeUnit({});", value),
+            Bool => format!("try serializer.serializeBool({});", value),
+            I8 => format!("try serializer.serializeI8({});", value),
+            I16 => format!("try serializer.serializeI16({});", value),
+            I32 => format!("try serializer.serializeI32({});", value),
+            I64 => format!("try serializer.serializeI64({});", value),
+            I128 => format!("try serializer.serializeI128({});", value),
+            U8 => format!("try serializer.serializeU8({});", value),
+            U16 => format!("try serializer.serializeU16({});", value),
+            U32 => format!("try serializer.serializeU32({});", value),
+            U64 => format!("try serializer.serializeU64({});", value),
+            U128 => format!("try serializer.serializeU128({});", value),
+            F32 => format!("try serializer.serializeF32({});", value),
+            F64 => format!("try serializer.serializeF64({});", value),
+            Char => format!("try serializer.serializeChar({});", value),
+            Str => format!("try serializer.serializeStr({});", value),
+            Bytes => format!("try serializer.serializeBytes({});", value),
+            _ => format!(
+                "try serialize_{}({}, serializer);",
+                common::mangle_type(format),
+                value
+            ),
+        }
+    }
+
+    fn quote_deserialize(&self, format: &Format) -> String {
+        use Format::*;
+        match format {
+            TypeName(x) if self.boxed_types.contains(x) => {
+                let qname = self.quote_qualified_name(x);
+                format!(
+                    "try deserializer.create({0}, try {0}.deserialize(deserializer))",
+                    qname
+                )
+            }
+            TypeName(x) => format!(
+                "try {}.deserialize(deserializer)",
+                self.quote_qualified_name(x)
+            ),
+            Unit => "try deserializer.deserializeUnit()".to_string(),
+            Bool => "try deserializer.deserializeBool()".to_string(),
+            I8 => "try deserializer.deserializeI8()".to_string(),
+            I16 => "try deserializer.deserializeI16()".to_string(),
+            I32 => "try deserializer.deserializeI32()".to_string(),
+            I64 => "try deserializer.deserializeI64()".to_string(),
+            I128 => "try deserializer.deserializeI128()".to_string(),
+            U8 => "try deserializer.deserializeU8()".to_string(),
+            U16 => "try deserializer.deserializeU16()".to_string(),
+            U32 => "try deserializer.deserializeU32()".to_string(),
+            U64 => "try deserializer.deserializeU64()".to_string(),
+            U128 => "try deserializer.deserializeU128()".to_string(),
+            F32 => "try deserializer.deserializeF32()".to_string(),
+            F64 => "try deserializer.deserializeF64()".to_string(),
+            Char => "try deserializer.deserializeChar()".to_string(),
+            Str => "try deserializer.deserializeStr()".to_string(),
+            Bytes => "try deserializer.deserializeBytes()".to_string(),
+            _ => format!(
+                "try deserialize_{}(deserializer)",
+                common::mangle_type(format)
+            ),
+        }
+    }
+
+    fn output_serialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "\nfn serialize_{}(value: {}, serializer: anytype) serde.Error!void {{",
+            name,
+            self.quote_type(format0)
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (value) |item| {{
+    try serializer.serializeOptionTag(true);
+    {}
+}} else {{
+    try serializer.serializeOptionTag(false);
+}}
+"#,
+                    self.quote_serialize_value("item", format)
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+try serializer.serializeLen(value.len);
+for (value) |item| {{
+    {}
+}}
+"#,
+                    self.quote_serialize_value("item", format)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+try serializer.serializeLen(value.len);
+const offsets = try serializer.allocator.alloc(usize, value.len);
+defer serializer.allocator.free(offsets);
+for (value, offsets) |entry, *offset| {{
+    offset.* = serializer.getBufferOffset();
+    {}
+    {}
+}}
+try serializer.sortMapEntries(offsets);
+"#,
+                    self.quote_serialize_value("entry.key", key),
+                    self.quote_serialize_value("entry.value", value)
+                )?;
+            }
+
+            Tuple(formats) => {
+                writeln!(self.out)?;
+                if formats.is_empty() {
+                    writeln!(self.out, "_ = value;\n_ = serializer;")?;
+                }
+                for (index, format) in formats.iter().enumerate() {
+                    let expr = format!("value[{}]", index);
+                    writeln!(self.out, "{}", self.quote_serialize_value(&expr, format))?;
+                }
+            }
+
+            TupleArray { content, size: _ } => {
+                write!(
+                    self.out,
+                    r#"
+for (value) |item| {{
+    {}
+}}
+"#,
+                    self.quote_serialize_value("item", content),
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_deserialization_helper(&mut self, name: &str, format0: &Format) -> Result<()> {
+        use Format::*;
+
+        write!(
+            self.out,
+            "\nfn deserialize_{}(deserializer: anytype) serde.Error!{} {{",
+            name,
+            self.quote_type(format0),
+        )?;
+        self.out.indent();
+        match format0 {
+            Option(format) => {
+                write!(
+                    self.out,
+                    r#"
+if (!try deserializer.deserializeOptionTag()) return null;
+return {};
+"#,
+                    self.quote_deserialize(format),
+                )?;
+            }
+
+            Seq(format) => {
+                write!(
+                    self.out,
+                    r#"
+const length = try deserializer.deserializeLen();
+const obj = try deserializer.alloc({}, length);
+for (obj) |*item| {{
+    item.* = {};
+}}
+return obj;
+"#,
+                    self.quote_type(format),
+                    self.quote_deserialize(format)
+                )?;
+            }
+
+            Map { key, value } => {
+                write!(
+                    self.out,
+                    r#"
+const length = try deserializer.deserializeLen();
+const obj = try deserializer.alloc(serde.Entry({}, {}), length);
+var previous_key: serde.Slice = undefined;
+for (obj, 0..) |*entry, i| {{
+    var key = serde.Slice{{ .start = deserializer.getBufferOffset(), .end = 0 }};
+    entry.key = {};
+    key.end = deserializer.getBufferOffset();
+    if (i > 0) try deserializer.checkThatKeySlicesAreIncreasing(previous_key, key);
+    previous_key = key;
+    entry.value = {};
+}}
+return obj;
+"#,
+                    self.quote_type(key),
+                    self.quote_type(value),
+                    self.quote_deserialize(key),
+                    self.quote_deserialize(value),
+                )?;
+            }
+
+            Tuple(formats) => {
+                writeln!(self.out)?;
+                if formats.is_empty() {
+                    writeln!(self.out, "_ = deserializer;\nreturn .{{}};")?;
+                } else {
+                    writeln!(
+                        self.out,
+                        "var obj: {} = undefined;",
+                        self.quote_type(format0)
+                    )?;
+                    for (index, format) in formats.iter().enumerate() {
+                        writeln!(
+                            self.out,
+                            "obj[{}] = {};",
+                            index,
+                            self.quote_deserialize(format)
+                        )?;
+                    }
+                    writeln!(self.out, "return obj;")?;
+                }
+            }
+
+            TupleArray { content, size } => {
+                write!(
+                    self.out,
+                    r#"
+var obj: [{}]{} = undefined;
+for (&obj) |*item| {{
+    item.* = {};
+}}
+return obj;
+"#,
+                    size,
+                    self.quote_type(content),
+                    self.quote_deserialize(content)
+                )?;
+            }
+
+            _ => panic!("unexpected case"),
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_struct_container(&mut self, name: &str, fields: &[Named<Format>]) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(self.out, "pub const {} = struct {{", name)?;
+        self.enter_class(name);
+        for field in fields {
+            self.output_comment(&field.name)?;
+            writeln!(
+                self.out,
+                "{}: {},",
+                self.quote_field(&field.name),
+                self.quote_type(&field.value)
+            )?;
+        }
+        if self.generator.config.serialization {
+            writeln!(
+                self.out,
+                "\npub fn serialize(self: {}, serializer: anytype) serde.Error!void {{",
+                name
+            )?;
+            self.out.indent();
+            if fields.is_empty() {
+                writeln!(self.out, "_ = self;")?;
+            }
+            writeln!(self.out, "try serializer.increaseContainerDepth();")?;
+            for field in fields {
+                let value = format!("self.{}", self.quote_field(&field.name));
+                writeln!(
+                    self.out,
+                    "{}",
+                    self.quote_serialize_value(&value, &field.value)
+                )?;
+            }
+            writeln!(self.out, "serializer.decreaseContainerDepth();")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            writeln!(
+                self.out,
+                "\npub fn deserialize(deserializer: anytype) serde.Error!{} {{",
+                name
+            )?;
+            self.out.indent();
+            writeln!(self.out, "try deserializer.increaseContainerDepth();")?;
+            if fields.is_empty() {
+                writeln!(self.out, "const obj = {}{{}};", name)?;
+            } else {
+                writeln!(self.out, "var obj: {} = undefined;", name)?;
+            }
+            for field in fields {
+                writeln!(
+                    self.out,
+                    "obj.{} = {};",
+                    self.quote_field(&field.name),
+                    self.quote_deserialize(&field.value)
+                )?;
+            }
+            writeln!(self.out, "deserializer.decreaseContainerDepth();")?;
+            writeln!(self.out, "return obj;")?;
+            self.out.unindent();
+            writeln!(self.out, "}}")?;
+
+            for encoding in &self.generator.config.encodings {
+                self.output_serialize_for_encoding(name, *encoding)?;
+                self.output_deserialize_for_encoding(name, *encoding)?;
+            }
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}};")
+    }
+
+    fn output_enum_container(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(self.out)?;
+        self.output_comment(name)?;
+        writeln!(self.out, "pub const {} = union(enum) {{", name)?;
+        self.enter_class(name);
+        for variant in variants.values() {
+            self.output_comment(&variant.name)?;
+            let variant_name = self.quote_field(&variant.name);
+            match &variant.value {
+                VariantFormat::Unit => writeln!(self.out, "{},", variant_name)?,
+                VariantFormat::NewType(format) => {
+                    writeln!(self.out, "{}: {},", variant_name, self.quote_type(format))?
+                }
+                VariantFormat::Tuple(formats) => writeln!(
+                    self.out,
+                    "{}: {},",
+                    variant_name,
+                    self.quote_tuple_type(formats)
+                )?,
+                VariantFormat::Struct(fields) => {
+                    writeln!(self.out, "{}: struct {{", variant_name)?;
+                    self.enter_class(&variant.name);
+                    for field in fields {
+                        self.output_comment(&field.name)?;
+                        writeln!(
+                            self.out,
+                            "{}: {},",
+                            self.quote_field(&field.name),
+                            self.quote_type(&field.value)
+                        )?;
+                    }
+                    self.leave_class();
+                    writeln!(self.out, "}},")?;
+                }
+                VariantFormat::Variable(_) => panic!("incorrect value"),
+            }
+        }
+        if self.generator.config.serialization {
+            self.output_enum_serialize(name, variants)?;
+            self.output_enum_deserialize(name, variants)?;
+            for encoding in &self.generator.config.encodings {
+                self.output_serialize_for_encoding(name, *encoding)?;
+                self.output_deserialize_for_encoding(name, *encoding)?;
+            }
+        }
+        self.output_custom_code()?;
+        self.leave_class();
+        writeln!(self.out, "}};")
+    }
+
+    /// Fields of the payload of a variant, as pairs of a Zig expression relative to the
+    /// payload and a format.
+    fn variant_fields(&self, variant: &VariantFormat) -> Vec<(String, Format)> {
+        match variant {
+            VariantFormat::Unit => Vec::new(),
+            VariantFormat::NewType(format) => vec![(String::new(), format.as_ref().clone())],
+            VariantFormat::Tuple(formats) => formats
+                .iter()
+                .enumerate()
+                .map(|(index, format)| (format!("[{}]", index), format.clone()))
+                .collect(),
+            VariantFormat::Struct(fields) => fields
+                .iter()
+                .map(|field| {
+                    (
+                        format!(".{}", self.quote_field(&field.name)),
+                        field.value.clone(),
+                    )
+                })
+                .collect(),
+            VariantFormat::Variable(_) => panic!("incorrect value"),
+        }
+    }
+
+    fn output_enum_serialize(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            "\npub fn serialize(self: {}, serializer: anytype) serde.Error!void {{",
+            name
+        )?;
+        self.out.indent();
+        writeln!(self.out, "try serializer.increaseContainerDepth();")?;
+        writeln!(self.out, "switch (self) {{")?;
+        self.out.indent();
+        for (index, variant) in variants {
+            let fields = self.variant_fields(&variant.value);
+            let variant_name = self.quote_field(&variant.name);
+            if fields.is_empty() {
+                writeln!(self.out, ".{} => {{", variant_name)?;
+            } else {
+                writeln!(self.out, ".{} => |value| {{", variant_name)?;
+            }
+            self.out.indent();
+            writeln!(self.out, "try serializer.serializeVariantIndex({});", index)?;
+            for (path, format) in &fields {
+                let value = format!("value{}", path);
+                writeln!(self.out, "{}", self.quote_serialize_value(&value, format))?;
+            }
+            self.out.unindent();
+            writeln!(self.out, "}},")?;
+        }
+        self.out.unindent();
+        writeln!(self.out, "}}")?;
+        writeln!(self.out, "serializer.decreaseContainerDepth();")?;
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_enum_deserialize(
+        &mut self,
+        name: &str,
+        variants: &BTreeMap<u32, Named<VariantFormat>>,
+    ) -> Result<()> {
+        writeln!(
+            self.out,
+            "\npub fn deserialize(deserializer: anytype) serde.Error!{} {{",
+            name
+        )?;
+        self.out.indent();
+        writeln!(self.out, "try deserializer.increaseContainerDepth();")?;
+        writeln!(
+            self.out,
+            "const index = try deserializer.deserializeVariantIndex();"
+        )?;
+        if variants.is_empty() {
+            writeln!(self.out, "_ = index;\nreturn error.UnknownVariant;")?;
+            self.out.unindent();
+            return writeln!(self.out, "}}");
+        }
+        writeln!(self.out, "const obj: {} = switch (index) {{", name)?;
+        self.out.indent();
+        for (index, variant) in variants {
+            let variant_name = self.quote_field(&variant.name);
+            match &variant.value {
+                VariantFormat::Unit => writeln!(self.out, "{} => .{},", index, variant_name)?,
+                VariantFormat::NewType(format) => writeln!(
+                    self.out,
+                    "{} => .{{ .{} = {} }},",
+                    index,
+                    variant_name,
+                    self.quote_deserialize(format)
+                )?,
+                VariantFormat::Tuple(formats) if formats.is_empty() => {
+                    writeln!(self.out, "{} => .{{ .{} = .{{}} }},", index, variant_name)?
+                }
+                VariantFormat::Struct(fields) if fields.is_empty() => {
+                    writeln!(self.out, "{} => .{{ .{} = .{{}} }},", index, variant_name)?
+                }
+                value => {
+                    writeln!(self.out, "{} => blk: {{", index)?;
+                    self.out.indent();
+                    writeln!(
+                        self.out,
+                        "var value: std.meta.TagPayload({}, .{}) = undefined;",
+                        name, variant_name
+                    )?;
+                    for (path, format) in self.variant_fields(value) {
+                        writeln!(
+                            self.out,
+                            "value{} = {};",
+                            path,
+                            self.quote_deserialize(&format)
+                        )?;
+                    }
+                    writeln!(self.out, "break :blk .{{ .{} = value }};", variant_name)?;
+                    self.out.unindent();
+                    writeln!(self.out, "}},")?;
+                }
+            }
+        }
+        writeln!(self.out, "else => return error.UnknownVariant,")?;
+        self.out.unindent();
+        writeln!(self.out, "}};")?;
+        writeln!(self.out, "deserializer.decreaseContainerDepth();")?;
+        writeln!(self.out, "return obj;")?;
+        self.out.unindent();
+        writeln!(self.out, "}}")
+    }
+
+    fn output_serialize_for_encoding(&mut self, name: &str, encoding: Encoding) -> Result<()> {
+        write!(
+            self.out,
+            r#"
+pub fn {1}Serialize(self: {0}, allocator: std.mem.Allocator) serde.Error![]u8 {{
+    var serializer = {1}.Serializer.init(allocator);
+    defer serializer.deinit();
+    try self.serialize(&serializer);
+    return serializer.toOwnedSlice();
+}}
+"#,
+            name,
+            encoding.name(),
+        )
+    }
+
+    fn output_deserialize_for_encoding(&mut self, name: &str, encoding: Encoding) -> Result<()> {
+        write!(
+            self.out,
+            r#"
+pub fn {1}Deserialize(allocator: std.mem.Allocator, input: []const u8) serde.Error!{0} {{
+    var deserializer = {1}.Deserializer.init(allocator, input);
+    const obj = try {0}.deserialize(&deserializer);
+    try deserializer.finish();
+    return obj;
+}}
+"#,
+            name,
+            encoding.name(),
+        )
+    }
+
+    fn output_container(&mut self, name: &str, format: &ContainerFormat) -> Result<()> {
+        use ContainerFormat::*;
+        let fields = match format {
+            UnitStruct => Vec::new(),
+            NewTypeStruct(format) => vec![Named {
+                name: "value".to_string(),
+                value: format.as_ref().clone(),
+            }],
+            TupleStruct(formats) => vec![Named {
+                name: "value".to_string(),
+                value: Format::Tuple(formats.clone()),
+            }],
+            Struct(fields) => fields.clone(),
+            Enum(variants) => return self.output_enum_container(name, variants),
+        };
+        self.output_struct_container(name, &fields)
+    }
+}
+
+/// Installer for generated source files in Zig.
+pub struct Installer {
+    install_dir: PathBuf,
+    manifest: bool,
+}
+
+impl Installer {
+    pub fn new(install_dir: PathBuf) -> Self {
+        Installer {
+            install_dir,
+            manifest: false,
+        }
+    }
+
+    /// Whether to write a `build.zig` file exposing the generated module. Default: false.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    fn create_source_file(&self, name: &str) -> Result<std::fs::File> {
+        std::fs::create_dir_all(&self.install_dir)?;
+        std::fs::File::create(self.install_dir.join(name))
+    }
+}
+
+impl crate::SourceInstaller for Installer {
+    type Error = Box<dyn std::error::Error>;
+
+    fn install_module(
+        &self,
+        config: &CodeGeneratorConfig,
+        registry: &Registry,
+    ) -> std::result::Result<(), Self::Error> {
+        let generator = CodeGenerator::try_new(config)?;
+        let file_name = format!("{}.zig", config.module_name);
+        let mut file = self.create_source_file(&file_name)?;
+        generator.output(&mut file, registry)?;
+        config.format_source(Language::Zig, &self.install_dir.join(&file_name))?;
+        if self.manifest {
+            let mut file = self.create_source_file("build.zig")?;
+            write!(
+                file,
+                r#"const std = @import("std");
+
+pub fn build(b: *std.Build) void {{
+    _ = b.addModule("{0}", .{{
+        .root_source_file = b.path("{0}.zig"),
+    }});
+}}
+"#,
+                config.module_name
+            )?;
+        }
+        Ok(())
+    }
+
+    fn install_serde_runtime(&self) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_source_file("serde.zig")?;
+        write!(file, "{}", include_str!("../runtime/zig/serde.zig"))?;
+        Ok(())
+    }
+
+    fn install_bincode_runtime(&self) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_source_file("bincode.zig")?;
+        write!(file, "{}", include_str!("../runtime/zig/bincode.zig"))?;
+        Ok(())
+    }
+
+    fn install_bcs_runtime(&self) -> std::result::Result<(), Self::Error> {
+        let mut file = self.create_source_file("bcs.zig")?;
+        write!(file, "{}", include_str!("../runtime/zig/bcs.zig"))?;
+        Ok(())
+    }
+}
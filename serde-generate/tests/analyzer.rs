@@ -96,3 +96,90 @@ fn test_on_larger_registry() {
         ]
     );
 }
+
+#[test]
+fn test_filter_registry() {
+    let registry = test_utils::get_registry().unwrap();
+    let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+    // Included containers come with their dependencies.
+    let filtered =
+        analyzer::filter_registry(&registry, &names(&["OtherTypes"]), &names(&[])).unwrap();
+    assert_eq!(
+        filtered.keys().collect::<Vec<_>>(),
+        vec!["OtherTypes", "Struct"]
+    );
+
+    // Excluding containers that no kept container references.
+    let filtered = analyzer::filter_registry(
+        &registry,
+        &names(&[]),
+        &names(&["List", "SerdeData", "Tree"]),
+    )
+    .unwrap();
+    assert!(!filtered.contains_key("SerdeData"));
+    assert_eq!(filtered.len(), registry.len() - 3);
+
+    // Excluding a dependency of a kept container.
+    assert_eq!(
+        analyzer::filter_registry(&registry, &names(&["SerdeData"]), &names(&["Struct"]))
+            .unwrap_err(),
+        analyzer::FilterError::ExcludedDependency {
+            container: "SerdeData".to_string(),
+            dependency: "Struct".to_string(),
+        }
+    );
+    assert_eq!(
+        analyzer::filter_registry(&registry, &names(&["Unknown"]), &names(&[])).unwrap_err(),
+        analyzer::FilterError::UnknownContainer("Unknown".to_string())
+    );
+}
+
+#[test]
+fn test_field_offsets() {
+    use serde_reflection::{ContainerFormat, Format::*, Named, Registry};
+    let fields = |fields: Vec<(&str, serde_reflection::Format)>| {
+        ContainerFormat::Struct(
+            fields
+                .into_iter()
+                .map(|(name, value)| Named {
+                    name: name.to_string(),
+                    value,
+                })
+                .collect(),
+        )
+    };
+    let registry: Registry = btreemap! {
+        "Point".to_string() => fields(vec![("x", I32), ("y", I32)]),
+        "Flags".to_string() => ContainerFormat::NewTypeStruct(Box::new(U64)),
+        "Header".to_string() => fields(vec![
+            ("version", U8),
+            ("origin", TypeName("Point".into())),
+            ("id", TupleArray { content: Box::new(U8), size: 16 }),
+            ("flags", TypeName("Flags".into())),
+            ("tag", Tuple(vec![U16, Unit])),
+        ]),
+        "Message".to_string() => fields(vec![("header", TypeName("Header".into())), ("body", Bytes)]),
+        "Chain".to_string() => fields(vec![("next", TypeName("Chain".into()))]),
+    };
+
+    assert_eq!(
+        analyzer::field_offsets(&registry, "Header"),
+        Some(vec![
+            ("version".to_string(), 0),
+            ("origin".to_string(), 1),
+            ("id".to_string(), 9),
+            ("flags".to_string(), 25),
+            ("tag".to_string(), 33),
+        ])
+    );
+    assert_eq!(
+        analyzer::bcs_fixed_size(&registry, &TypeName("Header".into())),
+        Some(35)
+    );
+    // Variable-size fields, recursive structs, and other containers have no offsets.
+    assert_eq!(analyzer::field_offsets(&registry, "Message"), None);
+    assert_eq!(analyzer::field_offsets(&registry, "Chain"), None);
+    assert_eq!(analyzer::field_offsets(&registry, "Flags"), None);
+    assert_eq!(analyzer::field_offsets(&registry, "Unknown"), None);
+}
@@ -0,0 +1,105 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::{cached_generate, validation::Language, CodeGeneratorConfig};
+use std::{cell::Cell, path::Path};
+use tempfile::tempdir;
+
+#[test]
+fn test_cached_generate() {
+    let dir = tempdir().unwrap();
+    let cache_dir = dir.path().join("cache");
+    let target_dir = dir.path().join("out");
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let calls = Cell::new(0);
+    let generate_in =
+        |target_dir: &Path, language, config: &CodeGeneratorConfig, registry, options| {
+            cached_generate(
+                &cache_dir,
+                target_dir,
+                language,
+                config,
+                registry,
+                options,
+                || {
+                    calls.set(calls.get() + 1);
+                    std::fs::create_dir_all(target_dir)?;
+                    std::fs::write(target_dir.join(format!("{:?}.txt", language)), "code")
+                },
+            )
+            .unwrap()
+        };
+    let generate = |language, config: &CodeGeneratorConfig, registry, options| {
+        generate_in(&target_dir, language, config, registry, options)
+    };
+
+    assert!(generate(Language::Python3, &config, &registry, ""));
+    assert!(!generate(Language::Python3, &config, &registry, ""));
+    assert_eq!(calls.get(), 1);
+
+    // Each language, module, and target directory has its own stamp.
+    assert!(generate(Language::Rust, &config, &registry, ""));
+    let other_config = CodeGeneratorConfig::new("other".to_string());
+    assert!(generate(Language::Python3, &other_config, &registry, ""));
+    let other_dir = dir.path().join("other");
+    assert!(generate_in(
+        &other_dir,
+        Language::Python3,
+        &config,
+        &registry,
+        ""
+    ));
+    assert!(!generate_in(
+        &dir.path().join("other/../out"),
+        Language::Python3,
+        &config,
+        &registry,
+        ""
+    ));
+
+    // Missing outputs invalidate the cache.
+    std::fs::remove_file(target_dir.join("Python3.txt")).unwrap();
+    assert!(generate(Language::Python3, &config, &registry, ""));
+    assert!(!generate(Language::Python3, &config, &registry, ""));
+    assert_eq!(calls.get(), 5);
+
+    // Any change of the inputs invalidates the cache.
+    let config = config.with_c_style_enums(true);
+    assert!(generate(Language::Python3, &config, &registry, ""));
+    assert!(generate(
+        Language::Python3,
+        &config,
+        &registry,
+        "--json-tags"
+    ));
+    let registry = test_utils::get_registry().unwrap();
+    assert!(generate(
+        Language::Python3,
+        &config,
+        &registry,
+        "--json-tags"
+    ));
+    assert!(!generate(
+        Language::Python3,
+        &config,
+        &registry,
+        "--json-tags"
+    ));
+    assert_eq!(calls.get(), 8);
+
+    // Failed generations are not recorded.
+    let config = config.with_c_style_enums(false);
+    let result = cached_generate(
+        &cache_dir,
+        &target_dir,
+        Language::Python3,
+        &config,
+        &registry,
+        "",
+        || Err(std::io::Error::new(std::io::ErrorKind::Other, "failed")),
+    );
+    assert!(result.is_err());
+    assert!(generate(Language::Python3, &config, &registry, ""));
+}
@@ -2,20 +2,53 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{cpp, CodeGeneratorConfig, Encoding};
+use serde_generate::{cpp, CodeGeneratorConfig, Encoding, KeywordEscaping, SourceInstaller};
+use serde_reflection::Registry;
 use std::{collections::BTreeMap, fs::File, io::Write, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break",
+    "case", "catch", "char", "char16_t", "char32_t", "class", "compl", "const", "constexpr",
+    "const_cast", "continue", "decltype", "default", "delete", "do", "double", "dynamic_cast",
+    "else", "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if",
+    "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq", "nullptr",
+    "operator", "or", "or_eq", "private", "protected", "public", "register", "reinterpret_cast",
+    "return", "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct",
+    "switch", "template", "this", "thread_local", "throw", "true", "try", "typedef", "typeid",
+    "typename", "union", "unsigned", "using", "virtual", "void", "volatile", "wchar_t", "while",
+    "xor", "xor_eq",
+];
+
 fn test_that_cpp_code_compiles_with_config(
     config: &CodeGeneratorConfig,
 ) -> (TempDir, std::path::PathBuf) {
-    let registry = test_utils::get_registry().unwrap();
+    test_that_cpp_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_that_cpp_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
+    test_that_cpp_code_compiles_with_generator(
+        &cpp::CodeGenerator::try_new(config).unwrap(),
+        registry,
+    )
+}
+
+fn test_that_cpp_code_compiles_with_generator(
+    generator: &cpp::CodeGenerator,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir = tempdir().unwrap();
     let header_path = dir.path().join("test.hpp");
     let mut header = File::create(&header_path).unwrap();
 
-    let generator = cpp::CodeGenerator::new(config);
-    generator.output(&mut header, &registry).unwrap();
+    generator.output(&mut header, registry).unwrap();
 
     let source_path = dir.path().join("test.cpp");
     let mut source = File::create(&source_path).unwrap();
@@ -51,8 +84,9 @@ fn test_that_cpp_code_compiles() {
 
 #[test]
 fn test_that_cpp_code_compiles_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_cpp_code_compiles_with_config(&config);
 }
 
@@ -118,7 +152,7 @@ fn test_cpp_code_with_external_definitions() {
     definitions.insert("pkg::foo".to_string(), vec!["Tree".to_string()]);
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_external_definitions(definitions);
-    let generator = cpp::CodeGenerator::new(&config);
+    let generator = cpp::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut header, &registry).unwrap();
 
     let content = std::fs::read_to_string(&header_path).unwrap();
@@ -161,9 +195,10 @@ fn test_that_cpp_code_links() {
     let header_path = dir.path().join("test.hpp");
     let mut header = File::create(header_path).unwrap();
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
-    let generator = cpp::CodeGenerator::new(&config);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let generator = cpp::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut header, &registry).unwrap();
 
     let source_path = dir.path().join("lib.cpp");
@@ -230,3 +265,130 @@ int main() {{
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_that_cpp_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    let (_dir, header_path) =
+        test_that_cpp_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(header_path).unwrap();
+    assert!(content.contains("uint8_t class_;"));
+}
+
+#[test]
+fn test_that_cpp_code_compiles_without_comparisons() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = cpp::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_comparisons(false);
+    let (_dir, header_path) = test_that_cpp_code_compiles_with_generator(
+        &generator,
+        &test_utils::get_registry().unwrap(),
+    );
+    let content = std::fs::read_to_string(header_path).unwrap();
+    assert!(!content.contains("operator=="));
+    assert!(!content.contains("std::hash"));
+}
+
+#[test]
+fn test_cpp_variant_enums_check_class_names() {
+    let registry: Registry = serde_yaml::from_str(
+        r#"---
+Foo:
+  ENUM:
+    0:
+      Bar: UNIT
+Foo_Bar: UNITSTRUCT
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = cpp::CodeGenerator::try_new(&config).unwrap();
+    assert!(generator.validate(&registry).is_empty());
+
+    let generator = generator.with_variant_enums(true);
+    let issues = generator.validate(&registry);
+    assert_eq!(
+        issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect::<Vec<_>>(),
+        vec!["type names: `Foo::Bar` and `Foo_Bar` both map to the identifier `Foo_Bar`"]
+    );
+    assert!(generator.output(&mut Vec::new(), &registry).is_err());
+}
+
+#[test]
+fn test_that_cpp_code_compiles_with_zero_copy() {
+    for variant_enums in [false, true] {
+        let config =
+            CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+        let generator = cpp::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_variant_enums(variant_enums)
+            .with_zero_copy(true);
+        let (_dir, header_path) = test_that_cpp_code_compiles_with_generator(
+            &generator,
+            &test_utils::get_registry().unwrap(),
+        );
+        let content = std::fs::read_to_string(header_path).unwrap();
+        assert!(content.contains("std::string_view"));
+        assert!(content.contains("serde::bytes_view"));
+        assert!(!content.contains("std::string "));
+        // The lifetime of the input is documented, and temporary inputs are rejected.
+        assert!(content.contains(
+            "/// Deserialize without copying strings and bytes: the result holds views into\n"
+        ));
+        assert!(content.contains("/// `input`, which must outlive it.\n"));
+        if variant_enums {
+            assert!(content.contains(
+                "inline SerdeData SerdeData_bincodeDeserialize(serde::bytes_view input) {"
+            ));
+            assert!(content.contains(
+                "SerdeData SerdeData_bincodeDeserialize(std::vector<uint8_t> &&) = delete;"
+            ));
+        } else {
+            assert!(
+                content.contains("static SerdeData bincodeDeserialize(serde::bytes_view input);")
+            );
+            assert!(content.contains(
+                "static SerdeData bincodeDeserialize(std::vector<uint8_t> &&) = delete;"
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_cpp_installer_with_cmake_lists() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let installer = cpp::Installer::new(dir.path().to_path_buf())
+        .with_manifest(true)
+        .with_cmake_namespace("serde_test".to_string());
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+    let cmake_lists = std::fs::read_to_string(dir.path().join("CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("add_library(testing INTERFACE)"));
+    assert!(cmake_lists.contains("add_library(serde_test::testing ALIAS testing)"));
+    assert!(cmake_lists.contains("target_compile_features(testing INTERFACE cxx_std_17)"));
+
+    if which::which("cmake").is_err() {
+        return;
+    }
+    let status = Command::new("cmake")
+        .current_dir(dir.path())
+        .args(["-S", ".", "-B", "build"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = Command::new("cmake")
+        .current_dir(dir.path())
+        .args(["--build", "build"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
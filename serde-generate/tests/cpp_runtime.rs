@@ -3,6 +3,7 @@
 
 use crate::test_utils;
 use crate::test_utils::{Choice, Runtime, Test};
+use heck::CamelCase;
 use serde_generate::{cpp, CodeGeneratorConfig};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
@@ -25,7 +26,7 @@ fn test_cpp_runtime_on_simple_date(runtime: Runtime) {
 
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = cpp::CodeGenerator::new(&config);
+    let generator = cpp::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut header, &registry).unwrap();
 
     let reference = runtime.serialize(&Test {
@@ -40,6 +41,7 @@ fn test_cpp_runtime_on_simple_date(runtime: Runtime) {
         source,
         r#"
 #include <cassert>
+#include <unordered_map>
 #include "test.hpp"
 
 using namespace testing;
@@ -55,6 +57,17 @@ int main() {{
 
     assert(value == value2);
 
+    // Values deserialized independently are equal and may be used as keys.
+    auto value3 = Test::{1}Deserialize(input);
+    assert(value == value3);
+    assert(std::hash<Test>{{}}(value) == std::hash<Test>{{}}(value3));
+    std::unordered_map<Test, int> counts;
+    counts[value] += 1;
+    counts[value2] += 1;
+    counts[value3] += 1;
+    assert(counts.size() == 1);
+    assert(counts[value] == 3);
+
     auto output = value2.{1}Serialize();
 
     assert(input == output);
@@ -94,12 +107,22 @@ int main() {{
 
 #[test]
 fn test_cpp_bcs_runtime_on_supported_types() {
-    test_cpp_runtime_on_supported_types(Runtime::Bcs);
+    test_cpp_runtime_on_supported_types(Runtime::Bcs, false);
 }
 
 #[test]
 fn test_cpp_bincode_runtime_on_supported_types() {
-    test_cpp_runtime_on_supported_types(Runtime::Bincode);
+    test_cpp_runtime_on_supported_types(Runtime::Bincode, false);
+}
+
+#[test]
+fn test_cpp_bcs_runtime_on_supported_types_with_variant_enums() {
+    test_cpp_runtime_on_supported_types(Runtime::Bcs, true);
+}
+
+#[test]
+fn test_cpp_bincode_runtime_on_supported_types_with_variant_enums() {
+    test_cpp_runtime_on_supported_types(Runtime::Bincode, true);
 }
 
 fn quote_bytes(bytes: &[u8]) -> String {
@@ -113,17 +136,33 @@ fn quote_bytes(bytes: &[u8]) -> String {
     )
 }
 
-fn test_cpp_runtime_on_supported_types(runtime: Runtime) {
+fn test_cpp_runtime_on_supported_types(runtime: Runtime, variant_enums: bool) {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let header_path = dir.path().join("test.hpp");
     let mut header = File::create(header_path).unwrap();
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = cpp::CodeGenerator::new(&config);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
+    let generator = cpp::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_variant_enums(variant_enums);
     generator.output(&mut header, &registry).unwrap();
 
+    // `SerdeData` is an enum, hence a `std::variant` without member functions in this mode.
+    let (deserialize, serialize) = if variant_enums {
+        (
+            format!("SerdeData_{}Deserialize", runtime.name()),
+            format!("SerdeData_{}Serialize(value)", runtime.name()),
+        )
+    } else {
+        (
+            format!("SerdeData::{}Deserialize", runtime.name()),
+            format!("value.{}Serialize()", runtime.name()),
+        )
+    };
+
     let positive_encodings: Vec<_> = runtime
         .get_positive_samples()
         .iter()
@@ -145,6 +184,7 @@ fn test_cpp_runtime_on_supported_types(runtime: Runtime) {
 #include <exception>
 #include <iostream>
 #include <cassert>
+#include <unordered_map>
 #include "test.hpp"
 
 using namespace testing;
@@ -154,14 +194,17 @@ int main() {{
     std::vector<std::vector<uint8_t>> negative_inputs = {{{1}}};
     try {{
         for (auto input: positive_inputs) {{
-            auto value = SerdeData::{2}Deserialize(input);
-            auto output = value.{2}Serialize();
+            auto value = {2}(input);
+            auto output = {3};
             assert(input == output);
 
             // Test self-equality for the Serde value.
             {{
-                auto value2 = SerdeData::{2}Deserialize(input);
+                auto value2 = {2}(input);
                 assert(value == value2);
+                std::unordered_map<decltype(value), int> keys;
+                keys[value] = 1;
+                assert(keys.count(value2) == 1);
             }}
 
             // Test simple mutations of the input.
@@ -169,7 +212,7 @@ int main() {{
                 auto input2 = input;
                 input2[i] ^= 0x81;
                 try {{
-                    auto value2 = SerdeData::{2}Deserialize(input2);
+                    auto value2 = {2}(input2);
                     assert(!(value2 == value));
                 }} catch (serde::deserialization_error e) {{
                     // All good
@@ -183,7 +226,7 @@ int main() {{
 
         for (auto input: negative_inputs) {{
             try {{
-                SerdeData::{2}Deserialize(input);
+                {2}(input);
                 printf("Input should fail to deserialize:");
                 for (auto x : input) {{
                     printf(" %d", x);
@@ -203,7 +246,8 @@ int main() {{
 "#,
         positive_encodings.join(", "),
         negative_encodings.join(", "),
-        runtime.name(),
+        deserialize,
+        serialize,
     )
     .unwrap();
 
@@ -223,3 +267,135 @@ int main() {{
     let status = Command::new(dir.path().join("test")).status().unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_cpp_bcs_runtime_with_zero_copy() {
+    test_cpp_runtime_with_zero_copy(Runtime::Bcs);
+}
+
+#[test]
+fn test_cpp_bincode_runtime_with_zero_copy() {
+    test_cpp_runtime_with_zero_copy(Runtime::Bincode);
+}
+
+fn test_cpp_runtime_with_zero_copy(runtime: Runtime) {
+    let registry = test_utils::get_document_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    // The same definitions, with owning containers and with views.
+    for (name, zero_copy) in [("owning", false), ("zero_copy", true)] {
+        let mut header = File::create(dir.path().join(format!("{}.hpp", name))).unwrap();
+        let config =
+            CodeGeneratorConfig::new(name.to_string()).with_encodings(vec![runtime.into()]);
+        let generator = cpp::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_zero_copy(zero_copy);
+        generator.output(&mut header, &registry).unwrap();
+    }
+
+    let reference = runtime.serialize(&test_utils::get_document_sample());
+
+    let source_path = dir.path().join("test.cpp");
+    let mut source = File::create(&source_path).unwrap();
+    writeln!(
+        source,
+        r#"
+#include <algorithm>
+#include <cassert>
+#include <type_traits>
+#include "owning.hpp"
+#include "zero_copy.hpp"
+
+// Whether deserialization accepts temporary buffers, which views would outlive.
+template <typename T, typename = void>
+struct accepts_temporaries : std::false_type {{}};
+
+template <typename T>
+struct accepts_temporaries<
+    T, std::void_t<decltype(T::{1}Deserialize(std::vector<uint8_t>{{}}))>>
+    : std::true_type {{}};
+
+static_assert(accepts_temporaries<owning::Document>::value);
+static_assert(!accepts_temporaries<zero_copy::Document>::value);
+
+int main() {{
+    std::vector<uint8_t> input = {{{0}}};
+    auto owned = owning::Document::{1}Deserialize(input);
+    auto viewed = zero_copy::Document::{1}Deserialize(input);
+
+    // Strings and bytes are not copied: they point into the input.
+    auto in_input = [&input](const void *data) {{
+        auto byte = static_cast<const uint8_t *>(data);
+        return byte >= input.data() && byte <= input.data() + input.size();
+    }};
+    assert(in_input(viewed.title.data()));
+    assert(in_input(viewed.digest.data()));
+    assert(in_input(viewed.subtitle->data()));
+    for (const auto &paragraph : viewed.paragraphs) {{
+        for (const auto &line : paragraph) {{
+            assert(in_input(line.data()));
+        }}
+    }}
+    for (const auto &entry : viewed.attachments) {{
+        assert(in_input(entry.first.data()));
+        assert(in_input(entry.second.data()));
+    }}
+
+    // Both modes read the same values.
+    assert(viewed.title == owned.title);
+    assert(viewed.digest == owned.digest);
+    assert(*viewed.subtitle == *owned.subtitle);
+    assert(viewed.paragraphs.size() == owned.paragraphs.size());
+    for (size_t i = 0; i < owned.paragraphs.size(); i++) {{
+        assert(std::equal(viewed.paragraphs[i].begin(), viewed.paragraphs[i].end(),
+                          owned.paragraphs[i].begin(), owned.paragraphs[i].end()));
+    }}
+    assert(viewed.attachments.size() == owned.attachments.size());
+    auto entry = owned.attachments.begin();
+    for (const auto &viewed_entry : viewed.attachments) {{
+        assert(viewed_entry.first == entry->first);
+        assert(viewed_entry.second == entry->second);
+        entry++;
+    }}
+
+    // Both modes write the same bytes.
+    assert(owned.{1}Serialize() == input);
+    assert(viewed.{1}Serialize() == input);
+    assert(zero_copy::Document::{1}Deserialize(input) == viewed);
+    assert(std::hash<zero_copy::Document>{{}}(viewed) ==
+           std::hash<zero_copy::Document>{{}}(zero_copy::Document::{1}Deserialize(input)));
+
+    // Views cannot be taken from a deserializer owning its input.
+    auto deserializer = serde::{2}Deserializer(std::vector<uint8_t>(input));
+    try {{
+        serde::Deserializable<zero_copy::Document>::deserialize(deserializer);
+    }} catch (const serde::deserialization_error &) {{
+        return 0;
+    }}
+    return 1;
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("0x{:02x}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-o")
+        .arg(dir.path().join("test"))
+        .arg("-I")
+        .arg("runtime/cpp")
+        .arg(source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new(dir.path().join("test")).status().unwrap();
+    assert!(status.success());
+}
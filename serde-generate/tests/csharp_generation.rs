@@ -2,10 +2,23 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{csharp, CodeGeneratorConfig, Encoding};
+use serde_generate::{csharp, CodeGeneratorConfig, Encoding, KeywordEscaping};
+use serde_reflection::Registry;
 use std::{collections::BTreeMap, process::Command, sync::Mutex};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked", "class",
+    "const", "continue", "decimal", "default", "delegate", "do", "double", "else", "enum", "event",
+    "explicit", "extern", "false", "finally", "fixed", "float", "for", "foreach", "goto", "if",
+    "implicit", "in", "int", "interface", "internal", "is", "lock", "long", "namespace", "new",
+    "null", "object", "operator", "out", "override", "params", "private", "protected", "public",
+    "readonly", "ref", "return", "sbyte", "sealed", "short", "sizeof", "stackalloc", "static",
+    "string", "struct", "switch", "this", "throw", "true", "try", "typeof", "uint", "ulong",
+    "unchecked", "unsafe", "ushort", "using", "virtual", "void", "volatile", "while",
+];
+
 lazy_static::lazy_static! {
     // `dotnet build` spuriously fails on linux if run concurrently
     static ref MUTEX: Mutex<()> = Mutex::new(());
@@ -13,15 +26,32 @@ lazy_static::lazy_static! {
 
 fn test_that_csharp_code_compiles_with_config(
     config: &CodeGeneratorConfig,
+) -> (TempDir, std::path::PathBuf) {
+    test_that_csharp_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_that_csharp_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
+    test_that_csharp_code_compiles_with_installer(csharp::Installer::new, config, registry)
+}
+
+fn test_that_csharp_code_compiles_with_installer(
+    installer: impl FnOnce(std::path::PathBuf) -> csharp::Installer,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
 ) -> (TempDir, std::path::PathBuf) {
     use serde_generate::SourceInstaller;
 
-    let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
     let dir_path = dir.path().to_path_buf();
 
-    let installer = csharp::Installer::new(dir_path.clone());
-    installer.install_module(config, &registry).unwrap();
+    let installer = installer(dir_path.clone());
+    installer.install_module(config, registry).unwrap();
     installer.install_serde_runtime().unwrap();
     installer.install_bincode_runtime().unwrap();
     installer.install_bcs_runtime().unwrap();
@@ -60,15 +90,17 @@ fn test_that_csharp_code_compiles_with_c_style_enums() {
 
 #[test]
 fn test_that_csharp_code_compiles_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("Generated".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("Generated".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_csharp_code_compiles_with_config(&config);
 }
 
 #[test]
 fn test_that_csharp_code_compiles_with_bcs_and_extra_nesting() {
     let config = CodeGeneratorConfig::new("My.Generated.Project".to_string())
-        .with_encodings(vec![Encoding::Bcs]);
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_csharp_code_compiles_with_config(&config);
 }
 
@@ -104,7 +136,7 @@ fn test_csharp_code_with_external_definitions() {
     definitions.insert("foo".to_string(), vec!["TraitHelpers".to_string()]);
     let config =
         CodeGeneratorConfig::new("Generated".to_string()).with_external_definitions(definitions);
-    let generator = csharp::CodeGenerator::new(&config);
+    let generator = csharp::CodeGenerator::try_new(&config).unwrap();
 
     generator
         .write_source_files(dir.path().to_path_buf(), &registry)
@@ -114,3 +146,64 @@ fn test_csharp_code_with_external_definitions() {
     let content = std::fs::read_to_string(dir.path().join("Generated/SerdeData.cs")).unwrap();
     assert!(content.contains("foo.TraitHelpers."));
 }
+
+#[test]
+fn test_that_csharp_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("Generated".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    let (_dir, path) = test_that_csharp_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(path.join("Keywords.cs")).unwrap();
+    assert!(content.contains(" namespace_;"));
+}
+
+#[test]
+fn test_that_csharp_code_compiles_with_defensive_copies() {
+    let config = CodeGeneratorConfig::new("Generated".to_string());
+    let (_dir, path) = test_that_csharp_code_compiles_with_installer(
+        |dir| csharp::Installer::new(dir).with_defensive_copies(true),
+        &config,
+        &test_utils::get_registry().unwrap(),
+    );
+    let content = std::fs::read_to_string(path.join("OtherTypes.cs")).unwrap();
+    assert!(content.contains("f_bytes = new Serde.ValueArray<byte>(_f_bytes.ToArray());"));
+    assert!(content.contains("f_string = _f_string;"));
+}
+
+#[test]
+fn test_csharp_code_with_nullable_annotations() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("Generated".to_string());
+    let generator = csharp::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_nullable_annotations(true);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let content = std::fs::read_to_string(dir.path().join("Generated/OtherTypes.cs")).unwrap();
+    assert!(content.contains("\n#nullable enable\n"));
+    // Optional references are annotated, other optional values keep using `Serde.Option`.
+    assert!(content.contains("public Struct? f_option;"));
+    assert!(content.contains("public Serde.ValueArray<int>? f_opt_seq;"));
+    assert!(content.contains("if (!Equals(f_option, other.f_option)) return false;"));
+    assert!(content.contains("public bool Equals(OtherTypes? other) {"));
+    assert!(content.contains("if (_f_string == null) throw new ArgumentNullException"));
+    let content = std::fs::read_to_string(dir.path().join("Generated/PrimitiveTypes.cs")).unwrap();
+    assert!(content.contains("public Serde.Option<float> f_f32;"));
+    let content = std::fs::read_to_string(dir.path().join("Generated/TraitHelpers.cs")).unwrap();
+    assert!(content.contains("if (value is {} val) {"));
+    assert!(content.contains("public static Struct? deserialize_option_Struct("));
+
+    // Without the option, the generated code does not change.
+    let dir = tempdir().unwrap();
+    csharp::CodeGenerator::try_new(&config)
+        .unwrap()
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+    let content = std::fs::read_to_string(dir.path().join("Generated/OtherTypes.cs")).unwrap();
+    assert!(!content.contains("#nullable"));
+    assert!(content.contains("public Serde.Option<Struct> f_option;"));
+    assert!(content.contains("public bool Equals(OtherTypes other) {"));
+}
@@ -58,6 +58,18 @@ fn dotnet_build(proj_dir: &Path) {
     assert!(status.success());
 }
 
+/// Build the project, failing on nullability warnings.
+fn dotnet_build_with_nullable_warnings_as_errors(proj_dir: &Path) {
+    let _lock = MUTEX.lock();
+    let status = Command::new("dotnet")
+        .arg("build")
+        .arg("-p:WarningsAsErrors=nullable")
+        .current_dir(proj_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 fn run_nunit(proj_dir: &Path) {
     let _lock = MUTEX.lock();
     let status = Command::new("dotnet")
@@ -138,6 +150,7 @@ fn test_csharp_runtime_on_simple_data(dir: PathBuf, runtime: Runtime) {
     match runtime {
         Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
         Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
+        Runtime::Postcard => unreachable!("C# does not support postcard"),
     }
     installer.install_module(&config, &registry).unwrap();
 
@@ -198,16 +211,91 @@ namespace Testing {{
     run_nunit(&test_dir);
 }
 
+#[test]
+fn test_csharp_bincode_runtime_on_float_edges() {
+    let (dir, _tmp) = create_test_dir("test_csharp_bincode_runtime_on_float_edges");
+    let registry = test_utils::get_float_edges_registry().unwrap();
+    let test_dir = make_test_project(&dir, Runtime::Bincode, "Testing", "FloatEdgesData").unwrap();
+    let config = CodeGeneratorConfig::new("FloatEdgesData".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()]);
+
+    let installer = csharp::Installer::new(dir);
+    installer.install_serde_runtime().unwrap();
+    installer.install_bincode_runtime().unwrap();
+    installer.install_module(&config, &registry).unwrap();
+
+    let reference = Runtime::Bincode.serialize(&test_utils::get_float_edges_sample());
+
+    let mut source = File::create(test_dir.join("TestRuntime.cs")).unwrap();
+    writeln!(
+        source,
+        r#"
+using System;
+using NUnit.Framework;
+using FloatEdgesData;
+
+namespace Testing {{
+    [TestFixture]
+    public class TestFloatEdges {{
+        [Test]
+        public void TestBitPatterns() {{
+            byte[] input = new byte[] {{{0}}};
+            uint[] singles = new uint[] {{{1}}};
+            ulong[] doubles = new ulong[] {{{2}}};
+
+            FloatEdges value = FloatEdges.BincodeDeserialize(input);
+            for (int i = 0; i < singles.Length; i++) {{
+                Assert.AreEqual(singles[i], (uint)BitConverter.SingleToInt32Bits(value.singles[i]));
+            }}
+            for (int i = 0; i < doubles.Length; i++) {{
+                Assert.AreEqual(doubles[i], (ulong)BitConverter.DoubleToInt64Bits(value.doubles[i]));
+            }}
+
+            byte[] output = value.BincodeSerialize();
+            CollectionAssert.AreEqual(input, output);
+        }}
+    }}
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", *x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F32_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F64_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    dotnet_build(&test_dir);
+    run_nunit(&test_dir);
+}
+
 #[test]
 fn test_csharp_bcs_runtime_on_supported_types() {
     let (dir, _tmp) = create_test_dir("test_csharp_bcs_runtime_on_supported_types");
-    test_csharp_runtime_on_supported_types(dir, Runtime::Bcs);
+    test_csharp_runtime_on_supported_types(dir, Runtime::Bcs, false);
+}
+
+#[test]
+fn test_csharp_bcs_runtime_on_supported_types_with_nullable_annotations() {
+    let (dir, _tmp) =
+        create_test_dir("test_csharp_bcs_runtime_on_supported_types_with_nullable_annotations");
+    test_csharp_runtime_on_supported_types(dir, Runtime::Bcs, true);
 }
 
 #[test]
 fn test_csharp_bincode_runtime_on_supported_types() {
     let (dir, _tmp) = create_test_dir("test_csharp_bincode_runtime_on_supported_types");
-    test_csharp_runtime_on_supported_types(dir, Runtime::Bincode);
+    test_csharp_runtime_on_supported_types(dir, Runtime::Bincode, false);
 }
 
 fn quote_bytes(bytes: &[u8]) -> String {
@@ -221,18 +309,29 @@ fn quote_bytes(bytes: &[u8]) -> String {
     )
 }
 
-fn test_csharp_runtime_on_supported_types(dir: PathBuf, runtime: Runtime) {
+fn test_csharp_runtime_on_supported_types(
+    dir: PathBuf,
+    runtime: Runtime,
+    nullable_annotations: bool,
+) {
     let registry = test_utils::get_registry().unwrap();
     let test_dir = make_test_project(&dir, runtime, "Testing", "Data").unwrap();
-    let config = CodeGeneratorConfig::new("Data".to_string()).with_encodings(vec![runtime.into()]);
+    let config = CodeGeneratorConfig::new("Data".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
 
-    let installer = csharp::Installer::new(dir);
+    let installer =
+        csharp::Installer::new(dir.clone()).with_nullable_annotations(nullable_annotations);
     installer.install_serde_runtime().unwrap();
     match runtime {
         Runtime::Bincode => installer.install_bincode_runtime().unwrap(),
         Runtime::Bcs => installer.install_bcs_runtime().unwrap(),
+        Runtime::Postcard => unreachable!("C# does not support postcard"),
     }
     installer.install_module(&config, &registry).unwrap();
+    if nullable_annotations {
+        dotnet_build_with_nullable_warnings_as_errors(&dir.join("Data"));
+    }
 
     let positive_encodings = runtime
         .get_positive_samples_quick()
@@ -326,3 +425,61 @@ namespace Testing {{
     dotnet_build(&test_dir);
     run_nunit(&test_dir);
 }
+
+#[test]
+fn test_csharp_bcs_runtime_with_defensive_copies() {
+    let (dir, _tmp) = create_test_dir("test_csharp_bcs_runtime_with_defensive_copies");
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"---
+Blob:
+  STRUCT:
+    - data: BYTES
+"#,
+    )
+    .unwrap();
+    let test_dir = make_test_project(&dir, Runtime::Bcs, "Testing", "Blobs").unwrap();
+    let config =
+        CodeGeneratorConfig::new("Blobs".to_string()).with_encodings(vec![Runtime::Bcs.into()]);
+
+    let installer = csharp::Installer::new(dir).with_defensive_copies(true);
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+    installer.install_module(&config, &registry).unwrap();
+
+    let mut source = File::create(test_dir.join("TestRuntime.cs")).unwrap();
+    writeln!(
+        source,
+        r#"
+using System;
+using NUnit.Framework;
+using Serde;
+using Blobs;
+
+namespace Testing {{
+    [TestFixture]
+    public class TestDefensiveCopies {{
+        [Test]
+        public void TestMutatedSourceArray() {{
+            byte[] array = new byte[] {{ 1, 2, 3 }};
+            Blob value = new Blob(new ValueArray<byte>(array));
+            byte[] output = value.BcsSerialize();
+            CollectionAssert.AreEqual(new byte[] {{ 3, 1, 2, 3 }}, output);
+
+            // Mutating the source array does not change the value.
+            array[0] = 7;
+            CollectionAssert.AreEqual(output, value.BcsSerialize());
+
+            Blob value2 = Blob.BcsDeserialize(output);
+            Assert.AreEqual(value, value2);
+            Assert.AreEqual(value.GetHashCode(), value2.GetHashCode());
+            CollectionAssert.AreEqual(new byte[] {{ 1, 2, 3 }}, value.data.AsReadOnlyMemory().ToArray());
+        }}
+    }}
+}}
+"#
+    )
+    .unwrap();
+
+    dotnet_build(&test_dir);
+    run_nunit(&test_dir);
+}
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{dart, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_generate::{dart, CodeGeneratorConfig, Encoding, KeywordEscaping, SourceInstaller};
+use serde_reflection::Registry;
 use std::fs::read_to_string;
 use std::{
     io::Result,
@@ -11,6 +12,14 @@ use std::{
 };
 use tempfile::tempdir;
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "assert", "break", "case", "catch", "class", "const", "continue", "default", "do", "else",
+    "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new", "null",
+    "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void", "while",
+    "with",
+];
+
 fn install_test_dependencies(path: &Path) -> Result<()> {
     Command::new("dart")
         .current_dir(path)
@@ -22,10 +31,16 @@ fn install_test_dependencies(path: &Path) -> Result<()> {
 }
 
 fn generate_with_config(source_path: PathBuf, config: &CodeGeneratorConfig) -> PathBuf {
-    let registry = test_utils::get_registry().unwrap();
+    generate_with_config_and_registry(source_path, config, &test_utils::get_registry().unwrap())
+}
 
+fn generate_with_config_and_registry(
+    source_path: PathBuf,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> PathBuf {
     let installer = dart::Installer::new(source_path.clone());
-    installer.install_module(config, &registry).unwrap();
+    installer.install_module(config, registry).unwrap();
     installer.install_serde_runtime().unwrap();
     installer.install_bincode_runtime().unwrap();
     installer.install_bcs_runtime().unwrap();
@@ -53,7 +68,8 @@ fn test_dart_code_compiles() {
 
     let config = CodeGeneratorConfig::new("example".to_string())
         .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
-        .with_c_style_enums(true);
+        .with_c_style_enums(true)
+        .with_allow_nonstandard_floats(true);
 
     generate_with_config(source_path.clone(), &config);
 
@@ -103,7 +119,8 @@ fn test_dart_code_compiles_with_class_enums() {
 
     let config = CodeGeneratorConfig::new("example".to_string())
         .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
-        .with_c_style_enums(false);
+        .with_c_style_enums(false)
+        .with_allow_nonstandard_floats(true);
 
     generate_with_config(source_path, &config);
 }
@@ -115,7 +132,8 @@ fn test_dart_code_compiles_class_enums_for_complex_enums() {
     let config = CodeGeneratorConfig::new("example".to_string())
         .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
         // we enable native Dart enums to test that complex Rust enums will still produce Dart classes
-        .with_c_style_enums(true);
+        .with_c_style_enums(true)
+        .with_allow_nonstandard_floats(true);
 
     generate_with_config(source_path.clone(), &config);
 
@@ -127,3 +145,67 @@ fn test_dart_code_compiles_class_enums_for_complex_enums() {
     assert!(generated_c_style.contains("enum CStyleEnum {"));
     assert!(generated_class_style.contains("abstract class List_ {"));
 }
+
+#[test]
+fn test_dart_installer_preserves_user_files() {
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("dart_incremental_project");
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("example".to_string());
+
+    dart::Installer::new(source_path.clone())
+        .install_module(&config, &registry)
+        .unwrap();
+    let module_path = source_path.join("lib/src/example");
+    assert!(read_to_string(module_path.join("test.dart"))
+        .unwrap()
+        .starts_with("// @generated by serde-generate"));
+
+    let user_file = module_path.join("test_extension.dart");
+    std::fs::write(&user_file, "part of example_types;\n").unwrap();
+    let stale_file = module_path.join("old_type.dart");
+    std::fs::write(
+        &stale_file,
+        "// @generated by serde-generate. Do not edit.\npart of example_types;\n",
+    )
+    .unwrap();
+
+    // Without cleaning, stale files are kept.
+    dart::Installer::new(source_path.clone())
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(stale_file.exists());
+
+    dart::Installer::new(source_path.clone())
+        .with_clean(true)
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(!stale_file.exists());
+    assert_eq!(
+        read_to_string(&user_file).unwrap(),
+        "part of example_types;\n"
+    );
+    assert!(module_path.join("test.dart").exists());
+    assert!(module_path.join("choice.dart").exists());
+    assert!(source_path.join("lib/example.dart").exists());
+
+    // Files without the marker are never overwritten.
+    let user_owned = module_path.join("choice.dart");
+    std::fs::write(&user_owned, "// Custom code\n").unwrap();
+    assert!(dart::Installer::new(source_path.clone())
+        .install_module(&config, &registry)
+        .is_err());
+    assert_eq!(read_to_string(&user_owned).unwrap(), "// Custom code\n");
+}
+
+#[test]
+fn test_dart_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let source_path = tempdir().unwrap().path().join("dart_project");
+    let config = CodeGeneratorConfig::new("example".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    generate_with_config_and_registry(source_path.clone(), &config, &registry);
+    let content =
+        std::fs::read_to_string(source_path.join("lib/src/example/keywords.dart")).unwrap();
+    assert!(content.contains("final int switch_;"));
+}
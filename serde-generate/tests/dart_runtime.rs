@@ -103,6 +103,10 @@ void main() {{
         );
 
         expect(deserializedInstance, equals(expectedInstance));
+        expect(deserializedInstance.hashCode, equals(expectedInstance.hashCode));
+        expect({{deserializedInstance}}.contains(expectedInstance), isTrue);
+        expect(deserializedInstance == expectedInstance.copyWith(a: [4, 7]), isFalse);
+        expect(deserializedInstance == expectedInstance.copyWith(c: ChoiceC(x: 8)), isFalse);
         final serializedBytes = expectedInstance.{0}Serialize();
         expect(serializedBytes, equals(expectedBytes));
     }});
@@ -154,7 +158,8 @@ fn test_dart_runtime_on_supported_types(runtime: Runtime) {
     let registry = test_utils::get_registry().unwrap();
     let config = CodeGeneratorConfig::new("example".to_string())
         .with_encodings(vec![runtime.into()])
-        .with_c_style_enums(false);
+        .with_c_style_enums(false)
+        .with_allow_nonstandard_floats(true);
 
     let installer = dart::Installer::new(source_path.clone());
     installer.install_module(&config, &registry).unwrap();
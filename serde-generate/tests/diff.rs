@@ -0,0 +1,149 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::diff::{diff_registries, ChangeKind};
+use serde_reflection::{ContainerFormat, Format, Named, Registry};
+
+fn parse(yaml: &str) -> Registry {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn report(old: &Registry, new: &Registry) -> Vec<String> {
+    diff_registries(old, new)
+        .iter()
+        .map(|change| format!("{} {}", change.is_breaking(), change))
+        .collect()
+}
+
+#[test]
+fn test_identical_registries() {
+    let registry = test_utils::get_registry().unwrap();
+    assert!(diff_registries(&registry, &registry).is_empty());
+}
+
+#[test]
+fn test_struct_changes() {
+    let old = parse(
+        r#"
+Point:
+  STRUCT:
+    - x: U32
+    - y: U32
+    - z: U32
+"#,
+    );
+    let new = parse(
+        r#"
+Point:
+  STRUCT:
+    - y: U32
+    - x: U64
+    - w: STR
+"#,
+    );
+    assert_eq!(
+        report(&old, &new),
+        vec![
+            "true Point: fields reordered from (x, y) to (y, x)",
+            "true Point.x: format changed from U32 to U64",
+            "true Point.z: field removed",
+            "true Point.w: field added with format Str",
+        ]
+    );
+}
+
+#[test]
+fn test_enum_changes() {
+    let old = parse(
+        r#"
+Shape:
+  ENUM:
+    0:
+      Circle:
+        NEWTYPE: F64
+    1:
+      Square:
+        TUPLE: [F64, F64]
+    2:
+      Empty: UNIT
+"#,
+    );
+    let new = parse(
+        r#"
+Shape:
+  ENUM:
+    0:
+      Circle:
+        NEWTYPE: F64
+    1:
+      Square:
+        TUPLE: [F64, F32]
+    2:
+      Triangle:
+        STRUCT:
+          - base: F64
+    3:
+      Empty:
+        NEWTYPE: STR
+    4:
+      Point: UNIT
+"#,
+    );
+    assert_eq!(
+        report(&old, &new),
+        vec![
+            "true Shape::Square.1: format changed from F64 to F32",
+            "true Shape::Empty: variant moved from index 2 to index 3",
+            "true Shape::Triangle: index 2 was previously used by variant Empty",
+            "false Shape::Point: variant added at index 4",
+        ]
+    );
+}
+
+#[test]
+fn test_container_changes() {
+    let mut old = Registry::new();
+    old.insert(
+        "Id".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::U64)),
+    );
+    old.insert("Unit".to_string(), ContainerFormat::UnitStruct);
+    let mut new = Registry::new();
+    new.insert(
+        "Id".to_string(),
+        ContainerFormat::Struct(vec![Named {
+            name: "value".to_string(),
+            value: Format::U64,
+        }]),
+    );
+    new.insert(
+        "Name".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::Str)),
+    );
+
+    let changes = diff_registries(&old, &new);
+    assert_eq!(
+        changes
+            .iter()
+            .map(|change| &change.kind)
+            .collect::<Vec<_>>(),
+        vec![
+            &ChangeKind::ChangedKind {
+                old: "newtype".to_string(),
+                new: "struct".to_string(),
+            },
+            &ChangeKind::AddedContainer,
+            &ChangeKind::RemovedContainer,
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&changes[0]).unwrap(),
+        serde_json::json!({
+            "path": "Id",
+            "kind": "changed_kind",
+            "old": "newtype",
+            "new": "struct",
+        })
+    );
+}
@@ -0,0 +1,59 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::{
+    facade::AsFrozenRegistry, generate, validation::Language, CodeGeneratorConfig, ConfigError,
+};
+use serde_reflection::Freeze;
+
+const LANGUAGES: [Language; 3] = [Language::Cpp, Language::Python3, Language::Rust];
+
+fn generate_to_string<R>(language: Language, registry: &R) -> String
+where
+    R: AsFrozenRegistry + ?Sized,
+{
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let mut out = Vec::new();
+    generate(language, &config, registry, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_generate_concurrently_from_frozen_registry() {
+    let registry = test_utils::get_registry().unwrap();
+    let frozen = registry.freeze().unwrap();
+
+    let sequential: Vec<_> = LANGUAGES
+        .iter()
+        .map(|language| generate_to_string(*language, &registry))
+        .collect();
+    let handles: Vec<_> = LANGUAGES
+        .iter()
+        .map(|language| {
+            let language = *language;
+            let frozen = frozen.clone();
+            std::thread::spawn(move || generate_to_string(language, &frozen))
+        })
+        .collect();
+    let concurrent: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(sequential, concurrent);
+}
+
+#[test]
+#[cfg(feature = "java")]
+fn test_generate_rejects_several_files() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let error = generate(Language::Java, &config, &registry, &mut Vec::new()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        ConfigError::SingleFile {
+            language: Language::Java
+        }
+        .to_string()
+    );
+}
@@ -0,0 +1,129 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::{
+    python3, rust, validation::Language, CodeGeneratorConfig, FormatterHook, SourceInstaller,
+};
+use std::{
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, Mutex},
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_formatter_hook_receives_each_language() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let calls = Arc::new(Mutex::new(Vec::<(Language, PathBuf)>::new()));
+    let recorded = calls.clone();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_formatter(
+        FormatterHook::new(move |language, path| {
+            assert!(path.exists());
+            recorded
+                .lock()
+                .unwrap()
+                .push((language, path.to_path_buf()));
+            Ok(())
+        }),
+    );
+
+    let dir = tempdir().unwrap();
+    rust::Installer::new(dir.path().join("rust"))
+        .install_module(&config, &registry)
+        .unwrap();
+    let installer = python3::Installer::new(dir.path().join("python"), None);
+    installer.install_module(&config, &registry).unwrap();
+    // Runtimes are not formatted.
+    installer.install_serde_runtime().unwrap();
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            (Language::Rust, dir.path().join("rust/testing/src/lib.rs")),
+            (
+                Language::Python3,
+                dir.path().join("python/testing/__init__.py")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_formatter_command_only_applies_to_its_language() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_formatter(FormatterHook::command(Language::Go, "false", &[]));
+    let dir = tempdir().unwrap();
+    rust::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+}
+
+#[test]
+fn test_failing_formatter_is_an_error() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_formatter(FormatterHook::command(Language::Rust, "false", &[]));
+    let dir = tempdir().unwrap();
+    assert!(rust::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .is_err());
+}
+
+#[test]
+fn test_missing_formatter_is_skipped() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_formatter(
+        FormatterHook::command(Language::Rust, "serde-generate-missing-formatter", &[]),
+    );
+    let dir = tempdir().unwrap();
+    rust::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(dir.path().join("testing/src/lib.rs").exists());
+}
+
+#[test]
+fn test_rustfmt_formatter() {
+    if which::which("rustfmt").is_err() {
+        return;
+    }
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_formatter(FormatterHook::standard());
+    let dir = tempdir().unwrap();
+    rust::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+
+    let status = Command::new("rustfmt")
+        .arg("--check")
+        .arg("--edition")
+        .arg("2018")
+        .arg(dir.path().join("testing/src/lib.rs"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_black_formatter() {
+    if which::which("black").is_err() {
+        return;
+    }
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_formatter(FormatterHook::black());
+    let dir = tempdir().unwrap();
+    python3::Installer::new(dir.path().to_path_buf(), None)
+        .install_module(&config, &registry)
+        .unwrap();
+
+    let status = Command::new("black")
+        .arg("--check")
+        .arg(dir.path().join("testing/__init__.py"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -3,11 +3,18 @@
 
 use crate::test_utils;
 use serde::{Deserialize, Serialize};
-use serde_generate::{golang, CodeGeneratorConfig, Encoding};
+use serde_generate::{golang, CodeGeneratorConfig, Encoding, KeywordEscaping, NonAsciiNames};
 use serde_reflection::{Registry, Result, Samples, Tracer, TracerConfig};
 use std::{collections::BTreeMap, fs::File, io::Write, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for",
+    "func", "go", "goto", "if", "import", "interface", "map", "package", "range", "return",
+    "select", "struct", "switch", "type", "var",
+];
+
 #[derive(Serialize, Deserialize)]
 struct Test {
     a: Vec<u32>,
@@ -44,7 +51,7 @@ fn test_that_golang_code_compiles_with_config_and_registry(
     let source_path = dir.path().join("test.go");
     let mut source = File::create(&source_path).unwrap();
 
-    let generator = golang::CodeGenerator::new(config);
+    let generator = golang::CodeGenerator::try_new(config).unwrap();
     generator.output(&mut source, registry).unwrap();
 
     writeln!(&mut source, "func main() {{}}").unwrap();
@@ -101,7 +108,9 @@ fn test_that_golang_code_compiles_without_serialization() {
 
 #[test]
 fn test_that_golang_code_compiles_with_bcs() {
-    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_golang_code_compiles_with_config(&config);
 }
 
@@ -156,7 +165,7 @@ fn test_golang_code_with_external_definitions() {
     definitions.insert("foo".to_string(), vec!["Tree".to_string()]);
     let config =
         CodeGeneratorConfig::new("main".to_string()).with_external_definitions(definitions);
-    let generator = golang::CodeGenerator::new(&config);
+    let generator = golang::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     // References were updated.
@@ -186,3 +195,39 @@ fn test_that_golang_code_compiles_with_custom_code() {
     assert!(content.contains("// custom1"));
     assert!(content.contains("// custom2"));
 }
+
+#[test]
+fn test_that_golang_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    let (_dir, source_path) =
+        test_that_golang_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("Func uint8"));
+}
+
+#[test]
+fn test_that_golang_code_compiles_with_transliterated_names() {
+    let registry = test_utils::get_non_ascii_registry().unwrap();
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_non_ascii_names(NonAsciiNames::Transliterate);
+    let (_dir, path) = test_that_golang_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(path).unwrap();
+    assert!(content.contains("type CafU00E9 struct {\n\tPrU00e9nom string\n"));
+    assert!(content.contains("type U6CE8U6587__U30b3u30fcu30d2u30fc struct {\n\tU91cf uint8\n"));
+}
+
+#[test]
+fn test_golang_enums_are_sealed_interfaces() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config = CodeGeneratorConfig::new("main".to_string());
+    let (_dir, path) = test_that_golang_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(path).unwrap();
+    assert!(content.contains("type Choice interface {\n\tisChoice()\n"));
+    assert!(content.contains("type Choice__C struct {\n\tX uint8\n}"));
+    assert!(content.contains("func (*Choice__C) isChoice() {}"));
+    assert!(content.contains("serializer.SerializeVariantIndex(2)"));
+    assert!(content
+        .contains("func DeserializeChoice(deserializer serde.Deserializer) (Choice, error) {"));
+}
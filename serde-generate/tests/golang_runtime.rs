@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use crate::test_utils::{Choice, Runtime, Test};
+use crate::test_utils::{Choice, LargeEnumValue, Runtime, Test};
 use heck::CamelCase;
+use serde::{Deserialize, Serialize};
 use serde_generate::{golang, CodeGeneratorConfig};
+use serde_reflection::{Tracer, TracerConfig};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
 
@@ -48,7 +50,7 @@ fn test_golang_runtime_on_simple_data(runtime: Runtime) {
                 .into_iter()
                 .collect(),
         );
-    let generator = golang::CodeGenerator::new(&config);
+    let generator = golang::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let reference = runtime.serialize(&Test {
@@ -72,6 +74,19 @@ func main() {{
 	}}
 	if !cmp.Equal(value, value2) {{ panic("value != value2") }}
 
+	switch c := value.C.(type) {{
+	case *Choice__C:
+		if c.X != 7 {{ panic("unexpected field value") }}
+	default:
+		panic("unexpected variant")
+	}}
+
+	choiceBytes, err := value.C.{1}Serialize()
+	if err != nil {{ panic("failed to serialize choice") }}
+	choice, err := {1}DeserializeChoice(choiceBytes)
+	if err != nil {{ panic("failed to deserialize choice") }}
+	if !cmp.Equal(choice, value.C) {{ panic("choice != value.C") }}
+
 	output, err := value2.{1}Serialize()
 	if err != nil {{ panic("failed to serialize") }}
 	if !cmp.Equal(input, output) {{ panic("input != output") }}
@@ -126,6 +141,268 @@ func main() {{
     assert!(status.success());
 }
 
+#[test]
+fn test_golang_bincode_runtime_on_float_edges() {
+    let registry = test_utils::get_float_edges_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.go");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()])
+        .with_external_definitions(
+            vec![("bytes".to_string(), vec![]), ("math".to_string(), vec![])]
+                .into_iter()
+                .collect(),
+        );
+    let generator = golang::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = Runtime::Bincode.serialize(&test_utils::get_float_edges_sample());
+
+    writeln!(
+        source,
+        r#"
+func main() {{
+	input := []byte{{{0}}}
+	singles := []uint32{{{1}}}
+	doubles := []uint64{{{2}}}
+	value, err := BincodeDeserializeFloatEdges(input)
+	if err != nil {{ panic("failed to deserialize") }}
+
+	for i, bits := range singles {{
+		if math.Float32bits(value.Singles[i]) != bits {{ panic("incorrect f32 bit pattern") }}
+	}}
+	for i, bits := range doubles {{
+		if math.Float64bits(value.Doubles[i]) != bits {{ panic("incorrect f64 bit pattern") }}
+	}}
+
+	output, err := value.BincodeSerialize()
+	if err != nil {{ panic("failed to serialize") }}
+	if !bytes.Equal(input, output) {{ panic("input != output") }}
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F32_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F64_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("mod")
+        .arg("init")
+        .arg("testing")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let runtime_mod_path = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("../../../serde-generate/runtime/golang");
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("mod")
+        .arg("edit")
+        .arg("-replace")
+        .arg(format!(
+            "github.com/novifinancial/serde-reflection/serde-generate/runtime/golang={}",
+            runtime_mod_path.to_str().unwrap()
+        ))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("run")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[derive(Serialize, Deserialize)]
+struct Account {
+    user_id: u32,
+    display_name: String,
+    is_active: bool,
+}
+
+#[test]
+fn test_golang_json_tags() {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Account>().unwrap();
+    let registry = tracer.registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.go");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_serialization(false)
+        .with_external_definitions(
+            vec![("encoding/json".to_string(), vec![])]
+                .into_iter()
+                .collect(),
+        );
+    let generator = golang::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_json_tags(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = serde_json::to_string(&Account {
+        user_id: 7,
+        display_name: "Alice".to_string(),
+        is_active: true,
+    })
+    .unwrap();
+
+    writeln!(
+        source,
+        r#"
+func main() {{
+	value := Account {{ UserId: 7, DisplayName: "Alice", IsActive: true }}
+	output, err := json.Marshal(value)
+	if err != nil {{ panic("failed to marshal") }}
+	if string(output) != {0:?} {{ panic(string(output)) }}
+
+	var value2 Account
+	if err := json.Unmarshal([]byte({0:?}), &value2); err != nil {{ panic("failed to unmarshal") }}
+	if value2 != value {{ panic("value != value2") }}
+}}
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("mod")
+        .arg("init")
+        .arg("testing")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("run")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_golang_bcs_runtime_on_large_enum() {
+    test_golang_runtime_on_large_enum(Runtime::Bcs);
+}
+
+#[test]
+fn test_golang_bincode_runtime_on_large_enum() {
+    test_golang_runtime_on_large_enum(Runtime::Bincode);
+}
+
+fn test_golang_runtime_on_large_enum(runtime: Runtime) {
+    let registry = test_utils::get_large_enum_registry();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.go");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_external_definitions(
+            vec![("github.com/google/go-cmp/cmp".to_string(), vec![])]
+                .into_iter()
+                .collect(),
+        );
+    let generator = golang::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let checks = test_utils::get_large_enum_sample_indices()
+        .into_iter()
+        .map(|index| {
+            let reference = quote_bytes(&runtime.serialize(&LargeEnumValue(index)));
+            let check = if index % 2 == 0 {
+                format!("_, ok := value.(*LargeEnum__V{})", index)
+            } else {
+                format!(
+                    "v, ok := value.(*LargeEnum__V{0}); ok = ok && uint32(*v) == {0}",
+                    index
+                )
+            };
+            format!(
+                r#"
+	{{
+		input := []byte{0}
+		value, err := {1}DeserializeLargeEnum(input)
+		if err != nil {{ panic(fmt.Sprintf("failed to deserialize input: %v", err)) }}
+		{2}
+		if !ok {{ panic(fmt.Sprintf("unexpected value: %v", value)) }}
+		output, err := value.{1}Serialize()
+		if err != nil {{ panic(fmt.Sprintf("failed to serialize: %v", err)) }}
+		if !cmp.Equal(input, output) {{ panic(fmt.Sprintf("input != output:\n  %v\n  %v", input, output)) }}
+	}}"#,
+                reference,
+                runtime.name().to_camel_case(),
+                check,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("");
+
+    writeln!(source, "\nfunc main() {{{}\n}}", checks).unwrap();
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("mod")
+        .arg("init")
+        .arg("testing")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let runtime_mod_path = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("../../../serde-generate/runtime/golang");
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("mod")
+        .arg("edit")
+        .arg("-replace")
+        .arg(format!(
+            "github.com/novifinancial/serde-reflection/serde-generate/runtime/golang={}",
+            runtime_mod_path.to_str().unwrap()
+        ))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("go")
+        .current_dir(dir.path())
+        .arg("run")
+        .arg(source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_golang_bcs_runtime_on_supported_types() {
     test_golang_runtime_on_supported_types(Runtime::Bcs);
@@ -159,8 +436,9 @@ fn test_golang_runtime_on_supported_types(runtime: Runtime) {
             vec![("github.com/google/go-cmp/cmp".to_string(), vec![])]
                 .into_iter()
                 .collect(),
-        );
-    let generator = golang::CodeGenerator::new(&config);
+        )
+        .with_allow_nonstandard_floats(true);
+    let generator = golang::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let positive_encodings = runtime
@@ -2,19 +2,50 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{java, CodeGeneratorConfig, Encoding};
+use serde_generate::{java, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_reflection::{Registry, WellKnownTypes};
 use std::{collections::BTreeMap, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+    "continue", "default", "do", "double", "else", "enum", "extends", "false", "final", "finally",
+    "float", "for", "goto", "if", "implements", "import", "instanceof", "int", "interface", "long",
+    "native", "new", "null", "package", "private", "protected", "public", "return", "short",
+    "static", "strictfp", "super", "switch", "synchronized", "this", "throw", "throws", "transient",
+    "true", "try", "void", "volatile", "while",
+];
+
 fn test_that_java_code_compiles_with_config(
     config: &CodeGeneratorConfig,
 ) -> (TempDir, std::path::PathBuf) {
-    let registry = test_utils::get_registry().unwrap();
+    test_that_java_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+        WellKnownTypes::new(),
+    )
+}
+
+fn test_that_java_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+    well_known_types: WellKnownTypes,
+) -> (TempDir, std::path::PathBuf) {
+    let generator = java::CodeGenerator::try_new(config)
+        .unwrap()
+        .with_well_known_types(well_known_types);
+    test_that_java_code_compiles_with_generator(&generator, registry)
+}
+
+fn test_that_java_code_compiles_with_generator(
+    generator: &java::CodeGenerator,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir = tempdir().unwrap();
 
-    let generator = java::CodeGenerator::new(config);
     generator
-        .write_source_files(dir.path().to_path_buf(), &registry)
+        .write_source_files(dir.path().to_path_buf(), registry)
         .unwrap();
 
     let paths = std::iter::empty()
@@ -48,10 +79,44 @@ fn test_that_java_code_compiles_without_serialization() {
     test_that_java_code_compiles_with_config(&config);
 }
 
+#[test]
+fn test_that_java_code_compiles_with_deserialization_only() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode])
+        .with_serialization(false)
+        .with_deserialization(true);
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains("public static SerdeData bincodeDeserialize(byte[] input)"));
+    assert!(!content.contains("serialize(com.novi.serde.Serializer serializer)"));
+    assert!(!content.contains("bincodeSerialize"));
+    let helpers = std::fs::read_to_string(path.join("TraitHelpers.java")).unwrap();
+    assert!(helpers.contains(" deserialize_"));
+    assert!(!helpers.contains(" serialize_"));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_serialization_only() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode])
+        .with_size_estimators(true)
+        .with_deserialization(false);
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains("public byte[] bincodeSerialize()"));
+    assert!(content.contains("public int estimatedBincodeSize()"));
+    assert!(!content.contains("deserialize"));
+    assert!(!content.contains("load(com.novi.serde.Deserializer deserializer)"));
+    let helpers = std::fs::read_to_string(path.join("TraitHelpers.java")).unwrap();
+    assert!(helpers.contains(" serialize_"));
+    assert!(!helpers.contains(" deserialize_"));
+}
+
 #[test]
 fn test_that_java_code_compiles_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_java_code_compiles_with_config(&config);
 }
 
@@ -62,6 +127,17 @@ fn test_that_java_code_compiles_with_bincode() {
     test_that_java_code_compiles_with_config(&config);
 }
 
+#[test]
+fn test_that_java_code_compiles_with_size_estimators() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
+        .with_size_estimators(true)
+        .with_allow_nonstandard_floats(true);
+    test_that_java_code_compiles_with_config(&config);
+    let (registry, well_known_types) = test_utils::get_schedule_registry().unwrap();
+    test_that_java_code_compiles_with_config_and_registry(&config, &registry, well_known_types);
+}
+
 #[test]
 fn test_that_java_code_compiles_with_comments() {
     let comments = vec![(
@@ -86,6 +162,35 @@ fn test_that_java_code_compiles_with_comments() {
     ));
 }
 
+#[test]
+fn test_that_java_code_compiles_with_variant_field_comments() {
+    let comments = vec![(
+        vec![
+            "testing".to_string(),
+            "SerdeData".to_string(),
+            "StructVariant".to_string(),
+            "f0".to_string(),
+        ],
+        "Some field comments".to_string(),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+
+    // Comment was generated before the field of the variant class.
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains(
+        r#"
+        /**
+         * Some field comments
+         */
+        public final UnitStruct f0;
+"#
+    ));
+}
+
 #[test]
 fn test_java_code_with_external_definitions() {
     let registry = test_utils::get_registry().unwrap();
@@ -96,7 +201,7 @@ fn test_java_code_with_external_definitions() {
     definitions.insert("foo".to_string(), vec!["TraitHelpers".to_string()]);
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_external_definitions(definitions);
-    let generator = java::CodeGenerator::new(&config);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
 
     generator
         .write_source_files(dir.path().to_path_buf(), &registry)
@@ -123,3 +228,62 @@ fn test_that_java_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
     assert!(content.contains("me()"));
 }
+
+#[test]
+fn test_that_java_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, path) = test_that_java_code_compiles_with_config_and_registry(
+        &config,
+        &registry,
+        WellKnownTypes::new(),
+    );
+    let content = std::fs::read_to_string(path.join("Keywords.java")).unwrap();
+    assert!(content.contains(" class_;"));
+}
+
+#[test]
+fn test_that_java_code_compiles_with_tuple_field_names() {
+    let mut names = BTreeMap::new();
+    names.insert(
+        ("SerdeData".to_string(), "TupleVariant".to_string()),
+        vec!["count".to_string(), "total".to_string()],
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_tuple_field_names(names);
+    let (_dir, path) = test_that_java_code_compiles_with_config(&config);
+
+    let content = std::fs::read_to_string(path.join("SerdeData.java")).unwrap();
+    assert!(content.contains("public TupleVariant(@com.novi.serde.Unsigned Integer count, @com.novi.serde.Unsigned Long total)"));
+}
+
+#[test]
+fn test_that_java_installer_rejects_unsupported_encodings() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Postcard])
+        .with_postcard_floats(true);
+    let installer = java::Installer::new(dir.path().to_path_buf());
+    let error = installer
+        .install_everything(&config, &registry)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Java does not support the postcard encoding yet"
+    );
+    // The config is rejected before installing anything.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_that_java_code_compiles_with_defensive_copies() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = java::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_defensive_copies(true);
+    let registry = test_utils::get_registry().unwrap();
+    let (_dir, path) = test_that_java_code_compiles_with_generator(&generator, &registry);
+    let content = std::fs::read_to_string(path.join("OtherTypes.java")).unwrap();
+    assert!(content.contains("this.f_bytes = new com.novi.serde.Bytes(f_bytes.content());"));
+    assert!(content.contains("this.f_string = f_string;"));
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 use crate::test_utils;
 use crate::test_utils::{Choice, Runtime, Test};
+use heck::CamelCase;
 use serde_generate::{java, CodeGeneratorConfig};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
@@ -22,7 +23,7 @@ fn test_java_runtime_on_simple_data(runtime: Runtime) {
 
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = java::CodeGenerator::new(&config);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
     generator
         .write_source_files(dir.path().to_path_buf(), &registry)
         .unwrap();
@@ -116,6 +117,299 @@ public class Main {{
     assert!(status.success());
 }
 
+#[test]
+fn test_java_bcs_runtime_with_length_prefix() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_size_estimators(true)
+        .with_bcs_length_prefix(true);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let content = Runtime::Bcs.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+    let mut reference = (content.len() as u32).to_be_bytes().to_vec();
+    reference.extend(&content);
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import java.util.Arrays;
+import com.novi.serde.DeserializationError;
+import testing.Test;
+
+public class Main {{
+    static String error(byte[] input) {{
+        try {{
+            Test.bcsDeserialize(input);
+        }} catch (DeserializationError e) {{
+            return e.getMessage();
+        }}
+        throw new AssertionError("Input should fail to deserialize");
+    }}
+
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] input = new byte[] {{{0}}};
+
+        Test value = Test.bcsDeserialize(input);
+        assert Arrays.equals(value.bcsSerialize(), input);
+        assert value.estimatedBcsSize() == input.length;
+
+        assert error(Arrays.copyOf(input, 3)).equals("Input is too short for a length prefix");
+        assert error(Arrays.copyOf(input, input.length - 1)).equals("Length prefix does not match the message length");
+        assert error(Arrays.copyOf(input, input.length + 1)).equals("Length prefix does not match the message length");
+
+        byte[] extended = Arrays.copyOf(input, input.length + 1);
+        extended[3] += 1;
+        assert error(extended).equals("Some input bytes were not read");
+    }}
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", *x as i8))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/bcs").unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_java_bcs_runtime_on_durations() {
+    test_java_runtime_on_durations(Runtime::Bcs);
+}
+
+#[test]
+fn test_java_bincode_runtime_on_durations() {
+    test_java_runtime_on_durations(Runtime::Bincode);
+}
+
+fn test_java_runtime_on_durations(runtime: Runtime) {
+    let (registry, well_known_types) = test_utils::get_schedule_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = java::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_well_known_types(well_known_types);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+    assert!(!dir.path().join("testing/Duration.java").exists());
+
+    let reference = runtime.serialize(&test_utils::get_schedule_sample());
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import java.time.Duration;
+import java.util.Optional;
+import com.novi.serde.SerializationError;
+import testing.Schedule;
+
+public class Main {{
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] input = new byte[] {{{0}}};
+
+        Schedule value = Schedule.{1}Deserialize(input);
+        Schedule value2 = new Schedule(
+            Duration.ofSeconds(90061, 500000),
+            Optional.of(Duration.ofMillis(1500)));
+        assert value.equals(value2);
+
+        byte[] output = value2.{1}Serialize();
+        assert java.util.Arrays.equals(input, output);
+
+        try {{
+            new Schedule(Duration.ofSeconds(-1), Optional.empty()).{1}Serialize();
+        }} catch (SerializationError e) {{
+            return;
+        }}
+        assert false;
+    }}
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", *x as i8))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/".to_string() + runtime.name()).unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_java_bincode_runtime_on_float_edges() {
+    let registry = test_utils::get_float_edges_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()]);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let reference = Runtime::Bincode.serialize(&test_utils::get_float_edges_sample());
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import testing.FloatEdges;
+
+public class Main {{
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] input = new byte[] {{{0}}};
+        int[] singles = new int[] {{{1}}};
+        long[] doubles = new long[] {{{2}}};
+
+        FloatEdges value = FloatEdges.bincodeDeserialize(input);
+        for (int i = 0; i < singles.length; i++) {{
+            assert Float.floatToRawIntBits(value.singles.get(i)) == singles[i];
+        }}
+        for (int i = 0; i < doubles.length; i++) {{
+            assert Double.doubleToRawLongBits(value.doubles.get(i)) == doubles[i];
+        }}
+
+        byte[] output = value.bincodeSerialize();
+        assert java.util.Arrays.equals(input, output);
+    }}
+}}
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", *x as i8))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F32_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}", *x as i32))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F64_EDGE_BITS
+            .iter()
+            .map(|x| format!("{}L", *x as i64))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/bincode").unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_java_bcs_runtime_on_supported_types() {
     test_java_runtime_on_supported_types(Runtime::Bcs);
@@ -141,9 +435,11 @@ fn test_java_runtime_on_supported_types(runtime: Runtime) {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = java::CodeGenerator::new(&config);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_size_estimators(true)
+        .with_allow_nonstandard_floats(true);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
     generator
         .write_source_files(dir.path().to_path_buf(), &registry)
         .unwrap();
@@ -181,6 +477,7 @@ public class Main {{
             byte[] output = value.{2}Serialize();
 
             assert java.util.Arrays.equals(input, output);
+            assert value.estimated{3}Size() == input.length;
 
             // Test self-equality for the Serde value.
             {{
@@ -218,6 +515,87 @@ public class Main {{
         positive_encodings.join(", "),
         negative_encodings.join(", "),
         runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/".to_string() + runtime.name()).unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_java_bcs_runtime_on_enum_keyed_maps() {
+    let runtime = Runtime::Bcs;
+    let registry = test_utils::get_keyed_map_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = java::CodeGenerator::try_new(&config).unwrap();
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let reference = runtime.serialize(&test_utils::get_keyed_map_sample());
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import java.util.Arrays;
+import testing.Key;
+import testing.KeyedMap;
+import testing.Value;
+
+public class Main {{
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] input = new byte[] {0};
+
+        KeyedMap value = KeyedMap.{1}Deserialize(input);
+        assert value.entries.size() == 3;
+        assert value.entries.get(new Key.Id(Integer.valueOf(2))).equals(new Value("two"));
+        assert value.entries.get(new Key.Name("a")).equals(new Value("a"));
+        assert value.entries.get(new Key.Pair(Byte.valueOf((byte) 1), Byte.valueOf((byte) 3))).equals(new Value("pair"));
+        assert !value.entries.containsKey(new Key.Id(Integer.valueOf(3)));
+
+        byte[] output = value.{1}Serialize();
+        assert Arrays.equals(input, output);
+    }}
+}}
+"#,
+        quote_bytes(&reference),
+        runtime.name(),
     )
     .unwrap();
 
@@ -281,3 +659,166 @@ fn test_java_bcs_runtime_autotest() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_java_enum_visitors() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_serialization(false);
+    let generator = java::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_enum_visitors(true);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import java.util.Arrays;
+import testing.Choice;
+
+public class Main {{
+    static class Describe implements Choice.Visitor<String> {{
+        public String visitA(Choice.A value) {{
+            return "A";
+        }}
+
+        public String visitB(Choice.B value) {{
+            return "B(" + value.value + ")";
+        }}
+
+        public String visitC(Choice.C value) {{
+            return "C(" + value.x + ")";
+        }}
+    }}
+
+    public static void main(String[] args) throws java.lang.Exception {{
+        Choice[] values = new Choice[] {{
+            new Choice.A(),
+            new Choice.B(Long.valueOf(3)),
+            new Choice.C(Byte.valueOf((byte) 7)),
+        }};
+        String[] expected = new String[] {{ "A", "B(3)", "C(7)" }};
+        String[] output = new String[values.length];
+        for (int i = 0; i < values.length; i++) {{
+            output[i] = values[i].accept(new Describe());
+        }}
+        assert Arrays.equals(output, expected);
+    }}
+}}
+"#
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path())
+        .chain(std::iter::once(dir.path().join("Main.java")));
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_java_runtime_with_defensive_copies() {
+    let registry: serde_reflection::Registry = serde_yaml::from_str(
+        r#"---
+Blob:
+  STRUCT:
+    - data: BYTES
+"#,
+    )
+    .unwrap();
+    let dir = tempdir().unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![serde_generate::Encoding::Bcs]);
+    let generator = java::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_defensive_copies(true);
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    let mut source = File::create(dir.path().join("Main.java")).unwrap();
+    writeln!(
+        source,
+        r#"
+import com.novi.serde.Bytes;
+import testing.Blob;
+
+public class Main {{
+    public static void main(String[] args) throws java.lang.Exception {{
+        byte[] array = new byte[] {{1, 2, 3}};
+        Blob value = new Blob(new Bytes(array));
+        byte[] output = value.bcsSerialize();
+        assert java.util.Arrays.equals(output, new byte[] {{3, 1, 2, 3}});
+
+        // Mutating the source array does not change the value.
+        array[0] = 7;
+        assert java.util.Arrays.equals(value.bcsSerialize(), output);
+        value.data.content()[1] = 7;
+        assert java.util.Arrays.equals(value.bcsSerialize(), output);
+
+        Blob value2 = Blob.bcsDeserialize(output);
+        assert value.equals(value2);
+        assert value.hashCode() == value2.hashCode();
+        assert value.data.asReadOnlyBuffer().isReadOnly();
+    }}
+}}
+"#
+    )
+    .unwrap();
+
+    let paths = std::iter::empty()
+        .chain(std::fs::read_dir("runtime/java/com/novi/serde").unwrap())
+        .chain(std::fs::read_dir("runtime/java/com/novi/bcs").unwrap())
+        .chain(std::fs::read_dir(dir.path().join("testing")).unwrap())
+        .map(|e| e.unwrap().path());
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-d")
+        .arg(dir.path())
+        .args(paths)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("javac")
+        .arg("-Xlint")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("-d")
+        .arg(dir.path())
+        .arg(dir.path().join("Main.java"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = Command::new("java")
+        .arg("-enableassertions")
+        .arg("-cp")
+        .arg(dir.path())
+        .arg("Main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -1,4 +1,5 @@
 mod analyzer;
+mod cache;
 #[cfg(feature = "cpp")]
 mod cpp_generation;
 #[cfg(feature = "cpp")]
@@ -11,6 +12,9 @@ mod csharp_runtime;
 mod dart_generation;
 #[cfg(feature = "dart")]
 mod dart_runtime;
+mod diff;
+#[cfg(all(feature = "cpp", feature = "python3", feature = "rust"))]
+mod facade;
 #[cfg(feature = "golang")]
 mod golang_generation;
 #[cfg(feature = "golang")]
@@ -23,6 +27,12 @@ mod java_runtime;
 mod ocaml_generation;
 #[cfg(feature = "ocaml")]
 mod ocaml_runtime;
+#[cfg(feature = "openapi")]
+mod openapi_generation;
+#[cfg(feature = "php")]
+mod php_generation;
+#[cfg(feature = "php")]
+mod php_runtime;
 #[cfg(feature = "python3")]
 mod python_generation;
 #[cfg(feature = "python3")]
@@ -43,5 +53,12 @@ mod swift_runtime;
 mod typescript_generation;
 #[cfg(feature = "typescript")]
 mod typescript_runtime;
+#[cfg(feature = "zig")]
+mod zig_generation;
+#[cfg(feature = "zig")]
+mod zig_runtime;
 
+#[cfg(all(feature = "python3", feature = "rust"))]
+mod formatter;
 mod test_utils;
+mod validation;
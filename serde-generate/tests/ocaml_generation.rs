@@ -3,16 +3,43 @@
 
 use crate::test_utils;
 use serde_generate::{ocaml, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_reflection::Registry;
 use std::{fs::File, io::Write, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "asr", "begin", "class", "constraint", "do", "done", "downto", "else",
+    "end", "exception", "external", "false", "for", "fun", "function", "functor", "if", "in",
+    "include", "inherit", "initializer", "land", "lazy", "let", "lor", "lsl", "lsr", "lxor",
+    "match", "method", "mod", "module", "mutable", "new", "nonrec", "object", "of", "open", "or",
+    "private", "rec", "sig", "struct", "then", "to", "true", "try", "type", "val", "virtual",
+    "when", "while", "with", "bool", "string", "bytes", "char", "unit", "option", "float", "list",
+    "int32", "int64",
+];
+
 fn test_that_ocaml_code_compiles_with_config(
     config: &CodeGeneratorConfig,
     must_fail: bool,
     more: Option<&str>,
     encoding: Option<Encoding>,
 ) -> (TempDir, std::path::PathBuf) {
-    let registry = test_utils::get_registry().unwrap();
+    test_that_ocaml_code_compiles_with_config_and_registry(
+        config,
+        must_fail,
+        more,
+        encoding,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_that_ocaml_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    must_fail: bool,
+    more: Option<&str>,
+    encoding: Option<Encoding>,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir0 = tempdir().unwrap();
     let dir = dir0.path();
     std::fs::create_dir_all(dir).unwrap();
@@ -24,8 +51,8 @@ fn test_that_ocaml_code_compiles_with_config(
         writeln!(source, "{}", s).unwrap()
     };
 
-    let generator = ocaml::CodeGenerator::new(config);
-    generator.output(&mut source, &registry).unwrap();
+    let generator = ocaml::CodeGenerator::try_new(config).unwrap();
+    generator.output(&mut source, registry).unwrap();
 
     let installer = ocaml::Installer::new(dir.to_path_buf());
     let runtime_str = match encoding {
@@ -37,6 +64,9 @@ fn test_that_ocaml_code_compiles_with_config(
             installer.install_bincode_runtime().unwrap();
             "\n(libraries bincode_runtime)"
         }
+        Some(Encoding::Postcard | Encoding::MessagePack) => {
+            unreachable!("OCaml does not support postcard and msgpack")
+        }
         None => {
             installer.install_serde_runtime().unwrap();
             ""
@@ -86,8 +116,9 @@ fn test_that_ocaml_code_compiles() {
 
 #[test]
 fn test_that_ocaml_code_compiles_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_ocaml_code_compiles_with_config(&config, false, None, Some(Encoding::Bcs));
 }
 
@@ -160,3 +191,53 @@ fn test_that_ocaml_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(source_path).unwrap();
     assert!(content.contains("serde_data_to_string"));
 }
+
+#[test]
+fn test_that_ocaml_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) = test_that_ocaml_code_compiles_with_config_and_registry(
+        &config, false, None, None, &registry,
+    );
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("type_: Stdint.uint8;"));
+}
+
+#[test]
+fn test_that_ocaml_code_with_yojson_converters_compiles() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let mut content = Vec::new();
+    ocaml::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_yojson(true)
+        .output(&mut content, &registry)
+        .unwrap();
+    let content = String::from_utf8(content).unwrap();
+    assert!(
+        content.contains("\nlet rec c_style_enum_to_yojson (x : c_style_enum) : Yojson.Safe.t =\n")
+    );
+    assert!(content.contains("  | CStyleEnum_A -> `String \"A\"\n"));
+    assert!(content.contains("  | `Assoc [(\"NewTypeVariant\", json)] ->\n"));
+    assert!(content.contains("    (\"f_u64\", Serde_yojson.uint64_to_yojson x.f_u64);\n"));
+    assert!(content.contains("  match x with SimpleList_ x -> "));
+
+    if which::which("dune").is_err() {
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let installer = ocaml::Installer::new(dir.path().to_path_buf()).with_yojson(true);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_bcs_runtime().unwrap();
+    let dune = std::fs::read_to_string(dir.path().join("testing/dune")).unwrap();
+    assert!(dune.contains("(libraries bcs_runtime yojson serde_yojson)"));
+    let status = Command::new("dune")
+        .arg("build")
+        .arg("--root")
+        .arg(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
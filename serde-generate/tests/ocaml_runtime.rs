@@ -42,6 +42,7 @@ fn test_ocaml_runtime_on_simple_data(runtime: Runtime) {
             installer.install_bincode_runtime().unwrap();
             "bincode"
         }
+        Runtime::Postcard => unreachable!("OCaml does not support postcard"),
     };
 
     let config =
@@ -79,7 +80,7 @@ fn test_ocaml_runtime_on_simple_data(runtime: Runtime) {
 
     let lib_path = dir_path.join("testing.ml");
     let mut lib = File::create(lib_path).unwrap();
-    let generator = ocaml::CodeGenerator::new(&config);
+    let generator = ocaml::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut lib, &registry).unwrap();
 
     let exe_path = dir_path.join("main.ml");
@@ -157,10 +158,12 @@ fn test_ocaml_runtime_on_supported_types(runtime: Runtime) {
             installer.install_bincode_runtime().unwrap();
             "bincode"
         }
+        Runtime::Postcard => unreachable!("OCaml does not support postcard"),
     };
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
 
     let dir_path = dir.join(config.module_name());
     std::fs::create_dir_all(&dir_path).unwrap();
@@ -190,7 +193,7 @@ fn test_ocaml_runtime_on_supported_types(runtime: Runtime) {
     let source_path = dir_path.join("test.ml");
     println!("{:?}", source_path);
     let mut source = File::create(&source_path).unwrap();
-    let generator = ocaml::CodeGenerator::new(&config);
+    let generator = ocaml::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let positive_encodings: Vec<_> = runtime
@@ -0,0 +1,660 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use serde_generate::{
+    indent::IndentConfig,
+    openapi::{self, OpenApiBytes, OpenApiVersion},
+    CodeGeneratorConfig, SourceInstaller,
+};
+use serde_json::{json, Value};
+use serde_reflection::{Registry, Tracer, TracerConfig};
+use std::collections::{BTreeMap, BTreeSet};
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize)]
+struct Reading {
+    id: Id,
+    label: Option<String>,
+    tags: Vec<char>,
+    raw: ByteBuf,
+    limits: (i8, u64),
+    span: Span,
+    marker: Marker,
+    levels: BTreeMap<String, Option<Level>>,
+    status: Status,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Id(u64);
+
+#[derive(Serialize, Deserialize)]
+struct Span(u16, u16);
+
+#[derive(Serialize, Deserialize)]
+struct Marker;
+
+#[derive(Serialize, Deserialize)]
+enum Level {
+    Low,
+    High(u16),
+}
+
+#[derive(Serialize, Deserialize)]
+enum Status {
+    Idle,
+    Level(Level),
+    Pair(u8, Option<Level>),
+    Detail { code: i64, note: () },
+    Wrapped(Id),
+}
+
+fn get_reading_registry() -> Registry {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Level>().unwrap();
+    tracer.trace_simple_type::<Status>().unwrap();
+    tracer.trace_simple_type::<Reading>().unwrap();
+    tracer.registry().unwrap()
+}
+
+/// One reading per variant of `Status`, including enums nested in newtype variants.
+fn get_reading_samples() -> Vec<Reading> {
+    let statuses = vec![
+        Status::Idle,
+        Status::Level(Level::Low),
+        Status::Level(Level::High(7)),
+        Status::Pair(3, None),
+        Status::Pair(4, Some(Level::High(u16::MAX))),
+        Status::Detail {
+            code: i64::MIN,
+            note: (),
+        },
+        Status::Wrapped(Id(u64::MAX)),
+    ];
+    statuses
+        .into_iter()
+        .enumerate()
+        .map(|(index, status)| Reading {
+            id: Id(index as u64),
+            label: if index % 2 == 0 {
+                None
+            } else {
+                Some("label".to_string())
+            },
+            tags: vec!['a', 'é'],
+            raw: ByteBuf::from(vec![0, 255, index as u8]),
+            limits: (-128, u64::MAX),
+            span: Span(1, 2),
+            marker: Marker,
+            levels: vec![
+                ("low".to_string(), Some(Level::Low)),
+                ("none".to_string(), None),
+            ]
+            .into_iter()
+            .collect(),
+            status,
+        })
+        .collect()
+}
+
+fn generate_document(config: &CodeGeneratorConfig, registry: &Registry, json: bool) -> String {
+    let generator = openapi::CodeGenerator::try_new(config)
+        .unwrap()
+        .with_json(json);
+    generate_document_with(&generator, registry)
+}
+
+fn generate_document_with(generator: &openapi::CodeGenerator, registry: &Registry) -> String {
+    let mut out = Vec::new();
+    generator.output(&mut out, registry).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn get_document(config: &CodeGeneratorConfig, registry: &Registry) -> Value {
+    serde_yaml::from_str(&generate_document(config, registry, false)).unwrap()
+}
+
+fn get_document_with(generator: &openapi::CodeGenerator, registry: &Registry) -> Value {
+    serde_yaml::from_str(&generate_document_with(generator, registry)).unwrap()
+}
+
+fn get_schema<'a>(document: &'a Value, name: &str) -> &'a Value {
+    &document["components"]["schemas"][name]
+}
+
+/// Check `instance` against `schema`, for the keywords used by the generated schemas.
+fn conforms(document: &Value, schema: &Value, instance: &Value) -> bool {
+    check(document, schema, instance, 0)
+}
+
+/// Schemas may refer to themselves without going down the instance, e.g. `SimpleList`, a
+/// newtype of options, so references are followed up to a fixed depth.
+fn check(document: &Value, schema: &Value, instance: &Value, depth: usize) -> bool {
+    if depth > 100 {
+        return false;
+    }
+    let schema = match schema {
+        Value::Bool(accepted) => return *accepted,
+        Value::Object(schema) => schema,
+        _ => panic!("invalid schema {}", schema),
+    };
+    if let Some(reference) = schema.get("$ref") {
+        let name = reference
+            .as_str()
+            .unwrap()
+            .strip_prefix("#/components/schemas/")
+            .unwrap();
+        if !check(document, get_schema(document, name), instance, depth + 1) {
+            return false;
+        }
+    }
+    // OpenAPI 3.0
+    if schema.get("nullable") == Some(&Value::Bool(true)) && instance.is_null() {
+        return true;
+    }
+    let check_all = |key: &str| match schema.get(key) {
+        Some(schemas) => schemas
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|schema| check(document, schema, instance, depth + 1)),
+        None => true,
+    };
+    let count_matches = |key: &str| {
+        schema.get(key).map(|schemas| {
+            schemas
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|schema| check(document, schema, instance, depth + 1))
+                .count()
+        })
+    };
+    if !check_all("allOf")
+        || count_matches("anyOf").map_or(false, |count| count == 0)
+        || count_matches("oneOf").map_or(false, |count| count != 1)
+    {
+        return false;
+    }
+    if let Some(kinds) = schema.get("type") {
+        let kinds = match kinds {
+            Value::Array(kinds) => kinds.clone(),
+            kind => vec![kind.clone()],
+        };
+        let has_kind = |kind: &Value| match kind.as_str().unwrap() {
+            "null" => instance.is_null(),
+            "boolean" => instance.is_boolean(),
+            "integer" => instance.is_i64() || instance.is_u64(),
+            "number" => instance.is_number(),
+            "string" => instance.is_string(),
+            "array" => instance.is_array(),
+            "object" => instance.is_object(),
+            kind => panic!("unexpected type {}", kind),
+        };
+        if !kinds.iter().any(has_kind) {
+            return false;
+        }
+    }
+    if let Some(values) = schema.get("enum") {
+        if !values.as_array().unwrap().contains(instance) {
+            return false;
+        }
+    }
+    let as_integer = |value: &Value| {
+        value
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| value.as_u64().map(i128::from))
+    };
+    if let (Some(minimum), Some(value)) = (schema.get("minimum"), as_integer(instance)) {
+        if value < as_integer(minimum).unwrap() {
+            return false;
+        }
+    }
+    if let (Some(maximum), Some(value)) = (schema.get("maximum"), as_integer(instance)) {
+        if value > as_integer(maximum).unwrap() {
+            return false;
+        }
+    }
+    if let Some(value) = instance.as_str() {
+        let length = value.chars().count() as u64;
+        if schema
+            .get("minLength")
+            .map_or(false, |min| length < min.as_u64().unwrap())
+            || schema
+                .get("maxLength")
+                .map_or(false, |max| length > max.as_u64().unwrap())
+        {
+            return false;
+        }
+    }
+    if let Some(items) = instance.as_array() {
+        let length = items.len() as u64;
+        if schema
+            .get("minItems")
+            .map_or(false, |min| length < min.as_u64().unwrap())
+            || schema
+                .get("maxItems")
+                .map_or(false, |max| length > max.as_u64().unwrap())
+        {
+            return false;
+        }
+        let prefix = schema
+            .get("prefixItems")
+            .map(|prefix| prefix.as_array().unwrap().clone())
+            .unwrap_or_default();
+        for (index, item) in items.iter().enumerate() {
+            let item_schema = match prefix.get(index) {
+                Some(schema) => schema,
+                None => match schema.get("items") {
+                    Some(schema) => schema,
+                    None => continue,
+                },
+            };
+            if !check(document, item_schema, item, depth + 1) {
+                return false;
+            }
+        }
+    }
+    if let Some(entries) = instance.as_object() {
+        if let Some(required) = schema.get("required") {
+            for name in required.as_array().unwrap() {
+                if !entries.contains_key(name.as_str().unwrap()) {
+                    return false;
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, value) in entries {
+            let value_schema = match properties.and_then(|properties| properties.get(key)) {
+                Some(schema) => schema,
+                None => match schema.get("additionalProperties") {
+                    Some(schema) => schema,
+                    None => continue,
+                },
+            };
+            if !check(document, value_schema, value, depth + 1) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Collect the targets of the `$ref` keywords of `value`.
+fn collect_references(value: &Value, references: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(entries) => {
+            for (key, value) in entries {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => {
+                        references.insert(reference.clone());
+                    }
+                    _ => collect_references(value, references),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_references(item, references);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[test]
+fn test_that_openapi_documents_have_one_schema_per_container() {
+    let registry = test_utils::get_registry().unwrap();
+    for version in [OpenApiVersion::V3_0, OpenApiVersion::V3_1] {
+        let config = CodeGeneratorConfig::new("testing".to_string());
+        let generator = openapi::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_openapi_version(version);
+        let document = get_document_with(&generator, &registry);
+
+        // The JSON output is the same document.
+        let generator = generator.with_json(true);
+        let json: Value =
+            serde_json::from_str(&generate_document_with(&generator, &registry)).unwrap();
+        assert_eq!(json, document);
+
+        assert_eq!(
+            document.as_object().unwrap().keys().collect::<Vec<_>>(),
+            vec!["components"]
+        );
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        assert_eq!(
+            schemas.keys().collect::<BTreeSet<_>>(),
+            registry.keys().collect::<BTreeSet<_>>()
+        );
+
+        let mut references = BTreeSet::new();
+        collect_references(&document, &mut references);
+        assert!(references.contains("#/components/schemas/SerdeData"));
+        for reference in references {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "{}", reference);
+        }
+
+        // Externally tagged enums.
+        let variants = get_schema(&document, "SerdeData")["oneOf"]
+            .as_array()
+            .unwrap();
+        assert_eq!(variants.len(), 15);
+        assert_eq!(
+            variants[2],
+            json!({"type": "string", "enum": ["UnitVariant"]})
+        );
+        assert_eq!(
+            variants[3],
+            json!({
+                "type": "object",
+                "properties": {"NewTypeVariant": {"type": "string"}},
+                "required": ["NewTypeVariant"],
+                "additionalProperties": false,
+            })
+        );
+    }
+}
+
+#[test]
+fn test_that_serde_json_values_conform_to_openapi_schemas() {
+    let registry = get_reading_registry();
+    for version in [OpenApiVersion::V3_0, OpenApiVersion::V3_1] {
+        let config = CodeGeneratorConfig::new("testing".to_string());
+        let generator = openapi::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_openapi_version(version);
+        let document = get_document_with(&generator, &registry);
+        let schema = get_schema(&document, "Reading");
+        for sample in get_reading_samples() {
+            let instance = serde_json::to_value(&sample).unwrap();
+            assert!(
+                conforms(&document, schema, &instance),
+                "{:?}: {}",
+                version,
+                instance
+            );
+        }
+    }
+}
+
+#[test]
+fn test_that_serde_json_test_data_conform_to_openapi_schemas() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let document = get_document(&config, &registry);
+    let schema = get_schema(&document, "SerdeData");
+    let mut count = 0;
+    for sample in test_utils::get_sample_values(true, true) {
+        // Skip values that JSON cannot represent, e.g. maps with tuple keys or `u128::MAX`.
+        if let Ok(instance) = serde_json::to_value(&sample) {
+            assert!(conforms(&document, schema, &instance), "{}", instance);
+            count += 1;
+        }
+    }
+    assert!(count > 10);
+}
+
+#[test]
+fn test_that_invalid_values_do_not_conform_to_openapi_schemas() {
+    let registry = get_reading_registry();
+    for version in [OpenApiVersion::V3_0, OpenApiVersion::V3_1] {
+        let config = CodeGeneratorConfig::new("testing".to_string());
+        let generator = openapi::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_openapi_version(version);
+        let document = get_document_with(&generator, &registry);
+        let schema = get_schema(&document, "Reading");
+        let valid = serde_json::to_value(&get_reading_samples()[1]).unwrap();
+        assert!(conforms(&document, schema, &valid));
+
+        let mutations: Vec<(&str, Value)> = vec![
+            ("id", json!(-1)),
+            ("id", json!("1")),
+            ("label", json!(3)),
+            ("tags", json!(["ab"])),
+            ("raw", json!([256])),
+            ("raw", json!("AAE=")),
+            ("limits", json!([-129, 0])),
+            ("limits", json!([0, 0, 0])),
+            ("span", json!([1])),
+            ("marker", json!({})),
+            ("levels", json!({"x": "Medium"})),
+            ("status", json!({"Idle": null})),
+            ("status", json!("Level")),
+            ("status", json!({"Level": {"High": -1}})),
+            ("status", json!({"Level": {"Low": null}})),
+            ("status", json!({"Level": "Low", "Idle": null})),
+            ("status", json!({"Pair": [1]})),
+            ("status", json!({"Detail": {"code": 1}})),
+            ("status", json!({"Unknown": 1})),
+        ];
+        for (field, value) in mutations {
+            let mut instance = valid.clone();
+            instance[field] = value;
+            assert!(
+                !conforms(&document, schema, &instance),
+                "{:?}: {}",
+                version,
+                instance
+            );
+        }
+        let mut instance = valid.clone();
+        instance.as_object_mut().unwrap().remove("label");
+        assert!(!conforms(&document, schema, &instance));
+    }
+}
+
+#[test]
+fn test_openapi_nullable_syntax() {
+    let registry = get_reading_registry();
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = openapi::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_openapi_version(OpenApiVersion::V3_0);
+    let document = get_document_with(&generator, &registry);
+    let fields = &get_schema(&document, "Reading")["properties"];
+    assert_eq!(fields["label"], json!({"type": "string", "nullable": true}));
+    assert_eq!(
+        fields["levels"]["additionalProperties"],
+        json!({"allOf": [{"$ref": "#/components/schemas/Level"}], "nullable": true})
+    );
+    assert_eq!(
+        get_schema(&document, "Marker"),
+        &json!({"type": "object", "nullable": true, "enum": [null]})
+    );
+    assert_eq!(
+        fields["limits"],
+        json!({
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    {"type": "integer", "minimum": -128, "maximum": 127},
+                    {"type": "integer", "minimum": 0, "maximum": u64::MAX},
+                ]
+            },
+            "minItems": 2,
+            "maxItems": 2,
+        })
+    );
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = openapi::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_openapi_version(OpenApiVersion::V3_1);
+    let document = get_document_with(&generator, &registry);
+    let fields = &get_schema(&document, "Reading")["properties"];
+    assert_eq!(fields["label"], json!({"type": ["string", "null"]}));
+    assert_eq!(
+        fields["levels"]["additionalProperties"],
+        json!({"anyOf": [{"$ref": "#/components/schemas/Level"}, {"type": "null"}]})
+    );
+    assert_eq!(get_schema(&document, "Marker"), &json!({"type": "null"}));
+    assert_eq!(
+        fields["limits"],
+        json!({
+            "type": "array",
+            "prefixItems": [
+                {"type": "integer", "minimum": -128, "maximum": 127},
+                {"type": "integer", "minimum": 0, "maximum": u64::MAX},
+            ],
+            "items": false,
+            "minItems": 2,
+            "maxItems": 2,
+        })
+    );
+}
+
+#[test]
+fn test_openapi_bytes() {
+    let registry = get_reading_registry();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let document = get_document(&config, &registry);
+    assert_eq!(
+        get_schema(&document, "Reading")["properties"]["raw"],
+        json!({"type": "array", "items": {"type": "integer", "minimum": 0, "maximum": 255}})
+    );
+
+    let generator = openapi::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_openapi_bytes(OpenApiBytes::Base64);
+    let document = get_document_with(&generator, &registry);
+    assert_eq!(
+        get_schema(&document, "Reading")["properties"]["raw"],
+        json!({"type": "string", "contentEncoding": "base64"})
+    );
+    let generator = generator.with_openapi_version(OpenApiVersion::V3_0);
+    let document = get_document_with(&generator, &registry);
+    assert_eq!(
+        get_schema(&document, "Reading")["properties"]["raw"],
+        json!({"type": "string", "format": "byte"})
+    );
+}
+
+#[test]
+fn test_openapi_descriptions() {
+    let registry = get_reading_registry();
+    let comments = vec![
+        (vec!["testing", "Reading"], "A reading.\n\nWith details."),
+        (vec!["testing", "Reading", "id"], "The \"id\"."),
+        (vec!["testing", "Status", "Idle"], "Nothing."),
+        (vec!["testing", "Status", "Detail", "code"], "A code."),
+    ]
+    .into_iter()
+    .map(|(path, comment)| {
+        (
+            path.into_iter().map(String::from).collect(),
+            comment.to_string(),
+        )
+    })
+    .collect::<BTreeMap<_, _>>();
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments.clone());
+    let generator = openapi::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_openapi_version(OpenApiVersion::V3_0);
+    let document = get_document_with(&generator, &registry);
+    let reading = get_schema(&document, "Reading");
+    assert_eq!(reading["description"], "A reading.\n\nWith details.");
+    // Siblings of `$ref` are ignored in OpenAPI 3.0.
+    assert_eq!(
+        reading["properties"]["id"],
+        json!({"allOf": [{"$ref": "#/components/schemas/Id"}], "description": "The \"id\"."})
+    );
+    let variants = &get_schema(&document, "Status")["oneOf"];
+    assert_eq!(variants[0]["description"], "Nothing.");
+    assert_eq!(
+        variants[3]["properties"]["Detail"]["properties"]["code"]["description"],
+        "A code."
+    );
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+    let generator = openapi::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_openapi_version(OpenApiVersion::V3_1);
+    let document = get_document_with(&generator, &registry);
+    assert_eq!(
+        get_schema(&document, "Reading")["properties"]["id"],
+        json!({"$ref": "#/components/schemas/Id", "description": "The \"id\"."})
+    );
+}
+
+#[test]
+fn test_that_openapi_names_are_quoted_in_yaml() {
+    let registry: Registry = serde_yaml::from_str(
+        r#"---
+Point:
+  STRUCT:
+    - x: U8
+    - y: U8
+    - "no": BOOL
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let text = generate_document(&config, &registry, false);
+    assert!(text.contains("\n        \"y\":\n"));
+    let document: Value = serde_yaml::from_str(&text).unwrap();
+    let instance = json!({"x": 1, "y": 2, "no": true});
+    assert!(conforms(
+        &document,
+        get_schema(&document, "Point"),
+        &instance
+    ));
+}
+
+#[test]
+fn test_that_openapi_documents_are_installed() {
+    let registry = get_reading_registry();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("readings".to_string()).with_external_definitions(
+        vec![("common".to_string(), vec!["Id".to_string()])]
+            .into_iter()
+            .collect(),
+    );
+
+    let installer = openapi::Installer::new(dir.path().to_path_buf());
+    installer.install_everything(&config, &registry).unwrap();
+    let text = std::fs::read_to_string(dir.path().join("readings.yaml")).unwrap();
+    assert!(text.starts_with("components:\n  schemas:\n"));
+    assert!(text.contains("$ref: \"common.yaml#/components/schemas/Id\""));
+
+    let installer = openapi::Installer::new(dir.path().to_path_buf()).with_json(true);
+    installer.install_module(&config, &registry).unwrap();
+    let document: Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join("readings.json")).unwrap()).unwrap();
+    assert_eq!(
+        get_schema(&document, "Reading")["properties"]["id"]["$ref"],
+        "common.json#/components/schemas/Id"
+    );
+    assert!(installer.install_bcs_runtime().is_err());
+}
+
+#[test]
+fn test_openapi_indentation() {
+    let registry = get_reading_registry();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let document = get_document(&config, &registry);
+
+    let config = config.with_indent(IndentConfig::Space(4));
+    let text = generate_document(&config, &registry, false);
+    assert!(text.starts_with("components:\n    schemas:\n        Id:\n"));
+    assert_eq!(serde_yaml::from_str::<Value>(&text).unwrap(), document);
+
+    let config = config.with_indent(IndentConfig::Tab);
+    let text = generate_document(&config, &registry, true);
+    assert!(text.starts_with("{\n\t\"components\": {\n\t\t\"schemas\": {\n"));
+    assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), document);
+
+    let generator = openapi::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "YAML documents cannot be indented with tabs"
+    );
+}
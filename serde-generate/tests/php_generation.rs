@@ -0,0 +1,253 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::{php, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_reflection::Registry;
+use std::{collections::BTreeMap, path::Path, process::Command};
+use tempfile::{tempdir, TempDir};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "array", "class", "echo", "enum", "fn", "function", "list", "match", "new",
+    "readonly", "static", "this", "yield",
+];
+
+/// Check the syntax of the PHP files in `dir`, if PHP is installed.
+fn check_php_syntax(dir: &Path) {
+    if which::which("php").is_err() {
+        return;
+    }
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            check_php_syntax(&path);
+        } else if path.extension().map_or(false, |ext| ext == "php") {
+            let status = Command::new("php").arg("-l").arg(&path).status().unwrap();
+            assert!(status.success());
+        }
+    }
+}
+
+/// Install the generated module and the runtimes in a new directory, then check the syntax
+/// of the code, if PHP is installed.
+fn test_that_php_code_parses_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> TempDir {
+    let dir = tempdir().unwrap();
+    let installer = php::Installer::new(dir.path().to_path_buf());
+    installer.install_everything(config, registry).unwrap();
+    check_php_syntax(dir.path());
+    dir
+}
+
+fn test_that_php_code_parses_with_config(config: &CodeGeneratorConfig) -> TempDir {
+    test_that_php_code_parses_with_config_and_registry(config, &Registry::new());
+    test_that_php_code_parses_with_config_and_registry(
+        config,
+        &test_utils::get_simple_registry().unwrap(),
+    );
+    test_that_php_code_parses_with_config_and_registry(config, &test_utils::get_registry().unwrap())
+}
+
+fn read_class(dir: &TempDir, name: &str) -> String {
+    std::fs::read_to_string(dir.path().join("testing").join(format!("{}.php", name))).unwrap()
+}
+
+#[test]
+fn test_that_php_code_parses() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let dir = test_that_php_code_parses_with_config(&config);
+
+    let content = read_class(&dir, "SerdeData");
+    assert!(content.contains("<?php\n\ndeclare(strict_types=1);\n\nnamespace testing;\n"));
+    assert!(content.contains("\nabstract class SerdeData\n{\n"));
+    assert!(content.contains("        0 => SerdeData__PrimitiveTypes::load($deserializer),\n"));
+
+    let content = read_class(&dir, "SerdeData__UnitVariant");
+    assert!(content.contains("\nfinal class SerdeData__UnitVariant extends SerdeData\n{\n"));
+
+    let content = read_class(&dir, "OtherTypes");
+    assert!(content.contains("        public array $f_stringmap,\n"));
+    assert!(content.contains("        public \\Serde\\Unit $f_unit,\n"));
+    assert!(content.contains("        public ?array $f_opt_seq,\n"));
+
+    let content = read_class(&dir, "PrimitiveTypes");
+    assert!(content.contains("        public int|string $f_u64,\n"));
+    assert!(content.contains("        public string $f_u128,\n"));
+    assert!(content.contains("        public ?float $f_f32,\n"));
+
+    // Enums without data are PHP enums.
+    let content = read_class(&dir, "CStyleEnum");
+    assert!(content.contains("\nenum CStyleEnum: int\n{\n    case A = 0;\n"));
+
+    let content = read_class(&dir, "TraitHelpers");
+    assert!(content.contains("\nfinal class TraitHelpers\n{\n"));
+    assert!(content.contains("    public static function serialize_vector_vector_Struct("));
+}
+
+#[test]
+fn test_that_php_code_parses_without_serialization() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_serialization(false);
+    let dir = test_that_php_code_parses_with_config(&config);
+    assert!(!read_class(&dir, "SerdeData").contains("serialize"));
+    assert!(!dir.path().join("testing/TraitHelpers.php").exists());
+}
+
+#[test]
+fn test_that_php_code_parses_with_bcs() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let dir = test_that_php_code_parses_with_config(&config);
+    let content = read_class(&dir, "SerdeData");
+    assert!(content.contains("    public static function bcsDeserialize(string $input): self\n"));
+    assert!(content.contains("        $serializer = new \\Serde\\Bcs\\BcsSerializer();\n"));
+    assert!(dir.path().join("Serde/BinarySerializer.php").exists());
+    assert!(dir.path().join("Serde/Bcs/BcsDeserializer.php").exists());
+    assert!(!dir.path().join("Serde/Bincode").exists());
+}
+
+#[test]
+fn test_that_php_code_parses_with_bincode() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+    let dir = test_that_php_code_parses_with_config(&config);
+    let content = read_class(&dir, "SerdeData");
+    assert!(content.contains("    public function bincodeSerialize(): string\n"));
+    assert!(dir
+        .path()
+        .join("Serde/Bincode/BincodeSerializer.php")
+        .exists());
+}
+
+#[test]
+fn test_that_php_installer_rejects_postcard() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Postcard]);
+    assert!(php::CodeGenerator::try_new(&config).is_err());
+    let dir = tempdir().unwrap();
+    let installer = php::Installer::new(dir.path().to_path_buf());
+    assert!(installer.install_postcard_runtime().is_err());
+}
+
+#[test]
+fn test_that_php_installer_writes_manifest() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("my_org.testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let dir = tempdir().unwrap();
+    php::Installer::new(dir.path().to_path_buf())
+        .install_everything(&config, &registry)
+        .unwrap();
+    assert!(!dir.path().join("composer.json").exists());
+
+    php::Installer::new(dir.path().to_path_buf())
+        .with_manifest(true)
+        .install_everything(&config, &registry)
+        .unwrap();
+    assert!(dir.path().join("my_org/testing/Test.php").exists());
+    let content = std::fs::read_to_string(dir.path().join("composer.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(manifest["name"], "serde-generate/my_org-testing");
+    assert_eq!(manifest["require"]["php"], ">=8.1");
+    assert_eq!(
+        manifest["autoload"]["psr-4"]["my_org\\testing\\"],
+        "my_org/testing/"
+    );
+    assert_eq!(manifest["autoload"]["psr-4"]["Serde\\"], "Serde/");
+}
+
+#[test]
+fn test_that_php_code_parses_with_comments() {
+    let comments = vec![
+        (
+            vec!["testing".to_string(), "SerdeData".to_string()],
+            "Some\ncomments".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "List".to_string(),
+                "Node".to_string(),
+            ],
+            "Some other comments".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+
+    let dir = test_that_php_code_parses_with_config(&config);
+    assert!(read_class(&dir, "SerdeData")
+        .contains("\n/**\n * Some\n * comments\n */\nabstract class SerdeData\n"));
+    assert!(read_class(&dir, "List__Node")
+        .contains("\n/**\n * Some other comments\n */\nfinal class List__Node extends List_\n"));
+}
+
+#[test]
+fn test_php_code_with_external_definitions() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    // Pretend that "Tree" is external.
+    let mut definitions = BTreeMap::new();
+    definitions.insert("foo.bar".to_string(), vec!["Tree".to_string()]);
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_external_definitions(definitions);
+    let generator = php::CodeGenerator::try_new(&config).unwrap();
+    generator
+        .write_source_files(dir.path().to_path_buf(), &registry)
+        .unwrap();
+
+    // References were updated.
+    let content = std::fs::read_to_string(
+        dir.path()
+            .join("testing/SerdeData__TreeWithMutualRecursion.php"),
+    )
+    .unwrap();
+    assert!(content.contains("        public \\foo\\bar\\Tree $value,\n"));
+    assert!(content.contains("\\foo\\bar\\Tree::deserialize($deserializer)"));
+}
+
+#[test]
+fn test_that_php_code_parses_with_custom_code() {
+    let custom_code = vec![
+        (
+            vec!["testing".to_string(), "SerdeData".to_string()],
+            "// custom1".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "List".to_string(),
+                "Node".to_string(),
+            ],
+            "// custom2".to_string(),
+        ),
+        (
+            vec!["testing".to_string(), "CStyleEnum".to_string()],
+            "// custom3".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_custom_code(custom_code);
+
+    let dir = test_that_php_code_parses_with_config(&config);
+    assert!(read_class(&dir, "SerdeData").contains("\n    // custom1\n}\n"));
+    assert!(read_class(&dir, "List__Node").contains("\n    // custom2\n}\n"));
+    assert!(read_class(&dir, "CStyleEnum").contains("\n    // custom3\n}\n"));
+}
+
+#[test]
+fn test_that_php_code_parses_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let dir = test_that_php_code_parses_with_config_and_registry(&config, &registry);
+    let content = read_class(&dir, "Keywords");
+    assert!(content.contains("        public int $this_,\n"));
+    assert!(content.contains("        $serializer->serializeU8($this->class_);\n"));
+}
@@ -0,0 +1,209 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use crate::test_utils::{Choice, Runtime, Test};
+use serde_generate::{php, CodeGeneratorConfig, SourceInstaller};
+use serde_reflection::Registry;
+use std::{fs::File, io::Write, path::Path, process::Command};
+use tempfile::tempdir;
+
+/// Install the generated module "testing" together with the runtimes, and create a test
+/// script registering an autoloader for them.
+fn install_module_with_script(
+    dir: &Path,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> File {
+    let installer = php::Installer::new(dir.to_path_buf());
+    installer.install_everything(config, registry).unwrap();
+    let mut script = File::create(dir.join("test.php")).unwrap();
+    writeln!(
+        script,
+        r#"<?php
+
+declare(strict_types=1);
+
+spl_autoload_register(function (string $class): void {{
+    $path = __DIR__ . '/' . str_replace('\\', '/', $class) . '.php';
+    if (is_file($path)) {{
+        require $path;
+    }}
+}});
+"#
+    )
+    .unwrap();
+    script
+}
+
+fn run_php_script(dir: &Path) {
+    let status = Command::new("php")
+        .current_dir(dir)
+        .arg("-d")
+        .arg("zend.assertions=1")
+        .arg("test.php")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// PHP string literal holding the given bytes.
+fn quote_bytes(bytes: &[u8]) -> String {
+    format!(
+        "\"{}\"",
+        bytes
+            .iter()
+            .map(|x| format!("\\x{:02x}", x))
+            .collect::<Vec<_>>()
+            .join("")
+    )
+}
+
+#[test]
+fn test_php_bcs_runtime_on_simple_data() {
+    test_php_runtime_on_simple_data(Runtime::Bcs);
+}
+
+#[test]
+fn test_php_bincode_runtime_on_simple_data() {
+    test_php_runtime_on_simple_data(Runtime::Bincode);
+}
+
+fn test_php_runtime_on_simple_data(runtime: Runtime) {
+    if which::which("php").is_err() {
+        return;
+    }
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let mut script = install_module_with_script(dir.path(), &config, &registry);
+
+    let reference = runtime.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+
+    writeln!(
+        script,
+        r#"
+use testing\Choice__C;
+use testing\Test;
+
+$input = {1};
+$value = new Test([4, 6], [-3, 5], new Choice__C(7));
+
+$output = $value->{0}Serialize();
+assert($output === $input);
+
+$value2 = Test::{0}Deserialize($input);
+assert($value2 == $value);
+assert($value2->c->x === 7);
+
+$value3 = new Test([4, 6], [-3, 0], new Choice__C(7));
+$output3 = $value3->{0}Serialize();
+assert(strlen($output3) === strlen($output));
+assert($output3 !== $output);
+
+try {{
+    Test::{0}Deserialize($input . "\x00");
+    assert(false);
+}} catch (\Serde\DeserializationError $e) {{
+}}
+"#,
+        runtime.name(),
+        quote_bytes(&reference),
+    )
+    .unwrap();
+
+    run_php_script(dir.path());
+}
+
+#[test]
+fn test_php_bcs_runtime_on_supported_types() {
+    test_php_runtime_on_supported_types(Runtime::Bcs);
+}
+
+#[test]
+fn test_php_bincode_runtime_on_supported_types() {
+    test_php_runtime_on_supported_types(Runtime::Bincode);
+}
+
+fn test_php_runtime_on_supported_types(runtime: Runtime) {
+    if which::which("php").is_err() {
+        return;
+    }
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
+    let mut script = install_module_with_script(dir.path(), &config, &registry);
+
+    let positive_encodings = runtime
+        .get_positive_samples_quick()
+        .iter()
+        .map(|bytes| quote_bytes(bytes))
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+
+    let negative_encodings = runtime
+        .get_negative_samples()
+        .iter()
+        .map(|bytes| quote_bytes(bytes))
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+
+    writeln!(
+        script,
+        r#"
+use testing\SerdeData;
+
+$positiveInputs = [
+    {0},
+];
+$negativeInputs = [
+    {1},
+];
+
+foreach ($positiveInputs as $input) {{
+    $value = SerdeData::{2}Deserialize($input);
+    $output = $value->{2}Serialize();
+    assert($output === $input);
+
+    // Test self-equality for the Serde value.
+    $value2 = SerdeData::{2}Deserialize($input);
+    assert($value == $value2);
+
+    // Test simple mutations of the input.
+    for ($i = 0; $i < strlen($input); $i++) {{
+        $input2 = $input;
+        $input2[$i] = chr(ord($input2[$i]) ^ 0x80);
+        try {{
+            $value3 = SerdeData::{2}Deserialize($input2);
+        }} catch (\Serde\DeserializationError $e) {{
+            continue;
+        }}
+        assert($value3->{2}Serialize() !== $input);
+    }}
+}}
+
+foreach ($negativeInputs as $input) {{
+    try {{
+        SerdeData::{2}Deserialize($input);
+    }} catch (\Serde\DeserializationError $e) {{
+        continue;
+    }}
+    fwrite(STDERR, 'Input should fail to deserialize: ' . bin2hex($input) . "\n");
+    exit(1);
+}}
+"#,
+        positive_encodings,
+        negative_encodings,
+        runtime.name(),
+    )
+    .unwrap();
+
+    run_php_script(dir.path());
+}
@@ -2,21 +2,52 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{python3, CodeGeneratorConfig, Encoding, SourceInstaller};
-use std::{collections::BTreeMap, fs::File, process::Command};
+use serde_generate::{
+    indent::IndentConfig,
+    python3::{self, PythonModels},
+    CodeGeneratorConfig, Encoding, NonAsciiNames, SourceInstaller,
+};
+use serde_reflection::Registry;
+use std::{collections::BTreeMap, fs::File, io::Write, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
 fn test_that_python_code_parses_with_config(
     config: &CodeGeneratorConfig,
 ) -> (TempDir, std::path::PathBuf) {
-    let registry = test_utils::get_registry().unwrap();
+    test_that_python_code_parses_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_that_python_code_parses_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
+    test_that_python_code_parses_with_generator(
+        &python3::CodeGenerator::try_new(config).unwrap(),
+        registry,
+    )
+}
+
+fn test_that_python_code_parses_with_generator(
+    generator: &python3::CodeGenerator,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir = tempdir().unwrap();
 
     let source_path = dir.path().join("test.py");
     let mut source = File::create(&source_path).unwrap();
 
-    let generator = python3::CodeGenerator::new(config);
-    generator.output(&mut source, &registry).unwrap();
+    generator.output(&mut source, registry).unwrap();
 
     let python_path = format!(
         "{}:runtime/python",
@@ -38,16 +69,147 @@ fn test_that_python_code_parses() {
     test_that_python_code_parses_with_config(&config);
 }
 
+#[test]
+fn test_that_python_code_parses_with_tabs() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
+        .with_allow_nonstandard_floats(true)
+        .with_runtime_schema(true)
+        .with_indent(IndentConfig::Tab);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_json_methods(true);
+    let registry = test_utils::get_registry().unwrap();
+    let (_dir, source_path) = test_that_python_code_parses_with_generator(&generator, &registry);
+    let source = std::fs::read_to_string(source_path).unwrap();
+    assert!(source.contains("\n\tdef bcs_serialize(self) -> bytes:\n\t\treturn bcs.serialize("));
+    assert!(source.lines().all(|line| !line.starts_with(' ')));
+}
+
 #[test]
 fn test_that_python_code_parses_without_serialization() {
     let config = CodeGeneratorConfig::new("testing".to_string()).with_serialization(false);
     test_that_python_code_parses_with_config(&config);
 }
 
+#[test]
+fn test_that_python_code_without_serialization_skips_encodings() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
+        .with_allow_nonstandard_floats(true)
+        .with_serialization(false);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let mut source = Vec::new();
+    generator.output(&mut source, &registry).unwrap();
+    let source = String::from_utf8(source).unwrap();
+    assert!(!source.contains("import bcs"));
+    assert!(!source.contains("import bincode"));
+    assert!(!source.contains("def bcs_serialize"));
+}
+
+#[test]
+fn test_that_python_code_parses_with_one_way_serialization() {
+    for (serialization, deserialization) in [(false, true), (true, false)] {
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_encodings(vec![Encoding::Bcs, Encoding::Bincode])
+            .with_allow_nonstandard_floats(true)
+            .with_serialization(serialization)
+            .with_deserialization(deserialization);
+        let generator = python3::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_json_methods(true);
+        let registry = test_utils::get_registry().unwrap();
+        let (_dir, source_path) =
+            test_that_python_code_parses_with_generator(&generator, &registry);
+        let source = std::fs::read_to_string(source_path).unwrap();
+        assert!(source.contains("import bcs"));
+        assert!(source.contains("    INDEX = 0"));
+        assert_eq!(source.contains("def bcs_serialize(self)"), serialization);
+        assert_eq!(source.contains("def to_json(self)"), serialization);
+        assert_eq!(
+            source.contains("def bcs_deserialize(input: bytes)"),
+            deserialization
+        );
+        assert_eq!(
+            source.contains("def from_json(input: str)"),
+            deserialization
+        );
+    }
+}
+
+#[test]
+fn test_that_python_code_has_stream_methods() {
+    let registry = test_utils::get_records_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let (_dir, source_path) =
+        test_that_python_code_parses_with_config_and_registry(&config, &registry);
+    let source = std::fs::read_to_string(source_path).unwrap();
+    assert!(source.contains(
+        "def bcs_deserialize_stream(reader: typing.BinaryIO) -> typing.Iterator[\"Record\"]:\n        return bcs.deserialize_stream(reader, Records)\n"
+    ));
+    assert!(source.contains("def bincode_deserialize_stream("));
+    // Only newtypes of sequences are streamed.
+    assert_eq!(source.matches("_deserialize_stream(").count(), 2);
+
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let mut stub = Vec::new();
+    generator.output_stub(&mut stub, &registry).unwrap();
+    let stub = String::from_utf8(stub).unwrap();
+    assert!(stub.contains(
+        "def bcs_deserialize_stream(reader: typing.BinaryIO) -> typing.Iterator[\"Record\"]: ..."
+    ));
+
+    let config = config.with_deserialization(false);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let mut source = Vec::new();
+    generator.output(&mut source, &registry).unwrap();
+    assert!(!String::from_utf8(source).unwrap().contains("_stream"));
+}
+
+#[test]
+fn test_python_code_with_base_classes() {
+    let registry = test_utils::get_registry().unwrap();
+    let base_classes = vec![
+        (vec!["testing".to_string()], "mixins.Message".to_string()),
+        (
+            vec!["testing".to_string(), "List".to_string()],
+            "my_package.mixins.Node".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "SerdeData".to_string(),
+                "UnitVariant".to_string(),
+            ],
+            "Unit".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_base_class(base_classes);
+    let mut source = Vec::new();
+    generator.output(&mut source, &registry).unwrap();
+    let source = String::from_utf8(source).unwrap();
+
+    assert!(source.contains("\nimport mixins\nimport my_package.mixins\n"));
+    assert!(source.contains("@dataclass(frozen=True, order=True)\nclass Struct(mixins.Message):\n"));
+    assert!(source.contains("\nclass SerdeData__PrimitiveTypes(SerdeData, mixins.Message):\n"));
+    assert!(source.contains("class SerdeData__UnitVariant(SerdeData, Unit):\n"));
+    assert!(source.contains("class List__Node(List, my_package.mixins.Node):\n"));
+    // Enum classes and C-style enums are not affected.
+    assert!(source.contains("\nclass SerdeData:\n"));
+}
+
 #[test]
 fn test_that_python_code_parses_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_python_code_parses_with_config(&config);
 }
 
@@ -58,6 +220,80 @@ fn test_that_python_code_parses_with_bincode() {
     test_that_python_code_parses_with_config(&config);
 }
 
+#[test]
+fn test_that_python_code_parses_with_postcard() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Postcard])
+        .with_postcard_floats(true);
+    test_that_python_code_parses_with_config(&config);
+}
+
+#[test]
+fn test_that_python_postcard_code_rejects_floats_by_default() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Postcard]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(error.to_string().contains("PrimitiveTypes"));
+
+    let registry = test_utils::get_simple_registry().unwrap();
+    assert!(generator.output(&mut Vec::new(), &registry).is_ok());
+}
+
+#[test]
+fn test_that_python_bcs_code_rejects_floats_by_default() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(error.to_string().contains(
+        "PrimitiveTypes.f_f32: floating-point numbers are not supported by the BCS encoding"
+    ));
+
+    let config = config.with_allow_nonstandard_floats(true);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    assert!(generator.output(&mut Vec::new(), &registry).is_ok());
+
+    // Bincode supports floating-point numbers.
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    assert!(generator.output(&mut Vec::new(), &registry).is_ok());
+}
+
+#[test]
+fn test_that_python_code_parses_with_msgpack() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::MessagePack]);
+    let registry = test_utils::get_simple_registry().unwrap();
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let mut source = Vec::new();
+    generator.output(&mut source, &registry).unwrap();
+    let source = String::from_utf8(source).unwrap();
+    assert!(source.contains("\nimport msgpack\n"));
+    assert!(source.contains("    def msgpack_serialize(self) -> bytes:\n"));
+    // The runtime reads the names of fields and variants from the schema.
+    assert!(source.contains("    SCHEMA = {\"kind\": \"struct\", \"fields\": [[\"a\", "));
+}
+
+#[test]
+fn test_that_python_msgpack_code_rejects_wide_integers() {
+    let registry = test_utils::get_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::MessagePack]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(error.to_string().contains(
+        "PrimitiveTypes.f_u128: integers wider than 64 bits are not supported by the msgpack encoding"
+    ));
+    assert!(!error.to_string().contains("floating-point"));
+}
+
 #[test]
 fn test_that_python_code_parses_with_comments() {
     let comments = vec![
@@ -97,6 +333,81 @@ fn test_that_python_code_parses_with_comments() {
     ));
 }
 
+#[test]
+fn test_that_python_code_parses_with_variant_field_comments() {
+    let comments = vec![(
+        vec![
+            "testing".to_string(),
+            "SerdeData".to_string(),
+            "StructVariant".to_string(),
+            "f0".to_string(),
+        ],
+        "Some field comments".to_string(),
+    )]
+    .into_iter()
+    .collect();
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+
+    // The comment follows the field as an attribute docstring.
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains(
+        r#"
+    f0: "UnitStruct"
+    """Some field comments
+    """
+"#
+    ));
+}
+
+#[test]
+fn test_that_python_code_exposes_runtime_schema() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_runtime_schema(true);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+    writeln!(
+        source,
+        r#"
+assert Struct.SCHEMA == {{"kind": "struct", "fields": [["x", {{"kind": "u32"}}], ["y", {{"kind": "u64"}}]]}}
+assert NewTypeStruct.SCHEMA == {{"kind": "newtype_struct", "format": {{"kind": "u64"}}}}
+assert List.SCHEMA == {{"kind": "enum", "variants": [
+    [0, "Empty", {{"kind": "unit"}}],
+    [1, "Node", {{"kind": "tuple", "formats": [{{"kind": "ref", "name": "SerdeData"}}, {{"kind": "ref", "name": "List"}}]}}],
+]}}
+assert List__Node.SCHEMA is List.SCHEMA
+assert Tree.SCHEMA == {{"kind": "struct", "fields": [
+    ["value", {{"kind": "ref", "name": "SerdeData"}}],
+    ["children", {{"kind": "seq", "format": {{"kind": "ref", "name": "Tree"}}}}],
+]}}
+assert dict(OtherTypes.SCHEMA["fields"])["f_stringmap"] == {{"kind": "map", "key": {{"kind": "str"}}, "value": {{"kind": "u32"}}}}
+
+paths = []
+st.walk_schema(SerdeData.SCHEMA, lambda schema, path: paths.append((path, schema["kind"])))
+assert (["TupleArray", "format"], "array") in paths
+assert (["StructVariant", "f3"], "ref") in paths
+assert (["ComplexMap", "format", "key", "0"], "array") in paths
+"#
+    )
+    .unwrap();
+
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
 #[test]
 fn test_python_code_with_external_definitions() {
     let registry = test_utils::get_registry().unwrap();
@@ -110,7 +421,7 @@ fn test_python_code_with_external_definitions() {
     definitions.insert("pkg.foo".to_string(), vec!["Tree".to_string()]);
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_external_definitions(definitions);
-    let generator = python3::CodeGenerator::new(&config);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let content = std::fs::read_to_string(&source_path).unwrap();
@@ -152,13 +463,16 @@ fn test_that_installed_python_code_passes_pyre_check() {
     let registry = test_utils::get_registry().unwrap();
     let dir = tempdir().unwrap();
 
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     let installer = python3::Installer::new(dir.path().join("src"), /* serde package */ None);
     installer.install_module(&config, &registry).unwrap();
     installer.install_serde_runtime().unwrap();
     installer.install_bincode_runtime().unwrap();
     installer.install_bcs_runtime().unwrap();
+    installer.install_postcard_runtime().unwrap();
+    installer.install_msgpack_runtime().unwrap();
 
     // Copy test files manually to type-check them as well.
     // This should go away when python runtimes are properly packaged.
@@ -176,6 +490,13 @@ fn test_that_installed_python_code_passes_pyre_check() {
         .status()
         .unwrap();
     assert!(status.success());
+    let status = Command::new("cp")
+        .arg("-r")
+        .arg("runtime/python/postcard/test_postcard.py")
+        .arg(dir.path().join("src/postcard"))
+        .status()
+        .unwrap();
+    assert!(status.success());
 
     let site_packages = Command::new("python3")
         .arg("-c")
@@ -219,3 +540,547 @@ fn test_python_autotest() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_that_python_code_parses_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) =
+        test_that_python_code_parses_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("from_: st.uint8"));
+}
+
+#[test]
+fn test_that_python_code_runs_with_keyword_and_dunder_names() {
+    let registry: Registry = serde_yaml::from_str(
+        r#"---
+Foo:
+  STRUCT:
+    - class: STR
+    - import: U8
+    - from:
+        OPTION: U8
+    - __init__: U8
+    - __post_init__: U8
+Bar:
+  ENUM:
+    0:
+      class:
+        STRUCT:
+          - __hash__: U8
+    1:
+      None: UNIT
+"#,
+    )
+    .unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .output(&mut source, &registry)
+        .unwrap();
+    writeln!(
+        source,
+        r#"
+value = Foo(class_="a", import_=1, from_=None, __init___=2, __post_init___=3)
+assert Foo.bcs_deserialize(value.bcs_serialize()) == value
+assert value.bcs_serialize() == bytes([1, 97, 1, 0, 2, 3])
+assert hash(value) == hash(Foo("a", 1, None, 2, 3))
+
+variant = Bar__class(__hash___=4)
+assert Bar.bcs_deserialize(variant.bcs_serialize()) == variant
+assert hash(variant) == hash(Bar__class(4))
+assert Bar.bcs_deserialize(bytes([1])) == Bar__None()
+"#
+    )
+    .unwrap();
+
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_installed_python_code_has_type_stubs() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let mut definitions = BTreeMap::new();
+    definitions.insert("pkg.foo".to_string(), vec!["Tree".to_string()]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true)
+        .with_external_definitions(definitions);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None).with_type_stubs(true);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+
+    assert!(dir.path().join("testing/py.typed").exists());
+    assert!(dir.path().join("serde_types/py.typed").exists());
+    let stub_path = dir.path().join("testing/__init__.pyi");
+    let stub = std::fs::read_to_string(&stub_path).unwrap();
+    assert!(stub.contains("from pkg import foo"));
+    assert!(stub.contains("value: foo.Tree"));
+    assert!(stub.contains("class List__Node(List):"));
+    assert!(stub.contains("def bcs_deserialize(input: bytes) -> \"SerdeData\": ..."));
+    assert!(!stub.contains("bincode"));
+
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import ast, sys; ast.parse(open(sys.argv[1]).read())")
+        .arg(&stub_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut source = Vec::new();
+    let config = config.with_serialization(false);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output_stub(&mut source, &registry).unwrap();
+    let stub = String::from_utf8(source).unwrap();
+    assert!(!stub.contains("_serialize"));
+    assert!(!stub.contains("VARIANTS"));
+
+    let mut source = Vec::new();
+    let config = config.with_serialization(true);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_json_methods(true);
+    generator.output_stub(&mut source, &registry).unwrap();
+    let stub = String::from_utf8(source).unwrap();
+    assert!(stub.contains("def from_json(input: str) -> \"SerdeData\": ..."));
+}
+
+#[test]
+fn test_that_installed_python_code_has_manifest() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None)
+        .with_manifest(true)
+        .with_type_stubs(true)
+        .with_python_version(3, 10);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+
+    // Without a Serde package name, modules are installed as top-level packages.
+    assert!(dir.path().join("testing/__init__.py").exists());
+    assert!(dir.path().join("serde_types/__init__.py").exists());
+    assert!(!dir.path().join("__init__.py").exists());
+    let manifest = std::fs::read_to_string(dir.path().join("pyproject.toml")).unwrap();
+    assert!(manifest.contains("name = \"testing\"\nversion = \"0.1.0\"\n"));
+    assert!(manifest.contains("requires-python = \">=3.10\"\n"));
+    assert!(!manifest.contains("include = "));
+    assert!(manifest.contains("\"*\" = [\"py.typed\", \"*.pyi\"]\n"));
+
+    let installer =
+        python3::Installer::new(dir.path().to_path_buf(), Some("my_package".to_string()))
+            .with_manifest(true)
+            .with_package_version("2.0.0".to_string());
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    assert!(dir.path().join("my_package/testing/__init__.py").exists());
+    let init = std::fs::read_to_string(dir.path().join("my_package/__init__.py")).unwrap();
+    assert_eq!(init, "__all__ = [\"bcs\", \"testing\"]\n");
+    let manifest = std::fs::read_to_string(dir.path().join("pyproject.toml")).unwrap();
+    assert!(manifest.contains("name = \"my_package\"\nversion = \"2.0.0\"\n"));
+    assert!(manifest.contains("include = [\"my_package\", \"my_package.*\"]\n"));
+}
+
+#[test]
+fn test_that_installed_python_code_has_split_modules() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None)
+        .with_split_modules(true)
+        .with_type_stubs(true);
+    assert!(installer.install_module(&config, &registry).is_err());
+
+    let installer =
+        python3::Installer::new(dir.path().to_path_buf(), None).with_split_modules(true);
+    let config = config.with_allow_nonstandard_floats(true);
+    installer.install_module(&config, &registry).unwrap();
+
+    let module_dir = dir.path().join("testing");
+    let init = std::fs::read_to_string(module_dir.join("__init__.py")).unwrap();
+    assert!(init.contains("from .c_style_enum import CStyleEnum, CStyleEnum__A, "));
+    assert!(init.contains("\n__all__ = [\"CStyleEnum\", \"CStyleEnum__A\", "));
+
+    // Acyclic dependencies are imported first.
+    let content = std::fs::read_to_string(module_dir.join("other_types.py")).unwrap();
+    assert!(content.contains("\nfrom .struct import Struct\n"));
+    let content = std::fs::read_to_string(module_dir.join("struct.py")).unwrap();
+    assert!(!content.contains("from ."));
+
+    // Mutually recursive containers are imported last.
+    let content = std::fs::read_to_string(module_dir.join("serde_data.py")).unwrap();
+    assert!(content.contains("\nfrom .other_types import OtherTypes\n"));
+    assert!(content.ends_with("defined.\nfrom .list import List\nfrom .tree import Tree\n"));
+    let content = std::fs::read_to_string(module_dir.join("tree.py")).unwrap();
+    assert!(content.ends_with("defined.\nfrom .serde_data import SerdeData\n"));
+    // Self-references need no import.
+    let content = std::fs::read_to_string(module_dir.join("simple_list.py")).unwrap();
+    assert!(!content.contains("from ."));
+}
+
+#[test]
+fn test_that_installed_python_type_stubs_pass_mypy() {
+    if which::which("mypy").is_err() {
+        return;
+    }
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None).with_type_stubs(true);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let mut consumer = File::create(dir.path().join("consumer.py")).unwrap();
+    writeln!(
+        consumer,
+        r#"
+import typing
+from testing import Choice, Choice__A, Choice__C, Test
+
+def describe(choice: Choice) -> str:
+    if isinstance(choice, Choice__C):
+        return "C" + str(int(choice.x))
+    return "other"
+
+value = Test.bcs_deserialize(bytes([0, 0, 0, 0]))
+items: typing.Sequence[int] = value.a
+print(describe(value.c), describe(Choice__A()), len(value.bcs_serialize()))
+"#
+    )
+    .unwrap();
+
+    let status = Command::new("mypy")
+        .current_dir(dir.path())
+        .arg("--strict")
+        .arg("--follow-imports=silent")
+        .arg("consumer.py")
+        .arg("testing/__init__.pyi")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_python_enums_have_final_variants_and_match_methods() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    // `typing.final` requires Python 3.8.
+    assert!(!content.contains("@typing.final"));
+    assert!(content.contains(
+        "    def match(\n        self,\n        *,\n        Empty: typing.Callable[[\"List__Empty\"], st.MatchResult],\n        Node: typing.Callable[[\"List__Node\"], st.MatchResult],\n    ) -> st.MatchResult:\n"
+    ));
+
+    let registry = test_utils::get_registry().unwrap();
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 8);
+    let (dir, source_path) = test_that_python_code_parses_with_generator(&generator, &registry);
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("\n@typing.final\n@dataclass(frozen=True)\nclass List__Node(List):\n"));
+    assert!(content.contains(
+        "    ) -> st.MatchResult:\n        if isinstance(self, List__Empty):\n            return Empty(self)\n        if isinstance(self, List__Node):\n            return Node(self)\n        raise TypeError(\"Unexpected variant\", self)\n"
+    ));
+
+    let mut source = File::options().append(true).open(&source_path).unwrap();
+    writeln!(
+        source,
+        r#"
+def describe(value: List) -> str:
+    return value.match(Empty=lambda _: "empty", Node=lambda node: "node")
+
+assert describe(List__Empty()) == "empty"
+assert describe(List__Node((SerdeData__UnitVariant(), List__Empty()))) == "node"
+"#
+    )
+    .unwrap();
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    drop(dir);
+
+    let mut stub = Vec::new();
+    generator.output_stub(&mut stub, &registry).unwrap();
+    let stub = String::from_utf8(stub).unwrap();
+    assert!(stub.contains("\n@typing.final\n@dataclass(frozen=True)\nclass List__Node(List):\n"));
+    assert!(stub.contains("        Node: typing.Callable[[\"List__Node\"], st.MatchResult],\n    ) -> st.MatchResult: ...\n"));
+
+    // Tagged unions are already unions of their variants.
+    let generator = generator
+        .with_python_version(3, 9)
+        .with_python_models(PythonModels::PydanticTaggedUnions);
+    let mut source = Vec::new();
+    generator.output(&mut source, &registry).unwrap();
+    let source = String::from_utf8(source).unwrap();
+    assert!(source.contains("@typing.final\nclass List__Node(pydantic.BaseModel):\n"));
+    assert!(!source.contains("def match("));
+}
+
+#[test]
+fn test_that_python_match_methods_are_exhaustive_for_mypy() {
+    if which::which("mypy").is_err() {
+        return;
+    }
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None).with_type_stubs(true);
+    installer.install_module(&config, &registry).unwrap();
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    for (cases, success) in [
+        (
+            r#"A=lambda _: "A", B=lambda b: str(int(b.value)), C=lambda c: str(int(c.x))"#,
+            true,
+        ),
+        (r#"A=lambda _: "A", B=lambda b: str(int(b.value))"#, false),
+    ] {
+        let mut consumer = File::create(dir.path().join("consumer.py")).unwrap();
+        writeln!(
+            consumer,
+            r#"
+from testing import Choice
+
+def describe(choice: Choice) -> str:
+    return choice.match({})
+"#,
+            cases
+        )
+        .unwrap();
+
+        let status = Command::new("mypy")
+            .current_dir(dir.path())
+            .arg("--strict")
+            .arg("--follow-imports=silent")
+            .arg("consumer.py")
+            .arg("testing/__init__.pyi")
+            .status()
+            .unwrap();
+        assert_eq!(status.success(), success);
+    }
+}
+
+#[test]
+fn test_that_python_dataclass_slots_require_python_3_10() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_dataclass_slots(true);
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Generating dataclasses with slots requires Python 3 version 3.10 or later"
+    );
+
+    let generator = generator.with_python_version(3, 10);
+    let (_dir, source_path) = test_that_python_code_parses_with_generator(&generator, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("@dataclass(frozen=True, order=True, slots=True)\nclass UnitStruct:"));
+}
+
+#[test]
+fn test_that_python_annotations_follow_python_version() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(!content.contains("from __future__ import annotations"));
+    assert!(content.contains("    f_option: typing.Optional[\"Struct\"]\n"));
+    assert!(content.contains("    f_seq: typing.Sequence[\"Struct\"]\n"));
+    assert!(content.contains("    f_stringmap: typing.Dict[str, st.uint32]\n"));
+
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 10);
+    let (_dir, source_path) = test_that_python_code_parses_with_generator(
+        &generator,
+        &test_utils::get_registry().unwrap(),
+    );
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.starts_with("# pyre-strict\nfrom __future__ import annotations\n"));
+    assert!(content.contains("    f_option: Struct | None\n"));
+    assert!(content.contains("    f_seq: list[Struct]\n"));
+    assert!(content.contains("    f_stringmap: dict[str, st.uint32]\n"));
+    assert!(content.contains("    f_tuple: tuple[st.uint8, st.uint16]\n"));
+    assert!(!content.contains("typing.Optional"));
+}
+
+#[test]
+fn test_that_python_pydantic_models_require_python_3_9() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_models(PythonModels::Pydantic);
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Generating Pydantic models requires Python 3 version 3.9 or later"
+    );
+
+    let generator = generator
+        .with_python_version(3, 10)
+        .with_dataclass_slots(true);
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Pydantic models cannot be combined with dataclass slots in Python 3"
+    );
+}
+
+#[test]
+fn test_that_python_code_parses_with_pydantic_models() {
+    if Command::new("python3")
+        .arg("-c")
+        .arg("import pydantic")
+        .status()
+        .map_or(true, |status| !status.success())
+    {
+        return;
+    }
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 9)
+        .with_python_models(PythonModels::Pydantic);
+    let (_dir, source_path) = test_that_python_code_parses_with_generator(&generator, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains(
+        "class UnitStruct(pydantic.BaseModel):\n    model_config = pydantic.ConfigDict(frozen=True, arbitrary_types_allowed=True)\n"
+    ));
+    assert!(content.contains(
+        "class SerdeData__UnitVariant(SerdeData):\n    INDEX: typing.ClassVar[int] = 2\n"
+    ));
+
+    let generator = generator.with_python_models(PythonModels::PydanticTaggedUnions);
+    let (_dir, source_path) = test_that_python_code_parses_with_generator(&generator, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("class SerdeData__UnitVariant(pydantic.BaseModel):\n"));
+    assert!(content.contains("    kind: typing.Literal[\"UnitVariant\"] = \"UnitVariant\"\n"));
+    assert!(content.contains(
+        "CStyleEnum = typing.Annotated[typing.Union[CStyleEnum__A, CStyleEnum__B, CStyleEnum__C, CStyleEnum__D, CStyleEnum__E], pydantic.Field(discriminator=\"kind\")]\n"
+    ));
+}
+
+#[test]
+fn test_that_python_tagged_unions_reserve_kind_fields() {
+    let registry: Registry = serde_yaml::from_str(
+        r#"---
+Shape:
+  ENUM:
+    0:
+      Circle:
+        STRUCT:
+          - kind: STR
+"#,
+    )
+    .unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 9)
+        .with_python_models(PythonModels::PydanticTaggedUnions);
+    let issues = generator.validate(&registry);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].to_string(),
+        "Shape::Circle.kind: `kind` is reserved for the tag of the variant"
+    );
+    assert!(generator.output(&mut Vec::new(), &registry).is_err());
+}
+
+#[test]
+fn test_that_python_code_parses_with_c_style_enums() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    let (_dir, source_path) = test_that_python_code_parses_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("import enum\n"));
+    assert!(content.contains("class CStyleEnum(enum.IntEnum):\n    A = 0\n"));
+    assert!(content.contains("    E = 4\n"));
+    assert!(content.contains("class SerdeData__UnitVariant(SerdeData):\n"));
+
+    let registry = test_utils::get_registry().unwrap();
+    let mut stub = Vec::new();
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output_stub(&mut stub, &registry).unwrap();
+    let stub = String::from_utf8(stub).unwrap();
+    assert!(stub.contains("class CStyleEnum(enum.IntEnum):\n    A = 0\n"));
+}
+
+#[test]
+fn test_that_python_code_parses_with_non_ascii_names() {
+    let registry = test_utils::get_non_ascii_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) =
+        test_that_python_code_parses_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("class Café:\n"));
+    assert!(content.contains("class 注文__コーヒー(注文):\n"));
+
+    let config = config.with_non_ascii_names(NonAsciiNames::Transliterate);
+    let (_dir, source_path) =
+        test_that_python_code_parses_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("class CafU00E9:\n    prU00E9nom: str\n"));
+    assert!(content.contains("class U6CE8U6587__U30B3U30FCU30D2U30FC(U6CE8U6587):\n"));
+}
+
+#[test]
+fn test_that_python_installer_installs_everything() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let installer = python3::Installer::new(dir.path().to_path_buf(), None);
+    installer.install_everything(&config, &registry).unwrap();
+
+    for package in ["testing", "serde_types", "serde_binary", "bcs"] {
+        assert!(dir.path().join(package).join("__init__.py").exists());
+    }
+    assert!(!dir.path().join("bincode").exists());
+
+    let status = Command::new("python3")
+        .arg("-c")
+        .arg("import bcs, testing; testing.SerdeData.bcs_deserialize")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -2,11 +2,19 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use crate::test_utils::{Choice, Runtime, Test};
-use serde_generate::{python3, CodeGeneratorConfig};
+use crate::test_utils::{Choice, LargeEnumValue, Runtime, Schedule, SerdeData, Test};
+use serde::{Deserialize, Serialize};
+use serde_generate::{
+    python3::{self, PythonModels, UnhashableFields},
+    CodeGeneratorConfig,
+};
+use serde_reflection::{Registry, Samples, Tracer, TracerConfig};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
 
+/// Options of the Python code generator that are not part of the config.
+type PythonOptions = fn(python3::CodeGenerator) -> python3::CodeGenerator;
+
 #[test]
 fn test_python_bcs_runtime_on_simple_data() {
     test_python_runtime_on_simple_data(Runtime::Bcs);
@@ -17,6 +25,11 @@ fn test_python_bincode_runtime_on_simple_data() {
     test_python_runtime_on_simple_data(Runtime::Bincode);
 }
 
+#[test]
+fn test_python_postcard_runtime_on_simple_data() {
+    test_python_runtime_on_simple_data(Runtime::Postcard);
+}
+
 fn test_python_runtime_on_simple_data(runtime: Runtime) {
     let registry = test_utils::get_simple_registry().unwrap();
     let dir = tempdir().unwrap();
@@ -25,7 +38,7 @@ fn test_python_runtime_on_simple_data(runtime: Runtime) {
 
     let config =
         CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = python3::CodeGenerator::new(&config);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let reference = runtime.serialize(&Test {
@@ -73,81 +86,1432 @@ assert seen_error
 }
 
 #[test]
-fn test_python_bcs_runtime_on_supported_types() {
-    test_python_runtime_on_supported_types(Runtime::Bcs);
+fn test_python_bcs_runtime_with_length_prefix() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_bcs_length_prefix(true);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let content = Runtime::Bcs.serialize(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    });
+    let mut reference = (content.len() as u32).to_be_bytes().to_vec();
+    reference.extend(&content);
+    writeln!(
+        source,
+        r#"
+input = bytes({:?})
+value = Test([4, 6], (3, 5), Choice__C(7))
+assert value.bcs_serialize() == input
+assert Test.bcs_deserialize(input) == value
+
+def error(input: bytes) -> str:
+    try:
+        Test.bcs_deserialize(input)
+    except st.DeserializationError as e:
+        return str(e)
+    assert False
+
+assert error(input[:3]) == "Input is too short for a length prefix"
+assert error(input[:-1]) == "Length prefix does not match the message length"
+assert error(input + bytes([0])) == "Length prefix does not match the message length"
+extended = (len(input) - 3).to_bytes(4, "big") + input[4:] + bytes([0])
+assert error(extended) == "Some input bytes were not read"
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
 }
 
 #[test]
-fn test_python_bincode_runtime_on_supported_types() {
-    test_python_runtime_on_supported_types(Runtime::Bincode);
+fn test_python_runtime_with_deserialization_only() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_serialization(false)
+        .with_deserialization(true);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = Runtime::Bcs.serialize(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    });
+    writeln!(
+        source,
+        r#"
+v = Test.bcs_deserialize(bytes({:?}))
+assert v == Test([4, 6], (3, 5), Choice__C(7))
+assert not hasattr(v, "bcs_serialize")
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
 }
 
-fn test_python_runtime_on_supported_types(runtime: Runtime) {
-    let registry = test_utils::get_registry().unwrap();
+#[test]
+fn test_python_runtime_with_base_classes() {
+    test_python_runtime_with_base_classes_and_models(PythonModels::Dataclasses);
+}
+
+#[test]
+fn test_python_runtime_with_base_classes_and_pydantic() {
+    if !has_pydantic() {
+        return;
+    }
+    test_python_runtime_with_base_classes_and_models(PythonModels::Pydantic);
+    test_python_runtime_with_base_classes_and_models(PythonModels::PydanticTaggedUnions);
+}
+
+fn test_python_runtime_with_base_classes_and_models(models: PythonModels) {
+    let registry = test_utils::get_simple_registry().unwrap();
     let dir = tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("mixins.py"),
+        "class Message:\n    def describe(self) -> str:\n        return type(self).__name__\n",
+    )
+    .unwrap();
     let source_path = dir.path().join("test.py");
     let mut source = File::create(&source_path).unwrap();
 
+    let base_classes = vec![(vec!["testing".to_string()], "mixins.Message".to_string())]
+        .into_iter()
+        .collect();
     let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = python3::CodeGenerator::new(&config);
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Runtime::Bcs.into()]);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 9)
+        .with_python_models(models)
+        .with_base_class(base_classes);
     generator.output(&mut source, &registry).unwrap();
 
-    let positive_encodings: Vec<_> = runtime.get_positive_samples_quick();
-    let negative_encodings: Vec<_> = runtime.get_negative_samples();
+    let reference = Runtime::Bcs.serialize(&Test {
+        a: vec![4, 6],
+        b: (3, 5),
+        c: Choice::C { x: 7 },
+    });
+    writeln!(
+        source,
+        r#"
+value = Test(
+    a=[st.uint32(4), st.uint32(6)],
+    b=(st.int64(3), st.uint64(5)),
+    c=Choice__C(x=st.uint8(7)),
+)
+assert isinstance(value, mixins.Message)
+assert value.describe() == "Test"
+assert value.c.describe() == "Choice__C"
+assert value.bcs_serialize() == bytes({:?})
+assert Test.bcs_deserialize(value.bcs_serialize()) == value
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_with_match_statements() {
+    test_python_runtime_with_match_statements_and_models(PythonModels::Dataclasses);
+}
+
+#[test]
+fn test_python_runtime_with_match_statements_and_pydantic() {
+    if !has_pydantic() {
+        return;
+    }
+    test_python_runtime_with_match_statements_and_models(PythonModels::Pydantic);
+}
 
+fn test_python_runtime_with_match_statements_and_models(models: PythonModels) {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()]);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_python_version(3, 10)
+        .with_python_models(models);
+    generator.output(&mut source, &registry).unwrap();
+
+    let unit = Runtime::Bincode.serialize(&SerdeData::UnitVariant);
+    let newtype = Runtime::Bincode.serialize(&SerdeData::NewTypeVariant("test".to_string()));
+    let tuple = Runtime::Bincode.serialize(&SerdeData::TupleVariant(3, 6));
+    let struct_ = test_utils::get_sample_values(false, true)
+        .into_iter()
+        .find(|value| matches!(value, SerdeData::StructVariant { .. }))
+        .map(|value| Runtime::Bincode.serialize(&value))
+        .unwrap();
     writeln!(
         source,
         r#"
-from copy import copy
-import serde_types as st
-import sys
-import bcs
+def describe(value: SerdeData) -> str:
+    match value:
+        case SerdeData__UnitVariant():
+            return "unit"
+        case SerdeData__NewTypeVariant(s):
+            return "newtype " + s[:4]
+        case SerdeData__TupleVariant(a, b):
+            return f"tuple {{int(a)}} {{int(b)}}"
+        case SerdeData__StructVariant(_, NewTypeStruct(value=x), f3=Struct(y=y)):
+            return f"struct {{int(x)}} {{int(y)}}"
+    return "other"
 
-# Required to avoid RecursionError's in python.
-sys.setrecursionlimit(bcs.MAX_CONTAINER_DEPTH * 5)
+assert describe(SerdeData.bincode_deserialize(bytes({:?}))) == "unit"
+assert describe(SerdeData.bincode_deserialize(bytes({:?}))) == "newtype test"
+assert describe(SerdeData.bincode_deserialize(bytes({:?}))) == "tuple 3 6"
+assert describe(SerdeData.bincode_deserialize(bytes({:?}))) == "struct 1 5"
+assert SerdeData__TupleVariant(value=(st.uint32(3), st.uint64(6))).field1 == 6
+"#,
+        unit, newtype, tuple, struct_,
+    )
+    .unwrap();
 
-positive_encodings = [bytes(a) for a in {1:?}]
-negative_encodings = [bytes(a) for a in {2:?}]
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
 
-for encoding in positive_encodings:
-    v = SerdeData.{0}_deserialize(encoding)
-    s = v.{0}_serialize()
-    assert s == encoding
+#[test]
+fn test_python_bincode_runtime_on_float_edges() {
+    let registry = test_utils::get_float_edges_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
 
-    # Test self-equality for the Serde value.
-    assert v == SerdeData.{0}_deserialize(encoding)
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bincode.into()]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
 
-    # Test simple mutations of the input.
-    for i in range(min(len(encoding), 20)):
-        encoding2 = bytearray(encoding)
-        encoding2[i] ^= 0x81
-        try:
-            v2 = SerdeData.{0}_deserialize(encoding2)
-            assert v2 != v
-        except st.DeserializationError:
-            pass
+    let reference = Runtime::Bincode.serialize(&test_utils::get_float_edges_sample());
+    writeln!(
+        source,
+        r#"
+import struct
 
-for encoding in negative_encodings:
-    try:
-        SerdeData.{0}_deserialize(encoding)
-        print('Input bitstring was wrongfully accepted:\n', encoding)
-        sys.exit(1)
-    except st.DeserializationError:
-        pass
+input = bytes({:?})
+value = FloatEdges.bincode_deserialize(input)
+assert [int(x.view(st.uint32)) for x in value.singles] == {:?}
+assert [struct.unpack("<Q", struct.pack("<d", x))[0] for x in value.doubles] == {:?}
+assert value.bincode_serialize() == input
+"#,
+        reference,
+        test_utils::F32_EDGE_BITS,
+        test_utils::F64_EDGE_BITS,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+// Nested containers making a payload of several megabytes.
+#[derive(Serialize, Deserialize)]
+struct Block {
+    height: u64,
+    transactions: Vec<Transaction>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Transaction {
+    sender: [u8; 32],
+    amount: u64,
+    #[serde(with = "serde_bytes")]
+    payload: Vec<u8>,
+    weights: Vec<u16>,
+    offsets: Vec<i32>,
+    memo: Option<String>,
+    kind: Kind,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Kind {
+    Transfer,
+    Call(String),
+}
+
+fn get_block_registry() -> Registry {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Block>(&samples).unwrap();
+    tracer.trace_type::<Kind>(&samples).unwrap();
+    tracer.registry().unwrap()
+}
+
+// Same as `make_transaction` in Python below.
+fn make_transaction(i: u64) -> Transaction {
+    Transaction {
+        sender: (0..32)
+            .map(|j| ((i + j) % 256) as u8)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap(),
+        amount: i * 1000,
+        payload: (0..200).map(|j| ((i + j) % 256) as u8).collect(),
+        weights: (0..16).map(|j| (i + j) as u16).collect(),
+        offsets: (0..8).map(|j| j - i as i32).collect(),
+        memo: if i % 2 == 1 {
+            None
+        } else {
+            Some(format!("memo {}", i))
+        },
+        kind: if i % 3 == 1 {
+            Kind::Transfer
+        } else {
+            Kind::Call(format!("method_{}", i))
+        },
+    }
+}
+
+#[test]
+fn test_python_bcs_runtime_on_large_payload() {
+    test_python_runtime_on_large_payload(Runtime::Bcs);
+}
+
+#[test]
+fn test_python_bincode_runtime_on_large_payload() {
+    test_python_runtime_on_large_payload(Runtime::Bincode);
+}
+
+#[test]
+fn test_python_postcard_runtime_on_large_payload() {
+    test_python_runtime_on_large_payload(Runtime::Postcard);
+}
+
+fn test_python_runtime_on_large_payload(runtime: Runtime) {
+    let registry = get_block_registry();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = runtime.serialize(&make_transaction(6));
+    writeln!(
+        source,
+        r#"
+def make_transaction(i):
+    return Transaction(
+        sender=tuple(st.uint8((i + j) % 256) for j in range(32)),
+        amount=st.uint64(i * 1000),
+        payload=bytes((i + j) % 256 for j in range(200)),
+        weights=[st.uint16(i + j) for j in range(16)],
+        offsets=[st.int32(j - i) for j in range(8)],
+        memo=None if i % 2 == 1 else "memo %d" % i,
+        kind=Kind__Transfer() if i % 3 == 1 else Kind__Call("method_%d" % i),
+    )
+
+assert make_transaction(6).{0}_serialize() == bytes({1:?})
+
+block = Block(height=st.uint64(1), transactions=[make_transaction(i) for i in range(20000)])
+s = block.{0}_serialize()
+assert len(s) > 5_000_000
+
+v = Block.{0}_deserialize(s)
+assert v == block
+assert v.{0}_serialize() == s
+
+seen_error = False
+try:
+    Block.{0}_deserialize(s[:-1])
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
 "#,
         runtime.name(),
-        positive_encodings,
-        negative_encodings,
+        reference,
     )
     .unwrap();
 
-    let python_path = format!(
-        "{}:runtime/python",
-        std::env::var("PYTHONPATH").unwrap_or_default()
-    );
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
     let status = Command::new("python3")
-        .arg(&source_path)
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_on_long_chains() {
+    let registry = test_utils::get_chain_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into(), Runtime::Bincode.into()]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let length = 10_000;
+    writeln!(
+        source,
+        r#"
+input = bytes({:?})
+
+# Bincode has no limit on the container depth.
+value = Chain.bincode_deserialize(input)
+assert value.bincode_serialize() == input
+node, count = value, 0
+while node is not None:
+    assert node.value == count
+    node, count = node.next, count + 1
+assert count == {}
+
+# BCS rejects the chain cleanly instead of exceeding the recursion limit of Python.
+seen_error = False
+try:
+    Chain.bcs_deserialize(input)
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+
+seen_error = False
+try:
+    value.bcs_serialize()
+except st.SerializationError:
+    seen_error = True
+assert seen_error
+
+short_value = Chain(st.uint32(0), Chain(st.uint32(1), None))
+assert Chain.bcs_deserialize(short_value.bcs_serialize()) == short_value
+"#,
+        test_utils::get_chain_encoding(length),
+        length,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_on_record_streams() {
+    let registry = test_utils::get_records_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into(), Runtime::Bincode.into()]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let length = 100_000;
+    let records = test_utils::get_records(length);
+    for runtime in [Runtime::Bcs, Runtime::Bincode] {
+        let path = dir.path().join(format!("records.{}", runtime.name()));
+        std::fs::write(path, runtime.serialize(&records)).unwrap();
+    }
+    writeln!(
+        source,
+        r#"
+import io
+
+for name in ["bcs", "bincode"]:
+    with open("{0}/records." + name, "rb") as f:
+        values = getattr(Records, name + "_deserialize")(f.read()).value
+    with open("{0}/records." + name, "rb") as f:
+        stream = getattr(Records, name + "_deserialize_stream")(f)
+        first = next(stream)
+        count = 1
+        for last in stream:
+            count += 1
+    assert count == len(values) == {1}
+    assert first == values[0] == Record(st.uint32(0), "record0")
+    assert last == values[-1] == Record(st.uint32({1} - 1), "record{2}")
+
+    with open("{0}/records." + name, "rb") as f:
+        content = f.read()
+    # The trailing-bytes check happens when the stream is exhausted.
+    stream = getattr(Records, name + "_deserialize_stream")(io.BytesIO(content + b"\x00"))
+    assert next(stream) == values[0]
+    seen_error = False
+    try:
+        for _ in stream:
+            pass
+    except st.DeserializationError:
+        seen_error = True
+    assert seen_error
+"#,
+        dir.path().display(),
+        length,
+        length - 1,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_bcs_runtime_on_large_enum() {
+    test_python_runtime_on_large_enum(Runtime::Bcs);
+}
+
+#[test]
+fn test_python_bincode_runtime_on_large_enum() {
+    test_python_runtime_on_large_enum(Runtime::Bincode);
+}
+
+#[test]
+fn test_python_postcard_runtime_on_large_enum() {
+    test_python_runtime_on_large_enum(Runtime::Postcard);
+}
+
+fn test_python_runtime_on_large_enum(runtime: Runtime) {
+    let registry = test_utils::get_large_enum_registry();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let samples = test_utils::get_large_enum_sample_indices()
+        .into_iter()
+        .map(|index| {
+            let reference = runtime.serialize(&LargeEnumValue(index));
+            let value = if index % 2 == 0 {
+                format!("LargeEnum__V{}()", index)
+            } else {
+                format!("LargeEnum__V{0}({0})", index)
+            };
+            format!("(bytes({:?}), {})", reference, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    writeln!(
+        source,
+        r#"
+for input, expected in [{1}]:
+    value = LargeEnum.{0}_deserialize(input)
+    assert value == expected
+    assert value.{0}_serialize() == input
+"#,
+        runtime.name(),
+        samples,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_bcs_runtime_on_durations() {
+    test_python_runtime_on_durations(Runtime::Bcs);
+}
+
+#[test]
+fn test_python_bincode_runtime_on_durations() {
+    test_python_runtime_on_durations(Runtime::Bincode);
+}
+
+#[test]
+fn test_python_postcard_runtime_on_durations() {
+    test_python_runtime_on_durations(Runtime::Postcard);
+}
+
+fn test_python_runtime_on_durations(runtime: Runtime) {
+    let (registry, well_known_types) = test_utils::get_schedule_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_well_known_types(well_known_types);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = runtime.serialize(&test_utils::get_schedule_sample());
+    // Python durations have microsecond precision.
+    let too_precise = runtime.serialize(&Schedule {
+        delay: std::time::Duration::new(1, 1),
+        timeout: None,
+    });
+    writeln!(
+        source,
+        r#"
+assert "Duration" not in globals()
+
+input = bytes({1:?})
+value = Schedule(
+    datetime.timedelta(days=1, hours=1, minutes=1, seconds=1, microseconds=500),
+    datetime.timedelta(milliseconds=1500),
+)
+
+s = value.{0}_serialize()
+assert s == input
+
+v = Schedule.{0}_deserialize(s)
+assert v == value
+
+seen_error = False
+try:
+    Schedule.{0}_deserialize(bytes({2:?}))
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+
+seen_error = False
+try:
+    Schedule(-datetime.timedelta(seconds=1), None).{0}_serialize()
+except st.SerializationError:
+    seen_error = True
+assert seen_error
+"#,
+        runtime.name(),
+        reference,
+        too_precise,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types() {
+    test_python_runtime_on_supported_types(Runtime::Bcs);
+}
+
+#[test]
+fn test_python_bincode_runtime_on_supported_types() {
+    test_python_runtime_on_supported_types(Runtime::Bincode);
+}
+
+#[test]
+fn test_python_postcard_runtime_on_supported_types() {
+    test_python_runtime_on_supported_types(Runtime::Postcard);
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_slots() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_options(Runtime::Bcs, config, |generator| {
+        generator
+            .with_python_version(3, 10)
+            .with_dataclass_slots(true)
+    });
+}
+
+#[test]
+fn test_python_bincode_runtime_on_supported_types_with_slots() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_options(Runtime::Bincode, config, |generator| {
+        generator
+            .with_python_version(3, 10)
+            .with_dataclass_slots(true)
+            .with_unhashable_fields(UnhashableFields::Tuple)
+    });
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_python_3_10() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_options(Runtime::Bcs, config, |generator| {
+        generator.with_python_version(3, 10)
+    });
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_c_style_enums() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    test_python_runtime_on_supported_types_with_config(Runtime::Bcs, config);
+}
+
+#[test]
+fn test_python_runtime_on_c_style_enums() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_allow_nonstandard_floats(true)
+        .with_c_style_enums(true);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_json_methods(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    writeln!(
+        source,
+        r#"
+assert issubclass(CStyleEnum, enum.IntEnum)
+assert CStyleEnum.C == 2
+
+s = CStyleEnum.C.bcs_serialize()
+assert s == bytes([2])
+assert CStyleEnum.bcs_deserialize(s) is CStyleEnum.C
+assert CStyleEnum.from_json(CStyleEnum.C.to_json()) is CStyleEnum.C
+
+value = SerdeData__CStyleEnum(CStyleEnum.E)
+v = SerdeData.bcs_deserialize(value.bcs_serialize())
+assert v == value
+assert v.value is CStyleEnum.E
+
+# Enums with data keep their classes.
+assert issubclass(SerdeData__UnitVariant, SerdeData)
+
+seen_error = False
+try:
+    CStyleEnum.bcs_deserialize(bytes([5]))
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+"#
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn has_pydantic() -> bool {
+    Command::new("python3")
+        .arg("-c")
+        .arg("import pydantic")
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_pydantic() {
+    if !has_pydantic() {
+        return;
+    }
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_options(Runtime::Bcs, config, |generator| {
+        generator
+            .with_python_version(3, 9)
+            .with_python_models(PythonModels::Pydantic)
+    });
+}
+
+#[test]
+fn test_python_bincode_runtime_on_supported_types_with_pydantic_tagged_unions() {
+    if !has_pydantic() {
+        return;
+    }
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_options(Runtime::Bincode, config, |generator| {
+        generator
+            .with_python_version(3, 9)
+            .with_python_models(PythonModels::PydanticTaggedUnions)
+    });
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_split_modules() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_layout(
+        Runtime::Bcs,
+        config,
+        |generator| generator,
+        true,
+    );
+}
+
+#[test]
+fn test_python_bincode_runtime_on_supported_types_with_split_modules() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_layout(
+        Runtime::Bincode,
+        config,
+        |generator| generator.with_python_version(3, 10),
+        true,
+    );
+}
+
+#[test]
+fn test_python_bcs_runtime_on_supported_types_with_split_modules_and_pydantic() {
+    if !has_pydantic() {
+        return;
+    }
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_layout(
+        Runtime::Bcs,
+        config,
+        |generator| {
+            generator
+                .with_python_version(3, 9)
+                .with_python_models(PythonModels::PydanticTaggedUnions)
+        },
+        true,
+    );
+}
+
+fn test_python_runtime_on_supported_types(runtime: Runtime) {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    test_python_runtime_on_supported_types_with_config(runtime, config);
+}
+
+fn test_python_runtime_on_supported_types_with_config(
+    runtime: Runtime,
+    config: CodeGeneratorConfig,
+) {
+    test_python_runtime_on_supported_types_with_options(runtime, config, |generator| generator);
+}
+
+fn test_python_runtime_on_supported_types_with_options(
+    runtime: Runtime,
+    config: CodeGeneratorConfig,
+    options: PythonOptions,
+) {
+    test_python_runtime_on_supported_types_with_layout(runtime, config, options, false);
+}
+
+/// Run the test on the definitions of `config` and `options`, written either in the test script
+/// itself or, with `split_modules`, as a package `testing` with one module per container.
+fn test_python_runtime_on_supported_types_with_layout(
+    runtime: Runtime,
+    config: CodeGeneratorConfig,
+    options: PythonOptions,
+    split_modules: bool,
+) {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = config
+        .with_encodings(vec![runtime.into()])
+        .with_postcard_floats(true)
+        .with_allow_nonstandard_floats(true);
+    let generator = options(python3::CodeGenerator::try_new(&config).unwrap());
+    if split_modules {
+        generator
+            .write_source_files(dir.path().join("testing"), &registry)
+            .unwrap();
+        writeln!(source, "import typing\nfrom testing import *").unwrap();
+    } else {
+        generator.output(&mut source, &registry).unwrap();
+    }
+
+    let positive_encodings: Vec<_> = runtime.get_positive_samples_quick();
+    let negative_encodings: Vec<_> = runtime.get_negative_samples();
+
+    writeln!(
+        source,
+        r#"
+from copy import copy
+import serde_types as st
+import sys
+import bcs
+
+# Required to avoid RecursionError's in python.
+sys.setrecursionlimit(bcs.MAX_CONTAINER_DEPTH * 5)
+
+# With Pydantic tagged unions, the methods of an enum are found in its variant classes.
+if isinstance(SerdeData, type):
+    SerdeDataMethods = SerdeData
+else:
+    SerdeDataMethods = typing.get_args(typing.get_args(SerdeData)[0])[0]
+
+positive_encodings = [bytes(a) for a in {1:?}]
+negative_encodings = [bytes(a) for a in {2:?}]
+
+for encoding in positive_encodings:
+    v = SerdeDataMethods.{0}_deserialize(encoding)
+    s = v.{0}_serialize()
+    assert s == encoding
+
+    # Test self-equality for the Serde value.
+    assert v == SerdeDataMethods.{0}_deserialize(encoding)
+
+    # Test simple mutations of the input.
+    for i in range(min(len(encoding), 20)):
+        encoding2 = bytearray(encoding)
+        encoding2[i] ^= 0x81
+        try:
+            v2 = SerdeDataMethods.{0}_deserialize(encoding2)
+            assert v2 != v
+        except st.DeserializationError:
+            pass
+
+for encoding in negative_encodings:
+    try:
+        SerdeDataMethods.{0}_deserialize(encoding)
+        print('Input bitstring was wrongfully accepted:\n', encoding)
+        sys.exit(1)
+    except st.DeserializationError:
+        pass
+"#,
+        runtime.name(),
+        positive_encodings,
+        negative_encodings,
+    )
+    .unwrap();
+
+    let python_path = format!(
+        "{}:runtime/python",
+        std::env::var("PYTHONPATH").unwrap_or_default()
+    );
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_on_hashable_containers() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Runtime::Bcs.into()])
+        .with_allow_nonstandard_floats(true);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    // Tuple structs serialize like tuples.
+    let reference = Runtime::Bcs.serialize(&(2u32, 3u64));
+    writeln!(
+        source,
+        r#"
+key = TupleStruct.bcs_deserialize(bytes({0:?}))
+table = {{key: "a", TupleStruct((st.uint32(2), st.uint64(4))): "b"}}
+assert table[TupleStruct((st.uint32(2), st.uint64(3)))] == "a"
+assert len(table) == 2
+assert TupleStruct((st.uint32(2), st.uint64(3))) < TupleStruct((st.uint32(2), st.uint64(4)))
+assert sorted(table)[-1].value[1] == 4
+
+# Containers with sequences are not hashable.
+seen_error = False
+try:
+    hash(Tree(SerdeData__UnitVariant(), []))
+except TypeError:
+    seen_error = True
+assert seen_error
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_runtime_on_unhashable_fields() {
+    let registry = test_utils::get_registry().unwrap();
+    let cases = [
+        (
+            UnhashableFields::Exclude,
+            r#"
+# Sequences are ignored when hashing.
+tree = Tree(SerdeData__UnitVariant(), [Tree(SerdeData__UnitVariant(), [])])
+assert hash(tree) == hash(Tree(SerdeData__UnitVariant(), []))
+assert tree != Tree(SerdeData__UnitVariant(), [])
+assert {tree: 1}[Tree.bcs_deserialize(tree.bcs_serialize())] == 1
+"#,
+        ),
+        (
+            UnhashableFields::Tuple,
+            r#"
+# Sequences are stored as tuples.
+tree = Tree(SerdeData__UnitVariant(), [Tree(SerdeData__UnitVariant(), [])])
+assert tree.children == (Tree(SerdeData__UnitVariant(), ()),)
+assert {tree: 1}[Tree.bcs_deserialize(tree.bcs_serialize())] == 1
+"#,
+        ),
+    ];
+    for (unhashable_fields, code) in cases {
+        let dir = tempdir().unwrap();
+        let source_path = dir.path().join("test.py");
+        let mut source = File::create(&source_path).unwrap();
+
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_encodings(vec![Runtime::Bcs.into()])
+            .with_allow_nonstandard_floats(true);
+        let generator = python3::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_unhashable_fields(unhashable_fields);
+        generator.output(&mut source, &registry).unwrap();
+        writeln!(source, "{}", code).unwrap();
+
+        let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+        let status = Command::new("python3")
+            .arg(source_path)
+            .env("PYTHONPATH", python_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn test_python_compact_encoding() {
+    let registry = test_utils::get_telemetry_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_elision_containers(["Telemetry".to_string()].into_iter().collect());
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let samples = test_utils::get_telemetry_samples()
+        .into_iter()
+        .map(|(_, bcs, compact)| format!("(bytes({:?}), bytes({:?}))", bcs, compact))
+        .collect::<Vec<_>>();
+    writeln!(
+        source,
+        r#"
+for (encoding, compact) in [{}]:
+    value, remaining = bcs.deserialize(encoding, Telemetry)
+    assert not remaining
+    assert Telemetry.compact_deserialize(compact) == value
+    assert value.compact_serialize() == compact
+
+value = Telemetry.compact_deserialize(bytes([0, 0, 1, 0, 2, 0]))
+assert value == Telemetry(st.uint64(0), Location(st.uint16(1), st.uint16(2)), "", False, None, [], {{}}, b"", st.int128(0), st.uint8(0))
+
+for invalid in [bytes([0]), bytes([0, 0, 1, 0, 2, 0, 0])]:
+    seen_error = False
+    try:
+        Telemetry.compact_deserialize(invalid)
+    except st.DeserializationError:
+        seen_error = True
+    assert seen_error
+"#,
+        samples.join(", "),
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_json_methods_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Runtime::Bcs.into()]);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_json_methods(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    writeln!(
+        source,
+        r#"
+import json
+
+value = Test([4, 6], (3, 1 << 63), Choice__C(7))
+assert json.loads(value.to_json()) == {{"a": [4, 6], "b": [3, 1 << 63], "c": {{"C": {{"x": 7}}}}}}
+assert Test.from_json(value.to_json()) == value
+
+assert Choice__A().to_json() == '"A"'
+assert Choice.from_json('"A"') == Choice__A()
+assert Choice.from_json('{{"B": {{"value": 5}}}}') == Choice__B(5)
+
+for content in ['"D"', '{{"C": {{"x": 256}}}}', '{{"C": {{}}}}', '{{"C": {{"x": 1, "y": 2}}}}', '[']:
+    seen_error = False
+    try:
+        Choice.from_json(content)
+    except st.DeserializationError:
+        seen_error = True
+    assert seen_error, content
+"#
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_json_methods_on_supported_types() {
+    test_python_json_methods_on_supported_types_with_options(|generator| generator);
+}
+
+#[test]
+fn test_python_json_methods_on_supported_types_with_pydantic_tagged_unions() {
+    if !has_pydantic() {
+        return;
+    }
+    test_python_json_methods_on_supported_types_with_options(|generator| {
+        generator
+            .with_python_version(3, 9)
+            .with_python_models(PythonModels::PydanticTaggedUnions)
+    });
+}
+
+#[test]
+fn test_python_json_methods_on_supported_types_with_python_3_10() {
+    test_python_json_methods_on_supported_types_with_options(|generator| {
+        generator.with_python_version(3, 10)
+    });
+}
+
+fn test_python_json_methods_on_supported_types_with_options(options: PythonOptions) {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bincode;
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
+    let generator =
+        options(python3::CodeGenerator::try_new(&config).unwrap()).with_json_methods(true);
+    generator.output(&mut source, &registry).unwrap();
+
+    let positive_encodings: Vec<_> = runtime.get_positive_samples_quick();
+
+    writeln!(
+        source,
+        r#"
+import json
+import sys
+import bcs
+
+sys.setrecursionlimit(bcs.MAX_CONTAINER_DEPTH * 5)
+
+if isinstance(SerdeData, type):
+    SerdeDataMethods = SerdeData
+else:
+    SerdeDataMethods = typing.get_args(typing.get_args(SerdeData)[0])[0]
+
+for encoding in [bytes(a) for a in {0:?}]:
+    v = SerdeDataMethods.bincode_deserialize(encoding)
+    s = v.to_json()
+    json.loads(s)
+    v2 = SerdeDataMethods.from_json(s)
+    assert v2 == v, s
+    assert v2.bincode_serialize() == encoding
+"#,
+        positive_encodings,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(&source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_msgpack_runtime_on_simple_data() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![serde_generate::Encoding::MessagePack]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let value = Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    };
+    let reference = rmp_serde::to_vec_named(&value).unwrap();
+    // Structs encoded as arrays are accepted too.
+    let compact = rmp_serde::to_vec(&value).unwrap();
+    writeln!(
+        source,
+        r#"
+input = bytes({0:?})
+value = Test([4, 6], (-3, 5), Choice__C(7))
+
+s = value.msgpack_serialize()
+assert s == input
+
+v = Test.msgpack_deserialize(s)
+assert v == value
+assert Test.msgpack_deserialize(bytes({1:?})) == value
+
+seen_error = False
+try:
+    Test.msgpack_deserialize(input + bytes([0]))
+except st.DeserializationError:
+    seen_error = True
+assert seen_error
+"#,
+        reference, compact,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[derive(Serialize, Deserialize)]
+struct Measurement {
+    small: u8,
+    medium: u16,
+    large: u64,
+    negative: i8,
+    very_negative: i64,
+    ratio: f64,
+    flag: bool,
+    label: String,
+    letter: char,
+    #[serde(with = "serde_bytes")]
+    data: Vec<u8>,
+    tags: std::collections::BTreeMap<String, u32>,
+    maybe: Option<i32>,
+    unit: (),
+    distance: Meters,
+    pair: Pair,
+    nothing: Nothing,
+    kinds: Vec<Sensor>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Meters(u32);
+
+#[derive(Serialize, Deserialize)]
+struct Pair(u8, i16);
+
+#[derive(Serialize, Deserialize)]
+struct Nothing;
+
+#[derive(Serialize, Deserialize)]
+enum Sensor {
+    Idle,
+    Level(u16),
+    Range(i32, String),
+    Position { x: i64, y: i64 },
+}
+
+fn make_measurement(i: u64) -> Measurement {
+    Measurement {
+        small: (i * 100 % 256) as u8,
+        medium: (i * 300) as u16,
+        large: i << (8 * (i % 8)),
+        negative: -((i * 20 % 128) as i8),
+        very_negative: -((i as i64) << (8 * (i % 7))),
+        ratio: i as f64 / 3.0,
+        flag: i % 2 == 0,
+        label: "x".repeat((i * 17 % 300) as usize),
+        letter: ['a', 'é', '€', '😀'][(i % 4) as usize],
+        data: vec![i as u8; (i * 70 % 300) as usize],
+        tags: (0..i % 20)
+            .map(|j| (format!("tag{}", j), j as u32))
+            .collect(),
+        maybe: if i % 3 == 0 { None } else { Some(-(i as i32)) },
+        unit: (),
+        distance: Meters(i as u32 * 70_000),
+        pair: Pair(i as u8, -(i as i16) * 100),
+        nothing: Nothing,
+        kinds: vec![
+            Sensor::Idle,
+            Sensor::Level(i as u16),
+            Sensor::Range(-(i as i32), format!("range {}", i)),
+            Sensor::Position {
+                x: i as i64,
+                y: -(i as i64),
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_python_msgpack_runtime_matches_rmp_serde() {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Measurement>(&samples).unwrap();
+    tracer.trace_type::<Sensor>(&samples).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![serde_generate::Encoding::MessagePack]);
+    let generator = python3::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let references = (0..40)
+        .map(|i| {
+            format!(
+                "bytes({:?})",
+                rmp_serde::to_vec_named(&make_measurement(i)).unwrap()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+    writeln!(
+        source,
+        r#"
+references = [
+    {}
+]
+for input in references:
+    value = Measurement.msgpack_deserialize(input)
+    assert value.msgpack_serialize() == input
+    assert Measurement.msgpack_deserialize(value.msgpack_serialize()) == value
+    assert value.kinds[0] == Sensor__Idle()
+"#,
+        references,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
+        .env("PYTHONPATH", python_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_python_msgpack_runtime_on_durations() {
+    let (registry, well_known_types) = test_utils::get_schedule_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.py");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![serde_generate::Encoding::MessagePack]);
+    let generator = python3::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_well_known_types(well_known_types);
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = rmp_serde::to_vec_named(&test_utils::get_schedule_sample()).unwrap();
+    writeln!(
+        source,
+        r#"
+input = bytes({:?})
+value = Schedule.msgpack_deserialize(input)
+assert value.delay == datetime.timedelta(days=1, hours=1, minutes=1, seconds=1, microseconds=500)
+assert value.msgpack_serialize() == input
+"#,
+        reference,
+    )
+    .unwrap();
+
+    let python_path = std::env::var("PYTHONPATH").unwrap_or_default() + ":runtime/python";
+    let status = Command::new("python3")
+        .arg(source_path)
         .env("PYTHONPATH", python_path)
         .status()
         .unwrap();
@@ -2,21 +2,43 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use serde_generate::{rust, CodeGeneratorConfig};
+use serde_generate::{
+    indent::IndentConfig, rust, validation::Language, CodeGeneratorConfig, Encoding,
+    KeywordEscaping,
+};
+use serde_reflection::{ContainerFormat, Format, Named, Registry};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
 // Quick test using rustc directly.
 fn test_that_rust_code_compiles_with_config(
     config: &CodeGeneratorConfig,
 ) -> (TempDir, std::path::PathBuf) {
-    let registry = test_utils::get_registry().unwrap();
+    test_that_rust_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_that_rust_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> (TempDir, std::path::PathBuf) {
     let dir = tempdir().unwrap();
     let source_path = dir.path().join("test.rs");
     let mut source = File::create(&source_path).unwrap();
 
     let generator = rust::CodeGenerator::new(config);
-    generator.output(&mut source, &registry).unwrap();
+    generator.output(&mut source, registry).unwrap();
 
     let status = Command::new("rustc")
         .current_dir(dir.path())
@@ -38,6 +60,69 @@ fn test_that_rust_code_compiles() {
     test_that_rust_code_compiles_with_config(&config);
 }
 
+#[test]
+fn test_rust_code_with_two_space_indentation() {
+    let registry = test_utils::get_registry().unwrap();
+    let generate = |config: &CodeGeneratorConfig| {
+        let mut source = Vec::new();
+        rust::CodeGenerator::new(config)
+            .output(&mut source, &registry)
+            .unwrap();
+        String::from_utf8(source).unwrap()
+    };
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let source = generate(&config);
+    let indented_source = generate(&config.with_indent(IndentConfig::Space(2)));
+    assert!(indented_source.contains("\n  pub x: u32,\n"));
+
+    // Only the leading spaces of each line change, by units of 4 spaces.
+    let mut expected = String::new();
+    for line in source.lines() {
+        let content = line.trim_start_matches(' ');
+        let columns = line.len() - content.len();
+        if !content.is_empty() {
+            expected.push_str(&" ".repeat(columns / 4 * 2 + columns % 4));
+            expected.push_str(content);
+        }
+        expected.push('\n');
+    }
+    assert_eq!(indented_source, expected);
+}
+
+#[test]
+fn test_rust_code_with_one_way_serialization() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    for (serialization, deserialization, import, derive) in [
+        (
+            false,
+            true,
+            "use serde::Deserialize;\n",
+            "PartialOrd, Deserialize)]",
+        ),
+        (
+            true,
+            false,
+            "use serde::Serialize;\n",
+            "PartialOrd, Serialize)]",
+        ),
+    ] {
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_serialization(serialization)
+            .with_deserialization(deserialization);
+        let mut source = Vec::new();
+        rust::CodeGenerator::new(&config)
+            .output(&mut source, &registry)
+            .unwrap();
+        let source = String::from_utf8(source).unwrap();
+        assert!(source.contains(import));
+        assert!(source.contains("use serde_bytes::ByteBuf as Bytes;\n"));
+        assert!(source.contains(&format!(
+            "#[derive(Clone, Debug, PartialEq, {}\npub struct Test",
+            derive
+        )));
+    }
+}
+
 #[test]
 fn test_that_rust_code_compiles_with_comments() {
     let comments = vec![(
@@ -111,10 +196,59 @@ mod foo {{
     assert!(status.success());
 }
 
-// Full test using cargo. This may take a while.
 #[test]
-fn test_that_rust_code_compiles_with_serialization() {
-    let registry = test_utils::get_registry().unwrap();
+fn test_that_rust_code_compiles_with_map_type() {
+    let mut registry = Registry::new();
+    registry.insert(
+        "Index".to_string(),
+        ContainerFormat::Struct(vec![Named {
+            name: "entries".to_string(),
+            value: Format::Map {
+                key: Box::new(Format::Str),
+                value: Box::new(Format::U64),
+            },
+        }]),
+    );
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("test.rs");
+    let mut source = File::create(&source_path).unwrap();
+
+    let map_types = vec![(Language::Rust, "std::collections::HashMap".to_string())]
+        .into_iter()
+        .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_map_types(map_types)
+        .with_serialization(false);
+    // Hash maps are not ordered.
+    let generator = rust::CodeGenerator::new(&config).with_derive_macros(vec![
+        "Clone".into(),
+        "Debug".into(),
+        "PartialEq".into(),
+    ]);
+    generator.output(&mut source, &registry).unwrap();
+
+    let status = Command::new("rustc")
+        .current_dir(dir.path())
+        .arg("--crate-type")
+        .arg("lib")
+        .arg("--edition")
+        .arg("2018")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("use std::collections::HashMap as Map;"));
+    assert!(content.contains("pub entries: Map<String, u64>,"));
+}
+
+// Full test using cargo. This may take a while.
+// Build the generated code with `cargo` and return its content.
+fn test_that_rust_code_compiles_with_generator(
+    generator: &rust::CodeGenerator,
+    registry: &Registry,
+) -> String {
     let dir = tempdir().unwrap();
     std::fs::write(
         dir.path().join("Cargo.toml"),
@@ -133,12 +267,9 @@ serde_bytes = "0.11"
     .unwrap();
     std::fs::create_dir(dir.path().join("src")).unwrap();
 
-    let config = CodeGeneratorConfig::new("testing".to_string());
-    let generator = rust::CodeGenerator::new(&config);
-
     let source_path = dir.path().join("src/lib.rs");
-    let mut source = File::create(source_path).unwrap();
-    generator.output(&mut source, &registry).unwrap();
+    let mut source = File::create(&source_path).unwrap();
+    generator.output(&mut source, registry).unwrap();
 
     // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
     let target_dir = std::env::current_dir().unwrap().join("../target");
@@ -150,6 +281,35 @@ serde_bytes = "0.11"
         .status()
         .unwrap();
     assert!(status.success());
+
+    std::fs::read_to_string(source_path).unwrap()
+}
+
+#[test]
+fn test_that_rust_code_compiles_with_serialization() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let generator = rust::CodeGenerator::new(&config);
+    test_that_rust_code_compiles_with_generator(&generator, &registry);
+}
+
+#[test]
+fn test_that_rust_code_compiles_with_non_exhaustive_containers() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+
+    let generator = rust::CodeGenerator::new(&config).with_non_exhaustive_enums(true);
+    let content = test_that_rust_code_compiles_with_generator(&generator, &registry);
+    assert!(content.contains("#[non_exhaustive]\npub enum SerdeData {"));
+    assert!(!content.contains("#[non_exhaustive]\npub struct"));
+
+    let generator = rust::CodeGenerator::new(&config)
+        .with_non_exhaustive_enums(true)
+        .with_non_exhaustive_structs(true);
+    let content = test_that_rust_code_compiles_with_generator(&generator, &registry);
+    assert!(content.contains("#[non_exhaustive]\npub enum SerdeData {"));
+    assert!(content.contains("#[non_exhaustive]\npub struct UnitStruct;"));
+    assert!(content.contains("#[non_exhaustive]\npub struct Struct {"));
 }
 
 #[test]
@@ -167,3 +327,112 @@ fn test_that_rust_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(source_path).unwrap();
     assert!(content.contains("// custom code\n"));
 }
+
+#[test]
+fn test_that_rust_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()))
+        .with_serialization(false);
+    let (_dir, source_path) =
+        test_that_rust_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("pub type_: u8,"));
+
+    // Serialized names are preserved.
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    let mut content = Vec::new();
+    let generator = rust::CodeGenerator::new(&config);
+    generator.output(&mut content, &registry).unwrap();
+    let content = String::from_utf8(content).unwrap();
+    assert!(content.contains("#[serde(rename = \"type\")]\n    pub type_: u8,"));
+}
+
+#[test]
+fn test_that_rust_code_compiles_with_raw_identifiers() {
+    let registry = test_utils::get_keyword_registry(&["fn", "match", "type"]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_serialization(false)
+        .with_keyword_escaping(KeywordEscaping::Prefix("r#".to_string()));
+    let (_dir, source_path) =
+        test_that_rust_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("pub r#type: u8,"));
+}
+
+#[test]
+fn test_rust_code_with_wasm_exports() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let generator = rust::CodeGenerator::new(&config).with_wasm_exports(true);
+    let mut content = Vec::new();
+    generator.output(&mut content, &registry).unwrap();
+    let content = String::from_utf8(content).unwrap();
+    assert!(content.contains(
+        "pub fn __serde_encode_SerdeData(value: wasm_bindgen::JsValue) -> Result<Vec<u8>, wasm_bindgen::JsError> {"
+    ));
+    assert!(content.contains("let value: SerdeData = bcs::from_bytes(input)?;"));
+
+    // A single encoding is exported.
+    for encodings in [vec![], vec![Encoding::Bcs, Encoding::Bincode]] {
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_encodings(encodings)
+            .with_allow_nonstandard_floats(true);
+        let generator = rust::CodeGenerator::new(&config).with_wasm_exports(true);
+        let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "WebAssembly exports require exactly one supported encoding"
+        );
+    }
+
+    let installed_targets = Command::new("rustup")
+        .arg("target")
+        .arg("list")
+        .arg("--installed")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default();
+    if !installed_targets.contains("wasm32-unknown-unknown") {
+        eprintln!("Skipping the wasm32 build: the target wasm32-unknown-unknown is not installed");
+        return;
+    }
+    let dir = tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        r#"[package]
+name = "testing"
+version = "0.1.0"
+edition = "2018"
+
+[lib]
+crate-type = ["cdylib"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+serde_bytes = "0.11"
+bcs = "0.1.1"
+wasm-bindgen = "0.2"
+serde-wasm-bindgen = "0.6"
+
+[workspace]
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/lib.rs"), content).unwrap();
+    let target_dir = std::env::current_dir().unwrap().join("../target");
+    let status = Command::new("cargo")
+        .current_dir(dir.path())
+        .arg("build")
+        .arg("--target")
+        .arg("wasm32-unknown-unknown")
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -3,7 +3,7 @@
 
 use crate::test_utils;
 use crate::test_utils::Runtime;
-use serde_generate::{rust, CodeGeneratorConfig};
+use serde_generate::{rust, CodeGeneratorConfig, SourceInstaller};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
 
@@ -82,3 +82,185 @@ fn main() {{
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_rust_runtime_with_single_file_output() {
+    test_rust_runtime_in_host_crate(
+        rust::OutputMode::SingleFile("src/generated.rs".into()),
+        false,
+        r#"include!("generated.rs");"#,
+    );
+}
+
+#[test]
+fn test_rust_runtime_with_module_dir_output() {
+    test_rust_runtime_in_host_crate(
+        rust::OutputMode::ModuleDir("src/generated".into()),
+        false,
+        "mod generated;\nuse generated::*;",
+    );
+}
+
+#[test]
+fn test_rust_runtime_with_split_module_dir_output() {
+    test_rust_runtime_in_host_crate(
+        rust::OutputMode::ModuleDir("src/generated".into()),
+        true,
+        "mod generated;\nuse generated::*;",
+    );
+}
+
+// Install the definitions into an existing crate whose `main.rs` starts with `header`.
+fn test_rust_runtime_in_host_crate(
+    output_mode: rust::OutputMode,
+    split_modules: bool,
+    header: &str,
+) {
+    let runtime = Runtime::Bcs;
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let mut file = std::fs::File::create(dir.path().join("Cargo.toml")).unwrap();
+    write!(
+        &mut file,
+        r#"[package]
+name = "host"
+version = "0.1.0"
+edition = "2018"
+
+[dependencies]
+serde = {{ version = "1.0", features = ["derive"] }}
+serde_bytes = "0.11"
+{}
+
+[workspace]
+"#,
+        runtime.rust_package()
+    )
+    .unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let installer = rust::Installer::new(dir.path().to_path_buf())
+        .with_output_mode(output_mode)
+        .with_split_modules(split_modules);
+    installer.install_module(&config, &registry).unwrap();
+    assert!(!dir.path().join("testing").exists());
+
+    let encodings: Vec<_> = runtime
+        .get_positive_samples()
+        .iter()
+        .map(|bytes| format!("vec!{:?}", bytes))
+        .collect();
+
+    let source_path = dir.path().join("src/main.rs");
+    let mut source = File::create(source_path).unwrap();
+    writeln!(
+        source,
+        r#"{}
+
+fn main() {{
+    for encoding in vec![{}] {{
+        let value = {}::<SerdeData>(&encoding).unwrap();
+        let s = {}(&value).unwrap();
+        assert_eq!(s, encoding);
+    }}
+}}
+"#,
+        header,
+        encodings.join(", "),
+        runtime.quote_deserialize(),
+        runtime.quote_serialize(),
+    )
+    .unwrap();
+
+    let target_dir = std::env::current_dir().unwrap().join("../target");
+    let status = Command::new("cargo")
+        .current_dir(dir.path())
+        .arg("run")
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_rust_single_file_output_conflicts_with_split_modules() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let installer = rust::Installer::new(dir.path().to_path_buf())
+        .with_output_mode(rust::OutputMode::SingleFile("generated.rs".into()))
+        .with_split_modules(true);
+    let error = installer.install_module(&config, &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "split modules cannot be combined with single-file output in Rust"
+    );
+}
+
+#[test]
+fn test_rust_compact_encoding() {
+    let registry = test_utils::get_telemetry_registry().unwrap();
+    let samples = test_utils::get_telemetry_samples();
+    // Defaulted fields make the encoding smaller.
+    assert!(samples[0].2.len() < samples[0].1.len());
+    assert!(samples[1].2.len() < samples[1].1.len());
+
+    let dir = tempdir().unwrap();
+    let mut file = std::fs::File::create(dir.path().join("Cargo.toml")).unwrap();
+    write!(
+        &mut file,
+        r#"[package]
+name = "testing2"
+version = "0.1.0"
+edition = "2018"
+
+[dependencies]
+serde = {{ version = "1.0", features = ["derive"] }}
+serde_bytes = "0.11"
+bcs = "0.1.1"
+
+[workspace]
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_elision_containers(["Telemetry".to_string()].into_iter().collect());
+    let generator = rust::CodeGenerator::new(&config);
+
+    let source_path = dir.path().join("src/main.rs");
+    let mut source = File::create(source_path).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let encodings: Vec<_> = samples
+        .iter()
+        .map(|(_, bcs, compact)| format!("(vec!{:?}, vec!{:?})", bcs, compact))
+        .collect();
+
+    writeln!(
+        source,
+        r#"
+fn main() {{
+    for (encoding, compact) in vec![{}] {{
+        let value = bcs::from_bytes::<Telemetry>(&encoding).unwrap();
+        assert_eq!(value.compact_serialize().unwrap(), compact);
+        assert_eq!(bcs::to_bytes(&value).unwrap(), encoding);
+    }}
+}}
+"#,
+        encodings.join(", "),
+    )
+    .unwrap();
+
+    let target_dir = std::env::current_dir().unwrap().join("../target");
+    let status = Command::new("cargo")
+        .current_dir(dir.path())
+        .arg("run")
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
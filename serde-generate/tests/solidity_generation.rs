@@ -1,6 +1,7 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::test_utils;
 use crate::test_utils::{NewTypeStruct, OtherTypes, Struct, TupleStruct};
 use revm::primitives::Bytes;
 use serde::{
@@ -19,6 +20,25 @@ use std::{
 };
 use tempfile::tempdir;
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "abstract", "after", "alias", "anonymous", "as", "assembly", "break", "catch", "constant",
+    "continue", "constructor", "contract", "delete", "do", "else", "emit", "enum", "error",
+    "event", "external", "fallback", "for", "function", "if", "immutable", "import", "indexed",
+    "interface", "internal", "is", "library", "mapping", "memory", "modifier", "new", "override",
+    "payable", "pragma", "private", "public", "pure", "receive", "return", "returns", "revert",
+    "storage", "struct", "throw", "try", "type", "unchecked", "using", "virtual", "view", "while",
+    "addmod", "blockhash", "ecrecover", "keccak256", "mulmod", "sha256", "ripemd160", "block",
+    "msg", "tx", "balance", "transfer", "send", "call", "delegatecall", "staticcall", "this",
+    "super", "gwei", "finney", "szabo", "ether", "seconds", "minutes", "hours", "days", "weeks",
+    "years", "wei", "hex", "address", "bool", "bytes", "string", "int", "int8", "int16", "int32",
+    "int64", "int128", "int256", "uint", "uint8", "uint16", "uint32", "uint64", "uint128",
+    "uint256", "bytes1", "bytes2", "bytes3", "bytes4", "bytes5", "bytes6", "bytes7", "bytes8",
+    "bytes9", "bytes10", "bytes11", "bytes12", "bytes13", "bytes14", "bytes15", "bytes16",
+    "bytes17", "bytes18", "bytes19", "bytes20", "bytes21", "bytes22", "bytes23", "bytes24",
+    "bytes25", "bytes26", "bytes27", "bytes28", "bytes29", "bytes30", "bytes31", "bytes32",
+];
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum SerdeData {
     PrimitiveTypes(PrimitiveTypes),
@@ -144,9 +164,87 @@ fn test_solidity_compilation() {
     let test_path = path.join("test.sol");
     {
         let mut test_file = File::create(&test_path).unwrap();
-        let generator = solidity::CodeGenerator::new(&config);
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
+        generator.output(&mut test_file, &registry).unwrap();
+    }
+
+    get_bytecode(path, "test.sol", "test").unwrap();
+}
+
+#[test]
+fn test_solidity_rejects_large_enums() {
+    let config = CodeGeneratorConfig::new("test".to_string());
+    let registry = test_utils::get_large_enum_registry();
+    let generator = solidity::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Enum LargeEnum has 300 variants but Solidity supports at most 128"
+    );
+}
+
+#[test]
+fn test_solidity_reports_unsupported_formats() {
+    let config = CodeGeneratorConfig::new("test".to_string());
+    let registry = test_utils::get_registry().unwrap();
+    let generator = solidity::CodeGenerator::try_new(&config).unwrap();
+    let issues = generator
+        .validate(&registry)
+        .into_iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        issues,
+        vec![
+            "PrimitiveTypes.f_f32: floating point is not supported in solidity",
+            "PrimitiveTypes.f_f64: floating point is not supported in solidity",
+            "UnitStruct: UnitStruct is not supported in solidity",
+        ]
+    );
+    // Code generation fails instead of panicking.
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(error.to_string(), issues.join("\n"));
+}
+
+#[test]
+fn test_solidity_compilation_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("test".to_string());
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    let test_path = path.join("test.sol");
+    {
+        let mut test_file = File::create(&test_path).unwrap();
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
+        generator.output(&mut test_file, &registry).unwrap();
+    }
+    let content = std::fs::read_to_string(&test_path).unwrap();
+    assert!(content.contains("uint8 type_;"));
+
+    get_bytecode(path, "test.sol", "test").unwrap();
+}
+
+#[test]
+fn test_solidity_compilation_with_custom_errors() {
+    let config = CodeGeneratorConfig::new("test".to_string());
+    let registry = get_solidity_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    let test_path = path.join("test.sol");
+    {
+        let mut test_file = File::create(&test_path).unwrap();
+        let generator = solidity::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_custom_errors(true);
         generator.output(&mut test_file, &registry).unwrap();
     }
+    let content = std::fs::read_to_string(&test_path).unwrap();
+    assert!(content.contains("pragma solidity ^0.8.4;"));
+    assert!(content.contains("error IncompleteDeserialization(uint256 expected, uint256 actual);"));
+    assert!(content.contains("revert IncompleteDeserialization(input.length, new_pos);"));
+    assert!(content.contains("revert PositionOutOfBound(pos, input.length);"));
+    assert!(!content.contains("incomplete deserialization"));
+    assert!(!content.contains("Position out of bound"));
 
     get_bytecode(path, "test.sol", "test").unwrap();
 }
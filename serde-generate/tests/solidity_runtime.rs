@@ -1,6 +1,6 @@
 use crate::solidity_generation::{get_bytecode, get_registry_from_type};
 use alloy_sol_types::sol;
-use alloy_sol_types::SolCall as _;
+use alloy_sol_types::{SolCall as _, SolError as _};
 use revm::db::InMemoryDB;
 use revm::{
     primitives::{Bytes, ExecutionResult, Output, TxKind},
@@ -14,7 +14,7 @@ use serde_generate::{solidity, CodeGeneratorConfig};
 use std::{fmt::Display, fs::File, io::Write};
 use tempfile::tempdir;
 
-fn test_contract(bytecode: Bytes, encoded_args: Bytes) {
+fn run_contract(bytecode: Bytes, encoded_args: Bytes) -> ExecutionResult {
     let mut database = InMemoryDB::default();
     let contract_address = {
         let mut evm: Evm<'_, (), _> = Evm::builder()
@@ -45,8 +45,11 @@ fn test_contract(bytecode: Bytes, encoded_args: Bytes) {
         })
         .build();
 
-    let result: ExecutionResult = evm.transact_commit().unwrap();
+    evm.transact_commit().unwrap()
+}
 
+fn test_contract(bytecode: Bytes, encoded_args: Bytes) {
+    let result = run_contract(bytecode, encoded_args);
     let ExecutionResult::Success { .. } = result else {
         panic!("The TxKind::Call execution failed");
     };
@@ -70,7 +73,7 @@ fn test_vector_serialization<T: Serialize + DeserializeOwned + Display>(
         let mut test_code_file = File::create(&test_code_path)?;
         let name = "ExampleCodeBase".to_string();
         let config = CodeGeneratorConfig::new(name);
-        let generator = solidity::CodeGenerator::new(&config);
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
         generator.output(&mut test_code_file, &registry).unwrap();
 
         let len = t.vec.len();
@@ -180,7 +183,7 @@ fn test_simple_enum_serialization() -> anyhow::Result<()> {
         let mut test_code_file = File::create(&test_code_path)?;
         let name = "ExampleCodeBase".to_string();
         let config = CodeGeneratorConfig::new(name);
-        let generator = solidity::CodeGenerator::new(&config);
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
         generator.output(&mut test_code_file, &registry).unwrap();
 
         writeln!(
@@ -240,7 +243,7 @@ fn test_struct_bool_string() -> anyhow::Result<()> {
         let mut test_code_file = File::create(&test_code_path)?;
         let name = "ExampleCodeBase".to_string();
         let config = CodeGeneratorConfig::new(name);
-        let generator = solidity::CodeGenerator::new(&config);
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
         generator.output(&mut test_code_file, &registry).unwrap();
 
         writeln!(
@@ -304,7 +307,7 @@ fn test_complex_enum_test() -> anyhow::Result<()> {
         let mut test_code_file = File::create(&test_code_path)?;
         let name = "ExampleCodeBase".to_string();
         let config = CodeGeneratorConfig::new(name);
-        let generator = solidity::CodeGenerator::new(&config);
+        let generator = solidity::CodeGenerator::try_new(&config).unwrap();
         generator.output(&mut test_code_file, &registry).unwrap();
 
         writeln!(
@@ -349,3 +352,68 @@ contract ExampleCode is ExampleCodeBase {{
     }
     Ok(())
 }
+
+#[test]
+fn test_custom_errors_for_invalid_inputs() -> anyhow::Result<()> {
+    let registry = get_registry_from_type::<TestVec<u16>>();
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+
+    let test_code_path = path.join("test_code.sol");
+    {
+        let mut test_code_file = File::create(&test_code_path)?;
+        let config = CodeGeneratorConfig::new("ExampleCodeBase".to_string());
+        let generator = solidity::CodeGenerator::try_new(&config)
+            .unwrap()
+            .with_custom_errors(true);
+        generator.output(&mut test_code_file, &registry).unwrap();
+        writeln!(
+            test_code_file,
+            r#"
+contract ExampleCode is ExampleCodeBase {{
+
+    function test_deserialization(bytes calldata input) external {{
+      bcs_deserialize_TestVec(input);
+    }}
+
+}}
+"#
+        )?;
+    }
+    let bytecode = get_bytecode(path, "test_code.sol", "ExampleCode")?;
+
+    sol! {
+      function test_deserialization(bytes calldata input);
+      error IncompleteDeserialization(uint256 expected, uint256 actual);
+      error PositionOutOfBound(uint256 position, uint256 length);
+    }
+    let revert_output = |input: Vec<u8>| {
+        let input = Bytes::from(input);
+        let fct_args = test_deserializationCall { input }.abi_encode().into();
+        match run_contract(bytecode.clone(), fct_args) {
+            ExecutionResult::Revert { output, .. } => output,
+            result => panic!("Expected the call to revert: {:?}", result),
+        }
+    };
+
+    let input = bcs::to_bytes(&TestVec { vec: vec![42_u16] }).unwrap();
+
+    // A truncated input reads past the end of the buffer.
+    let output = revert_output(input[..input.len() - 1].to_vec());
+    assert_eq!(output[..4], PositionOutOfBound::SELECTOR);
+
+    // Trailing bytes are reported with the expected and actual lengths.
+    let mut extended_input = input.clone();
+    extended_input.push(0);
+    let output = revert_output(extended_input);
+    let error = IncompleteDeserialization::abi_decode(&output, true)?;
+    assert_eq!(
+        error.expected,
+        alloy_sol_types::private::U256::from(input.len() + 1)
+    );
+    assert_eq!(
+        error.actual,
+        alloy_sol_types::private::U256::from(input.len())
+    );
+    Ok(())
+}
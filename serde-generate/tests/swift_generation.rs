@@ -3,11 +3,21 @@
 
 use crate::test_utils;
 use serde::{Deserialize, Serialize};
-use serde_generate::{swift, CodeGeneratorConfig, Encoding};
+use serde_generate::{swift, CodeGeneratorConfig, Encoding, KeywordEscaping, SourceInstaller};
 use serde_reflection::{Registry, Result, Samples, Tracer, TracerConfig};
 use std::{collections::BTreeMap, fs::File, io::Write, process::Command, sync::Mutex};
 use tempfile::{tempdir, TempDir};
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "Any", "Self", "as", "associatedtype", "break", "case", "catch", "class", "continue", "default",
+    "defer", "deinit", "do", "else", "enum", "extension", "fallthrough", "false", "fileprivate",
+    "for", "func", "guard", "if", "import", "in", "init", "inout", "internal", "is", "let", "nil",
+    "open", "operator", "private", "protocol", "public", "repeat", "rethrows", "return", "self",
+    "static", "struct", "subscript", "super", "switch", "throw", "throws", "true", "try",
+    "typealias", "var", "where", "while",
+];
+
 lazy_static::lazy_static! {
     // Avoid interleaving compiler calls because the output gets very messy.
     static ref MUTEX: Mutex<()> = Mutex::new(());
@@ -75,7 +85,7 @@ let package = Package(
     let source_path = dir.path().join("Sources/Testing/Testing.swift");
     let mut source = File::create(&source_path).unwrap();
 
-    let generator = swift::CodeGenerator::new(config);
+    let generator = swift::CodeGenerator::try_new(config).unwrap();
     generator.output(&mut source, registry).unwrap();
 
     {
@@ -105,8 +115,9 @@ fn test_that_swift_code_compiles_without_serialization() {
 
 #[test]
 fn test_that_swift_code_compiles_with_bcs() {
-    let config =
-        CodeGeneratorConfig::new("Testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("Testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_that_swift_code_compiles_with_config(&config);
 }
 
@@ -165,12 +176,43 @@ fn test_swift_code_with_external_definitions() {
     definitions.insert("foo".to_string(), vec!["Tree".to_string()]);
     let config =
         CodeGeneratorConfig::new("Testing".to_string()).with_external_definitions(definitions);
-    let generator = swift::CodeGenerator::new(&config);
+    let generator = swift::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     // References were updated.
     let content = std::fs::read_to_string(source_path).unwrap();
     assert!(content.contains("foo.Tree"));
+    // Containers depending on external types are not known to be `Sendable`.
+    assert!(content.contains("indirect public enum SerdeData: Hashable {"));
+    assert!(content.contains("public struct PrimitiveTypes: Hashable, Sendable {"));
+}
+
+#[test]
+fn test_that_swift_code_is_sendable() {
+    let registry = test_utils::get_registry().unwrap();
+    let config = CodeGeneratorConfig::new("Testing".to_string());
+    let generator = swift::CodeGenerator::try_new(&config).unwrap();
+    let mut content = Vec::new();
+    generator.output(&mut content, &registry).unwrap();
+    let content = String::from_utf8(content).unwrap();
+    assert!(content.contains("indirect public enum SerdeData: Hashable, Sendable {"));
+    assert!(content.contains("public struct PrimitiveTypes: Hashable, Sendable {"));
+
+    if which::which("swift").is_err() {
+        return;
+    }
+    let (dir, _) = test_that_swift_code_compiles_with_config_and_registry(&config, &registry);
+    let _lock = MUTEX.lock();
+    let output = Command::new("swift")
+        .current_dir(dir.path())
+        .arg("build")
+        .arg("-Xswiftc")
+        .arg("-strict-concurrency=complete")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("Sendable"), "{}", stderr);
 }
 
 #[test]
@@ -188,3 +230,88 @@ fn test_that_swift_code_compiles_with_custom_code() {
     let content = std::fs::read_to_string(source_path).unwrap();
     assert!(content.contains("// custom1"));
 }
+
+#[test]
+fn test_swift_installer_preserves_user_files() {
+    let dir = tempdir().unwrap();
+    let registry = get_small_registry().unwrap();
+    let config = CodeGeneratorConfig::new("Testing".to_string());
+
+    swift::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+    let module_path = dir.path().join("Sources/Testing");
+    let source_path = module_path.join("Testing.swift");
+    assert!(std::fs::read_to_string(&source_path)
+        .unwrap()
+        .starts_with("// @generated by serde-generate"));
+
+    let user_file = module_path.join("TestExtensions.swift");
+    std::fs::write(&user_file, "extension Test {}\n").unwrap();
+    let stale_file = module_path.join("Old.swift");
+    std::fs::write(
+        &stale_file,
+        "// @generated by serde-generate. Do not edit.\n\nimport Serde\n",
+    )
+    .unwrap();
+
+    // Without cleaning, stale files are kept.
+    swift::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(stale_file.exists());
+
+    swift::Installer::new(dir.path().to_path_buf())
+        .with_clean(true)
+        .install_module(&config, &registry)
+        .unwrap();
+    assert!(!stale_file.exists());
+    assert!(source_path.exists());
+    assert_eq!(
+        std::fs::read_to_string(&user_file).unwrap(),
+        "extension Test {}\n"
+    );
+
+    // Files without the marker are never overwritten.
+    std::fs::write(&source_path, "// Custom code\n").unwrap();
+    assert!(swift::Installer::new(dir.path().to_path_buf())
+        .install_module(&config, &registry)
+        .is_err());
+    assert_eq!(
+        std::fs::read_to_string(&source_path).unwrap(),
+        "// Custom code\n"
+    );
+}
+
+#[test]
+fn test_that_swift_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("Testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    let (_dir, source_path) =
+        test_that_swift_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("public var protocol_: UInt8"));
+}
+
+#[test]
+fn test_swift_code_with_tuple_field_names() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("Testing.swift");
+    let mut source = File::create(&source_path).unwrap();
+
+    let mut names = BTreeMap::new();
+    names.insert(
+        ("SerdeData".to_string(), "TupleVariant".to_string()),
+        vec!["count".to_string(), "total".to_string()],
+    );
+    let config = CodeGeneratorConfig::new("Testing".to_string()).with_tuple_field_names(names);
+    let generator = swift::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("case tupleVariant(count: UInt32, total: UInt64)"));
+    assert!(content.contains("case .tupleVariant(let count, let total):"));
+    assert!(content.contains("return .tupleVariant(count: count, total: total)"));
+}
@@ -165,8 +165,9 @@ fn test_swift_runtime_on_supported_types(runtime: Runtime) {
     // std::fs::remove_dir_all(my_path).unwrap_or(());
     // std::fs::create_dir_all(my_path).unwrap();
     let dir = tempfile::tempdir().unwrap();
-    let config =
-        CodeGeneratorConfig::new("Testing".to_string()).with_encodings(vec![runtime.into()]);
+    let config = CodeGeneratorConfig::new("Testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
     let registry = test_utils::get_registry().unwrap();
     let installer = swift::Installer::new(dir.path().to_path_buf());
     installer.install_module(&config, &registry).unwrap();
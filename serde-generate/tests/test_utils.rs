@@ -5,7 +5,10 @@ use maplit::btreemap;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use serde_generate::Encoding;
-use serde_reflection::{Registry, Result, Samples, Tracer, TracerConfig};
+use serde_reflection::{
+    ContainerFormat, Format, FormatHolder, Named, Registry, Result, Samples, Tracer, TracerConfig,
+    VariantFormat, WellKnownTypes,
+};
 use std::collections::BTreeMap;
 
 // Simple data formats used to create and test values in each language.
@@ -31,6 +34,437 @@ pub fn get_simple_registry() -> Result<Registry> {
     tracer.registry()
 }
 
+// Maps keyed by data-carrying enums: lookups after deserialization require value equality.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Key {
+    Id(u32),
+    Name(String),
+    Pair(u8, u8),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Value {
+    pub label: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeyedMap {
+    pub entries: BTreeMap<Key, Value>,
+}
+
+pub fn get_keyed_map_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<KeyedMap>(&samples)?;
+    tracer.trace_type::<Key>(&samples)?;
+    tracer.registry()
+}
+
+pub fn get_keyed_map_sample() -> KeyedMap {
+    KeyedMap {
+        entries: btreemap! {
+            Key::Id(2) => Value { label: "two".to_string() },
+            Key::Name("a".to_string()) => Value { label: "a".to_string() },
+            Key::Pair(1, 3) => Value { label: "pair".to_string() },
+        },
+    }
+}
+
+// Durations recognized as a standard type by the tracer.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Schedule {
+    pub delay: std::time::Duration,
+    pub timeout: Option<std::time::Duration>,
+}
+
+pub fn get_schedule_registry() -> Result<(Registry, WellKnownTypes)> {
+    let mut tracer = Tracer::new(TracerConfig::default().recognize_standard_types(true));
+    tracer.trace_simple_type::<Schedule>()?;
+    let well_known_types = tracer.well_known_types();
+    Ok((tracer.registry()?, well_known_types))
+}
+
+pub fn get_schedule_sample() -> Schedule {
+    Schedule {
+        delay: std::time::Duration::new(90061, 500_000),
+        timeout: Some(std::time::Duration::from_millis(1500)),
+    }
+}
+
+// Tuples nested in sequences, options, and map values.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TupleShapes {
+    pub pairs: Vec<(u32, String)>,
+    pub maybe: Option<(u8, u8)>,
+    pub table: BTreeMap<String, (u32, String)>,
+}
+
+pub fn get_tuple_shapes_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<TupleShapes>()?;
+    tracer.registry()
+}
+
+pub fn get_tuple_shapes_sample() -> TupleShapes {
+    TupleShapes {
+        pairs: vec![(1, "one".to_string()), (2, "two".to_string())],
+        maybe: Some((3, 4)),
+        table: btreemap! {
+            "a".to_string() => (5, "five".to_string()),
+            "b".to_string() => (6, "six".to_string()),
+        },
+    }
+}
+
+// 64-bit and 128-bit integers that cannot be represented exactly by a double.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct WideIntegers {
+    pub a: u64,
+    pub b: u128,
+    pub c: i64,
+    pub d: i128,
+}
+
+pub fn get_wide_integers_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<WideIntegers>()?;
+    tracer.registry()
+}
+
+pub fn get_wide_integers_sample() -> WideIntegers {
+    WideIntegers {
+        a: (1 << 53) + 1,
+        b: (1 << 100) + 1,
+        c: -(1 << 53) - 1,
+        d: -(1 << 100) - 1,
+    }
+}
+
+// Floating-point values whose bit patterns must survive a round-trip in every runtime. Since
+// `==` is unreliable on such values, tests compare bit patterns instead.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FloatEdges {
+    pub singles: Vec<f32>,
+    pub doubles: Vec<f64>,
+}
+
+pub fn get_float_edges_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<FloatEdges>()?;
+    tracer.registry()
+}
+
+/// Bit patterns of the sample below: NaN, a negative NaN with a payload, -0.0, +inf, -inf, and
+/// a signaling NaN.
+pub const F32_EDGE_BITS: [u32; 6] = [
+    0x7fc0_0000,
+    0xffc0_0001,
+    0x8000_0000,
+    0x7f80_0000,
+    0xff80_0000,
+    0x7fa0_0000,
+];
+
+pub const F64_EDGE_BITS: [u64; 6] = [
+    0x7ff8_0000_0000_0000,
+    0xfff8_0000_0000_0001,
+    0x8000_0000_0000_0000,
+    0x7ff0_0000_0000_0000,
+    0xfff0_0000_0000_0000,
+    0x7ff4_0000_0000_0000,
+];
+
+pub fn get_float_edges_sample() -> FloatEdges {
+    FloatEdges {
+        singles: F32_EDGE_BITS
+            .iter()
+            .map(|bits| f32::from_bits(*bits))
+            .collect(),
+        doubles: F64_EDGE_BITS
+            .iter()
+            .map(|bits| f64::from_bits(*bits))
+            .collect(),
+    }
+}
+
+// Linked list chained through its last field, used to test long chains of values.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Chain {
+    pub value: u32,
+    pub next: Option<Box<Chain>>,
+}
+
+pub fn get_chain_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Chain>()?;
+    tracer.registry()
+}
+
+// Long sequence of small records, used to test streaming deserialization.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Record {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Records(pub Vec<Record>);
+
+pub fn get_records_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Records>()?;
+    tracer.registry()
+}
+
+/// The records `0..length`, named after their ids.
+pub fn get_records(length: u32) -> Records {
+    Records(
+        (0..length)
+            .map(|id| Record {
+                id,
+                name: format!("record{}", id),
+            })
+            .collect(),
+    )
+}
+
+// Strings and bytes nested in sequences, options, and maps, used to test zero-copy
+// deserialization.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Document {
+    pub title: String,
+    pub digest: ByteBuf,
+    pub paragraphs: Vec<Vec<String>>,
+    pub subtitle: Option<String>,
+    pub attachments: BTreeMap<String, ByteBuf>,
+}
+
+pub fn get_document_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Document>()?;
+    tracer.registry()
+}
+
+pub fn get_document_sample() -> Document {
+    Document {
+        title: "Caf\u{e9}".to_string(),
+        digest: ByteBuf::from(vec![0xde, 0xad, 0xbe, 0xef]),
+        paragraphs: vec![
+            vec!["first".to_string(), "second".to_string()],
+            vec![],
+            vec!["".to_string(), "third".to_string()],
+        ],
+        subtitle: Some("notes".to_string()),
+        attachments: btreemap! {
+            "b".to_string() => ByteBuf::from(vec![2]),
+            "a".to_string() => ByteBuf::from(vec![1, 1]),
+            "empty".to_string() => ByteBuf::new(),
+        },
+    }
+}
+
+/// Bincode encoding (also valid in BCS, up to the container depth) of the chain holding the
+/// values `0..length`. Computed by hand since serializing long chains in Rust would recurse.
+pub fn get_chain_encoding(length: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for i in 0..length {
+        bytes.extend_from_slice(&i.to_le_bytes());
+        bytes.push(u8::from(i + 1 < length));
+    }
+    bytes
+}
+
+// Struct with many fields at their default value, used to test the compact encoding.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Telemetry {
+    pub sequence: u64,
+    pub origin: Location,
+    pub label: String,
+    pub active: bool,
+    pub retries: Option<u16>,
+    pub samples: Vec<i32>,
+    pub attributes: BTreeMap<String, u8>,
+    pub payload: ByteBuf,
+    pub checksum: i128,
+    pub level: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Location {
+    pub x: u16,
+    pub y: u16,
+}
+
+pub fn get_telemetry_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Telemetry>()?;
+    tracer.registry()
+}
+
+/// Telemetry records together with their BCS and compact encodings.
+pub fn get_telemetry_samples() -> Vec<(Telemetry, Vec<u8>, Vec<u8>)> {
+    let values = vec![
+        Telemetry {
+            origin: Location { x: 1, y: 2 },
+            ..Telemetry::default()
+        },
+        Telemetry {
+            label: "temperature".to_string(),
+            retries: Some(0),
+            level: 3,
+            ..Telemetry::default()
+        },
+        Telemetry {
+            sequence: 1 << 40,
+            origin: Location { x: 3, y: 4 },
+            label: "pressure".to_string(),
+            active: true,
+            retries: Some(2),
+            samples: vec![-1, 7],
+            attributes: btreemap! { "unit".to_string() => 5 },
+            payload: ByteBuf::from(vec![1, 2, 3]),
+            checksum: -(1 << 100),
+            level: 9,
+        },
+    ];
+    values
+        .into_iter()
+        .map(|value| {
+            let bcs = bcs::to_bytes(&value).unwrap();
+            let compact = get_telemetry_compact_encoding(&value);
+            (value, bcs, compact)
+        })
+        .collect()
+}
+
+// Reference implementation of the compact encoding for `Telemetry`.
+fn get_telemetry_compact_encoding(value: &Telemetry) -> Vec<u8> {
+    let fields = vec![
+        (Some(value.sequence != 0), bcs::to_bytes(&value.sequence)),
+        (None, bcs::to_bytes(&value.origin)),
+        (Some(!value.label.is_empty()), bcs::to_bytes(&value.label)),
+        (Some(value.active), bcs::to_bytes(&value.active)),
+        (Some(value.retries.is_some()), bcs::to_bytes(&value.retries)),
+        (
+            Some(!value.samples.is_empty()),
+            bcs::to_bytes(&value.samples),
+        ),
+        (
+            Some(!value.attributes.is_empty()),
+            bcs::to_bytes(&value.attributes),
+        ),
+        (
+            Some(!value.payload.is_empty()),
+            bcs::to_bytes(&value.payload),
+        ),
+        (Some(value.checksum != 0), bcs::to_bytes(&value.checksum)),
+        (Some(value.level != 0), bcs::to_bytes(&value.level)),
+    ];
+    let mut bitmap = vec![0u8; 2];
+    let mut bytes = Vec::new();
+    let mut bit = 0;
+    for (present, field) in fields {
+        match present {
+            None => bytes.extend(field.unwrap()),
+            Some(present) => {
+                if present {
+                    bitmap[bit / 8] |= 1 << (bit % 8);
+                    bytes.extend(field.unwrap());
+                }
+                bit += 1;
+            }
+        }
+    }
+    bitmap.extend(bytes);
+    bitmap
+}
+
+// Struct whose fields are named after reserved keywords of a target language.
+pub fn get_keyword_registry(keywords: &[&str]) -> Registry {
+    let fields = keywords
+        .iter()
+        .map(|keyword| Named {
+            name: keyword.to_string(),
+            value: Format::U8,
+        })
+        .collect();
+    let mut registry = Registry::new();
+    registry.insert("Keywords".to_string(), ContainerFormat::Struct(fields));
+    registry
+}
+
+// Containers, fields, and variants renamed after non-ASCII names.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "Café")]
+pub struct Cafe {
+    #[serde(rename = "prénom")]
+    pub first_name: String,
+    #[serde(rename = "ご注文")]
+    pub order: Order,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "注文")]
+pub enum Order {
+    #[serde(rename = "Thé")]
+    Tea,
+    #[serde(rename = "コーヒー")]
+    Coffee {
+        #[serde(rename = "量")]
+        size: u8,
+    },
+}
+
+pub fn get_non_ascii_registry() -> Result<Registry> {
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    tracer.trace_type::<Cafe>(&samples)?;
+    tracer.trace_type::<Order>(&samples)?;
+    tracer.registry()
+}
+
+// Enum with more variants than fit in a byte: variant indices above 255 must round-trip.
+pub const LARGE_ENUM_SIZE: u32 = 300;
+
+pub fn get_large_enum_registry() -> Registry {
+    let variants = (0..LARGE_ENUM_SIZE)
+        .map(|index| {
+            let value = if index % 2 == 0 {
+                VariantFormat::Unit
+            } else {
+                VariantFormat::NewType(Box::new(Format::U32))
+            };
+            let name = format!("V{}", index);
+            (index, Named { name, value })
+        })
+        .collect();
+    let mut registry = Registry::new();
+    registry.insert("LargeEnum".to_string(), ContainerFormat::Enum(variants));
+    registry
+}
+
+/// Value `V<n>` of the enum described by `get_large_enum_registry`. Odd variants hold `n`.
+pub struct LargeEnumValue(pub u32);
+
+impl Serialize for LargeEnumValue {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Variant names are not part of the binary encodings.
+        if self.0 % 2 == 0 {
+            serializer.serialize_unit_variant("LargeEnum", self.0, "")
+        } else {
+            serializer.serialize_newtype_variant("LargeEnum", self.0, "", &self.0)
+        }
+    }
+}
+
+/// Indices of `LargeEnum` covering one- and two-byte ULEB128 variant tags.
+pub fn get_large_enum_sample_indices() -> Vec<u32> {
+    vec![0, 1, 127, 128, 255, 256, 257, LARGE_ENUM_SIZE - 1]
+}
+
 // More complex data format used to test re-serialization and basic fuzzing.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum SerdeData {
@@ -344,6 +778,7 @@ fn get_sample_value_with_long_sequence(length: usize) -> SerdeData {
 pub enum Runtime {
     Bcs,
     Bincode,
+    Postcard,
 }
 
 impl std::convert::From<Runtime> for Encoding {
@@ -351,6 +786,7 @@ impl std::convert::From<Runtime> for Encoding {
         match runtime {
             Runtime::Bcs => Encoding::Bcs,
             Runtime::Bincode => Encoding::Bincode,
+            Runtime::Postcard => Encoding::Postcard,
         }
     }
 }
@@ -364,6 +800,7 @@ impl Runtime {
         match self {
             Self::Bcs => "bcs = \"0.1.1\"",
             Self::Bincode => "bincode = \"1.3\"",
+            Self::Postcard => "postcard = { version = \"1.0\", features = [\"alloc\"] }",
         }
     }
 
@@ -374,6 +811,7 @@ impl Runtime {
         match self {
             Self::Bcs => bcs::to_bytes(value).unwrap(),
             Self::Bincode => bincode::serialize(value).unwrap(),
+            Self::Postcard => postcard::to_allocvec(value).unwrap(),
         }
     }
 
@@ -384,6 +822,10 @@ impl Runtime {
         match self {
             Self::Bcs => bcs::from_bytes(bytes).ok(),
             Self::Bincode => bincode::deserialize(bytes).ok(),
+            Self::Postcard => match postcard::take_from_bytes(bytes) {
+                Ok((value, [])) => Some(value),
+                _ => None,
+            },
         }
     }
 
@@ -436,6 +878,7 @@ impl Runtime {
         match self {
             Self::Bcs => "bcs::to_bytes",
             Self::Bincode => "bincode::serialize",
+            Self::Postcard => "postcard::to_allocvec",
         }
     }
 
@@ -443,6 +886,7 @@ impl Runtime {
         match self {
             Self::Bcs => "bcs::from_bytes",
             Self::Bincode => "bincode::deserialize",
+            Self::Postcard => "postcard::from_bytes",
         }
     }
 
@@ -451,7 +895,7 @@ impl Runtime {
     pub fn has_canonical_maps(self) -> bool {
         match self {
             Self::Bcs => true,
-            Self::Bincode => false,
+            Self::Bincode | Self::Postcard => false,
         }
     }
 
@@ -459,21 +903,21 @@ impl Runtime {
     pub fn has_floats(self) -> bool {
         match self {
             Self::Bcs => false,
-            Self::Bincode => true,
+            Self::Bincode | Self::Postcard => true,
         }
     }
 
     pub fn maximum_length(self) -> Option<usize> {
         match self {
             Self::Bcs => Some(bcs::MAX_SEQUENCE_LENGTH),
-            Self::Bincode => None,
+            Self::Bincode | Self::Postcard => None,
         }
     }
 
     pub fn maximum_container_depth(self) -> Option<usize> {
         match self {
             Self::Bcs => Some(bcs::MAX_CONTAINER_DEPTH),
-            Self::Bincode => None,
+            Self::Bincode | Self::Postcard => None,
         }
     }
 
@@ -533,6 +977,14 @@ impl Runtime {
             negative_samples.push(vec![0x09, 0x80, 0x00]);
             negative_samples.push(vec![0x09, 0xff, 0xff, 0xff, 0xff, 0x10]);
             negative_samples.push(vec![0x09, 0xff, 0xff, 0xff, 0xff, 0x08]);
+            // Overlong ULEB128 encodings of otherwise valid values: the length 1 of a
+            // `Vec<()>` and the variant indices 9 and 2 (`UnitVariant`).
+            negative_samples.push(vec![0x09, 0x81, 0x00]);
+            negative_samples.push(vec![0x09, 0x81, 0x80, 0x00]);
+            negative_samples.push(vec![0x89, 0x00, 0x00]);
+            negative_samples.push(vec![0x82, 0x80, 0x80, 0x80, 0x00]);
+            // Minimal encoding of a length exceeding `bcs::MAX_SEQUENCE_LENGTH`.
+            negative_samples.push(vec![0x09, 0x80, 0x80, 0x80, 0x80, 0x08]);
         }
         negative_samples
     }
@@ -590,7 +1042,7 @@ impl Runtime {
         let mut result = f0[..f0.len() - e.len()].to_vec();
         match self {
             Runtime::Bincode => result.append(&mut self.serialize(&(length as u64))),
-            Runtime::Bcs => {
+            Runtime::Bcs | Runtime::Postcard => {
                 // ULEB-128 encoding of the length.
                 let mut value = length;
                 while value >= 0x80 {
@@ -605,6 +1057,19 @@ impl Runtime {
     }
 }
 
+#[test]
+fn test_get_chain_encoding() {
+    let chain = Chain {
+        value: 0,
+        next: Some(Box::new(Chain {
+            value: 1,
+            next: None,
+        })),
+    };
+    assert_eq!(get_chain_encoding(2), bincode::serialize(&chain).unwrap());
+    assert_eq!(get_chain_encoding(2), bcs::to_bytes(&chain).unwrap());
+}
+
 #[test]
 fn test_get_sample_values() {
     assert_eq!(get_sample_values(false, true).len(), 18);
@@ -641,6 +1106,29 @@ Test:
     );
 }
 
+#[test]
+fn test_large_enum_variant_indices() {
+    let registry = get_large_enum_registry();
+    match registry.get("LargeEnum") {
+        Some(ContainerFormat::Enum(variants)) => {
+            assert_eq!(variants.len(), LARGE_ENUM_SIZE as usize)
+        }
+        _ => panic!("unexpected registry"),
+    }
+    assert_eq!(
+        Runtime::Bcs.serialize(&LargeEnumValue(256)),
+        vec![0x80, 0x02]
+    );
+    assert_eq!(
+        Runtime::Postcard.serialize(&LargeEnumValue(256)),
+        vec![0x80, 0x02]
+    );
+    assert_eq!(
+        Runtime::Bincode.serialize(&LargeEnumValue(257)),
+        vec![1, 1, 0, 0, 1, 1, 0, 0]
+    );
+}
+
 #[test]
 fn test_get_registry() {
     let registry = get_registry().unwrap();
@@ -864,6 +1352,11 @@ fn test_bincode_samples_with_container_depth() {
     test_get_alternate_sample_with_container_depth(Runtime::Bincode);
 }
 
+#[test]
+fn test_postcard_get_sample_with_long_sequence() {
+    test_get_sample_with_long_sequence(Runtime::Postcard);
+}
+
 #[test]
 fn test_bcs_samples_with_container_depth() {
     test_get_sample_with_container_depth(Runtime::Bcs);
@@ -934,6 +1427,11 @@ fn test_bcs_get_positive_samples() {
     assert_eq!(test_get_positive_samples(Runtime::Bcs), 82);
 }
 
+#[test]
+fn test_postcard_get_positive_samples() {
+    assert_eq!(test_get_positive_samples(Runtime::Postcard), 35);
+}
+
 // Make sure all the "positive" samples successfully deserialize with the reference Rust
 // implementation.
 #[cfg(test)]
@@ -955,7 +1453,12 @@ fn test_bincode_get_negative_samples() {
 // This test requires --release because of deserialization of long (unit) vectors.
 #[cfg(not(debug_assertions))]
 fn test_bcs_get_negative_samples() {
-    assert_eq!(test_get_negative_samples(Runtime::Bcs), 59);
+    assert_eq!(test_get_negative_samples(Runtime::Bcs), 64);
+}
+
+#[test]
+fn test_postcard_get_negative_samples() {
+    assert_eq!(test_get_negative_samples(Runtime::Postcard), 81);
 }
 
 // Make sure all the "negative" samples fail to deserialize with the reference Rust
@@ -979,3 +1482,38 @@ fn test_bcs_serialize_with_noise_and_deserialize() {
     // 1 for added incorrect 5-byte UTF8-like codepoint
     assert_eq!(samples.len(), value.len() + 3);
 }
+
+#[test]
+fn test_get_registry_rendering() {
+    let registry = get_registry().unwrap();
+    let rendering = registry
+        .iter()
+        .map(|(name, format)| format!("{} = {}", name, format))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rendering,
+        vec![
+            "CStyleEnum = A | B | C | D | E",
+            "List = Empty | Node(SerdeData, List)",
+            "NewTypeStruct = (U64)",
+            "OtherTypes = { f_string: Str, f_bytes: Bytes, f_option: Option<Struct>, f_unit: Unit, f_seq: [Struct], f_opt_seq: Option<[I32]>, f_tuple: (U8, U16), f_stringmap: Map<Str, U32>, f_intset: Map<U64, Unit>, f_nested_seq: [[Struct]] }",
+            "PrimitiveTypes = { f_bool: Bool, f_u8: U8, f_u16: U16, f_u32: U32, f_u64: U64, f_u128: U128, f_i8: I8, f_i16: I16, f_i32: I32, f_i64: I64, f_i128: I128, f_f32: Option<F32>, f_f64: Option<F64>, f_char: Option<Char> }",
+            "SerdeData = PrimitiveTypes(PrimitiveTypes) | OtherTypes(OtherTypes) | UnitVariant | NewTypeVariant(Str) | TupleVariant(U32, U64) | StructVariant { f0: UnitStruct, f1: NewTypeStruct, f2: TupleStruct, f3: Struct } | ListWithMutualRecursion(List) | TreeWithMutualRecursion(Tree) | TupleArray([U32; 3]) | UnitVector([Unit]) | SimpleList(SimpleList) | CStyleEnum(CStyleEnum) | ComplexMap(Map<([U32; 2], [U8; 4]), Unit>) | EmptyTupleVariant() | EmptyStructVariant {}",
+            "SimpleList = (Option<SimpleList>)",
+            "Struct = { x: U32, y: U64 }",
+            "Tree = { value: SerdeData, children: [Tree] }",
+            "TupleStruct = (U32, U64)",
+            "UnitStruct = Unit",
+        ]
+    );
+
+    // Every format of the registry can be parsed back.
+    for format in registry.values() {
+        format
+            .visit(&mut |format| {
+                assert_eq!(&Format::parse(&format.to_string()).unwrap(), format);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
@@ -4,6 +4,7 @@
 use crate::test_utils;
 use regex::Regex;
 use serde_generate::{typescript, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_reflection::Registry;
 use std::{
     collections::BTreeMap,
     fs::File,
@@ -12,11 +13,31 @@ use std::{
 };
 use tempfile::tempdir;
 
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+    "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements",
+    "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private",
+    "protected", "public", "return", "static", "super", "switch", "this", "throw", "true", "try",
+    "typeof", "var", "void", "while", "with", "yield",
+];
+
 fn test_typescript_code_compiles_with_config(
     dir_path: &Path,
     config: &CodeGeneratorConfig,
 ) -> std::path::PathBuf {
-    let registry = test_utils::get_registry().unwrap();
+    test_typescript_code_compiles_with_config_and_registry(
+        dir_path,
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+fn test_typescript_code_compiles_with_config_and_registry(
+    dir_path: &Path,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> std::path::PathBuf {
     make_output_file(dir_path);
 
     let installer = typescript::Installer::new(dir_path.to_path_buf());
@@ -29,8 +50,8 @@ fn test_typescript_code_compiles_with_config(
     let source_path = dir_path.join("testing").join("test.ts");
     let mut source = File::create(&source_path).unwrap();
 
-    let generator = typescript::CodeGenerator::new(config);
-    generator.output(&mut source, &registry).unwrap();
+    let generator = typescript::CodeGenerator::try_new(config).unwrap();
+    generator.output(&mut source, registry).unwrap();
 
     assert_deno_info(&source_path);
     dir_path.join("testing")
@@ -78,11 +99,105 @@ fn test_is_error_output() {
 #[test]
 fn test_typescript_code_compiles_with_bcs() {
     let dir = tempdir().unwrap();
-    let config =
-        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
     test_typescript_code_compiles_with_config(dir.path(), &config);
 }
 
+#[test]
+fn test_typescript_code_compiles_with_size_estimators() {
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_size_estimators(true)
+        .with_allow_nonstandard_floats(true);
+    test_typescript_code_compiles_with_config(dir.path(), &config);
+}
+
+#[test]
+fn test_typescript_code_compiles_with_nested_tuples() {
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = test_utils::get_tuple_shapes_registry().unwrap();
+    let path =
+        test_typescript_code_compiles_with_config_and_registry(dir.path(), &config, &registry);
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    // Tuples are read by helpers rather than inlined in enclosing expressions.
+    assert!(content.contains("list.push(Helpers.deserializeTuple2U32Str(deserializer));"));
+    assert!(content.contains("return Helpers.deserializeTuple2U8U8(deserializer);"));
+    assert!(content.contains("const value = Helpers.deserializeTuple2U32Str(deserializer);"));
+}
+
+#[test]
+fn test_typescript_code_compiles_with_long_chains() {
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = test_utils::get_chain_registry().unwrap();
+    let path =
+        test_typescript_code_compiles_with_config_and_registry(dir.path(), &config, &registry);
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    // Chains of values are (de)serialized with loops rather than recursive calls.
+    assert!(content.contains("    node = next;\n"));
+    assert!(content.contains("  } while (deserializer.deserializeOptionTag());\n"));
+    assert!(!content.contains("Chain.deserialize(deserializer)"));
+}
+
+#[test]
+fn test_typescript_code_with_one_way_serialization() {
+    let registry = test_utils::get_registry().unwrap();
+    for (serialization, deserialization) in [(false, true), (true, false)] {
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_serialization(serialization)
+            .with_deserialization(deserialization);
+        let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+        let mut content = Vec::new();
+        generator.output(&mut content, &registry).unwrap();
+        let content = String::from_utf8(content).unwrap();
+        assert!(content.contains("export class Helpers {"));
+        assert_eq!(
+            content.contains("public serialize(serializer: Serializer): void {"),
+            serialization
+        );
+        assert_eq!(content.contains("static serializeVector"), serialization);
+        assert_eq!(
+            content.contains("static deserialize(deserializer: Deserializer): SerdeData {"),
+            deserialization
+        );
+        assert_eq!(
+            content.contains("static deserializeVector"),
+            deserialization
+        );
+    }
+}
+
+#[test]
+fn test_typescript_code_compiles_with_runtime_schema() {
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_runtime_schema(true);
+    let path = test_typescript_code_compiles_with_config(dir.path(), &config);
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content.contains("import { Schema } from '../serde/mod.ts';"));
+    assert!(content.contains(
+        r#"
+export class Struct {
+static readonly SCHEMA: Schema = {"kind": "struct", "fields": [["x", {"kind": "u32"}], ["y", {"kind": "u64"}]]};
+"#
+    ));
+    assert!(content.contains(
+        r#"
+export abstract class List {
+static readonly SCHEMA: Schema = {"kind": "enum", "variants": [[0, "Empty", {"kind": "unit"}], [1, "Node", {"kind": "tuple", "formats": [{"kind": "ref", "name": "SerdeData"}, {"kind": "ref", "name": "List"}]}]]};
+"#
+    ));
+    assert!(content.contains(
+        r#"static readonly SCHEMA: Schema = {"kind": "struct", "fields": [["value", {"kind": "ref", "name": "SerdeData"}], ["children", {"kind": "seq", "format": {"kind": "ref", "name": "Tree"}}]]};"#
+    ));
+    assert!(content.contains(
+        r#"["f_stringmap", {"kind": "map", "key": {"kind": "str"}, "value": {"kind": "u32"}}]"#
+    ));
+}
+
 #[test]
 fn test_typescript_code_compiles_with_comments() {
     let dir = tempdir().unwrap();
@@ -104,6 +219,40 @@ fn test_typescript_code_compiles_with_comments() {
     ));
 }
 
+#[test]
+fn test_typescript_code_compiles_with_variant_field_comments() {
+    let dir = tempdir().unwrap();
+    let comments = vec![(
+        vec![
+            "testing".to_string(),
+            "SerdeData".to_string(),
+            "StructVariant".to_string(),
+            "f0".to_string(),
+        ],
+        "Some field comments".to_string(),
+    )]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+
+    let path = test_typescript_code_compiles_with_config(dir.path(), &config);
+    // Comment was generated before the parameter property.
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content.contains(
+        r#"
+constructor (
+  /**
+   * Some field comments
+   */
+  public f0: UnitStruct,
+  public f1: NewTypeStruct,
+  public f2: TupleStruct,
+  public f3: Struct
+) {
+"#
+    ));
+}
+
 #[test]
 fn test_typescript_code_compiles_with_external_definitions() {
     let dir = tempdir().unwrap();
@@ -123,3 +272,76 @@ fn test_typescript_code_compiles_with_external_definitions() {
 
     test_typescript_code_compiles_with_config(dir.path(), &config);
 }
+
+#[test]
+fn test_typescript_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let path =
+        test_typescript_code_compiles_with_config_and_registry(dir.path(), &config, &registry);
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content.contains("public delete_: uint8"));
+}
+
+#[test]
+fn test_typescript_code_compiles_with_non_ascii_names() {
+    let registry = test_utils::get_non_ascii_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let path =
+        test_typescript_code_compiles_with_config_and_registry(dir.path(), &config, &registry);
+    let content = std::fs::read_to_string(path.join("test.ts")).unwrap();
+    assert!(content
+        .contains("export class Café {\nconstructor (public prénom: str, public ご注文: 注文)"));
+    assert!(content.contains("export class 注文Variantコーヒー extends 注文 {"));
+}
+
+#[test]
+fn test_typescript_code_compiles_with_wasm_bridge() {
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    let registry = test_utils::get_registry().unwrap();
+    make_output_file(dir_path);
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    // Stand-in for the bindings generated by wasm-pack.
+    std::fs::create_dir_all(dir_path.join("wasm")).unwrap();
+    let mut bindings = String::new();
+    for name in registry.keys() {
+        bindings.push_str(&format!(
+            "export function __serde_encode_{0}(value: any): Uint8Array {{ throw new Error(); }}\n\
+             export function __serde_decode_{0}(input: Uint8Array): any {{ throw new Error(); }}\n",
+            name
+        ));
+    }
+    std::fs::write(dir_path.join("wasm/mod.ts"), bindings).unwrap();
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let generator = typescript::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_wasm_bridge("../wasm/mod.ts".to_string());
+    let source_path = dir_path.join("testing/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let content = std::fs::read_to_string(&source_path).unwrap();
+    assert!(content.contains("import * as wasmBridge from '../wasm/mod.ts';"));
+    assert!(content.contains("return wasmBridge.__serde_encode_SerdeData(this.toWasmValue());"));
+    assert!(content.contains("static decodeWasm(input: Uint8Array): SerdeData {"));
+    assert!(content.contains(
+        "case \"UnitVariant\": return SerdeDataVariantUnitVariant.loadWasmValue(content);"
+    ));
+
+    assert_deno_info(&source_path);
+    let status = Command::new("deno")
+        .arg("check")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
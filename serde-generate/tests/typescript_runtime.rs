@@ -2,9 +2,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::test_utils;
-use crate::test_utils::{Choice, Runtime, Test};
+use crate::test_utils::{Choice, LargeEnumValue, Runtime, Test};
 use heck::CamelCase;
-use serde_generate::{typescript, CodeGeneratorConfig, SourceInstaller};
+use serde_generate::{rust, typescript, CodeGeneratorConfig, SourceInstaller};
 use std::{fs::File, io::Write, process::Command};
 use tempfile::tempdir;
 
@@ -24,7 +24,7 @@ fn test_typescript_runtime_bcs_serialization() {
 
     let runtime = Runtime::Bcs;
     let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
-    let generator = typescript::CodeGenerator::new(&config);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
     generator.output(&mut source, &registry).unwrap();
 
     let reference = runtime.serialize(&Test {
@@ -75,3 +75,681 @@ Deno.test("{1} serialization matches deserialization", () => {{
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn test_typescript_runtime_bcs_enum_keyed_maps() {
+    let registry = test_utils::get_keyed_map_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bcs;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = runtime.serialize(&test_utils::get_keyed_map_sample());
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("{1} enum-keyed maps support lookups", () => {{
+  const expectedBytes = new Uint8Array([{0}]);
+  const deserializer = new {2}Deserializer(expectedBytes);
+  const value: KeyedMap = KeyedMap.deserialize(deserializer);
+
+  assertEquals(value.entries.size, 3);
+  assertEquals(value.entries.get(new KeyVariantId(2)), new Value("two"));
+  assertEquals(value.entries.get(new KeyVariantName("a")), new Value("a"));
+  assertEquals(value.entries.get(new KeyVariantPair(1, 3)), new Value("pair"));
+  assertEquals(value.entries.has(new KeyVariantId(3)), false);
+
+  const serializer = new {2}Serializer();
+  value.serialize(serializer);
+  assertEquals(serializer.getBytes(), expectedBytes, "{1} bytes should match");
+}});
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_bcs_nested_tuples() {
+    let registry = test_utils::get_tuple_shapes_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bcs;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = runtime.serialize(&test_utils::get_tuple_shapes_sample());
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("{1} nested tuples round-trip", () => {{
+  const expectedBytes = new Uint8Array([{0}]);
+  const deserializer = new {2}Deserializer(expectedBytes);
+  const value: TupleShapes = TupleShapes.deserialize(deserializer);
+
+  assertEquals(value.pairs, [[1, "one"], [2, "two"]]);
+  assertEquals(value.maybe, [3, 4]);
+  assertEquals(value.table.get("a"), [5, "five"]);
+  assertEquals(value.table.get("b"), [6, "six"]);
+
+  const serializer = new {2}Serializer();
+  value.serialize(serializer);
+  assertEquals(serializer.getBytes(), expectedBytes, "{1} bytes should match");
+}});
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_bcs_wide_integers() {
+    let registry = test_utils::get_wide_integers_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bcs;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let sample = test_utils::get_wide_integers_sample();
+    let reference = runtime.serialize(&sample);
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("{1} integers above 2^53 round-trip exactly", () => {{
+  const expectedBytes = new Uint8Array([{0}]);
+  const deserializer = new {2}Deserializer(expectedBytes);
+  const value: WideIntegers = WideIntegers.deserialize(deserializer);
+
+  assertEquals(value.a, BigInt("{3}"));
+  assertEquals(value.b, BigInt("{4}"));
+  assertEquals(value.c, BigInt("{5}"));
+  assertEquals(value.d, BigInt("{6}"));
+
+  const serializer = new {2}Serializer();
+  value.serialize(serializer);
+  assertEquals(serializer.getBytes(), expectedBytes, "{1} bytes should match");
+}});
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        runtime.name(),
+        runtime.name().to_camel_case(),
+        sample.a,
+        sample.b,
+        sample.c,
+        sample.d,
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_bincode_float_edges() {
+    let registry = test_utils::get_float_edges_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bincode_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bincode;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let reference = runtime.serialize(&test_utils::get_float_edges_sample());
+
+    // `F32` values are JavaScript numbers (i.e. doubles), which do not preserve the payloads of
+    // single-precision NaNs.
+    writeln!(
+        source,
+        r#"
+import {{ assert, assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+
+function f32Bits(x: number): number {{
+  const view = new DataView(new ArrayBuffer(4));
+  view.setFloat32(0, x, true);
+  return view.getUint32(0, true);
+}}
+
+function f64Bits(x: number): bigint {{
+  const view = new DataView(new ArrayBuffer(8));
+  view.setFloat64(0, x, true);
+  return view.getBigUint64(0, true);
+}}
+
+function check(value: FloatEdges) {{
+  const singles = [{1}];
+  const doubles = [{2}];
+  singles.forEach((bits, i) => {{
+    if (Number.isNaN(value.singles[i])) {{
+      assert((bits & 0x7fffffff) > 0x7f800000, "unexpected NaN");
+    }} else {{
+      assertEquals(f32Bits(value.singles[i]), bits);
+    }}
+  }});
+  doubles.forEach((bits, i) => assertEquals(f64Bits(value.doubles[i]), bits));
+}}
+
+Deno.test("bincode float edge values round-trip", () => {{
+  const input = new Uint8Array([{0}]);
+  const value = FloatEdges.deserialize(new BincodeDeserializer(input));
+  check(value);
+
+  const serializer = new BincodeSerializer();
+  value.serialize(serializer);
+  check(FloatEdges.deserialize(new BincodeDeserializer(serializer.getBytes())));
+}});
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F32_EDGE_BITS
+            .iter()
+            .map(|x| format!("{:#x}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+        test_utils::F64_EDGE_BITS
+            .iter()
+            .map(|x| format!("{:#x}n", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_bcs_large_enum() {
+    let registry = test_utils::get_large_enum_registry();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let runtime = Runtime::Bcs;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let samples = test_utils::get_large_enum_sample_indices()
+        .into_iter()
+        .map(|index| {
+            let reference = runtime.serialize(&LargeEnumValue(index));
+            let value = if index % 2 == 0 {
+                format!("new LargeEnumVariantV{}()", index)
+            } else {
+                format!("new LargeEnumVariantV{0}({0})", index)
+            };
+            format!("[new Uint8Array({:?}), {}]", reference, value)
+        })
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("{1} variant indices above 255 round-trip", () => {{
+  const samples: [Uint8Array, LargeEnum][] = [
+    {0},
+  ];
+  for (const [expectedBytes, expectedValue] of samples) {{
+    const deserializer = new {2}Deserializer(expectedBytes);
+    const value = LargeEnum.deserialize(deserializer);
+    assertEquals(value, expectedValue);
+
+    const serializer = new {2}Serializer();
+    value.serialize(serializer);
+    assertEquals(serializer.getBytes(), expectedBytes, "{1} bytes should match");
+  }}
+}});
+"#,
+        samples,
+        runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_on_long_chains() {
+    let registry = test_utils::get_chain_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config =
+        CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![Runtime::Bcs.into()]);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let length = 100_000;
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("long chains round-trip without exceeding the call stack", () => {{
+  const input = new Uint8Array({:?});
+
+  const value = Chain.deserialize(new BcsDeserializer(input));
+  const serializer = new BcsSerializer();
+  value.serialize(serializer);
+  assertEquals(serializer.getBytes(), input);
+  let node: Chain | null = value;
+  let count = 0;
+  while (node !== null) {{
+    assertEquals(node.value, count);
+    node = node.next;
+    count += 1;
+  }}
+  assertEquals(count, {});
+}});
+"#,
+        test_utils::get_chain_encoding(length),
+        length,
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_bcs_size_estimators() {
+    test_typescript_size_estimators(Runtime::Bcs);
+}
+
+#[test]
+fn test_typescript_bincode_size_estimators() {
+    test_typescript_size_estimators(Runtime::Bincode);
+}
+
+fn test_typescript_size_estimators(runtime: Runtime) {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+    installer.install_bincode_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_size_estimators(true)
+        .with_allow_nonstandard_floats(true);
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let positive_encodings = runtime
+        .get_positive_samples_quick()
+        .iter()
+        .map(|bytes| format!("new Uint8Array({:?})", bytes))
+        .collect::<Vec<_>>()
+        .join(",\n    ");
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+import {{ BincodeDeserializer }} from "../bincode/mod.ts";
+Deno.test("{1} size estimators match serialized lengths", () => {{
+  const samples: Uint8Array[] = [
+    {0},
+  ];
+  for (const input of samples) {{
+    const deserializer = new {2}Deserializer(input);
+    const value = SerdeData.deserialize(deserializer);
+    assertEquals(value.estimated{2}Size(), input.length);
+  }}
+}});
+"#,
+        positive_encodings,
+        runtime.name(),
+        runtime.name().to_camel_case(),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_compact_encoding() {
+    let registry = test_utils::get_telemetry_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string())
+        .with_elision_containers(["Telemetry".to_string()].into_iter().collect());
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    let samples = test_utils::get_telemetry_samples()
+        .into_iter()
+        .map(|(_, bcs, compact)| {
+            format!("[new Uint8Array({:?}), new Uint8Array({:?})]", bcs, compact)
+        })
+        .collect::<Vec<_>>();
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals, assertThrows }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("Compact encoding round-trips", () => {{
+  const samples: [Uint8Array, Uint8Array][] = [{}];
+  for (const [encoding, compact] of samples) {{
+    const value = Telemetry.deserialize(new BcsDeserializer(encoding));
+    assertEquals(Telemetry.compactDeserialize(compact), value);
+    assertEquals(value.compactSerialize(), compact);
+  }}
+
+  const value = Telemetry.compactDeserialize(new Uint8Array([0, 0, 1, 0, 2, 0]));
+  assertEquals(value.sequence, BigInt(0));
+  assertEquals(value.origin, new Location(1, 2));
+  assertEquals(value.label, "");
+  assertEquals(value.retries, null);
+  assertEquals(value.samples, []);
+  assertEquals(value.attributes.size, 0);
+  assertEquals(value.payload, new Uint8Array());
+
+  assertThrows(() => Telemetry.compactDeserialize(new Uint8Array([0])));
+  assertThrows(() => Telemetry.compactDeserialize(new Uint8Array([0, 0, 1, 0, 2, 0, 0])));
+}});
+"#,
+        samples.join(", "),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_enum_narrowing_helpers() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+
+    let config = CodeGeneratorConfig::new("main".to_string());
+    let generator = typescript::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("Enum variants can be narrowed", () => {{
+  const values: Choice[] = [new ChoiceVariantA(), new ChoiceVariantB(BigInt(3)), new ChoiceVariantC(7)];
+
+  const value: Choice = values[1];
+  assertEquals(isChoiceVariantA(value), false);
+  if (!isChoiceVariantB(value)) {{
+    throw new Error("expected variant B");
+  }}
+  const inner: bigint = value.value;
+  assertEquals(inner, BigInt(3));
+
+  const described = values.map((v) => matchChoice(v, {{
+    A: (_) => "A",
+    B: (b) => "B" + b.value,
+    C: (c) => "C" + c.x,
+  }}));
+  assertEquals(described, ["A", "B3", "C7"]);
+}});
+"#
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_typescript_runtime_bcs_wasm_bridge() {
+    if Command::new("wasm-pack").arg("--version").output().is_err() {
+        eprintln!("Skipping the WebAssembly round-trip: wasm-pack is not installed");
+        return;
+    }
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let dir_path = dir.path();
+    let runtime = Runtime::Bcs;
+    let config = CodeGeneratorConfig::new("main".to_string()).with_encodings(vec![runtime.into()]);
+
+    // Rust definitions exported by a WebAssembly module.
+    let crate_path = dir_path.join("crate");
+    std::fs::create_dir_all(crate_path.join("src")).unwrap();
+    std::fs::write(
+        crate_path.join("Cargo.toml"),
+        r#"[package]
+name = "testing"
+version = "0.1.0"
+edition = "2018"
+
+[lib]
+crate-type = ["cdylib"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+serde_bytes = "0.11"
+bcs = "0.1.1"
+wasm-bindgen = "0.2"
+serde-wasm-bindgen = "0.6"
+
+[workspace]
+"#,
+    )
+    .unwrap();
+    let mut source = File::create(crate_path.join("src/lib.rs")).unwrap();
+    rust::CodeGenerator::new(&config)
+        .with_wasm_exports(true)
+        .output(&mut source, &registry)
+        .unwrap();
+    let status = Command::new("wasm-pack")
+        .current_dir(&crate_path)
+        .arg("build")
+        .arg("--target")
+        .arg("deno")
+        .arg("--out-dir")
+        .arg(dir_path.join("wasm"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    std::fs::create_dir_all(dir_path.join("tests")).unwrap();
+    let installer = typescript::Installer::new(dir_path.to_path_buf());
+    installer.install_serde_runtime().unwrap();
+    installer.install_bcs_runtime().unwrap();
+    let source_path = dir_path.join("tests/test.ts");
+    let mut source = File::create(&source_path).unwrap();
+    typescript::CodeGenerator::try_new(&config)
+        .unwrap()
+        .with_wasm_bridge("../wasm/testing.js".to_string())
+        .output(&mut source, &registry)
+        .unwrap();
+
+    let reference = runtime.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+    writeln!(
+        source,
+        r#"
+import {{ assertEquals }} from "https://deno.land/std@0.110.0/testing/asserts.ts";
+Deno.test("wasm bridge matches the TypeScript implementation", () => {{
+  const expectedBytes = new Uint8Array([{}]);
+  const value = Test.decodeWasm(expectedBytes);
+  assertEquals(value, Test.deserialize(new BcsDeserializer(expectedBytes)));
+  assertEquals(value.encodeWasm(), expectedBytes);
+}});
+"#,
+        reference
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+    .unwrap();
+
+    let status = Command::new("deno")
+        .current_dir(dir_path)
+        .arg("test")
+        .arg("--allow-read")
+        .arg(&source_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
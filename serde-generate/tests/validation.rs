@@ -0,0 +1,977 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde_generate::{
+    validation::{validate_registry_for, CollisionError, Language},
+    CodeGeneratorConfig, ConfigError, Encoding, KeywordEscaping,
+};
+use serde_reflection::Registry;
+
+fn get_registry(yaml: &str) -> Registry {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn validate(language: Language, yaml: &str) -> Vec<CollisionError> {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    validate_registry_for(&config, language, &get_registry(yaml)).unwrap_err()
+}
+
+fn validate_without_escaping(language: Language, yaml: &str) -> Vec<CollisionError> {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Error);
+    validate_registry_for(&config, language, &get_registry(yaml)).unwrap_err()
+}
+
+fn keyword(scope: &str, name: &str) -> CollisionError {
+    CollisionError::Keyword {
+        scope: scope.to_string(),
+        name: name.to_string(),
+        identifier: name.to_string(),
+    }
+}
+
+fn names(scope: &str, first: &str, second: &str, identifier: &str) -> CollisionError {
+    CollisionError::Names {
+        scope: scope.to_string(),
+        first: first.to_string(),
+        second: second.to_string(),
+        identifier: identifier.to_string(),
+    }
+}
+
+const MIXED_CASE_FIELDS: &str = r#"---
+Foo:
+  STRUCT:
+    - userId: U8
+    - user_id: U8
+"#;
+
+const MIXED_CASE_TYPES: &str = r#"---
+HTTPRequest: UNITSTRUCT
+HttpRequest: UNITSTRUCT
+"#;
+
+const MIXED_CASE_VARIANTS: &str = r#"---
+Foo:
+  ENUM:
+    0:
+      FooBar:
+        NEWTYPE: U8
+    1:
+      foo_bar:
+        NEWTYPE: U8
+    2:
+      fooBar: UNIT
+"#;
+
+const VARIANT_AND_TYPE: &str = r#"---
+Foo:
+  ENUM:
+    0:
+      Bar: UNIT
+Foo__Bar: UNITSTRUCT
+FooBar: UNITSTRUCT
+FooVariantBar: UNITSTRUCT
+"#;
+
+fn keyword_field(name: &str) -> String {
+    format!(
+        r#"---
+Foo:
+  STRUCT:
+    - {}: U8
+"#,
+        name
+    )
+}
+
+#[test]
+fn test_valid_registry() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = get_registry(MIXED_CASE_TYPES);
+    for language in [
+        Language::Cpp,
+        Language::CSharp,
+        Language::Go,
+        Language::Java,
+        Language::Python3,
+        Language::Rust,
+        Language::Solidity,
+        Language::Swift,
+        Language::TypeScript,
+    ] {
+        assert_eq!(validate_registry_for(&config, language, &registry), Ok(()));
+    }
+}
+
+#[test]
+fn test_cpp_keywords() {
+    assert_eq!(
+        validate_without_escaping(Language::Cpp, &keyword_field("class")),
+        vec![keyword("fields of Foo", "class")]
+    );
+}
+
+#[test]
+fn test_default_keyword_escaping() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = get_registry(&keyword_field("class"));
+    // Keywords are escaped in Java, OCaml, Python, and TypeScript.
+    for language in [
+        Language::Java,
+        Language::OCaml,
+        Language::Python3,
+        Language::TypeScript,
+    ] {
+        assert_eq!(validate_registry_for(&config, language, &registry), Ok(()));
+    }
+    assert_eq!(
+        validate(Language::Cpp, &keyword_field("class")),
+        vec![keyword("fields of Foo", "class")]
+    );
+
+    let config = config.with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    assert_eq!(
+        validate_registry_for(&config, Language::Cpp, &registry),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_python_dunder_names() {
+    assert_eq!(
+        validate_without_escaping(Language::Python3, &keyword_field("__init__")),
+        vec![keyword("fields of Foo", "__init__")]
+    );
+    // Class-private names are not reserved.
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Error);
+    assert_eq!(
+        validate_registry_for(
+            &config,
+            Language::Python3,
+            &get_registry(&keyword_field("__x"))
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_csharp_keywords() {
+    assert_eq!(
+        validate_without_escaping(Language::CSharp, &keyword_field("namespace")),
+        vec![keyword("fields of Foo", "namespace")]
+    );
+}
+
+#[test]
+fn test_dart_collisions() {
+    assert_eq!(
+        validate(Language::Dart, MIXED_CASE_FIELDS),
+        vec![names("fields of Foo", "userId", "user_id", "userId")]
+    );
+    assert_eq!(
+        validate(Language::Dart, MIXED_CASE_TYPES),
+        vec![names(
+            "file names",
+            "HTTPRequest",
+            "HttpRequest",
+            "http_request"
+        )]
+    );
+    assert_eq!(
+        validate(Language::Dart, VARIANT_AND_TYPE),
+        vec![
+            names("type names", "Foo::Bar", "FooBar", "FooBar"),
+            names("file names", "FooBar", "Foo__Bar", "foo_bar"),
+        ]
+    );
+    assert_eq!(
+        validate_without_escaping(Language::Dart, &keyword_field("switch")),
+        vec![keyword("fields of Foo", "switch")]
+    );
+}
+
+#[test]
+fn test_dart_collisions_with_c_style_enums() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    let registry = get_registry(
+        r#"---
+Foo:
+  ENUM:
+    0:
+      A_B: UNIT
+    1:
+      aB: UNIT
+"#,
+    );
+    assert_eq!(
+        validate_registry_for(&config, Language::Dart, &registry),
+        Err(vec![names("variants of Foo", "A_B", "aB", "aB")])
+    );
+}
+
+#[test]
+fn test_golang_collisions() {
+    assert_eq!(
+        validate(Language::Go, MIXED_CASE_FIELDS),
+        vec![names("fields of Foo", "userId", "user_id", "UserId")]
+    );
+    assert_eq!(
+        validate(Language::Go, VARIANT_AND_TYPE),
+        vec![names("type names", "Foo::Bar", "Foo__Bar", "Foo__Bar")]
+    );
+}
+
+#[test]
+fn test_java_keywords() {
+    assert_eq!(
+        validate_without_escaping(Language::Java, &keyword_field("package")),
+        vec![keyword("fields of Foo", "package")]
+    );
+}
+
+#[test]
+fn test_ocaml_collisions() {
+    assert_eq!(
+        validate(Language::OCaml, MIXED_CASE_TYPES),
+        vec![names(
+            "type names",
+            "HTTPRequest",
+            "HttpRequest",
+            "http_request"
+        )]
+    );
+    assert_eq!(
+        validate(Language::OCaml, MIXED_CASE_FIELDS),
+        vec![names("fields of Foo", "userId", "user_id", "user_id")]
+    );
+    // Keywords are escaped.
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = get_registry(&keyword_field("type"));
+    assert_eq!(
+        validate_registry_for(&config, Language::OCaml, &registry),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_python_collisions() {
+    assert_eq!(
+        validate(Language::Python3, VARIANT_AND_TYPE),
+        vec![names("type names", "Foo::Bar", "Foo__Bar", "Foo__Bar")]
+    );
+    assert_eq!(
+        validate_without_escaping(Language::Python3, &keyword_field("from")),
+        vec![keyword("fields of Foo", "from")]
+    );
+}
+
+#[test]
+fn test_rust_keywords() {
+    assert_eq!(
+        validate_without_escaping(Language::Rust, &keyword_field("type")),
+        vec![keyword("fields of Foo", "type")]
+    );
+}
+
+#[test]
+fn test_solidity_collisions() {
+    assert_eq!(
+        validate(Language::Solidity, MIXED_CASE_VARIANTS),
+        vec![
+            names("variants of Foo", "FooBar", "foo_bar", "foo_bar"),
+            names("variants of Foo", "FooBar", "fooBar", "foo_bar"),
+        ]
+    );
+}
+
+#[test]
+fn test_swift_collisions() {
+    assert_eq!(
+        validate(Language::Swift, MIXED_CASE_VARIANTS),
+        vec![names("variants of Foo", "FooBar", "fooBar", "fooBar")]
+    );
+    assert_eq!(
+        validate_without_escaping(Language::Swift, &keyword_field("protocol")),
+        vec![keyword("fields of Foo", "protocol")]
+    );
+}
+
+#[test]
+fn test_typescript_collisions() {
+    assert_eq!(
+        validate(Language::TypeScript, VARIANT_AND_TYPE),
+        vec![names(
+            "type names",
+            "Foo::Bar",
+            "FooVariantBar",
+            "FooVariantBar"
+        )]
+    );
+    assert_eq!(
+        validate_without_escaping(Language::TypeScript, &keyword_field("delete")),
+        vec![keyword("fields of Foo", "delete")]
+    );
+}
+
+#[test]
+fn test_keywords_are_escaped_with_a_suffix() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    for (language, name) in [
+        (Language::Cpp, "class"),
+        (Language::CSharp, "namespace"),
+        (Language::Dart, "switch"),
+        (Language::Java, "package"),
+        (Language::Python3, "from"),
+        (Language::Rust, "type"),
+        (Language::Solidity, "address"),
+        (Language::Swift, "protocol"),
+        (Language::TypeScript, "delete"),
+    ] {
+        let registry = get_registry(&keyword_field(name));
+        assert_eq!(validate_registry_for(&config, language, &registry), Ok(()));
+    }
+}
+
+#[test]
+fn test_escaped_keywords_collisions() {
+    let registry = get_registry(
+        r#"---
+Foo:
+  STRUCT:
+    - type: U8
+    - type_: U8
+"#,
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Suffix("_".to_string()));
+    assert_eq!(
+        validate_registry_for(&config, Language::Rust, &registry),
+        Err(vec![names("fields of Foo", "type", "type_", "type_")])
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Prefix("r#".to_string()));
+    assert_eq!(
+        validate_registry_for(&config, Language::Rust, &registry),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_keyword_type_names_are_not_escaped() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = get_registry("---\nbytes: UNITSTRUCT\n");
+    assert_eq!(
+        validate_registry_for(&config, Language::Solidity, &registry),
+        Err(vec![keyword("type names", "bytes")])
+    );
+    // OCaml escapes type names.
+    assert_eq!(
+        validate_registry_for(&config, Language::OCaml, &registry),
+        Ok(())
+    );
+}
+
+#[cfg(feature = "python3")]
+#[test]
+fn test_python_code_generation_reports_collisions() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Error);
+    let registry = get_registry(&keyword_field("from"));
+    let generator = serde_generate::python3::CodeGenerator::try_new(&config).unwrap();
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+        error.to_string(),
+        "In fields of Foo, `from` maps to the reserved keyword `from`"
+    );
+}
+
+#[cfg(feature = "rust")]
+#[test]
+fn test_rust_code_generation_reports_collisions() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_keyword_escaping(KeywordEscaping::Error);
+    let registry = get_registry(&keyword_field("type"));
+    let generator = serde_generate::rust::CodeGenerator::new(&config);
+    let error = generator.output(&mut Vec::new(), &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "In fields of Foo, `type` maps to the reserved keyword `type`"
+    );
+}
+
+#[cfg(feature = "java")]
+#[test]
+fn test_unsupported_c_style_enums_are_config_errors() {
+    use serde_generate::{java, ConfigError};
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    let error = java::CodeGenerator::try_new(&config).err().unwrap();
+    assert_eq!(
+        error,
+        ConfigError::CStyleEnums {
+            language: Language::Java
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Java does not support generating c-style enums"
+    );
+}
+
+#[cfg(feature = "cpp")]
+#[test]
+fn test_unsupported_encodings_are_config_errors() {
+    use serde_generate::{cpp, ConfigError, Encoding, SourceInstaller};
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bincode, Encoding::Postcard]);
+    let error = cpp::CodeGenerator::try_new(&config).err().unwrap();
+    assert_eq!(
+        error,
+        ConfigError::Encoding {
+            language: Language::Cpp,
+            encoding: Encoding::Postcard
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "C++ does not support the postcard encoding yet"
+    );
+
+    // Installers without a runtime for the encoding rely on the default method.
+    let installer = cpp::Installer::new(std::env::temp_dir());
+    assert_eq!(
+        installer
+            .install_postcard_runtime()
+            .unwrap_err()
+            .to_string(),
+        "The installer does not provide a runtime for the postcard encoding"
+    );
+}
+
+#[test]
+fn test_installers_may_use_any_error_type() {
+    use serde_generate::SourceInstaller;
+
+    // Installers written against the original trait still compile.
+    struct Installer;
+
+    impl SourceInstaller for Installer {
+        type Error = std::fmt::Error;
+
+        fn install_module(
+            &self,
+            _config: &CodeGeneratorConfig,
+            _registry: &Registry,
+        ) -> Result<(), Self::Error> {
+            Err(std::fmt::Error)
+        }
+
+        fn install_serde_runtime(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn install_bincode_runtime(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn install_bcs_runtime(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    assert!(Installer
+        .install_module(&config, &get_registry("{}"))
+        .is_err());
+    assert!(Installer.install_bcs_runtime().is_ok());
+}
+
+#[cfg(feature = "golang")]
+#[test]
+fn test_installer_reports_config_errors() {
+    use serde_generate::{golang, SourceInstaller};
+
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+    let dir = tempfile::tempdir().unwrap();
+    let installer = golang::Installer::new(dir.path().to_path_buf(), None);
+    let error = installer
+        .install_module(&config, &get_registry("{}"))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Go does not support generating c-style enums"
+    );
+}
+
+#[test]
+fn test_elision_containers_must_be_structs() {
+    use serde_generate::validation::{validate_support, Issue};
+
+    let registry = get_registry(
+        r#"---
+Choice:
+  ENUM:
+    0:
+      A: UNIT
+Record:
+  STRUCT:
+    - x: U64
+    - y:
+        SEQ: F32
+"#,
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_elision_containers(
+        ["Choice", "Missing", "Record"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+    );
+    let issue = |path: &str, reason: &str| Issue {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+    assert_eq!(
+        validate_support(&config, Language::Python3, &registry),
+        vec![
+            issue(
+                "Choice",
+                "the compact encoding only applies to structs with named fields"
+            ),
+            issue(
+                "Missing",
+                "unknown container selected for the compact encoding"
+            ),
+            issue(
+                "Record.y",
+                "floating-point numbers are not supported by the compact encoding"
+            ),
+        ]
+    );
+    // Other languages ignore the compact encoding.
+    assert!(validate_support(&config, Language::Java, &registry).is_empty());
+    let config = config.with_allow_nonstandard_floats(true);
+    assert_eq!(
+        validate_support(&config, Language::Rust, &registry).len(),
+        2
+    );
+}
+
+#[test]
+fn test_tuple_field_names_must_match_tuple_variants() {
+    use serde_generate::validation::{validate_support, Issue};
+
+    let registry = get_registry(
+        r#"---
+Choice:
+  ENUM:
+    0:
+      A: UNIT
+    1:
+      B:
+        TUPLE:
+          - U64
+          - STR
+"#,
+    );
+    let names = |variant: &str, names: &[&str]| {
+        (
+            ("Choice".to_string(), variant.to_string()),
+            names.iter().map(|name| name.to_string()).collect(),
+        )
+    };
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_tuple_field_names(
+        vec![
+            names("A", &["x"]),
+            names("B", &["amount"]),
+            names("C", &["x", "y"]),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let issue = |path: &str, reason: &str| Issue {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    };
+    assert_eq!(
+        validate_support(&config, Language::Swift, &registry),
+        vec![
+            issue(
+                "Choice::A",
+                "tuple field names only apply to tuple variants"
+            ),
+            issue("Choice::B", "expected 2 tuple field names, found 1"),
+            issue(
+                "Choice::C",
+                "unknown variant selected for tuple field names"
+            ),
+        ]
+    );
+    // Other languages ignore the names.
+    assert!(validate_support(&config, Language::Rust, &registry).is_empty());
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_tuple_field_names(vec![names("B", &["x", "x"])].into_iter().collect());
+    assert_eq!(
+        validate_support(&config, Language::Java, &registry),
+        vec![issue("Choice::B", "duplicate tuple field name `x`")]
+    );
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_tuple_field_names(vec![names("B", &["amount"])].into_iter().collect());
+    let error = serde_generate::typescript::CodeGenerator::try_new(&config)
+        .unwrap()
+        .output(&mut Vec::new(), &registry)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Choice::B: expected 2 tuple field names, found 1"
+    );
+}
+
+#[test]
+fn test_registry_limits() {
+    use serde_generate::validation::{validate_registry_limits, RegistryError, RegistryLimits};
+    use serde_reflection::{ContainerFormat, Format};
+
+    let registry = get_registry(
+        r#"---
+Foo:
+  STRUCT:
+    - data:
+        TUPLEARRAY:
+          CONTENT: U8
+          SIZE: 4294967296
+"#,
+    );
+    assert_eq!(
+        validate_registry_limits(&registry, &RegistryLimits::default()),
+        Err(RegistryError::TupleArraySize {
+            path: "Foo.data".to_string(),
+            size: 1 << 32,
+            max_size: 1 << 16,
+        })
+    );
+    let limits = RegistryLimits::new().with_max_tuple_array_size(1 << 32);
+    assert_eq!(validate_registry_limits(&registry, &limits), Ok(()));
+
+    let mut format = Format::U8;
+    for _ in 0..100 {
+        format = Format::Option(Box::new(format));
+    }
+    let registry: Registry = vec![(
+        "Bar".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(format)),
+    )]
+    .into_iter()
+    .collect();
+    let error = validate_registry_limits(&registry, &RegistryLimits::default()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Bar: formats are nested more than 64 levels deep"
+    );
+    let limits = RegistryLimits::new().with_max_depth(101);
+    assert_eq!(validate_registry_limits(&registry, &limits), Ok(()));
+}
+
+#[test]
+fn test_file_names() {
+    use serde_generate::{
+        java,
+        validation::{validate_support, Issue},
+        SourceInstaller,
+    };
+
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let registry = get_registry(
+        r#"---
+"../Evil": UNITSTRUCT
+Con: UNITSTRUCT
+"Lpt1.x": UNITSTRUCT
+"#,
+    );
+    let issue = |path: &str, file_name: &str| Issue {
+        path: path.to_string(),
+        reason: format!("cannot be written to the file `{}`", file_name),
+    };
+    // Such names are not valid identifiers either.
+    let characters = |name: &str| Issue {
+        path: "type names".to_string(),
+        reason: format!(
+            "`{}` contains characters that are not allowed in identifiers",
+            name
+        ),
+    };
+    for language in [Language::CSharp, Language::Java] {
+        assert_eq!(
+            validate_support(&config, language, &registry),
+            vec![
+                characters("../Evil"),
+                characters("Lpt1.x"),
+                issue("../Evil", "../Evil"),
+                issue("Con", "Con"),
+                issue("Lpt1.x", "Lpt1.x"),
+            ]
+        );
+    }
+    // Dart file names are in snake case, which drops separators.
+    assert_eq!(
+        validate_support(&config, Language::Dart, &registry),
+        vec![
+            characters("../Evil"),
+            characters("Lpt1.x"),
+            issue("Con", "con")
+        ]
+    );
+    // Other languages write container names in a single file.
+    assert_eq!(
+        validate_support(&config, Language::Python3, &registry),
+        vec![characters("../Evil"), characters("Lpt1.x")]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let installer = java::Installer::new(dir.path().join("java"));
+    let error = installer.install_module(&config, &registry).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Container `../Evil` cannot be written to the file `../Evil`"));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_non_ascii_names() {
+    use serde_generate::{validation::renamed_identifiers, NonAsciiNames};
+
+    let registry = crate::test_utils::get_non_ascii_registry().unwrap();
+    let characters = |scope: &str, name: &str| CollisionError::Characters {
+        scope: scope.to_string(),
+        name: name.to_string(),
+    };
+
+    // By default, Unicode identifiers are kept where the language allows them.
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    for language in [Language::Java, Language::Python3, Language::TypeScript] {
+        assert_eq!(validate_registry_for(&config, language, &registry), Ok(()));
+    }
+    let go_errors = vec![
+        characters("type names", "Café"),
+        characters("fields of Café", "prénom"),
+        characters("fields of Café", "ご注文"),
+        characters("type names", "注文"),
+        characters("variants of 注文", "Thé"),
+        characters("variants of 注文", "コーヒー"),
+        characters("fields of 注文::コーヒー", "量"),
+    ];
+    assert_eq!(
+        validate_registry_for(&config, Language::Go, &registry),
+        Err(go_errors.clone())
+    );
+    assert!(renamed_identifiers(&config, &registry).is_empty());
+
+    let config = config.with_non_ascii_names(NonAsciiNames::Error);
+    assert_eq!(
+        validate_registry_for(&config, Language::Python3, &registry),
+        Err(go_errors)
+    );
+
+    let config = config.with_non_ascii_names(NonAsciiNames::Transliterate);
+    for language in [Language::Go, Language::Python3, Language::TypeScript] {
+        assert_eq!(validate_registry_for(&config, language, &registry), Ok(()));
+    }
+    let renames: Vec<_> = renamed_identifiers(&config, &registry)
+        .into_iter()
+        .map(|(path, name)| format!("{} -> {}", path, name))
+        .collect();
+    assert_eq!(
+        renames,
+        vec![
+            "Café -> CafU00E9",
+            "Café.prénom -> prU00E9nom",
+            "Café.ご注文 -> U3054U6CE8U6587",
+            "注文 -> U6CE8U6587",
+            "注文::Thé -> ThU00E9",
+            "注文::コーヒー -> U30B3U30FCU30D2U30FC",
+            "注文::コーヒー.量 -> U91CF",
+        ]
+    );
+
+    // Other characters are never allowed in identifiers.
+    let registry = get_registry(&keyword_field("user-id"));
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    assert_eq!(
+        validate_registry_for(&config, Language::Python3, &registry),
+        Err(vec![characters("fields of Foo", "user-id")])
+    );
+    let config = config.with_non_ascii_names(NonAsciiNames::Transliterate);
+    assert_eq!(
+        validate_registry_for(&config, Language::Python3, &registry),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_transliterated_names_may_collide() {
+    use serde_generate::NonAsciiNames;
+
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_non_ascii_names(NonAsciiNames::Transliterate);
+    let registry = get_registry(
+        r#"---
+Foo:
+  STRUCT:
+    - "a-b": U8
+    - aU002Db: U8
+"#,
+    );
+    assert_eq!(
+        validate_registry_for(&config, Language::Python3, &registry),
+        Err(vec![names("fields of Foo", "a-b", "aU002Db", "aU002Db")])
+    );
+}
+
+#[test]
+fn test_available_languages_follow_features() {
+    let enabled = [
+        (Language::Cpp, cfg!(feature = "cpp")),
+        (Language::CSharp, cfg!(feature = "csharp")),
+        (Language::Dart, cfg!(feature = "dart")),
+        (Language::Go, cfg!(feature = "golang")),
+        (Language::Java, cfg!(feature = "java")),
+        (Language::OCaml, cfg!(feature = "ocaml")),
+        (Language::OpenApi, cfg!(feature = "openapi")),
+        (Language::Php, cfg!(feature = "php")),
+        (Language::Python3, cfg!(feature = "python3")),
+        (Language::Rust, cfg!(feature = "rust")),
+        (Language::Solidity, cfg!(feature = "solidity")),
+        (Language::Swift, cfg!(feature = "swift")),
+        (Language::TypeScript, cfg!(feature = "typescript")),
+        (Language::Zig, cfg!(feature = "zig")),
+    ];
+    assert_eq!(
+        enabled
+            .iter()
+            .map(|(language, _)| *language)
+            .collect::<Vec<_>>(),
+        Language::ALL
+    );
+    assert_eq!(
+        Language::available(),
+        enabled
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(language, _)| *language)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_language_names_round_trip() {
+    for language in Language::ALL {
+        assert_eq!(language.name().parse::<Language>(), Ok(*language));
+        assert_eq!(
+            language.name().to_lowercase().parse::<Language>(),
+            Ok(*language)
+        );
+    }
+    let error = "python".parse::<Language>().unwrap_err();
+    assert!(error.starts_with("Unknown language python (expected one of Cpp, CSharp,"));
+}
+
+/// Construct the code generator of the language, which must be available.
+fn try_new_generator(language: Language, config: &CodeGeneratorConfig) -> Result<(), ConfigError> {
+    match language {
+        #[cfg(feature = "cpp")]
+        Language::Cpp => serde_generate::cpp::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "csharp")]
+        Language::CSharp => serde_generate::csharp::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "dart")]
+        Language::Dart => serde_generate::dart::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "golang")]
+        Language::Go => serde_generate::golang::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "java")]
+        Language::Java => serde_generate::java::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "ocaml")]
+        Language::OCaml => serde_generate::ocaml::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "openapi")]
+        Language::OpenApi => serde_generate::openapi::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "php")]
+        Language::Php => serde_generate::php::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "python3")]
+        Language::Python3 => serde_generate::python3::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "rust")]
+        Language::Rust => {
+            serde_generate::rust::CodeGenerator::new(config);
+            Ok(())
+        }
+        #[cfg(feature = "solidity")]
+        Language::Solidity => serde_generate::solidity::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "swift")]
+        Language::Swift => serde_generate::swift::CodeGenerator::try_new(config).map(|_| ()),
+        #[cfg(feature = "typescript")]
+        Language::TypeScript => {
+            serde_generate::typescript::CodeGenerator::try_new(config).map(|_| ())
+        }
+        #[cfg(feature = "zig")]
+        Language::Zig => serde_generate::zig::CodeGenerator::try_new(config).map(|_| ()),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("{} is not available", language.name()),
+    }
+}
+
+#[test]
+fn test_code_generators_follow_capabilities() {
+    for language in Language::available() {
+        let capabilities = language.capabilities().unwrap();
+        let config = CodeGeneratorConfig::new("testing".to_string())
+            .with_encodings(capabilities.encodings.to_vec())
+            .with_c_style_enums(capabilities.c_style_enums);
+        assert_eq!(try_new_generator(language, &config), Ok(()));
+
+        // The Rust code generator cannot fail.
+        if language == Language::Rust {
+            continue;
+        }
+        if !capabilities.c_style_enums {
+            let config = CodeGeneratorConfig::new("testing".to_string()).with_c_style_enums(true);
+            assert_eq!(
+                try_new_generator(language, &config),
+                Err(ConfigError::CStyleEnums { language })
+            );
+        }
+        let one_way_configs = [
+            CodeGeneratorConfig::new("testing".to_string()).with_deserialization(false),
+            CodeGeneratorConfig::new("testing".to_string())
+                .with_serialization(false)
+                .with_deserialization(true),
+        ];
+        for config in &one_way_configs {
+            let expected = if capabilities.one_way_serialization {
+                Ok(())
+            } else {
+                Err(ConfigError::OneWaySerialization { language })
+            };
+            assert_eq!(try_new_generator(language, config), expected);
+        }
+        let config = CodeGeneratorConfig::new("testing".to_string()).with_bcs_length_prefix(true);
+        let expected = if capabilities.bcs_length_prefix {
+            Ok(())
+        } else {
+            Err(ConfigError::BcsLengthPrefix { language })
+        };
+        assert_eq!(try_new_generator(language, &config), expected);
+        for encoding in [Encoding::Bincode, Encoding::Bcs, Encoding::Postcard] {
+            if capabilities.encodings.contains(&encoding) {
+                continue;
+            }
+            let config =
+                CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![encoding]);
+            assert_eq!(
+                try_new_generator(language, &config),
+                Err(ConfigError::Encoding { language, encoding })
+            );
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use serde_generate::{zig, CodeGeneratorConfig, Encoding, SourceInstaller};
+use serde_reflection::Registry;
+use std::{collections::BTreeMap, fs::File, io::Write, path::PathBuf, process::Command};
+use tempfile::{tempdir, TempDir};
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+    "align", "and", "anytype", "comptime", "const", "defer", "errdefer", "error", "fn", "opaque",
+    "or", "orelse", "pub", "struct", "test", "try", "union", "unreachable", "usize", "var", "void",
+    "serialize", "deserialize",
+];
+
+/// Install the generated module and the runtimes in a new directory, then check that the
+/// code compiles, if Zig is installed. Return the path of the module.
+fn test_that_zig_code_compiles_with_config_and_registry(
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> (TempDir, PathBuf) {
+    let dir = tempdir().unwrap();
+    let installer = zig::Installer::new(dir.path().to_path_buf());
+    installer.install_everything(config, registry).unwrap();
+    let source_path = dir.path().join(format!("{}.zig", config.module_name()));
+    assert!(source_path.exists());
+
+    if which::which("zig").is_err() {
+        return (dir, source_path);
+    }
+    let test_path = dir.path().join("main.zig");
+    let mut test = File::create(&test_path).unwrap();
+    // Definitions are analyzed lazily so we reference all of them.
+    writeln!(
+        test,
+        r#"
+const std = @import("std");
+const module = @import("{}.zig");
+
+test {{
+    std.testing.refAllDeclsRecursive(module);
+}}
+"#,
+        config.module_name()
+    )
+    .unwrap();
+
+    let status = Command::new("zig")
+        .current_dir(dir.path())
+        .arg("test")
+        .arg(&test_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    (dir, source_path)
+}
+
+fn test_that_zig_code_compiles_with_config(config: &CodeGeneratorConfig) -> (TempDir, PathBuf) {
+    test_that_zig_code_compiles_with_config_and_registry(config, &Registry::new());
+    test_that_zig_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_simple_registry().unwrap(),
+    );
+    test_that_zig_code_compiles_with_config_and_registry(
+        config,
+        &test_utils::get_registry().unwrap(),
+    )
+}
+
+#[test]
+fn test_that_zig_code_compiles() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("pub const SerdeData = union(enum) {\n"));
+    assert!(content.contains("    UnitVariant,\n    NewTypeVariant: []const u8,\n"));
+    assert!(content.contains("    f_stringmap: []const serde.Entry([]const u8, u32),\n"));
+    assert!(content.contains("    f_tuple: std.meta.Tuple(&.{ u8, u16 }),\n"));
+    assert!(content.contains("    TupleArray: [3]u32,\n"));
+    assert!(content.contains("\nfn serialize_vector_vector_Struct("));
+}
+
+#[test]
+fn test_that_recursive_zig_containers_use_pointers() {
+    let config = CodeGeneratorConfig::new("testing".to_string());
+    let (_dir, source_path) = test_that_zig_code_compiles_with_config_and_registry(
+        &config,
+        &test_utils::get_registry().unwrap(),
+    );
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("pub const SimpleList = struct {\n    value: ?*const SimpleList,\n"));
+    assert!(content
+        .contains("try deserializer.create(SimpleList, try SimpleList.deserialize(deserializer))"));
+    // Slices do not need pointers.
+    assert!(content.contains("    children: []const Tree,\n"));
+}
+
+#[test]
+fn test_that_zig_code_compiles_without_serialization() {
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_serialization(false);
+    let (_dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(!content.contains("serialize"));
+}
+
+#[test]
+fn test_that_zig_code_compiles_with_bcs() {
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs])
+        .with_allow_nonstandard_floats(true);
+    let (dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("const bcs = @import(\"bcs.zig\");\n"));
+    assert!(content.contains(
+        "    pub fn bcsDeserialize(allocator: std.mem.Allocator, input: []const u8) serde.Error!SerdeData {\n"
+    ));
+    assert!(dir.path().join("serde.zig").exists());
+    assert!(dir.path().join("bcs.zig").exists());
+    assert!(!dir.path().join("bincode.zig").exists());
+}
+
+#[test]
+fn test_that_zig_code_compiles_with_bincode() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bincode]);
+    let (dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("    pub fn bincodeSerialize(self: SerdeData, allocator: std.mem.Allocator) serde.Error![]u8 {\n"));
+    assert!(dir.path().join("bincode.zig").exists());
+}
+
+#[test]
+fn test_that_zig_installer_rejects_postcard() {
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Postcard]);
+    assert!(zig::CodeGenerator::try_new(&config).is_err());
+    let dir = tempdir().unwrap();
+    let installer = zig::Installer::new(dir.path().to_path_buf());
+    assert!(installer.install_postcard_runtime().is_err());
+}
+
+#[test]
+fn test_that_zig_installer_writes_manifest() {
+    let registry = test_utils::get_simple_registry().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![Encoding::Bcs]);
+    let dir = tempdir().unwrap();
+    zig::Installer::new(dir.path().to_path_buf())
+        .install_everything(&config, &registry)
+        .unwrap();
+    assert!(!dir.path().join("build.zig").exists());
+
+    zig::Installer::new(dir.path().to_path_buf())
+        .with_manifest(true)
+        .install_everything(&config, &registry)
+        .unwrap();
+    let content = std::fs::read_to_string(dir.path().join("build.zig")).unwrap();
+    assert!(content.contains("b.addModule(\"testing\", .{\n"));
+    assert!(content.contains(".root_source_file = b.path(\"testing.zig\"),\n"));
+
+    if which::which("zig").is_err() {
+        return;
+    }
+    let status = Command::new("zig")
+        .current_dir(dir.path())
+        .arg("build")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_that_zig_code_compiles_with_comments() {
+    let comments = vec![
+        (
+            vec!["testing".to_string(), "SerdeData".to_string()],
+            "Some\ncomments".to_string(),
+        ),
+        (
+            vec![
+                "testing".to_string(),
+                "List".to_string(),
+                "Node".to_string(),
+            ],
+            "Some other comments".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_comments(comments);
+
+    let (_dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("\n/// Some\n/// comments\npub const SerdeData"));
+    assert!(content.contains("\n    /// Some other comments\n    Node: "));
+}
+
+#[test]
+fn test_zig_code_with_external_definitions() {
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("testing.zig");
+    let mut source = File::create(&source_path).unwrap();
+
+    // Pretend that "Tree" is external.
+    let mut definitions = BTreeMap::new();
+    definitions.insert("foo".to_string(), vec!["Tree".to_string()]);
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_external_definitions(definitions);
+    let generator = zig::CodeGenerator::try_new(&config).unwrap();
+    generator.output(&mut source, &registry).unwrap();
+
+    // References were updated.
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("const foo = @import(\"foo.zig\");\n"));
+    assert!(content.contains("TreeWithMutualRecursion: foo.Tree,\n"));
+}
+
+#[test]
+fn test_that_zig_code_compiles_with_custom_code() {
+    let custom_code = vec![
+        (
+            vec!["testing".to_string(), "SerdeData".to_string()],
+            "// custom1".to_string(),
+        ),
+        (
+            vec!["testing".to_string(), "List".to_string()],
+            "// custom2".to_string(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = CodeGeneratorConfig::new("testing".to_string()).with_custom_code(custom_code);
+
+    let (_dir, source_path) = test_that_zig_code_compiles_with_config(&config);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("// custom1"));
+    assert!(content.contains("// custom2"));
+}
+
+#[test]
+fn test_that_zig_code_compiles_with_keyword_fields() {
+    let registry = test_utils::get_keyword_registry(KEYWORDS);
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![Encoding::Bcs, Encoding::Bincode]);
+    let (_dir, source_path) =
+        test_that_zig_code_compiles_with_config_and_registry(&config, &registry);
+    let content = std::fs::read_to_string(source_path).unwrap();
+    assert!(content.contains("    fn_: u8,\n"));
+    assert!(content.contains("    serialize_: u8,\n"));
+}
@@ -0,0 +1,191 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::test_utils;
+use crate::test_utils::{Choice, Runtime, Test};
+use serde_generate::{zig, CodeGeneratorConfig, SourceInstaller};
+use serde_reflection::Registry;
+use std::{fs::File, io::Write, path::Path, process::Command};
+use tempfile::tempdir;
+
+/// Install the generated module "testing" together with the runtimes, and open the module
+/// to append Zig tests to it.
+fn install_module_with_tests(
+    dir: &Path,
+    config: &CodeGeneratorConfig,
+    registry: &Registry,
+) -> File {
+    let installer = zig::Installer::new(dir.to_path_buf());
+    installer.install_everything(config, registry).unwrap();
+    File::options()
+        .append(true)
+        .open(dir.join("testing.zig"))
+        .unwrap()
+}
+
+fn run_zig_tests(dir: &Path) {
+    let status = Command::new("zig")
+        .current_dir(dir)
+        .arg("test")
+        .arg("testing.zig")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn quote_bytes(bytes: &[u8]) -> String {
+    format!(
+        "&[_]u8{{ {} }}",
+        bytes
+            .iter()
+            .map(|x| format!("{}", x))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[test]
+fn test_zig_bcs_runtime_on_simple_data() {
+    test_zig_runtime_on_simple_data(Runtime::Bcs);
+}
+
+#[test]
+fn test_zig_bincode_runtime_on_simple_data() {
+    test_zig_runtime_on_simple_data(Runtime::Bincode);
+}
+
+fn test_zig_runtime_on_simple_data(runtime: Runtime) {
+    if which::which("zig").is_err() {
+        return;
+    }
+    let registry = test_utils::get_simple_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config =
+        CodeGeneratorConfig::new("testing".to_string()).with_encodings(vec![runtime.into()]);
+    let mut source = install_module_with_tests(dir.path(), &config, &registry);
+
+    let reference = runtime.serialize(&Test {
+        a: vec![4, 6],
+        b: (-3, 5),
+        c: Choice::C { x: 7 },
+    });
+
+    writeln!(
+        source,
+        r#"
+test "simple data" {{
+    var arena = std.heap.ArenaAllocator.init(std.testing.allocator);
+    defer arena.deinit();
+    const allocator = arena.allocator();
+
+    const input: []const u8 = {0};
+    const value = try Test.{1}Deserialize(allocator, input);
+
+    const value2 = Test{{
+        .a = &[_]u32{{ 4, 6 }},
+        .b = .{{ -3, 5 }},
+        .c = .{{ .C = .{{ .x = 7 }} }},
+    }};
+    try std.testing.expectEqualDeep(value2, value);
+
+    const output = try value2.{1}Serialize(allocator);
+    try std.testing.expectEqualSlices(u8, input, output);
+
+    const input2 = try std.mem.concat(allocator, u8, &.{{ input, &[_]u8{{1}} }});
+    try std.testing.expectError(error.RemainingInput, Test.{1}Deserialize(allocator, input2));
+}}
+"#,
+        quote_bytes(&reference),
+        runtime.name(),
+    )
+    .unwrap();
+
+    run_zig_tests(dir.path());
+}
+
+#[test]
+fn test_zig_bcs_runtime_on_supported_types() {
+    test_zig_runtime_on_supported_types(Runtime::Bcs);
+}
+
+#[test]
+fn test_zig_bincode_runtime_on_supported_types() {
+    test_zig_runtime_on_supported_types(Runtime::Bincode);
+}
+
+fn test_zig_runtime_on_supported_types(runtime: Runtime) {
+    if which::which("zig").is_err() {
+        return;
+    }
+    let registry = test_utils::get_registry().unwrap();
+    let dir = tempdir().unwrap();
+    let config = CodeGeneratorConfig::new("testing".to_string())
+        .with_encodings(vec![runtime.into()])
+        .with_allow_nonstandard_floats(true);
+    let mut source = install_module_with_tests(dir.path(), &config, &registry);
+
+    let positive_encodings = runtime
+        .get_positive_samples_quick()
+        .iter()
+        .map(|bytes| quote_bytes(bytes))
+        .collect::<Vec<_>>()
+        .join(",\n        ");
+
+    let negative_encodings = runtime
+        .get_negative_samples()
+        .iter()
+        .map(|bytes| quote_bytes(bytes))
+        .collect::<Vec<_>>()
+        .join(",\n        ");
+
+    writeln!(
+        source,
+        r#"
+test "supported types" {{
+    var arena = std.heap.ArenaAllocator.init(std.testing.allocator);
+    defer arena.deinit();
+    const allocator = arena.allocator();
+
+    const positive_inputs = [_][]const u8{{
+        {0},
+    }};
+    const negative_inputs = [_][]const u8{{
+        {1},
+    }};
+
+    for (positive_inputs) |input| {{
+        const value = try SerdeData.{2}Deserialize(allocator, input);
+        const output = try value.{2}Serialize(allocator);
+        try std.testing.expectEqualSlices(u8, input, output);
+
+        // Test self-equality for the Serde value.
+        const value2 = try SerdeData.{2}Deserialize(allocator, input);
+        try std.testing.expectEqualDeep(value, value2);
+
+        // Test simple mutations of the input.
+        const input2 = try allocator.dupe(u8, input);
+        for (0..input2.len) |i| {{
+            input2[i] ^= 0x80;
+            defer input2[i] ^= 0x80;
+            const value3 = SerdeData.{2}Deserialize(allocator, input2) catch continue;
+            const output3 = try value3.{2}Serialize(allocator);
+            try std.testing.expect(!std.mem.eql(u8, input, output3));
+        }}
+    }}
+
+    for (negative_inputs) |input| {{
+        if (SerdeData.{2}Deserialize(allocator, input)) |_| {{
+            std.debug.print("Input should fail to deserialize: {{any}}\n", .{{input}});
+            return error.TestUnexpectedResult;
+        }} else |_| {{}}
+    }}
+}}
+"#,
+        positive_encodings,
+        negative_encodings,
+        runtime.name(),
+    )
+    .unwrap();
+
+    run_zig_tests(dir.path());
+}
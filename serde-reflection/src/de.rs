@@ -24,6 +24,8 @@ pub(crate) struct Deserializer<'de, 'a> {
     tracer: &'a mut Tracer,
     samples: &'de Samples,
     format: &'a mut Format,
+    /// Whether this is the top-level value of a tracing run.
+    is_root: bool,
 }
 
 impl<'de, 'a> Deserializer<'de, 'a> {
@@ -36,6 +38,20 @@ impl<'de, 'a> Deserializer<'de, 'a> {
             tracer,
             samples,
             format,
+            is_root: false,
+        }
+    }
+
+    /// Deserializer for the top-level value of a tracing run. If this value is an enum, the
+    /// run explores a new variant even if the enum is already known to be incomplete.
+    pub(crate) fn new_root(
+        tracer: &'a mut Tracer,
+        samples: &'de Samples,
+        format: &'a mut Format,
+    ) -> Self {
+        Deserializer {
+            is_root: true,
+            ..Self::new(tracer, samples, format)
         }
     }
 }
@@ -43,11 +59,36 @@ impl<'de, 'a> Deserializer<'de, 'a> {
 impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    // Untagged enums are only traced on request: see `TracerConfig::allow_untagged`.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::NotSupported("deserialize_any"))
+        if !self.tracer.config.allow_untagged {
+            return Err(Error::NotSupported("deserialize_any"));
+        }
+        let occurrence = self.tracer.untagged_occurrences;
+        self.tracer.untagged_occurrences += 1;
+        let index = match self.tracer.untagged_progress.get(&occurrence) {
+            Some(index) => *index,
+            None => return Err(Error::UntaggedDetected(occurrence)),
+        };
+        let (name, variants) = &self.tracer.config.untagged_variant_hints[&occurrence];
+        let name = name.clone();
+        let variant = variants[index].clone();
+        self.format.unify(Format::TypeName(name.clone()))?;
+        let mut formats = BTreeMap::new();
+        formats.insert(index as u32, variant.clone());
+        self.tracer
+            .registry
+            .entry(name.clone())
+            .unify(ContainerFormat::Enum(formats))?;
+        self.tracer.untagged_enums.insert(name);
+        let hint = HintDeserializer {
+            tracer: self.tracer,
+            format: &Format::Unit,
+        };
+        hint.deserialize_variant(&variant.value, visitor)
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -237,7 +278,7 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         self.format.unify(Format::TypeName(name.into()))?;
         if self.tracer.config.record_samples_for_newtype_structs {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
+            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name)? {
                 return visitor
                     .visit_newtype_struct(sample.into_deserializer())
                     .map_err(|err| match err {
@@ -283,6 +324,9 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     where
         V: Visitor<'de>,
     {
+        // Arrays are deserialized as tuples, including arrays of more than 32 elements
+        // implemented with const generics. Tuples of identical formats become `TupleArray`s
+        // when the format is reduced.
         let mut formats: Vec<_> = std::iter::repeat_with(Format::unknown).take(len).collect();
         self.format.unify(Format::Tuple(formats.clone()))?;
         let inner = SeqDeserializer::new(self.tracer, self.samples, formats.iter_mut());
@@ -301,7 +345,7 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         self.format.unify(Format::TypeName(name.into()))?;
         if self.tracer.config.record_samples_for_tuple_structs {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
+            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name)? {
                 let result = || visitor.visit_seq(sample.seq_values()?.into_seq_deserializer());
                 return result().map_err(|err| match err {
                     Error::DeserializationError(msg) => {
@@ -361,7 +405,7 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         self.format.unify(Format::TypeName(name.into()))?;
         if self.tracer.config.record_samples_for_structs {
             // If a value was recorded during serialization, use it.
-            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name) {
+            if let Some((format, sample)) = self.tracer.get_sample(self.samples, name)? {
                 let result = || visitor.visit_seq(sample.seq_values()?.into_seq_deserializer());
                 return result().map_err(|err| match err {
                     Error::DeserializationError(msg) => {
@@ -395,7 +439,8 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
     }
 
     // Assumption: The first variant(s) should be "base cases", i.e. not cause infinite recursion
-    // while constructing sample values.
+    // while constructing sample values, unless another "base" variant is given by
+    // `TracerConfig::base_variant_hint`.
     #[allow(clippy::map_entry)] // false positive https://github.com/rust-lang/rust-clippy/issues/9470
     fn deserialize_enum<V>(
         self,
@@ -410,8 +455,16 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
             return Err(Error::NotSupported("deserialize_enum with 0 variants"));
         }
 
+        let base_index = match self.tracer.config.base_variant_hints.get(enum_name) {
+            Some(&index) if (index as usize) < variants.len() => index,
+            Some(&index) => return Err(Error::InvalidBaseVariantHint(enum_name.into(), index)),
+            None => 0,
+        };
         let enum_type_id = typeid::of::<V::Value>();
         self.format.unify(Format::TypeName(enum_name.into()))?;
+        self.tracer
+            .enum_variant_names
+            .insert(enum_name.to_string(), variants);
         // Pre-update the registry.
         self.tracer
             .registry
@@ -422,22 +475,52 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
             _ => unreachable!(),
         };
 
-        // If the enum is already marked as incomplete, visit the first index, hoping
+        // If we only want the base variant, visit its index and record it under the
+        // corresponding variant name. (Like `#[derive(Deserialize)]`, this assumes that indices
+        // follow the order of `variants`.)
+        if !self.tracer.config.trace_all_variants {
+            let mut value = VariantFormat::unknown();
+            let enum_value = visitor.visit_enum(EnumDeserializer::new(
+                self.tracer,
+                self.samples,
+                VariantId::Index(base_index),
+                &mut value,
+            ))?;
+            let known_variants = match self.tracer.registry.get_mut(enum_name) {
+                Some(ContainerFormat::Enum(x)) => x,
+                _ => unreachable!(),
+            };
+            known_variants
+                .entry(base_index)
+                .or_insert_with(|| Named {
+                    name: variants[base_index as usize].to_owned(),
+                    value: VariantFormat::unknown(),
+                })
+                .value
+                .unify(value)?;
+            return Ok(enum_value);
+        }
+
+        // If the enum is already marked as incomplete, visit the base index, hoping
         // to avoid recursion.
-        if self.tracer.incomplete_enums.contains_key(enum_name) {
+        if !self.is_root && self.tracer.incomplete_enums.contains_key(enum_name) {
             return visitor.visit_enum(EnumDeserializer::new(
                 self.tracer,
                 self.samples,
-                VariantId::Index(0),
+                VariantId::Index(base_index),
                 &mut VariantFormat::unknown(),
             ));
         }
 
-        // First, visit each of the variants by name according to `variants`. Later, we
-        // will revisit them by u32 index until an index matching each of the named
-        // variants has been determined.
+        // First, visit each of the variants by name according to `variants`, starting with the
+        // base variant. Later, we will revisit them by u32 index until an index matching each
+        // of the named variants has been determined.
         let provisional_min = u32::MAX - (variants.len() - 1) as u32;
-        for (i, &variant_name) in variants.iter().enumerate() {
+        let base_position = base_index as usize;
+        let positions = std::iter::once(base_position)
+            .chain((0..variants.len()).filter(|i| *i != base_position));
+        for i in positions {
+            let variant_name = variants[i];
             if self
                 .tracer
                 .discriminants
@@ -468,6 +551,9 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
             self.tracer
                 .discriminants
                 .insert((enum_type_id, VariantId::Name(variant_name)), discriminant);
+            self.tracer
+                .last_explored_variants
+                .insert(enum_name.to_string(), variant_name.to_string());
             return Ok(enum_value);
         }
 
@@ -475,12 +561,13 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         // by index to find the u32 id that goes with each name.
         //
         // If there are no provisional entries waiting for an index, just go
-        // with index 0.
-        let mut index = 0;
+        // with the base index.
+        let mut index = base_index;
         if known_variants.range(provisional_min..).next().is_some() {
             self.tracer
                 .incomplete_enums
                 .insert(enum_name.into(), EnumProgress::IndexedVariantsRemaining);
+            index = 0;
             while known_variants.contains_key(&index)
                 && self
                     .tracer
@@ -539,6 +626,9 @@ impl<'de, 'a> de::Deserializer<'de> for Deserializer<'de, 'a> {
         }
         if let Some(existing_entry) = known_variants.get_mut(&index) {
             existing_entry.value.unify(value)?;
+            self.tracer
+                .last_explored_variants
+                .insert(enum_name.to_string(), existing_entry.name.clone());
         }
         if has_indexed_variants_remaining {
             // Signal that the top-level tracing must continue.
@@ -735,3 +825,114 @@ impl<'de, 'a> de::VariantAccess<'de> for EnumDeserializer<'de, 'a> {
         visitor.visit_seq(inner)
     }
 }
+
+/// Deserialize a default value of a user-provided format in place of the content buffered
+/// by an untagged enum.
+#[derive(Clone, Copy)]
+struct HintDeserializer<'a> {
+    tracer: &'a Tracer,
+    format: &'a Format,
+}
+
+impl<'a> HintDeserializer<'a> {
+    fn with_format(self, format: &'a Format) -> Self {
+        Self { format, ..self }
+    }
+
+    fn deserialize_variant<'de, V>(self, variant: &'a VariantFormat, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match variant {
+            VariantFormat::Unit => visitor.visit_unit(),
+            VariantFormat::NewType(format) => {
+                de::Deserializer::deserialize_any(self.with_format(format), visitor)
+            }
+            VariantFormat::Tuple(formats) => self.visit_tuple(formats, visitor),
+            VariantFormat::Struct(fields) => self.visit_struct(fields, visitor),
+            VariantFormat::Variable(_) => Err(Error::NotSupported("untagged variant hint")),
+        }
+    }
+
+    fn visit_tuple<'de, V>(self, formats: &'a [Format], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let elements = formats.iter().map(|format| self.with_format(format));
+        visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+    }
+
+    fn visit_struct<'de, V>(self, fields: &'a [Named<Format>], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let entries = fields
+            .iter()
+            .map(|field| (field.name.as_str(), self.with_format(&field.value)));
+        visitor.visit_map(de::value::MapDeserializer::new(entries))
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for HintDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for HintDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let config = &self.tracer.config;
+        match self.format {
+            Format::TypeName(name) => match self.tracer.registry.get(name) {
+                Some(ContainerFormat::UnitStruct) => visitor.visit_unit(),
+                Some(ContainerFormat::NewTypeStruct(format)) => {
+                    self.with_format(format).deserialize_any(visitor)
+                }
+                Some(ContainerFormat::TupleStruct(formats)) => self.visit_tuple(formats, visitor),
+                Some(ContainerFormat::Struct(fields)) => self.visit_struct(fields, visitor),
+                _ => Err(Error::NotSupported("untagged variant hint")),
+            },
+            Format::Unit => visitor.visit_unit(),
+            Format::Bool => visitor.visit_bool(config.default_bool_value),
+            Format::I8 => visitor.visit_i8(config.default_i8_value),
+            Format::I16 => visitor.visit_i16(config.default_i16_value),
+            Format::I32 => visitor.visit_i32(config.default_i32_value),
+            Format::I64 => visitor.visit_i64(config.default_i64_value),
+            Format::I128 => visitor.visit_i128(config.default_i128_value),
+            Format::U8 => visitor.visit_u8(config.default_u8_value),
+            Format::U16 => visitor.visit_u16(config.default_u16_value),
+            Format::U32 => visitor.visit_u32(config.default_u32_value),
+            Format::U64 => visitor.visit_u64(config.default_u64_value),
+            Format::U128 => visitor.visit_u128(config.default_u128_value),
+            Format::F32 => visitor.visit_f32(config.default_f32_value),
+            Format::F64 => visitor.visit_f64(config.default_f64_value),
+            Format::Char => visitor.visit_char(config.default_char_value),
+            Format::Str => visitor.visit_string(config.default_string_value.clone()),
+            Format::Bytes => visitor.visit_byte_buf(config.default_byte_buf_value.clone()),
+            Format::Option(_) => visitor.visit_none(),
+            Format::Seq(_) => self.visit_tuple(&[], visitor),
+            Format::Map { .. } => visitor.visit_map(de::value::MapDeserializer::new(
+                std::iter::empty::<(Self, Self)>(),
+            )),
+            Format::Tuple(formats) => self.visit_tuple(formats, visitor),
+            Format::TupleArray { content, size } => {
+                let elements = std::iter::repeat(self.with_format(content)).take(*size);
+                visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+            }
+            Format::Variable(_) => Err(Error::NotSupported("untagged variant hint")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -27,6 +27,22 @@ pub enum Error {
     UnknownFormatInContainer(String),
     #[error("Missing variants detected for specific enums: {0:?}")]
     MissingVariants(Vec<String>),
+    #[error("Untagged enum detected at occurrence {0} of deserialize_any")]
+    UntaggedDetected(usize),
+    #[error("Failed to parse format: {0}")]
+    ParseFormat(String),
+    #[error("No sample value for container {0}")]
+    NoSampleValue(String),
+    #[error("Sample value for container {0} was not recorded: its estimated size of {1} bytes exceeds the maximum")]
+    SampleTooLarge(&'static str, usize),
+    #[error("Base variant hint {1} is out of range for enum {0}")]
+    InvalidBaseVariantHint(String, u32),
+    #[error("Container {0} refers to the missing container {1}")]
+    MissingContainer(String, String),
+    #[error("Formats of container {0} are nested more than {1} levels deep")]
+    FormatTooDeep(String, usize),
+    #[error("Failed to trace type {0}: {1}")]
+    TypeTracing(String, Box<Error>),
 }
 
 impl ser::Error for Error {
@@ -65,7 +81,8 @@ are generally not supported. This includes: `#[serde(flatten)]`, `#[serde(tag =
 `#[serde(tag = "t", content = "c")]`, and `#[serde(untagged)]`.
 
 To fix this, avoid unsupported Serde attributes or use custom (de)serialize implementations with different
-behaviors depending on the Serde callback `(De)Serializer::is_human_readable()`.
+behaviors depending on the Serde callback `(De)Serializer::is_human_readable()`. Untagged enums may also
+be traced on a best-effort basis with `TracerConfig::allow_untagged`.
 "#.to_string()
             }
             DeserializationError(_) => {
@@ -119,6 +136,69 @@ corpus of definitions. You may also use `tracer.registry_unchecked()` for debugg
 "#,
                 names)
             }
+            UntaggedDetected(occurrence) => {
+                format!(r#"
+The callback `deserialize_any` was called during deserialization tracing with the option
+`TracerConfig::allow_untagged` enabled, but no variants were provided for this occurrence. In practice,
+this happens when deserializing an enum marked with `#[serde(untagged)]`.
+
+To fix this, describe the untagged enum with
+`TracerConfig::untagged_variant_hint({}, "Name", vec![..])` and call `tracer.trace_type<T>(..)`.
+"#,
+                occurrence)
+            }
+            ParseFormat(_) => {
+                r#"
+A string could not be parsed as a `Format`. The expected syntax is the one produced by the `Display`
+implementation of `Format`, e.g. `[Map<Str, U32>]`, `Option<Foo>`, `(U8, Bool)`, or `[U16; 3]`.
+"#.to_string()
+            }
+            NoSampleValue(name) => {
+                format!(r#"
+A sample value was requested with `Value::sample` but none could be built for the container {}.
+This happens when the container is missing from the registry, or when it is an enum whose variants
+all contain the enum itself (or no variants at all).
+"#,
+                name)
+            }
+            SampleTooLarge(name, size) => {
+                format!(r#"
+A sample value was recorded for the container {} during serialization tracing, but it was not stored
+because its estimated size ({} bytes) exceeds `TracerConfig::max_sample_bytes`, and `trace_type`
+needs it.
+
+To fix this, raise the limit, or record a smaller sample for this container with `Samples::record`.
+"#,
+                name, size)
+            }
+            InvalidBaseVariantHint(name, index) => {
+                format!(r#"
+A base variant was given for the enum {} with `TracerConfig::base_variant_hint`, but the index {}
+does not designate one of its variants.
+
+To fix this, use the index of a variant of the enum, in the order of declaration.
+"#,
+                name, index)
+            }
+            MissingContainer(name, other) => {
+                format!(r#"
+A registry was frozen with `Freeze::freeze`, but the formats of the container {} refer to the
+container {}, which is not part of the registry.
+
+To fix this, trace the missing container as well, or merge the registries that define it.
+"#,
+                name, other)
+            }
+            FormatTooDeep(name, max_depth) => {
+                format!(r#"
+A registry was frozen with `Freeze::freeze`, but the formats of the container {} are nested more than
+{} levels deep, e.g. `Option<Vec<...>>`.
+
+To fix this, use `FrozenRegistry::new` with a larger maximum depth.
+"#,
+                name, max_depth)
+            }
+            TypeTracing(_, error) => error.explanation(),
         }
     }
 }
@@ -21,8 +21,10 @@ use serde::{
 use std::{
     cell::{Ref, RefCell, RefMut},
     collections::{btree_map::Entry, BTreeMap},
+    fmt,
     ops::DerefMut,
     rc::Rc,
+    str::FromStr,
 };
 
 /// Serde-based serialization format for anonymous "value" types.
@@ -93,6 +95,42 @@ pub enum ContainerFormat {
     Enum(BTreeMap<u32, Named<VariantFormat>>),
 }
 
+/// Standard types that are serialized as containers of a known name and shape.
+#[derive(Serialize, Deserialize, Debug, Eq, Clone, Copy, PartialEq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WellKnownType {
+    /// `std::time::Duration`, serialized as `struct Duration { secs: u64, nanos: u32 }`.
+    Duration,
+}
+
+impl WellKnownType {
+    /// Recognize the standard type serialized as the container `name` with the given format.
+    pub fn recognize(name: &str, format: &ContainerFormat) -> Option<Self> {
+        match (name, format) {
+            ("Duration", ContainerFormat::Struct(fields))
+                if fields.len() == 2
+                    && fields[0].name == "secs"
+                    && fields[0].value == Format::U64
+                    && fields[1].name == "nanos"
+                    && fields[1].value == Format::U32 =>
+            {
+                Some(Self::Duration)
+            }
+            _ => None,
+        }
+    }
+
+    /// Recognize the standard types among the given (normalized) containers.
+    pub fn recognize_all(
+        registry: &BTreeMap<String, ContainerFormat>,
+    ) -> BTreeMap<String, WellKnownType> {
+        registry
+            .iter()
+            .filter_map(|(name, format)| Some((name.clone(), Self::recognize(name, format)?)))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq)]
 /// A named value.
 /// Used for named parameters or variants.
@@ -148,28 +186,11 @@ pub trait FormatHolder {
     /// if any variable has an unknown value.
     fn normalize(&mut self) -> Result<()> {
         self.visit_mut(&mut |format: &mut Format| {
-            let normalized = match format {
-                Format::Tuple(formats) => {
-                    let size = formats.len();
-                    if size <= 1 {
-                        return Ok(());
-                    }
-                    let format0 = &formats[0];
-                    for format in formats.iter().skip(1) {
-                        if format != format0 {
-                            return Ok(());
-                        }
-                    }
-                    Format::TupleArray {
-                        content: Box::new(std::mem::take(&mut formats[0])),
-                        size,
-                    }
-                }
-                _ => {
-                    return Ok(());
+            if let Format::Tuple(formats) = format {
+                if let Some(normalized) = tuple_array(formats) {
+                    *format = normalized;
                 }
-            };
-            *format = normalized;
+            }
             Ok(())
         })
     }
@@ -184,6 +205,18 @@ pub trait FormatHolder {
     fn is_unknown(&self) -> bool;
 }
 
+/// Compress a tuple of at least two identical formats into a `TupleArray`.
+fn tuple_array(formats: &mut [Format]) -> Option<Format> {
+    let size = formats.len();
+    if size <= 1 || formats.iter().skip(1).any(|format| *format != formats[0]) {
+        return None;
+    }
+    Some(Format::TupleArray {
+        content: Box::new(std::mem::take(&mut formats[0])),
+        size,
+    })
+}
+
 fn unification_error<T1, T2>(v1: T1, v2: T2) -> Error
 where
     T1: std::fmt::Debug,
@@ -712,6 +745,50 @@ impl Format {
     pub fn unknown() -> Self {
         Self::Variable(Variable::new(None))
     }
+
+    /// Return the canonical form of this format, as found in the registries of the tracer:
+    /// known variables are replaced by their values and tuples of identical formats are
+    /// compressed into `TupleArray`s. Unlike `FormatHolder::normalize`, variables with unknown
+    /// values are kept as they are.
+    pub fn normalized(self) -> Format {
+        match self {
+            Self::Variable(variable) => {
+                if variable.borrow().is_none() {
+                    return Self::Variable(variable);
+                }
+                variable
+                    .into_inner()
+                    .expect("variable is known")
+                    .normalized()
+            }
+            Self::Option(format) => Self::Option(Box::new(format.normalized())),
+            Self::Seq(format) => Self::Seq(Box::new(format.normalized())),
+            Self::Map { key, value } => Self::Map {
+                key: Box::new(key.normalized()),
+                value: Box::new(value.normalized()),
+            },
+            Self::Tuple(formats) => {
+                let mut formats: Vec<_> = formats.into_iter().map(Format::normalized).collect();
+                tuple_array(&mut formats).unwrap_or(Self::Tuple(formats))
+            }
+            Self::TupleArray { content, size } => Self::TupleArray {
+                content: Box::new(content.normalized()),
+                size,
+            },
+            format => format,
+        }
+    }
+}
+
+impl ContainerFormat {
+    /// Return the canonical form of this container, as found in the registries of the tracer
+    /// (see `Format::normalized`), so that hand-built and traced registries may be compared.
+    /// Fails with `Error::UnknownFormat` if a variable has an unknown value. In this case,
+    /// `Tracer::registry` reports `Error::UnknownFormatInContainer` with the container name.
+    pub fn normalized(mut self) -> Result<ContainerFormat> {
+        self.normalize()?;
+        Ok(self)
+    }
 }
 
 impl VariantFormat {
@@ -733,6 +810,342 @@ impl Default for VariantFormat {
     }
 }
 
+// Compact textual syntax of formats, close to Rust: `[Option<Foo>]` for a sequence of optional
+// `Foo`, `Map<Str, U32>`, `(U8, Bool)` for tuples and `[U16; 3]` for tuple arrays. Type names
+// that are not identifiers or that collide with a keyword of the syntax are written
+// `TypeName(Str)`. Unknown formats are written `Unknown`.
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Format::*;
+        match self {
+            Variable(variable) => match variable.borrow().as_ref() {
+                Some(format) => format.fmt(f),
+                None => write!(f, "Unknown"),
+            },
+            TypeName(name) => {
+                if is_plain_type_name(name) {
+                    write!(f, "{}", name)
+                } else {
+                    write!(f, "TypeName({})", name)
+                }
+            }
+            Option(format) => write!(f, "Option<{}>", format),
+            Seq(format) => write!(f, "[{}]", format),
+            Map { key, value } => write!(f, "Map<{}, {}>", key, value),
+            Tuple(formats) => write_tuple(f, formats),
+            TupleArray { content, size } => write!(f, "[{}; {}]", content, size),
+            primitive => write!(f, "{:?}", primitive),
+        }
+    }
+}
+
+// Variants are written like the payload of a Rust variant: `Unit`, `(U32)`, `(U8, Bool)` or
+// `{ name: Str, age: U32 }`.
+impl fmt::Display for VariantFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use VariantFormat::*;
+        match self {
+            Variable(variable) => match variable.borrow().as_ref() {
+                Some(format) => format.fmt(f),
+                None => write!(f, "Unknown"),
+            },
+            Unit => write!(f, "Unit"),
+            NewType(format) => write!(f, "({})", format),
+            Tuple(formats) => write_tuple(f, formats),
+            Struct(fields) => write_fields(f, fields),
+        }
+    }
+}
+
+// Containers are written like the body of a Rust definition: `Unit`, `(U32)`, `(U8, Bool)` or
+// `{ name: Str, age: U32 }` for structs, and `A | B(U32) | C { x: U32 }` for enums. Enums
+// without variants are written `!`. Well-known types are written as their serialized format.
+impl fmt::Display for ContainerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ContainerFormat::*;
+        match self {
+            UnitStruct => write!(f, "Unit"),
+            NewTypeStruct(format) => write!(f, "({})", format),
+            TupleStruct(formats) => write_tuple(f, formats),
+            Struct(fields) => write_fields(f, fields),
+            Enum(variants) => {
+                if variants.is_empty() {
+                    return write!(f, "!");
+                }
+                for (index, variant) in variants.values().enumerate() {
+                    if index > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", variant.name)?;
+                    match &variant.value {
+                        VariantFormat::Unit => (),
+                        VariantFormat::Struct(_) => write!(f, " {}", variant.value)?,
+                        value => write!(f, "{}", value)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Tuples of one element are written `(U8,)` as in Rust.
+fn write_tuple<T: fmt::Display>(f: &mut fmt::Formatter<'_>, formats: &[T]) -> fmt::Result {
+    write!(f, "(")?;
+    for (index, format) in formats.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", format)?;
+    }
+    if formats.len() == 1 {
+        write!(f, ",")?;
+    }
+    write!(f, ")")
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[Named<Format>]) -> fmt::Result {
+    if fields.is_empty() {
+        return write!(f, "{{}}");
+    }
+    write!(f, "{{ ")?;
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}: {}", field.name, field.value)?;
+    }
+    write!(f, " }}")
+}
+
+/// Whether a type name can be written without the `TypeName(..)` escape.
+fn is_plain_type_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && FormatParser::keyword(name).is_none()
+}
+
+impl Format {
+    /// Parse the textual syntax of `Display`, e.g. `[Option<Foo>]` or `Map<Str, (U8, U64)>`.
+    /// This is the same as `str::parse`.
+    pub fn parse(input: &str) -> Result<Format> {
+        input.parse()
+    }
+}
+
+// Parse the syntax of `Display`. Keywords are not case-sensitive (e.g. `u32` or `U32`) and
+// whitespace is ignored between tokens. Other identifiers are type names. The syntax of
+// earlier versions (`Seq<U8>`, `Tuple<U8, Bool>`, `TupleArray<U16, 3>`) is still accepted.
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut parser = FormatParser { input, position: 0 };
+        let format = parser.parse_format()?;
+        parser.skip_whitespace();
+        if parser.position < input.len() {
+            return Err(parser.error("end of input"));
+        }
+        Ok(format)
+    }
+}
+
+struct FormatParser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+/// Keywords of the textual syntax, including the names of primitive formats.
+#[derive(Clone, Copy)]
+enum Keyword {
+    TypeName,
+    Option,
+    Seq,
+    Map,
+    Tuple,
+    TupleArray,
+    Unknown,
+    Primitive(fn() -> Format),
+}
+
+impl<'a> FormatParser<'a> {
+    fn keyword(identifier: &str) -> Option<Keyword> {
+        let keyword = match identifier.to_ascii_lowercase().as_str() {
+            "typename" => Keyword::TypeName,
+            "option" => Keyword::Option,
+            "seq" => Keyword::Seq,
+            "map" => Keyword::Map,
+            "tuple" => Keyword::Tuple,
+            "tuplearray" => Keyword::TupleArray,
+            "unknown" => Keyword::Unknown,
+            "unit" => Keyword::Primitive(|| Format::Unit),
+            "bool" => Keyword::Primitive(|| Format::Bool),
+            "i8" => Keyword::Primitive(|| Format::I8),
+            "i16" => Keyword::Primitive(|| Format::I16),
+            "i32" => Keyword::Primitive(|| Format::I32),
+            "i64" => Keyword::Primitive(|| Format::I64),
+            "i128" => Keyword::Primitive(|| Format::I128),
+            "u8" => Keyword::Primitive(|| Format::U8),
+            "u16" => Keyword::Primitive(|| Format::U16),
+            "u32" => Keyword::Primitive(|| Format::U32),
+            "u64" => Keyword::Primitive(|| Format::U64),
+            "u128" => Keyword::Primitive(|| Format::U128),
+            "f32" => Keyword::Primitive(|| Format::F32),
+            "f64" => Keyword::Primitive(|| Format::F64),
+            "char" => Keyword::Primitive(|| Format::Char),
+            "str" => Keyword::Primitive(|| Format::Str),
+            "bytes" => Keyword::Primitive(|| Format::Bytes),
+            _ => return None,
+        };
+        Some(keyword)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        Error::ParseFormat(format!(
+            "expected {} at position {} in {:?}",
+            expected, self.position, self.input
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, token: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", token)))
+        }
+    }
+
+    fn consume(&mut self, token: char) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.position += token.len_utf8();
+        }
+        found
+    }
+
+    fn parse_identifier(&mut self) -> Result<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("a format"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn parse_size(&mut self) -> Result<usize> {
+        self.skip_whitespace();
+        self.parse_identifier()?
+            .parse()
+            .map_err(|_| self.error("an array size"))
+    }
+
+    fn parse_format(&mut self) -> Result<Format> {
+        if self.consume('[') {
+            let content = Box::new(self.parse_format()?);
+            if self.consume(';') {
+                let size = self.parse_size()?;
+                self.expect(']')?;
+                return Ok(Format::TupleArray { content, size });
+            }
+            self.expect(']')?;
+            return Ok(Format::Seq(content));
+        }
+        if self.consume('(') {
+            let mut formats = Vec::new();
+            while !self.consume(')') {
+                formats.push(self.parse_format()?);
+                if !self.consume(',') {
+                    self.expect(')')?;
+                    break;
+                }
+            }
+            return Ok(Format::Tuple(formats));
+        }
+        let start = self.position;
+        let identifier = self.parse_identifier()?;
+        let keyword = match Self::keyword(identifier) {
+            Some(keyword) => keyword,
+            None if identifier.starts_with(|c: char| c.is_ascii_digit()) => {
+                self.position = start;
+                return Err(self.error("a format"));
+            }
+            None => return Ok(Format::TypeName(identifier.to_string())),
+        };
+        let format = match keyword {
+            Keyword::TypeName => {
+                self.expect('(')?;
+                self.skip_whitespace();
+                let rest = self.rest();
+                let length = rest.find(')').ok_or_else(|| self.error("')'"))?;
+                let name = rest[..length].trim();
+                if name.is_empty() {
+                    return Err(self.error("a type name"));
+                }
+                self.position += length + 1;
+                Format::TypeName(name.to_string())
+            }
+            Keyword::Option => Format::Option(Box::new(self.parse_single_parameter()?)),
+            Keyword::Seq => Format::Seq(Box::new(self.parse_single_parameter()?)),
+            Keyword::Map => {
+                self.expect('<')?;
+                let key = Box::new(self.parse_format()?);
+                self.expect(',')?;
+                let value = Box::new(self.parse_format()?);
+                self.expect('>')?;
+                Format::Map { key, value }
+            }
+            Keyword::Tuple => {
+                self.expect('<')?;
+                let mut formats = Vec::new();
+                if !self.consume('>') {
+                    loop {
+                        formats.push(self.parse_format()?);
+                        if self.consume('>') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Format::Tuple(formats)
+            }
+            Keyword::TupleArray => {
+                self.expect('<')?;
+                let content = Box::new(self.parse_format()?);
+                self.expect(',')?;
+                let size = self.parse_size()?;
+                self.expect('>')?;
+                Format::TupleArray { content, size }
+            }
+            Keyword::Unknown => Format::unknown(),
+            Keyword::Primitive(primitive) => primitive(),
+        };
+        Ok(format)
+    }
+
+    fn parse_single_parameter(&mut self) -> Result<Format> {
+        self.expect('<')?;
+        let format = self.parse_format()?;
+        self.expect('>')?;
+        Ok(format)
+    }
+}
+
 // For better rendering in human readable formats, we wish to serialize
 // `Named { key: x, value: y }` as a map `{ x: y }`.
 impl<T> Serialize for Named<T>
@@ -0,0 +1,366 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Immutable registries that can be shared between threads.
+
+use crate::{ContainerFormat, Error, Format, Named, Registry, Result, VariantFormat};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// A validated, immutable snapshot of a `Registry`, obtained with `Freeze::freeze`.
+///
+/// Unlike `Registry`, whose formats may hold variables, a frozen registry is `Send` and `Sync`,
+/// so that a single `Arc<FrozenRegistry>` may be used by several threads, e.g. to generate code
+/// in several languages concurrently. References to containers are resolved into indices,
+/// following the order of the names of the containers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrozenRegistry {
+    /// Names of the containers, sorted.
+    names: Vec<String>,
+    /// Formats of the containers, in the order of `names`.
+    containers: Vec<FrozenContainerFormat>,
+}
+
+/// Format of a value in a `FrozenRegistry` (see `Format`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrozenFormat {
+    /// The index of a container in the registry.
+    TypeName(usize),
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    Option(Box<FrozenFormat>),
+    Seq(Box<FrozenFormat>),
+    Map {
+        key: Box<FrozenFormat>,
+        value: Box<FrozenFormat>,
+    },
+    Tuple(Vec<FrozenFormat>),
+    TupleArray {
+        content: Box<FrozenFormat>,
+        size: usize,
+    },
+}
+
+/// Format of a container in a `FrozenRegistry` (see `ContainerFormat`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrozenContainerFormat {
+    UnitStruct,
+    NewTypeStruct(Box<FrozenFormat>),
+    TupleStruct(Vec<FrozenFormat>),
+    Struct(Vec<Named<FrozenFormat>>),
+    Enum(BTreeMap<u32, Named<FrozenVariantFormat>>),
+}
+
+/// Format of a variant in a `FrozenRegistry` (see `VariantFormat`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrozenVariantFormat {
+    Unit,
+    NewType(Box<FrozenFormat>),
+    Tuple(Vec<FrozenFormat>),
+    Struct(Vec<Named<FrozenFormat>>),
+}
+
+/// Conversion of registries into frozen registries.
+pub trait Freeze {
+    /// Validate the registry and take an immutable snapshot of it, with formats nested at most
+    /// `FrozenRegistry::DEFAULT_MAX_DEPTH` levels deep.
+    fn freeze(&self) -> Result<Arc<FrozenRegistry>>;
+}
+
+impl Freeze for Registry {
+    fn freeze(&self) -> Result<Arc<FrozenRegistry>> {
+        FrozenRegistry::new(self, FrozenRegistry::DEFAULT_MAX_DEPTH).map(Arc::new)
+    }
+}
+
+impl FrozenRegistry {
+    /// Default maximum number of nested formats in a container, e.g. 3 for `Option<Vec<u8>>`.
+    pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+    /// Validate `registry` and take an immutable snapshot of it. Fails if a format refers to a
+    /// missing container, if a format is still unknown, or if formats are nested more than
+    /// `max_depth` levels deep in a container.
+    pub fn new(registry: &Registry, max_depth: usize) -> Result<Self> {
+        let names: Vec<String> = registry.keys().cloned().collect();
+        let freezer = Freezer {
+            names: &names,
+            max_depth,
+        };
+        let containers = registry
+            .iter()
+            .map(|(name, format)| freezer.freeze_container(name, format))
+            .collect::<Result<_>>()?;
+        Ok(Self { names, containers })
+    }
+
+    /// Number of containers.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether the registry has no containers.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Index of the container `name`, if any.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names
+            .binary_search_by(|probe| probe.as_str().cmp(name))
+            .ok()
+    }
+
+    /// Name of the container at `index`. Panics if `index` is out of range.
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    /// Format of the container at `index`. Panics if `index` is out of range.
+    pub fn container(&self, index: usize) -> &FrozenContainerFormat {
+        &self.containers[index]
+    }
+
+    /// Format of the container `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&FrozenContainerFormat> {
+        self.index_of(name).map(|index| &self.containers[index])
+    }
+
+    /// Iterate over the names and formats of the containers, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FrozenContainerFormat)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.containers.iter())
+    }
+
+    /// Rebuild the equivalent `Registry`, e.g. for a code generator running in the current
+    /// thread.
+    pub fn to_registry(&self) -> Registry {
+        self.iter()
+            .map(|(name, format)| (name.to_string(), self.thaw_container(format)))
+            .collect()
+    }
+
+    fn thaw_container(&self, format: &FrozenContainerFormat) -> ContainerFormat {
+        use FrozenContainerFormat::*;
+        match format {
+            UnitStruct => ContainerFormat::UnitStruct,
+            NewTypeStruct(format) => ContainerFormat::NewTypeStruct(Box::new(self.thaw(format))),
+            TupleStruct(formats) => ContainerFormat::TupleStruct(self.thaw_all(formats)),
+            Struct(fields) => ContainerFormat::Struct(self.thaw_fields(fields)),
+            Enum(variants) => ContainerFormat::Enum(
+                variants
+                    .iter()
+                    .map(|(index, variant)| {
+                        let value = match &variant.value {
+                            FrozenVariantFormat::Unit => VariantFormat::Unit,
+                            FrozenVariantFormat::NewType(format) => {
+                                VariantFormat::NewType(Box::new(self.thaw(format)))
+                            }
+                            FrozenVariantFormat::Tuple(formats) => {
+                                VariantFormat::Tuple(self.thaw_all(formats))
+                            }
+                            FrozenVariantFormat::Struct(fields) => {
+                                VariantFormat::Struct(self.thaw_fields(fields))
+                            }
+                        };
+                        let name = variant.name.clone();
+                        (*index, Named { name, value })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn thaw_fields(&self, fields: &[Named<FrozenFormat>]) -> Vec<Named<Format>> {
+        fields
+            .iter()
+            .map(|field| Named {
+                name: field.name.clone(),
+                value: self.thaw(&field.value),
+            })
+            .collect()
+    }
+
+    fn thaw_all(&self, formats: &[FrozenFormat]) -> Vec<Format> {
+        formats.iter().map(|format| self.thaw(format)).collect()
+    }
+
+    fn thaw(&self, format: &FrozenFormat) -> Format {
+        use FrozenFormat::*;
+        match format {
+            TypeName(index) => Format::TypeName(self.names[*index].clone()),
+            Unit => Format::Unit,
+            Bool => Format::Bool,
+            I8 => Format::I8,
+            I16 => Format::I16,
+            I32 => Format::I32,
+            I64 => Format::I64,
+            I128 => Format::I128,
+            U8 => Format::U8,
+            U16 => Format::U16,
+            U32 => Format::U32,
+            U64 => Format::U64,
+            U128 => Format::U128,
+            F32 => Format::F32,
+            F64 => Format::F64,
+            Char => Format::Char,
+            Str => Format::Str,
+            Bytes => Format::Bytes,
+            Option(format) => Format::Option(Box::new(self.thaw(format))),
+            Seq(format) => Format::Seq(Box::new(self.thaw(format))),
+            Map { key, value } => Format::Map {
+                key: Box::new(self.thaw(key)),
+                value: Box::new(self.thaw(value)),
+            },
+            Tuple(formats) => Format::Tuple(self.thaw_all(formats)),
+            TupleArray { content, size } => Format::TupleArray {
+                content: Box::new(self.thaw(content)),
+                size: *size,
+            },
+        }
+    }
+}
+
+/// Conversion of the formats of a registry.
+struct Freezer<'a> {
+    /// Sorted names of the containers.
+    names: &'a [String],
+    max_depth: usize,
+}
+
+impl Freezer<'_> {
+    fn freeze_container(
+        &self,
+        name: &str,
+        format: &ContainerFormat,
+    ) -> Result<FrozenContainerFormat> {
+        use ContainerFormat::*;
+        Ok(match format {
+            UnitStruct => FrozenContainerFormat::UnitStruct,
+            NewTypeStruct(format) => {
+                FrozenContainerFormat::NewTypeStruct(Box::new(self.freeze(name, format, 1)?))
+            }
+            TupleStruct(formats) => {
+                FrozenContainerFormat::TupleStruct(self.freeze_all(name, formats, 1)?)
+            }
+            Struct(fields) => FrozenContainerFormat::Struct(self.freeze_fields(name, fields)?),
+            Enum(variants) => FrozenContainerFormat::Enum(
+                variants
+                    .iter()
+                    .map(|(index, variant)| {
+                        let value = self.freeze_variant(name, &variant.value)?;
+                        let name = variant.name.clone();
+                        Ok((*index, Named { name, value }))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    fn freeze_variant(&self, name: &str, format: &VariantFormat) -> Result<FrozenVariantFormat> {
+        use VariantFormat::*;
+        Ok(match format {
+            Variable(variable) => match variable.borrow().as_ref() {
+                Some(format) => return self.freeze_variant(name, format),
+                None => return Err(Error::UnknownFormatInContainer(name.to_string())),
+            },
+            Unit => FrozenVariantFormat::Unit,
+            NewType(format) => {
+                FrozenVariantFormat::NewType(Box::new(self.freeze(name, format, 1)?))
+            }
+            Tuple(formats) => FrozenVariantFormat::Tuple(self.freeze_all(name, formats, 1)?),
+            Struct(fields) => FrozenVariantFormat::Struct(self.freeze_fields(name, fields)?),
+        })
+    }
+
+    fn freeze_fields(
+        &self,
+        name: &str,
+        fields: &[Named<Format>],
+    ) -> Result<Vec<Named<FrozenFormat>>> {
+        fields
+            .iter()
+            .map(|field| {
+                Ok(Named {
+                    name: field.name.clone(),
+                    value: self.freeze(name, &field.value, 1)?,
+                })
+            })
+            .collect()
+    }
+
+    fn freeze_all(
+        &self,
+        name: &str,
+        formats: &[Format],
+        depth: usize,
+    ) -> Result<Vec<FrozenFormat>> {
+        formats
+            .iter()
+            .map(|format| self.freeze(name, format, depth))
+            .collect()
+    }
+
+    /// Stop before exceeding the maximum depth so that deeply nested formats cannot overflow
+    /// the stack.
+    fn freeze(&self, name: &str, format: &Format, depth: usize) -> Result<FrozenFormat> {
+        if depth > self.max_depth {
+            return Err(Error::FormatTooDeep(name.to_string(), self.max_depth));
+        }
+        let depth = depth + 1;
+        use Format::*;
+        Ok(match format {
+            Variable(variable) => match variable.borrow().as_ref() {
+                Some(format) => return self.freeze(name, format, depth - 1),
+                None => return Err(Error::UnknownFormatInContainer(name.to_string())),
+            },
+            TypeName(other) => match self.names.binary_search(other) {
+                Ok(index) => FrozenFormat::TypeName(index),
+                Err(_) => return Err(Error::MissingContainer(name.to_string(), other.clone())),
+            },
+            Unit => FrozenFormat::Unit,
+            Bool => FrozenFormat::Bool,
+            I8 => FrozenFormat::I8,
+            I16 => FrozenFormat::I16,
+            I32 => FrozenFormat::I32,
+            I64 => FrozenFormat::I64,
+            I128 => FrozenFormat::I128,
+            U8 => FrozenFormat::U8,
+            U16 => FrozenFormat::U16,
+            U32 => FrozenFormat::U32,
+            U64 => FrozenFormat::U64,
+            U128 => FrozenFormat::U128,
+            F32 => FrozenFormat::F32,
+            F64 => FrozenFormat::F64,
+            Char => FrozenFormat::Char,
+            Str => FrozenFormat::Str,
+            Bytes => FrozenFormat::Bytes,
+            Option(format) => FrozenFormat::Option(Box::new(self.freeze(name, format, depth)?)),
+            Seq(format) => FrozenFormat::Seq(Box::new(self.freeze(name, format, depth)?)),
+            Map { key, value } => FrozenFormat::Map {
+                key: Box::new(self.freeze(name, key, depth)?),
+                value: Box::new(self.freeze(name, value, depth)?),
+            },
+            Tuple(formats) => FrozenFormat::Tuple(self.freeze_all(name, formats, depth)?),
+            TupleArray { content, size } => FrozenFormat::TupleArray {
+                content: Box::new(self.freeze(name, content, depth)?),
+                size: *size,
+            },
+        })
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Stable fingerprints of registries.
+
+use crate::{ContainerFormat, Format, Registry, VariantFormat};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Compute a SHA-256 fingerprint of the structure of `registry`.
+///
+/// The hash is structural: it describes the containers of the registry and the references
+/// between them, not the bytes on the wire. Names of containers, fields and variants are
+/// ignored, as well as the kind of structs (unit, newtype, tuple or named fields), the
+/// representation of tuples (see `Format::normalized`), and the way containers are shared
+/// or duplicated. Two registries that differ only in these respects are BCS-compatible and
+/// produce the same hash, whereas any change of the encoding of a container changes it.
+///
+/// The converse does not hold: BCS-compatible registries may have different hashes. Notably,
+/// a reference to a container (`Format::TypeName`) is never replaced by the formats of its
+/// content, so a field of type `struct Inner { a: u32 }` and a field of type `u32` are hashed
+/// differently, although both are serialized as a `u32`.
+///
+/// Every container of `registry` is part of the hash, whether or not other containers refer to
+/// it. Adding, removing or changing a container changes the hash even if the payloads of
+/// interest never contain it: to fingerprint some types only, hash a registry restricted to
+/// the containers that they use.
+///
+/// References to containers missing from `registry` are hashed by name, and unknown formats
+/// are hashed as such.
+pub fn schema_hash(registry: &Registry) -> [u8; 32] {
+    // Group containers by wire format, starting from a single group and refining groups
+    // according to the groups of the containers they refer to, until this is stable.
+    let mut groups: BTreeMap<&str, u32> = registry.keys().map(|name| (name.as_str(), 0)).collect();
+    let mut count = std::cmp::min(registry.len(), 1);
+    loop {
+        let signatures: BTreeMap<&str, Vec<u8>> = registry
+            .iter()
+            .map(|(name, format)| {
+                let mut signature = Vec::new();
+                encode_container(format, &groups, &mut signature);
+                (name.as_str(), signature)
+            })
+            .collect();
+        let distinct: BTreeSet<&Vec<u8>> = signatures.values().collect();
+        if distinct.len() == count {
+            let mut hasher = Sha256::new();
+            hasher.update(b"serde-reflection schema v1");
+            hasher.update((count as u64).to_le_bytes());
+            for signature in distinct {
+                hasher.update((signature.len() as u64).to_le_bytes());
+                hasher.update(signature);
+            }
+            return hasher.finalize().into();
+        }
+        count = distinct.len();
+        let ids: BTreeMap<&Vec<u8>, u32> = distinct.into_iter().zip(0..).collect();
+        groups = signatures
+            .iter()
+            .map(|(name, signature)| (*name, ids[signature]))
+            .collect();
+    }
+}
+
+// The tags below are part of the hash and must never change.
+
+fn encode_container(format: &ContainerFormat, groups: &BTreeMap<&str, u32>, out: &mut Vec<u8>) {
+    use ContainerFormat::*;
+    match format {
+        UnitStruct => encode_product(&[], groups, out),
+        NewTypeStruct(format) => encode_product(&[format], groups, out),
+        TupleStruct(formats) => encode_product(&formats.iter().collect::<Vec<_>>(), groups, out),
+        Struct(named_formats) => {
+            let formats: Vec<_> = named_formats.iter().map(|named| &named.value).collect();
+            encode_product(&formats, groups, out)
+        }
+        Enum(variants) => {
+            out.push(1);
+            out.extend((variants.len() as u32).to_le_bytes());
+            for (index, variant) in variants {
+                out.extend(index.to_le_bytes());
+                encode_variant(&variant.value, groups, out);
+            }
+        }
+    }
+}
+
+fn encode_variant(format: &VariantFormat, groups: &BTreeMap<&str, u32>, out: &mut Vec<u8>) {
+    use VariantFormat::*;
+    match format {
+        Variable(variable) => match variable.borrow().as_ref() {
+            Some(format) => encode_variant(format, groups, out),
+            None => out.push(2),
+        },
+        Unit => encode_product(&[], groups, out),
+        NewType(format) => encode_product(&[format], groups, out),
+        Tuple(formats) => encode_product(&formats.iter().collect::<Vec<_>>(), groups, out),
+        Struct(named_formats) => {
+            let formats: Vec<_> = named_formats.iter().map(|named| &named.value).collect();
+            encode_product(&formats, groups, out)
+        }
+    }
+}
+
+fn encode_product(formats: &[&Format], groups: &BTreeMap<&str, u32>, out: &mut Vec<u8>) {
+    out.push(0);
+    out.extend((formats.len() as u32).to_le_bytes());
+    for format in formats {
+        encode_format(&(*format).clone().normalized(), groups, out);
+    }
+}
+
+fn encode_format(format: &Format, groups: &BTreeMap<&str, u32>, out: &mut Vec<u8>) {
+    use Format::*;
+    match format {
+        Variable(variable) => match variable.borrow().as_ref() {
+            Some(format) => encode_format(format, groups, out),
+            None => out.push(0),
+        },
+        TypeName(name) => match groups.get(name.as_str()) {
+            Some(group) => {
+                out.push(1);
+                out.extend(group.to_le_bytes());
+            }
+            None => {
+                out.push(2);
+                out.extend((name.len() as u64).to_le_bytes());
+                out.extend(name.as_bytes());
+            }
+        },
+        Unit => out.push(3),
+        Bool => out.push(4),
+        I8 => out.push(5),
+        I16 => out.push(6),
+        I32 => out.push(7),
+        I64 => out.push(8),
+        I128 => out.push(9),
+        U8 => out.push(10),
+        U16 => out.push(11),
+        U32 => out.push(12),
+        U64 => out.push(13),
+        U128 => out.push(14),
+        F32 => out.push(15),
+        F64 => out.push(16),
+        Char => out.push(17),
+        Str => out.push(18),
+        Bytes => out.push(19),
+        Option(format) => {
+            out.push(20);
+            encode_format(format, groups, out);
+        }
+        Seq(format) => {
+            out.push(21);
+            encode_format(format, groups, out);
+        }
+        Map { key, value } => {
+            out.push(22);
+            encode_format(key, groups, out);
+            encode_format(value, groups, out);
+        }
+        Tuple(formats) => {
+            out.push(23);
+            out.extend((formats.len() as u32).to_le_bytes());
+            for format in formats {
+                encode_format(format, groups, out);
+            }
+        }
+        TupleArray { content, size } => {
+            out.push(24);
+            encode_format(content, groups, out);
+            out.extend((*size as u64).to_le_bytes());
+        }
+    }
+}
@@ -91,6 +91,8 @@
 //! * Mutually recursive types provided that the first variant of each enum is
 //!   recursion-free. (For instance, `enum List { None, Some(Box<List>)}`.) Note that each
 //!   enum must be traced separately with `trace_type` to discover all the variants.
+//!   Otherwise, a recursion-free variant may be designated with
+//!   `TracerConfig::base_variant_hint`.
 //!
 //! ## Unsupported idioms
 //!
@@ -102,11 +104,15 @@
 //!
 //! * Attributes that are not compatible with binary formats (e.g. `#[serde(flatten)]`, `#[serde(tag = ..)]`)
 //!
+//! * Untagged enums (`#[serde(untagged)]`), unless variants are provided manually. (Work around:
+//!   use `TracerConfig::allow_untagged` and `TracerConfig::untagged_variant_hint`.)
+//!
 //! * Tracing type aliases. (E.g. `type Pair = (u32, u64)` will not create an entry "Pair".)
 //!
 //! * Mutually recursive types for which picking the first variant of each enum does not
-//!   terminate. (Work around: re-order the variants. For instance `enum List {
-//!   Some(Box<List>), None}` must be rewritten `enum List { None, Some(Box<List>)}`.)
+//!   terminate. (Work around: re-order the variants, or use `TracerConfig::base_variant_hint`.
+//!   For instance `enum List { Some(Box<List>), None}` must be rewritten
+//!   `enum List { None, Some(Box<List>)}` or traced with `base_variant_hint("List", 1)`.)
 //!
 //! * Certain standard types such as `std::num::NonZeroU8` may not be tracked as a
 //!   container and appear simply as their underlying primitive type (e.g. `u8`) in the
@@ -262,6 +268,9 @@
 //! # }
 //! ```
 //!
+//! To trace many values until they stop revealing new formats, use `trace_value_with_stats`
+//! instead: it also returns a `TraceStats` telling whether the registry or the samples changed.
+//!
 //! This approach works well but it can only recover the formats of datatypes for which
 //! nontrivial samples have been provided:
 //!
@@ -319,15 +328,100 @@
 //! (0) Container names must not collide. If this happens, consider using `#[serde(rename = "name")]`,
 //! or implementing serde traits manually.
 //!
-//! (1) The first variants of mutually recursive enums must be a "base case". That is,
+//! (1) The first variants of mutually recursive enums must be a "base case" (unless another variant
+//! is designated with `TracerConfig::base_variant_hint`). That is,
 //! defaulting to the first variant for every enum type (along with `None` for option values
 //! and `[]` for sequences) must guarantee termination of depth-first traversals of the graph of type
 //! declarations.
 //!
 //! (2) If a type runs custom validation checks during deserialization, sample values must have been provided
-//! previously by calling `trace_value`. Besides, the corresponding registered formats
+//! previously by calling `trace_value` or `Samples::record`. Besides, the corresponding registered formats
 //! must not contain unknown parts.
 //!
+//! ## Custom Serialization with `#[serde(with = "...")]`
+//!
+//! Fields using `#[serde(with = "...")]`, `serialize_with`, or `deserialize_with` are traced
+//! according to their custom representation. For instance, a timestamp serialized as a string
+//! is recorded as `Format::Str`. If the custom deserializer rejects the witness values proposed by
+//! `trace_type` (here, an empty string), wrap the field in a newtype struct and use
+//! `Samples::record` to provide a valid sample under the name of this container:
+//!
+//! ```rust
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_reflection::*;
+//! mod seconds {
+//!     use serde::{de::Error, Deserialize, Deserializer, Serializer};
+//!
+//!     pub fn serialize<S: Serializer>(x: &u64, s: S) -> Result<S::Ok, S::Error> {
+//!         s.serialize_str(&format!("{}s", x))
+//!     }
+//!
+//!     pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+//!         let value = String::deserialize(d)?;
+//!         let seconds = value.strip_suffix('s').and_then(|x| x.parse().ok());
+//!         seconds.ok_or_else(|| D::Error::custom("Invalid timestamp"))
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Timestamp(#[serde(with = "seconds")] u64);
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Event {
+//!     name: String,
+//!     time: Timestamp,
+//! }
+//!
+//! # fn main() -> Result<(), Error> {
+//! let mut tracer = Tracer::new(TracerConfig::default());
+//! let mut samples = Samples::new();
+//! samples.record(&mut tracer, "Timestamp", &Timestamp(0))?;
+//! tracer.trace_type::<Event>(&samples)?;
+//!
+//! let registry = tracer.registry()?;
+//! assert_eq!(
+//!     registry.get("Timestamp").unwrap(),
+//!     &ContainerFormat::NewTypeStruct(Box::new(Format::Str)),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! By default, recorded samples are only used for newtype structs. See
+//! `TracerConfig::record_samples_for_tuple_structs` and `TracerConfig::record_samples_for_structs`
+//! for other containers.
+//!
+//! ## Schema Hashes
+//!
+//! `schema_hash` computes a structural fingerprint of a registry, e.g. to stamp serialized
+//! payloads with a schema version. Names are not part of the hash, so renaming a field or a
+//! container keeps the same fingerprint:
+//!
+//! ```rust
+//! # use serde::Deserialize;
+//! # use serde_reflection::*;
+//! #[derive(Deserialize)]
+//! struct Point { x: u32, y: u32 }
+//!
+//! #[derive(Deserialize)]
+//! struct Position { left: u32, top: u32 }
+//!
+//! # fn main() -> Result<(), Error> {
+//! let mut tracer = Tracer::new(TracerConfig::default());
+//! tracer.trace_simple_type::<Point>()?;
+//! let point = schema_hash(&tracer.registry()?);
+//!
+//! let mut tracer = Tracer::new(TracerConfig::default());
+//! tracer.trace_simple_type::<Position>()?;
+//! assert_eq!(point, schema_hash(&tracer.registry()?));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every container of the registry counts, including the ones that other containers do not
+//! use. References to containers are not inlined, so registries with the same BCS encoding may
+//! still hash differently.
+//!
 //! ## Design Considerations
 //!
 //! Whenever we traverse the graph of type declarations using deserialization callbacks, the type
@@ -357,11 +451,22 @@
 mod de;
 mod error;
 mod format;
+mod frozen;
+mod hash;
 mod ser;
 mod trace;
 mod value;
 
 pub use error::{Error, Result};
-pub use format::{ContainerFormat, Format, FormatHolder, Named, Variable, VariantFormat};
-pub use trace::{Registry, Samples, Tracer, TracerConfig};
-pub use value::Value;
+pub use format::{
+    ContainerFormat, Format, FormatHolder, Named, Variable, VariantFormat, WellKnownType,
+};
+pub use frozen::{
+    Freeze, FrozenContainerFormat, FrozenFormat, FrozenRegistry, FrozenVariantFormat,
+};
+pub use hash::schema_hash;
+pub use trace::{
+    EnumProgress, EnumTraceStatus, Registry, Samples, TraceStats, Tracer, TracerConfig,
+    WellKnownTypes,
+};
+pub use value::{FormattedValue, Value};
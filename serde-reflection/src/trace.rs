@@ -12,11 +12,14 @@ use erased_discriminant::Discriminant;
 use once_cell::sync::Lazy;
 use serde::{de::DeserializeSeed, Deserialize, Serialize};
 use std::any::TypeId;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// A map of container formats.
 pub type Registry = BTreeMap<String, ContainerFormat>;
 
+/// Containers of a registry recognized as standard types, indexed by name.
+pub type WellKnownTypes = BTreeMap<String, WellKnownType>;
+
 /// Structure to drive the tracing of Serde serialization and deserialization.
 /// This typically aims at computing a `Registry`.
 #[derive(Debug)]
@@ -34,16 +37,48 @@ pub struct Tracer {
 
     /// Discriminant associated with each variant of each enum.
     pub(crate) discriminants: BTreeMap<(TypeId, VariantId<'static>), Discriminant>,
+
+    /// Number of calls to `deserialize_any` so far in the current deserialization run.
+    pub(crate) untagged_occurrences: usize,
+
+    /// Variant currently forced for each hinted occurrence of `deserialize_any`.
+    pub(crate) untagged_progress: BTreeMap<usize, usize>,
+
+    /// Enums recorded from untagged variant hints.
+    pub(crate) untagged_enums: BTreeSet<String>,
+
+    /// Names of the variants of each enum traced during deserialization, as declared in Rust.
+    pub(crate) enum_variant_names: BTreeMap<String, &'static [&'static str]>,
+
+    /// Name of the variant of each enum explored last while tracing deserialization.
+    pub(crate) last_explored_variants: BTreeMap<String, String>,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub(crate) enum EnumProgress {
+/// Phase of the tracing of an incomplete enum. Variants are first explored by name, then by
+/// index, until the index of each name is known.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EnumProgress {
     /// There are variant names that have not yet been traced.
     NamedVariantsRemaining,
     /// There are variant numbers that have not yet been traced.
     IndexedVariantsRemaining,
 }
 
+/// Tracing status of an enum that is still missing variants (see `Tracer::pending_enums`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumTraceStatus {
+    /// Current phase of the tracing.
+    pub progress: EnumProgress,
+    /// Number of variants whose index is known.
+    pub known_variants: usize,
+    /// Number of variants traced by name that still await their index.
+    pub provisional_variants: usize,
+    /// Names of the variants whose index is not known yet, in declaration order.
+    pub missing_variants: Vec<String>,
+    /// Name of the variant explored last, if any.
+    pub last_variant: Option<String>,
+}
+
 #[derive(Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub(crate) enum VariantId<'a> {
     Index(u32),
@@ -55,6 +90,10 @@ pub(crate) enum VariantId<'a> {
 #[derive(Debug, Default)]
 pub struct Samples {
     pub(crate) values: BTreeMap<&'static str, Value>,
+    /// Estimated sizes of the samples that were too large to be recorded.
+    pub(crate) skipped: BTreeMap<&'static str, usize>,
+    /// Number of times that a sample was added or replaced by a different value.
+    pub(crate) changes: usize,
 }
 
 impl Samples {
@@ -67,6 +106,63 @@ impl Samples {
     pub fn value(&self, name: &'static str) -> Option<&Value> {
         self.values.get(name)
     }
+
+    /// Trace the serialization of `value`, a container with the given Serde name, and
+    /// record its serialized content as the sample for this container.
+    /// * This is useful for containers whose (de)serialization is customized, e.g. with
+    ///   `#[serde(with = "...")]`, and which only deserialize from valid data.
+    /// * Recorded samples are used by `trace_type` for newtype structs and, if enabled in
+    ///   `TracerConfig`, for tuple structs and structs.
+    /// * Returns an error if `value` does not serialize as a container called `name`.
+    pub fn record<T>(&mut self, tracer: &mut Tracer, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (mut format, sample) = tracer.trace_value(self, value)?;
+        format.unify(Format::TypeName(name.into()))?;
+        self.insert(name, &sample, tracer.config.max_sample_bytes);
+        Ok(())
+    }
+
+    /// Record `value` as the sample for the container `name`, unless its estimated size
+    /// exceeds `max_sample_bytes`. A previous sample is then kept.
+    pub(crate) fn insert(
+        &mut self,
+        name: &'static str,
+        value: &Value,
+        max_sample_bytes: Option<usize>,
+    ) {
+        if let Some(max_sample_bytes) = max_sample_bytes {
+            let size = value.estimated_size();
+            if size > max_sample_bytes {
+                if !self.values.contains_key(name) {
+                    self.skipped.insert(name, size);
+                }
+                return;
+            }
+        }
+        self.skipped.remove(name);
+        if self.values.get(name) != Some(value) {
+            self.values.insert(name, value.clone());
+            self.changes += 1;
+        }
+    }
+}
+
+/// What a call to `Tracer::trace_value_with_stats` changed in the tracing state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceStats {
+    /// Whether containers or variants were added to the registry, or known formats refined.
+    pub registry_changed: bool,
+    /// Whether samples were added or replaced by different values.
+    pub samples_changed: bool,
+}
+
+impl TraceStats {
+    /// Whether the traced value covered anything new.
+    pub fn is_new(&self) -> bool {
+        self.registry_changed || self.samples_changed
+    }
 }
 
 /// Configuration object to create a tracer.
@@ -76,6 +172,8 @@ pub struct TracerConfig {
     pub(crate) record_samples_for_newtype_structs: bool,
     pub(crate) record_samples_for_tuple_structs: bool,
     pub(crate) record_samples_for_structs: bool,
+    pub(crate) trace_all_variants: bool,
+    pub(crate) recognize_standard_types: bool,
     pub(crate) default_bool_value: bool,
     pub(crate) default_u8_value: u8,
     pub(crate) default_u16_value: u16,
@@ -94,6 +192,10 @@ pub struct TracerConfig {
     pub(crate) default_string_value: String,
     pub(crate) default_borrowed_bytes_value: &'static [u8],
     pub(crate) default_byte_buf_value: Vec<u8>,
+    pub(crate) allow_untagged: bool,
+    pub(crate) untagged_variant_hints: BTreeMap<usize, (String, Vec<Named<VariantFormat>>)>,
+    pub(crate) base_variant_hints: BTreeMap<String, u32>,
+    pub(crate) max_sample_bytes: Option<usize>,
 }
 
 impl Default for TracerConfig {
@@ -104,6 +206,8 @@ impl Default for TracerConfig {
             record_samples_for_newtype_structs: true,
             record_samples_for_tuple_structs: false,
             record_samples_for_structs: false,
+            trace_all_variants: true,
+            recognize_standard_types: false,
             default_bool_value: false,
             default_u8_value: 0,
             default_u16_value: 0,
@@ -122,6 +226,10 @@ impl Default for TracerConfig {
             default_string_value: String::new(),
             default_borrowed_bytes_value: b"",
             default_byte_buf_value: Vec::new(),
+            allow_untagged: false,
+            untagged_variant_hints: BTreeMap::new(),
+            base_variant_hints: BTreeMap::new(),
+            max_sample_bytes: None,
         }
     }
 }
@@ -162,6 +270,72 @@ impl TracerConfig {
         self
     }
 
+    /// Whether `trace_type` should cover all the variants of an enum (default) or only record
+    /// the first variant. In the latter case, incomplete enums are accepted by `registry`.
+    pub fn trace_all_variants(mut self, value: bool) -> Self {
+        self.trace_all_variants = value;
+        self
+    }
+
+    /// Whether `Tracer::well_known_types` should report the containers recognized as standard
+    /// types, such as `Duration` (default: false). The registry itself is not modified.
+    pub fn recognize_standard_types(mut self, value: bool) -> Self {
+        self.recognize_standard_types = value;
+        self
+    }
+
+    /// Whether to trace `#[serde(untagged)]` enums on a best-effort basis (default: false).
+    ///
+    /// Untagged enums buffer their input through `Deserializer::deserialize_any`, which
+    /// reveals neither the name of the enum nor its variants. When this option is enabled,
+    /// the N-th call to `deserialize_any` (counting from 0 in each deserialization run) is
+    /// resolved using the hint given by `untagged_variant_hint(N, ..)`, and `trace_type`
+    /// repeats the deserialization once per hinted variant.
+    ///
+    /// The resulting container is recorded as a `ContainerFormat::Enum` whose variant
+    /// indices follow the order of the hint. Since untagged enums do not serialize
+    /// any variant index, such enums are marked as "untagged" and reported by
+    /// `Tracer::untagged_enum_names`. Any other use of `deserialize_any` (e.g.
+    /// `#[serde(flatten)]`) is also counted as an occurrence, hence the heuristic nature
+    /// of this option.
+    pub fn allow_untagged(mut self, value: bool) -> Self {
+        self.allow_untagged = value;
+        self
+    }
+
+    /// Provide the name and the variants of the untagged enum deserialized by the N-th call
+    /// to `deserialize_any` (see `allow_untagged`). Variants are given in the order
+    /// of declaration. The containers mentioned by the variant formats are not traced and
+    /// should be traced separately.
+    pub fn untagged_variant_hint(
+        mut self,
+        index: usize,
+        name: &str,
+        variants: Vec<Named<VariantFormat>>,
+    ) -> Self {
+        self.untagged_variant_hints
+            .insert(index, (name.to_string(), variants));
+        self
+    }
+
+    /// Provide the index of a variant of the enum `name` that does not contain the enum itself,
+    /// such as `Leaf` in `enum Tree { Node(Box<Tree>), Leaf }`. By default, the tracer assumes
+    /// that the first variant is such a "base case": nested occurrences of an enum being traced
+    /// are deserialized with this variant to avoid infinite recursion. The base variant is also
+    /// visited first, and it is the only variant recorded without `trace_all_variants`.
+    pub fn base_variant_hint(mut self, name: &str, index: u32) -> Self {
+        self.base_variant_hints.insert(name.to_string(), index);
+        self
+    }
+
+    /// Maximal estimated size of a recorded sample, in bytes (default: no limit). Larger
+    /// samples are not recorded, and `trace_type` fails if it needs them. The estimate counts
+    /// the length of strings and byte arrays plus the memory of each node of the `Value`.
+    pub fn max_sample_bytes(mut self, value: Option<usize>) -> Self {
+        self.max_sample_bytes = value;
+        self
+    }
+
     define_default_value_setter!(default_bool_value, bool);
     define_default_value_setter!(default_u8_value, u8);
     define_default_value_setter!(default_u16_value, u16);
@@ -190,6 +364,11 @@ impl Tracer {
             registry: BTreeMap::new(),
             incomplete_enums: BTreeMap::new(),
             discriminants: BTreeMap::new(),
+            untagged_occurrences: 0,
+            untagged_progress: BTreeMap::new(),
+            untagged_enums: BTreeSet::new(),
+            enum_variant_names: BTreeMap::new(),
+            last_explored_variants: BTreeMap::new(),
         }
     }
 
@@ -208,6 +387,35 @@ impl Tracer {
         Ok((format, sample))
     }
 
+    /// Same as `trace_value` but also report whether the registry or the samples changed.
+    /// This helps tracing many values until they stop revealing new containers or formats.
+    pub fn trace_value_with_stats<T>(
+        &mut self,
+        samples: &mut Samples,
+        value: &T,
+    ) -> Result<(Format, Value, TraceStats)>
+    where
+        T: ?Sized + Serialize,
+    {
+        // Formats that are not fully known share their variables with the registry, hence
+        // the reduced copies.
+        let reduced_registry = |registry: &Registry| {
+            let mut registry = registry.clone();
+            for format in registry.values_mut() {
+                format.reduce();
+            }
+            registry
+        };
+        let registry = reduced_registry(&self.registry);
+        let changes = samples.changes;
+        let (format, sample) = self.trace_value(samples, value)?;
+        let stats = TraceStats {
+            registry_changed: reduced_registry(&self.registry) != registry,
+            samples_changed: samples.changes != changes,
+        };
+        Ok((format, sample, stats))
+    }
+
     /// Trace a single deserialization of a particular type.
     /// * Nested containers will be added to the tracing registry, indexed by
     ///   their (non-qualified) name.
@@ -216,12 +424,15 @@ impl Tracer {
     ///   have implemented a custom deserializer that validates data. The solution is
     ///   to make sure that `samples` holds enough sampled Rust values to cover all the
     ///   custom types.
+    /// * If `T` is an enum, each call explores one more step of its variants (see
+    ///   `pending_enums`).
     pub fn trace_type_once<'de, T>(&mut self, samples: &'de Samples) -> Result<(Format, T)>
     where
         T: Deserialize<'de>,
     {
+        self.untagged_occurrences = 0;
         let mut format = Format::unknown();
-        let deserializer = Deserializer::new(self, samples, &mut format);
+        let deserializer = Deserializer::new_root(self, samples, &mut format);
         let value = T::deserialize(deserializer)?;
         format.reduce();
         Ok((format, value))
@@ -236,24 +447,43 @@ impl Tracer {
     where
         S: DeserializeSeed<'de>,
     {
+        self.untagged_occurrences = 0;
         let mut format = Format::unknown();
-        let deserializer = Deserializer::new(self, samples, &mut format);
+        let deserializer = Deserializer::new_root(self, samples, &mut format);
         let value = seed.deserialize(deserializer)?;
         format.reduce();
         Ok((format, value))
     }
 
     /// Same as `trace_type_once` but if `T` is an enum, we repeat the process
-    /// until all variants of `T` are covered.
+    /// until all variants of `T` are covered, unless the configuration says otherwise
+    /// (see `TracerConfig::trace_all_variants`).
     /// We accumulate and return all the sampled values at the end.
     pub fn trace_type<'de, T>(&mut self, samples: &'de Samples) -> Result<(Format, Vec<T>)>
     where
         T: Deserialize<'de>,
     {
         let mut values = Vec::new();
+        self.untagged_progress.clear();
         loop {
-            let (format, value) = self.trace_type_once::<T>(samples)?;
+            let (format, value) = match self.trace_type_once::<T>(samples) {
+                Err(Error::UntaggedDetected(occurrence))
+                    if self.config.untagged_variant_hints.contains_key(&occurrence) =>
+                {
+                    // Restart the analysis with the first hinted variant.
+                    self.untagged_progress.insert(occurrence, 0);
+                    continue;
+                }
+                result => result?,
+            };
             values.push(value);
+            if !self.config.trace_all_variants {
+                return Ok((format, values));
+            }
+            if self.next_untagged_variants() {
+                // Restart the analysis to cover more variants of untagged enums.
+                continue;
+            }
             if let Format::TypeName(name) = &format {
                 if let Some(&progress) = self.incomplete_enums.get(name) {
                     // Restart the analysis to find more variants of T.
@@ -290,9 +520,26 @@ impl Tracer {
         S: DeserializeSeed<'de> + Clone,
     {
         let mut values = Vec::new();
+        self.untagged_progress.clear();
         loop {
-            let (format, value) = self.trace_type_once_with_seed(samples, seed.clone())?;
+            let (format, value) = match self.trace_type_once_with_seed(samples, seed.clone()) {
+                Err(Error::UntaggedDetected(occurrence))
+                    if self.config.untagged_variant_hints.contains_key(&occurrence) =>
+                {
+                    // Restart the analysis with the first hinted variant.
+                    self.untagged_progress.insert(occurrence, 0);
+                    continue;
+                }
+                result => result?,
+            };
             values.push(value);
+            if !self.config.trace_all_variants {
+                return Ok((format, values));
+            }
+            if self.next_untagged_variants() {
+                // Restart the analysis to cover more variants of untagged enums.
+                continue;
+            }
             if let Format::TypeName(name) = &format {
                 if let Some(&progress) = self.incomplete_enums.get(name) {
                     // Restart the analysis to find more variants of T.
@@ -307,12 +554,80 @@ impl Tracer {
         }
     }
 
+    /// Names of the enums whose variants are not all covered yet by the tracing of
+    /// deserialization. Calling `trace_type` on each of them fixes the corresponding
+    /// `MissingVariants` error in `registry`.
+    pub fn incomplete_enum_names(&self) -> Vec<&str> {
+        self.incomplete_enums.keys().map(String::as_str).collect()
+    }
+
+    /// Tracing status of the enums whose variants are not all covered yet (see
+    /// `incomplete_enum_names`), e.g. to report progress while calling `trace_type_once`
+    /// repeatedly.
+    pub fn pending_enums(&self) -> BTreeMap<String, EnumTraceStatus> {
+        self.incomplete_enums
+            .iter()
+            .map(|(name, progress)| {
+                let variant_names = self.enum_variant_names.get(name).copied().unwrap_or(&[]);
+                // Variants traced by name are recorded with a provisional index at the end of
+                // the range of `u32`.
+                let provisional_min = u32::MAX - variant_names.len().saturating_sub(1) as u32;
+                let (provisional, known): (Vec<_>, Vec<_>) = match self.registry.get(name) {
+                    Some(ContainerFormat::Enum(variants)) => variants
+                        .iter()
+                        .partition(|(index, _)| **index >= provisional_min),
+                    _ => Default::default(),
+                };
+                let missing_variants = variant_names
+                    .iter()
+                    .filter(|name| !known.iter().any(|(_, variant)| &variant.name == *name))
+                    .map(|name| name.to_string())
+                    .collect();
+                let status = EnumTraceStatus {
+                    progress: *progress,
+                    known_variants: known.len(),
+                    provisional_variants: provisional.len(),
+                    missing_variants,
+                    last_variant: self.last_explored_variants.get(name).cloned(),
+                };
+                (name.clone(), status)
+            })
+            .collect()
+    }
+
+    /// Names of the enums recorded from untagged variant hints (see
+    /// `TracerConfig::allow_untagged`). The variant indices of these enums are not part
+    /// of their serialized data.
+    pub fn untagged_enum_names(&self) -> Vec<&str> {
+        self.untagged_enums.iter().map(String::as_str).collect()
+    }
+
+    /// Select the next combination of variants for the hinted occurrences of
+    /// `deserialize_any`. Returns false once all combinations have been traced.
+    fn next_untagged_variants(&mut self) -> bool {
+        for (occurrence, index) in self.untagged_progress.iter_mut().rev() {
+            let (_, variants) = &self.config.untagged_variant_hints[occurrence];
+            if *index + 1 < variants.len() {
+                *index += 1;
+                return true;
+            }
+            *index = 0;
+        }
+        false
+    }
+
+    /// Whether no enum is known to be missing variants.
+    pub fn is_complete(&self) -> bool {
+        self.incomplete_enums.is_empty()
+    }
+
     /// Finish tracing and recover a map of normalized formats.
     /// Returns an error if we detect incompletely traced types.
     /// This may happen in a few of cases:
     /// * We traced serialization of user-provided values but we are still missing the content
     ///   of an option type, the content of a sequence type, the key or the value of a dictionary type.
     /// * We traced deserialization of an enum type but we detect that some enum variants are still missing.
+    ///   This check is skipped when `TracerConfig::trace_all_variants` is disabled.
     pub fn registry(self) -> Result<Registry> {
         let mut registry = self.registry;
         for (name, format) in registry.iter_mut() {
@@ -320,7 +635,7 @@ impl Tracer {
                 .normalize()
                 .map_err(|_| Error::UnknownFormatInContainer(name.clone()))?;
         }
-        if self.incomplete_enums.is_empty() {
+        if self.incomplete_enums.is_empty() || !self.config.trace_all_variants {
             Ok(registry)
         } else {
             Err(Error::MissingVariants(
@@ -339,6 +654,20 @@ impl Tracer {
         registry
     }
 
+    /// Containers recognized as standard types so far, indexed by name. Always empty unless
+    /// `TracerConfig::recognize_standard_types` is enabled.
+    /// Must be called before `registry`, which consumes the tracer.
+    pub fn well_known_types(&self) -> WellKnownTypes {
+        if !self.config.recognize_standard_types {
+            return WellKnownTypes::new();
+        }
+        let mut registry = self.registry.clone();
+        for format in registry.values_mut() {
+            format.normalize().unwrap_or(());
+        }
+        WellKnownType::recognize_all(&registry)
+    }
+
     pub(crate) fn record_container(
         &mut self,
         samples: &mut Samples,
@@ -349,7 +678,7 @@ impl Tracer {
     ) -> Result<(Format, Value)> {
         self.registry.entry(name.to_string()).unify(format)?;
         if record_value {
-            samples.values.insert(name, value.clone());
+            samples.insert(name, &value, self.config.max_sample_bytes);
         }
         Ok((Format::TypeName(name.into()), value))
     }
@@ -380,16 +709,55 @@ impl Tracer {
         &'a self,
         samples: &'de Samples,
         name: &'static str,
-    ) -> Option<(&'a ContainerFormat, &'de Value)> {
+    ) -> Result<Option<(&'a ContainerFormat, &'de Value)>> {
         match samples.value(name) {
             Some(value) => {
                 let format = self
                     .registry
                     .get(name)
                     .expect("recorded containers should have a format already");
-                Some((format, value))
+                Ok(Some((format, value)))
             }
-            None => None,
+            None => match samples.skipped.get(name) {
+                Some(size) => Err(Error::SampleTooLarge(name, *size)),
+                None => Ok(None),
+            },
         }
     }
 }
+
+/// Trace a list of types that are simple enough that no samples of values are needed,
+/// stopping at the first failure.
+///
+/// This expands to one call to `trace_simple_type` per type. Errors are wrapped in
+/// `Error::TypeTracing` together with the name of the type that failed.
+///
+/// ```rust
+/// # use serde::Deserialize;
+/// # use serde_reflection::{trace_types, Error, Tracer, TracerConfig};
+/// #[derive(Deserialize)]
+/// struct Foo(Choice);
+///
+/// #[derive(Deserialize)]
+/// enum Choice { A, B }
+///
+/// # fn main() -> Result<(), Error> {
+/// let mut tracer = Tracer::new(TracerConfig::default());
+/// trace_types!(tracer, [Foo, Choice])?;
+/// assert_eq!(tracer.registry()?.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_types {
+    ($tracer:expr, [$($ty:ty),* $(,)?]) => {
+        (|| -> $crate::Result<()> {
+            $(
+                $tracer.trace_simple_type::<$ty>().map_err(|error| {
+                    $crate::Error::TypeTracing(stringify!($ty).to_string(), Box::new(error))
+                })?;
+            )*
+            Ok(())
+        })()
+    };
+}
@@ -1,8 +1,16 @@
 // Copyright (c) Facebook, Inc. and its affiliates
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::error::{Error, Result};
-use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use crate::{
+    error::{Error, Result},
+    format::{ContainerFormat, Format, Named, VariantFormat},
+    trace::{Registry, TracerConfig},
+};
+use serde::{
+    de::{self, DeserializeSeed, IntoDeserializer, Visitor},
+    ser::{self, SerializeSeq, SerializeStruct, SerializeTuple},
+    Serialize,
+};
 
 /// A structured Serde value.
 /// Meant to be easily recorded while tracing serialization and easily used while tracing deserialization.
@@ -55,12 +63,310 @@ impl<'de> IntoDeserializer<'de, Error> for &'de Value {
 }
 
 impl Value {
+    /// Estimate the memory used by the value: the length of strings and byte arrays plus the
+    /// size of each node.
+    pub(crate) fn estimated_size(&self) -> usize {
+        let content = match self {
+            Value::Str(x) => x.len(),
+            Value::Bytes(x) => x.len(),
+            Value::Option(Some(x)) | Value::Variant(_, x) => x.estimated_size(),
+            Value::Seq(x) => x.iter().map(Value::estimated_size).sum(),
+            _ => 0,
+        };
+        std::mem::size_of::<Value>() + content
+    }
+
     pub(crate) fn seq_values(&self) -> Result<&Vec<Value>> {
         match self {
             Value::Seq(x) => Ok(x),
             _ => Err(Error::DeserializationError("seq_values")),
         }
     }
+
+    /// Build a canonical value of the given format, following the default rules of the tracer:
+    /// the default values of `config` for primitive types, `None` for options, empty sequences
+    /// and maps, and the variant of smallest index for enums, skipping the variants that would
+    /// contain the same enum again.
+    pub fn sample(config: &TracerConfig, registry: &Registry, format: &Format) -> Result<Value> {
+        sample_value(config, registry, format, &mut Vec::new())
+    }
+}
+
+fn sample_value(
+    config: &TracerConfig,
+    registry: &Registry,
+    format: &Format,
+    stack: &mut Vec<String>,
+) -> Result<Value> {
+    use Format::*;
+    let value = match format {
+        Variable(_) => return Err(Error::UnknownFormat),
+        TypeName(name) => {
+            let container = match registry.get(name) {
+                Some(container) if !stack.contains(name) => container,
+                _ => return Err(Error::NoSampleValue(name.clone())),
+            };
+            stack.push(name.clone());
+            let value = sample_container_value(config, registry, name, container, stack);
+            stack.pop();
+            value?
+        }
+        Unit => Value::Unit,
+        Bool => Value::Bool(config.default_bool_value),
+        I8 => Value::I8(config.default_i8_value),
+        I16 => Value::I16(config.default_i16_value),
+        I32 => Value::I32(config.default_i32_value),
+        I64 => Value::I64(config.default_i64_value),
+        I128 => Value::I128(config.default_i128_value),
+        U8 => Value::U8(config.default_u8_value),
+        U16 => Value::U16(config.default_u16_value),
+        U32 => Value::U32(config.default_u32_value),
+        U64 => Value::U64(config.default_u64_value),
+        U128 => Value::U128(config.default_u128_value),
+        F32 => Value::F32(config.default_f32_value),
+        F64 => Value::F64(config.default_f64_value),
+        Char => Value::Char(config.default_char_value),
+        Str => Value::Str(config.default_string_value.clone()),
+        Bytes => Value::Bytes(config.default_byte_buf_value.clone()),
+        Option(_) => Value::Option(None),
+        Seq(_) | Map { .. } => Value::Seq(Vec::new()),
+        Tuple(formats) => sample_values(config, registry, formats.iter(), stack)?,
+        TupleArray { content, size } => {
+            Value::Seq(vec![sample_value(config, registry, content, stack)?; *size])
+        }
+    };
+    Ok(value)
+}
+
+fn sample_values<'a>(
+    config: &TracerConfig,
+    registry: &Registry,
+    formats: impl Iterator<Item = &'a Format>,
+    stack: &mut Vec<String>,
+) -> Result<Value> {
+    let values = formats
+        .map(|format| sample_value(config, registry, format, stack))
+        .collect::<Result<_>>()?;
+    Ok(Value::Seq(values))
+}
+
+fn sample_container_value(
+    config: &TracerConfig,
+    registry: &Registry,
+    name: &str,
+    format: &ContainerFormat,
+    stack: &mut Vec<String>,
+) -> Result<Value> {
+    use ContainerFormat::*;
+    match format {
+        UnitStruct => Ok(Value::Unit),
+        NewTypeStruct(format) => sample_value(config, registry, format, stack),
+        TupleStruct(formats) => sample_values(config, registry, formats.iter(), stack),
+        Struct(fields) => sample_values(config, registry, fields.iter().map(|f| &f.value), stack),
+        Enum(variants) => {
+            for (index, variant) in variants {
+                let value = match &variant.value {
+                    VariantFormat::Variable(_) => Err(Error::UnknownFormat),
+                    VariantFormat::Unit => Ok(Value::Unit),
+                    VariantFormat::NewType(format) => sample_value(config, registry, format, stack),
+                    VariantFormat::Tuple(formats) => {
+                        sample_values(config, registry, formats.iter(), stack)
+                    }
+                    VariantFormat::Struct(fields) => {
+                        sample_values(config, registry, fields.iter().map(|f| &f.value), stack)
+                    }
+                };
+                match value {
+                    Ok(value) => return Ok(Value::Variant(*index, Box::new(value))),
+                    Err(Error::NoSampleValue(_)) => continue,
+                    Err(error) => return Err(error),
+                }
+            }
+            Err(Error::NoSampleValue(name.to_string()))
+        }
+    }
+}
+
+/// A value together with its format, which can be serialized with any Serde serializer,
+/// e.g. to compute reference encodings of sample values.
+/// Names of containers, fields, and variants are not passed to the serializer, hence this is
+/// only meant for binary encodings such as BCS or bincode.
+pub struct FormattedValue<'a> {
+    registry: &'a Registry,
+    format: &'a Format,
+    value: &'a Value,
+}
+
+impl<'a> FormattedValue<'a> {
+    pub fn new(registry: &'a Registry, format: &'a Format, value: &'a Value) -> Self {
+        Self {
+            registry,
+            format,
+            value,
+        }
+    }
+
+    fn with(&self, format: &'a Format, value: &'a Value) -> Self {
+        Self::new(self.registry, format, value)
+    }
+
+    fn mismatch<E: ser::Error>(&self) -> E {
+        E::custom(format!(
+            "value {:?} does not match format {}",
+            self.value, self.format
+        ))
+    }
+
+    fn serialize_container<S>(
+        &self,
+        format: &'a ContainerFormat,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use ContainerFormat::*;
+        match (format, self.value) {
+            (UnitStruct, Value::Unit) => serializer.serialize_unit_struct(""),
+            (NewTypeStruct(format), value) => {
+                serializer.serialize_newtype_struct("", &self.with(format, value))
+            }
+            (TupleStruct(formats), Value::Seq(values)) if formats.len() == values.len() => {
+                let mut serializer = serializer.serialize_tuple_struct("", values.len())?;
+                for (format, value) in formats.iter().zip(values) {
+                    ser::SerializeTupleStruct::serialize_field(
+                        &mut serializer,
+                        &self.with(format, value),
+                    )?;
+                }
+                ser::SerializeTupleStruct::end(serializer)
+            }
+            (Struct(fields), Value::Seq(values)) if fields.len() == values.len() => {
+                let mut serializer = serializer.serialize_struct("", values.len())?;
+                for (field, value) in fields.iter().zip(values) {
+                    serializer.serialize_field("", &self.with(&field.value, value))?;
+                }
+                serializer.end()
+            }
+            (Enum(variants), Value::Variant(index, value)) => match variants.get(index) {
+                Some(variant) => self.serialize_variant(*index, variant, value, serializer),
+                None => Err(self.mismatch()),
+            },
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_variant<S>(
+        &self,
+        index: u32,
+        variant: &'a Named<VariantFormat>,
+        value: &'a Value,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use VariantFormat::*;
+        match (&variant.value, value) {
+            (Unit, Value::Unit) => serializer.serialize_unit_variant("", index, ""),
+            (NewType(format), value) => {
+                serializer.serialize_newtype_variant("", index, "", &self.with(format, value))
+            }
+            (Tuple(formats), Value::Seq(values)) if formats.len() == values.len() => {
+                let mut serializer =
+                    serializer.serialize_tuple_variant("", index, "", values.len())?;
+                for (format, value) in formats.iter().zip(values) {
+                    ser::SerializeTupleVariant::serialize_field(
+                        &mut serializer,
+                        &self.with(format, value),
+                    )?;
+                }
+                ser::SerializeTupleVariant::end(serializer)
+            }
+            (Struct(fields), Value::Seq(values)) if fields.len() == values.len() => {
+                let mut serializer =
+                    serializer.serialize_struct_variant("", index, "", values.len())?;
+                for (field, value) in fields.iter().zip(values) {
+                    ser::SerializeStructVariant::serialize_field(
+                        &mut serializer,
+                        "",
+                        &self.with(&field.value, value),
+                    )?;
+                }
+                ser::SerializeStructVariant::end(serializer)
+            }
+            _ => Err(self.mismatch()),
+        }
+    }
+}
+
+impl<'a> Serialize for FormattedValue<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use Format::*;
+        match (self.format, self.value) {
+            (TypeName(name), _) => match self.registry.get(name) {
+                Some(format) => self.serialize_container(format, serializer),
+                None => Err(ser::Error::custom(format!("unknown container {}", name))),
+            },
+            (Unit, Value::Unit) => serializer.serialize_unit(),
+            (Bool, Value::Bool(x)) => serializer.serialize_bool(*x),
+            (I8, Value::I8(x)) => serializer.serialize_i8(*x),
+            (I16, Value::I16(x)) => serializer.serialize_i16(*x),
+            (I32, Value::I32(x)) => serializer.serialize_i32(*x),
+            (I64, Value::I64(x)) => serializer.serialize_i64(*x),
+            (I128, Value::I128(x)) => serializer.serialize_i128(*x),
+            (U8, Value::U8(x)) => serializer.serialize_u8(*x),
+            (U16, Value::U16(x)) => serializer.serialize_u16(*x),
+            (U32, Value::U32(x)) => serializer.serialize_u32(*x),
+            (U64, Value::U64(x)) => serializer.serialize_u64(*x),
+            (U128, Value::U128(x)) => serializer.serialize_u128(*x),
+            (F32, Value::F32(x)) => serializer.serialize_f32(*x),
+            (F64, Value::F64(x)) => serializer.serialize_f64(*x),
+            (Char, Value::Char(x)) => serializer.serialize_char(*x),
+            (Str, Value::Str(x)) => serializer.serialize_str(x),
+            (Bytes, Value::Bytes(x)) => serializer.serialize_bytes(x),
+            (Option(_), Value::Option(None)) => serializer.serialize_none(),
+            (Option(format), Value::Option(Some(value))) => {
+                serializer.serialize_some(&self.with(format, value))
+            }
+            (Seq(format), Value::Seq(values)) => {
+                let mut serializer = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    serializer.serialize_element(&self.with(format, value))?;
+                }
+                serializer.end()
+            }
+            (Map { key, value }, Value::Seq(values)) if values.len() % 2 == 0 => {
+                let mut serializer = serializer.serialize_map(Some(values.len() / 2))?;
+                for entry in values.chunks(2) {
+                    ser::SerializeMap::serialize_entry(
+                        &mut serializer,
+                        &self.with(key, &entry[0]),
+                        &self.with(value, &entry[1]),
+                    )?;
+                }
+                ser::SerializeMap::end(serializer)
+            }
+            (Tuple(formats), Value::Seq(values)) if formats.len() == values.len() => {
+                let mut serializer = serializer.serialize_tuple(values.len())?;
+                for (format, value) in formats.iter().zip(values) {
+                    serializer.serialize_element(&self.with(format, value))?;
+                }
+                serializer.end()
+            }
+            (TupleArray { content, size }, Value::Seq(values)) if values.len() == *size => {
+                let mut serializer = serializer.serialize_tuple(values.len())?;
+                for value in values {
+                    serializer.serialize_element(&self.with(content, value))?;
+                }
+                serializer.end()
+            }
+            _ => Err(self.mismatch()),
+        }
+    }
 }
 
 macro_rules! declare_deserialize {
@@ -119,9 +425,14 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     declare_deserialize!(deserialize_f64, F64, visit_f64, "f64");
 
     declare_deserialize!(deserialize_char, Char, visit_char, "char");
-    declare_deserialize!(deserialize_string, Str, visit_string, "string");
+    declare_deserialize_borrowed!(deserialize_string, Str, visit_borrowed_str, "string");
     declare_deserialize_borrowed!(deserialize_str, Str, visit_borrowed_str, "str");
-    declare_deserialize!(deserialize_byte_buf, Bytes, visit_byte_buf, "byte_buf");
+    declare_deserialize_borrowed!(
+        deserialize_byte_buf,
+        Bytes,
+        visit_borrowed_bytes,
+        "byte_buf"
+    );
     declare_deserialize_borrowed!(deserialize_bytes, Bytes, visit_borrowed_bytes, "bytes");
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
@@ -202,7 +513,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.value {
-            Value::Seq(x) => visitor.visit_seq(x.into_seq_deserializer()),
+            Value::Seq(x) => visitor.visit_map(x.into_seq_deserializer()),
             _ => Err(Error::DeserializationError("map")),
         }
     }
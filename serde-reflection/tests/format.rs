@@ -273,3 +273,485 @@ fn test_container_format_unification() {
             .is_err());
     }
 }
+
+#[test]
+fn test_format_parsing_and_printing() {
+    use Format::*;
+
+    let formats = vec![
+        Unit,
+        Bool,
+        I8,
+        I16,
+        I32,
+        I64,
+        I128,
+        U8,
+        U16,
+        U32,
+        U64,
+        U128,
+        F32,
+        F64,
+        Char,
+        Str,
+        Bytes,
+        TypeName("Foo".into()),
+        Option(Box::new(TypeName("Foo".into()))),
+        Seq(Box::new(Map {
+            key: Box::new(Str),
+            value: Box::new(U32),
+        })),
+        Tuple(vec![]),
+        Tuple(vec![U8, Seq(Box::new(Option(Box::new(Bool))))]),
+        TupleArray {
+            content: Box::new(Tuple(vec![U16, Char])),
+            size: 3,
+        },
+    ];
+    for format in formats {
+        let text = format.to_string();
+        assert_eq!(text.parse::<Format>().unwrap(), format, "{}", text);
+    }
+
+    assert_eq!(
+        Seq(Box::new(Map {
+            key: Box::new(Str),
+            value: Box::new(U32)
+        }))
+        .to_string(),
+        "[Map<Str, U32>]"
+    );
+    assert_eq!(
+        Seq(Box::new(Option(Box::new(TypeName("Foo".into()))))).to_string(),
+        "[Option<Foo>]"
+    );
+    assert_eq!(Tuple(vec![U8]).to_string(), "(U8,)");
+    assert_eq!(
+        TupleArray {
+            content: Box::new(Tuple(vec![U16, Char])),
+            size: 3,
+        }
+        .to_string(),
+        "[(U16, Char); 3]"
+    );
+    assert_eq!(
+        " [ Map<str,u32 > ]".parse::<Format>().unwrap(),
+        "[Map<Str, U32>]".parse::<Format>().unwrap()
+    );
+    assert_eq!(
+        Format::parse("Option<Foo>").unwrap(),
+        Option(Box::new(TypeName("Foo".into())))
+    );
+    assert_eq!(Format::parse("(U8)").unwrap(), Tuple(vec![U8]));
+    assert_eq!(Format::unknown().to_string(), "Unknown");
+    assert!("Unknown".parse::<Format>().unwrap().is_unknown());
+
+    // Type names colliding with keywords are escaped.
+    for name in ["Str", "u8", "Option", "Unknown", "my::Type", "3D"] {
+        let format = TypeName(name.into());
+        let text = format.to_string();
+        assert_eq!(text, format!("TypeName({})", name));
+        assert_eq!(Format::parse(&text).unwrap(), format);
+    }
+
+    // The syntax of earlier versions is still accepted.
+    assert_eq!(
+        Format::parse("Seq<Tuple<TypeName(Foo), TupleArray<U8, 2>>>").unwrap(),
+        Format::parse("[(Foo, [U8; 2])]").unwrap()
+    );
+
+    for text in [
+        "",
+        "3D",
+        "Foo<U8>",
+        "[U8",
+        "[U8]]",
+        "[U8; x]",
+        "(U8",
+        "(U8,,)",
+        "Map<U8>",
+        "TypeName()",
+        "TupleArray<U8, x>",
+    ] {
+        assert!(
+            matches!(Format::parse(text), Err(Error::ParseFormat(_))),
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn test_container_format_printing() {
+    use serde::Serialize;
+    use serde_reflection::{Samples, Tracer, TracerConfig};
+
+    #[derive(Serialize)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Id(u64);
+
+    #[derive(Serialize)]
+    struct Pair(u8, Option<Id>);
+
+    #[derive(Serialize)]
+    struct Nothing;
+
+    #[derive(Serialize)]
+    struct Empty {}
+
+    #[derive(Serialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rectangle(f64, f64),
+        Polygon { points: Vec<(f64, f64)> },
+        Single((u8,)),
+    }
+
+    #[derive(Serialize)]
+    struct Everything {
+        person: Person,
+        id: Id,
+        pair: Pair,
+        nothing: Nothing,
+        empty: Empty,
+        shapes: Vec<Shape>,
+        arrays: std::collections::BTreeMap<String, [u16; 3]>,
+        timeout: std::time::Duration,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let value = Everything {
+        person: Person {
+            name: "Alice".into(),
+            age: 30,
+        },
+        id: Id(1),
+        pair: Pair(2, Some(Id(3))),
+        nothing: Nothing,
+        empty: Empty {},
+        shapes: vec![
+            Shape::Point,
+            Shape::Circle(1.0),
+            Shape::Rectangle(1.0, 2.0),
+            Shape::Polygon {
+                points: vec![(0.0, 1.0)],
+            },
+            Shape::Single((4,)),
+        ],
+        arrays: vec![("a".to_string(), [1, 2, 3])].into_iter().collect(),
+        timeout: std::time::Duration::from_secs(1),
+    };
+    tracer.trace_value(&mut Samples::new(), &value).unwrap();
+    let registry = tracer.registry().unwrap();
+    let rendering = registry
+        .iter()
+        .map(|(name, format)| format!("{} = {}", name, format))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rendering,
+        vec![
+            "Duration = { secs: U64, nanos: U32 }",
+            "Empty = {}",
+            "Everything = { person: Person, id: Id, pair: Pair, nothing: Nothing, empty: Empty, shapes: [Shape], arrays: Map<Str, [U16; 3]>, timeout: Duration }",
+            "Id = (U64)",
+            "Nothing = Unit",
+            "Pair = (U8, Option<Id>)",
+            "Person = { name: Str, age: U32 }",
+            "Shape = Point | Circle(F64) | Rectangle(F64, F64) | Polygon { points: [[F64; 2]] } | Single((U8,))",
+        ]
+    );
+
+    let variants = match registry.get("Shape").unwrap() {
+        ContainerFormat::Enum(variants) => variants,
+        _ => panic!("unexpected format"),
+    };
+    let variants = variants
+        .values()
+        .map(|variant| variant.value.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        variants,
+        vec![
+            "Unit",
+            "(F64)",
+            "(F64, F64)",
+            "{ points: [[F64; 2]] }",
+            "((U8,))"
+        ]
+    );
+    assert_eq!(ContainerFormat::Enum(Default::default()).to_string(), "!");
+    assert_eq!(VariantFormat::unknown().to_string(), "Unknown");
+}
+
+#[test]
+fn test_normalization() {
+    use serde::Serialize;
+    use serde_reflection::{Samples, Tracer, TracerConfig};
+    use Format::*;
+
+    #[derive(Serialize)]
+    struct Foo {
+        a: (u8, u8),
+        b: std::option::Option<((u16, u16), (u16, u16))>,
+        c: (u32, u64),
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let value = Foo {
+        a: (1, 2),
+        b: Some(((3, 4), (5, 6))),
+        c: (7, 8),
+    };
+    tracer.trace_value(&mut Samples::new(), &value).unwrap();
+    let registry = tracer.registry().unwrap();
+
+    let mut variable = Format::unknown();
+    variable.unify(U8).unwrap();
+    let format = ContainerFormat::Struct(vec![
+        Named {
+            name: "a".into(),
+            value: Tuple(vec![variable, U8]),
+        },
+        Named {
+            name: "b".into(),
+            value: Option(Box::new(Tuple(vec![
+                Tuple(vec![U16, U16]),
+                Tuple(vec![U16, U16]),
+            ]))),
+        },
+        Named {
+            name: "c".into(),
+            value: Tuple(vec![U32, U64]),
+        },
+    ]);
+    assert_eq!(&format.normalized().unwrap(), registry.get("Foo").unwrap());
+
+    let format = ContainerFormat::NewTypeStruct(Box::new(Format::unknown()));
+    assert!(matches!(format.normalized(), Err(Error::UnknownFormat)));
+
+    // Unknown formats are kept but the rest is normalized.
+    let format = Tuple(vec![Format::unknown(), Tuple(vec![Bool, Bool])]).normalized();
+    match format {
+        Tuple(formats) => {
+            assert!(formats[0].is_unknown());
+            assert_eq!(
+                formats[1],
+                TupleArray {
+                    content: Box::new(Bool),
+                    size: 2
+                }
+            );
+        }
+        _ => panic!("unexpected format {}", format),
+    }
+}
+
+#[test]
+fn test_schema_hash() {
+    use serde_reflection::{schema_hash, Registry};
+    use Format::*;
+
+    let registry = |containers: Vec<(&str, ContainerFormat)>| -> Registry {
+        containers
+            .into_iter()
+            .map(|(name, format)| (name.to_string(), format))
+            .collect()
+    };
+    fn named<T>(name: &str, value: T) -> Named<T> {
+        Named {
+            name: name.into(),
+            value,
+        }
+    }
+    let list = |name: &str, field: &str| {
+        registry(vec![(
+            name,
+            ContainerFormat::Enum(
+                vec![
+                    (0, named("Nil", VariantFormat::Unit)),
+                    (
+                        1,
+                        named(
+                            "Cons",
+                            VariantFormat::Struct(vec![
+                                named(field, U8),
+                                named("tail", TypeName(name.into())),
+                            ]),
+                        ),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        )])
+    };
+
+    // Names and struct kinds are not part of the wire format.
+    assert_eq!(
+        schema_hash(&list("List", "head")),
+        schema_hash(&list("Stack", "top"))
+    );
+    assert_eq!(
+        schema_hash(&registry(vec![(
+            "A",
+            ContainerFormat::Struct(vec![named("x", Tuple(vec![U16, U16]))])
+        )])),
+        schema_hash(&registry(vec![(
+            "B",
+            ContainerFormat::NewTypeStruct(Box::new(TupleArray {
+                content: Box::new(U16),
+                size: 2
+            }))
+        )])),
+    );
+
+    // Shared and duplicated containers are equivalent.
+    let pair = |second: &str| {
+        let mut registry = registry(vec![
+            (
+                "Pair",
+                ContainerFormat::TupleStruct(vec![TypeName("A".into()), TypeName(second.into())]),
+            ),
+            ("A", ContainerFormat::NewTypeStruct(Box::new(Str))),
+        ]);
+        registry.insert(
+            second.to_string(),
+            ContainerFormat::NewTypeStruct(Box::new(Str)),
+        );
+        registry
+    };
+    assert_eq!(schema_hash(&pair("A")), schema_hash(&pair("B")));
+
+    // Changes of the encoding are detected.
+    let hash = schema_hash(&list("List", "head"));
+    let mut other = list("List", "head");
+    if let Some(ContainerFormat::Enum(variants)) = other.get_mut("List") {
+        let nil = variants.remove(&0).unwrap();
+        variants.insert(2, nil);
+    }
+    assert_ne!(hash, schema_hash(&other));
+    assert_ne!(
+        schema_hash(&pair("A")),
+        schema_hash(&registry(vec![(
+            "Pair",
+            ContainerFormat::TupleStruct(vec![Str, Str])
+        )]))
+    );
+    assert_ne!(schema_hash(&Registry::new()), hash);
+
+    // Containers that no other container uses are part of the hash.
+    let mut other = list("List", "head");
+    other.insert("Unused".to_string(), ContainerFormat::UnitStruct);
+    assert_ne!(hash, schema_hash(&other));
+    let mut changed = list("List", "head");
+    changed.insert(
+        "Unused".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(U8)),
+    );
+    assert_ne!(schema_hash(&other), schema_hash(&changed));
+
+    // The hash follows references between containers: a field of type `Inner` is not the same
+    // as the fields of `Inner` inlined, even though both are serialized in the same way.
+    let outer = |field: Format| {
+        registry(vec![
+            (
+                "Outer",
+                ContainerFormat::Struct(vec![named("inner", field)]),
+            ),
+            ("Inner", ContainerFormat::Struct(vec![named("a", U32)])),
+        ])
+    };
+    assert_ne!(
+        schema_hash(&outer(TypeName("Inner".into()))),
+        schema_hash(&outer(U32))
+    );
+}
+
+#[test]
+fn test_frozen_registry() {
+    use serde_reflection::{Freeze, FrozenContainerFormat, FrozenFormat, FrozenRegistry, Registry};
+    use Format::*;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "List".to_string(),
+        ContainerFormat::Struct(vec![
+            Named {
+                name: "head".into(),
+                value: Option(Box::new(Seq(Box::new(TypeName("Node".into()))))),
+            },
+            Named {
+                name: "len".into(),
+                value: U64,
+            },
+        ]),
+    );
+    registry.insert(
+        "Node".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(TypeName("List".into()))),
+    );
+
+    let frozen = registry.freeze().unwrap();
+    assert_send_sync(&frozen);
+    assert_eq!(frozen.len(), 2);
+    assert_eq!(frozen.index_of("Node"), Some(1));
+    assert_eq!(frozen.index_of("Tree"), None);
+    assert_eq!(frozen.name(0), "List");
+    assert_eq!(
+        frozen.get("Node"),
+        Some(&FrozenContainerFormat::NewTypeStruct(Box::new(
+            FrozenFormat::TypeName(0)
+        )))
+    );
+    assert_eq!(frozen.to_registry(), registry);
+
+    // Resolved variables are replaced by their values.
+    let mut with_variable = registry.clone();
+    let variable = Format::unknown();
+    if let Variable(content) = &variable {
+        *content.borrow_mut() = Some(U64);
+    }
+    with_variable.insert(
+        "Id".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(variable)),
+    );
+    let mut expected = registry.clone();
+    expected.insert(
+        "Id".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(U64)),
+    );
+    assert_eq!(with_variable.freeze().unwrap().to_registry(), expected);
+
+    // Dangling names, unknown formats, and deep nesting are rejected.
+    registry.insert(
+        "Tree".to_string(),
+        ContainerFormat::TupleStruct(vec![TypeName("Forest".into())]),
+    );
+    assert_eq!(
+        registry.freeze(),
+        Err(Error::MissingContainer("Tree".into(), "Forest".into()))
+    );
+    registry.insert(
+        "Tree".to_string(),
+        ContainerFormat::NewTypeStruct(Box::new(Format::unknown())),
+    );
+    assert_eq!(
+        registry.freeze(),
+        Err(Error::UnknownFormatInContainer("Tree".into()))
+    );
+    registry.remove("Tree");
+    assert!(FrozenRegistry::new(&registry, 3).is_ok());
+    assert_eq!(
+        FrozenRegistry::new(&registry, 2),
+        Err(Error::FormatTooDeep("List".into(), 2))
+    );
+}
@@ -3,8 +3,9 @@
 
 use serde::{de::IntoDeserializer, Deserialize, Serialize};
 use serde_reflection::{
-    ContainerFormat, Error, Format, FormatHolder, Named, Samples, Tracer, TracerConfig, Value,
-    VariantFormat,
+    trace_types, ContainerFormat, EnumProgress, EnumTraceStatus, Error, Format, FormatHolder,
+    FormattedValue, Named, Samples, TraceStats, Tracer, TracerConfig, Value, VariantFormat,
+    WellKnownType, WellKnownTypes,
 };
 use std::collections::BTreeMap;
 
@@ -407,6 +408,332 @@ fn test_mixed_tracing_for_multiple_enums() {
     assert_eq!(variants.len(), 2);
 }
 
+#[test]
+fn test_tracing_only_the_first_variant() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Foo {
+        A(Bar),
+        B,
+        C(u32),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Bar {
+        D(u8),
+        E,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default().trace_all_variants(false));
+    let (format, values) = tracer.trace_simple_type::<Foo>().unwrap();
+    assert_eq!(format, Format::TypeName("Foo".into()));
+    assert_eq!(values, vec![Foo::A(Bar::D(0))]);
+
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        *registry.get("Foo").unwrap(),
+        ContainerFormat::Enum(BTreeMap::from([(
+            0,
+            Named {
+                name: "A".into(),
+                value: VariantFormat::NewType(Box::new(Format::TypeName("Bar".into()))),
+            }
+        )]))
+    );
+    assert_eq!(
+        *registry.get("Bar").unwrap(),
+        ContainerFormat::Enum(BTreeMap::from([(
+            0,
+            Named {
+                name: "D".into(),
+                value: VariantFormat::NewType(Box::new(Format::U8)),
+            }
+        )]))
+    );
+
+    // By default, a single pass is not enough.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_type_once::<Foo>(&Samples::new()).unwrap();
+    assert!(tracer.registry().is_err());
+}
+
+#[test]
+fn test_tracing_with_base_variant_hint() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Tree {
+        Node(Box<Tree>, Box<Tree>),
+        Leaf(u32),
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default().base_variant_hint("Tree", 1));
+    let (format, values) = tracer.trace_simple_type::<Tree>().unwrap();
+    assert_eq!(format, Format::TypeName("Tree".into()));
+    assert_eq!(
+        values,
+        vec![
+            Tree::Node(Box::new(Tree::Leaf(0)), Box::new(Tree::Leaf(0))),
+            Tree::Leaf(0)
+        ]
+    );
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        *registry.get("Tree").unwrap(),
+        ContainerFormat::Enum(BTreeMap::from([
+            (
+                0,
+                Named {
+                    name: "Node".into(),
+                    value: VariantFormat::Tuple(vec![
+                        Format::TypeName("Tree".into()),
+                        Format::TypeName("Tree".into())
+                    ]),
+                }
+            ),
+            (
+                1,
+                Named {
+                    name: "Leaf".into(),
+                    value: VariantFormat::NewType(Box::new(Format::U32)),
+                }
+            ),
+        ]))
+    );
+
+    // Only the base variant is recorded when tracing a single variant.
+    let mut tracer = Tracer::new(
+        TracerConfig::default()
+            .base_variant_hint("Tree", 1)
+            .trace_all_variants(false),
+    );
+    let (_, values) = tracer.trace_simple_type::<Tree>().unwrap();
+    assert_eq!(values, vec![Tree::Leaf(0)]);
+    assert_eq!(
+        *tracer.registry().unwrap().get("Tree").unwrap(),
+        ContainerFormat::Enum(BTreeMap::from([(
+            1,
+            Named {
+                name: "Leaf".into(),
+                value: VariantFormat::NewType(Box::new(Format::U32)),
+            }
+        )]))
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default().base_variant_hint("Tree", 2));
+    assert_eq!(
+        tracer.trace_simple_type::<Tree>().unwrap_err(),
+        Error::InvalidBaseVariantHint("Tree".into(), 2)
+    );
+}
+
+#[test]
+fn test_incomplete_enum_names() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Foo {
+        A(Bar),
+        B,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Bar {
+        C,
+        D,
+    }
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert!(tracer.is_complete());
+
+    tracer.trace_type_once::<Foo>(&samples).unwrap();
+    assert_eq!(tracer.incomplete_enum_names(), vec!["Bar", "Foo"]);
+    assert!(!tracer.is_complete());
+
+    tracer.trace_type::<Foo>(&samples).unwrap();
+    assert_eq!(tracer.incomplete_enum_names(), vec!["Bar"]);
+
+    tracer.trace_type::<Bar>(&samples).unwrap();
+    assert!(tracer.incomplete_enum_names().is_empty());
+    assert!(tracer.is_complete());
+    tracer.registry().unwrap();
+}
+
+#[test]
+fn test_pending_enums() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    enum Choice {
+        A,
+        B(u8),
+        C { x: bool },
+    }
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert!(tracer.pending_enums().is_empty());
+
+    let status =
+        |progress, known_variants, provisional_variants, missing: &[&str], last| EnumTraceStatus {
+            progress,
+            known_variants,
+            provisional_variants,
+            missing_variants: missing.iter().map(|name| name.to_string()).collect(),
+            last_variant: Some(String::from(last)),
+        };
+    use EnumProgress::{IndexedVariantsRemaining as Indexed, NamedVariantsRemaining as Named};
+    let expected = [
+        status(Named, 0, 1, &["A", "B", "C"], "A"),
+        status(Named, 0, 2, &["A", "B", "C"], "B"),
+        status(Named, 0, 3, &["A", "B", "C"], "C"),
+        status(Indexed, 1, 2, &["B", "C"], "A"),
+        status(Indexed, 2, 1, &["C"], "B"),
+    ];
+    for expected_status in expected {
+        tracer.trace_type_once::<Choice>(&samples).unwrap();
+        let pending = tracer.pending_enums();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending["Choice"], expected_status);
+    }
+
+    tracer.trace_type_once::<Choice>(&samples).unwrap();
+    assert!(tracer.pending_enums().is_empty());
+    assert!(tracer.is_complete());
+    tracer.registry().unwrap();
+}
+
+#[test]
+fn test_tracing_untagged_enums_with_hints() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    #[serde(untagged)]
+    enum Untagged {
+        A(u64),
+        B(String),
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    struct Foo {
+        x: Untagged,
+        y: u8,
+    }
+
+    let samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default());
+    assert_eq!(
+        tracer.trace_type::<Foo>(&samples).unwrap_err(),
+        Error::NotSupported("deserialize_any")
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default().allow_untagged(true));
+    assert_eq!(
+        tracer.trace_type::<Foo>(&samples).unwrap_err(),
+        Error::UntaggedDetected(0)
+    );
+
+    let variants = vec![
+        Named {
+            name: "A".into(),
+            value: VariantFormat::NewType(Box::new(Format::U64)),
+        },
+        Named {
+            name: "B".into(),
+            value: VariantFormat::NewType(Box::new(Format::Str)),
+        },
+    ];
+    let config = TracerConfig::default()
+        .allow_untagged(true)
+        .untagged_variant_hint(0, "Untagged", variants.clone());
+    let mut tracer = Tracer::new(config);
+    let (format, values) = tracer.trace_type::<Foo>(&samples).unwrap();
+    assert_eq!(format, Format::TypeName("Foo".into()));
+    assert_eq!(
+        values,
+        vec![
+            Foo {
+                x: Untagged::A(0),
+                y: 0
+            },
+            Foo {
+                x: Untagged::B(String::new()),
+                y: 0
+            },
+        ]
+    );
+    assert_eq!(tracer.untagged_enum_names(), vec!["Untagged"]);
+
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("Foo").unwrap(),
+        &ContainerFormat::Struct(vec![
+            Named {
+                name: "x".into(),
+                value: Format::TypeName("Untagged".into())
+            },
+            Named {
+                name: "y".into(),
+                value: Format::U8
+            },
+        ])
+    );
+    assert_eq!(
+        registry.get("Untagged").unwrap(),
+        &ContainerFormat::Enum(
+            variants
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (i as u32, v))
+                .collect()
+        )
+    );
+}
+
+#[test]
+fn test_recognizing_standard_types() {
+    #[derive(Serialize, Deserialize)]
+    struct Timeout {
+        delay: std::time::Duration,
+    }
+
+    let duration_format = ContainerFormat::Struct(vec![
+        Named {
+            name: "secs".into(),
+            value: Format::U64,
+        },
+        Named {
+            name: "nanos".into(),
+            value: Format::U32,
+        },
+    ]);
+
+    // Disabled by default.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Timeout>().unwrap();
+    assert!(tracer.well_known_types().is_empty());
+
+    let mut tracer = Tracer::new(TracerConfig::default().recognize_standard_types(true));
+    tracer.trace_simple_type::<Timeout>().unwrap();
+    let well_known_types = tracer.well_known_types();
+    assert_eq!(
+        well_known_types,
+        [("Duration".to_string(), WellKnownType::Duration)]
+            .into_iter()
+            .collect()
+    );
+    // The registry is unchanged.
+    let registry = tracer.registry().unwrap();
+    assert_eq!(*registry.get("Duration").unwrap(), duration_format);
+    assert_eq!(WellKnownType::recognize_all(&registry), well_known_types);
+
+    let data = serde_yaml::to_string(&well_known_types).unwrap();
+    assert_eq!(data, "---\nDuration: DURATION\n");
+    assert_eq!(
+        serde_yaml::from_str::<WellKnownTypes>(&data).unwrap(),
+        well_known_types
+    );
+
+    // Containers with a different name or shape are not recognized.
+    assert_eq!(WellKnownType::recognize("Timeout", &duration_format), None);
+    assert_eq!(
+        WellKnownType::recognize("Duration", &ContainerFormat::UnitStruct),
+        None
+    );
+}
+
 #[test]
 fn test_value_recording_for_structs() {
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -461,6 +788,99 @@ fn test_repeated_tracing() {
     );
 }
 
+#[test]
+fn test_tracing_stats() {
+    #[derive(Serialize, Deserialize)]
+    enum Event {
+        Start,
+        Stop(Option<u64>),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Record(Vec<Event>);
+
+    let mut samples = Samples::new();
+    let mut tracer = Tracer::new(TracerConfig::default().record_samples_for_tuple_structs(true));
+
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Start]))
+        .unwrap();
+    assert!(stats.registry_changed && stats.samples_changed);
+
+    // The same value covers nothing new.
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Start]))
+        .unwrap();
+    assert_eq!(stats, TraceStats::default());
+    assert!(!stats.is_new());
+
+    // A different value of a known format only replaces the sample.
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Start, Event::Start]))
+        .unwrap();
+    assert!(!stats.registry_changed && stats.samples_changed);
+
+    // A new variant changes the registry, and so does a format refined later.
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Stop(None)]))
+        .unwrap();
+    assert!(stats.registry_changed);
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Stop(Some(1))]))
+        .unwrap();
+    assert!(stats.registry_changed);
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &Record(vec![Event::Stop(Some(2))]))
+        .unwrap();
+    assert!(!stats.registry_changed && stats.is_new());
+
+    tracer.registry().unwrap();
+}
+
+#[test]
+fn test_max_sample_bytes() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    struct Blob(#[serde(with = "serde_bytes")] Vec<u8>);
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    struct Message {
+        blob: Blob,
+    }
+
+    let message = Message {
+        blob: Blob(vec![7; 1 << 20]),
+    };
+
+    // Without a limit, the sample is recorded and used.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let mut samples = Samples::new();
+    tracer.trace_value(&mut samples, &message).unwrap();
+    assert!(samples.value("Blob").is_some());
+    let (_, values) = tracer.trace_type::<Message>(&samples).unwrap();
+    assert_eq!(values[0], message);
+
+    // Under a small limit, the sample is skipped and needed later.
+    let mut tracer = Tracer::new(TracerConfig::default().max_sample_bytes(Some(1024)));
+    let mut samples = Samples::new();
+    let (_, _, stats) = tracer
+        .trace_value_with_stats(&mut samples, &message)
+        .unwrap();
+    assert!(!stats.samples_changed);
+    assert!(samples.value("Blob").is_none());
+    assert!(matches!(
+        tracer.trace_type::<Message>(&samples),
+        Err(Error::SampleTooLarge("Blob", size)) if size > 1 << 20
+    ));
+
+    // A smaller sample is accepted.
+    let small = Message {
+        blob: Blob(vec![1, 2, 3]),
+    };
+    tracer.trace_value(&mut samples, &small).unwrap();
+    let (_, values) = tracer.trace_type::<Message>(&samples).unwrap();
+    assert_eq!(values[0], small);
+}
+
 #[test]
 fn test_default_value_for_primitive_types() {
     let config = TracerConfig::default()
@@ -505,3 +925,317 @@ fn test_default_value_for_primitive_types() {
     assert_eq!(format, Format::Str);
     assert_eq!(value, "A borrowed str");
 }
+
+mod timestamp_as_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seconds: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}s", seconds))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .strip_suffix('s')
+            .and_then(|seconds| seconds.parse().ok())
+            .ok_or_else(|| D::Error::custom(format!("Invalid timestamp {:?}", value)))
+    }
+}
+
+#[test]
+fn test_recording_samples_for_serialize_with() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Timestamp(#[serde(with = "timestamp_as_string")] u64);
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+    struct Event {
+        name: String,
+        time: Timestamp,
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    assert!(tracer.trace_type::<Event>(&samples).is_err());
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let mut samples = Samples::new();
+    samples
+        .record(&mut tracer, "Timestamp", &Timestamp(42))
+        .unwrap();
+    assert_eq!(
+        samples.value("Timestamp"),
+        Some(&Value::Str("42s".to_string()))
+    );
+    let (_, values) = tracer.trace_type::<Event>(&samples).unwrap();
+    assert_eq!(values[0].time, Timestamp(42));
+
+    // The name must match the container being serialized.
+    assert!(matches!(
+        samples.record(&mut tracer, "Time", &Timestamp(42)),
+        Err(Error::Incompatible(_, _))
+    ));
+    assert!(samples.value("Time").is_none());
+
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("Timestamp").unwrap(),
+        &ContainerFormat::NewTypeStruct(Box::new(Format::Str))
+    );
+}
+
+#[test]
+fn test_sample_values() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Tree {
+        Leaf,
+        Node(u32, Box<Tree>, Box<Tree>),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        id: u64,
+        name: String,
+        tags: Vec<String>,
+        parent: Option<Box<Record>>,
+        tree: Tree,
+        pair: (bool, char),
+        counts: BTreeMap<u8, i16>,
+        array: [u16; 2],
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    tracer.trace_simple_type::<Record>().unwrap();
+    tracer.trace_simple_type::<Tree>().unwrap();
+    let registry = tracer.registry().unwrap();
+    let config = TracerConfig::default();
+    let format = Format::TypeName("Record".to_string());
+    let value = Value::sample(&config, &registry, &format).unwrap();
+
+    let expected = Record {
+        id: 0,
+        name: String::new(),
+        tags: Vec::new(),
+        parent: None,
+        tree: Tree::Leaf,
+        pair: (false, 'A'),
+        counts: BTreeMap::new(),
+        array: [0, 0],
+    };
+    assert_eq!(
+        Record::deserialize(value.clone().into_deserializer()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        bincode::serialize(&FormattedValue::new(&registry, &format, &value)).unwrap(),
+        bincode::serialize(&expected).unwrap()
+    );
+
+    // Values must match their format.
+    let value = Value::Seq(vec![Value::U64(0)]);
+    assert!(bincode::serialize(&FormattedValue::new(&registry, &format, &value)).is_err());
+
+    // Variants that contain their own enum are skipped.
+    let variant = |name: &str, format: VariantFormat| Named {
+        name: name.to_string(),
+        value: format,
+    };
+    let again = || VariantFormat::NewType(Box::new(Format::TypeName("Loop".to_string())));
+    let mut registry = registry;
+    registry.insert(
+        "Loop".to_string(),
+        ContainerFormat::Enum(
+            vec![
+                (0, variant("Again", again())),
+                (1, variant("Stop", VariantFormat::Unit)),
+            ]
+            .into_iter()
+            .collect(),
+        ),
+    );
+    let format = Format::TypeName("Loop".to_string());
+    assert_eq!(
+        Value::sample(&config, &registry, &format),
+        Ok(Value::Variant(1, Box::new(Value::Unit)))
+    );
+    registry.insert(
+        "Loop".to_string(),
+        ContainerFormat::Enum(vec![(0, variant("Again", again()))].into_iter().collect()),
+    );
+    assert_eq!(
+        Value::sample(&config, &registry, &format),
+        Err(Error::NoSampleValue("Loop".to_string()))
+    );
+}
+
+// Arrays of any length, serialized as tuples like serde does for lengths up to 32.
+#[derive(Debug, PartialEq)]
+struct LargeArray<T, const N: usize>([T; N]);
+
+impl<T: Serialize, const N: usize> Serialize for LargeArray<T, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeTuple;
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for item in &self.0 {
+            tuple.serialize_element(item)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for LargeArray<T, N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ArrayVisitor<T, const N: usize>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>, const N: usize> serde::de::Visitor<'de> for ArrayVisitor<T, N> {
+            type Value = LargeArray<T, N>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "an array of length {}", N)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut items = Vec::with_capacity(N);
+                for i in 0..N {
+                    match seq.next_element()? {
+                        Some(item) => items.push(item),
+                        None => return Err(serde::de::Error::invalid_length(i, &self)),
+                    }
+                }
+                match items.try_into() {
+                    Ok(array) => Ok(LargeArray(array)),
+                    Err(_) => unreachable!(),
+                }
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ArrayVisitor(std::marker::PhantomData))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct LargeArrays {
+    a: LargeArray<u8, 48>,
+    b: LargeArray<u8, 64>,
+    c: LargeArray<u32, 100>,
+}
+
+#[test]
+fn test_tracing_arrays_larger_than_32() {
+    let expected = vec![
+        Named {
+            name: "a".into(),
+            value: Format::TupleArray {
+                content: Box::new(Format::U8),
+                size: 48,
+            },
+        },
+        Named {
+            name: "b".into(),
+            value: Format::TupleArray {
+                content: Box::new(Format::U8),
+                size: 64,
+            },
+        },
+        Named {
+            name: "c".into(),
+            value: Format::TupleArray {
+                content: Box::new(Format::U32),
+                size: 100,
+            },
+        },
+    ];
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let samples = Samples::new();
+    let (_, values) = tracer.trace_type::<LargeArrays>(&samples).unwrap();
+    assert_eq!(
+        values,
+        vec![LargeArrays {
+            a: LargeArray([0; 48]),
+            b: LargeArray([0; 64]),
+            c: LargeArray([0; 100]),
+        }]
+    );
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("LargeArrays").unwrap(),
+        &ContainerFormat::Struct(expected.clone())
+    );
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let mut samples = Samples::new();
+    let value = LargeArrays {
+        a: LargeArray([1; 48]),
+        b: LargeArray([2; 64]),
+        c: LargeArray([3; 100]),
+    };
+    tracer.trace_value(&mut samples, &value).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.get("LargeArrays").unwrap(),
+        &ContainerFormat::Struct(expected)
+    );
+}
+
+#[test]
+fn test_trace_types_macro() {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Foo(Choice, Vec<Bar>);
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum Choice {
+        A,
+        B(u8),
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Bar {
+        x: bool,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    #[serde(untagged)]
+    enum Untagged {
+        A(u8),
+    }
+
+    let mut tracer = Tracer::new(TracerConfig::default());
+    trace_types!(tracer, [Foo, Choice]).unwrap();
+    let registry = tracer.registry().unwrap();
+    assert_eq!(
+        registry.keys().collect::<Vec<_>>(),
+        vec!["Bar", "Choice", "Foo"]
+    );
+
+    // Errors report the type that failed.
+    let mut tracer = Tracer::new(TracerConfig::default());
+    let tracer_ref = &mut tracer;
+    let error = trace_types!(tracer_ref, [Choice, Vec<Untagged>, Foo]).unwrap_err();
+    assert_eq!(
+        error,
+        Error::TypeTracing(
+            "Vec<Untagged>".to_string(),
+            Box::new(Error::NotSupported("deserialize_any"))
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        "Failed to trace type Vec<Untagged>: Not supported: deserialize_any"
+    );
+    let registry = tracer.registry_unchecked();
+    assert!(registry.contains_key("Choice"));
+    assert!(!registry.contains_key("Foo"));
+}